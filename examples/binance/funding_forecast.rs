@@ -0,0 +1,23 @@
+//! Prints what the next Binance funding would cost a hardcoded pair of positions, as the predicted rates move.
+use v_exchanges::prelude::*;
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let mut forecaster = FundingForecaster::new(ExchangeName::Binance.init_client());
+	forecaster.set_position(Pair::new("BTC", "USDT"), 0.1, Side::Buy);
+	forecaster.set_position(Pair::new("ETH", "USDT"), 2., Side::Sell);
+
+	loop {
+		match forecaster.next().await {
+			Ok(forecast) => {
+				for (pair, e) in &forecast.positions {
+					println!("{pair}: {:+.4}% at {} -> {:+.4} USDT", e.predicted_rate * 100., e.next_funding_time, e.est_payment_usd);
+				}
+				println!("total: {:+.4} USDT (positive is paid)\n", forecast.total_usd);
+			}
+			Err(e) => eprintln!("Funding forecast failed: {e}"),
+		}
+	}
+}
@@ -0,0 +1,36 @@
+//! Prints Binance perp listings, delistings and status changes as they happen, checking every 5 minutes.
+//!
+//! Pass a path as the first argument to keep the last snapshot there, so a restart doesn't re-announce every pair.
+use std::{env, time::Duration};
+
+use v_exchanges::prelude::*;
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let mut binance = ExchangeName::Binance.init_client();
+	binance.set_timeout(Duration::from_secs(10)); // exchange_info is a large response
+
+	let mut watcher = SymbolWatcher::new(binance, Instrument::Perp, Duration::from_secs(5 * 60));
+	if let Some(path) = env::args().nth(1) {
+		watcher = watcher.persist(path);
+	}
+
+	loop {
+		let events = match watcher.next().await {
+			Ok(events) => events,
+			Err(e) => {
+				eprintln!("Failed to poll exchange info: {e}");
+				continue;
+			}
+		};
+		for event in events {
+			match event {
+				SymbolEvent::Added(pair, info) => println!("+ {pair} ({})", info.status.as_deref().unwrap_or("-")),
+				SymbolEvent::Removed(pair) => println!("- {pair}"),
+				SymbolEvent::StatusChanged { pair, from, to } => println!("~ {pair}: {from} -> {to}"),
+			}
+		}
+	}
+}
@@ -14,7 +14,11 @@ async fn main() {
 			std::process::exit(1);
 		}
 	};
-	let client = ticker.exchange_name.init_client();
+	let Some(name) = ticker.exchange_name.builtin() else {
+		eprintln!("Error: {} is not built into v_exchanges", ticker.exchange_name);
+		std::process::exit(1);
+	};
+	let client = name.init_client();
 
 	let klines: Klines = client.klines(ticker.symbol, "1m".into(), 2.into()).await.unwrap();
 	println!("{:#?}", klines.v);
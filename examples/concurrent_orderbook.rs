@@ -0,0 +1,89 @@
+//! Follows the BTCUSDT perp books of Binance and Bybit side by side for 30 seconds, flagging every time one's ask drops under the other's bid.
+use std::time::Duration;
+
+use v_exchanges::prelude::*;
+
+const RUN_FOR: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let pairs = [Pair::new("BTC", "USDT")];
+	let mut binance = Binance::default();
+	let mut bybit = Bybit::default();
+	let mut binance_stream = binance.ws_book(&pairs, Instrument::Perp).await.unwrap();
+	let mut bybit_stream = bybit.ws_book(&pairs, Instrument::Perp).await.unwrap();
+
+	let (mut binance_book, mut bybit_book) = (LocalBook::default(), LocalBook::default());
+	let mut in_window = false;
+	let mut windows = 0;
+	let deadline = tokio::time::sleep(RUN_FOR);
+	tokio::pin!(deadline);
+
+	loop {
+		let source = tokio::select! {
+			_ = &mut deadline => break,
+			batch = binance_stream.next() => match batch {
+				Ok(batch) => {
+					for update in &batch {
+						binance_book.apply(update);
+					}
+					"binance"
+				}
+				Err(e) => {
+					eprintln!("binance: {e}");
+					continue;
+				}
+			},
+			batch = bybit_stream.next() => match batch {
+				Ok(batch) => {
+					for update in &batch {
+						bybit_book.apply(update);
+					}
+					"bybit"
+				}
+				Err(e) => {
+					eprintln!("bybit: {e}");
+					continue;
+				}
+			},
+		};
+
+		let cross = cross(&binance_book, &bybit_book);
+		if cross.is_some() && !in_window {
+			windows += 1;
+		}
+		in_window = cross.is_some();
+		println!(
+			"[{source:>7}] binance {} | bybit {}{}",
+			top(&binance_book),
+			top(&bybit_book),
+			cross.map_or(String::new(), |c| format!(" | CROSS: {c}")),
+		);
+	}
+
+	println!("\n{windows} arbitrage windows over {RUN_FOR:?}");
+}
+
+/// `None` unless both books are usable and one's best ask is under the other's best bid.
+fn cross(binance: &LocalBook, bybit: &LocalBook) -> Option<String> {
+	let usable = |book: &LocalBook| !book.needs_resync && !book.is_crossed();
+	if !usable(binance) || !usable(bybit) {
+		return None;
+	}
+	let ((binance_bid, _), (binance_ask, _)) = (binance.best_bid()?, binance.best_ask()?);
+	let ((bybit_bid, _), (bybit_ask, _)) = (bybit.best_bid()?, bybit.best_ask()?);
+	if binance_ask < bybit_bid {
+		Some(format!("buy binance at {binance_ask}, sell bybit at {bybit_bid}"))
+	} else if bybit_ask < binance_bid {
+		Some(format!("buy bybit at {bybit_ask}, sell binance at {binance_bid}"))
+	} else {
+		None
+	}
+}
+
+fn top(book: &LocalBook) -> String {
+	let fmt = |level: Option<(f64, f64)>| level.map_or("-".to_string(), |(price, _)| price.to_string());
+	format!("{:>10} / {:<10}", fmt(book.best_bid()), fmt(book.best_ask()))
+}
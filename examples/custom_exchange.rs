@@ -0,0 +1,230 @@
+//! Connecting an exchange this crate doesn't ship. Toyex is a toy REST API, served locally here: a [RequestHandler] in the style of the adapters talks to it, a client wrapper implements [ExchangeImpl] over that, and a registered [Venue] names it, so that it parses in [Ticker]s and dispatches through [ExchangeRouter] like the built-in exchanges.
+//!
+//! Toyex serves `GET /api/price?symbol=BTCUSDT` as `{"price":50000.1}`, and `GET /api/candles?symbol=BTCUSDT&interval=1m&limit=2` as `[[open_ms, open, high, low, close, volume_quote], ..]`, oldest first.
+use std::{
+	io::{BufRead as _, BufReader, Write as _},
+	marker::PhantomData,
+	net::TcpListener,
+};
+
+use secrecy::SecretString;
+use v_exchanges::{custom::ExchangeImpl, prelude::*};
+use v_exchanges_adapters::{
+	Client, HttpClient as _,
+	generics::{
+		UrlError,
+		http::{ApiError, BuildError, Bytes, HandleError, HeaderMap, Request, RequestBuilder, RequestHandler, StatusCode, parse_response},
+		reqwest::Url,
+	},
+};
+
+// Handler {{{
+/// What [ToyexRequestHandler]s are built from. Held by [Toyex], as the adapters' `*Options` are held by [Client].
+#[derive(Clone, Debug)]
+struct ToyexOptions {
+	base_url: Url,
+}
+
+/// Toyex is public-only, so there is nothing to sign: requests go out as built, and errors are whatever isn't a 2xx.
+struct ToyexRequestHandler<R> {
+	options: ToyexOptions,
+	_phantom: PhantomData<R>,
+}
+impl<B, R: DeserializeOwned> RequestHandler<B> for ToyexRequestHandler<R> {
+	type Successful = R;
+
+	fn base_url(&self, _is_test: bool) -> Result<Url, UrlError> {
+		Ok(self.options.base_url.clone())
+	}
+
+	fn build_request(&self, builder: RequestBuilder, _request_body: &Option<B>, _attempt_count: u8) -> Result<Request, BuildError> {
+		builder.build().map_err(|e| BuildError::Other(eyre!(e)))
+	}
+
+	fn handle_response(&self, status: StatusCode, _headers: HeaderMap, response_body: Bytes) -> Result<Self::Successful, HandleError> {
+		match status.is_success() {
+			true => parse_response(&response_body),
+			false => Err(ApiError::Other(eyre!("Toyex answered {status}: {}", String::from_utf8_lossy(&response_body))).into()),
+		}
+	}
+}
+//,}}}
+
+// Client {{{
+/// Carries the state [ExchangeImpl] expects of every exchange. Requests go through the http client of the wrapped [Client], so they share its retries and rate limiting.
+#[derive(Debug)]
+struct Toyex {
+	client: Client,
+	options: ToyexOptions,
+	venue: Venue,
+	info_cache: InfoCache,
+	label: Option<AccountLabel>,
+	log_context: Option<String>,
+	klines_guard: Option<KlinesGuard>,
+	mutation_journal: Option<Arc<dyn MutationJournal>>,
+}
+impl Toyex {
+	fn new(base_url: Url) -> Result<Self> {
+		Ok(Self {
+			client: Client::default(),
+			options: ToyexOptions { base_url },
+			venue: Venue::register("toyex")?,
+			info_cache: InfoCache::default(),
+			label: None,
+			log_context: None,
+			klines_guard: None,
+			mutation_journal: None,
+		})
+	}
+
+	async fn public_get<R: DeserializeOwned>(&self, url: &str, query: &[(&str, String)]) -> ExchangeResult<R> {
+		let handler = ToyexRequestHandler {
+			options: self.options.clone(),
+			_phantom: PhantomData,
+		};
+		Ok(self.client.http_client().get(url, query, &handler).await?)
+	}
+}
+impl std::ops::Deref for Toyex {
+	type Target = Client;
+
+	fn deref(&self) -> &Client {
+		&self.client
+	}
+}
+impl std::ops::DerefMut for Toyex {
+	fn deref_mut(&mut self) -> &mut Client {
+		&mut self.client
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct PriceResponse {
+	price: f64,
+}
+/// `[open_ms, open, high, low, close, volume_quote]`
+type CandleResponse = (i64, f64, f64, f64, f64, f64);
+
+#[async_trait::async_trait]
+impl ExchangeImpl for Toyex {
+	fn name(&self) -> Venue {
+		self.venue
+	}
+
+	fn info_cache(&self) -> &InfoCache {
+		&self.info_cache
+	}
+
+	fn label(&self) -> Option<&AccountLabel> {
+		self.label.as_ref()
+	}
+
+	fn label_mut(&mut self) -> &mut Option<AccountLabel> {
+		&mut self.label
+	}
+
+	fn log_context(&self) -> Option<&str> {
+		self.log_context.as_deref()
+	}
+
+	fn log_context_mut(&mut self) -> &mut Option<String> {
+		&mut self.log_context
+	}
+
+	fn klines_guard(&self) -> Option<KlinesGuard> {
+		self.klines_guard
+	}
+
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard> {
+		&mut self.klines_guard
+	}
+
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>> {
+		self.mutation_journal.as_ref()
+	}
+
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>> {
+		&mut self.mutation_journal
+	}
+
+	fn auth(&mut self, _pubkey: String, _secret: SecretString) {
+		warn!("Toyex has no private endpoints, ignoring the credentials");
+	}
+
+	fn set_recv_window(&mut self, _recv_window: std::time::Duration) {}
+
+	fn default_recv_window(&self) -> Option<std::time::Duration> {
+		None
+	}
+
+	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		let r: PriceResponse = self.public_get("/api/price", &[("symbol", symbol.pair.fmt_binance())]).await?;
+		Ok(r.price)
+	}
+
+	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
+		let RequestRange::Limit(limit) = range else {
+			return Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.venue, symbol.instrument, "klines")));
+		};
+		let params = [("symbol", symbol.pair.fmt_binance()), ("interval", tf.to_string()), ("limit", limit.to_string())];
+		let r: Vec<CandleResponse> = self.public_get("/api/candles", &params).await?;
+		let v = r
+			.into_iter()
+			.map(|(open_ms, open, high, low, close, volume_quote)| -> ExchangeResult<Kline> {
+				Ok(Kline {
+					open_time: Timestamp::from_millisecond(open_ms).map_err(|e| eyre!("Invalid Toyex open time {open_ms}: {e}"))?,
+					ohlc: Ohlc { open, high, low, close },
+					volume_quote,
+					trades: None,
+					taker_buy_volume_quote: None,
+				})
+			})
+			.collect::<ExchangeResult<VecDeque<Kline>>>()?;
+		Ok(Klines::new(v, tf))
+	}
+}
+//,}}}
+
+/// Answers like Toyex would, one request per connection.
+fn serve_toyex() -> Url {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+	std::thread::spawn(move || {
+		for stream in listener.incoming() {
+			let mut stream = stream.unwrap();
+			let mut request_line = String::new();
+			BufReader::new(&stream).read_line(&mut request_line).unwrap();
+			let (status, body) = match request_line.split_whitespace().nth(1) {
+				Some(path) if path.starts_with("/api/price?") => ("200 OK", r#"{"price":50000.1}"#),
+				Some(path) if path.starts_with("/api/candles?") => ("200 OK", "[[1700000000000,50000,50010,49990,50005,120000],[1700000060000,50005,50020,50000,50000.1,95000]]"),
+				_ => ("404 Not Found", "no such endpoint"),
+			};
+			write!(stream, "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len()).unwrap();
+		}
+	});
+	url
+}
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let mut router = ExchangeRouter::default();
+	router.add(Box::new(Toyex::new(serve_toyex()).unwrap()));
+
+	// parses only once `Toyex::new` has registered the venue
+	let ticker: Ticker = "toyex:BTC-USDT.P".parse().unwrap();
+	let price = router.price(ticker.clone()).await.unwrap();
+	let klines = router.klines(ticker.clone(), "1m".into(), 2.into()).await.unwrap();
+	dbg!(price, &klines);
+
+	// anything not implemented fails as it would on a built-in exchange, naming the venue
+	let e = router.open_interest(ticker, "1h".into(), 5.into()).await.unwrap_err();
+	println!("{e}");
+}
+
+#[cfg(test)]
+#[test]
+fn test_main() {
+	main();
+}
@@ -0,0 +1,43 @@
+//! Snapshots Binance perp equity every hour, printing a summary whenever a day rolls over.
+//!
+//! Pass a path as the first argument to also journal every snapshot there as JSONL.
+use std::{env, time::Duration};
+
+use jiff::{Timestamp, ToSpan as _, tz::TimeZone};
+use v_exchanges::prelude::*;
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let (Ok(key), Ok(secret)) = (env::var("BINANCE_TIGER_READ_PUBKEY"), env::var("BINANCE_TIGER_READ_SECRET")) else {
+		eprintln!("BINANCE_TIGER_READ_PUBKEY and BINANCE_TIGER_READ_SECRET must be set");
+		return;
+	};
+	let mut binance = ExchangeName::Binance.init_client();
+	binance.auth(key, secret.into());
+
+	let mut tracker = EquityTracker::new(binance, Instrument::Perp, Duration::from_secs(60 * 60));
+	if let Some(path) = env::args().nth(1) {
+		tracker = tracker.journal(path);
+	}
+
+	let day_of = |t: Timestamp| t.to_zoned(TimeZone::UTC).date();
+	let mut current_day = day_of(Timestamp::now());
+	let state = tracker
+		.run(|tracker| {
+			let latest = tracker.history().back().expect("called after a point is recorded");
+			if day_of(latest.timestamp()) == current_day {
+				return;
+			}
+			current_day = day_of(latest.timestamp());
+			let day_ago = latest.timestamp() - 24.hours();
+			let equity = tracker.snapshots().last().map(|s| format!("{:.2}", *s.total)).unwrap_or_else(|| "-".into());
+			let pnl = tracker.pnl_since(day_ago).map(|p| format!("{:+.2}", *p)).unwrap_or_else(|| "-".into());
+			let drawdown = tracker.drawdown().map(|d| format!("{:.2}%", d * 100.)).unwrap_or_else(|| "-".into());
+			let gaps = tracker.history().iter().filter(|p| p.timestamp() >= day_ago && p.snapshot().is_none()).count();
+			println!("{current_day}: equity ${equity}, 24h pnl ${pnl}, max drawdown {drawdown}, {gaps} gaps");
+		})
+		.await;
+	println!("Tracker stopped: {state:?}");
+}
@@ -28,7 +28,10 @@ async fn check_binance() {
 			binance.auth(key, secret.into());
 
 			match binance.personal_info(Instrument::Perp, Some(Duration::from_millis(5000))).await {
-				Ok(_) => println!("✅ Binance: API key is valid and active"),
+				Ok(_) => {
+					println!("✅ Binance: API key is valid and active");
+					print_permissions(&*binance).await;
+				}
 				Err(e) => println!("❌ Binance: API key error - {}", e),
 			}
 		}
@@ -48,7 +51,10 @@ async fn check_bybit() {
 			bybit.auth(key, secret.into());
 
 			match bybit.personal_info(Instrument::Perp, Some(Duration::from_millis(5000))).await {
-				Ok(_) => println!("✅ Bybit: API key is valid and active"),
+				Ok(_) => {
+					println!("✅ Bybit: API key is valid and active");
+					print_permissions(&*bybit).await;
+				}
 				Err(e) => println!("❌ Bybit: API key error - {}", e),
 			}
 		}
@@ -74,7 +80,10 @@ async fn check_kucoin() {
 				kucoin.update_default_option(KucoinOption::Passphrase(passphrase.into()));
 
 				match kucoin.personal_info(Instrument::Spot, None).await {
-					Ok(_) => println!("✅ Kucoin: API key is valid and active"),
+					Ok(_) => {
+						println!("✅ Kucoin: API key is valid and active");
+						print_permissions(&*kucoin).await;
+					}
 					Err(e) => {
 						let err_str = e.to_string();
 						if err_str.contains("400003") || err_str.contains("KC-API-KEY not exists") {
@@ -131,6 +140,13 @@ async fn check_mexc() {
 	}
 }
 
+async fn print_permissions(client: &dyn Exchange) {
+	match client.key_permissions().await {
+		Ok(permissions) => println!("   {}: {permissions}", client.name()),
+		Err(e) => println!("   {}: couldn't read key permissions - {e}", client.name()),
+	}
+}
+
 #[cfg(test)]
 #[test]
 fn test_main() {
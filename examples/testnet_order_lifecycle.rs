@@ -0,0 +1,102 @@
+//! Full life of a limit order on a testnet, timing every step: placed well under the market, looked up until acknowledged, amended, and found among the open orders.
+//!
+//! `cargo run --example testnet_order_lifecycle -- bybit`, with `BYBIT_TESTNET_PUBKEY` and `BYBIT_TESTNET_SECRET` set (`BINANCE_` for binance).
+//!
+//! Steps take any [Exchange], so they run the same against a [mock client](ExchangeInit::init_mock_client).
+use std::time::{Duration, Instant};
+
+use v_exchanges::prelude::*;
+
+/// How far under the market the order goes, so that it doesn't fill.
+const DISCOUNT: f64 = 0.05;
+const POLL_EVERY: Duration = Duration::from_millis(500);
+const POLL_FOR: Duration = Duration::from_secs(10);
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let Some(Ok(exchange)) = std::env::args().nth(1).map(|s| ExchangeName::from_str(&s)) else {
+		eprintln!("Usage: testnet_order_lifecycle <binance|bybit>");
+		std::process::exit(1);
+	};
+	let env_prefix = exchange.to_string().to_uppercase();
+	let (Ok(key), Ok(secret)) = (std::env::var(format!("{env_prefix}_TESTNET_PUBKEY")), std::env::var(format!("{env_prefix}_TESTNET_SECRET"))) else {
+		eprintln!("{env_prefix}_TESTNET_PUBKEY or {env_prefix}_TESTNET_SECRET is missing");
+		std::process::exit(1);
+	};
+	let mut client = exchange.init_client();
+	client.set_use_testnet(true);
+	client.auth(key, secret.into());
+
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+	let info = timed("exchange_info", pair_info(&mut *client, symbol)).await;
+	let order = timed("price", limit_below_market(&*client, symbol, &info)).await;
+	println!("  buying {} at {}", order.qty.as_f64(), order.price.as_f64());
+
+	let placed = timed("place_order", client.place_order(symbol, order.clone().into(), None)).await;
+	let acked = timed("order_status", await_ack(&*client, symbol, placed.order_id)).await;
+	println!("  {} is {:?}", acked.order_id.client_order_id(), acked.status);
+
+	let new_price = Price::new(order.price.raw - 1, order.price.precision);
+	let amended = timed("amend_order", client.amend_order(acked.order_id, symbol, None, Some(new_price.as_f64()), None)).await;
+	let open = timed("get_orders", find_open(&*client, symbol, &amended.order_id)).await;
+	println!("  open at {:?}, amended from {}", open.price, order.price.as_f64());
+
+	// there's no cancel on `Exchange` yet
+	println!("\nOrder {} is left resting on the {exchange} testnet", open.order_id.client_order_id());
+}
+
+/// Runs a step, printing how long it took. Exits on failure, as every step builds on the previous.
+async fn timed<T>(step: &str, fut: impl Future<Output = ExchangeResult<T>>) -> T {
+	let start = Instant::now();
+	match fut.await {
+		Ok(v) => {
+			println!("{step:<14} {:>10.1?}", start.elapsed());
+			v
+		}
+		Err(e) => {
+			eprintln!("{step} failed after {:.1?}: {e}", start.elapsed());
+			std::process::exit(1);
+		}
+	}
+}
+
+async fn pair_info(client: &mut dyn Exchange, symbol: Symbol) -> ExchangeResult<PairInfo> {
+	let mut info = client.exchange_info(symbol.instrument).await?;
+	info.pairs.remove(&symbol.pair).ok_or_else(|| ExchangeError::Other(eyre!("{symbol} is not listed")))
+}
+
+/// Limit buy [DISCOUNT] under the market, at the tick, and at the smallest qty the pair accepts.
+async fn limit_below_market(client: &dyn Exchange, symbol: Symbol, info: &PairInfo) -> ExchangeResult<LimitOrder> {
+	let market = client.price(symbol).await?;
+	let price = Price::from_f64(market * (1. - DISCOUNT), info.price_precision);
+	let min_qty = info.min_qty.unwrap_or(0.).max(info.min_notional.unwrap_or(0.) / price.as_f64());
+	let step = info.qty_step.unwrap_or(10f64.powi(-(info.qty_precision as i32)));
+	let qty = Qty::from_f64((min_qty / step).ceil() * step, info.qty_precision);
+	Ok(LimitOrder {
+		order_id: OrderId::with_generated_id("lifecycle"),
+		..LimitOrder::new(Side::Buy, price, qty)
+	})
+}
+
+/// Polls [order_status](Exchange::order_status) until the exchange knows of the order.
+async fn await_ack(client: &dyn Exchange, symbol: Symbol, order_id: OrderId) -> ExchangeResult<OrderPlaced> {
+	let deadline = Instant::now() + POLL_FOR;
+	loop {
+		if let OrderState::Found(placed) = client.order_status(symbol, order_id.clone(), None).await? {
+			return Ok(placed);
+		}
+		if Instant::now() > deadline {
+			return Err(ExchangeError::Other(eyre!("{} wasn't acknowledged within {POLL_FOR:?}", order_id.client_order_id())));
+		}
+		tokio::time::sleep(POLL_EVERY).await;
+	}
+}
+
+async fn find_open(client: &dyn Exchange, symbol: Symbol, order_id: &OrderId) -> ExchangeResult<OrderDetails> {
+	let open = client.get_orders(symbol.instrument, Some(symbol.pair), None, None).await?;
+	open.into_iter()
+		.find(|o| o.order_id.client_order_id() == order_id.client_order_id())
+		.ok_or_else(|| ExchangeError::Other(eyre!("{} is not among the open orders", order_id.client_order_id())))
+}
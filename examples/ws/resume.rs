@@ -0,0 +1,48 @@
+//! Reconnect-to-first-trade of a 50-pair Binance perp stream, opened afresh vs resumed from the snapshot the fresh one leaves behind. Each side starts on a new client, as after a restart, so both pay for their own `exchange_info`.
+use std::time::{Duration, Instant};
+
+use v_exchanges::prelude::*;
+use v_exchanges_adapters::generics::ws::SubscriptionSnapshot;
+
+const PAIRS: usize = 50;
+
+async fn until_first_trade(stream: &mut Box<dyn ExchangeStream<Item = BatchTrades>>) {
+	while stream.next().await.unwrap().is_empty() {}
+}
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	// fresh: re-derive the pair set, then subscribe to it
+	let started = Instant::now();
+	let mut binance = Binance::default();
+	let info = binance.exchange_info(Instrument::Perp).await.unwrap();
+	let pairs: Vec<Pair> = info.pairs.keys().filter(|p| p.quote().as_ref() == "USDT").take(PAIRS).copied().collect();
+	let mut fresh = binance.ws_trades(&pairs, Instrument::Perp).await.unwrap();
+	until_first_trade(&mut fresh).await;
+	let fresh_took = started.elapsed();
+
+	// what would be persisted on the way down
+	let persisted = serde_json::to_string(&fresh.subscription_snapshot().unwrap()).unwrap();
+	drop(fresh);
+
+	let started = Instant::now();
+	let snapshot: SubscriptionSnapshot = serde_json::from_str(&persisted).unwrap();
+	let mut binance = Binance::default();
+	let mut resumed = binance.ws_trades_resume(Instrument::Perp, &snapshot).await.unwrap();
+	until_first_trade(&mut resumed).await;
+	let resumed_took = started.elapsed();
+
+	println!("{} topics to first trade: fresh {}, resumed {}", snapshot.topics.len(), ms(fresh_took), ms(resumed_took));
+}
+
+fn ms(d: Duration) -> String {
+	format!("{:.0}ms", d.as_secs_f64() * 1000.)
+}
+
+#[cfg(test)]
+#[test]
+fn test_main() {
+	main();
+}
@@ -0,0 +1,78 @@
+use v_exchanges_adapters::{
+	Client,
+	binance::{BinanceOption, BinanceWsUrl},
+	generics::ws::{TopicInterpreter, TypedRouter, WsError},
+};
+
+#[derive(Debug, serde::Deserialize)]
+struct Trade {
+	#[serde(rename = "s")]
+	pair: String,
+	#[serde(rename = "p")]
+	price: String,
+	#[serde(rename = "q")]
+	qty: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Kline {
+	#[serde(rename = "s")]
+	pair: String,
+	#[serde(rename = "k")]
+	k: KlineData,
+}
+#[derive(Debug, serde::Deserialize)]
+struct KlineData {
+	#[serde(rename = "c")]
+	close: String,
+	#[serde(rename = "x")]
+	closed: bool,
+}
+
+fn parse<T: serde::de::DeserializeOwned>(data: &serde_json::Value) -> Result<T, WsError> {
+	T::deserialize(data).map_err(WsError::Parse)
+}
+
+#[tokio::main]
+async fn main() {
+	v_utils::clientside!();
+
+	let client = Client::default();
+	let connection = client
+		.ws_connection(
+			"",
+			vec![
+				BinanceOption::WsUrl(BinanceWsUrl::FuturesUsdM),
+				BinanceOption::WsTopics(vec!["btcusdt@trade".to_owned(), "btcusdt@kline_1m".to_owned(), "btcusdt@markPrice".to_owned()]),
+			],
+		)
+		.unwrap();
+
+	let mut router = TypedRouter::new(connection);
+	let mut trades = router
+		.register(TopicInterpreter::<Trade> {
+			event_name: "trade".to_owned(),
+			interpret: parse,
+		})
+		.unwrap();
+	let mut unmatched = router.unmatched().unwrap();
+	router.start().unwrap();
+
+	// registering after start works too
+	let mut klines = router
+		.register(TopicInterpreter::<Kline> {
+			event_name: "kline".to_owned(),
+			interpret: parse,
+		})
+		.unwrap();
+
+	loop {
+		tokio::select! {
+			Some(trade) = trades.recv() => println!("trade {}: {} @ {}", trade.pair, trade.qty, trade.price),
+			Some(kline) = klines.recv() => println!("kline {}: close {} (closed: {})", kline.pair, kline.k.close, kline.k.closed),
+			Some(event) = unmatched.recv() => println!("unmatched `{}` on {}", event.event_type, event.topic),
+			else => break,
+		}
+	}
+	eprintln!("router stopped: {:?}", router.join().await);
+}
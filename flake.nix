@@ -103,6 +103,7 @@
             valgrind # iai-based microbenches in v_exchanges_persistence
             (writeShellScriptBin "test_all" "cargo t && cargo t --examples")
             (writeShellScriptBin "examples" "cargo -Zscript -q scripts/list_examples.rs")
+            (writeShellScriptBin "check_features" "cargo -Zscript -q scripts/check_feature_split.rs")
           ] ++ pre-commit-check.enabledPackages ++ combined.enabledPackages;
 
           env.RUST_BACKTRACE = 1;
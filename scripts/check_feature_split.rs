@@ -0,0 +1,29 @@
+#!/usr/bin/env -S cargo -Zscript -q
+---cargo
+[package]
+edition = "2024"
+---
+
+//! Asserts that REST-only builds (`{exchange}-http`) don't pull in the websocket stack, and that `-ws` ones do.
+//! Run from the workspace root.
+
+use std::process::Command;
+
+fn tree(features: &str) -> String {
+	let out = Command::new("cargo")
+		.args(["tree", "-p", "v_exchanges", "--no-default-features", "--features", features, "-e", "features,normal", "--prefix", "none"])
+		.output()
+		.expect("failed to run `cargo tree`");
+	assert!(out.status.success(), "`cargo tree --features {features}` failed:\n{}", String::from_utf8_lossy(&out.stderr));
+	String::from_utf8(out.stdout).expect("cargo tree output is utf8")
+}
+
+fn main() {
+	for features in ["binance-http", "bybit-http", "kucoin-http", "mexc-http", "binance-http,bybit-http,kucoin-http,mexc-http,data"] {
+		assert!(!tree(features).contains("tungstenite"), "`{features}` build pulls in tungstenite");
+	}
+	for features in ["binance-ws", "bybit-ws", "kucoin-ws", "mexc-ws", "binance", "major"] {
+		assert!(tree(features).contains("tungstenite"), "`{features}` build doesn't include tungstenite");
+	}
+	println!("feature split ok");
+}
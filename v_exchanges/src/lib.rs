@@ -1 +1,3 @@
 pub use v_exchanges_methods::*;
+
+pub mod utils;
@@ -0,0 +1,54 @@
+//! Sizing math for inverse perpetuals (Binance COIN-M, Bybit inverse), where a contract is a fixed amount of USD but margin and pnl are in the base coin. Purely computational.
+//!
+//! `contract_size` is one contract's face value in USD: 100 for BTCUSD on Binance COIN-M (10 for the alts), 1 on Bybit.
+use crate::Side;
+
+/// Contracts amounting to `notional_usd`. Unrounded; floor to whole contracts before ordering.
+///
+/// The count doesn't depend on `price`, as the face value is fixed in USD. What it does move is the coin exposure: see [inverse_contract_notional].
+pub fn inverse_contract_qty(notional_usd: f64, price: f64, contract_size: f64) -> f64 {
+	debug_assert!(price > 0., "price must be positive, got {price}");
+	notional_usd / contract_size
+}
+
+/// Exposure of `qty_contracts` in the base coin at `price`. One $100 contract at $50k is 0.002 BTC.
+pub fn inverse_contract_notional(qty_contracts: f64, price: f64, contract_size: f64) -> f64 {
+	qty_contracts * contract_size / price
+}
+
+/// Realized pnl in the base coin, the currency inverse contracts settle in.
+///
+/// Not symmetric in price: a long gains less coin on a rise than it loses on an equal fall.
+pub fn inverse_pnl(entry_price: f64, exit_price: f64, qty_contracts: f64, contract_size: f64, side: Side) -> f64 {
+	let long = qty_contracts * contract_size * (1. / entry_price - 1. / exit_price);
+	match side {
+		Side::Buy => long,
+		Side::Sell => -long,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn contract_round_trip() {
+		let contracts = inverse_contract_qty(10_000., 50_000., 100.);
+		assert_eq!(contracts, 100.);
+		assert_eq!(inverse_contract_notional(contracts, 50_000., 100.), 0.2);
+		// same contracts, half the price: twice the coin
+		assert_eq!(inverse_contract_notional(contracts, 25_000., 100.), 0.4);
+	}
+
+	#[test]
+	fn pnl_in_coin() {
+		// 100 contracts of $100, 50k -> 40k
+		let short = inverse_pnl(50_000., 40_000., 100., 100., Side::Sell);
+		assert!((short - 0.05).abs() < 1e-12);
+		let long = inverse_pnl(50_000., 40_000., 100., 100., Side::Buy);
+		assert_eq!(long, -short);
+		// +20% earns less than -20% loses
+		let up = inverse_pnl(50_000., 60_000., 100., 100., Side::Buy);
+		assert!(up < -long);
+	}
+}
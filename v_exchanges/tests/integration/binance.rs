@@ -0,0 +1,49 @@
+use v_exchanges::prelude::*;
+
+use super::testnet_client;
+
+#[tokio::test]
+async fn exchange_info() {
+	let mut c = testnet_client(ExchangeName::Binance);
+	let info = c.exchange_info(Instrument::Perp).await.unwrap();
+	assert!(!info.pairs.is_empty());
+}
+
+#[tokio::test]
+async fn klines_spot() {
+	let c = testnet_client(ExchangeName::Binance);
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Spot);
+	let klines = c.klines(symbol, "1m".into(), 5.into()).await.unwrap();
+	assert_eq!(klines.len(), 5);
+}
+
+#[tokio::test]
+async fn klines_perp() {
+	let c = testnet_client(ExchangeName::Binance);
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+	let klines = c.klines(symbol, "1m".into(), 5.into()).await.unwrap();
+	assert_eq!(klines.len(), 5);
+}
+
+#[tokio::test]
+async fn prices_spot() {
+	let c = testnet_client(ExchangeName::Binance);
+	let prices = c.prices(Some(vec![Pair::new("BTC", "USDT"), Pair::new("ETH", "USDT")]), Instrument::Spot).await.unwrap();
+	assert_eq!(prices.len(), 2);
+}
+
+#[tokio::test]
+async fn price_perp() {
+	let c = testnet_client(ExchangeName::Binance);
+	let price = c.price(Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp)).await.unwrap();
+	assert!(price > 0.);
+}
+
+#[tokio::test]
+async fn open_interest() {
+	let c = testnet_client(ExchangeName::Binance);
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+	let oi = c.open_interest(symbol, "5m".into(), 5.into()).await.unwrap();
+	assert!(!oi.is_empty());
+	assert!(oi.iter().all(|p| p.val_usd.is_some()));
+}
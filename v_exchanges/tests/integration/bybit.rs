@@ -0,0 +1,40 @@
+use v_exchanges::prelude::*;
+
+use super::testnet_client;
+
+#[tokio::test]
+async fn exchange_info() {
+	let mut c = testnet_client(ExchangeName::Bybit);
+	let info = c.exchange_info(Instrument::Perp).await.unwrap();
+	assert!(!info.pairs.is_empty());
+}
+
+#[tokio::test]
+async fn klines_perp() {
+	let c = testnet_client(ExchangeName::Bybit);
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+	let klines = c.klines(symbol, "1m".into(), 5.into()).await.unwrap();
+	assert_eq!(klines.len(), 5);
+}
+
+#[tokio::test]
+async fn prices_perp() {
+	let c = testnet_client(ExchangeName::Bybit);
+	let prices = c.prices(Some(vec![Pair::new("BTC", "USDT"), Pair::new("ETH", "USDT")]), Instrument::Perp).await.unwrap();
+	assert_eq!(prices.len(), 2);
+}
+
+#[tokio::test]
+async fn price_perp() {
+	let c = testnet_client(ExchangeName::Bybit);
+	let price = c.price(Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp)).await.unwrap();
+	assert!(price > 0.);
+}
+
+#[tokio::test]
+async fn open_interest() {
+	let c = testnet_client(ExchangeName::Bybit);
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+	let oi = c.open_interest(symbol, "5m".into(), 5.into()).await.unwrap();
+	assert!(!oi.is_empty());
+}
@@ -0,0 +1,39 @@
+use v_exchanges::prelude::*;
+
+use super::testnet_client;
+
+#[tokio::test]
+async fn exchange_info_spot() {
+	let mut c = testnet_client(ExchangeName::Kucoin);
+	let info = c.exchange_info(Instrument::Spot).await.unwrap();
+	assert!(!info.pairs.is_empty());
+}
+
+#[tokio::test]
+async fn exchange_info_perp() {
+	let mut c = testnet_client(ExchangeName::Kucoin);
+	let info = c.exchange_info(Instrument::Perp).await.unwrap();
+	assert!(!info.pairs.is_empty());
+}
+
+#[tokio::test]
+async fn klines_spot() {
+	let c = testnet_client(ExchangeName::Kucoin);
+	let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Spot);
+	let klines = c.klines(symbol, "1m".into(), 5.into()).await.unwrap();
+	assert!(!klines.is_empty());
+}
+
+#[tokio::test]
+async fn prices_spot() {
+	let c = testnet_client(ExchangeName::Kucoin);
+	let prices = c.prices(Some(vec![Pair::new("BTC", "USDT")]), Instrument::Spot).await.unwrap();
+	assert_eq!(prices.len(), 1);
+}
+
+#[tokio::test]
+async fn price_perp() {
+	let c = testnet_client(ExchangeName::Kucoin);
+	let price = c.price(Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp)).await.unwrap();
+	assert!(price > 0.);
+}
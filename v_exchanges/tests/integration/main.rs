@@ -0,0 +1,24 @@
+//! Exercises every implemented endpoint against exchange testnets.
+//!
+//! Responses are cached via `RequestConfig::cache_testnet_calls`, so only the first run hits the network; afterwards this acts as a regression test
+//! for our deserialization against the stored payloads.
+#[cfg(feature = "binance")]
+mod binance;
+#[cfg(feature = "bybit")]
+mod bybit;
+#[cfg(feature = "kucoin")]
+mod kucoin;
+
+use std::time::Duration;
+
+use v_exchanges::prelude::*;
+
+const CACHE_DURATION: Duration = Duration::from_secs(30 * 86_400);
+
+#[allow(dead_code)]
+fn testnet_client(exchange: ExchangeName) -> Box<dyn Exchange> {
+	let mut client = exchange.init_client();
+	client.set_use_testnet(true);
+	client.set_cache_testnet_calls(Some(CACHE_DURATION));
+	client
+}
@@ -0,0 +1,39 @@
+//! HTTP/1.1 vs HTTP/2 latency for a burst of 100 concurrent Binance price requests.
+//!
+//! Hits the live API, so numbers depend on the network; run with `cargo bench -p v_exchanges_adapters --bench http_client`.
+use criterion::{Criterion, criterion_group, criterion_main};
+use futures_util::future::join_all;
+use v_exchanges_adapters::{
+	Client, HttpClient as _,
+	binance::{BinanceHttpUrl, BinanceOption},
+};
+
+const CONCURRENT_REQUESTS: usize = 100;
+
+fn client(prefer_http2: bool) -> Client {
+	let mut client = Client::default();
+	client.http_client_mut().config.prefer_http2 = prefer_http2;
+	client
+}
+
+async fn burst(client: &Client) {
+	let requests = (0..CONCURRENT_REQUESTS).map(|_| client.get::<serde_json::Value, _, _>("/fapi/v1/ticker/price", &[("symbol", "BTCUSDT")], [BinanceOption::HttpUrl(BinanceHttpUrl::FuturesUsdM)]));
+	for r in join_all(requests).await {
+		r.expect("Binance price request failed");
+	}
+}
+
+fn http_versions(c: &mut Criterion) {
+	let rt = tokio::runtime::Runtime::new().unwrap();
+	let mut group = c.benchmark_group("binance_100_prices");
+	group.sample_size(10);
+	for (name, prefer_http2) in [("http1.1", false), ("http2", true)] {
+		let client = client(prefer_http2);
+		rt.block_on(burst(&client)); // warm the connection pool
+		group.bench_function(name, |b| b.iter(|| rt.block_on(burst(&client))));
+	}
+	group.finish();
+}
+
+criterion_group!(benches, http_versions);
+criterion_main!(benches);
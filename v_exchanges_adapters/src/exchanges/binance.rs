@@ -1,14 +1,27 @@
 // A module for communicating with the [Binance API](https://binance-docs.github.io/apidocs/spot/en/).
 
-use std::{marker::PhantomData, str::FromStr, time::SystemTime};
+use std::{
+	collections::BTreeMap,
+	marker::PhantomData,
+	str::FromStr,
+	sync::{
+		Arc,
+		atomic::{AtomicU32, Ordering},
+	},
+	time::{Duration, SystemTime},
+};
 
 use ahash::AHashSet;
 use eyre::eyre;
 use generics::{
 	ConstructAuthError, UrlError,
 	http::{ApiError, BuildError, HandleError, *},
+	ws::WsConfig,
+};
+#[cfg(feature = "binance-ws")]
+use generics::{
 	tokio_tungstenite::tungstenite,
-	ws::{ContentEvent, ResponseOrContent, Topic, WsConfig, WsError, WsHandler},
+	ws::{ContentEvent, ResponseOrContent, SubscriptionStatus, Topic, WsError, WsHandler},
 };
 use hmac::{Hmac, KeyInit as _, Mac};
 use jiff::{SignedDuration, Timestamp};
@@ -16,7 +29,7 @@ use secrecy::{ExposeSecret as _, SecretString};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use sha2::Sha256;
 use url::Url;
-use v_utils::utils::truncate_msg;
+use v_utils::trades::Instrument;
 
 use crate::traits::*;
 
@@ -35,7 +48,7 @@ where
 		}
 	}
 
-	#[tracing::instrument(skip_all, fields(?builder))]
+	#[tracing::instrument(target = "v_exchanges::binance", skip_all, fields(?builder))]
 	fn build_request(&self, mut builder: RequestBuilder, request_body: &Option<B>, _: u8) -> Result<Request, BuildError> {
 		if let Some(body) = request_body {
 			let encoded = serde_urlencoded::to_string(body)?;
@@ -79,12 +92,17 @@ where
 		self.options.pubkey.as_deref().map(|k| hex::encode(&Sha256::digest(k.as_bytes())[..4]))
 	}
 
+	fn supports_http2(&self) -> bool {
+		true
+	}
+
 	fn handle_response(&self, status: StatusCode, headers: HeaderMap, response_body: Bytes) -> Result<Self::Successful, HandleError> {
+		// sent on errors too, 429s included
+		if let Some(used) = headers.get("X-MBX-USED-WEIGHT-1M").and_then(|v| v.to_str().ok()).and_then(|s| u32::from_str(s).ok()) {
+			self.options.used_weight_1m.store(used, Ordering::Relaxed);
+		}
 		if status.is_success() {
-			serde_json::from_slice(&response_body).map_err(|error| {
-				let response_str = truncate_msg(String::from_utf8_lossy(&response_body));
-				HandleError::Parse(eyre!("Failed to parse response: {error}\nResponse body: {response_str}"))
-			})
+			parse_response(&response_body)
 		} else {
 			// https://binance-docs.github.io/apidocs/spot/en/#limits
 
@@ -108,11 +126,11 @@ where
 						if let Ok(retry_after) = u32::from_str(string) {
 							Some(retry_after)
 						} else {
-							tracing::debug!("Invalid number in Retry-After header");
+							tracing::debug!(target: "v_exchanges::binance", "Invalid number in Retry-After header");
 							None
 						}
 					} else {
-						tracing::debug!("Non-ASCII character in Retry-After header");
+						tracing::debug!(target: "v_exchanges::binance", "Non-ASCII character in Retry-After header");
 						None
 					}
 				} else {
@@ -128,38 +146,34 @@ where
 				return Err(e);
 			}
 
-			let e: BinanceError = match serde_json::from_slice::<BinanceError>(&response_body) {
-				Ok(binance_error) => binance_error,
-				Err(parse_error) => {
-					let response_str = truncate_msg(String::from_utf8_lossy(&response_body));
-					return Err(HandleError::Parse(eyre!("Failed to parse error response: {parse_error}\nResponse body: {response_str}")));
-				}
-			};
+			let e: BinanceError = parse_response(&response_body)?;
 			Err(ApiError::from(e).into())
 		}
 	}
 }
 
 // Ws stuff {{{
+#[cfg(feature = "binance-ws")]
 #[derive(Clone, Debug)]
 pub struct BinanceWsHandler {
 	options: BinanceOptions,
-	/// Binance has a retarded `listen-key` system. This is needed only for that.
-	_last_keep_alive: SystemTime = SystemTime::UNIX_EPOCH,
 }
+#[cfg(feature = "binance-ws")]
 impl BinanceWsHandler {
 	pub fn new(options: BinanceOptions) -> Self {
-		Self {
-			options,
-			_last_keep_alive: SystemTime::UNIX_EPOCH, // semantically creation itself does nothing for refreshing the token. But refreshment timer on it will be set to 0 on creation, so that's when we'll set it to [now](SystemTime::now)
-		}
+		Self { options }
 	}
 }
+/// Streams per `SUBSCRIBE`, so that resubscribing to a large set doesn't make for one oversized message.
+#[cfg(feature = "binance-ws")]
+const MAX_STREAMS_PER_SUBSCRIBE: usize = 200;
+#[cfg(feature = "binance-ws")]
 impl WsHandler for BinanceWsHandler {
 	fn config(&self) -> Result<WsConfig, UrlError> {
 		let mut config = self.options.ws_config.clone();
 		match self.options.ws_url {
 			BinanceWsUrl::None => tracing::warn!(
+				target: "v_exchanges::binance",
 				"BinanceWsUrl was not set. Due to Binance shenanigans, any provided topics will now be ignored, and must be manually hardcoded into the provided url on creation of the websocket. However, recommended approach is to simply provide a BinanceOption::WsUrl."
 			),
 			_ => {
@@ -205,14 +219,17 @@ impl WsHandler for BinanceWsHandler {
 			.filter_map(|topic| if let Topic::String(s) = topic { Some(s) } else { None })
 			.cloned()
 			.collect::<Vec<_>>();
-		let messages = {
-			let msg = serde_json::json!({
-				"method": "SUBSCRIBE",
-				"params": string_topics,
-				"id": rand::random::<u64>(),
-			});
-			vec![tungstenite::Message::Text(msg.to_string().into())]
-		};
+		let messages = string_topics
+			.chunks(MAX_STREAMS_PER_SUBSCRIBE)
+			.map(|chunk| {
+				let msg = serde_json::json!({
+					"method": "SUBSCRIBE",
+					"params": chunk,
+					"id": rand::random::<u64>(),
+				});
+				tungstenite::Message::Text(msg.to_string().into())
+			})
+			.collect::<Vec<_>>();
 
 		let order_topics = topics
 			.into_iter()
@@ -225,6 +242,20 @@ impl WsHandler for BinanceWsHandler {
 		Ok(messages)
 	}
 
+	/// Goes into the combined-stream url, so resumed topics come back with the connection itself, no subscribe round-trip.
+	fn extend_topics(&mut self, topics: AHashSet<String>) -> Result<(), WsError> {
+		self.options.ws_topics.extend(topics);
+		Ok(())
+	}
+
+	/// Streams baked into the url are live as soon as the connection is, there being no per-topic ack to wait on.
+	fn subscription_status(&self) -> std::collections::HashMap<String, SubscriptionStatus> {
+		match self.options.ws_url {
+			BinanceWsUrl::None => std::collections::HashMap::new(),
+			_ => self.options.ws_config.topics.union(&self.options.ws_topics).map(|t| (t.clone(), SubscriptionStatus::Active)).collect(),
+		}
+	}
+
 	fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
 		#[derive(serde::Deserialize)]
 		struct NamedStreamData {
@@ -242,18 +273,22 @@ impl WsHandler for BinanceWsHandler {
 		let (event_type, event_time, event_data) = {
 			//dbg: dirty impl
 			let mut event_data = data.as_object().unwrap().to_owned();
-			let event_type = data["e"].as_str().unwrap().to_owned();
-			event_data.remove("e");
-			let event_ts: i64 = data["E"].as_i64().unwrap();
-			let event_time = Timestamp::from_millisecond(event_ts).unwrap();
-			event_data.remove("E");
+			// spot `@bookTicker` carries neither: it's named after its stream, and left at the epoch, so that it's kept out of latency stats
+			let event_type = match event_data.remove("e") {
+				Some(serde_json::Value::String(e)) => e,
+				_ => event_topic.rsplit('@').next().unwrap_or_default().to_owned(),
+			};
+			let event_time = match event_data.remove("E").and_then(|e| e.as_i64()) {
+				Some(event_ts) => Timestamp::from_millisecond(event_ts).unwrap(),
+				None => Timestamp::UNIX_EPOCH,
+			};
 			(event_type, event_time, event_data.into())
 		};
 
-		//TEST: handle listen-key expiration //NB: Claude wrote this, no clue if logic is correct
+		//TEST: handle listen-key expiration
 		if event_type == "listenKeyExpired" {
-			tracing::error!("Listen key expired. This requires re-authentication and reconnection.");
-			return Err(WsError::Auth(ConstructAuthError::Other(eyre!("Listen key expired"))));
+			tracing::error!(target: "v_exchanges::binance", "Listen key expired. This requires re-authentication and reconnection.");
+			return Err(WsError::SessionExpired("Binance listen key expired".to_owned()));
 		}
 
 		let content = ContentEvent {
@@ -264,39 +299,8 @@ impl WsHandler for BinanceWsHandler {
 		};
 		Ok(ResponseOrContent::Content(content))
 	}
-
-	// stream listen-key keepalive works for:
-	// - [x] binance spot
-	// - [?] binance perp
-
-	//DEPRECATE
-	//	fn handle_post(&mut self) -> Result<Option<Vec<tungstenite::Message>>, WsError> {
-	//	if SystemTime::now().duration_since(self.last_keep_alive).unwrap() > Duration::from_mins(30) {
-	//		//XXX: will fail if it's not a USER_DATA_STREAM //TODO: generalize to all binance streams
-	//		let msg_json = serde_json::json!({
-	//			"id": "815d5fce-0880-4287-a567-80badf004c74",
-	//			"method": "userDataStream.ping",
-	//			"params": {
-	//				"apiKey": self.options.pubkey.as_ref().unwrap()
-	//			}
-	//		});
-	//		return Ok(Some(vec![tungstenite::Message::Text(msg_json.to_string().into())]));
-	//	}
-	//	Ok(None)
-	//}
-	//DEPRECATE
-	//if SystemTime::now().duration_since(self.last_keep_alive).unwrap() > Duration::from_mins(30) {
-	//	//XXX: will fail if it's not a USER_DATA_STREAM
-	//	//TODO send `PUT /api/v3/userDataStream`
-	//	let client = crate::Client::default();
-	//	.request(
-	//		&self.options,
-	//		"PUT",
-	//		"/api/v3/userDataStream",
-	//		None::<()>,
-	//	)
-	//}
 }
+#[cfg(feature = "binance-ws")]
 impl WsOption for BinanceOption {
 	type WsHandler = BinanceWsHandler;
 
@@ -326,6 +330,8 @@ pub enum BinanceOption {
 	BookSnapshotFreq(Option<std::time::Duration>),
 	/// Base url for HTTP requests
 	HttpUrl(BinanceHttpUrl),
+	/// Base url for requests scoped to an [Instrument], overriding [BinanceHttpUrl::for_instrument]. Eg `SpotData` for a market-data-only key.
+	InstrumentHttpUrl(Instrument, BinanceHttpUrl),
 	/// Authentication type for HTTP requests
 	HttpAuth(BinanceAuth),
 
@@ -364,6 +370,17 @@ pub enum BinanceHttpUrl {
 	#[default]
 	None,
 }
+impl BinanceHttpUrl {
+	/// Where `instrument`'s endpoints live, absent an [override](BinanceOption::InstrumentHttpUrl).
+	pub fn for_instrument(instrument: Instrument) -> Self {
+		match instrument {
+			Instrument::Spot | Instrument::Margin => Self::Spot,
+			Instrument::Perp => Self::FuturesUsdM,
+			Instrument::PerpInverse => Self::FuturesCoinM,
+			Instrument::Options => Self::EuropeanOptions,
+		}
+	}
+}
 impl EndpointUrl for BinanceHttpUrl {
 	fn url_mainnet(&self) -> Url {
 		match self {
@@ -453,10 +470,15 @@ impl EndpointUrl for BinanceWsUrl {
 	}
 }
 
+/// How a request proves who's making it. Pick by the endpoint's security type in Binance docs.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum BinanceAuth {
+	/// `X-MBX-APIKEY` header, plus `timestamp`, optional `recvWindow` and an HMAC `signature` over the query and body.
+	/// Required by TRADE, MARGIN and USER_DATA endpoints: anything reading or changing account state.
 	Sign,
-	Key, //Q: Not sure if anything uses it.
+	/// Only the `X-MBX-APIKEY` header, no signature. For USER_STREAM endpoints (listen key management) and the few MARKET_DATA ones that identify the key, like `/api/v3/historicalTrades`.
+	/// Sending this to a signed endpoint is rejected for the missing `timestamp`.
+	Key,
 	#[default]
 	None,
 }
@@ -476,7 +498,7 @@ pub struct BinanceRequestHandler<'a, R: DeserializeOwned> {
 }
 
 /// A `struct` that represents a set of [BinanceOption] s.
-#[derive(Clone, derive_more::Debug, Default)]
+#[derive(Clone, derive_more::Debug, smart_default::SmartDefault)]
 pub struct BinanceOptions {
 	/// see [BinanceOption::Pubkey]
 	pub pubkey: Option<String>,
@@ -487,11 +509,14 @@ pub struct BinanceOptions {
 	pub recv_window: Option<std::time::Duration>,
 	/// see [BinanceOption::HttpUrl]
 	pub http_url: BinanceHttpUrl,
+	/// see [BinanceOption::InstrumentHttpUrl]
+	pub instrument_http_urls: BTreeMap<Instrument, BinanceHttpUrl>,
 	/// see [BinanceOption::HttpAuth]
 	pub http_auth: BinanceAuth,
 	/// see [BinanceOption::WsUrl]
 	pub ws_url: BinanceWsUrl,
 	/// see [BinanceOption::WsConfig]
+	#[default(binance_ws_config())]
 	pub ws_config: WsConfig,
 	/// see [BinanceOption::WsTopics]
 	pub ws_topics: AHashSet<String>,
@@ -499,7 +524,19 @@ pub struct BinanceOptions {
 	pub test: bool,
 	/// see [BinanceOption::BookSnapshotFreq]
 	pub book_snapshot_freq: Option<std::time::Duration>,
+	/// Request weight used this minute, as of the last response's `X-MBX-USED-WEIGHT-1M`. Shared by all clones, so every request made through a client updates it.
+	pub used_weight_1m: Arc<AtomicU32>,
+}
+impl BinanceOptions {
+	pub fn http_url_for(&self, instrument: Instrument) -> BinanceHttpUrl {
+		self.instrument_http_urls.get(&instrument).copied().unwrap_or_else(|| BinanceHttpUrl::for_instrument(instrument))
+	}
 }
+/// [WsConfig::default] with Binance's limit of 5 incoming messages per second per connection seeded in. Going over gets the connection dropped.
+fn binance_ws_config() -> WsConfig {
+	WsConfig::default().with_outbound_rate(Some((5, Duration::from_secs(1))))
+}
+
 impl HandlerOptions for BinanceOptions {
 	type OptionItem = BinanceOption;
 
@@ -511,6 +548,9 @@ impl HandlerOptions for BinanceOptions {
 			Self::OptionItem::Test(v) => self.test = v,
 			Self::OptionItem::Secret(v) => self.secret = Some(v),
 			Self::OptionItem::HttpUrl(v) => self.http_url = v,
+			Self::OptionItem::InstrumentHttpUrl(instrument, v) => {
+				self.instrument_http_urls.insert(instrument, v);
+			}
 			Self::OptionItem::HttpAuth(v) => self.http_auth = v,
 			Self::OptionItem::WsUrl(v) => self.ws_url = v,
 			Self::OptionItem::WsConfig(v) => self.ws_config = v,
@@ -541,7 +581,9 @@ impl HandlerOption for BinanceOption {
 }
 
 // Error Codes {{{
-#[derive(Clone, Debug, Deserialize)]
+/// Kept intact inside [ApiError::Other], so callers can `downcast_ref::<BinanceError>()` to match on the [code](BinanceErrorCode).
+#[derive(Clone, Debug, Deserialize, thiserror::Error)]
+#[error("Binance API error: {msg}")]
 pub struct BinanceError {
 	pub code: BinanceErrorCode,
 	pub msg: String,
@@ -552,7 +594,7 @@ impl From<BinanceError> for ApiError {
 		match e.code {
 			BinanceErrorCode::RejectedMbxKey(_) | BinanceErrorCode::InvalidListenKey(_) => AuthError::KeyExpired { msg: e.msg }.into(),
 			BinanceErrorCode::Unauthorized(_) | BinanceErrorCode::InvalidSignature(_) | BinanceErrorCode::BadApiKeyFmt(_) => AuthError::Unauthorized { msg: e.msg }.into(),
-			_ => ApiError::Other(eyre!("Binance API error: {}", e.msg)),
+			_ => ApiError::Other(eyre::Report::new(e)),
 		}
 	}
 }
@@ -752,7 +794,7 @@ impl From<i32> for BinanceErrorCode {
 			-2026 => Self::OrderArchived(code),
 
 			code => {
-				tracing::warn!("Encountered unknown Binance error code: {code}");
+				tracing::warn!(target: "v_exchanges::binance", "Encountered unknown Binance error code: {code}");
 				Self::Other(code)
 			}
 		}
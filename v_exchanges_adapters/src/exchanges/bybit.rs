@@ -9,20 +9,24 @@ use std::{
 };
 
 use ahash::AHashSet;
-use eyre::{WrapErr as _, eyre};
-use generics::{ConstructAuthError, UrlError, tokio_tungstenite::tungstenite};
+use eyre::eyre;
+use generics::{ConstructAuthError, UrlError};
 use hmac::{Hmac, KeyInit as _, Mac};
-use jiff::Timestamp;
 use secrecy::{ExposeSecret as _, SecretString};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
 use sha2::Sha256;
-use tracing::instrument;
 use url::Url;
 use v_exchanges_api_generics::{
 	http::{header::HeaderValue, *},
 	ws::*,
 };
+#[cfg(feature = "bybit-ws")]
+use generics::tokio_tungstenite::tungstenite;
+#[cfg(feature = "bybit-ws")]
+use jiff::Timestamp;
+#[cfg(feature = "bybit-ws")]
+use tracing::instrument;
 
 use crate::traits::*;
 
@@ -144,13 +148,14 @@ pub enum BybitErrorCode {
 	// 110xxx - Order/Position errors
 	OrderNotExist(i32),
 	InsufficientBalance(i32),
+	OrderCompletedOrCancelled(i32),
 
 	#[default]
 	Ok,
 	Other(i32),
 }
 impl BybitErrorCode {
-	fn as_i32(self) -> i32 {
+	pub fn as_i32(self) -> i32 {
 		match self {
 			Self::Ok => 0,
 			Self::InvalidApiKey(c)
@@ -168,6 +173,7 @@ impl BybitErrorCode {
 			| Self::ApiKeyExpired(c)
 			| Self::OrderNotExist(c)
 			| Self::InsufficientBalance(c)
+			| Self::OrderCompletedOrCancelled(c)
 			| Self::Other(c) => c,
 		}
 	}
@@ -345,9 +351,16 @@ where
 	}
 }
 
+#[cfg(feature = "bybit-ws")]
 #[derive(Debug, derive_new::new)]
 pub struct BybitWsHandler {
 	options: BybitOptions,
+	/// Topics of subscribe requests not yet answered, by `req_id`. Bybit's rejections only reference the id.
+	#[new(default)]
+	pending: ahash::AHashMap<String, String>,
+	/// Answered subscribe requests of the current connection. See [WsHandler::subscription_status].
+	#[new(default)]
+	subscriptions: std::collections::HashMap<String, SubscriptionStatus>,
 }
 /// A `enum` that represents the base url of the Bybit Ws API.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
@@ -362,9 +375,7 @@ pub enum BybitWsUrlBase {
 }
 /// [WsConfig::default] with Bybit's 20s active-ping interval seeded in.
 fn bybit_ws_config() -> WsConfig {
-	let mut config = WsConfig::default();
-	config.set_active_ping_freq(Duration::from_secs(20)).expect("non-zero literal");
-	config
+	WsConfig::default().with_active_ping_freq(Duration::from_secs(20))
 }
 
 impl EndpointUrl for BybitHttpUrl {
@@ -385,10 +396,12 @@ impl EndpointUrl for BybitHttpUrl {
 	}
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-struct BybitError {
-	code: BybitErrorCode,
-	msg: String,
+/// Kept intact inside [ApiError::Other], so callers can `downcast_ref::<BybitError>()` to match on the [code](BybitErrorCode).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, thiserror::Error)]
+#[error("Bybit error {}: {msg}", code.as_i32())]
+pub struct BybitError {
+	pub code: BybitErrorCode,
+	pub msg: String,
 }
 impl From<BybitError> for ApiError {
 	fn from(e: BybitError) -> Self {
@@ -400,7 +413,7 @@ impl From<BybitError> for ApiError {
 			BybitErrorCode::PermissionDenied(_) => AuthError::Unauthorized { msg: e.msg }.into(),
 			BybitErrorCode::TooManyVisits(_) | BybitErrorCode::IpBanned(_) | BybitErrorCode::IpRateLimit(_) => IpError::Timeout { until: None }.into(),
 			BybitErrorCode::ComplianceRules(_) => IpError::GeoBlocked { msg: e.msg }.into(),
-			_ => ApiError::Other(eyre!("Bybit error {}: {}", e.code.as_i32(), e.msg)),
+			_ => ApiError::Other(eyre::Report::new(e)),
 		}
 	}
 }
@@ -424,8 +437,9 @@ impl From<i32> for BybitErrorCode {
 			33004 => Self::ApiKeyExpired(code),
 			110001 => Self::OrderNotExist(code),
 			110007 => Self::InsufficientBalance(code),
+			110008 => Self::OrderCompletedOrCancelled(code),
 			code => {
-				tracing::warn!("Encountered unknown Bybit error code: {code}");
+				tracing::warn!(target: "v_exchanges::bybit", "Encountered unknown Bybit error code: {code}");
 				Self::Other(code)
 			}
 		}
@@ -483,14 +497,15 @@ where
 		self.options.pubkey.as_deref().map(|k| hex::encode(&Sha256::digest(k.as_bytes())[..4]))
 	}
 
+	fn supports_http2(&self) -> bool {
+		true
+	}
+
 	fn handle_response(&self, status: StatusCode, _: HeaderMap, response_body: Bytes) -> Result<Self::Successful, HandleError> {
 		if status.is_success() {
 			// Bybit returns HTTP 200 even for API errors, so we need to check retCode
 			// First, try to parse as a generic response to check for errors
-			let value: serde_json::Value = serde_json::from_slice(&response_body).map_err(|error| {
-				let response_str = v_utils::utils::truncate_msg(String::from_utf8_lossy(&response_body));
-				HandleError::Parse(eyre!("Failed to parse response: {error}\nResponse body: {response_str}"))
-			})?;
+			let value: serde_json::Value = parse_response(&response_body)?;
 
 			// Check if response contains retCode field (V3/V5 API format)
 			if let Some(ret_code) = value.get("retCode").and_then(|v| v.as_i64())
@@ -506,10 +521,7 @@ where
 			}
 
 			// No error, deserialize to the expected type
-			serde_json::from_value(value.clone()).map_err(|error| {
-				let response_str = v_utils::utils::truncate_msg(value.to_string());
-				HandleError::Parse(eyre!("Failed to parse successful response: {error}\nResponse body: {response_str}"))
-			})
+			serde_json::from_value(value.clone()).map_err(|error| parse_error(error, &value.to_string()))
 		} else {
 			if status == 403 {
 				let msg = std::str::from_utf8(&response_body).unwrap_or("<non-utf8 body>").to_string();
@@ -524,19 +536,14 @@ where
 				return Err(ApiError::Auth(AuthError::Unauthorized { msg }).into());
 			}
 			// https://bybit-exchange.github.io/docs/spot/v3/#t-ratelimits
-			let api_error: BybitError = match serde_json::from_slice(&response_body) {
-				Ok(parsed) => parsed,
-				Err(error) => {
-					let response_str = v_utils::utils::truncate_msg(String::from_utf8_lossy(&response_body));
-					return Err(HandleError::Parse(eyre!("Failed to parse error response: {error}\nResponse body: {response_str}")));
-				}
-			};
+			let api_error: BybitError = parse_response(&response_body)?;
 			Err(ApiError::from(api_error).into())
 		}
 	}
 }
 
 // Ws stuff {{{
+#[cfg(feature = "bybit-ws")]
 impl WsHandler for BybitWsHandler {
 	fn config(&self) -> Result<WsConfig, UrlError> {
 		let mut config = self.options.ws_config.clone();
@@ -550,8 +557,9 @@ impl WsHandler for BybitWsHandler {
 		Ok(config)
 	}
 
-	#[instrument(skip_all)]
+	#[instrument(target = "v_exchanges::bybit", skip_all)]
 	fn handle_auth(&mut self) -> Result<Vec<tungstenite::Message>, WsError> {
+		self.subscriptions.clear();
 		match self.options.ws_auth {
 			true => {
 				let pubkey = self.options.pubkey.as_ref().ok_or(ConstructAuthError::new_missing_pubkey())?;
@@ -591,10 +599,22 @@ impl WsHandler for BybitWsHandler {
 				Topic::Order(_) => todo!(),
 			})
 			.collect();
-		Ok(vec![tungstenite::Message::Text(json!({ "op": "subscribe", "args": topics }).to_string().into())])
+		let req_id = Timestamp::now().as_millisecond().to_string();
+		self.pending.insert(req_id.clone(), topics.join(","));
+		Ok(vec![tungstenite::Message::Text(json!({ "op": "subscribe", "args": topics, "req_id": req_id }).to_string().into())])
+	}
+
+	/// Subscribed in one batch on connect, after auth when [ws_auth](BybitOptions::ws_auth) is set.
+	fn extend_topics(&mut self, topics: AHashSet<String>) -> Result<(), WsError> {
+		self.options.ws_topics.extend(topics);
+		Ok(())
 	}
 
-	#[instrument(skip_all, fields(jrpc = ?format_args!("{:#?}", jrpc)))]
+	fn subscription_status(&self) -> std::collections::HashMap<String, SubscriptionStatus> {
+		self.subscriptions.clone()
+	}
+
+	#[instrument(target = "v_exchanges::bybit", skip_all, fields(jrpc = ?format_args!("{:#?}", jrpc)))]
 	fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
 		//TODO!!!!!!!!!!!: tell serde that enum name is not part of it
 		#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
@@ -624,8 +644,11 @@ impl WsHandler for BybitWsHandler {
 		pub struct ContentResponse {
 			pub data: serde_json::Value,
 			pub topic: String,
+			/// Private topics stamp `creationTime` instead.
+			#[serde(alias = "creationTime")]
 			pub ts: i64,
-			#[serde(rename = "type")]
+			/// Absent on private topics.
+			#[serde(rename = "type", default)]
 			pub event_type: String,
 		}
 		impl From<ContentResponse> for ContentEvent {
@@ -639,12 +662,15 @@ impl WsHandler for BybitWsHandler {
 			}
 		}
 
-		let bybit_response = serde_json::from_value::<BybitResponse>(jrpc.clone()).wrap_err_with(|| format!("Failed to deserialize Bybit response: {jrpc:?}"))?;
+		let bybit_response = serde_json::from_value::<BybitResponse>(jrpc.clone()).map_err(|e| {
+			tracing::warn!(target: "v_exchanges::bybit", "Failed to deserialize Bybit response: {jrpc:?}");
+			WsError::Parse(e)
+		})?;
 		match bybit_response {
-			BybitResponse::Feedback(FeedbackResponse { op, success, ret_msg, .. }) => match op {
+			BybitResponse::Feedback(FeedbackResponse { op, success, ret_msg, req_id, .. }) => match op {
 				Operation::Auth => match success {
 					true => {
-						tracing::info!("Ws authentication successful");
+						tracing::info!(target: "v_exchanges::bybit", "Ws authentication successful");
 						Ok(ResponseOrContent::Response(
 							self.handle_subscribe(self.options.ws_topics.clone().into_iter().map(Topic::String).collect())?,
 						))
@@ -652,15 +678,32 @@ impl WsHandler for BybitWsHandler {
 					false => Err(ConstructAuthError::Other(eyre!("Authentication was not successful: {ret_msg}")).into()),
 				},
 				Operation::Subscribe => {
-					if jrpc["success"].as_bool() == Some(true) {
-						tracing::info!("Ws topics subscription successful");
+					let topic = req_id.and_then(|id| self.pending.remove(&id));
+					if success {
+						tracing::info!(target: "v_exchanges::bybit", "Ws topics subscription successful");
+						for t in topic.iter().flat_map(|t| t.split(',')) {
+							self.subscriptions.insert(t.to_owned(), SubscriptionStatus::Active);
+						}
 					} else {
-						match self.options.ws_auth || &ret_msg != "Request not authorized" {
-							true => return Err(WsError::Subscription(ret_msg)),
+						let reason = match self.options.ws_auth || &ret_msg != "Request not authorized" {
+							true => SubscriptionRejectReason::from_message(&ret_msg),
 							false => {
-								return Err(ConstructAuthError::Other(eyre!("Tried to access a private endpoint without authentication")).into());
+								tracing::error!(target: "v_exchanges::bybit", "Tried to access a private endpoint without authentication");
+								SubscriptionRejectReason::Unauthorized
 							}
+						};
+						// rejections of unknown topics name the offender: `error:handler not found,topic:orderbook.1.NOTACOIN`
+						let named = ret_msg.split_once("topic:").map(|(_, t)| t.trim().to_owned());
+						// a named offender is the only rejected one; otherwise the whole batch went down
+						let failed: Vec<String> = match &named {
+							Some(t) => vec![t.clone()],
+							None => topic.iter().flat_map(|t| t.split(',')).map(str::to_owned).collect(),
+						};
+						for t in failed {
+							self.subscriptions.insert(t, SubscriptionStatus::Failed(ret_msg.clone()));
 						}
+						let topic = named.or(topic).unwrap_or_else(|| "<unknown>".to_owned());
+						return Err(WsError::Subscription { topic, reason });
 					}
 					Ok(ResponseOrContent::Response(vec![]))
 				}
@@ -687,6 +730,7 @@ impl EndpointUrl for BybitWsUrlBase {
 		}
 	}
 }
+#[cfg(feature = "bybit-ws")]
 impl WsOption for BybitOption {
 	type WsHandler = BybitWsHandler;
 
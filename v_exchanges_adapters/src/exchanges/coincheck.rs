@@ -135,7 +135,7 @@ where
 	fn base_url(&self, is_test: bool) -> Result<url::Url, generics::UrlError> {
 		match is_test {
 			true => todo!(),
-			false => url::Url::parse(self.options.http_url.as_str()).map_err(generics::UrlError::Parse),
+			false => Ok(url::Url::parse(self.options.http_url.as_str())?),
 		}
 	}
 
@@ -180,17 +180,11 @@ where
 
 	fn handle_response(&self, status: StatusCode, _: HeaderMap, response_body: Bytes) -> Result<Self::Successful, HandleError> {
 		if status.is_success() {
-			serde_json::from_slice(&response_body).map_err(|error| {
-				let response_str = v_utils::utils::truncate_msg(String::from_utf8_lossy(&response_body));
-				HandleError::Parse(eyre::eyre!("Failed to parse response: {error}\nResponse body: {response_str}"))
-			})
+			parse_response(&response_body)
 		} else {
-			let error = match serde_json::from_slice::<serde_json::Value>(&response_body) {
+			let error = match parse_response::<serde_json::Value>(&response_body) {
 				Ok(parsed_error) => HandleError::Api(ApiError::Other(eyre::eyre!("Coincheck API error (status {status}): {parsed_error}"))),
-				Err(error) => {
-					let response_str = v_utils::utils::truncate_msg(String::from_utf8_lossy(&response_body));
-					HandleError::Parse(eyre::eyre!("Failed to parse error response: {error}\nResponse body: {response_str}"))
-				}
+				Err(e) => e,
 			};
 			Err(error)
 		}
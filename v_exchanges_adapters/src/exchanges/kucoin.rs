@@ -1,14 +1,22 @@
 //! A module for communicating with the [Kucoin API](https://www.kucoin.com/docs/beginners/introduction).
 
-use std::{collections::HashSet, marker::PhantomData, time::SystemTime};
+use std::{
+	collections::{BTreeMap, HashSet},
+	marker::PhantomData,
+	time::{Duration, SystemTime},
+};
 
 use ahash::AHashSet;
 use eyre::eyre;
 use generics::{
 	ConstructAuthError, UrlError,
 	http::{ApiError, BuildError, HandleError, *},
+	ws::WsConfig,
+};
+#[cfg(feature = "kucoin-ws")]
+use generics::{
 	tokio_tungstenite::tungstenite,
-	ws::{ContentEvent, ResponseOrContent, Topic, WsConfig, WsError, WsHandler},
+	ws::{ContentEvent, ResponseOrContent, SubscriptionRejectReason, SubscriptionStatus, Topic, WsError, WsHandler},
 };
 use hmac::{Hmac, KeyInit as _, Mac};
 use jiff::Timestamp;
@@ -16,7 +24,7 @@ use secrecy::{ExposeSecret as _, SecretString};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use sha2::Sha256;
 use url::Url;
-use v_utils::utils::truncate_msg;
+use v_utils::trades::Instrument;
 
 use crate::traits::*;
 
@@ -35,7 +43,7 @@ where
 		}
 	}
 
-	#[tracing::instrument(skip_all, fields(?builder))]
+	#[tracing::instrument(target = "v_exchanges::kucoin", skip_all, fields(?builder))]
 	fn build_request(&self, mut builder: RequestBuilder, request_body: &Option<B>, _: u8) -> Result<Request, BuildError> {
 		let body_str = if let Some(body) = request_body {
 			let json = serde_json::to_string(body)?;
@@ -109,10 +117,7 @@ where
 	fn handle_response(&self, status: StatusCode, _headers: HeaderMap, response_body: Bytes) -> Result<Self::Successful, HandleError> {
 		if status.is_success() {
 			// Kucoin returns HTTP 200 even for API errors, so we need to check code field
-			let value: serde_json::Value = serde_json::from_slice(&response_body).map_err(|error| {
-				let response_str = truncate_msg(String::from_utf8_lossy(&response_body));
-				HandleError::Parse(eyre!("Failed to parse response: {error}\nResponse body: {response_str}"))
-			})?;
+			let value: serde_json::Value = parse_response(&response_body)?;
 
 			// Check if response contains code field
 			if let Some(code) = value.get("code").and_then(|v| v.as_str())
@@ -128,10 +133,7 @@ where
 			}
 
 			// No error, deserialize to the expected type
-			serde_json::from_value(value.clone()).map_err(|error| {
-				let response_str = truncate_msg(value.to_string());
-				HandleError::Parse(eyre!("Failed to parse successful response: {error}\nResponse body: {response_str}"))
-			})
+			serde_json::from_value(value.clone()).map_err(|error| parse_error(error, &value.to_string()))
 		} else {
 			if status == 401 {
 				use generics::http::AuthError;
@@ -141,27 +143,36 @@ where
 				};
 				return Err(ApiError::Auth(AuthError::Unauthorized { msg }).into());
 			}
-			let api_error: KucoinError = match serde_json::from_slice(&response_body) {
-				Ok(parsed) => parsed,
-				Err(error) => {
-					let response_str = truncate_msg(String::from_utf8_lossy(&response_body));
-					return Err(HandleError::Parse(eyre!("Failed to parse error response: {error}\nResponse body: {response_str}")));
-				}
-			};
+			let api_error: KucoinError = parse_response(&response_body)?;
 			Err(ApiError::from(api_error).into())
 		}
 	}
 }
 
 // Ws stuff {{{
+#[cfg(feature = "kucoin-ws")]
 #[derive(Clone, Debug, derive_new::new)]
 pub struct KucoinWsHandler {
 	options: KucoinOptions,
+	/// Topics of subscribe requests not yet acked, by request id. Kucoin's error frames only reference the id.
+	#[new(default)]
+	pending: ahash::AHashMap<String, String>,
+	/// Answered subscribe requests of the current connection. See [WsHandler::subscription_status].
+	#[new(default)]
+	subscriptions: std::collections::HashMap<String, SubscriptionStatus>,
 }
+#[cfg(feature = "kucoin-ws")]
 impl WsHandler for KucoinWsHandler {
 	fn config(&self) -> Result<WsConfig, UrlError> {
 		let mut config = self.options.ws_config.clone();
-		if self.options.ws_url != KucoinWsUrl::None {
+		if let Some(bullet) = &self.options.ws_bullet {
+			let mut url = bullet.endpoint.clone();
+			url.query_pairs_mut().append_pair("token", bullet.token.expose_secret());
+			config.base_url = Some(url);
+			if !bullet.ping_interval.is_zero() {
+				config.set_active_ping_freq(bullet.ping_interval).expect("checked to be non-zero");
+			}
+		} else if self.options.ws_url != KucoinWsUrl::None {
 			config.base_url = match self.options.test {
 				true => Some(self.options.ws_url.url_testnet().ok_or_else(|| UrlError::MissingTestnet(self.options.ws_url.url_mainnet()))?),
 				false => Some(self.options.ws_url.url_mainnet()),
@@ -171,37 +182,98 @@ impl WsHandler for KucoinWsHandler {
 		Ok(config)
 	}
 
+	/// Kucoin authenticates the connection itself, through the token in its url (see [KucoinBullet]), so all that's left here is subscribing to the private channels.
 	fn handle_auth(&mut self) -> Result<Vec<tungstenite::Message>, WsError> {
-		if self.options.ws_config.auth {
-			let _pubkey = self.options.pubkey.as_ref().ok_or(ConstructAuthError::new_missing_pubkey())?;
-			let _secret = self.options.secret.as_ref().ok_or(ConstructAuthError::new_missing_secret())?;
-			//TODO: implement ws auth for kucoin
+		self.subscriptions.clear();
+		if !self.options.private_ws {
+			return Ok(vec![]);
 		}
+		if self.options.ws_bullet.is_none() {
+			return Err(ConstructAuthError::Other(eyre!("Private Kucoin ws requires a token from `/api/v1/bullet-private`, provided through KucoinOption::WsBullet")).into());
+		}
+		let id = Timestamp::now().as_millisecond();
+		let messages = KUCOIN_PRIVATE_TOPICS
+			.iter()
+			.enumerate()
+			.map(|(i, topic)| {
+				let id = (id + i as i64).to_string();
+				self.pending.insert(id.clone(), topic.to_string());
+				let msg = serde_json::json!({
+					"id": id,
+					"type": "subscribe",
+					"topic": topic,
+					"privateChannel": true,
+					"response": true,
+				});
+				tungstenite::Message::Text(msg.to_string().into())
+			})
+			.collect();
+		Ok(messages)
+	}
 
-		Ok(vec![])
+	fn active_ping(&self) -> Vec<tungstenite::Message> {
+		let msg = serde_json::json!({ "id": Timestamp::now().as_millisecond().to_string(), "type": "ping" });
+		vec![tungstenite::Message::Text(msg.to_string().into())]
 	}
 
+	/// One request per topic: Kucoin joins several symbols of one channel with commas inside a single topic (`/market/match:BTC-USDT,ETH-USDT`), so joining whole topics would produce a malformed one.
 	fn handle_subscribe(&mut self, topics: AHashSet<Topic>) -> Result<Vec<tungstenite::Message>, WsError> {
-		let string_topics = topics
+		let id = Timestamp::now().as_millisecond();
+		let messages = topics
 			.iter()
 			.filter_map(|topic| if let Topic::String(s) = topic { Some(s) } else { None })
-			.cloned()
-			.collect::<Vec<_>>();
-		let messages = {
-			let msg = serde_json::json!({
-				"type": "subscribe",
-				"topic": string_topics.join(","),
-				"response": true,
-			});
-			vec![tungstenite::Message::Text(msg.to_string().into())]
-		};
-
+			.enumerate()
+			.map(|(i, topic)| {
+				let id = (id + i as i64).to_string();
+				self.pending.insert(id.clone(), topic.clone());
+				let msg = serde_json::json!({
+					"id": id,
+					"type": "subscribe",
+					"topic": topic,
+					"response": true,
+				});
+				tungstenite::Message::Text(msg.to_string().into())
+			})
+			.collect();
 		Ok(messages)
 	}
 
+	/// Subscribed on connect like those of [KucoinOption::WsTopics]. The bullet token is left as is: it's fetched per connection, so a resumed one needs a fresh [KucoinOption::WsBullet] like any other.
+	fn extend_topics(&mut self, topics: AHashSet<String>) -> Result<(), WsError> {
+		self.options.ws_topics.extend(topics);
+		Ok(())
+	}
+
+	fn subscription_status(&self) -> std::collections::HashMap<String, SubscriptionStatus> {
+		self.subscriptions.clone()
+	}
+
 	fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
 		// Basic structure for Kucoin websocket messages
 		let event_type = jrpc.get("type").and_then(|v| v.as_str()).unwrap_or("unknown").to_string();
+		match event_type.as_str() {
+			"welcome" | "pong" => return Ok(ResponseOrContent::Response(vec![])),
+			"ack" => {
+				if let Some(id) = jrpc.get("id").and_then(|v| v.as_str()) {
+					if let Some(topic) = self.pending.remove(id) {
+						self.subscriptions.insert(topic, SubscriptionStatus::Active);
+					}
+				}
+				return Ok(ResponseOrContent::Response(vec![]));
+			}
+			"error" => {
+				let topic = jrpc.get("id").and_then(|v| v.as_str()).and_then(|id| self.pending.remove(id)).unwrap_or_else(|| "<unknown>".to_owned());
+				let msg = jrpc.get("data").and_then(|v| v.as_str()).map(str::to_owned).unwrap_or_else(|| jrpc.to_string());
+				self.subscriptions.insert(topic.clone(), SubscriptionStatus::Failed(msg.clone()));
+				return Err(WsError::Subscription {
+					topic,
+					reason: SubscriptionRejectReason::from_message(&msg),
+				});
+			}
+			_ => {}
+		}
+		// private channels multiplex several kinds of updates onto one topic, told apart by `subject`
+		let event_type = jrpc.get("subject").and_then(|v| v.as_str()).map(str::to_owned).unwrap_or(event_type);
 		let topic = jrpc.get("topic").and_then(|v| v.as_str()).unwrap_or("").to_string();
 		let data = jrpc.get("data").cloned().unwrap_or(serde_json::Value::Null);
 		let time_ms = jrpc.get("time").and_then(|v| v.as_i64()).unwrap_or(0);
@@ -211,6 +283,7 @@ impl WsHandler for KucoinWsHandler {
 		Ok(ResponseOrContent::Content(content))
 	}
 }
+#[cfg(feature = "kucoin-ws")]
 impl WsOption for KucoinOption {
 	type WsHandler = KucoinWsHandler;
 
@@ -236,6 +309,8 @@ pub enum KucoinOption {
 
 	/// Base url for HTTP requests
 	HttpUrl(KucoinHttpUrl),
+	/// Base url for requests scoped to an [Instrument], overriding [KucoinHttpUrl::for_instrument].
+	InstrumentHttpUrl(Instrument, KucoinHttpUrl),
 	/// Authentication type for HTTP requests
 	HttpAuth(KucoinAuth),
 
@@ -245,6 +320,24 @@ pub enum KucoinOption {
 	WsConfig(WsConfig),
 	/// See [WsConfig::topics]. Will be merged with those manually defined in [Self::WsConfig::topics], if any.
 	WsTopics(Vec<String>),
+	/// Subscribe to the private futures channels: `/contractAccount/wallet` (balances) and `/contractMarket/tradeOrders` (orders). Requires [Self::WsBullet] from `/api/v1/bullet-private`.
+	PrivateWs(bool),
+	/// Connection token and endpoint, as handed out by `/api/v1/bullet-{public,private}`. Overrides [Self::WsUrl].
+	WsBullet(KucoinBullet),
+}
+
+/// Private futures channels subscribed to with [KucoinOption::PrivateWs].
+pub const KUCOIN_PRIVATE_TOPICS: [&str; 2] = ["/contractAccount/wallet", "/contractMarket/tradeOrders"];
+
+/// Kucoin doesn't have fixed ws endpoints: each connection is opened with a token fetched over REST right before.
+///
+/// The token is only good for establishing a connection shortly after it was issued, so reconnects of a long-lived connection may be refused once it goes stale.
+#[derive(Clone, derive_more::Debug)]
+pub struct KucoinBullet {
+	#[debug("[REDACTED]")]
+	pub token: SecretString,
+	pub endpoint: Url,
+	pub ping_interval: Duration,
 }
 
 /// A `enum` that represents the base url of the Kucoin REST API.
@@ -259,6 +352,16 @@ pub enum KucoinHttpUrl {
 	/// The url will not be modified by [KucoinRequestHandler]
 	None,
 }
+impl KucoinHttpUrl {
+	/// Where `instrument`'s endpoints live, absent an [override](KucoinOption::InstrumentHttpUrl).
+	pub fn for_instrument(instrument: Instrument) -> Self {
+		match instrument {
+			Instrument::Spot | Instrument::Margin => Self::Spot,
+			Instrument::Perp | Instrument::PerpInverse => Self::Futures,
+			Instrument::Options => Self::None,
+		}
+	}
+}
 impl EndpointUrl for KucoinHttpUrl {
 	fn url_mainnet(&self) -> Url {
 		match self {
@@ -332,6 +435,8 @@ pub struct KucoinOptions {
 	pub passphrase: Option<SecretString>,
 	/// see [KucoinOption::HttpUrl]
 	pub http_url: KucoinHttpUrl,
+	/// see [KucoinOption::InstrumentHttpUrl]
+	pub instrument_http_urls: BTreeMap<Instrument, KucoinHttpUrl>,
 	/// see [KucoinOption::HttpAuth]
 	pub http_auth: KucoinAuth,
 	/// see [KucoinOption::WsUrl]
@@ -340,9 +445,18 @@ pub struct KucoinOptions {
 	pub ws_config: WsConfig,
 	/// see [KucoinOption::WsTopics]
 	pub ws_topics: AHashSet<String>,
+	/// see [KucoinOption::PrivateWs]
+	pub private_ws: bool,
+	/// see [KucoinOption::WsBullet]
+	pub ws_bullet: Option<KucoinBullet>,
 	/// see [KucoinOption::Test]
 	pub test: bool,
 }
+impl KucoinOptions {
+	pub fn http_url_for(&self, instrument: Instrument) -> KucoinHttpUrl {
+		self.instrument_http_urls.get(&instrument).copied().unwrap_or_else(|| KucoinHttpUrl::for_instrument(instrument))
+	}
+}
 impl HandlerOptions for KucoinOptions {
 	type OptionItem = KucoinOption;
 
@@ -354,10 +468,15 @@ impl HandlerOptions for KucoinOptions {
 			Self::OptionItem::Passphrase(v) => self.passphrase = Some(v),
 			Self::OptionItem::Test(v) => self.test = v,
 			Self::OptionItem::HttpUrl(v) => self.http_url = v,
+			Self::OptionItem::InstrumentHttpUrl(instrument, v) => {
+				self.instrument_http_urls.insert(instrument, v);
+			}
 			Self::OptionItem::HttpAuth(v) => self.http_auth = v,
 			Self::OptionItem::WsUrl(v) => self.ws_url = v,
 			Self::OptionItem::WsConfig(v) => self.ws_config = v,
 			Self::OptionItem::WsTopics(v) => self.ws_topics = v.into_iter().collect(),
+			Self::OptionItem::PrivateWs(v) => self.private_ws = v,
+			Self::OptionItem::WsBullet(v) => self.ws_bullet = Some(v),
 		}
 	}
 
@@ -459,7 +578,7 @@ impl From<String> for KucoinErrorCode {
 			"400100" => Self::ParameterError,
 			"500000" => Self::ServerError,
 			_ => {
-				tracing::warn!("Encountered unknown Kucoin error code: {code}");
+				tracing::warn!(target: "v_exchanges::kucoin", "Encountered unknown Kucoin error code: {code}");
 				Self::Other(code)
 			}
 		}
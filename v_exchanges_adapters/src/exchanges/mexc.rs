@@ -1,6 +1,11 @@
 // A module for communicating with the MEXC API (https://mexcdevelop.github.io/apidocs/spot/en/)
 
-use std::{collections::HashSet, marker::PhantomData, str::FromStr, time::SystemTime};
+use std::{
+	collections::{BTreeMap, HashSet},
+	marker::PhantomData,
+	str::FromStr,
+	time::SystemTime,
+};
 
 use ahash::AHashSet;
 use eyre::eyre;
@@ -12,6 +17,7 @@ use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use sha2::Sha256;
 use url::Url;
 use v_exchanges_api_generics::{http::*, ws::*};
+use v_utils::trades::Instrument;
 
 use crate::traits::*;
 
@@ -29,6 +35,8 @@ pub enum MexcOption {
 	Testnet(bool),
 	/// Base url for HTTP requests
 	HttpUrl(MexcHttpUrl),
+	/// Base url for requests scoped to an [Instrument], overriding [MexcHttpUrl::for_instrument].
+	InstrumentHttpUrl(Instrument, MexcHttpUrl),
 	/// Authentication type for HTTP requests
 	HttpAuth(MexcAuth),
 	/// receive window parameter used for requests
@@ -52,6 +60,8 @@ pub struct MexcOptions {
 	pub testnet: bool,
 	/// see [MexcOption::HttpUrl]
 	pub http_url: MexcHttpUrl,
+	/// see [MexcOption::InstrumentHttpUrl]
+	pub instrument_http_urls: BTreeMap<Instrument, MexcHttpUrl>,
 	/// see [MexcOption::HttpAuth]
 	pub http_auth: MexcAuth,
 	/// see [MexcOption::RecvWindow]
@@ -127,6 +137,7 @@ pub struct MexcRequestHandler<'a, R: DeserializeOwned> {
 	_phantom: PhantomData<&'a R>,
 }
 /// A struct that implements [WsHandler]
+#[cfg(feature = "mexc-ws")]
 #[derive(Debug, derive_new::new)]
 pub struct MexcWsHandler {
 	options: MexcOptions,
@@ -142,6 +153,16 @@ pub enum MexcWsUrl {
 }
 static MAX_RECV_WINDOW: std::time::Duration = std::time::Duration::from_millis(60000); // as of (2025/01/18)
 
+impl MexcHttpUrl {
+	/// Where `instrument`'s endpoints live, absent an [override](MexcOption::InstrumentHttpUrl).
+	pub fn for_instrument(instrument: Instrument) -> Self {
+		match instrument {
+			Instrument::Spot | Instrument::Margin => Self::Spot,
+			Instrument::Perp | Instrument::PerpInverse => Self::Futures,
+			Instrument::Options => Self::None,
+		}
+	}
+}
 impl EndpointUrl for MexcHttpUrl {
 	fn url_mainnet(&self) -> Url {
 		match self {
@@ -217,7 +238,7 @@ where
 		}
 	}
 
-	#[tracing::instrument(skip_all, fields(?builder))]
+	#[tracing::instrument(target = "v_exchanges::mexc", skip_all, fields(?builder))]
 	fn build_request(&self, mut builder: RequestBuilder, request_body: &Option<B>, _: u8) -> Result<Request, BuildError> {
 		if let Some(body) = request_body {
 			let encoded = serde_urlencoded::to_string(body)?;
@@ -272,10 +293,7 @@ where
 				};
 				return Err(ApiError::from(api_error).into());
 			}
-			serde_json::from_slice(&response_body).map_err(|error| {
-				let response_str = v_utils::utils::truncate_msg(String::from_utf8_lossy(&response_body));
-				HandleError::Parse(eyre!("Failed to parse response: {error}\nResponse body: {response_str}"))
-			})
+			parse_response(&response_body)
 		} else {
 			if status == 401 {
 				use v_exchanges_api_generics::http::AuthError;
@@ -292,11 +310,11 @@ where
 						if let Ok(retry_after) = u32::from_str(string) {
 							Some(retry_after)
 						} else {
-							tracing::debug!("Invalid number in Retry-After header");
+							tracing::debug!(target: "v_exchanges::mexc", "Invalid number in Retry-After header");
 							None
 						}
 					} else {
-						tracing::debug!("Non-ASCII character in Retry-After header");
+						tracing::debug!(target: "v_exchanges::mexc", "Non-ASCII character in Retry-After header");
 						None
 					}
 				} else {
@@ -312,19 +330,14 @@ where
 				return Err(e);
 			}
 
-			let api_error: MexcError = match serde_json::from_slice(&response_body) {
-				Ok(parsed) => parsed,
-				Err(error) => {
-					let response_str = v_utils::utils::truncate_msg(String::from_utf8_lossy(&response_body));
-					return Err(HandleError::Parse(eyre!("Failed to parse error response: {error}\nResponse body: {response_str}")));
-				}
-			};
+			let api_error: MexcError = parse_response(&response_body)?;
 			Err(ApiError::from(api_error).into())
 		}
 	}
 }
 
 // Ws stuff {{{
+#[cfg(feature = "mexc-ws")]
 impl WsHandler for MexcWsHandler {
 	fn config(&self) -> Result<WsConfig, UrlError> {
 		let mut config = self.options.ws_config.clone();
@@ -363,6 +376,7 @@ impl EndpointUrl for MexcWsUrl {
 		}
 	}
 }
+#[cfg(feature = "mexc-ws")]
 impl WsOption for MexcOption {
 	type WsHandler = MexcWsHandler;
 
@@ -372,6 +386,11 @@ impl WsOption for MexcOption {
 }
 //,}}}
 
+impl MexcOptions {
+	pub fn http_url_for(&self, instrument: Instrument) -> MexcHttpUrl {
+		self.instrument_http_urls.get(&instrument).copied().unwrap_or_else(|| MexcHttpUrl::for_instrument(instrument))
+	}
+}
 impl HandlerOptions for MexcOptions {
 	type OptionItem = MexcOption;
 
@@ -382,10 +401,13 @@ impl HandlerOptions for MexcOptions {
 			MexcOption::Secret(v) => self.secret = Some(v),
 			MexcOption::Testnet(v) => self.testnet = v,
 			MexcOption::HttpUrl(v) => self.http_url = v,
+			MexcOption::InstrumentHttpUrl(instrument, v) => {
+				self.instrument_http_urls.insert(instrument, v);
+			}
 			MexcOption::HttpAuth(v) => self.http_auth = v,
 			MexcOption::RecvWindow(v) =>
 				if v > MAX_RECV_WINDOW {
-					tracing::warn!("recvWindow is too large, overwriting with maximum value of {MAX_RECV_WINDOW:?}");
+					tracing::warn!(target: "v_exchanges::mexc", "recvWindow is too large, overwriting with maximum value of {MAX_RECV_WINDOW:?}");
 					self.recv_window = Some(MAX_RECV_WINDOW);
 				} else {
 					self.recv_window = Some(v);
@@ -1,18 +1,18 @@
-#[cfg(feature = "binance")]
-#[cfg_attr(docsrs, doc(cfg(feature = "binance")))]
+#[cfg(feature = "binance-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "binance-http")))]
 pub mod binance;
 #[cfg(feature = "bitflyer")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bitflyer")))]
 pub mod bitflyer;
-#[cfg(feature = "bybit")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bybit")))]
+#[cfg(feature = "bybit-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bybit-http")))]
 pub mod bybit;
 #[cfg(feature = "coincheck")]
 #[cfg_attr(docsrs, doc(cfg(feature = "coincheck")))]
 pub mod coincheck;
-#[cfg(feature = "kucoin")]
-#[cfg_attr(docsrs, doc(cfg(feature = "kucoin")))]
+#[cfg(feature = "kucoin-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kucoin-http")))]
 pub mod kucoin;
-#[cfg(feature = "mexc")]
-#[cfg_attr(docsrs, doc(cfg(feature = "mexc")))]
+#[cfg(feature = "mexc-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mexc-http")))]
 pub mod mexc;
@@ -9,16 +9,27 @@ pub use exchanges::*;
 use serde::Serialize;
 use traits::*;
 use ustr::Ustr;
+#[cfg(feature = "ws")]
+use v_exchanges_api_generics::ws::*;
 use v_exchanges_api_generics::{
 	RateLimiter,
 	http::{self, *},
 	ratelimiter::clock::MonotonicClock,
-	ws::*,
 };
 
 mod exchanges;
 pub mod traits;
 
+/// [Client] along with the traits needed to configure it and call requests on it.
+pub mod prelude {
+	#[cfg(feature = "ws")]
+	pub use crate::traits::WsOption;
+	pub use crate::{
+		Client, GetOptions, HttpClient,
+		traits::{EndpointUrl, HandlerOption, HandlerOptions, HttpOption},
+	};
+}
+
 // very long type, make it a macro
 macro_rules! request_ret {
     ($lt:lifetime, $Response:ty, $Options:ty,  $Body:ty) => {
@@ -34,6 +45,9 @@ pub trait HttpClient {
 	fn http_client(&self) -> &http::Client;
 	fn http_client_mut(&mut self) -> &mut http::Client;
 }
+/// Access to the default [HandlerOptions] of one exchange held by a [Client]. Every request starts from a clone of these.
+///
+/// Implemented by [Client] for each enabled exchange; usually reached through [Client::update_default_option] rather than directly.
 pub trait GetOptions<O: HandlerOptions> {
 	fn default_options(&self) -> &O;
 	fn default_options_mut(&mut self) -> &mut O;
@@ -113,6 +127,23 @@ impl Client {
 		self.http_client().request(method, url, query, body, &O::request_handler(self.merged_options(options))).await
 	}
 
+	/// see [http::Client::request_with_headers()]
+	pub async fn request_with_headers<'a, R, O, Q, B>(
+		&self,
+		method: Method,
+		url: &str,
+		query: Option<&Q>,
+		body: Option<B>,
+		options: impl IntoIterator<Item = O>,
+	) -> Result<WithHeaders<<O::RequestHandler as RequestHandler<B>>::Successful>, RequestError>
+	where
+		O: HttpOption<'a, R, B>,
+		O::RequestHandler: RequestHandler<B>,
+		Self: GetOptions<O::Options>,
+		Q: Serialize + ?Sized + std::fmt::Debug, {
+		self.http_client().request_with_headers(method, url, query, body, &O::request_handler(self.merged_options(options))).await
+	}
+
 	/// see [http::Client::get()]
 	pub async fn get<'a, R, O, Q>(&self, url: &str, query: &Q, options: impl IntoIterator<Item = O>) -> request_ret!('a, R, O, ())
 	where
@@ -187,6 +218,8 @@ impl Client {
 		self.http_client().delete_no_query(url, &O::request_handler(self.merged_options(options))).await
 	}
 
+	#[cfg(feature = "ws")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 	pub fn ws_connection<O>(&self, url: &str, options: impl IntoIterator<Item = O>) -> Result<WsConnection<O::WsHandler>, WsError>
 	where
 		O: WsOption,
@@ -194,6 +227,19 @@ impl Client {
 		Self: GetOptions<O::Options>, {
 		WsConnection::try_new(url, O::ws_handler(self.merged_options(options)))
 	}
+
+	/// [ws_connection](Self::ws_connection), resubscribing to everything in `snapshot`. See [WsConnection::try_resume].
+	///
+	/// Plumbing: what comes back is the raw connection, yielding unparsed events. Snapshots of `v_exchanges` streams are resumed into streams of the same kind through `Exchange::ws_trades_resume` and `Exchange::ws_book_resume`.
+	#[cfg(feature = "ws")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+	pub fn ws_resume<O>(&self, url: &str, options: impl IntoIterator<Item = O>, snapshot: &SubscriptionSnapshot) -> Result<WsConnection<O::WsHandler>, WsError>
+	where
+		O: WsOption,
+		O::WsHandler: WsHandler,
+		Self: GetOptions<O::Options>, {
+		WsConnection::try_resume(url, O::ws_handler(self.merged_options(options)), snapshot)
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -201,17 +247,17 @@ pub struct ClientInner {
 	pub client: http::Client,
 	/// Rate limiter shared across clones (same exchange instance).
 	pub rate_limiter: Arc<RateLimiter<Ustr, MonotonicClock>>,
-	#[cfg(feature = "binance")]
+	#[cfg(feature = "binance-http")]
 	binance: binance::BinanceOptions,
 	#[cfg(feature = "bitflyer")]
 	bitflyer: bitflyer::BitFlyerOptions,
-	#[cfg(feature = "bybit")]
+	#[cfg(feature = "bybit-http")]
 	bybit: bybit::BybitOptions,
 	#[cfg(feature = "coincheck")]
 	coincheck: coincheck::CoincheckOptions,
-	#[cfg(feature = "kucoin")]
+	#[cfg(feature = "kucoin-http")]
 	kucoin: kucoin::KucoinOptions,
-	#[cfg(feature = "mexc")]
+	#[cfg(feature = "mexc-http")]
 	mexc: mexc::MexcOptions,
 }
 impl Default for ClientInner {
@@ -219,17 +265,17 @@ impl Default for ClientInner {
 		Self {
 			client: http::Client::default(),
 			rate_limiter: Arc::new(RateLimiter::new_with_quota(None, vec![])),
-			#[cfg(feature = "binance")]
+			#[cfg(feature = "binance-http")]
 			binance: binance::BinanceOptions::default(),
 			#[cfg(feature = "bitflyer")]
 			bitflyer: bitflyer::BitFlyerOptions::default(),
-			#[cfg(feature = "bybit")]
+			#[cfg(feature = "bybit-http")]
 			bybit: bybit::BybitOptions::default(),
 			#[cfg(feature = "coincheck")]
 			coincheck: coincheck::CoincheckOptions::default(),
-			#[cfg(feature = "kucoin")]
+			#[cfg(feature = "kucoin-http")]
 			kucoin: kucoin::KucoinOptions::default(),
-			#[cfg(feature = "mexc")]
+			#[cfg(feature = "mexc-http")]
 			mexc: mexc::MexcOptions::default(),
 		}
 	}
@@ -261,8 +307,8 @@ impl HttpClient for Client {
 }
 
 // GetOptions impls for ClientInner {{{
-#[cfg(feature = "binance")]
-#[cfg_attr(docsrs, doc(cfg(feature = "binance")))]
+#[cfg(feature = "binance-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "binance-http")))]
 impl GetOptions<binance::BinanceOptions> for ClientInner {
 	fn default_options(&self) -> &binance::BinanceOptions {
 		&self.binance
@@ -285,8 +331,8 @@ impl GetOptions<bitflyer::BitFlyerOptions> for ClientInner {
 	}
 }
 
-#[cfg(feature = "bybit")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bybit")))]
+#[cfg(feature = "bybit-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bybit-http")))]
 impl GetOptions<bybit::BybitOptions> for ClientInner {
 	fn default_options(&self) -> &bybit::BybitOptions {
 		&self.bybit
@@ -308,8 +354,8 @@ impl GetOptions<coincheck::CoincheckOptions> for ClientInner {
 		&mut self.coincheck
 	}
 }
-#[cfg(feature = "kucoin")]
-#[cfg_attr(docsrs, doc(cfg(feature = "kucoin")))]
+#[cfg(feature = "kucoin-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kucoin-http")))]
 impl GetOptions<kucoin::KucoinOptions> for ClientInner {
 	fn default_options(&self) -> &kucoin::KucoinOptions {
 		&self.kucoin
@@ -319,8 +365,8 @@ impl GetOptions<kucoin::KucoinOptions> for ClientInner {
 		&mut self.kucoin
 	}
 }
-#[cfg(feature = "mexc")]
-#[cfg_attr(docsrs, doc(cfg(feature = "mexc")))]
+#[cfg(feature = "mexc-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mexc-http")))]
 impl GetOptions<mexc::MexcOptions> for ClientInner {
 	fn default_options(&self) -> &mexc::MexcOptions {
 		&self.mexc
@@ -333,8 +379,8 @@ impl GetOptions<mexc::MexcOptions> for ClientInner {
 //,}}}
 
 // GetOptions impls for Client: delegate to inner {{{
-#[cfg(feature = "binance")]
-#[cfg_attr(docsrs, doc(cfg(feature = "binance")))]
+#[cfg(feature = "binance-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "binance-http")))]
 impl GetOptions<binance::BinanceOptions> for Client {
 	fn default_options(&self) -> &binance::BinanceOptions {
 		self.inner().default_options()
@@ -357,8 +403,8 @@ impl GetOptions<bitflyer::BitFlyerOptions> for Client {
 	}
 }
 
-#[cfg(feature = "bybit")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bybit")))]
+#[cfg(feature = "bybit-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bybit-http")))]
 impl GetOptions<bybit::BybitOptions> for Client {
 	fn default_options(&self) -> &bybit::BybitOptions {
 		self.inner().default_options()
@@ -380,8 +426,8 @@ impl GetOptions<coincheck::CoincheckOptions> for Client {
 		self.inner_mut().default_options_mut()
 	}
 }
-#[cfg(feature = "kucoin")]
-#[cfg_attr(docsrs, doc(cfg(feature = "kucoin")))]
+#[cfg(feature = "kucoin-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kucoin-http")))]
 impl GetOptions<kucoin::KucoinOptions> for Client {
 	fn default_options(&self) -> &kucoin::KucoinOptions {
 		self.inner().default_options()
@@ -391,8 +437,8 @@ impl GetOptions<kucoin::KucoinOptions> for Client {
 		self.inner_mut().default_options_mut()
 	}
 }
-#[cfg(feature = "mexc")]
-#[cfg_attr(docsrs, doc(cfg(feature = "mexc")))]
+#[cfg(feature = "mexc-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mexc-http")))]
 impl GetOptions<mexc::MexcOptions> for Client {
 	fn default_options(&self) -> &mexc::MexcOptions {
 		self.inner().default_options()
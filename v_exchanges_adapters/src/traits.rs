@@ -1,33 +1,133 @@
+//! The option machinery every exchange module is built on.
+//!
+//! Each exchange defines two types:
+//! - an `enum` of settings, e.g. `BinanceOption::HttpAuth(..)`, implementing [HandlerOption] (and [HttpOption] / [WsOption] for the transports it supports);
+//! - a `struct` holding the resolved value of every setting, e.g. `BinanceOptions`, implementing [HandlerOptions].
+//!
+//! [Client](crate::Client) keeps one default `*Options` per exchange (reachable through [GetOptions](crate::GetOptions)), and per request clones it, folds the passed `*Option`s in with [HandlerOptions::update], then hands the result to [HttpOption::request_handler] or [WsOption::ws_handler] to build the handler that actually talks to the exchange.
+//!
+//! # Example
+//! A minimal exchange with a single setting and an HTTP handler that returns the raw body:
+//! ```
+//! use std::marker::PhantomData;
+//!
+//! use v_exchanges_adapters::{
+//! 	generics::http::{BuildError, Bytes, HandleError, HeaderMap, Request, RequestBuilder, RequestHandler, StatusCode},
+//! 	prelude::*,
+//! };
+//!
+//! #[derive(Debug, Default)]
+//! enum AcmeOption {
+//! 	#[default]
+//! 	Default,
+//! 	Key(String),
+//! }
+//!
+//! #[derive(Clone, Debug, Default)]
+//! struct AcmeOptions {
+//! 	key: Option<String>,
+//! }
+//!
+//! impl HandlerOption for AcmeOption {
+//! 	type Options = AcmeOptions;
+//! }
+//!
+//! impl HandlerOptions for AcmeOptions {
+//! 	type OptionItem = AcmeOption;
+//!
+//! 	fn update(&mut self, option: AcmeOption) {
+//! 		match option {
+//! 			AcmeOption::Default => (),
+//! 			AcmeOption::Key(v) => self.key = Some(v),
+//! 		}
+//! 	}
+//!
+//! 	fn is_authenticated(&self) -> bool {
+//! 		self.key.is_some()
+//! 	}
+//! }
+//!
+//! struct AcmeRequestHandler<'a, R> {
+//! 	options: AcmeOptions,
+//! 	_phantom: PhantomData<&'a R>,
+//! }
+//!
+//! impl<B> RequestHandler<B> for AcmeRequestHandler<'_, String> {
+//! 	type Successful = String;
+//!
+//! 	fn build_request(&self, builder: RequestBuilder, _: &Option<B>, _: u8) -> Result<Request, BuildError> {
+//! 		let builder = match &self.options.key {
+//! 			Some(key) => builder.header("X-ACME-KEY", key),
+//! 			None => builder,
+//! 		};
+//! 		builder.build().map_err(|e| BuildError::Other(e.into()))
+//! 	}
+//!
+//! 	fn handle_response(&self, _: StatusCode, _: HeaderMap, body: Bytes) -> Result<String, HandleError> {
+//! 		Ok(String::from_utf8_lossy(&body).into_owned())
+//! 	}
+//! }
+//!
+//! impl<'a, B> HttpOption<'a, String, B> for AcmeOption {
+//! 	type RequestHandler = AcmeRequestHandler<'a, String>;
+//!
+//! 	fn request_handler(options: AcmeOptions) -> Self::RequestHandler {
+//! 		AcmeRequestHandler { options, _phantom: PhantomData }
+//! 	}
+//! }
+//!
+//! let mut options = AcmeOptions::default();
+//! options.update(AcmeOption::Key("pubkey".into()));
+//! assert!(options.is_authenticated());
+//! ```
 use std::fmt::Debug;
 
-use v_exchanges_api_generics::{http, ws};
+use v_exchanges_api_generics::http;
+#[cfg(feature = "ws")]
+use v_exchanges_api_generics::ws;
 
-/// A `trait` that represents an option which can be set when creating handlers
+/// A single setting that can be passed along with a request, e.g. `BinanceOption::HttpAuth(BinanceAuth::Sign)`.
+///
+/// Implement on the exchange's option `enum`. Its [Default] should be a variant that does nothing when applied.
 pub trait HandlerOption: Default {
+	/// The set this option is folded into.
 	type Options: HandlerOptions<OptionItem = Self>;
 }
 
-/// Set of [HandlerOption] s
+/// Resolved value of every [HandlerOption] of one exchange. This is what handlers are built from.
+///
+/// [Default] gives the exchange's out-of-the-box configuration (mainnet urls, no credentials, etc), which [Client](crate::Client) then holds as its defaults.
 pub trait HandlerOptions: Default + Clone + Debug {
 	/// The element of this set
 	type OptionItem: HandlerOption<Options = Self>;
 
 	//Q: searched through impls, only differing options are HttpAuth and RecvWindow, (on unimportant exchanges at that), rest seem to have exact same types and uses. So maybe I could describe OptionItem procedurally + have part of the implementation for free? Really only problem would be the differing types and the websocket_url/http_url, which are effectively enums of `&'static str`
+	/// Apply `option` on top of the current values. Later options win.
 	fn update(&mut self, option: Self::OptionItem);
+	/// Whether enough credentials are set for authenticated endpoints.
 	fn is_authenticated(&self) -> bool;
 }
 
-/// A `trait` that shows the implementing type is able to create [http::RequestHandler]s
+/// Shows that the implementing [HandlerOption] is able to create [http::RequestHandler]s.
+///
+/// - `'a`: lifetime the handler may borrow for; handlers only hold it in a `PhantomData`.
+/// - `R`: type the successful response gets deserialized into.
+/// - `B`: request body type; `()` for requests without one.
+///
+/// Usually implemented generically over all `R: DeserializeOwned` and `B: Serialize`.
 pub trait HttpOption<'a, R, B>: HandlerOption {
 	type RequestHandler: http::RequestHandler<B>;
 
+	/// Build the handler for a single request from the already merged options.
 	fn request_handler(options: Self::Options) -> Self::RequestHandler;
 }
 
-/// shows that the implementing type is able to create [websocket::WebSocketHandler]s
+/// Shows that the implementing [HandlerOption] is able to create [ws::WsHandler]s.
+#[cfg(feature = "ws")]
 pub trait WsOption: HandlerOption {
 	type WsHandler: ws::WsHandler;
 
+	/// Build the handler for a connection from the already merged options. Called once per [WsConnection](ws::WsConnection).
 	fn ws_handler(options: Self::Options) -> Self::WsHandler;
 }
 
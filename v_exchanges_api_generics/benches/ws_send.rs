@@ -0,0 +1,92 @@
+//! Send-to-delivery latency against a local server: a write awaited inline on the socket, as [WsConnection] used to do from within `next()`, against [WsConnection::send] handing the message to its writer task. Either way an iteration ends once the server has read the message, so the writer task's hop and flush are inside the measurement.
+//!
+//! The server doesn't reply, so neither side is held up by reads. Run with `cargo bench -p v_exchanges_api_generics --bench ws_send --features ws`.
+use std::hint::black_box;
+
+use ahash::AHashSet;
+use criterion::{Criterion, criterion_group, criterion_main};
+use futures_util::{SinkExt as _, StreamExt as _};
+use jiff::Timestamp;
+use tokio::{net::TcpListener, runtime::Runtime, sync::mpsc};
+use tokio_tungstenite::tungstenite::Message;
+use v_exchanges_api_generics::{
+	UrlError,
+	ws::{ContentEvent, ResponseOrContent, Topic, WsConfig, WsConnection, WsError, WsHandler},
+};
+
+#[derive(Debug)]
+struct PassThrough;
+impl WsHandler for PassThrough {
+	fn config(&self) -> Result<WsConfig, UrlError> {
+		Ok(WsConfig::default())
+	}
+
+	fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+		Ok(vec![])
+	}
+
+	fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+		Ok(ResponseOrContent::Content(ContentEvent {
+			data: jrpc,
+			topic: "bench".to_owned(),
+			time: Timestamp::UNIX_EPOCH,
+			event_type: "bench".to_owned(),
+		}))
+	}
+}
+
+/// Accepts one connection, greets it with one frame, then reports every message read off it. Returns the url, and where the reports go.
+async fn serve() -> (String, mpsc::UnboundedReceiver<()>) {
+	let listener = TcpListener::bind("127.0.0.1:0").await.expect("loopback bind");
+	let url = format!("ws://{}", listener.local_addr().unwrap());
+	let (delivered_tx, delivered) = mpsc::unbounded_channel();
+	tokio::spawn(async move {
+		let (tcp, _) = listener.accept().await.expect("accept");
+		let mut ws = tokio_tungstenite::accept_async(tcp).await.expect("handshake");
+		ws.send(Message::Text("{\"hello\":true}".into())).await.expect("greeting");
+		while let Some(Ok(message)) = ws.next().await {
+			if message.is_text() {
+				delivered_tx.send(()).expect("bench outlives the server");
+			}
+		}
+	});
+	(url, delivered)
+}
+
+fn order_message() -> Message {
+	Message::Text(r#"{"id":"56374a46","method":"order.place","params":{"symbol":"BTCUSDT","side":"SELL","type":"LIMIT","price":"23416.1","quantity":"0.00847"}}"#.into())
+}
+
+fn ws_send(c: &mut Criterion) {
+	let rt = Runtime::new().unwrap();
+
+	let (url, mut inline_delivered) = rt.block_on(serve());
+	let (mut inline, _) = rt.block_on(tokio_tungstenite::connect_async(&url)).expect("connect");
+
+	let (url, mut queued_delivered) = rt.block_on(serve());
+	let mut queued = WsConnection::try_new(&url, PassThrough).expect("try_new");
+	// connects, and spawns the writer task
+	rt.block_on(queued.next()).expect("greeting");
+
+	let mut group = c.benchmark_group("ws_send");
+	group.bench_function("inline_write", |b| {
+		b.iter(|| {
+			rt.block_on(async {
+				inline.send(black_box(order_message())).await.unwrap();
+				inline_delivered.recv().await.unwrap();
+			})
+		})
+	});
+	group.bench_function("writer_task", |b| {
+		b.iter(|| {
+			rt.block_on(async {
+				queued.send(black_box(order_message())).unwrap();
+				queued_delivered.recv().await.unwrap();
+			})
+		})
+	});
+	group.finish();
+}
+
+criterion_group!(benches, ws_send);
+criterion_main!(benches);
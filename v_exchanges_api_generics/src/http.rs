@@ -18,7 +18,7 @@ pub use reqwest::{
 	Method, Request, RequestBuilder, StatusCode,
 	header::{self, HeaderMap},
 };
-use serde::Serialize;
+use serde::{Serialize, de::DeserializeOwned};
 use tracing::{Span, debug, error, field::Empty, info, instrument, warn};
 pub use ustr::Ustr;
 
@@ -31,6 +31,11 @@ use crate::{
 /// The User Agent string
 pub static USER_AGENT: &str = concat!("v_exchanges_api_generics/", env!("CARGO_PKG_VERSION"));
 
+/// Called on every request right before it's sent, after [RequestHandler::build_request]. Whatever it returns is what gets sent.
+pub type RequestInterceptor = Arc<dyn Fn(Request) -> Request + Send + Sync>;
+/// Called on every response received over the network, before it's handed to [RequestHandler::handle_response]. Cache hits don't go through it.
+pub type ResponseInterceptor = Arc<dyn Fn(StatusCode, &HeaderMap, &Bytes) + Send + Sync>;
+
 /// Client for communicating with APIs through HTTP/HTTPS.
 ///
 /// When making a HTTP request or starting a websocket connection with this client,
@@ -41,7 +46,7 @@ pub struct Client {
 	/// network-identity change. `Arc<ArcSwap<_>>` (not bare `ArcSwap`): `ArcSwap` is not
 	/// `Clone`, but `Client` must stay `Clone` (cloned into WS snapshot futures and held by
 	/// every adapter), and all clones must share the SAME cell so one swap heals them all.
-	client: Arc<ArcSwap<reqwest::Client>>,
+	client: Arc<ArcSwap<InnerClient>>,
 	/// Set by netwatcher's callback when host interface addresses change; checked-and-cleared
 	/// at the top of `request()`. Shared across clones (Arc) so the single watch serves all.
 	net_dirty: Arc<AtomicBool>,
@@ -53,6 +58,9 @@ pub struct Client {
 	_net_watch: Arc<std::sync::Mutex<netwatcher::WatchHandle>>,
 	pub config: RequestConfig,
 	pub rate_limiter: Option<Arc<RateLimiter<Ustr, MonotonicClock>>>,
+	/// For logging or patching requests without touching the handlers, eg to inject test headers.
+	pub request_interceptor: Option<RequestInterceptor>,
+	pub response_interceptor: Option<ResponseInterceptor>,
 	/// Per-bucket unban time. Once an exchange reports a ban (`IpError::Timeout`), the bucket is
 	/// short-circuited until this instant instead of re-hitting the API (which renews/escalates the
 	/// ban). Keyed by the same bucket as `rate_limiter`; shared across clones (Arc) like it.
@@ -68,13 +76,47 @@ impl Debug for Client {
 			.field("net_dirty", &self.net_dirty)
 			.field("config", &self.config)
 			.field("rate_limiter", &self.rate_limiter)
+			.field("request_interceptor", &self.request_interceptor.is_some())
+			.field("response_interceptor", &self.response_interceptor.is_some())
 			.finish_non_exhaustive()
 	}
 }
 
-/// Centralizes inner-client construction so `Default` and the network-change rebuild can never drift.
-fn build_reqwest_client() -> reqwest::Client {
-	reqwest::Client::new()
+/// Which HTTP versions the inner client speaks. Picked per request from [RequestConfig::prefer_http2] and [RequestHandler::supports_http2].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum HttpVersion {
+	/// HTTP/2 if the server offers it through ALPN, HTTP/1.1 otherwise.
+	#[default]
+	Negotiate,
+	Http1Only,
+	/// Skip negotiation and open every connection as HTTP/2.
+	Http2PriorKnowledge,
+}
+impl HttpVersion {
+	fn pick(config: &RequestConfig, handler_supports_http2: bool) -> Self {
+		match (config.prefer_http2, handler_supports_http2) {
+			(false, _) => Self::Http1Only,
+			(true, true) => Self::Http2PriorKnowledge,
+			(true, false) => Self::Negotiate,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct InnerClient {
+	version: HttpVersion,
+	client: reqwest::Client,
+}
+
+/// Centralizes inner-client construction so `Default`, the network-change rebuild and version switches can never drift.
+fn build_reqwest_client(version: HttpVersion) -> InnerClient {
+	let builder = match version {
+		HttpVersion::Negotiate => reqwest::Client::builder(),
+		HttpVersion::Http1Only => reqwest::Client::builder().http1_only(),
+		HttpVersion::Http2PriorKnowledge => reqwest::Client::builder().http2_prior_knowledge(),
+	};
+	let client = builder.build().expect("only fails on TLS backend init, which reqwest::Client::new() would panic on too");
+	InnerClient { version, client }
 }
 
 impl Default for Client {
@@ -89,11 +131,13 @@ impl Default for Client {
 		})
 		.expect("netwatcher: failed to subscribe to host network change events");
 		Self {
-			client: Arc::new(ArcSwap::from_pointee(build_reqwest_client())),
+			client: Arc::new(ArcSwap::from_pointee(build_reqwest_client(HttpVersion::default()))),
 			net_dirty,
 			_net_watch: Arc::new(std::sync::Mutex::new(watch)),
 			config: RequestConfig::default(),
 			rate_limiter: None,
+			request_interceptor: None,
+			response_interceptor: None,
 			banned_until: Arc::new(DashMap::new()),
 		}
 	}
@@ -106,22 +150,35 @@ impl Client {
 	///
 	/// The request is passed to `handler` before being sent, and the response is passed to `handler` before being returned.
 	/// Note, that as stated in the docs for [RequestBuilder::query()], parameter `query` only accepts a **sequence of** key-value pairs.
-	#[instrument(skip_all, fields(?url, ?query, request_builder = Empty))] //TODO: get all generics to impl std::fmt::Debug
+	#[instrument(target = "v_exchanges::http", name = "request", skip_all, fields(exchange = Empty, %method, endpoint = url, ?query, request_builder = Empty))] //TODO: get all generics to impl std::fmt::Debug
 	pub async fn request<Q, B, H>(&self, method: Method, url: &str, query: Option<&Q>, body: Option<B>, handler: &H) -> Result<H::Successful, RequestError>
 	where
 		Q: Serialize + ?Sized + std::fmt::Debug,
 		H: RequestHandler<B>, {
+		let config = &self.config;
+		let version = HttpVersion::pick(config, handler.supports_http2());
 		// Drop the stale connection pool if the host network identity changed since last call.
 		if self.net_dirty.swap(false, Ordering::Relaxed) {
-			self.client.store(Arc::new(build_reqwest_client()));
-			info!("host network change observed; rebuilt HTTP connection pool");
+			self.client.store(Arc::new(build_reqwest_client(version)));
+			info!(target: "v_exchanges::http", "host network change observed; rebuilt HTTP connection pool");
+		} else if self.client.load().version != version {
+			self.client.store(Arc::new(build_reqwest_client(version)));
+			debug!(target: "v_exchanges::http", ?version, "rebuilt HTTP connection pool for a different protocol version");
 		}
 		let reqwest_client = self.client.load();
 
-		let config = &self.config;
 		let base_url = handler.base_url(config.use_testnet)?;
 		let url = base_url.join(url).map_err(|_| RequestError::Other(eyre!("Failed to parse provided URL")))?;
-		debug!(?config);
+		// second-level domain of the request host (e.g. "binance" from "api.binance.com")
+		let exchange = url.host_str().and_then(|h| {
+			let parts: Vec<&str> = h.split('.').collect();
+			// SLD is second-from-last for normal domains, last for single-label (localhost etc.)
+			parts.len().checked_sub(2).map(|i| parts[i])
+		});
+		if let Some(exchange) = exchange {
+			Span::current().record("exchange", exchange);
+		}
+		debug!(target: "v_exchanges::http", ?config);
 
 		// Mock cache: check before making any requests
 		let mock_path = config.mock_cache_dir.as_ref().map(|dir| mock_cache_path(dir, &url));
@@ -135,7 +192,7 @@ impl Client {
 				.elapsed()
 				.unwrap() < MOCK_CACHE_DURATION
 		{
-			debug!("Mock cache hit: {}", path.display());
+			debug!(target: "v_exchanges::http", "Mock cache hit: {}", path.display());
 			let body = Bytes::from(file);
 			let (status, headers) = (StatusCode::OK, header::HeaderMap::new());
 			return handler.handle_response(status, headers, body).map_err(RequestError::HandleResponse);
@@ -143,13 +200,8 @@ impl Client {
 
 		let bucket: Ustr = {
 			// Segment 1: always "ip"
-			// Segment 2: exchange name — second-level domain of the request host (e.g. "binance" from "api.binance.com")
+			// Segment 2: exchange name
 			// Segment 3: credential name — truncated hash of pubkey, present only if handler carries one
-			let exchange = url.host_str().and_then(|h| {
-				let parts: Vec<&str> = h.split('.').collect();
-				// SLD is second-from-last for normal domains, last for single-label (localhost etc.)
-				parts.len().checked_sub(2).map(|i| parts[i])
-			});
 			let key_name = handler.rate_limit_key_name();
 			let s = match (exchange, key_name.as_deref()) {
 				(Some(ex), Some(kn)) => format!("ip.{ex}.{kn}"),
@@ -164,7 +216,7 @@ impl Client {
 		let banned = self.banned_until.get(&bucket).map(|r| *r);
 		if let Some(until) = banned {
 			if Timestamp::now() < until {
-				warn!(%bucket, ?until, "IP banned; short-circuiting request until unban time");
+				warn!(target: "v_exchanges::http", %bucket, ?until, "IP banned; short-circuiting request until unban time");
 				return Err(RequestError::HandleResponse(HandleError::Api(ApiError::Ip(IpError::Timeout { until: Some(until) }))));
 			}
 			self.banned_until.remove(&bucket);
@@ -176,51 +228,57 @@ impl Client {
 
 		let mut backoff = ExponentialBackoff::try_from(&config.retry).map_err(|e| RequestError::Other(eyre!("Invalid retry configuration: {e}")))?;
 
+		let test_cache_entry = (config.use_testnet && !config.test_cache_exclude.iter().any(|p| url.path().ends_with(p))).then(|| TestCacheEntry::new(&method, &url, query));
+
 		let mut attempt: u32 = 0;
 		loop {
 			let attempt_num = attempt + 1;
 			//HACK: hate to create a new request every time, but I haven't yet figured out how to provide by reference
-			let mut request_builder = reqwest_client.request(method.clone(), url.clone()).timeout(config.timeout);
+			let mut request_builder = reqwest_client.client.request(method.clone(), url.clone()).timeout(config.timeout);
 			if let Some(query) = query {
 				request_builder = request_builder.query(query);
 			}
 			Span::current().record("request_builder", format!("{request_builder:?}"));
 
-			if config.use_testnet
+			if let Some(ref entry) = test_cache_entry
 				&& let Some(cache_duration) = config.cache_testnet_calls
+				&& let Some(file) = entry.read(cache_duration)
 			{
-				let path = test_calls_path(&url, &query);
-				if let Ok(file) = std::fs::read_to_string(&path)
-					&& path
-						.metadata()
-						.expect("already read the file, guaranteed to exist")
-						.modified()
-						.expect("switch OSes, you're on something stupid")
-						.elapsed()
-						.unwrap() < cache_duration
-				{
-					let body = Bytes::from(file);
-					let (status, headers) = (StatusCode::OK, header::HeaderMap::new()); // we only cache if we get a 200 (headers are only relevant on unsuccessful), so pass defaults.
-					return handler.handle_response(status, headers, body).map_err(RequestError::HandleResponse);
-				}
+				debug!(target: "v_exchanges::http", request = %entry.description, "Testnet cache hit: {}", entry.body_path.display());
+				let body = Bytes::from(file);
+				let (status, headers) = (StatusCode::OK, header::HeaderMap::new()); // we only cache if we get a 200 (headers are only relevant on unsuccessful), so pass defaults.
+				return handler.handle_response(status, headers, body).map_err(RequestError::HandleResponse);
 			}
 
-			let request = handler.build_request(request_builder, &body, attempt_num as u8).map_err(RequestError::BuildRequest)?;
-			match reqwest_client.execute(request).await {
+			let mut request = handler.build_request(request_builder, &body, attempt_num as u8).map_err(RequestError::BuildRequest)?;
+			if let Some(intercept) = &self.request_interceptor {
+				request = intercept(request);
+			}
+			if config.dry_run {
+				log_dry_run(&request);
+				return Err(RequestError::DryRun {
+					method: request.method().clone(),
+					url: redact_url(request.url()),
+				});
+			}
+			match reqwest_client.client.execute(request).await {
 				Ok(mut response) => {
 					let status = response.status();
 					let headers = std::mem::take(response.headers_mut());
-					debug!(?status, ?headers, "Received response headers");
+					debug!(target: "v_exchanges::http", ?status, ?headers, "Received response headers");
 					let body: Bytes = match response.bytes().await {
 						Ok(b) => b,
 						Err(e) => {
-							error!(?status, ?headers, ?e, "Failed to read response body");
+							error!(target: "v_exchanges::http", ?status, ?headers, ?e, "Failed to read response body");
 							return Err(RequestError::ReceiveResponse(e));
 						}
 					};
 					{
 						let truncated_body = v_utils::utils::truncate_msg(std::str::from_utf8(&body)?.trim());
-						debug!(truncated_body);
+						debug!(target: "v_exchanges::http", truncated_body);
+					}
+					if let Some(observe) = &self.response_interceptor {
+						observe(status, &headers, &body);
 					}
 
 					// Persist to mock cache on successful response
@@ -231,25 +289,42 @@ impl Client {
 							std::fs::create_dir_all(parent).ok();
 						}
 						std::fs::write(path, &body).ok();
-						debug!("Mock cache write: {}", path.display());
+						debug!(target: "v_exchanges::http", "Mock cache write: {}", path.display());
 					}
 
 					match config.use_testnet {
 						true => {
 							// if we're here, the cache file didn't exist or is outdated
 							let handled = handler.handle_response(status, headers.clone(), body.clone())?;
-							std::fs::write(test_calls_path(&url, &query), &body).ok();
+							if let Some(ref entry) = test_cache_entry
+								&& config.cache_testnet_calls.is_some()
+							{
+								entry.write(&body, config.test_cache_max_bytes);
+							}
 							return Ok(handled);
 						}
 						false => {
+							if status == StatusCode::TOO_MANY_REQUESTS
+								&& config.respect_retry_after
+								&& attempt < config.retry.max_retries
+								&& let Some(retry_after) = parse_retry_after(&headers)
+							{
+								let wait = retry_after.min(config.max_retry_after);
+								warn!(target: "v_exchanges::http", attempt = attempt_num, ?retry_after, ?wait, "Rate limited; sleeping out Retry-After before retrying");
+								// gate the bucket for as long, so that concurrent requests don't keep hitting the limit meanwhile
+								self.banned_until.insert(bucket, Timestamp::now() + wait);
+								tokio::time::sleep(wait).await;
+								attempt += 1;
+								continue;
+							}
 							let handled = handler.handle_response(status, headers.clone(), body.clone());
 							if let Err(HandleError::Api(ApiError::Ip(IpError::Timeout { until }))) = &handled {
 								let until = until.unwrap_or_else(|| Timestamp::now() + config.ban_cooldown);
-								warn!(%bucket, ?until, "exchange reported IP ban; gating bucket until unban time");
+								warn!(target: "v_exchanges::http", %bucket, ?until, "exchange reported IP ban; gating bucket until unban time");
 								self.banned_until.insert(bucket, until);
 							}
 							return handled.map_err(|e| {
-								error!(?status, ?headers, body = ?v_utils::utils::truncate_msg(std::str::from_utf8(&body).unwrap_or("<invalid utf8>")), "Failed to handle response");
+								error!(target: "v_exchanges::http", ?status, ?headers, body = ?v_utils::utils::truncate_msg(std::str::from_utf8(&body).unwrap_or("<invalid utf8>")), "Failed to handle response");
 								RequestError::HandleResponse(e)
 							});
 						}
@@ -258,7 +333,7 @@ impl Client {
 				Err(e) =>
 					if attempt < config.retry.max_retries && is_retryable_request_error(&e) {
 						let delay = backoff.next_duration();
-						info!(attempt = attempt_num, delay_ms = delay.as_millis(), "Retrying after network error");
+						info!(target: "v_exchanges::http", attempt = attempt_num, delay_ms = delay.as_millis(), "Retrying after network error");
 						if delay.is_zero() {
 							tokio::task::yield_now().await;
 						} else {
@@ -266,7 +341,7 @@ impl Client {
 						}
 						attempt += 1;
 					} else {
-						warn!(?e);
+						warn!(target: "v_exchanges::http", ?e);
 						return Err(RequestError::SendRequest(e));
 					},
 			}
@@ -348,6 +423,28 @@ impl Client {
 		H: RequestHandler<()>, {
 		self.request::<&[(&str, &str)], (), H>(Method::DELETE, url, None, None, handler).await
 	}
+
+	/// [request()][Self::request()], also handing back the response headers, for endpoints that keep pagination cursors or rate-limit state there.
+	///
+	/// Headers are filtered through [RequestConfig::exposed_headers] first. Responses served from the mock or testnet caches come without any.
+	pub async fn request_with_headers<Q, B, H>(&self, method: Method, url: &str, query: Option<&Q>, body: Option<B>, handler: &H) -> Result<WithHeaders<H::Successful>, RequestError>
+	where
+		Q: Serialize + ?Sized + Debug,
+		H: RequestHandler<B>, {
+		let handler = HeaderCapture {
+			inner: handler,
+			exposed: self.config.exposed_headers,
+		};
+		self.request(method, url, query, body, &handler).await
+	}
+
+	/// Wipes every response stored under [RequestConfig::cache_testnet_calls].
+	pub fn clear_test_cache() -> std::io::Result<()> {
+		match std::fs::remove_dir_all(test_calls_dir()) {
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+			r => r,
+		}
+	}
 }
 
 /// A `trait` which is used to process requests and responses for the [Client].
@@ -358,7 +455,7 @@ pub trait RequestHandler<B> {
 	/// Produce a url prefix (if any).
 	#[allow(unused_variables)]
 	fn base_url(&self, is_test: bool) -> Result<url::Url, UrlError> {
-		Url::parse("").map_err(UrlError::Parse)
+		Ok(Url::parse("")?)
 	}
 
 	/// Build a HTTP request to be sent.
@@ -396,6 +493,58 @@ pub trait RequestHandler<B> {
 	fn rate_limit_key_name(&self) -> Option<String> {
 		None
 	}
+
+	/// Whether all hosts this handler talks to are known to speak HTTP/2, so connections can skip protocol negotiation.
+	///
+	/// Only consulted when [RequestConfig::prefer_http2] is set. Handlers sharing one [Client] should agree on this, or its connection pool gets rebuilt on every switch.
+	fn supports_http2(&self) -> bool {
+		false
+	}
+}
+
+/// Successful response of [Client::request_with_headers()], along with the headers it came with.
+#[derive(Clone, Debug)]
+pub struct WithHeaders<T>(pub T, pub HeaderMap);
+
+/// Passes everything through to `inner`, keeping the [exposed](RequestConfig::exposed_headers) response headers to return along with its result.
+struct HeaderCapture<'h, H> {
+	inner: &'h H,
+	exposed: Option<&'static [&'static str]>,
+}
+impl<B, H: RequestHandler<B>> RequestHandler<B> for HeaderCapture<'_, H> {
+	type Successful = WithHeaders<H::Successful>;
+
+	fn base_url(&self, is_test: bool) -> Result<Url, UrlError> {
+		self.inner.base_url(is_test)
+	}
+
+	fn build_request(&self, builder: RequestBuilder, request_body: &Option<B>, attempt_count: u8) -> Result<Request, BuildError> {
+		self.inner.build_request(builder, request_body, attempt_count)
+	}
+
+	fn handle_response(&self, status: StatusCode, headers: HeaderMap, response_body: Bytes) -> Result<Self::Successful, HandleError> {
+		let exposed = match self.exposed {
+			Some(allowed) => headers
+				.iter()
+				.filter(|(name, _)| allowed.iter().any(|a| name.as_str().eq_ignore_ascii_case(a)))
+				.map(|(name, value)| (name.clone(), value.clone()))
+				.collect(),
+			None => {
+				let mut exposed = headers.clone();
+				exposed.remove(header::SET_COOKIE);
+				exposed
+			}
+		};
+		self.inner.handle_response(status, headers, response_body).map(|r| WithHeaders(r, exposed))
+	}
+
+	fn rate_limit_key_name(&self) -> Option<String> {
+		self.inner.rate_limit_key_name()
+	}
+
+	fn supports_http2(&self) -> bool {
+		self.inner.supports_http2()
+	}
 }
 
 /// Configuration when sending a request using [Client].
@@ -415,6 +564,10 @@ pub struct RequestConfig {
 	pub use_testnet: bool,
 	/// if `test` is true, then we will try to read the file with the cached result of any request to the same URL, aged less than specified [Duration]
 	pub cache_testnet_calls: Option<Duration> = Some(Duration::from_days(30)),
+	/// Once the testnet cache outgrows this, least recently served entries are evicted.
+	pub test_cache_max_bytes: u64 = 256 * 1024 * 1024,
+	/// URL path suffixes that are never served from (or written to) the testnet cache. Anything time-dependent belongs here.
+	pub test_cache_exclude: &'static [&'static str] = &["/time", "/timestamp"],
 
 	/// When set, responses are cached under this directory. On cache hit (< 30 days old), the cached response is returned without making a network request.
 	/// On cache miss or stale cache, the real request is made, the response is persisted, then returned.
@@ -422,6 +575,21 @@ pub struct RequestConfig {
 
 	/// Fallback ban duration when the exchange reports a ban without an unban time (e.g. Bybit).
 	pub ban_cooldown: Duration = Duration::from_secs(300),
+
+	/// Open connections as HTTP/2 straight away for [handlers known to support it](RequestHandler::supports_http2), multiplexing concurrent requests over a single connection.
+	/// Other handlers still negotiate. When `false`, everything goes over HTTP/1.1.
+	pub prefer_http2: bool = true,
+
+	/// On a 429 carrying a `Retry-After`, sleep it out and retry (while [retry](Self::retry) allows), instead of returning the [IpError::Timeout] right away.
+	pub respect_retry_after: bool = true,
+	/// Cap on a single [respect_retry_after](Self::respect_retry_after) sleep, so a bogus header can't stall the caller indefinitely.
+	pub max_retry_after: Duration = Duration::from_secs(300),
+
+	/// Response headers handed out by [Client::request_with_headers()], matched case-insensitively. `None` hands out all of them but `Set-Cookie`.
+	pub exposed_headers: Option<&'static [&'static str]>,
+
+	/// Log each request at `INFO` instead of sending it, failing it with [RequestError::DryRun]. Responses already in the mock or testnet caches are still served.
+	pub dry_run: bool,
 }
 
 /// Error type encompassing all the failure modes of [RequestHandler::handle_response()].
@@ -430,10 +598,28 @@ pub enum HandleError {
 	/// Refer to [ApiError]
 	#[diagnostic(transparent)]
 	Api(ApiError),
-	/// Couldn't parse the response. Normally just wraps the [JsonError](serde_json::Error) with [truncate_msg](v_utils::utils::truncate_msg) around the response msg.
+	/// Couldn't parse the response. Normally made by [parse_response], carrying the [JsonError](serde_json::Error) and the start of the body.
 	#[diagnostic(code(v_exchanges::http::handle::parse), help("The response body could not be parsed. Check if the API response format has changed."))]
 	Parse(Report),
 }
+
+/// Chars of the body kept in a [HandleError::Parse].
+const PARSE_ERROR_BODY_CHARS: usize = 200;
+
+/// Deserializes a response body. On failure, the body is logged and quoted in the error (up to [PARSE_ERROR_BODY_CHARS]), as the [JsonError](serde_json::Error) alone says nothing of what was actually sent.
+pub fn parse_response<T: DeserializeOwned>(body: &[u8]) -> Result<T, HandleError> {
+	serde_json::from_slice(body).map_err(|error| parse_error(error, &String::from_utf8_lossy(body)))
+}
+
+/// [HandleError::Parse] of `error`, hit on `body`. For parsing from something other than the raw bytes; see [parse_response].
+pub fn parse_error(error: serde_json::Error, body: &str) -> HandleError {
+	let head = match body.char_indices().nth(PARSE_ERROR_BODY_CHARS) {
+		Some((cut, _)) => format!("{}...", &body[..cut]),
+		None => body.to_owned(),
+	};
+	debug!(target: "v_exchanges::http", %error, body = head, "Failed to parse response");
+	HandleError::Parse(eyre!("Failed to parse response: {error}\nResponse body: {head}"))
+}
 /// Errors that exchanges purposefully transmit.
 #[non_exhaustive]
 #[derive(Debug, miette::Diagnostic, derive_more::Display, thiserror::Error, derive_more::From)]
@@ -516,11 +702,47 @@ pub enum RequestError {
 	#[error("{0}")]
 	#[diagnostic(transparent)]
 	Url(#[from] UrlError),
+	/// [RequestConfig::dry_run] is on, so the request was logged and never sent.
+	#[error("dry run: {method} {url} was not sent")]
+	#[diagnostic(code(v_exchanges::http::request::dry_run))]
+	DryRun { method: Method, url: Url },
 	/// errors meant to be propagated to the user or the developer, thus having no defined type.
 	#[allow(missing_docs)]
 	#[error(transparent)]
 	Other(#[from] Report),
 }
+impl RequestError {
+	/// Refused by [RequestConfig::dry_run], rather than failing for real.
+	pub fn is_dry_run(&self) -> bool {
+		matches!(self, Self::DryRun { .. })
+	}
+}
+impl From<url::ParseError> for RequestError {
+	fn from(e: url::ParseError) -> Self {
+		Self::Url(e.into())
+	}
+}
+/// `{"type": <variant>, "message": <Display>}`: the underlying `reqwest`/`eyre` errors aren't serializable themselves.
+impl Serialize for RequestError {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::SerializeMap as _;
+
+		let variant = match self {
+			Self::SendRequest(_) => "SendRequest",
+			Self::Utf8Error(_) => "Utf8Error",
+			Self::ReceiveResponse(_) => "ReceiveResponse",
+			Self::BuildRequest(_) => "BuildRequest",
+			Self::HandleResponse(_) => "HandleResponse",
+			Self::Url(_) => "Url",
+			Self::DryRun { .. } => "DryRun",
+			Self::Other(_) => "Other",
+		};
+		let mut map = serializer.serialize_map(Some(2))?;
+		map.serialize_entry("type", variant)?;
+		map.serialize_entry("message", &self.to_string())?;
+		map.end()
+	}
+}
 
 /// Errors that can occur during exchange's implementation of the build-request process.
 #[derive(Debug, miette::Diagnostic, derive_more::Display, thiserror::Error, derive_more::From)]
@@ -542,6 +764,12 @@ pub enum BuildError {
 	Other(Report),
 }
 
+/// `Retry-After` in its delay-seconds form, which is the only one exchanges send.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+	let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?;
+	value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
 /// Returns true if the reqwest error is a transport-level failure worth retrying.
 ///
 /// Retryable: timeout, connection failure, or a request error without a status (never got a response).
@@ -550,18 +778,183 @@ fn is_retryable_request_error(e: &reqwest::Error) -> bool {
 	e.is_timeout() || e.is_connect() || (e.is_request() && e.status().is_none())
 }
 
+// Dry Run {{{
+/// Query and body keys whose values are credentials, or derived from them.
+const DRY_RUN_REDACTED_KEYS: [&str; 4] = ["signature", "sign", "apiKey", "api_key"];
+
+fn log_dry_run(request: &Request) {
+	let headers: Vec<String> = request
+		.headers()
+		.iter()
+		.map(|(name, value)| {
+			// covers every exchange's key, signature and passphrase headers (`X-MBX-APIKEY`, `X-BAPI-SIGN`, `KC-API-PASSPHRASE`, ..)
+			let is_secret = ["key", "sign", "passphrase"].iter().any(|s| name.as_str().contains(s));
+			match is_secret {
+				true => format!("{name}: <redacted>"),
+				false => format!("{name}: {}", value.to_str().unwrap_or("<binary>")),
+			}
+		})
+		.collect();
+	let body = request.body().and_then(|b| b.as_bytes()).map(redact_body);
+	info!(target: "v_exchanges::http", method = %request.method(), url = %redact_url(request.url()), ?headers, ?body, "Dry run, not sending");
+}
+
+fn redact_url(url: &Url) -> Url {
+	let mut url = url.clone();
+	let pairs: Vec<(String, String)> = url
+		.query_pairs()
+		.map(|(k, v)| match DRY_RUN_REDACTED_KEYS.contains(&k.as_ref()) {
+			true => (k.into_owned(), "<redacted>".to_owned()),
+			false => (k.into_owned(), v.into_owned()),
+		})
+		.collect();
+	if !pairs.is_empty() {
+		url.query_pairs_mut().clear().extend_pairs(pairs);
+	}
+	url
+}
+
+/// Bodies are json or url-encoded depending on the exchange (Bybit's legacy signing puts `api_key` and `sign` in there), and get redacted like [the url](redact_url). Anything else is logged as-is.
+fn redact_body(body: &[u8]) -> String {
+	if let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(body) {
+		redact_json(&mut json);
+		return json.to_string();
+	}
+	let text = String::from_utf8_lossy(body);
+	match serde_urlencoded::from_str::<Vec<(String, String)>>(&text) {
+		Ok(pairs) if pairs.iter().any(|(k, _)| DRY_RUN_REDACTED_KEYS.contains(&k.as_str())) => {
+			let pairs: Vec<(String, String)> = pairs
+				.into_iter()
+				.map(|(k, v)| match DRY_RUN_REDACTED_KEYS.contains(&k.as_str()) {
+					true => (k, "<redacted>".to_owned()),
+					false => (k, v),
+				})
+				.collect();
+			serde_urlencoded::to_string(pairs).expect("strings always encode")
+		}
+		_ => text.into_owned(),
+	}
+}
+
+fn redact_json(value: &mut serde_json::Value) {
+	match value {
+		serde_json::Value::Object(map) =>
+			for (k, v) in map.iter_mut() {
+				match DRY_RUN_REDACTED_KEYS.contains(&k.as_str()) {
+					true => *v = serde_json::Value::String("<redacted>".to_owned()),
+					false => redact_json(v),
+				}
+			},
+		serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+		_ => {}
+	}
+}
+//,}}}
+
+// Testnet Cache {{{
 static TEST_CALLS_PATH: OnceLock<PathBuf> = OnceLock::new();
-fn test_calls_path<Q: Serialize>(url: &Url, query: &Option<Q>) -> PathBuf {
-	let base = TEST_CALLS_PATH.get_or_init(|| v_utils::xdg_cache_dir!("test_calls"));
+fn test_calls_dir() -> &'static PathBuf {
+	TEST_CALLS_PATH.get_or_init(|| v_utils::xdg_cache_dir!("test_calls"))
+}
+
+/// Query keys that either change on every call or must never touch the disk.
+const TEST_CACHE_STRIPPED_KEYS: [&str; 6] = ["signature", "timestamp", "recvWindow", "apiKey", "api_key", "sign"];
+
+/// Location of a single cached testnet response.
+///
+/// Filename is a hash of `(method, url, sorted query)` with volatile and secret params stripped, so it's always a valid fs name and never leaks credentials.
+/// Next to the body lives a `.json` sidecar with the human-readable request; its mtime marks when the entry was stored, while the body's mtime marks last use (for LRU).
+#[derive(Debug)]
+struct TestCacheEntry {
+	body_path: PathBuf,
+	sidecar_path: PathBuf,
+	description: String,
+}
+impl TestCacheEntry {
+	fn new<Q: Serialize + ?Sized>(method: &Method, url: &Url, query: Option<&Q>) -> Self {
+		use sha2::{Digest as _, Sha256};
+
+		let mut pairs: Vec<(String, String)> = query
+			.and_then(|q| serde_urlencoded::to_string(q).ok())
+			.map(|encoded| url::form_urlencoded::parse(encoded.as_bytes()).into_owned().collect())
+			.unwrap_or_default();
+		pairs.retain(|(k, _)| !TEST_CACHE_STRIPPED_KEYS.contains(&k.as_str()));
+		pairs.sort();
+
+		let mut url = url.clone();
+		url.set_query(None);
+		let query_str = pairs.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join("&");
+		let description = match query_str.is_empty() {
+			true => format!("{method} {url}"),
+			false => format!("{method} {url}?{query_str}"),
+		};
 
-	let mut filename = url.to_string();
-	if query.is_some() {
-		filename.push('?');
-		filename.push_str(&serde_urlencoded::to_string(query).unwrap_or_default());
+		let hash = hex::encode(Sha256::digest(description.as_bytes()));
+		let dir = test_calls_dir();
+		Self {
+			body_path: dir.join(&hash),
+			sidecar_path: dir.join(format!("{hash}.json")),
+			description,
+		}
+	}
+
+	/// Cached body, if present and stored less than `max_age` ago. Bumps the entry in LRU order.
+	fn read(&self, max_age: Duration) -> Option<String> {
+		let stored_at = self.sidecar_path.metadata().and_then(|m| m.modified()).ok()?;
+		if stored_at.elapsed().unwrap_or_default() >= max_age {
+			return None;
+		}
+		let body = std::fs::read_to_string(&self.body_path).ok()?;
+		if let Ok(f) = std::fs::File::options().write(true).open(&self.body_path) {
+			f.set_modified(std::time::SystemTime::now()).ok();
+		}
+		Some(body)
+	}
+
+	fn write(&self, body: &[u8], max_total_bytes: u64) {
+		let sidecar = serde_json::json!({ "request": self.description });
+		let written: std::io::Result<()> = try {
+			std::fs::create_dir_all(test_calls_dir())?;
+			std::fs::write(&self.body_path, body)?;
+			std::fs::write(&self.sidecar_path, sidecar.to_string())?;
+		};
+		match written {
+			Ok(()) => {
+				debug!(target: "v_exchanges::http", request = %self.description, "Testnet cache write: {}", self.body_path.display());
+				evict_test_cache(max_total_bytes);
+			}
+			Err(e) => warn!(target: "v_exchanges::http", request = %self.description, ?e, "Failed to persist testnet cache entry"),
+		}
 	}
-	base.join(filename)
 }
 
+/// Drops least recently served entries until the cache fits into `max_total_bytes`.
+fn evict_test_cache(max_total_bytes: u64) {
+	let Ok(read_dir) = std::fs::read_dir(test_calls_dir()) else { return };
+	let mut bodies: Vec<(PathBuf, std::time::SystemTime, u64)> = read_dir
+		.filter_map(|e| e.ok())
+		.filter(|e| e.path().extension().is_none())
+		.filter_map(|e| {
+			let meta = e.metadata().ok()?;
+			Some((e.path(), meta.modified().ok()?, meta.len()))
+		})
+		.collect();
+	let mut total: u64 = bodies.iter().map(|(_, _, len)| len).sum();
+	if total <= max_total_bytes {
+		return;
+	}
+	bodies.sort_by_key(|(_, used_at, _)| *used_at);
+	for (path, _, len) in bodies {
+		if total <= max_total_bytes {
+			break;
+		}
+		std::fs::remove_file(&path).ok();
+		std::fs::remove_file(path.with_extension("json")).ok();
+		total -= len;
+	}
+}
+//,}}}
+
 const MOCK_CACHE_DURATION: Duration = Duration::from_days(30);
 
 /// Constructs a cache path from the mock cache dir and the URL.
@@ -604,6 +997,16 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn test_cache_key_ignores_order_and_secrets() {
+		let url = Url::parse("https://testnet.binancefuture.com/fapi/v1/klines").unwrap();
+		let a = TestCacheEntry::new(&Method::GET, &url, Some(&[("symbol", "BTCUSDT"), ("interval", "1m")]));
+		let b = TestCacheEntry::new(&Method::GET, &url, Some(&[("interval", "1m"), ("timestamp", "1700000000000"), ("symbol", "BTCUSDT"), ("signature", "deadbeef")]));
+		assert_eq!(a.body_path, b.body_path);
+		assert_eq!(a.description, "GET https://testnet.binancefuture.com/fapi/v1/klines?interval=1m&symbol=BTCUSDT");
+		assert!(!b.description.contains("deadbeef"));
+	}
+
 	#[tokio::test]
 	async fn banned_bucket_short_circuits_then_recovers() {
 		let mut client = Client::default();
@@ -627,6 +1030,216 @@ mod tests {
 		assert!(!client.banned_until.contains_key(&bucket), "expired ban was not evicted");
 	}
 
+	#[test]
+	fn url_parse_errors_convert() {
+		fn join(base: &Url, path: &str) -> Result<Url, RequestError> {
+			Ok(base.join(path)?)
+		}
+		let base = Url::parse("https://fapi.binance.com").unwrap();
+		assert_eq!(join(&base, "/fapi/v1/time").unwrap().as_str(), "https://fapi.binance.com/fapi/v1/time");
+		assert!(matches!(join(&base, "http://[::1"), Err(RequestError::Url(UrlError::Parse(url::ParseError::InvalidIpv6Address)))));
+		assert!(matches!(crate::ws::WsError::from(url::ParseError::EmptyHost), crate::ws::WsError::Url(UrlError::Parse(url::ParseError::EmptyHost))));
+	}
+
+	#[tokio::test]
+	async fn pool_follows_http_version() {
+		let mut client = Client::default();
+		let handler = BanHandler {
+			base: Url::parse("https://api.testex.com/").unwrap(),
+			network_ran: AtomicBool::new(false),
+		};
+		// keep requests off the network
+		client.banned_until.insert(Ustr::from("ip.testex"), Timestamp::now() + SignedDuration::from_secs(60));
+
+		let _ = client.get_no_query("", &handler).await;
+		assert_eq!(client.client.load().version, HttpVersion::Negotiate, "handler doesn't claim HTTP/2 support");
+
+		client.config.prefer_http2 = false;
+		let _ = client.get_no_query("", &handler).await;
+		assert_eq!(client.client.load().version, HttpVersion::Http1Only);
+		assert_eq!(HttpVersion::pick(&RequestConfig::default(), true), HttpVersion::Http2PriorKnowledge);
+	}
+
+	#[tokio::test]
+	async fn interceptors_see_the_wire() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+
+		let server = async {
+			let (mut sock, _) = listener.accept().await.unwrap();
+			let mut buf = [0u8; 1024];
+			let n = sock.read(&mut buf).await.unwrap();
+			sock.write_all(b"HTTP/1.1 200 OK\r\nX-Test: 1\r\nContent-Length: 2\r\n\r\n{}").await.unwrap();
+			String::from_utf8_lossy(&buf[..n]).to_lowercase()
+		};
+
+		let seen = Arc::new(std::sync::Mutex::new(None));
+		let mut client = Client::default();
+		client.request_interceptor = Some(Arc::new(|mut r: Request| {
+			r.headers_mut().insert("x-injected", header::HeaderValue::from_static("yes"));
+			r
+		}));
+		let seen_clone = Arc::clone(&seen);
+		client.response_interceptor = Some(Arc::new(move |status, headers: &HeaderMap, body: &Bytes| {
+			*seen_clone.lock().unwrap() = Some((status, headers.contains_key("x-test"), body.clone()));
+		}));
+		let handler = BanHandler {
+			base: Url::parse(&format!("http://{addr}/")).unwrap(),
+			network_ran: AtomicBool::new(false),
+		};
+		let (raw_request, _) = tokio::join!(server, client.get_no_query("", &handler));
+
+		assert!(raw_request.contains("x-injected: yes"), "{raw_request}");
+		assert_eq!(seen.lock().unwrap().take(), Some((StatusCode::OK, true, Bytes::from_static(b"{}"))));
+	}
+
+	#[tokio::test]
+	async fn request_logs_under_http_target() {
+		let capture = crate::tracing_capture::Capture::default();
+		let _guard = tracing::subscriber::set_default(capture.clone());
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+
+		let server = async {
+			let (mut sock, _) = listener.accept().await.unwrap();
+			let mut buf = [0u8; 1024];
+			let _ = sock.read(&mut buf).await.unwrap();
+			sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}").await.unwrap();
+		};
+		let handler = BanHandler {
+			base: Url::parse(&format!("http://{addr}/")).unwrap(),
+			network_ran: AtomicBool::new(false),
+		};
+		let (_, r) = tokio::join!(server, Client::default().get_no_query("", &handler));
+		assert!(r.is_err());
+
+		let captured = capture.0.lock().unwrap();
+		assert!(captured.spans.iter().any(|s| s.target == "v_exchanges::http" && s.name == "request"), "{:?}", captured.spans);
+		let ours: Vec<_> = captured.events.iter().filter(|e| e.target.starts_with("v_exchanges")).collect();
+		assert!(ours.iter().all(|e| e.target == "v_exchanges::http" && e.span == Some("request")), "{ours:?}");
+		// response headers at debug, the reported ban at warn
+		assert!(ours.iter().any(|e| e.level == tracing::Level::DEBUG));
+		assert!(ours.iter().any(|e| e.level == tracing::Level::WARN));
+	}
+
+	#[tokio::test]
+	async fn dry_run_logs_instead_of_sending() {
+		let capture = crate::tracing_capture::Capture::default();
+		let _guard = tracing::subscriber::set_default(capture.clone());
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+
+		let mut client = Client::default();
+		client.config.dry_run = true;
+		let handler = BanHandler {
+			base: Url::parse(&format!("http://{addr}/")).unwrap(),
+			network_ran: AtomicBool::new(false),
+		};
+		let r = client.get("order", &[("symbol", "BTCUSDT"), ("signature", "deadbeef")], &handler).await;
+
+		let e = r.unwrap_err();
+		assert!(e.is_dry_run(), "{e}");
+		assert_eq!(e.to_string(), format!("dry run: GET http://{addr}/order?symbol=BTCUSDT&signature=%3Credacted%3E was not sent"));
+		assert!(tokio::time::timeout(Duration::from_millis(50), listener.accept()).await.is_err(), "nothing reached the server");
+		let captured = capture.0.lock().unwrap();
+		assert!(captured.events.iter().any(|e| e.target == "v_exchanges::http" && e.level == tracing::Level::INFO), "{:?}", captured.events);
+	}
+
+	#[test]
+	fn dry_run_bodies_are_redacted() {
+		let json: serde_json::Value = serde_json::from_str(&redact_body(br#"{"symbol":"BTCUSDT","api_key":"k","orders":[{"sign":"s"}]}"#)).unwrap();
+		assert_eq!(json, serde_json::json!({"symbol": "BTCUSDT", "api_key": "<redacted>", "orders": [{"sign": "<redacted>"}]}));
+		assert_eq!(redact_body(b"symbol=BTCUSDT&api_key=k&sign=s"), "symbol=BTCUSDT&api_key=%3Credacted%3E&sign=%3Credacted%3E");
+		assert_eq!(redact_body(b"symbol=BTCUSDT&qty=1"), "symbol=BTCUSDT&qty=1");
+	}
+
+	#[tokio::test]
+	async fn sleeps_out_retry_after() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+
+		// every response 429s, so the number of requests served is 1 + retries taken
+		let server = async {
+			let mut served = 0;
+			while let Ok(Ok((mut sock, _))) = tokio::time::timeout(Duration::from_millis(500), listener.accept()).await {
+				let mut buf = [0u8; 1024];
+				let _ = sock.read(&mut buf).await;
+				sock.write_all(b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 60\r\nConnection: close\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+				served += 1;
+			}
+			served
+		};
+
+		let mut client = Client::default();
+		client.config.retry.max_retries = 2;
+		client.config.max_retry_after = Duration::from_millis(300);
+		let handler = BanHandler {
+			base: Url::parse(&format!("http://{addr}/")).unwrap(),
+			network_ran: AtomicBool::new(false),
+		};
+		// midway through the first sleep, others on the bucket are already turned away
+		let probe = async {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			client.banned_until.contains_key(&Ustr::from("ip.0"))
+		};
+		let started = std::time::Instant::now();
+		let (served, res, gated) = tokio::join!(server, client.get_no_query("", &handler), probe);
+		assert_eq!(served, 3);
+		assert!(gated, "bucket was not gated while sleeping out Retry-After");
+		assert!(started.elapsed() < Duration::from_secs(5), "Retry-After was not capped");
+		// out of retries: the error surfaces as before
+		assert!(matches!(res, Err(RequestError::HandleResponse(HandleError::Api(ApiError::Ip(IpError::Timeout { .. }))))));
+
+		assert_eq!(parse_retry_after(&HeaderMap::from_iter([(header::RETRY_AFTER, header::HeaderValue::from_static(" 7"))])), Some(Duration::from_secs(7)));
+		assert_eq!(parse_retry_after(&HeaderMap::from_iter([(header::RETRY_AFTER, header::HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"))])), None);
+	}
+
+	#[tokio::test]
+	async fn headers_come_back_filtered() {
+		struct Body(Url);
+		impl RequestHandler<()> for Body {
+			type Successful = String;
+
+			fn base_url(&self, _is_test: bool) -> Result<Url, UrlError> {
+				Ok(self.0.clone())
+			}
+
+			fn build_request(&self, builder: RequestBuilder, _body: &Option<()>, _attempt: u8) -> Result<Request, BuildError> {
+				builder.build().map_err(|e| BuildError::Other(eyre!(e)))
+			}
+
+			fn handle_response(&self, _status: StatusCode, _headers: HeaderMap, body: Bytes) -> Result<String, HandleError> {
+				Ok(String::from_utf8_lossy(&body).into_owned())
+			}
+		}
+
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		let server = async {
+			for _ in 0..2 {
+				let (mut sock, _) = listener.accept().await.unwrap();
+				let mut buf = [0u8; 1024];
+				let _ = sock.read(&mut buf).await;
+				sock.write_all(b"HTTP/1.1 200 OK\r\nX-Ratelimit-Remaining: 29\r\nSet-Cookie: session=secret\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok").await.unwrap();
+			}
+		};
+		let handler = Body(Url::parse(&format!("http://{addr}/")).unwrap());
+		let requests = async {
+			let mut client = Client::default();
+			let all = client.request_with_headers::<(), _, _>(Method::GET, "", None, None::<()>, &handler).await.unwrap();
+			client.config.exposed_headers = Some(&["X-Ratelimit-Remaining"]);
+			let allowed = client.request_with_headers::<(), _, _>(Method::GET, "", None, None::<()>, &handler).await.unwrap();
+			(all, allowed)
+		};
+		let (_, (WithHeaders(body, all), WithHeaders(_, allowed))) = tokio::join!(server, requests);
+
+		assert_eq!(body, "ok");
+		assert_eq!(all["x-ratelimit-remaining"], "29");
+		assert!(!all.contains_key(header::SET_COOKIE));
+		assert_eq!(allowed.len(), 1, "{allowed:?}");
+		assert_eq!(allowed["x-ratelimit-remaining"], "29");
+	}
+
 	#[tokio::test]
 	async fn ban_recorded_with_cooldown_fallback() {
 		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -657,4 +1270,16 @@ mod tests {
 			"recorded unban {until} far from expected {expected}"
 		);
 	}
+
+	#[test]
+	fn parse_error_quotes_the_body() {
+		let body = format!(r#"{{"code":0,"msg":"{}"}}"#, "x".repeat(500));
+		let Err(HandleError::Parse(e)) = parse_response::<Vec<u8>>(body.as_bytes()) else { panic!() };
+		let msg = e.to_string();
+		assert!(msg.contains("invalid type: map"), "{msg}");
+		assert!(msg.contains(&body[..PARSE_ERROR_BODY_CHARS]));
+		assert!(!msg.contains(&body[..=PARSE_ERROR_BODY_CHARS]), "cut at {PARSE_ERROR_BODY_CHARS}");
+
+		assert_eq!(parse_response::<Vec<u8>>(b"[1,2]").unwrap(), vec![1, 2]);
+	}
 }
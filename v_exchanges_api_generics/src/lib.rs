@@ -18,6 +18,16 @@
 //! - [WebSocketHandler][websocket::WebSocketHandler] A `trait` that is used to handle messages etc.. for a WebSocket Connection.
 //!
 //! For a more detailed documentation, see the links above.
+//!
+//! # Logging
+//! Everything across the `v_exchanges*` crates logs under one of these [tracing] targets, rather than the module path:
+//! - `v_exchanges::http`: REST transport. Each request runs in a `request` span, with `exchange`, `method` and `endpoint`.
+//! - `v_exchanges::ws`: websocket transport. A `ws_connection` span (with `url`) lives as long as the connection, with a `ws_session` child (with `reconnects`) for every socket it opens.
+//! - `v_exchanges::retry`: [RetryManager].
+//! - `v_exchanges::<exchange>`: anything specific to one venue, eg `v_exchanges::binance`.
+//! - `v_exchanges`: exchange-agnostic logic on top, and the per-call `exchange` span.
+//!
+//! So `RUST_LOG=v_exchanges=info,v_exchanges::ws=debug` shows ws events at debug and everything else at info, and `v_exchanges::bybit=trace` zooms in on one venue.
 
 use std::backtrace::Backtrace;
 
@@ -29,6 +39,7 @@ pub mod ws;
 pub use ratelimiter::{RateLimiter, quota::Quota};
 pub use retry::{ExponentialBackoff, RetryConfig, RetryManager};
 pub extern crate reqwest;
+#[cfg(feature = "ws")]
 pub extern crate tokio_tungstenite;
 
 #[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
@@ -69,3 +80,77 @@ pub enum UrlError {
 	)]
 	MissingTestnet(url::Url),
 }
+
+/// Records targets and levels of everything that fires while it's the default subscriber, with the span each event fired in.
+#[cfg(test)]
+pub(crate) mod tracing_capture {
+	use std::sync::{Arc, Mutex};
+
+	use tracing::{Event, Level, Metadata, Subscriber, span};
+
+	#[derive(Debug)]
+	pub(crate) struct CapturedSpan {
+		pub target: &'static str,
+		pub name: &'static str,
+		pub parent: Option<&'static str>,
+	}
+	#[derive(Debug)]
+	pub(crate) struct CapturedEvent {
+		pub target: &'static str,
+		pub level: Level,
+		/// Innermost span entered when it fired.
+		pub span: Option<&'static str>,
+	}
+	#[derive(Debug, Default)]
+	pub(crate) struct Captured {
+		pub spans: Vec<CapturedSpan>,
+		pub events: Vec<CapturedEvent>,
+		stack: Vec<u64>,
+	}
+
+	#[derive(Clone, Debug, Default)]
+	pub(crate) struct Capture(pub Arc<Mutex<Captured>>);
+	impl Subscriber for Capture {
+		fn enabled(&self, _: &Metadata<'_>) -> bool {
+			true
+		}
+
+		fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+			let mut c = self.0.lock().unwrap();
+			let parent = match attrs.parent() {
+				Some(id) => Some(id.into_u64()),
+				None if attrs.is_contextual() => c.stack.last().copied(),
+				None => None,
+			};
+			let parent = parent.map(|id| c.spans[id as usize - 1].name);
+			c.spans.push(CapturedSpan {
+				target: attrs.metadata().target(),
+				name: attrs.metadata().name(),
+				parent,
+			});
+			span::Id::from_u64(c.spans.len() as u64)
+		}
+
+		fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+		fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+		fn event(&self, event: &Event<'_>) {
+			let mut c = self.0.lock().unwrap();
+			let span = c.stack.last().map(|id| c.spans[*id as usize - 1].name);
+			c.events.push(CapturedEvent {
+				target: event.metadata().target(),
+				level: *event.metadata().level(),
+				span,
+			});
+		}
+
+		fn enter(&self, id: &span::Id) {
+			self.0.lock().unwrap().stack.push(id.into_u64());
+		}
+
+		fn exit(&self, _: &span::Id) {
+			self.0.lock().unwrap().stack.pop();
+		}
+	}
+}
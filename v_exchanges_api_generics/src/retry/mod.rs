@@ -66,6 +66,7 @@ where
 				let elapsed = start_time.elapsed();
 				if elapsed.as_millis() >= u128::from(max_elapsed_ms) {
 					tracing::trace!(
+						target: "v_exchanges::retry",
 						operation = %operation_name,
 						attempts = attempt + 1,
 						budget_ms = max_elapsed_ms,
@@ -78,18 +79,18 @@ where
 			match operation().await {
 				Ok(success) => {
 					if attempt > 0 {
-						tracing::trace!(operation = %operation_name, attempts = attempt + 1, "Retry succeeded");
+						tracing::trace!(target: "v_exchanges::retry", operation = %operation_name, attempts = attempt + 1, "Retry succeeded");
 					}
 					return Ok(success);
 				}
 				Err(e) => {
 					if !should_retry(&e) {
-						tracing::trace!(operation = %operation_name, error = %e, "Non-retryable error");
+						tracing::trace!(target: "v_exchanges::retry", operation = %operation_name, error = %e, "Non-retryable error");
 						return Err(e);
 					}
 
 					if attempt >= self.config.max_retries {
-						tracing::trace!(operation = %operation_name, attempts = attempt + 1, error = %e, "Retries exhausted");
+						tracing::trace!(target: "v_exchanges::retry", operation = %operation_name, attempts = attempt + 1, error = %e, "Retries exhausted");
 						return Err(e);
 					}
 
@@ -101,6 +102,7 @@ where
 
 						if remaining.is_zero() {
 							tracing::trace!(
+								target: "v_exchanges::retry",
 								operation = %operation_name,
 								attempts = attempt + 1,
 								budget_ms = max_elapsed_ms,
@@ -113,6 +115,7 @@ where
 					}
 
 					tracing::trace!(
+						target: "v_exchanges::retry",
 						operation = %operation_name,
 						attempt = attempt + 1,
 						delay_ms = delay.as_millis() as u64,
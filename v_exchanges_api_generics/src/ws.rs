@@ -1,439 +1,55 @@
-use std::{
-	future::Future,
-	pin::Pin,
-	time::{Duration, SystemTime},
-	vec,
-};
+use std::time::Duration;
 
 use ahash::AHashSet;
 use eyre::{Result, bail};
-use futures_util::{
-	FutureExt as _, SinkExt as _, StreamExt as _,
-	stream::{FuturesUnordered, SplitSink, SplitStream},
-};
-use jiff::Timestamp;
+use jiff::{SignedDuration, Timestamp};
 use reqwest::Url;
-use tokio::net::TcpStream;
-use tokio_tungstenite::{
-	MaybeTlsStream, WebSocketStream,
-	tungstenite::{self, Bytes, Message},
-};
+#[cfg(feature = "ws")]
+use tokio_tungstenite::tungstenite;
 
-use crate::{ConstructAuthError, RetryConfig, UrlError, retry::ExponentialBackoff};
+use crate::{ConstructAuthError, RetryConfig, UrlError};
 
-type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
-type WsSink = SplitSink<WsStream, Message>;
-type WsRead = SplitStream<WsStream>;
-/// `Send + Sync` boxed FU member. `Sync` (vs the stock `BoxFuture`, which is `Send`-only) is required
-/// because [WsConnection] is exposed through `ExchangeStream: Sync` downstream.
-type BoxedFu = Pin<Box<dyn Future<Output = FuEvent> + Send + Sync>>;
+#[cfg(feature = "ws")]
+mod connection;
+#[cfg(feature = "ws")]
+mod router;
+#[cfg(feature = "ws")]
+pub use connection::*;
+#[cfg(feature = "ws")]
+pub use router::TypedRouter;
 
-/// handle exchange-level events on the [WsConnection].
-pub trait WsHandler: std::fmt::Debug {
-	/// Returns a [WsConfig] that will be applied for all WebSocket connections handled by this handler.
-	fn config(&self) -> Result<WsConfig, UrlError> {
-		Ok(WsConfig::default())
-	}
-
-	/// Called when the [WsConnection] is created and on reconnection. Returned messages will be sent back to the server as-is.
-	///
-	/// Handling of `listen-key`s or any other authentication methods exchange demands should be done here. Although oftentimes handling the auth will spread into the [handle_message](Self::handle_message) too.
-	/// Can be ran multiple times (on every reconnect). Thus this inherently cannot be used to initiate connectionions based on a change of state (ie order creation).
-	#[allow(unused_variables)]
-	fn handle_auth(&mut self) -> Result<Vec<tungstenite::Message>, WsError> {
-		Ok(vec![])
-	}
-
-	//Q: problem: can be either {String, serde_json::Value} //? other things?
-	/*
-	  "position"
-	  ||
-	  json!{
-	"id": "56374a46-3061-486b-a311-99ee972eb648",
-	"method": "order.place",
-	"params": {
-	  "symbol": "BTCUSDT",
-	  "side": "SELL",
-	  "type": "LIMIT",
-	  "timeInForce": "GTC",
-	  "price": "23416.10000000",
-	  "quantity": "0.00847000",
-	  "apiKey": "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A",
-	  "signature": "15af09e41c36f3cc61378c2fbe2c33719a03dd5eba8d0f9206fbda44de717c88",
-	  "timestamp": 1660801715431
-	  }
-	  }
-	  - and then the latter could be requiring signing
-	  */
-	#[allow(unused_variables)]
-	fn handle_subscribe(&mut self, topics: AHashSet<Topic>) -> Result<Vec<tungstenite::Message>, WsError>;
-
-	/// Active-heartbeat payload, sent every [WsConfig::active_ping_freq] when that is `Some`. Some
-	/// exchanges (eg Bybit) require the *client* to proactively keep the connection alive with an
-	/// app-level message (`{"op":"ping"}`) rather than relying on the WebSocket protocol's ping/pong
-	/// — for those, return that message here and set `active_ping_freq`. Default: no active ping.
-	fn active_ping(&self) -> Vec<tungstenite::Message> {
-		vec![]
-	}
-
-	/// Called when the [WsConnection] received a JSON-RPC value, returns messages to be sent to the server or the content with parsed event name. If not the desired content and no respose is to be sent (like after a confirmation for a subscription), return a Response with an empty Vec.
-	#[allow(unused_variables)]
-	fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError>;
-	//A: use this iff spot&&perp binance accept listen-key refresh through stream
-	///// Additional POST communication with the exchange, not conditional on received messages, can be handled here.
-	///// Really this is just for damn Binance with their stupid `listn-key` standard.
-	//fn handle_post(&mut self) -> Result<Option<Vec<tungstenite::Message>>, WsError> {
-	//	Ok(None)
-	//}
-
-	//#[allow(unused_variables)]
-	//fn handle_jrpc(&mut self, jrpc: &serde_json::Value) -> Result<Option<Vec<tungstenite::Message>>, WsError> {
-	//	Ok(None)
-	//}
-}
-#[derive(Clone, Debug)]
-pub enum ResponseOrContent {
-	/// Response to a message sent to the server.
-	Response(Vec<tungstenite::Message>),
-	/// Content received from the server.
-	Content(ContentEvent),
-}
 #[derive(Clone, Debug)]
 pub struct ContentEvent {
 	pub data: serde_json::Value,
 	pub topic: String,
+	/// Exchange's event time. [Timestamp::UNIX_EPOCH] for events that carry none, which keeps them out of [LatencyStats].
 	pub time: Timestamp,
 	pub event_type: String,
 }
 #[derive(Clone, Debug, Eq)]
 pub struct TopicInterpreter<T> {
-	/// Only one interpreter for this name is allowed to exist // enforced through `Hash` impl defined over `event_name` only
+	/// Only one interpreter for this name is allowed to exist // enforced through `Hash` impl defined over `event_name` only, and by [TypedRouter::register]
 	pub event_name: String,
 	/// When name matches, interpretation should succeed.
 	pub interpret: fn(&serde_json::Value) -> Result<T, WsError>,
 }
-/// Main way to interact with the WebSocket APIs.
-//REVIEW: manual `Debug` (was derived) — the `fu`/`sink` I/O futures aren't `Debug`; we skip them.
-pub struct WsConnection<H: WsHandler> {
-	url: Url,
-	config: WsConfig,
-	handler: H,
-	backoff: ExponentialBackoff,
-	/// When set, `next()` will sleep until this instant before attempting to connect.
-	/// Only set on actual connection failure (not on cancellation), making `next()` cancel-safe.
-	reconnect_after: Option<tokio::time::Instant>,
-	/// Drives I/O concurrently: the reader is a permanent standing member; a writer is transient
-	/// (0..=1). Both members own their resources (no `&self` borrow), so they survive across calls.
-	fu: FuturesUnordered<BoxedFu>,
-	/// The sink, parked here whenever no writer-future currently owns it.
-	sink: Option<WsSink>,
-	/// Ordered control / JRPC replies awaiting a flush to the server, plus any idle-timeout self-Ping.
-	///
-	/// Note we do NOT queue Pongs here: tungstenite auto-answers inbound Pings with a Pong carrying
-	/// the exact payload (`tungstenite::protocol::WebSocket`, flushed on the next read/write), which
-	/// already satisfies eg Binance's exact-echo requirement. Manually ponging would duplicate it.
-	outbox: Vec<Message>,
-	/// `None` == disconnected (replaces the old `stream.is_none()` check). `Some` holds the instant
-	/// the live connection started, for `refresh_after`.
-	connected_since: Option<SystemTime>,
-	/// When we last sent something the server hasn't acknowledged (used to pick the response_ vs
-	/// message_ timeout). Moved up from the old `WsConnectionStream`.
-	last_unanswered_communication: Option<SystemTime>,
-	/// Saw a Close / reconnecting error but returned already-collected content first; reconnect on
-	/// the next `next()` call.
-	pending_reconnect: bool,
-	/// How often to fire the standing active-ping timer (`PingDue`), enqueueing the handler's
-	/// [active_ping](WsHandler::active_ping) payload. `None` == no active ping (rely on inbound traffic
-	/// + protocol pong, as Binance does). Copied from [WsConfig::active_ping_freq] at construction.
-	active_ping_freq: Option<Duration>,
-}
-impl<H: WsHandler> WsConnection<H> {
-	#[allow(missing_docs)]
-	pub fn try_new(url_suffix: &str, handler: H) -> Result<Self, WsError> {
-		let config = handler.config()?;
-		let url = match &config.base_url {
-			Some(base_url) => base_url.join(url_suffix).map_err(UrlError::Parse)?,
-			None => Url::parse(url_suffix).map_err(UrlError::Parse)?,
-		};
-		let backoff = ExponentialBackoff::try_from(&config.reconnect).map_err(|e| WsError::Other(eyre::eyre!("Invalid reconnect backoff configuration: {e}")))?;
-		let active_ping_freq = config.active_ping_freq;
-
-		Ok(Self {
-			url,
-			config,
-			handler,
-			backoff,
-			reconnect_after: None,
-			fu: FuturesUnordered::new(),
-			sink: None,
-			outbox: Vec::new(),
-			connected_since: None,
-			last_unanswered_communication: None,
-			pending_reconnect: false,
-			active_ping_freq,
-		})
-	}
-
-	/**
-	The main interface.
-	All connection upkeep (ping/pong, JRPC control replies, reconnect, refresh) is hidden; a call blocks until the socket buffer has something, then drains **all** immediately-available frames and returns every content event from them in one batch.
-
-	cancel-safe: any path with non-empty collected content returns *before* the next `.await`, so a cancellation can only land on `self.fu.next().await` — where the half-read reader future still lives on `self.fu`, losing nothing.
-	Deferred reconnect/upkeep is picked up on the next call.
-	**/
-	pub async fn next(&mut self) -> Result<Vec<ContentEvent>, WsError> {
-		// Cancel-safe backoff: a previous failed attempt parked a target Instant; resume the wait.
-		if let Some(until) = self.reconnect_after.take() {
-			tokio::time::sleep_until(until).await;
-		}
-		if self.pending_reconnect {
-			self.pending_reconnect = false;
-			self.reconnect().await?;
-		}
-		if let Some(since) = self.connected_since
-			&& since + self.config.refresh_after < SystemTime::now()
-		{
-			tracing::info!("Refreshing connection, as `refresh_after` specified in WsConfig has elapsed ({:?})", self.config.refresh_after);
-			self.reconnect().await?;
-		}
-		if self.connected_since.is_none() {
-			self.connect().await?;
-		}
-
-		let mut content: Vec<ContentEvent> = Vec::new();
-
-		loop {
-			self.try_flush_outbox(); // deferred upkeep flies concurrently with the read, in the same FU
-
-			let timeout = match self.choose_timeout() {
-				Some(d) => d,
-				None => {
-					tracing::error!("Timeout for last unanswered communication ended before `.next()` was called. This likely indicates a clientside implementation error.");
-					self.reconnect().await?;
-					continue;
-				}
-			};
-
-			match tokio::time::timeout(timeout, self.fu.next()).await {
-				Err(_) => {
-					// Nothing arrived in time. Return any collected content first; defer ping/reconnect.
-					if !content.is_empty() {
-						return Ok(content);
-					}
-					if self.last_unanswered_communication.is_some() {
-						tracing::warn!("Response to a forced communication timed out after {timeout:?}. Reconnecting.");
-						self.reconnect().await?;
-					} else {
-						// Standard message_timeout elapsed with nothing pending, — force life out of the server with our own Ping (queued; flushed on the next loop's `try_flush_outbox`).
-						self.outbox.push(Message::Ping(Bytes::default()));
-					}
-					continue;
-				}
-				Ok(None) => {
-					// FU drained (defensive; the reader is a permanent member so this shouldn't happen).
-					tracing::warn!("FuturesUnordered empty despite permanent reader. Reconnecting.");
-					self.reconnect().await?;
-					continue;
-				}
-				Ok(Some(FuEvent::Write { sink, result })) => {
-					self.sink = Some(sink); // park the sink back
-					if let Err(e) = result
-						&& is_reconnecting(&e)
-					{
-						tracing::warn!("Write failed ({e:?}). Reconnecting.");
-						self.reconnect().await?;
-					}
-					continue; // a write is never content
-				}
-				Ok(Some(FuEvent::PingDue)) => {
-					// Active heartbeat fell due: queue the handler's ping payload (flushed by the next loop's `try_flush_outbox`) and re-arm the standing timer.
-					// Treated like any other outbound action — it sets `last_unanswered_communication` on flush, so a missed reply trips the response-timeout reconnect just as a self-Ping would.
-					self.outbox.extend(self.handler.active_ping());
-					self.arm_ping();
-					continue; // a ping is never content
-				}
-				Ok(Some(FuEvent::Read { reader, batch })) => {
-					if batch.is_empty() {
-						// EOF.
-						drop(reader);
-						if !content.is_empty() {
-							self.pending_reconnect = true;
-							return Ok(content);
-						}
-						tracing::warn!("tungstenite read EOF from the stream. Reconnecting.");
-						self.reconnect().await?;
-						continue;
-					}
-					self.arm_reader(reader); // re-arm the standing member NOW
-					self.last_unanswered_communication = None; // heard from the server
-
-					let mut terminal = false; // saw Close / a reconnecting error
-					for frame in batch {
-						let __pong_ack = || tracing::trace!("Received app-level pong (active-ping ack)");
-						match frame {
-							Ok(Message::Text(text)) => {
-								let value: serde_json::Value =
-									serde_json::from_str(&text).expect("API sent invalid JSON, which is completely unexpected. Disappointment is immeasurable and the day is ruined.");
-								// App-level heartbeat ack to our active-ping (Bybit et al. answer `{"op":"ping"}` with a text-frame `{"op":"pong"}` / `{"ret_msg":"pong"}`, NOT a protocol Pong).
-								// It carries no content and needs no reply — drop it before it reaches the handler, whose strict response parse doesn't model a pong.
-								if value["op"] == "pong" || value["ret_msg"] == "pong" {
-									__pong_ack();
-									continue;
-								}
-								tracing::trace!("{value:#?}");
-								match self.handler.handle_jrpc(value)? {
-									ResponseOrContent::Response(messages) => self.outbox.extend(messages),
-									ResponseOrContent::Content(c) => content.push(c),
-								}
-							}
-							// tungstenite already queued a Pong with this exact payload (auto-answered on read, flushed on the next read/write), — (satisfying even Binance's exact-echo rule).
-							// No need to pong ourselves or the server receives two Pongs per Ping.
-							Ok(Message::Ping(_)) => tracing::trace!("Received ping (tungstenite auto-pongs)"),
-							Ok(Message::Pong(_)) => __pong_ack(),
-							Ok(Message::Close(maybe_reason)) => {
-								match maybe_reason {
-									Some(close_frame) => tracing::info!("Server closed connection; reason: {close_frame:?}"),
-									None => tracing::info!("Server closed connection; no reason specified."),
-								}
-								terminal = true;
-								break;
-							}
-							Ok(Message::Binary(_)) => panic!("Received binary. But exchanges are not smart enough to send this, what is happening"),
-							Ok(Message::Frame(_)) => unreachable!("Can't get from reading"),
-							Err(e) if is_reconnecting(&e) => {
-								tracing::warn!("Reconnecting-class error mid-batch: {e:?}");
-								terminal = true;
-								break;
-							}
-							// Non-reconnecting class: `is_reconnecting` already panicked on Utf8 / unreachable on the write-only variant. The remainder (Capacity) is skippable.
-							Err(e) => {
-								debug_assert!(!is_reconnecting(&e));
-								tracing::warn!("Skipping non-fatal polling error: {e:?}");
-							}
-						}
-					}
-
-					if terminal {
-						// Reconnecting class: any queued writes target a soon-dead connection -> discard.
-						self.outbox.clear();
-						if !content.is_empty() {
-							self.pending_reconnect = true; // reconnect on the next call
-							return Ok(content); // content-before-Close returned first, never lost
-						}
-						self.reconnect().await?;
-						continue;
-					}
-					if !content.is_empty() {
-						return Ok(content);
-					}
-					continue; // only upkeep parsed -> keep awaiting the FU
-				}
-			}
-		}
-	}
-
-	/// Push the permanent standing reader future onto the FU.
-	fn arm_reader(&mut self, reader: WsRead) {
-		self.fu.push(Box::pin(read_future(reader)));
-	}
-
-	/// Push the standing active-ping timer onto the FU, IF one is configured. No-op when
-	/// `active_ping_freq` is `None` (no heartbeat needed — the reader stays the only standing member).
-	fn arm_ping(&mut self) {
-		if let Some(freq) = self.active_ping_freq {
-			self.fu.push(Box::pin(ping_future(freq)));
-		}
-	}
-
-	/// Launch a writer-future IF the sink is parked and there's queued work. Does nothing if a writer
-	/// is already in flight (sink absent) or the outbox is empty.
-	fn try_flush_outbox(&mut self) {
-		if self.sink.is_none() {
-			return; // a writer is already in flight
-		}
-		if self.outbox.is_empty() {
-			return;
-		}
-		let sink = self.sink.take().expect("guarded `is_none` above");
-		let msgs = std::mem::take(&mut self.outbox);
-		tracing::debug!("flushing to server: {msgs:#?}");
-		self.last_unanswered_communication = Some(SystemTime::now());
-		self.fu.push(Box::pin(write_future(sink, msgs)));
-	}
-
-	/// Pick the read timeout based on whether we're awaiting a forced response. `None` means a forced
-	/// response's window already elapsed before this call — the caller reconnects.
-	fn choose_timeout(&self) -> Option<Duration> {
-		match self.last_unanswered_communication {
-			Some(last_unanswered) => match last_unanswered + self.config.response_timeout > SystemTime::now() {
-				true => Some(self.config.response_timeout),
-				false => None,
-			},
-			None => Some(self.config.message_timeout),
-		}
-	}
-
-	async fn connect(&mut self) -> Result<(), WsError> {
-		tracing::info!("Connecting to {}...", self.url);
-
-		let (stream, http_resp) = match tokio_tungstenite::connect_async(self.url.as_str()).await {
-			Ok(result) => result,
-			Err(e) => {
-				let delay = self.backoff.next_duration();
-				if !delay.is_zero() {
-					tracing::warn!(delay_ms = delay.as_millis(), "Connection failed, backing off before retry.");
-					self.reconnect_after = Some(tokio::time::Instant::now() + delay);
-				}
-				return Err(e.into());
-			}
-		};
-		tracing::debug!("Ws handshake with server: {http_resp:#?}");
-
-		let (sink, reader) = stream.split();
-		self.fu = FuturesUnordered::new();
-		self.sink = Some(sink);
-		self.arm_reader(reader);
-		self.arm_ping(); // standing heartbeat timer (no-op if `active_ping_freq` is None)
-		self.outbox.clear();
-		self.last_unanswered_communication = None;
-		self.connected_since = Some(SystemTime::now());
-
-		// Auth/subscribe messages are *enqueued*, not inline-sent: the flush flies on the FU like any other write, concurrently with the standing read.
-		let auth_messages = self.handler.handle_auth()?;
-		self.outbox.extend(auth_messages);
-		self.try_flush_outbox();
-
-		self.reconnect_after = None;
-		self.backoff.reset();
-		Ok(())
-	}
-
-	/// Best-effort `Close` the existing connection (if its sink is parked), drop everything, and open
-	/// a new one. If a writer currently owns the sink, we skip the Close and just drop the FU — the
-	/// same best-effort contract as before.
-	///
-	/// `pub` for testing only, does not {have to || is expected to} be exposed in any wrappers.
-	pub async fn reconnect(&mut self) -> Result<(), WsError> {
-		// Clear any pending backoff — a server-initiated reconnect should be attempted immediately.
-		// If the new connection fails, `connect()` will set a fresh backoff.
-		self.reconnect_after = None;
-		if let Some(mut sink) = self.sink.take() {
-			tracing::info!("Dropping old connection before reconnecting...");
-			// Best-effort close - ignore errors since the connection may already be broken.
-			if let Err(e) = sink.send(Message::Close(None)).await {
-				tracing::debug!("Failed to send Close frame (connection likely already dead): {e}");
-			}
-		}
-		self.fu = FuturesUnordered::new(); // drops the reader/writer futures + the old split halves
-		self.connected_since = None;
-		self.connect().await
-	}
-}
-
 /// Configuration for [WsHandler].
 ///
 /// Should be returned by [WsHandler::ws_config()].
+///
+/// Every knob has a fallible `set_*` and a consuming `with_*` counterpart:
+/// ```
+/// # use std::time::Duration;
+/// # use v_exchanges_api_generics::ws::WsConfig;
+/// let config = WsConfig::default().with_message_timeout(Duration::from_secs(30)).with_auth(true).with_topics(["trade.BTCUSDT".to_owned()]);
+/// assert!(config.auth);
+/// assert!(config.topics.contains("trade.BTCUSDT"));
+///
+/// let mut config = config;
+/// assert!(config.set_refresh_after(Duration::ZERO).is_err());
+/// ```
 #[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "ws"), allow(dead_code))] // private knobs are only read by the connection
 pub struct WsConfig {
 	/// Whether the connection should be authenticated. Normally implemented through a "listen key"
 	pub auth: bool,
@@ -443,6 +59,8 @@ pub struct WsConfig {
 	pub base_url: Option<Url>,
 	/// Backoff configuration for reconnect attempts.
 	pub reconnect: RetryConfig,
+	/// Whether to reconnect at all once the connection drops.
+	pub reconnect_policy: ReconnectPolicy,
 	/// The [WebSocketConnection] will automatically reconnect when `refresh_after` has elapsed since the last connection started.
 	refresh_after: Duration,
 	/// A reconnection will be triggered if no messages are received within this amount of time.
@@ -458,140 +76,464 @@ pub struct WsConfig {
 	/// `Some(d)` == fire every `d` regardless of inbound traffic — required by exchanges like Bybit that
 	/// drop a connection unless the *client* sends an app-level `{"op":"ping"}` within a fixed window.
 	active_ping_freq: Option<Duration>,
+	/// Sliding window over which [WsConnection::latency_stats] are computed.
+	latency_window: Duration,
+	/// Log when p95 feed latency exceeds this. `None` (default) == don't watch.
+	stale_event_threshold: Option<Duration>,
+	/// Reconnect (in addition to logging) on crossing [stale_event_threshold](Self::stale_event_threshold).
+	reconnect_on_stale: bool,
+	/// At most `n` outbound messages per `Duration`, pings included. Sends over the budget are delayed, never dropped. `None` (default) == unthrottled.
+	outbound_rate: Option<(u32, Duration)>,
+	/// What happens to messages [sent](WsSender::send) while there's no live connection.
+	pub queued_during_reconnect: QueuedDuringReconnect,
+	/// Ids remembered by streams on top that skip items they've already yielded, as a reconnect replays the tail of a feed. Only read by those with exchange-assigned ids to key on; `None` == deliver everything.
+	pub dedup_window: Option<DedupWindow>,
 }
 impl WsConfig {
-	pub fn set_reconnect(&mut self, reconnect: RetryConfig) {
+	pub fn set_reconnect(&mut self, reconnect: RetryConfig) -> &mut Self {
 		self.reconnect = reconnect;
+		self
 	}
 
-	pub fn set_refresh_after(&mut self, refresh_after: Duration) -> Result<()> {
+	pub fn set_refresh_after(&mut self, refresh_after: Duration) -> Result<&mut Self> {
 		if refresh_after.is_zero() {
 			bail!("refresh_after must be greater than 0");
 		}
 		self.refresh_after = refresh_after;
-		Ok(())
+		Ok(self)
 	}
 
-	pub fn set_message_timeout(&mut self, message_timeout: Duration) -> Result<()> {
+	pub fn set_message_timeout(&mut self, message_timeout: Duration) -> Result<&mut Self> {
 		if message_timeout.is_zero() {
 			bail!("message_timeout must be greater than 0");
 		}
 		self.message_timeout = message_timeout;
-		Ok(())
+		Ok(self)
 	}
 
-	pub fn set_response_timout(&mut self, response_timeout: Duration) -> Result<()> {
+	pub fn set_response_timout(&mut self, response_timeout: Duration) -> Result<&mut Self> {
 		if response_timeout.is_zero() {
 			bail!("response_timeout must be greater than 0");
 		}
 		self.response_timeout = response_timeout;
-		Ok(())
+		Ok(self)
 	}
 
-	pub fn set_active_ping_freq(&mut self, active_ping_freq: Duration) -> Result<()> {
+	pub fn set_active_ping_freq(&mut self, active_ping_freq: Duration) -> Result<&mut Self> {
 		if active_ping_freq.is_zero() {
 			bail!("active_ping_freq must be greater than 0");
 		}
 		self.active_ping_freq = Some(active_ping_freq);
-		Ok(())
+		Ok(self)
+	}
+
+	pub fn set_latency_window(&mut self, latency_window: Duration) -> Result<&mut Self> {
+		if latency_window.is_zero() {
+			bail!("latency_window must be greater than 0");
+		}
+		self.latency_window = latency_window;
+		Ok(self)
+	}
+
+	pub fn set_stale_event_threshold(&mut self, threshold: Duration, reconnect: bool) -> Result<&mut Self> {
+		if threshold.is_zero() {
+			bail!("stale_event_threshold must be greater than 0");
+		}
+		self.stale_event_threshold = Some(threshold);
+		self.reconnect_on_stale = reconnect;
+		Ok(self)
+	}
+
+	/// `None` lifts the limit.
+	pub fn set_outbound_rate(&mut self, outbound_rate: Option<(u32, Duration)>) -> Result<&mut Self> {
+		if let Some((n, per)) = outbound_rate
+			&& (n == 0 || per.is_zero())
+		{
+			bail!("outbound_rate must allow at least one message over a non-zero duration");
+		}
+		self.outbound_rate = outbound_rate;
+		Ok(self)
+	}
+
+	/// Checks the knobs against each other; the setters only check them one at a time. Reports every failure, not just the first.
+	pub fn validate(&self) -> Result<(), Vec<WsConfigError>> {
+		let mut errors = Vec::new();
+		if self.message_timeout < self.response_timeout {
+			errors.push(WsConfigError::MessageTimeoutLessThanResponseTimeout {
+				message_timeout: self.message_timeout,
+				response_timeout: self.response_timeout,
+			});
+		}
+		let reconnect_cooldown = Duration::from_millis(self.reconnect.initial_delay_ms);
+		if reconnect_cooldown.is_zero() {
+			errors.push(WsConfigError::ZeroReconnectCooldown);
+		} else if self.refresh_after < reconnect_cooldown {
+			errors.push(WsConfigError::RefreshAfterLessThanReconnectCooldown {
+				refresh_after: self.refresh_after,
+				reconnect_cooldown,
+			});
+		}
+		match errors.is_empty() {
+			true => Ok(()),
+			false => Err(errors),
+		}
+	}
+
+	/// Slower to give up on a quiet connection and slower to reconnect than [default](Self::default). For consumers that would rather sit out a hiccup than churn connections over it.
+	pub fn safe_defaults() -> Self {
+		Self {
+			reconnect: RetryConfig {
+				initial_delay_ms: 5_000,
+				max_delay_ms: 120_000,
+				jitter_ms: 2_000,
+				..Self::default().reconnect
+			},
+			message_timeout: Duration::from_secs(90),
+			response_timeout: Duration::from_secs(30),
+			..Self::default()
+		}
+	}
+
+	// builder {{{
+	// Consuming counterparts of the setters above, for configs spelled out inline:
+	// `WsConfig::default().with_message_timeout(Duration::from_secs(30)).with_auth(true)`.
+	// Each panics where its `set_*` would error, as arguments here are expected to be literals.
+
+	pub fn with_auth(mut self, auth: bool) -> Self {
+		self.auth = auth;
+		self
+	}
+
+	pub fn with_base_url(mut self, base_url: Url) -> Self {
+		self.base_url = Some(base_url);
+		self
+	}
+
+	/// Adds to the already present [topics](Self::topics).
+	pub fn with_topics(mut self, topics: impl IntoIterator<Item = String>) -> Self {
+		self.topics.extend(topics);
+		self
+	}
+
+	pub fn with_reconnect(mut self, reconnect: RetryConfig) -> Self {
+		self.set_reconnect(reconnect);
+		self
+	}
+
+	pub fn with_reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+		self.reconnect_policy = reconnect_policy;
+		self
+	}
+
+	pub fn with_refresh_after(mut self, refresh_after: Duration) -> Self {
+		self.set_refresh_after(refresh_after).unwrap_or_else(|e| panic!("WsConfig::with_refresh_after: {e}"));
+		self
+	}
+
+	pub fn with_message_timeout(mut self, message_timeout: Duration) -> Self {
+		self.set_message_timeout(message_timeout).unwrap_or_else(|e| panic!("WsConfig::with_message_timeout: {e}"));
+		self
+	}
+
+	pub fn with_response_timeout(mut self, response_timeout: Duration) -> Self {
+		self.set_response_timout(response_timeout).unwrap_or_else(|e| panic!("WsConfig::with_response_timeout: {e}"));
+		self
+	}
+
+	pub fn with_active_ping_freq(mut self, active_ping_freq: Duration) -> Self {
+		self.set_active_ping_freq(active_ping_freq).unwrap_or_else(|e| panic!("WsConfig::with_active_ping_freq: {e}"));
+		self
+	}
+
+	pub fn with_latency_window(mut self, latency_window: Duration) -> Self {
+		self.set_latency_window(latency_window).unwrap_or_else(|e| panic!("WsConfig::with_latency_window: {e}"));
+		self
+	}
+
+	pub fn with_stale_event_threshold(mut self, threshold: Duration, reconnect: bool) -> Self {
+		self.set_stale_event_threshold(threshold, reconnect).unwrap_or_else(|e| panic!("WsConfig::with_stale_event_threshold: {e}"));
+		self
+	}
+
+	pub fn with_outbound_rate(mut self, outbound_rate: Option<(u32, Duration)>) -> Self {
+		self.set_outbound_rate(outbound_rate).unwrap_or_else(|e| panic!("WsConfig::with_outbound_rate: {e}"));
+		self
+	}
+
+	pub fn with_queued_during_reconnect(mut self, queued_during_reconnect: QueuedDuringReconnect) -> Self {
+		self.queued_during_reconnect = queued_during_reconnect;
+		self
+	}
+
+	pub fn with_dedup_window(mut self, dedup_window: Option<DedupWindow>) -> Self {
+		self.dedup_window = dedup_window;
+		self
+	}
+	//,}}}
+}
+
+/// What [WsConnection] does when the connection drops (server Close, EOF, transport error, unanswered ping, [stale feed](WsConfig::stale_event_threshold)).
+///
+/// Scheduled [refreshes](WsConfig::refresh_after) reconnect too, so they go by the same policy: under [Never](Self::Never) the connection ends once it's due for one, and each counts towards [UpTo](Self::UpTo).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ReconnectPolicy {
+	/// Reconnect transparently, backing off per [WsConfig::reconnect].
+	#[default]
+	Always,
+	/// Surface the drop as a terminal [WsError::ConnectionLost]. For single-shot jobs, where a silent retry loop is worse than an error.
+	Never,
+	/// Reconnect at most this many times over the connection's lifetime, then behave as [Never](Self::Never).
+	UpTo(u32),
+}
+
+/// Fate of messages [sent](WsSender::send) while a [WsConnection] is between connections.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum QueuedDuringReconnect {
+	/// Send them once the new connection is up, right after its auth and subscriptions.
+	#[default]
+	Flush,
+	/// Drop them with a warning. For messages that are only meaningful on the connection they were meant for.
+	Drop,
+}
+
+/// How much [WsConfig::dedup_window] remembers: the last `max_ids` ids, none of them seen longer than `max_age` ago.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DedupWindow {
+	pub max_ids: usize,
+	pub max_age: Duration,
+}
+impl Default for DedupWindow {
+	/// Well past what a reconnect replays, even on a busy pair.
+	fn default() -> Self {
+		Self {
+			max_ids: 4096,
+			max_age: Duration::from_secs(60),
+		}
 	}
 }
 
-#[derive(Debug, miette::Diagnostic, derive_more::Display, thiserror::Error, derive_more::From)]
+/// Percentiles of `local_receive_time - exchange_event_time` (clock-offset compensated). Can be negative on residual skew.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LatencyStats {
+	pub p50: SignedDuration,
+	pub p95: SignedDuration,
+	pub p99: SignedDuration,
+	pub max: SignedDuration,
+	pub samples: usize,
+}
+
+/// Everything that can go wrong with a websocket stream.
+///
+/// Wrapped errors are reachable through [source()](std::error::Error::source). For coarse handling, match on [kind()](Self::kind) and check [is_terminal()](Self::is_terminal):
+/// ```
+/// # use v_exchanges_api_generics::ws::{SubscriptionRejectReason, WsError, WsErrorKind};
+/// fn on_stream_error(e: &WsError) -> &'static str {
+/// 	match e.kind() {
+/// 		WsErrorKind::Auth | WsErrorKind::SessionExpired => "re-authenticate and open a new stream",
+/// 		WsErrorKind::Subscription if e.is_terminal() => "drop the topic",
+/// 		_ if e.is_terminal() => "give up",
+/// 		_ => "keep polling",
+/// 	}
+/// }
+///
+/// let rejected = WsError::Subscription {
+/// 	topic: "orderbook.50.NOTACOIN".into(),
+/// 	reason: SubscriptionRejectReason::from_message("error:handler not found"),
+/// };
+/// assert_eq!(on_stream_error(&rejected), "drop the topic");
+/// let parse = WsError::Parse(serde_json::from_str::<u8>("{").unwrap_err());
+/// assert_eq!(on_stream_error(&parse), "keep polling");
+/// assert!(std::error::Error::source(&parse).is_some());
+/// ```
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_more::From)]
+#[non_exhaustive]
 pub enum WsError {
+	#[error("Invalid websocket definition")]
 	#[diagnostic(transparent)]
-	Definition(WsDefinitionError),
+	Definition(#[source] WsDefinitionError),
+	#[cfg(feature = "ws")]
+	#[error("Websocket transport error")]
 	#[diagnostic(code(v_exchanges::ws::tungstenite), help("WebSocket protocol error. The connection may need to be reestablished."))]
-	Tungstenite(tungstenite::Error),
+	Tungstenite(#[source] tungstenite::Error),
+	#[error("Failed to authenticate the websocket connection")]
 	#[diagnostic(transparent)]
-	Auth(ConstructAuthError),
+	Auth(#[source] ConstructAuthError),
+	#[error("Failed to parse a websocket message")]
 	#[diagnostic(code(v_exchanges::ws::parse), help("Failed to parse WebSocket message. Check if the exchange API has changed."))]
-	Parse(serde_json::Error),
+	Parse(#[source] serde_json::Error),
+	#[error("Exchange rejected subscription to `{topic}`: {reason}")]
 	#[diagnostic(code(v_exchanges::ws::subscription))]
-	Subscription(String),
+	#[from(skip)]
+	Subscription { topic: String, reason: SubscriptionRejectReason },
+	/// Whatever the connection was opened with (listen key, connect token, ..) is no longer accepted. Reconnecting with it won't help; open a new stream.
+	#[error("Websocket session expired: {0}")]
+	#[diagnostic(code(v_exchanges::ws::session_expired), help("Open a new stream, which fetches fresh session credentials."))]
+	#[from(skip)]
+	SessionExpired(String),
+	/// The connection dropped, and [ReconnectPolicy] allowed no further reconnects.
+	#[cfg(feature = "ws")]
+	#[error("Websocket connection lost, and `ReconnectPolicy` allows no further reconnects")]
+	#[diagnostic(code(v_exchanges::ws::connection_lost), help("Open a new stream, or allow reconnects through `WsConfig::reconnect_policy`."))]
+	#[from(skip)]
+	ConnectionLost(#[source] DisconnectCause),
+	/// Sending onto a [WsConnection] that was dropped or [lost](Self::ConnectionLost) for good.
+	#[cfg(feature = "ws")]
+	#[error("Websocket connection is closed")]
+	#[diagnostic(code(v_exchanges::ws::closed), help("Open a new stream to send on."))]
+	Closed,
+	#[error("Network connection failed")]
 	#[diagnostic(code(v_exchanges::ws::network), help("Network connection failed. Check your internet connection."))]
 	NetworkConnection,
+	#[error("Invalid websocket URL")]
 	#[diagnostic(transparent)]
-	Url(UrlError),
+	Url(#[source] UrlError),
+	#[error("Unexpected websocket event: {0}")]
 	#[diagnostic(code(v_exchanges::ws::unexpected_event), help("Received an unexpected event from the WebSocket. This may indicate an API change."))]
 	UnexpectedEvent(serde_json::Value),
 	#[error(transparent)]
 	Other(eyre::Report),
 }
-#[derive(Debug, miette::Diagnostic, derive_more::Display, thiserror::Error)]
-pub enum WsDefinitionError {
-	#[diagnostic(code(v_exchanges::ws::definition::missing_url), help("WebSocket base URL must be configured in WsConfig."))]
-	MissingUrl,
-}
-#[derive(Clone, Debug, derive_more::Display, Eq, Hash, PartialEq, serde::Serialize)]
-pub enum Topic {
-	String(String),
-	Order(serde_json::Value),
-}
-/// Homogeneous [FuturesUnordered] members for [WsConnection]; both own their resources, so each
-/// future is `'static` and holds no `&self` borrow — letting it live on the struct across `next()`
-/// calls (which is what makes the draining `next()` cancel-safe).
-enum FuEvent {
-	/// The permanent standing reader: blocks for the first frame, then drains all immediately-
-	/// available ones. `batch` empty == EOF sentinel.
-	Read { reader: WsRead, batch: Vec<Result<Message, tungstenite::Error>> },
-	/// A transient writer (0..=1 in flight): owned the sink + queued messages, sent them, hands the
-	/// sink back via this event.
-	Write { sink: WsSink, result: Result<(), tungstenite::Error> },
-	/// The (optional) standing active-ping timer: sleeps `active_ping_freq`, then fires. Re-armed on
-	/// every fire, so it ticks for the whole connection lifetime. Carries no resources — the handler
-	/// supplies the actual ping payload when it fires.
-	PingDue,
+impl From<url::ParseError> for WsError {
+	fn from(e: url::ParseError) -> Self {
+		Self::Url(e.into())
+	}
 }
+impl WsError {
+	pub fn kind(&self) -> WsErrorKind {
+		match self {
+			Self::Definition(_) => WsErrorKind::Definition,
+			#[cfg(feature = "ws")]
+			Self::Tungstenite(_) => WsErrorKind::Transport,
+			#[cfg(feature = "ws")]
+			Self::ConnectionLost(_) | Self::Closed => WsErrorKind::Transport,
+			Self::NetworkConnection => WsErrorKind::Transport,
+			Self::Auth(_) => WsErrorKind::Auth,
+			Self::Parse(_) | Self::UnexpectedEvent(_) => WsErrorKind::Parse,
+			Self::Subscription { .. } => WsErrorKind::Subscription,
+			Self::SessionExpired(_) => WsErrorKind::SessionExpired,
+			Self::Url(_) => WsErrorKind::Definition,
+			Self::Other(_) => WsErrorKind::Other,
+		}
+	}
 
-/// Block for the first frame, then drain everything already buffered without blocking.
-///
-/// `now_or_never` polls the RAW reader once with a no-op waker: `Some(frame)` -> keep & continue,
-/// `None` -> nothing immediately ready -> stop. Safe because a raw `reader.next()` is cancel-safe at
-/// the frame boundary (a `Pending` poll consumes no frame, loses nothing). This is NOT a kernel
-/// "read all" call (tungstenite / `futures::Stream` expose no such API and one can't exist cleanly);
-/// it drains what's cheaply available — tungstenite's parsed `in_buffer` plus whatever one chunk-read
-/// pulled (~1 syscall for many frames). Any straggler still in the kernel simply becomes the first
-/// frame of the next `next()` call, which is what we want (don't block to chase stragglers).
-async fn read_future(mut reader: WsRead) -> FuEvent {
-	let mut batch = Vec::new();
-	if let Some(first) = reader.next().await {
-		batch.push(first);
-		while let Some(Some(m)) = reader.next().now_or_never() {
-			batch.push(m);
+	/// Whether polling the same stream again can't help. Transient failures (network, a single bad message, rate limits) are not terminal: the connection reconnects on its own.
+	pub fn is_terminal(&self) -> bool {
+		match self {
+			Self::Definition(_) | Self::Url(_) | Self::Auth(_) | Self::SessionExpired(_) => true,
+			Self::Subscription { reason, .. } => reason.is_terminal(),
+			#[cfg(feature = "ws")]
+			Self::Tungstenite(e) => match e {
+				tungstenite::Error::Url(_) => true,
+				tungstenite::Error::Http(response) => response.status().is_client_error() && response.status().as_u16() != 429,
+				_ => false,
+			},
+			#[cfg(feature = "ws")]
+			Self::ConnectionLost(_) | Self::Closed => true,
+			Self::NetworkConnection | Self::Parse(_) | Self::UnexpectedEvent(_) | Self::Other(_) => false,
 		}
-	} // else: empty batch == EOF sentinel
-	FuEvent::Read { reader, batch }
+	}
 }
 
-/// Send every queued message, then hand the sink back via the returned event.
-async fn write_future(mut sink: WsSink, msgs: Vec<Message>) -> FuEvent {
-	let mut s = futures_util::stream::iter(msgs).map(Ok);
-	let result = sink.send_all(&mut s).await;
-	FuEvent::Write { sink, result }
+/// Coarse classification of a [WsError], for `match`ing without caring about payloads.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum WsErrorKind {
+	/// Bad configuration or URL; fix the code.
+	Definition,
+	/// Network or protocol level failure.
+	Transport,
+	/// Credentials were rejected or missing.
+	Auth,
+	/// A message didn't look like what the exchange is documented to send.
+	Parse,
+	Subscription,
+	SessionExpired,
+	Other,
 }
 
-/// Sleep one active-ping interval, then fire. Owns no resources — re-armed by the caller on each fire.
-async fn ping_future(freq: Duration) -> FuEvent {
-	tokio::time::sleep(freq).await;
-	FuEvent::PingDue
+/// Why an exchange refused a subscription. Classified from its free-form message, see [from_message](Self::from_message).
+#[derive(Clone, Debug, derive_more::Display, Eq, PartialEq)]
+pub enum SubscriptionRejectReason {
+	/// No such topic, or it names an unlisted symbol.
+	#[display("unknown topic")]
+	UnknownTopic,
+	/// Topic is private and the connection is not authenticated for it.
+	#[display("not authorized")]
+	Unauthorized,
+	/// Too many topics or subscription requests.
+	#[display("limit exceeded")]
+	Limit,
+	/// Anything else, with the exchange's message.
+	#[display("{_0}")]
+	Other(String),
 }
+impl SubscriptionRejectReason {
+	pub fn from_message(msg: &str) -> Self {
+		let lower = msg.to_lowercase();
+		let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+		if has(&["not authorized", "unauthorized", "permission", "login", "auth"]) {
+			Self::Unauthorized
+		} else if has(&["too many", "limit", "exceed"]) {
+			Self::Limit
+		} else if has(&["not found", "not exist", "invalid topic", "unknown topic", "unsupported topic", "invalid symbol"]) {
+			Self::UnknownTopic
+		} else {
+			Self::Other(msg.to_owned())
+		}
+	}
 
-/// Whether a polling error from tungstenite warrants tearing down & reconnecting.
-///
-/// Folds the per-variant arms of the old single-frame `next()` match: the connection-fatal classes
-/// reconnect; `Capacity` is skippable (false); `Utf8` panics at the call site; the TLS/URL/HTTP
-/// classes are still `todo!()` as before; the write-only variants are unreachable on a read.
-fn is_reconnecting(err: &tungstenite::Error) -> bool {
-	match err {
-		tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed | tungstenite::Error::Io(_) | tungstenite::Error::Protocol(_) | tungstenite::Error::AttackAttempt => true,
-		tungstenite::Error::Capacity(_) => false,
-		tungstenite::Error::Utf8(e) => panic!("received `tungstenite::Error::Utf8` from polling: {e:?}. Exchange is going crazy, aborting"),
-		tungstenite::Error::WriteBufferFull(_) => unreachable!("can only get from writing"),
-		tungstenite::Error::Tls(_) | tungstenite::Error::Url(_) | tungstenite::Error::Http(_) | tungstenite::Error::HttpFormat(_) => todo!(),
+	/// Resubscribing to the same topic on the same connection will be rejected again.
+	pub fn is_terminal(&self) -> bool {
+		matches!(self, Self::UnknownTopic | Self::Unauthorized)
 	}
 }
 
+/// Where a topic's subscription stands on the current connection. See [WsConnection::subscription_status].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SubscriptionStatus {
+	/// Requested, or about to be on (re)connect, and not answered yet.
+	Pending,
+	Active,
+	/// Refused, with the exchange's message.
+	Failed(String),
+}
+
+#[derive(Debug, miette::Diagnostic, thiserror::Error)]
+pub enum WsDefinitionError {
+	#[error("WebSocket base URL is not set")]
+	#[diagnostic(code(v_exchanges::ws::definition::missing_url), help("WebSocket base URL must be configured in WsConfig."))]
+	MissingUrl,
+	#[error("Invalid reconnect backoff configuration: {0}")]
+	#[diagnostic(code(v_exchanges::ws::definition::invalid_reconnect), help("Check `WsConfig::reconnect`."))]
+	InvalidReconnect(String),
+	#[error("Subscription snapshot of {expected} can't be resumed on {got}")]
+	#[diagnostic(code(v_exchanges::ws::definition::snapshot_mismatch), help("Snapshots resume onto the endpoint they were taken on only."))]
+	SnapshotMismatch { expected: String, got: String },
+	#[error("This handler doesn't take topics after construction, so can't resume subscription snapshots")]
+	#[diagnostic(code(v_exchanges::ws::definition::resume_unsupported))]
+	ResumeUnsupported,
+	#[error("Contradictory WsConfig: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+	#[diagnostic(code(v_exchanges::ws::definition::invalid_config))]
+	InvalidConfig(#[related] Vec<WsConfigError>),
+}
+/// A [WsConfig] whose knobs contradict each other, see [WsConfig::validate].
+#[derive(Clone, Debug, Eq, miette::Diagnostic, PartialEq, thiserror::Error)]
+pub enum WsConfigError {
+	#[error("message_timeout ({message_timeout:?}) is shorter than response_timeout ({response_timeout:?})")]
+	#[diagnostic(code(v_exchanges::ws::config::message_timeout), help("A quiet connection is probed with a ping, which gets `response_timeout` to be answered; `message_timeout` should be the longer of the two."))]
+	MessageTimeoutLessThanResponseTimeout { message_timeout: Duration, response_timeout: Duration },
+	#[error("refresh_after ({refresh_after:?}) is shorter than the reconnect cooldown ({reconnect_cooldown:?})")]
+	#[diagnostic(code(v_exchanges::ws::config::refresh_after), help("The connection would be due for a refresh before a reconnect is even attempted."))]
+	RefreshAfterLessThanReconnectCooldown { refresh_after: Duration, reconnect_cooldown: Duration },
+	#[error("reconnect cooldown (`reconnect.initial_delay_ms`) is zero")]
+	#[diagnostic(code(v_exchanges::ws::config::zero_reconnect_cooldown), help("Every backoff step multiplies the initial delay, so reconnects would be attempted in a hot loop."))]
+	ZeroReconnectCooldown,
+}
+#[derive(Clone, Debug, derive_more::Display, Eq, Hash, PartialEq, serde::Serialize)]
+pub enum Topic {
+	String(String),
+	Order(serde_json::Value),
+}
 impl<T> std::hash::Hash for TopicInterpreter<T> {
 	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
 		self.event_name.hash(state);
@@ -603,23 +545,6 @@ impl<T> PartialEq for TopicInterpreter<T> {
 	}
 }
 
-impl<H: WsHandler> std::fmt::Debug for WsConnection<H> {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		f.debug_struct("WsConnection")
-			.field("url", &self.url)
-			.field("config", &self.config)
-			.field("handler", &self.handler)
-			.field("backoff", &self.backoff)
-			.field("reconnect_after", &self.reconnect_after)
-			.field("connected_since", &self.connected_since)
-			.field("last_unanswered_communication", &self.last_unanswered_communication)
-			.field("pending_reconnect", &self.pending_reconnect)
-			.field("outbox_len", &self.outbox.len())
-			.field("active_ping_freq", &self.active_ping_freq)
-			.finish_non_exhaustive()
-	}
-}
-
 impl Default for WsConfig {
 	fn default() -> Self {
 		Self {
@@ -634,12 +559,19 @@ impl Default for WsConfig {
 				immediate_first: false,
 				max_elapsed_ms: None,
 			},
+			reconnect_policy: ReconnectPolicy::default(),
 			refresh_after: Duration::from_hours(12),
 			// A dead-but-open socket is caught in `message_timeout + response_timeout`. These stay short because the probe is a protocol Ping/Pong liveness check (see `next`), not a data-rate assumption: a healthy quiet connection just answers the Ping, so it never false-reconnects.
 			message_timeout: Duration::from_secs(32),
 			response_timeout: Duration::from_secs(8),
 			topics: AHashSet::new(),
 			active_ping_freq: None,
+			latency_window: Duration::from_secs(60),
+			stale_event_threshold: None,
+			reconnect_on_stale: false,
+			outbound_rate: None,
+			queued_during_reconnect: QueuedDuringReconnect::default(),
+			dedup_window: Some(DedupWindow::default()),
 		}
 	}
 }
@@ -657,240 +589,3 @@ impl Default for WsConfig {
 //	topic: Topic,
 //	base_url: Url,
 //}
-
-#[cfg(test)]
-mod tests {
-	use futures_util::SinkExt as _;
-	use tokio::net::TcpListener;
-	use tokio_tungstenite::accept_async;
-
-	use super::*;
-
-	/// Trivial handler for the in-process server: every text frame is content (`{"n": <i>}`), nothing
-	/// is ever replied. Short timeouts keep the hermetic tests sub-second.
-	#[derive(Debug)]
-	struct EchoHandler;
-	impl WsHandler for EchoHandler {
-		fn config(&self) -> Result<WsConfig, UrlError> {
-			let mut c = WsConfig::default();
-			// Short timeouts keep the hermetic tests sub-second. `.expect`: literals are non-zero.
-			c.set_message_timeout(Duration::from_millis(200)).expect("non-zero literal");
-			c.set_response_timout(Duration::from_millis(200)).expect("non-zero literal");
-			Ok(c)
-		}
-
-		fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
-			Ok(vec![])
-		}
-
-		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
-			Ok(ResponseOrContent::Content(ContentEvent {
-				data: jrpc.clone(),
-				topic: "test".to_owned(),
-				time: Timestamp::UNIX_EPOCH,
-				event_type: "test".to_owned(),
-			}))
-		}
-	}
-
-	/// Like [EchoHandler] but with a fast active-ping configured, emitting Bybit-style `{"op":"ping"}`.
-	/// Used to exercise the standing `PingDue` timer and the upstream pong-ack drop.
-	#[derive(Debug)]
-	struct PingHandler;
-	impl WsHandler for PingHandler {
-		fn config(&self) -> Result<WsConfig, UrlError> {
-			let mut c = WsConfig::default();
-			// Fire the active-ping fast; keep read timeouts long so the test ends by our own logic, not a
-			// message_timeout. `.expect`: literals are non-zero.
-			c.set_active_ping_freq(Duration::from_millis(80)).expect("non-zero literal");
-			c.set_message_timeout(Duration::from_secs(5)).expect("non-zero literal");
-			c.set_response_timout(Duration::from_secs(5)).expect("non-zero literal");
-			Ok(c)
-		}
-
-		fn active_ping(&self) -> Vec<Message> {
-			vec![Message::Text("{\"op\":\"ping\"}".into())]
-		}
-
-		// Would wrongly surface ANY text as content — so a pong-ack reaching here fails the drop test.
-		fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
-			Ok(vec![])
-		}
-
-		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
-			Ok(ResponseOrContent::Content(ContentEvent {
-				data: jrpc,
-				topic: "test".to_owned(),
-				time: Timestamp::UNIX_EPOCH,
-				event_type: "test".to_owned(),
-			}))
-		}
-	}
-
-	/// Bind an ephemeral loopback port, returning `(listener, "ws://127.0.0.1:<port>")`.
-	async fn bind() -> (TcpListener, String) {
-		let listener = TcpListener::bind("127.0.0.1:0").await.expect("loopback bind");
-		let url = format!("ws://{}", listener.local_addr().unwrap());
-		(listener, url)
-	}
-
-	/// Drain: the server writes N text frames back-to-back (one buffered flush so they land together
-	/// over loopback), then goes quiet → a single `next()` must return all N (whole-buffer drain in one
-	/// call, not one-per-call).
-	#[tokio::test]
-	async fn drains_whole_buffer_in_one_call() {
-		const N: usize = 8;
-		let (listener, url) = bind().await;
-
-		let server = async move {
-			let (tcp, _) = listener.accept().await.expect("accept");
-			let mut ws = accept_async(tcp).await.expect("handshake");
-			// `feed` all then a single `flush`: coalesces into one write so the frames arrive as one
-			// readable chunk and the client's `now_or_never` drain sees them all.
-			for i in 0..N {
-				ws.feed(Message::Text(format!("{{\"n\":{i}}}").into())).await.expect("feed");
-			}
-			ws.flush().await.expect("flush");
-			// Stay quiet but keep the connection open so the client doesn't see EOF.
-			tokio::time::sleep(Duration::from_secs(3)).await;
-			drop(ws);
-		};
-		let handle = tokio::spawn(server);
-
-		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
-		let batch = conn.next().await.expect("next");
-		assert_eq!(batch.len(), N, "expected all {N} buffered frames drained in a single next()");
-		handle.abort();
-	}
-
-	/// Side-effects mid-drain (constraint: never drop ping handling): server sends `[Ping, Text, Text]`
-	/// → the call returns 2 content events AND the server observes exactly one Pong.
-	///
-	/// The Pong is tungstenite's automatic reply to the inbound Ping (carrying the exact payload),
-	/// flushed when our reader is next polled — NOT a manual pong (manual would duplicate it).
-	#[tokio::test]
-	async fn pongs_once_and_returns_content_from_same_batch() {
-		let (listener, url) = bind().await;
-
-		let server = async move {
-			let (tcp, _) = listener.accept().await.expect("accept");
-			let mut ws = accept_async(tcp).await.expect("handshake");
-			ws.feed(Message::Ping(Bytes::from_static(b"hi"))).await.expect("feed ping");
-			ws.feed(Message::Text("{\"n\":1}".into())).await.expect("feed t1");
-			ws.feed(Message::Text("{\"n\":2}".into())).await.expect("feed t2");
-			ws.flush().await.expect("flush");
-			// Count every Pong the client sends until it disconnects (we drop it client-side below).
-			let mut pongs = 0usize;
-			while let Some(msg) = ws.next().await {
-				match msg {
-					Ok(Message::Pong(_)) => pongs += 1,
-					Ok(Message::Close(_)) | Err(_) => break,
-					_ => {}
-				}
-			}
-			pongs
-		};
-		let handle = tokio::spawn(server);
-
-		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
-		let batch = conn.next().await.expect("next");
-		assert_eq!(batch.len(), 2, "two text frames in the batch become two content events");
-
-		// Drive one more bounded `next()`: it re-polls the (re-armed) reader, which flushes the queued
-		// auto-pong, then blocks on the now-quiet socket and times out. We only care that the Pong went
-		// out exactly once. Then drop the client so the server's read loop ends and yields its count.
-		// Bounded drive purely for its side-effect (flush the auto-pong); the timeout-elapsed result
-		// is expected and discarded.
-		let _drove_flush = tokio::time::timeout(Duration::from_millis(150), conn.next()).await;
-		drop(conn);
-		let pongs = tokio::time::timeout(Duration::from_secs(1), handle).await.expect("server join timeout").expect("server task");
-		assert_eq!(pongs, 1, "exactly one Pong (tungstenite's auto-reply) for the single Ping");
-	}
-
-	/// Terminal: server sends `[Text, Close]` → `next()` returns `Ok(vec![one])` (content before Close
-	/// kept, not lost, not errored). The next call must reconnect (observed as a second accept).
-	#[tokio::test]
-	async fn returns_content_before_close_then_reconnects() {
-		let (listener, url) = bind().await;
-
-		let server = async move {
-			// First connection: one text then a Close.
-			let (tcp, _) = listener.accept().await.expect("accept 1");
-			let mut ws = accept_async(tcp).await.expect("handshake 1");
-			ws.feed(Message::Text("{\"n\":1}".into())).await.expect("feed");
-			ws.feed(Message::Close(None)).await.expect("feed close");
-			ws.flush().await.expect("flush");
-			drop(ws);
-
-			// Second connection proves the client reconnected after the Close.
-			let (tcp2, _) = listener.accept().await.expect("accept 2 (reconnect)");
-			let mut ws2 = accept_async(tcp2).await.expect("handshake 2");
-			ws2.feed(Message::Text("{\"n\":2}".into())).await.expect("feed 2");
-			ws2.flush().await.expect("flush 2");
-			tokio::time::sleep(Duration::from_secs(2)).await;
-		};
-		let handle = tokio::spawn(server);
-
-		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
-		let first = conn.next().await.expect("next 1");
-		assert_eq!(first.len(), 1, "content collected before Close is returned, not dropped");
-
-		// Next call reconnects (pending_reconnect was set) and yields the second connection's frame.
-		let second = conn.next().await.expect("next 2 after reconnect");
-		assert_eq!(second.len(), 1, "after reconnect we receive the new connection's frame");
-		handle.abort();
-	}
-
-	/// Active-ping: with `active_ping_freq` set, the client must proactively send the handler's
-	/// `{"op":"ping"}` payload on a quiet connection (no inbound traffic), within the configured window.
-	#[tokio::test]
-	async fn sends_active_ping_on_quiet_connection() {
-		let (listener, url) = bind().await;
-
-		let server = async move {
-			let (tcp, _) = listener.accept().await.expect("accept");
-			let mut ws = accept_async(tcp).await.expect("handshake");
-			// Stay silent; just wait for the client's first app-level ping text frame.
-			loop {
-				match ws.next().await {
-					Some(Ok(Message::Text(t))) if t.contains("\"op\":\"ping\"") => return true,
-					Some(Ok(_)) => continue, // ignore protocol pings/pongs etc.
-					_ => return false,       // closed/errored before any app ping
-				}
-			}
-		};
-		let handle = tokio::spawn(server);
-
-		let mut conn = WsConnection::try_new(&url, PingHandler).expect("try_new");
-		// Drive `next()` long enough for the 80ms ping timer to fire and flush; it returns no content
-		// (only the ping flies), so the timeout-elapsed result is expected and discarded.
-		let _drove_ping = tokio::time::timeout(Duration::from_millis(400), conn.next()).await;
-		let saw_ping = tokio::time::timeout(Duration::from_secs(1), handle).await.expect("server join timeout").expect("server task");
-		assert!(saw_ping, "client must send an app-level `{{\"op\":\"ping\"}}` on a quiet connection");
-	}
-
-	/// Pong-ack drop: a text-frame `{"op":"pong"}` (the heartbeat reply some exchanges send instead of a
-	/// protocol Pong) must be swallowed upstream — never surfaced as content, even though `PingHandler`
-	/// would wrongly turn any text into content if it ever reached `handle_jrpc`.
-	#[tokio::test]
-	async fn drops_app_level_pong_ack() {
-		let (listener, url) = bind().await;
-
-		let server = async move {
-			let (tcp, _) = listener.accept().await.expect("accept");
-			let mut ws = accept_async(tcp).await.expect("handshake");
-			// A pong-ack, then a real content frame. Only the latter may surface to the caller.
-			ws.feed(Message::Text("{\"op\":\"pong\"}".into())).await.expect("feed pong");
-			ws.feed(Message::Text("{\"n\":1}".into())).await.expect("feed content");
-			ws.flush().await.expect("flush");
-			tokio::time::sleep(Duration::from_secs(2)).await;
-		};
-		let handle = tokio::spawn(server);
-
-		let mut conn = WsConnection::try_new(&url, PingHandler).expect("try_new");
-		let batch = conn.next().await.expect("next");
-		assert_eq!(batch.len(), 1, "pong-ack dropped upstream; only the real content frame surfaces");
-		assert_eq!(batch[0].data["n"], 1, "the surviving event is the content frame, not the pong");
-		handle.abort();
-	}
-}
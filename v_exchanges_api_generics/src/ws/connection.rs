@@ -0,0 +1,1709 @@
+use std::{
+	collections::{HashMap, VecDeque},
+	future::Future,
+	pin::Pin,
+	sync::{
+		Arc,
+		atomic::{AtomicU8, AtomicU64, Ordering},
+	},
+	time::{Duration, SystemTime},
+	vec,
+};
+
+use ahash::AHashSet;
+use eyre::Result;
+use futures_util::{
+	FutureExt as _, SinkExt as _, StreamExt as _,
+	stream::{FuturesUnordered, SplitSink, SplitStream},
+};
+use jiff::{SignedDuration, Timestamp};
+use reqwest::Url;
+use tokio::{
+	net::TcpStream,
+	sync::{mpsc, oneshot},
+	task::JoinHandle,
+};
+use tracing::Instrument as _;
+use tokio_tungstenite::{
+	MaybeTlsStream, WebSocketStream,
+	tungstenite::{self, Bytes, Message},
+};
+
+use super::{ContentEvent, LatencyStats, QueuedDuringReconnect, ReconnectPolicy, SubscriptionStatus, Topic, WsConfig, WsDefinitionError, WsError};
+use crate::{UrlError, retry::ExponentialBackoff};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type WsSink = SplitSink<WsStream, Message>;
+type WsRead = SplitStream<WsStream>;
+/// `Send + Sync` boxed FU member. `Sync` (vs the stock `BoxFuture`, which is `Send`-only) is required
+/// because [WsConnection] is exposed through `ExchangeStream: Sync` downstream.
+type BoxedFu = Pin<Box<dyn Future<Output = FuEvent> + Send + Sync>>;
+
+/// handle exchange-level events on the [WsConnection].
+pub trait WsHandler: std::fmt::Debug {
+	/// Returns a [WsConfig] that will be applied for all WebSocket connections handled by this handler.
+	fn config(&self) -> Result<WsConfig, UrlError> {
+		Ok(WsConfig::default())
+	}
+
+	/// Called when the [WsConnection] is created and on reconnection. Returned messages will be sent back to the server as-is.
+	///
+	/// Handling of `listen-key`s or any other authentication methods exchange demands should be done here. Although oftentimes handling the auth will spread into the [handle_message](Self::handle_message) too.
+	/// Can be ran multiple times (on every reconnect). Thus this inherently cannot be used to initiate connectionions based on a change of state (ie order creation).
+	#[allow(unused_variables)]
+	fn handle_auth(&mut self) -> Result<Vec<tungstenite::Message>, WsError> {
+		Ok(vec![])
+	}
+
+	//Q: problem: can be either {String, serde_json::Value} //? other things?
+	/*
+	  "position"
+	  ||
+	  json!{
+	"id": "56374a46-3061-486b-a311-99ee972eb648",
+	"method": "order.place",
+	"params": {
+	  "symbol": "BTCUSDT",
+	  "side": "SELL",
+	  "type": "LIMIT",
+	  "timeInForce": "GTC",
+	  "price": "23416.10000000",
+	  "quantity": "0.00847000",
+	  "apiKey": "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A",
+	  "signature": "15af09e41c36f3cc61378c2fbe2c33719a03dd5eba8d0f9206fbda44de717c88",
+	  "timestamp": 1660801715431
+	  }
+	  }
+	  - and then the latter could be requiring signing
+	  */
+	#[allow(unused_variables)]
+	fn handle_subscribe(&mut self, topics: AHashSet<Topic>) -> Result<Vec<tungstenite::Message>, WsError>;
+
+	/// Adds `topics` to those subscribed on every (re)connect, ahead of [config](Self::config) being read. Used by [WsConnection::try_resume]; handlers that only take topics at construction keep the default, which refuses.
+	#[allow(unused_variables)]
+	fn extend_topics(&mut self, topics: AHashSet<String>) -> Result<(), WsError> {
+		Err(WsDefinitionError::ResumeUnsupported.into())
+	}
+
+	/// Topics the exchange has answered for since the last [handle_auth](Self::handle_auth), which should start over. Anything not in here is [Pending](SubscriptionStatus::Pending). Default: no acks tracked.
+	fn subscription_status(&self) -> HashMap<String, SubscriptionStatus> {
+		HashMap::new()
+	}
+
+	/// Active-heartbeat payload, sent every [WsConfig::active_ping_freq] when that is `Some`. Some
+	/// exchanges (eg Bybit) require the *client* to proactively keep the connection alive with an
+	/// app-level message (`{"op":"ping"}`) rather than relying on the WebSocket protocol's ping/pong
+	/// — for those, return that message here and set `active_ping_freq`. Default: no active ping.
+	fn active_ping(&self) -> Vec<tungstenite::Message> {
+		vec![]
+	}
+
+	/// Called when the [WsConnection] received a JSON-RPC value, returns messages to be sent to the server or the content with parsed event name. If not the desired content and no respose is to be sent (like after a confirmation for a subscription), return a Response with an empty Vec.
+	#[allow(unused_variables)]
+	fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError>;
+	//A: use this iff spot&&perp binance accept listen-key refresh through stream
+	///// Additional POST communication with the exchange, not conditional on received messages, can be handled here.
+	///// Really this is just for damn Binance with their stupid `listn-key` standard.
+	//fn handle_post(&mut self) -> Result<Option<Vec<tungstenite::Message>>, WsError> {
+	//	Ok(None)
+	//}
+
+	//#[allow(unused_variables)]
+	//fn handle_jrpc(&mut self, jrpc: &serde_json::Value) -> Result<Option<Vec<tungstenite::Message>>, WsError> {
+	//	Ok(None)
+	//}
+}
+#[derive(Clone, Debug)]
+pub enum ResponseOrContent {
+	/// Response to a message sent to the server.
+	Response(Vec<tungstenite::Message>),
+	/// Content received from the server.
+	Content(ContentEvent),
+}
+/// Main way to interact with the WebSocket APIs.
+///
+/// Reading is driven by [next](Self::next). Writing is done by a separate task owning the socket's sink, fed through a channel, so neither side waits on the other: a slow consumer of `next()` doesn't hold up pongs and keepalives, and any number of [senders](Self::sender) can write concurrently.
+//REVIEW: manual `Debug` (was derived) — the `fu` I/O futures aren't `Debug`; we skip them.
+pub struct WsConnection<H: WsHandler> {
+	url: Url,
+	config: WsConfig,
+	handler: H,
+	backoff: ExponentialBackoff,
+	/// When set, `next()` will sleep until this instant before attempting to connect.
+	/// Only set on actual connection failure (not on cancellation), making `next()` cancel-safe.
+	reconnect_after: Option<tokio::time::Instant>,
+	/// Drives reading: the reader is a permanent standing member, alongside the optional active-ping
+	/// timer and a watch on the writer task. All own their resources (no `&self` borrow), so they
+	/// survive across calls.
+	fu: FuturesUnordered<BoxedFu>,
+	/// Task owning the sink of the current socket. `None` while disconnected.
+	writer: Option<Writer>,
+	/// Feeds the writer task. Lives as long as the connection, so [senders](Self::sender) stay valid over reconnects.
+	///
+	/// Note we never queue Pongs here: tungstenite auto-answers inbound Pings with a Pong carrying
+	/// the exact payload (`tungstenite::protocol::WebSocket`, flushed on the next read/write), which
+	/// already satisfies eg Binance's exact-echo requirement. Manually ponging would duplicate it.
+	tx: mpsc::UnboundedSender<Outgoing>,
+	/// Held by the writer task of the current socket, handed over to the next one on reconnect.
+	outbox: Arc<tokio::sync::Mutex<Outbox>>,
+	/// [ConnectionState], readable by [senders](Self::sender) without a lock.
+	state: Arc<AtomicU8>,
+	/// Unix ms of the writer's last flush. The response window of a forced communication starts there, rather than at the enqueue, so a send delayed by [WsConfig::outbound_rate] doesn't eat into it.
+	last_flush: Arc<AtomicU64>,
+	/// Sockets opened so far. Upkeep the connection queues itself is tagged with this, and dropped by writers of later sockets.
+	sessions: u32,
+	/// `None` == disconnected (replaces the old `stream.is_none()` check). `Some` holds the instant
+	/// the live connection started, for `refresh_after`.
+	connected_since: Option<SystemTime>,
+	/// When we last sent something the server hasn't acknowledged (used to pick the response_ vs
+	/// message_ timeout). Moved up from the old `WsConnectionStream`.
+	last_unanswered_communication: Option<SystemTime>,
+	/// Saw a Close / reconnecting error but returned already-collected content first; reconnect on
+	/// the next `next()` call.
+	pending_reconnect: Option<DisconnectCause>,
+	/// Reconnects after a drop so far, checked against [WsConfig::reconnect_policy].
+	reconnects: u32,
+	/// How often to fire the standing active-ping timer (`PingDue`), enqueueing the handler's
+	/// [active_ping](WsHandler::active_ping) payload. `None` == no active ping (rely on inbound traffic
+	/// + protocol pong, as Binance does). Copied from [WsConfig::active_ping_freq] at construction.
+	active_ping_freq: Option<Duration>,
+	/// Exchange event time vs local receive time of every [ContentEvent] that carries one.
+	latency: LatencyTracker,
+	/// `ws_connection`, spanning the whole lifetime of this connection, reconnects included.
+	span: tracing::Span,
+	/// `ws_session`, a child of [span](Self::span) per underlying socket; replaced on every (re)connect.
+	session: tracing::Span,
+}
+impl<H: WsHandler> WsConnection<H> {
+	#[allow(missing_docs)]
+	pub fn try_new(url_suffix: &str, handler: H) -> Result<Self, WsError> {
+		let config = handler.config()?;
+		config.validate().map_err(WsDefinitionError::InvalidConfig)?;
+		let url = match &config.base_url {
+			Some(base_url) => base_url.join(url_suffix)?,
+			None => Url::parse(url_suffix).map_err(|e| match e {
+				url::ParseError::RelativeUrlWithoutBase => WsError::Definition(WsDefinitionError::MissingUrl),
+				e => e.into(),
+			})?,
+		};
+		let backoff = ExponentialBackoff::try_from(&config.reconnect).map_err(|e| WsDefinitionError::InvalidReconnect(e.to_string()))?;
+		let active_ping_freq = config.active_ping_freq;
+		let latency = LatencyTracker::new(config.latency_window);
+		let span = tracing::info_span!(target: "v_exchanges::ws", "ws_connection", %url);
+		let (tx, rx) = mpsc::unbounded_channel();
+
+		Ok(Self {
+			url,
+			config,
+			handler,
+			backoff,
+			reconnect_after: None,
+			fu: FuturesUnordered::new(),
+			writer: None,
+			tx,
+			outbox: Arc::new(tokio::sync::Mutex::new(Outbox { rx, unsent: VecDeque::new() })),
+			state: Arc::new(AtomicU8::new(ConnectionState::Reconnecting as u8)),
+			last_flush: Arc::new(AtomicU64::new(0)),
+			sessions: 0,
+			connected_since: None,
+			last_unanswered_communication: None,
+			pending_reconnect: None,
+			reconnects: 0,
+			active_ping_freq,
+			latency,
+			span,
+			session: tracing::Span::none(),
+		})
+	}
+
+	/// Like [try_new](Self::try_new), but subscribed to everything `snapshot` was, in one go on connect. Refuses snapshots of other endpoints.
+	///
+	/// For restarts: take a [subscription_snapshot](Self::subscription_snapshot) before going down, persist it, and resume from it on the way up, so no time is spent re-deriving the topic set.
+	pub fn try_resume(url_suffix: &str, mut handler: H, snapshot: &SubscriptionSnapshot) -> Result<Self, WsError> {
+		handler.extend_topics(snapshot.topics.iter().cloned().collect())?;
+		let connection = Self::try_new(url_suffix, handler)?;
+		let endpoint = endpoint(&connection.url);
+		if endpoint != snapshot.endpoint {
+			return Err(WsDefinitionError::SnapshotMismatch {
+				expected: snapshot.endpoint.clone(),
+				got: endpoint,
+			}
+			.into());
+		}
+		Ok(connection)
+	}
+
+	/// Everything this connection subscribes to on connect, and where. See [try_resume](Self::try_resume).
+	pub fn subscription_snapshot(&self) -> SubscriptionSnapshot {
+		SubscriptionSnapshot {
+			endpoint: endpoint(&self.url),
+			topics: self.config.topics.iter().cloned().collect(),
+		}
+	}
+
+	/// Where each topic subscribed on connect stands. Those the handler has no answer for yet are [Pending](SubscriptionStatus::Pending), and so is everything while disconnected.
+	pub fn subscription_status(&self) -> HashMap<String, SubscriptionStatus> {
+		let mut status: HashMap<String, SubscriptionStatus> = self.config.topics.iter().map(|t| (t.clone(), SubscriptionStatus::Pending)).collect();
+		if self.connected_since.is_some() {
+			status.extend(self.handler.subscription_status());
+		}
+		status
+	}
+
+	/// Feed latency over the last [WsConfig::latency_window]. `None` until at least one event with a parseable exchange time arrived.
+	pub fn latency_stats(&self) -> Option<LatencyStats> {
+		self.latency.stats()
+	}
+
+	/// `server_time - local_time`, as measured over REST. Applied to every latency sample, so local clock drift doesn't pollute the numbers.
+	pub fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.latency.clock_offset = offset;
+	}
+
+	/// Handle for writing to this connection from elsewhere, eg another task, while this one is busy in [next](Self::next).
+	pub fn sender(&self) -> WsSender {
+		WsSender {
+			tx: self.tx.clone(),
+			state: self.state.clone(),
+		}
+	}
+
+	/// See [WsSender::send].
+	pub fn send(&self, message: Message) -> Result<(), WsError> {
+		self.sender().send(message)
+	}
+
+	/// See [WsSender::send_all].
+	pub fn send_all(&self, messages: impl IntoIterator<Item = Message>) -> Result<(), WsError> {
+		self.sender().send_all(messages)
+	}
+
+	pub fn state(&self) -> ConnectionState {
+		ConnectionState::load(&self.state)
+	}
+
+	/**
+	The main interface.
+	All connection upkeep (ping/pong, JRPC control replies, reconnect, refresh) is hidden; a call blocks until the socket buffer has something, then drains **all** immediately-available frames and returns every content event from them in one batch.
+
+	cancel-safe: any path with non-empty collected content returns *before* the next `.await`, so a cancellation can only land on `self.fu.next().await` — where the half-read reader future still lives on `self.fu`, losing nothing.
+	Deferred reconnect/upkeep is picked up on the next call.
+	**/
+	pub async fn next(&mut self) -> Result<Vec<ContentEvent>, WsError> {
+		let span = self.span.clone();
+		self.next_inner().instrument(span).await
+	}
+
+	async fn next_inner(&mut self) -> Result<Vec<ContentEvent>, WsError> {
+		// Cancel-safe backoff: a previous failed attempt parked a target Instant; resume the wait.
+		if let Some(until) = self.reconnect_after.take() {
+			tokio::time::sleep_until(until).await;
+		}
+		if let Some(cause) = self.pending_reconnect.take() {
+			self.recover(cause).await?;
+		}
+		if let Some(since) = self.connected_since
+			&& since + self.config.refresh_after < SystemTime::now()
+		{
+			tracing::info!(target: "v_exchanges::ws", "Refreshing connection, as `refresh_after` specified in WsConfig has elapsed ({:?})", self.config.refresh_after);
+			self.recover(DisconnectCause::Refresh(self.config.refresh_after)).await?;
+		}
+		if self.connected_since.is_none() {
+			self.connect().await?;
+		}
+
+		let mut content: Vec<ContentEvent> = Vec::new();
+
+		loop {
+			let timeout = match self.choose_timeout() {
+				Some(d) => d,
+				None => {
+					tracing::error!(target: "v_exchanges::ws", "Timeout for last unanswered communication ended before `.next()` was called. This likely indicates a clientside implementation error.");
+					self.recover(DisconnectCause::Unanswered(self.config.response_timeout)).await?;
+					continue;
+				}
+			};
+
+			match tokio::time::timeout(timeout, self.fu.next()).await {
+				Err(_) => {
+					// Nothing arrived in time. Return any collected content first; defer ping/reconnect.
+					if !content.is_empty() {
+						return Ok(content);
+					}
+					if self.last_unanswered_communication.is_some() {
+						tracing::warn!(target: "v_exchanges::ws", "Response to a forced communication timed out after {timeout:?}. Reconnecting.");
+						self.recover(DisconnectCause::Unanswered(timeout)).await?;
+					} else {
+						// Standard message_timeout elapsed with nothing pending, — force life out of the server with our own Ping.
+						self.enqueue(vec![Message::Ping(Bytes::default())]);
+					}
+					continue;
+				}
+				Ok(None) => {
+					// FU drained (defensive; the reader is a permanent member so this shouldn't happen).
+					tracing::warn!(target: "v_exchanges::ws", "FuturesUnordered empty despite permanent reader. Reconnecting.");
+					self.recover(DisconnectCause::Eof).await?;
+					continue;
+				}
+				Ok(Some(FuEvent::WriteFailed(e))) => {
+					// the writer task is gone with it, so even otherwise skippable errors take the connection down
+					tracing::warn!(target: "v_exchanges::ws", "Write failed ({e:?}). Reconnecting.");
+					if !content.is_empty() {
+						self.pending_reconnect = Some(DisconnectCause::Transport(e));
+						return Ok(content);
+					}
+					self.recover(DisconnectCause::Transport(e)).await?;
+					continue;
+				}
+				Ok(Some(FuEvent::PingDue)) => {
+					// Active heartbeat fell due: hand the handler's ping payload to the writer and re-arm the standing timer.
+					// Treated like any other outbound action — it sets `last_unanswered_communication`, so a missed reply trips the response-timeout reconnect just as a self-Ping would.
+					let ping = self.handler.active_ping();
+					self.enqueue(ping);
+					self.arm_ping();
+					continue; // a ping is never content
+				}
+				Ok(Some(FuEvent::Read { reader, batch })) => {
+					if batch.is_empty() {
+						// EOF.
+						drop(reader);
+						if !content.is_empty() {
+							self.pending_reconnect = Some(DisconnectCause::Eof);
+							return Ok(content);
+						}
+						tracing::warn!(target: "v_exchanges::ws", "tungstenite read EOF from the stream. Reconnecting.");
+						self.recover(DisconnectCause::Eof).await?;
+						continue;
+					}
+					self.arm_reader(reader); // re-arm the standing member NOW
+					self.last_unanswered_communication = None; // heard from the server
+					let received_at = Timestamp::now();
+
+					let mut terminal = None; // saw Close / a reconnecting error
+					let session = self.session.clone().entered();
+					for frame in batch {
+						let __pong_ack = || tracing::trace!(target: "v_exchanges::ws", "Received app-level pong (active-ping ack)");
+						match frame {
+							Ok(Message::Text(text)) => {
+								let value: serde_json::Value =
+									serde_json::from_str(&text).expect("API sent invalid JSON, which is completely unexpected. Disappointment is immeasurable and the day is ruined.");
+								// App-level heartbeat ack to our active-ping (Bybit et al. answer `{"op":"ping"}` with a text-frame `{"op":"pong"}` / `{"ret_msg":"pong"}`, NOT a protocol Pong).
+								// It carries no content and needs no reply — drop it before it reaches the handler, whose strict response parse doesn't model a pong.
+								if value["op"] == "pong" || value["ret_msg"] == "pong" {
+									__pong_ack();
+									continue;
+								}
+								tracing::trace!(target: "v_exchanges::ws", "{value:#?}");
+								match self.handler.handle_jrpc(value)? {
+									ResponseOrContent::Response(messages) => self.enqueue(messages),
+									ResponseOrContent::Content(c) => {
+										// handlers that can't parse an event time leave it at the epoch
+										if c.time != Timestamp::UNIX_EPOCH {
+											self.latency.record(c.time, received_at);
+										}
+										content.push(c);
+									}
+								}
+							}
+							// tungstenite already queued a Pong with this exact payload (auto-answered on read, flushed on the next read/write), — (satisfying even Binance's exact-echo rule).
+							// No need to pong ourselves or the server receives two Pongs per Ping.
+							Ok(Message::Ping(_)) => tracing::trace!(target: "v_exchanges::ws", "Received ping (tungstenite auto-pongs)"),
+							Ok(Message::Pong(_)) => __pong_ack(),
+							Ok(Message::Close(maybe_reason)) => {
+								match &maybe_reason {
+									Some(close_frame) => tracing::info!(target: "v_exchanges::ws", "Server closed connection; reason: {close_frame:?}"),
+									None => tracing::info!(target: "v_exchanges::ws", "Server closed connection; no reason specified."),
+								}
+								terminal = Some(DisconnectCause::Closed(maybe_reason));
+								break;
+							}
+							Ok(Message::Binary(_)) => panic!("Received binary. But exchanges are not smart enough to send this, what is happening"),
+							Ok(Message::Frame(_)) => unreachable!("Can't get from reading"),
+							Err(e) if is_reconnecting(&e) => {
+								tracing::warn!(target: "v_exchanges::ws", "Reconnecting-class error mid-batch: {e:?}");
+								terminal = Some(DisconnectCause::Transport(e));
+								break;
+							}
+							// Non-reconnecting class: `is_reconnecting` already panicked on Utf8 / unreachable on the write-only variant. The remainder (Capacity) is skippable.
+							Err(e) => {
+								debug_assert!(!is_reconnecting(&e));
+								tracing::warn!(target: "v_exchanges::ws", "Skipping non-fatal polling error: {e:?}");
+							}
+						}
+					}
+					drop(session);
+
+					if let Some(cause) = terminal {
+						// Reconnecting class: upkeep still queued targets a soon-dead connection, and is discarded by the next socket's writer.
+						self.set_state(ConnectionState::Reconnecting);
+						if !content.is_empty() {
+							self.pending_reconnect = Some(cause); // reconnect on the next call
+							return Ok(content); // content-before-Close returned first, never lost
+						}
+						self.recover(cause).await?;
+						continue;
+					}
+					if self.stale_check() {
+						self.pending_reconnect = Some(DisconnectCause::Stale);
+					}
+					if !content.is_empty() {
+						return Ok(content);
+					}
+					continue; // only upkeep parsed -> keep awaiting the FU
+				}
+			}
+		}
+	}
+
+	/// Warns once p95 latency crosses [WsConfig::stale_event_threshold]. Returns whether a reconnect was requested for it.
+	fn stale_check(&mut self) -> bool {
+		let Some(threshold) = self.config.stale_event_threshold else { return false };
+		let Some(p95) = self.latency.p95_throttled() else { return false };
+		// signed, so a clock running ahead of the exchange can't pass for lag
+		let is_stale = p95 > SignedDuration::try_from(threshold).unwrap_or(SignedDuration::MAX);
+		if is_stale && !self.latency.stale {
+			tracing::warn!(target: "v_exchanges::ws", ?p95, ?threshold, url = %self.url, "Feed latency p95 exceeds `stale_event_threshold`");
+		}
+		self.latency.stale = is_stale;
+		if is_stale && self.config.reconnect_on_stale {
+			self.latency.clear(); // fresh connection gets a fresh window, otherwise we'd loop on old samples
+			return true;
+		}
+		false
+	}
+
+	/// Push the permanent standing reader future onto the FU.
+	fn arm_reader(&mut self, reader: WsRead) {
+		self.fu.push(Box::pin(read_future(reader)));
+	}
+
+	/// Push the standing active-ping timer onto the FU, IF one is configured. No-op when
+	/// `active_ping_freq` is `None` (no heartbeat needed — the reader stays the only standing member).
+	fn arm_ping(&mut self) {
+		if let Some(freq) = self.active_ping_freq {
+			self.fu.push(Box::pin(ping_future(freq)));
+		}
+	}
+
+	/// Hand the connection's own upkeep to the writer, tagged with the current socket so it's never replayed on another. Arms the response timeout.
+	fn enqueue(&mut self, messages: Vec<Message>) {
+		if messages.is_empty() {
+			return;
+		}
+		for message in messages {
+			// can't fail: `self.outbox` keeps the receiver alive
+			let _ = self.tx.send(Outgoing {
+				message,
+				session: Some(self.sessions),
+			});
+		}
+		self.last_unanswered_communication = Some(SystemTime::now());
+	}
+
+	fn set_state(&self, state: ConnectionState) {
+		self.state.store(state as u8, Ordering::Release);
+	}
+
+	/// Pick the read timeout based on whether we're awaiting a forced response. `None` means a forced
+	/// response's window already elapsed before this call — the caller reconnects.
+	fn choose_timeout(&self) -> Option<Duration> {
+		match self.last_unanswered_communication {
+			Some(enqueued) => match enqueued.max(self.last_flush()) + self.config.response_timeout > SystemTime::now() {
+				true => Some(self.config.response_timeout),
+				false => None,
+			},
+			None => Some(self.config.message_timeout),
+		}
+	}
+
+	fn last_flush(&self) -> SystemTime {
+		SystemTime::UNIX_EPOCH + Duration::from_millis(self.last_flush.load(Ordering::Acquire))
+	}
+
+	async fn connect(&mut self) -> Result<(), WsError> {
+		self.session = tracing::info_span!(target: "v_exchanges::ws", parent: &self.span, "ws_session", reconnects = self.reconnects);
+		let session = self.session.clone();
+		self.connect_inner().instrument(session).await
+	}
+
+	async fn connect_inner(&mut self) -> Result<(), WsError> {
+		tracing::info!(target: "v_exchanges::ws", "Connecting to {}...", self.url);
+
+		let (stream, http_resp) = match tokio_tungstenite::connect_async(self.url.as_str()).await {
+			Ok(result) => result,
+			Err(e) => {
+				let delay = self.backoff.next_duration();
+				if !delay.is_zero() {
+					tracing::warn!(target: "v_exchanges::ws", delay_ms = delay.as_millis(), "Connection failed, backing off before retry.");
+					self.reconnect_after = Some(tokio::time::Instant::now() + delay);
+				}
+				return Err(e.into());
+			}
+		};
+		tracing::debug!(target: "v_exchanges::ws", "Ws handshake with server: {http_resp:#?}");
+
+		let (sink, reader) = stream.split();
+		self.sessions += 1;
+		self.fu = FuturesUnordered::new();
+		self.arm_reader(reader);
+		self.arm_ping(); // standing heartbeat timer (no-op if `active_ping_freq` is None)
+		self.last_unanswered_communication = None;
+		self.connected_since = Some(SystemTime::now());
+
+		if self.config.queued_during_reconnect == QueuedDuringReconnect::Drop {
+			let mut outbox = self.outbox.lock().await; // free: the previous writer is gone by now
+			let mut dropped = outbox.unsent.drain(..).filter(|o| o.session.is_none()).count();
+			while let Ok(outgoing) = outbox.rx.try_recv() {
+				dropped += outgoing.session.is_none() as usize;
+			}
+			if dropped != 0 {
+				tracing::warn!(target: "v_exchanges::ws", dropped, "Dropping messages sent while disconnected, per `WsConfig::queued_during_reconnect`");
+			}
+		}
+
+		// Auth/subscribe messages go out first thing, ahead of anything queued while disconnected; all of it concurrently with the standing read.
+		let auth_messages = self.handler.handle_auth()?;
+		if !auth_messages.is_empty() {
+			self.last_unanswered_communication = Some(SystemTime::now());
+		}
+		self.spawn_writer(sink, auth_messages);
+		self.set_state(ConnectionState::Connected);
+
+		self.reconnect_after = None;
+		self.backoff.reset();
+		Ok(())
+	}
+
+	/// Start the writer task of a fresh socket, and watch it from the FU for write failures.
+	fn spawn_writer(&mut self, sink: WsSink, auth: Vec<Message>) {
+		let (shutdown_tx, shutdown_rx) = oneshot::channel();
+		let task = WriterTask {
+			sink,
+			outbox: self.outbox.clone(),
+			session: self.sessions,
+			limiter: self.config.outbound_rate.map(|(n, per)| OutboundLimiter::new(n, per)), // budgets are per connection
+			last_flush: self.last_flush.clone(),
+			shutdown: shutdown_rx,
+			shutting_down: false,
+		};
+		let (failed_tx, failed_rx) = oneshot::channel();
+		let handle = tokio::spawn(
+			async move {
+				if let Err(e) = task.run(auth).await {
+					let _ = failed_tx.send(e);
+				}
+			}
+			.instrument(self.session.clone()),
+		);
+		self.writer = Some(Writer { handle, shutdown: Some(shutdown_tx) });
+		self.fu.push(Box::pin(async move {
+			match failed_rx.await {
+				Ok(e) => FuEvent::WriteFailed(e),
+				// exited cleanly, on shutdown
+				Err(_) => std::future::pending().await,
+			}
+		}));
+	}
+
+	/// [reconnect](Self::reconnect) after the connection dropped for `cause`, if [WsConfig::reconnect_policy] still allows it. Otherwise `cause` is surfaced as [WsError::ConnectionLost].
+	async fn recover(&mut self, cause: DisconnectCause) -> Result<(), WsError> {
+		let allowed = match self.config.reconnect_policy {
+			ReconnectPolicy::Always => true,
+			ReconnectPolicy::Never => false,
+			ReconnectPolicy::UpTo(n) => self.reconnects < n,
+		};
+		if !allowed {
+			tracing::warn!(target: "v_exchanges::ws", policy = ?self.config.reconnect_policy, reconnects = self.reconnects, "Connection lost ({cause}); not reconnecting.");
+			self.set_state(ConnectionState::Closed);
+			if let Some(writer) = self.writer.take() {
+				writer.close(self.config.response_timeout).await;
+			}
+			return Err(WsError::ConnectionLost(cause));
+		}
+		self.reconnects += 1;
+		self.reconnect().await
+	}
+
+	/// Best-effort `Close` the existing connection, drop everything, and open a new one. The old
+	/// writer gets [response_timeout](WsConfig::response_timeout) to get the Close out, and is
+	/// aborted past that.
+	///
+	/// `pub` for testing only, does not {have to || is expected to} be exposed in any wrappers.
+	pub async fn reconnect(&mut self) -> Result<(), WsError> {
+		// Clear any pending backoff — a server-initiated reconnect should be attempted immediately.
+		// If the new connection fails, `connect()` will set a fresh backoff.
+		self.reconnect_after = None;
+		self.set_state(ConnectionState::Reconnecting);
+		if let Some(writer) = self.writer.take() {
+			tracing::info!(target: "v_exchanges::ws", "Dropping old connection before reconnecting...");
+			writer.close(self.config.response_timeout).await;
+		}
+		self.fu = FuturesUnordered::new(); // drops the reader future + the old read half
+		self.connected_since = None;
+		self.connect().await
+	}
+}
+
+/// Where a [WsConnection] stands, as seen by its [senders](WsSender).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum ConnectionState {
+	/// No live socket: before the first connect, and between a drop and the reconnect. Sends are held per [WsConfig::queued_during_reconnect].
+	Reconnecting,
+	Connected,
+	/// Lost for good per [ReconnectPolicy]. Sends fail with [WsError::Closed].
+	Closed,
+}
+impl ConnectionState {
+	fn load(state: &AtomicU8) -> Self {
+		match state.load(Ordering::Acquire) {
+			0 => Self::Reconnecting,
+			1 => Self::Connected,
+			_ => Self::Closed,
+		}
+	}
+}
+
+/// Cloneable write handle of a [WsConnection]. Get one from [WsConnection::sender].
+///
+/// Sends never wait on the socket: messages are queued for the connection's writer task, which goes through them in order, paced by [WsConfig::outbound_rate].
+#[derive(Clone, Debug)]
+pub struct WsSender {
+	tx: mpsc::UnboundedSender<Outgoing>,
+	state: Arc<AtomicU8>,
+}
+impl WsSender {
+	/// Errors only once the connection is [closed](ConnectionState::Closed) or dropped.
+	pub fn send(&self, message: Message) -> Result<(), WsError> {
+		if self.state() == ConnectionState::Closed {
+			return Err(WsError::Closed);
+		}
+		self.tx.send(Outgoing { message, session: None }).map_err(|_| WsError::Closed)
+	}
+
+	/// Queued back to back, so nothing sent from elsewhere lands in between.
+	pub fn send_all(&self, messages: impl IntoIterator<Item = Message>) -> Result<(), WsError> {
+		messages.into_iter().try_for_each(|m| self.send(m))
+	}
+
+	pub fn state(&self) -> ConnectionState {
+		ConnectionState::load(&self.state)
+	}
+}
+
+/// Message on its way to the writer task.
+#[derive(Debug)]
+struct Outgoing {
+	message: Message,
+	/// Socket the connection queued this upkeep for. `None` for [WsSender] sends, which are good on any.
+	session: Option<u32>,
+}
+
+/// Handle on the writer task of the current socket. Aborts it on drop.
+#[derive(Debug)]
+struct Writer {
+	handle: JoinHandle<()>,
+	shutdown: Option<oneshot::Sender<()>>,
+}
+impl Writer {
+	/// Have the task send a Close and exit, giving it `timeout` to.
+	async fn close(mut self, timeout: Duration) {
+		if let Some(shutdown) = self.shutdown.take() {
+			let _ = shutdown.send(());
+		}
+		if tokio::time::timeout(timeout, &mut self.handle).await.is_err() {
+			tracing::debug!(target: "v_exchanges::ws", "Writer didn't close within {timeout:?}, aborting it");
+		}
+	}
+}
+impl Drop for Writer {
+	fn drop(&mut self) {
+		self.handle.abort();
+	}
+}
+
+/// Receiving end of [WsConnection::tx], passed from the writer task of one socket to that of the next.
+#[derive(Debug)]
+struct Outbox {
+	rx: mpsc::UnboundedReceiver<Outgoing>,
+	/// Taken off `rx`, but not flushed yet. Outlives a writer task that failed or was aborted mid-write, so the next socket's writer sends it first.
+	unsent: VecDeque<Outgoing>,
+}
+
+/// Owns the sink of one socket, sending whatever comes through the channel.
+struct WriterTask {
+	sink: WsSink,
+	outbox: Arc<tokio::sync::Mutex<Outbox>>,
+	session: u32,
+	/// Paces writes per [WsConfig::outbound_rate].
+	limiter: Option<OutboundLimiter>,
+	last_flush: Arc<AtomicU64>,
+	shutdown: oneshot::Receiver<()>,
+	/// `shutdown` fired, and can't be polled again.
+	shutting_down: bool,
+}
+impl WriterTask {
+	/// Sends `auth`, then whatever the previous socket's writer left unsent, then everything queued, until told to shut down or the connection and all its senders are gone.
+	async fn run(mut self, auth: Vec<Message>) -> Result<(), tungstenite::Error> {
+		let outbox = self.outbox.clone();
+		let mut outbox = outbox.lock().await;
+		let session = self.session;
+		let mut auth: VecDeque<Outgoing> = auth.into_iter().map(|message| Outgoing { message, session: Some(session) }).collect();
+		self.write(&mut auth).await?;
+		self.write(&mut outbox.unsent).await?;
+		while !self.shutting_down {
+			tokio::select! {
+				biased;
+				_ = &mut self.shutdown => self.shutting_down = true,
+				next = outbox.rx.recv() => match next {
+					Some(outgoing) => outbox.unsent.push_back(outgoing),
+					None => return Ok(()),
+				},
+			}
+			while let Ok(outgoing) = outbox.rx.try_recv() {
+				outbox.unsent.push_back(outgoing);
+			}
+			self.write(&mut outbox.unsent).await?;
+		}
+		// Best-effort close - ignore errors since the connection may already be broken.
+		if let Err(e) = self.sink.send(Message::Close(None)).await {
+			tracing::debug!(target: "v_exchanges::ws", "Failed to send Close frame (connection likely already dead): {e}");
+		}
+		Ok(())
+	}
+
+	/// Send everything in `queue` no earlier than the limiter allows, flushing whatever was already due before each wait. Messages leave `queue` only once flushed; a shutdown during a wait leaves the rest in it.
+	async fn write(&mut self, queue: &mut VecDeque<Outgoing>) -> Result<(), tungstenite::Error> {
+		let session = self.session;
+		queue.retain(|o| o.session.is_none_or(|s| s == session));
+		if queue.is_empty() || self.shutting_down {
+			return Ok(());
+		}
+		tracing::debug!(target: "v_exchanges::ws", "flushing to server: {:#?}", queue.iter().map(|o| &o.message).collect::<Vec<_>>());
+		let now = tokio::time::Instant::now();
+		let scheduled: Vec<_> = match &mut self.limiter {
+			Some(limiter) => (0..queue.len()).map(|_| limiter.reserve(now)).collect(),
+			None => vec![now; queue.len()],
+		};
+		let delay = *scheduled.last().expect("checked non-empty") - now;
+		if !delay.is_zero() {
+			tracing::info!(target: "v_exchanges::ws", messages = scheduled.len(), ?delay, "Outbound rate limit engaged, spreading out the sends");
+		}
+		let mut fed = 0;
+		for at in scheduled {
+			if at > tokio::time::Instant::now() {
+				self.flush(queue, &mut fed).await?; // whatever was already due goes out before the wait
+				tokio::select! {
+					biased;
+					_ = &mut self.shutdown => {
+						self.shutting_down = true;
+						return Ok(());
+					}
+					_ = tokio::time::sleep_until(at) => {}
+				}
+			}
+			self.sink.feed(queue[fed].message.clone()).await?;
+			fed += 1;
+		}
+		self.flush(queue, &mut fed).await
+	}
+
+	/// Flush the `fed` first messages of `queue`, and only then take them off it.
+	async fn flush(&mut self, queue: &mut VecDeque<Outgoing>, fed: &mut usize) -> Result<(), tungstenite::Error> {
+		if *fed == 0 {
+			return Ok(());
+		}
+		self.sink.flush().await?;
+		queue.drain(..std::mem::take(fed));
+		let flushed_at = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+		self.last_flush.store(flushed_at, Ordering::Release);
+		Ok(())
+	}
+}
+
+/// Serializable topic set of a [WsConnection], for [resuming](WsConnection::try_resume) it after a restart.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SubscriptionSnapshot {
+	/// Url the topics were subscribed on, less the query: that's where exchanges put topics and tokens, neither of which identify the endpoint.
+	pub endpoint: String,
+	pub topics: std::collections::BTreeSet<String>,
+}
+
+fn endpoint(url: &Url) -> String {
+	let mut url = url.clone();
+	url.set_query(None);
+	url.to_string()
+}
+
+/// Why a [WsConnection] dropped. Carried by [WsError::ConnectionLost].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum DisconnectCause {
+	#[error("server closed the connection ({0:?})")]
+	Closed(Option<tungstenite::protocol::CloseFrame>),
+	#[error("stream ended")]
+	Eof,
+	#[error("transport error")]
+	Transport(#[source] tungstenite::Error),
+	#[error("no response from the server within {0:?}")]
+	Unanswered(Duration),
+	#[error("feed latency exceeded `stale_event_threshold`")]
+	Stale,
+	/// Not a drop: the connection was due for its scheduled [refresh](WsConfig::refresh_after).
+	#[error("`refresh_after` of {0:?} elapsed")]
+	Refresh(Duration),
+}
+
+/// Enforces [WsConfig::outbound_rate] over a sliding window: any `n + 1` consecutive sends span at least `per`. Hands out the instant each message may go at, so sends are scheduled up front and the writer only has to sleep until their turn.
+#[derive(Clone, Debug)]
+struct OutboundLimiter {
+	n: usize,
+	per: Duration,
+	/// Scheduled send times of the last `n` messages, oldest first.
+	sent: VecDeque<tokio::time::Instant>,
+}
+impl OutboundLimiter {
+	fn new(n: u32, per: Duration) -> Self {
+		Self {
+			n: n as usize,
+			per,
+			sent: VecDeque::with_capacity(n as usize),
+		}
+	}
+
+	/// Takes a slot, returning when it opens.
+	fn reserve(&mut self, now: tokio::time::Instant) -> tokio::time::Instant {
+		let at = match self.sent.len() == self.n {
+			true => (self.sent.pop_front().expect("full") + self.per).max(now),
+			false => now,
+		};
+		self.sent.push_back(at);
+		at
+	}
+}
+
+/// Hard cap on retained samples, so a firehose stream can't blow up memory within one window.
+const MAX_LATENCY_SAMPLES: usize = 4096;
+/// Sorting the window on every batch is wasteful; stale checks look at most this often.
+const STALE_CHECK_INTERVAL: SignedDuration = SignedDuration::from_secs(1);
+
+#[derive(Clone, Debug)]
+struct LatencyTracker {
+	window: Duration,
+	/// `(received_at, latency)`, oldest first.
+	samples: VecDeque<(Timestamp, SignedDuration)>,
+	clock_offset: SignedDuration,
+	/// Whether we've last seen p95 above the threshold; only log on crossing.
+	stale: bool,
+	last_check: Option<(Timestamp, SignedDuration)>,
+}
+impl LatencyTracker {
+	fn new(window: Duration) -> Self {
+		Self {
+			window,
+			samples: VecDeque::new(),
+			clock_offset: SignedDuration::ZERO,
+			stale: false,
+			last_check: None,
+		}
+	}
+
+	fn record(&mut self, event_time: Timestamp, received_at: Timestamp) {
+		let latency = received_at.duration_since(event_time) + self.clock_offset;
+		self.samples.push_back((received_at, latency));
+		let cutoff = received_at.checked_sub(self.window).unwrap_or(Timestamp::MIN);
+		while self.samples.front().is_some_and(|(t, _)| *t < cutoff) || self.samples.len() > MAX_LATENCY_SAMPLES {
+			self.samples.pop_front();
+		}
+	}
+
+	fn stats(&self) -> Option<LatencyStats> {
+		if self.samples.is_empty() {
+			return None;
+		}
+		let mut sorted: Vec<SignedDuration> = self.samples.iter().map(|(_, l)| *l).collect();
+		sorted.sort_unstable();
+		let at = |q: f64| sorted[((sorted.len() - 1) as f64 * q).round() as usize];
+		Some(LatencyStats {
+			p50: at(0.50),
+			p95: at(0.95),
+			p99: at(0.99),
+			max: *sorted.last().expect("checked non-empty"),
+			samples: sorted.len(),
+		})
+	}
+
+	fn p95_throttled(&mut self) -> Option<SignedDuration> {
+		let now = Timestamp::now();
+		let p95 = match self.last_check {
+			Some((checked_at, p95)) if now.duration_since(checked_at) < STALE_CHECK_INTERVAL => p95,
+			_ => {
+				let p95 = self.stats()?.p95;
+				self.last_check = Some((now, p95));
+				p95
+			}
+		};
+		Some(p95)
+	}
+
+	fn clear(&mut self) {
+		self.samples.clear();
+		self.stale = false;
+		self.last_check = None;
+	}
+}
+
+/// Homogeneous [FuturesUnordered] members for [WsConnection]; all own their resources, so each
+/// future is `'static` and holds no `&self` borrow — letting it live on the struct across `next()`
+/// calls (which is what makes the draining `next()` cancel-safe).
+enum FuEvent {
+	/// The permanent standing reader: blocks for the first frame, then drains all immediately-
+	/// available ones. `batch` empty == EOF sentinel.
+	Read { reader: WsRead, batch: Vec<Result<Message, tungstenite::Error>> },
+	/// The writer task of the current socket failed, and exited.
+	WriteFailed(tungstenite::Error),
+	/// The (optional) standing active-ping timer: sleeps `active_ping_freq`, then fires. Re-armed on
+	/// every fire, so it ticks for the whole connection lifetime. Carries no resources — the handler
+	/// supplies the actual ping payload when it fires.
+	PingDue,
+}
+
+/// Block for the first frame, then drain everything already buffered without blocking.
+///
+/// `now_or_never` polls the RAW reader once with a no-op waker: `Some(frame)` -> keep & continue,
+/// `None` -> nothing immediately ready -> stop. Safe because a raw `reader.next()` is cancel-safe at
+/// the frame boundary (a `Pending` poll consumes no frame, loses nothing). This is NOT a kernel
+/// "read all" call (tungstenite / `futures::Stream` expose no such API and one can't exist cleanly);
+/// it drains what's cheaply available — tungstenite's parsed `in_buffer` plus whatever one chunk-read
+/// pulled (~1 syscall for many frames). Any straggler still in the kernel simply becomes the first
+/// frame of the next `next()` call, which is what we want (don't block to chase stragglers).
+async fn read_future(mut reader: WsRead) -> FuEvent {
+	let mut batch = Vec::new();
+	if let Some(first) = reader.next().await {
+		batch.push(first);
+		while let Some(Some(m)) = reader.next().now_or_never() {
+			batch.push(m);
+		}
+	} // else: empty batch == EOF sentinel
+	FuEvent::Read { reader, batch }
+}
+
+/// Sleep one active-ping interval, then fire. Owns no resources — re-armed by the caller on each fire.
+async fn ping_future(freq: Duration) -> FuEvent {
+	tokio::time::sleep(freq).await;
+	FuEvent::PingDue
+}
+
+/// Whether a polling error from tungstenite warrants tearing down & reconnecting.
+///
+/// Folds the per-variant arms of the old single-frame `next()` match: the connection-fatal classes
+/// reconnect; `Capacity` is skippable (false); `Utf8` panics at the call site; the TLS/URL/HTTP
+/// classes are still `todo!()` as before; the write-only variants are unreachable on a read.
+fn is_reconnecting(err: &tungstenite::Error) -> bool {
+	match err {
+		tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed | tungstenite::Error::Io(_) | tungstenite::Error::Protocol(_) | tungstenite::Error::AttackAttempt => true,
+		tungstenite::Error::Capacity(_) => false,
+		tungstenite::Error::Utf8(e) => panic!("received `tungstenite::Error::Utf8` from polling: {e:?}. Exchange is going crazy, aborting"),
+		tungstenite::Error::WriteBufferFull(_) => unreachable!("can only get from writing"),
+		tungstenite::Error::Tls(_) | tungstenite::Error::Url(_) | tungstenite::Error::Http(_) | tungstenite::Error::HttpFormat(_) => todo!(),
+	}
+}
+
+impl<H: WsHandler> std::fmt::Debug for WsConnection<H> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("WsConnection")
+			.field("url", &self.url)
+			.field("config", &self.config)
+			.field("handler", &self.handler)
+			.field("backoff", &self.backoff)
+			.field("reconnect_after", &self.reconnect_after)
+			.field("connected_since", &self.connected_since)
+			.field("last_unanswered_communication", &self.last_unanswered_communication)
+			.field("pending_reconnect", &self.pending_reconnect)
+			.field("reconnects", &self.reconnects)
+			.field("state", &self.state())
+			.field("sessions", &self.sessions)
+			.field("active_ping_freq", &self.active_ping_freq)
+			.field("latency", &self.latency.stats())
+			.field("span", &self.span)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use futures_util::SinkExt as _;
+	use tokio::net::TcpListener;
+	use tokio_tungstenite::accept_async;
+
+	use super::*;
+	use crate::{RetryConfig, ws::WsConfigError};
+
+	/// Trivial handler for the in-process server: every text frame is content (`{"n": <i>}`), nothing
+	/// is ever replied. Short timeouts keep the hermetic tests sub-second.
+	#[derive(Debug)]
+	struct EchoHandler;
+	impl WsHandler for EchoHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			// Short timeouts keep the hermetic tests sub-second.
+			Ok(WsConfig::default().with_message_timeout(Duration::from_millis(200)).with_response_timeout(Duration::from_millis(200)))
+		}
+
+		fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			Ok(vec![])
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			Ok(ResponseOrContent::Content(ContentEvent {
+				data: jrpc.clone(),
+				topic: "test".to_owned(),
+				time: Timestamp::UNIX_EPOCH,
+				event_type: "test".to_owned(),
+			}))
+		}
+	}
+
+	/// Like [EchoHandler] but with a fast active-ping configured, emitting Bybit-style `{"op":"ping"}`.
+	/// Used to exercise the standing `PingDue` timer and the upstream pong-ack drop.
+	#[derive(Debug)]
+	struct PingHandler;
+	impl WsHandler for PingHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			// Fire the active-ping fast; keep read timeouts long so the test ends by our own logic, not a
+			// message_timeout.
+			Ok(WsConfig::default()
+				.with_active_ping_freq(Duration::from_millis(80))
+				.with_message_timeout(Duration::from_secs(5))
+				.with_response_timeout(Duration::from_secs(5)))
+		}
+
+		fn active_ping(&self) -> Vec<Message> {
+			vec![Message::Text("{\"op\":\"ping\"}".into())]
+		}
+
+		// Would wrongly surface ANY text as content — so a pong-ack reaching here fails the drop test.
+		fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			Ok(vec![])
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			Ok(ResponseOrContent::Content(ContentEvent {
+				data: jrpc,
+				topic: "test".to_owned(),
+				time: Timestamp::UNIX_EPOCH,
+				event_type: "test".to_owned(),
+			}))
+		}
+	}
+
+	/// Keeps its topics in the url, as Binance's combined streams do.
+	#[derive(Debug, Default)]
+	struct TopicsHandler(AHashSet<String>);
+	impl WsHandler for TopicsHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			let mut topics: Vec<_> = self.0.iter().cloned().collect();
+			topics.sort();
+			let url = Url::parse(&format!("ws://127.0.0.1:1/stream?streams={}", topics.join("/"))).unwrap();
+			Ok(WsConfig::default().with_topics(self.0.clone()).with_base_url(url))
+		}
+
+		fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			Ok(vec![])
+		}
+
+		fn extend_topics(&mut self, topics: AHashSet<String>) -> Result<(), WsError> {
+			self.0.extend(topics);
+			Ok(())
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			EchoHandler.handle_jrpc(jrpc)
+		}
+	}
+
+	#[test]
+	fn subscription_snapshot_round_trip() {
+		let topics: AHashSet<String> = (0..50).map(|i| format!("pair{i}@aggTrade")).collect();
+		let original = WsConnection::try_new("", TopicsHandler(topics)).unwrap();
+		let snapshot = original.subscription_snapshot();
+		assert_eq!(snapshot.endpoint, "ws://127.0.0.1:1/stream");
+		assert_eq!(snapshot.topics.len(), 50);
+
+		let persisted: SubscriptionSnapshot = serde_json::from_str(&serde_json::to_string(&snapshot).unwrap()).unwrap();
+		let resumed = WsConnection::try_resume("", TopicsHandler::default(), &persisted).unwrap();
+		assert_eq!(resumed.subscription_snapshot(), snapshot);
+		assert_eq!(resumed.url, original.url, "topics baked into the url, same as the original");
+
+		let elsewhere = SubscriptionSnapshot {
+			endpoint: "ws://127.0.0.1:2/stream".to_owned(),
+			..snapshot
+		};
+		let err = WsConnection::try_resume("", TopicsHandler::default(), &elsewhere).unwrap_err();
+		assert!(matches!(err, WsError::Definition(WsDefinitionError::SnapshotMismatch { .. })), "{err:?}");
+		assert!(matches!(
+			WsConnection::try_resume("", EchoHandler, &persisted),
+			Err(WsError::Definition(WsDefinitionError::ResumeUnsupported))
+		));
+	}
+
+	#[test]
+	fn contradictory_config_is_refused() {
+		assert_eq!(WsConfig::default().validate(), Ok(()));
+		assert_eq!(WsConfig::safe_defaults().validate(), Ok(()));
+
+		let config = WsConfig::default()
+			.with_message_timeout(Duration::from_secs(1))
+			.with_response_timeout(Duration::from_secs(2))
+			.with_reconnect(RetryConfig { initial_delay_ms: 0, ..RetryConfig::default() });
+		assert_eq!(config.validate(), Err(vec![
+			WsConfigError::MessageTimeoutLessThanResponseTimeout {
+				message_timeout: Duration::from_secs(1),
+				response_timeout: Duration::from_secs(2),
+			},
+			WsConfigError::ZeroReconnectCooldown,
+		]));
+		let config = WsConfig::default().with_refresh_after(Duration::from_millis(500));
+		assert!(matches!(config.validate().as_deref(), Err([WsConfigError::RefreshAfterLessThanReconnectCooldown { .. }])));
+
+		struct Contradictory;
+		impl WsHandler for Contradictory {
+			fn config(&self) -> Result<WsConfig, UrlError> {
+				Ok(EchoHandler.config()?.with_message_timeout(Duration::from_millis(100)))
+			}
+
+			fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+				Ok(vec![])
+			}
+
+			fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+				EchoHandler.handle_jrpc(jrpc)
+			}
+		}
+		let err = WsConnection::try_new("ws://127.0.0.1:1", Contradictory).unwrap_err();
+		assert!(matches!(&err, WsError::Definition(WsDefinitionError::InvalidConfig(errors)) if errors.len() == 1), "{err:?}");
+	}
+
+	/// Subscribes to `a`, `b` and `c`; the server acks with `{"ack":<topic>}` or rejects with `{"nack":<topic>}`.
+	#[derive(Debug, Default)]
+	struct AckHandler(HashMap<String, SubscriptionStatus>);
+	impl WsHandler for AckHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			Ok(EchoHandler.config()?.with_topics(["a", "b", "c"].map(str::to_owned)))
+		}
+
+		fn handle_auth(&mut self) -> Result<Vec<Message>, WsError> {
+			self.0.clear();
+			Ok(vec![])
+		}
+
+		fn handle_subscribe(&mut self, _topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			Ok(vec![])
+		}
+
+		fn subscription_status(&self) -> HashMap<String, SubscriptionStatus> {
+			self.0.clone()
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			if let Some(topic) = jrpc.get("ack").and_then(|t| t.as_str()) {
+				self.0.insert(topic.to_owned(), SubscriptionStatus::Active);
+				return Ok(ResponseOrContent::Response(vec![]));
+			}
+			if let Some(topic) = jrpc.get("nack").and_then(|t| t.as_str()) {
+				self.0.insert(topic.to_owned(), SubscriptionStatus::Failed("no such topic".to_owned()));
+				return Ok(ResponseOrContent::Response(vec![]));
+			}
+			EchoHandler.handle_jrpc(jrpc)
+		}
+	}
+
+	#[tokio::test]
+	async fn subscription_status_follows_acks() {
+		let (listener, url) = bind().await;
+		let server = async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			for frame in [r#"{"ack":"a"}"#, r#"{"nack":"b"}"#, r#"{"n":0}"#] {
+				ws.feed(Message::Text(frame.into())).await.expect("feed");
+			}
+			ws.flush().await.expect("flush");
+			tokio::time::sleep(Duration::from_secs(3)).await;
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, AckHandler::default()).expect("try_new");
+		assert!(conn.subscription_status().values().all(|s| *s == SubscriptionStatus::Pending), "nothing is active before connecting");
+
+		conn.next().await.expect("next");
+		let status = conn.subscription_status();
+		assert_eq!(status["a"], SubscriptionStatus::Active);
+		assert_eq!(status["b"], SubscriptionStatus::Failed("no such topic".to_owned()));
+		assert_eq!(status["c"], SubscriptionStatus::Pending, "never answered");
+		handle.abort();
+	}
+
+	/// [EchoHandler] with a chosen [ReconnectPolicy].
+	#[derive(Debug)]
+	struct PolicyHandler(ReconnectPolicy);
+	impl WsHandler for PolicyHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			Ok(EchoHandler.config()?.with_reconnect_policy(self.0))
+		}
+
+		fn handle_subscribe(&mut self, topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			EchoHandler.handle_subscribe(topics)
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			EchoHandler.handle_jrpc(jrpc)
+		}
+	}
+
+	/// Server whose first connection sends 3 frames and dies; every later connection sends 3 more and stays open.
+	async fn dies_after_three(listener: TcpListener) {
+		let mut n = 0;
+		for connection in 0.. {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			for _ in 0..3 {
+				ws.feed(Message::Text(format!("{{\"n\":{n}}}").into())).await.expect("feed");
+				n += 1;
+			}
+			ws.flush().await.expect("flush");
+			if connection == 0 {
+				drop(ws);
+			} else {
+				tokio::time::sleep(Duration::from_secs(3)).await;
+			}
+		}
+	}
+
+	/// Everything `conn` yields until the first error, or until `limit` items.
+	async fn collect_until_err<H: WsHandler>(conn: &mut WsConnection<H>, limit: usize) -> (usize, Option<WsError>) {
+		let mut items = 0;
+		while items < limit {
+			match conn.next().await {
+				Ok(batch) => items += batch.len(),
+				Err(e) => return (items, Some(e)),
+			}
+		}
+		(items, None)
+	}
+
+	#[tokio::test]
+	async fn never_reconnect_surfaces_the_drop() {
+		let (listener, url) = bind().await;
+		let handle = tokio::spawn(dies_after_three(listener));
+
+		let mut conn = WsConnection::try_new(&url, PolicyHandler(ReconnectPolicy::Never)).expect("try_new");
+		let (items, err) = tokio::time::timeout(Duration::from_secs(2), collect_until_err(&mut conn, usize::MAX)).await.expect("must not hang");
+		assert_eq!(items, 3, "everything sent before the drop is still delivered");
+		let err = err.expect("drop surfaces as an error");
+		assert!(matches!(err, WsError::ConnectionLost(_)), "got {err:?}");
+		assert!(err.is_terminal());
+		handle.abort();
+	}
+
+	#[tokio::test]
+	async fn always_reconnect_keeps_going() {
+		let (listener, url) = bind().await;
+		let handle = tokio::spawn(dies_after_three(listener));
+
+		let mut conn = WsConnection::try_new(&url, PolicyHandler(ReconnectPolicy::Always)).expect("try_new");
+		let (items, err) = tokio::time::timeout(Duration::from_secs(2), collect_until_err(&mut conn, 6)).await.expect("must not hang");
+		assert!(err.is_none(), "got {err:?}");
+		assert_eq!(items, 6, "frames of the second connection arrive after the transparent reconnect");
+		handle.abort();
+	}
+
+	/// [PolicyHandler] due for a refresh every 300ms.
+	#[derive(Debug)]
+	struct RefreshingHandler(ReconnectPolicy);
+	impl WsHandler for RefreshingHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			Ok(PolicyHandler(self.0)
+				.config()?
+				.with_reconnect(RetryConfig { initial_delay_ms: 50, ..RetryConfig::default() })
+				.with_refresh_after(Duration::from_millis(300)))
+		}
+
+		fn handle_subscribe(&mut self, topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			EchoHandler.handle_subscribe(topics)
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			EchoHandler.handle_jrpc(jrpc)
+		}
+	}
+
+	/// Server sending a frame every 50ms on every connection, never dropping any.
+	async fn steady(listener: TcpListener) {
+		loop {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			tokio::spawn(async move {
+				let mut ws = accept_async(tcp).await.expect("handshake");
+				for n in 0.. {
+					if ws.send(Message::Text(format!("{{\"n\":{n}}}").into())).await.is_err() {
+						return;
+					}
+					tokio::time::sleep(Duration::from_millis(50)).await;
+				}
+			});
+		}
+	}
+
+	#[tokio::test]
+	async fn refreshes_obey_the_reconnect_policy() {
+		let (listener, url) = bind().await;
+		let handle = tokio::spawn(steady(listener));
+
+		let mut conn = WsConnection::try_new(&url, RefreshingHandler(ReconnectPolicy::Never)).expect("try_new");
+		let (items, err) = tokio::time::timeout(Duration::from_secs(2), collect_until_err(&mut conn, usize::MAX)).await.expect("must not hang");
+		assert!(items > 0);
+		assert!(matches!(err, Some(WsError::ConnectionLost(DisconnectCause::Refresh(_)))), "got {err:?}");
+
+		let mut conn = WsConnection::try_new(&url, RefreshingHandler(ReconnectPolicy::UpTo(1))).expect("try_new");
+		let started = std::time::Instant::now();
+		let (_, err) = tokio::time::timeout(Duration::from_secs(2), collect_until_err(&mut conn, usize::MAX)).await.expect("must not hang");
+		assert!(matches!(err, Some(WsError::ConnectionLost(DisconnectCause::Refresh(_)))), "got {err:?}");
+		assert!(started.elapsed() >= Duration::from_millis(600), "the one allowed refresh went through first");
+		handle.abort();
+	}
+
+	/// Bind an ephemeral loopback port, returning `(listener, "ws://127.0.0.1:<port>")`.
+	async fn bind() -> (TcpListener, String) {
+		let listener = TcpListener::bind("127.0.0.1:0").await.expect("loopback bind");
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		(listener, url)
+	}
+
+	#[tokio::test]
+	async fn logs_under_ws_target_in_session_span() {
+		let capture = crate::tracing_capture::Capture::default();
+		let _guard = tracing::subscriber::set_default(capture.clone());
+		let (listener, url) = bind().await;
+		let handle = tokio::spawn(async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			ws.send(Message::Text("{\"n\":0}".into())).await.expect("send");
+			tokio::time::sleep(Duration::from_secs(3)).await;
+		});
+
+		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
+		assert_eq!(conn.next().await.expect("next").len(), 1);
+		handle.abort();
+
+		let captured = capture.0.lock().unwrap();
+		assert!(
+			captured.spans.iter().any(|s| s.target == "v_exchanges::ws" && s.name == "ws_session" && s.parent == Some("ws_connection")),
+			"{:?}",
+			captured.spans
+		);
+		let ours: Vec<_> = captured.events.iter().filter(|e| e.target.starts_with("v_exchanges")).collect();
+		assert!(ours.iter().all(|e| e.target == "v_exchanges::ws"), "{ours:?}");
+		// connecting at info, the received message at trace; both on the socket they concern
+		assert!(ours.iter().any(|e| e.level == tracing::Level::INFO && e.span == Some("ws_session")), "{ours:?}");
+		assert!(ours.iter().any(|e| e.level == tracing::Level::TRACE && e.span == Some("ws_session")), "{ours:?}");
+	}
+
+	/// Drain: the server writes N text frames back-to-back (one buffered flush so they land together
+	/// over loopback), then goes quiet → a single `next()` must return all N (whole-buffer drain in one
+	/// call, not one-per-call).
+	#[tokio::test]
+	async fn drains_whole_buffer_in_one_call() {
+		const N: usize = 8;
+		let (listener, url) = bind().await;
+
+		let server = async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			// `feed` all then a single `flush`: coalesces into one write so the frames arrive as one
+			// readable chunk and the client's `now_or_never` drain sees them all.
+			for i in 0..N {
+				ws.feed(Message::Text(format!("{{\"n\":{i}}}").into())).await.expect("feed");
+			}
+			ws.flush().await.expect("flush");
+			// Stay quiet but keep the connection open so the client doesn't see EOF.
+			tokio::time::sleep(Duration::from_secs(3)).await;
+			drop(ws);
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
+		let batch = conn.next().await.expect("next");
+		assert_eq!(batch.len(), N, "expected all {N} buffered frames drained in a single next()");
+		handle.abort();
+	}
+
+	/// Side-effects mid-drain (constraint: never drop ping handling): server sends `[Ping, Text, Text]`
+	/// → the call returns 2 content events AND the server observes exactly one Pong.
+	///
+	/// The Pong is tungstenite's automatic reply to the inbound Ping (carrying the exact payload),
+	/// flushed when our reader is next polled — NOT a manual pong (manual would duplicate it).
+	#[tokio::test]
+	async fn pongs_once_and_returns_content_from_same_batch() {
+		let (listener, url) = bind().await;
+
+		let server = async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			ws.feed(Message::Ping(Bytes::from_static(b"hi"))).await.expect("feed ping");
+			ws.feed(Message::Text("{\"n\":1}".into())).await.expect("feed t1");
+			ws.feed(Message::Text("{\"n\":2}".into())).await.expect("feed t2");
+			ws.flush().await.expect("flush");
+			// Count every Pong the client sends until it disconnects (we drop it client-side below).
+			let mut pongs = 0usize;
+			while let Some(msg) = ws.next().await {
+				match msg {
+					Ok(Message::Pong(_)) => pongs += 1,
+					Ok(Message::Close(_)) | Err(_) => break,
+					_ => {}
+				}
+			}
+			pongs
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
+		let batch = conn.next().await.expect("next");
+		assert_eq!(batch.len(), 2, "two text frames in the batch become two content events");
+
+		// Drive one more bounded `next()`: it re-polls the (re-armed) reader, which flushes the queued
+		// auto-pong, then blocks on the now-quiet socket and times out. We only care that the Pong went
+		// out exactly once. Then drop the client so the server's read loop ends and yields its count.
+		// Bounded drive purely for its side-effect (flush the auto-pong); the timeout-elapsed result
+		// is expected and discarded.
+		let _drove_flush = tokio::time::timeout(Duration::from_millis(150), conn.next()).await;
+		drop(conn);
+		let pongs = tokio::time::timeout(Duration::from_secs(1), handle).await.expect("server join timeout").expect("server task");
+		assert_eq!(pongs, 1, "exactly one Pong (tungstenite's auto-reply) for the single Ping");
+	}
+
+	/// Terminal: server sends `[Text, Close]` → `next()` returns `Ok(vec![one])` (content before Close
+	/// kept, not lost, not errored). The next call must reconnect (observed as a second accept).
+	#[tokio::test]
+	async fn returns_content_before_close_then_reconnects() {
+		let (listener, url) = bind().await;
+
+		let server = async move {
+			// First connection: one text then a Close.
+			let (tcp, _) = listener.accept().await.expect("accept 1");
+			let mut ws = accept_async(tcp).await.expect("handshake 1");
+			ws.feed(Message::Text("{\"n\":1}".into())).await.expect("feed");
+			ws.feed(Message::Close(None)).await.expect("feed close");
+			ws.flush().await.expect("flush");
+			drop(ws);
+
+			// Second connection proves the client reconnected after the Close.
+			let (tcp2, _) = listener.accept().await.expect("accept 2 (reconnect)");
+			let mut ws2 = accept_async(tcp2).await.expect("handshake 2");
+			ws2.feed(Message::Text("{\"n\":2}".into())).await.expect("feed 2");
+			ws2.flush().await.expect("flush 2");
+			tokio::time::sleep(Duration::from_secs(2)).await;
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
+		let first = conn.next().await.expect("next 1");
+		assert_eq!(first.len(), 1, "content collected before Close is returned, not dropped");
+
+		// Next call reconnects (pending_reconnect was set) and yields the second connection's frame.
+		let second = conn.next().await.expect("next 2 after reconnect");
+		assert_eq!(second.len(), 1, "after reconnect we receive the new connection's frame");
+		handle.abort();
+	}
+
+	/// Active-ping: with `active_ping_freq` set, the client must proactively send the handler's
+	/// `{"op":"ping"}` payload on a quiet connection (no inbound traffic), within the configured window.
+	#[tokio::test]
+	async fn sends_active_ping_on_quiet_connection() {
+		let (listener, url) = bind().await;
+
+		let server = async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			// Stay silent; just wait for the client's first app-level ping text frame.
+			loop {
+				match ws.next().await {
+					Some(Ok(Message::Text(t))) if t.contains("\"op\":\"ping\"") => return true,
+					Some(Ok(_)) => continue, // ignore protocol pings/pongs etc.
+					_ => return false,       // closed/errored before any app ping
+				}
+			}
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, PingHandler).expect("try_new");
+		// Drive `next()` long enough for the 80ms ping timer to fire and flush; it returns no content
+		// (only the ping flies), so the timeout-elapsed result is expected and discarded.
+		let _drove_ping = tokio::time::timeout(Duration::from_millis(400), conn.next()).await;
+		let saw_ping = tokio::time::timeout(Duration::from_secs(1), handle).await.expect("server join timeout").expect("server task");
+		assert!(saw_ping, "client must send an app-level `{{\"op\":\"ping\"}}` on a quiet connection");
+	}
+
+	/// Pong-ack drop: a text-frame `{"op":"pong"}` (the heartbeat reply some exchanges send instead of a
+	/// protocol Pong) must be swallowed upstream — never surfaced as content, even though `PingHandler`
+	/// would wrongly turn any text into content if it ever reached `handle_jrpc`.
+	#[tokio::test]
+	async fn drops_app_level_pong_ack() {
+		let (listener, url) = bind().await;
+
+		let server = async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			// A pong-ack, then a real content frame. Only the latter may surface to the caller.
+			ws.feed(Message::Text("{\"op\":\"pong\"}".into())).await.expect("feed pong");
+			ws.feed(Message::Text("{\"n\":1}".into())).await.expect("feed content");
+			ws.flush().await.expect("flush");
+			tokio::time::sleep(Duration::from_secs(2)).await;
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, PingHandler).expect("try_new");
+		let batch = conn.next().await.expect("next");
+		assert_eq!(batch.len(), 1, "pong-ack dropped upstream; only the real content frame surfaces");
+		assert_eq!(batch[0].data["n"], 1, "the surviving event is the content frame, not the pong");
+		handle.abort();
+	}
+
+	/// Writes go out from the writer task: queued before connecting, they follow the connect; queued after, they don't wait for `next()` to be polled again.
+	#[tokio::test]
+	async fn sender_writes_without_polling_next() {
+		let (listener, url) = bind().await;
+		let server = async move {
+			let (tcp, _) = listener.accept().await.expect("accept");
+			let mut ws = accept_async(tcp).await.expect("handshake");
+			ws.send(Message::Text("{\"n\":0}".into())).await.expect("send");
+			let mut received = Vec::new();
+			while received.len() < 2 {
+				match ws.next().await {
+					Some(Ok(Message::Text(t))) => received.push(t.to_string()),
+					Some(Ok(_)) => continue,
+					_ => break,
+				}
+			}
+			received
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, EchoHandler).expect("try_new");
+		let sender = conn.sender();
+		assert_eq!(sender.state(), ConnectionState::Reconnecting);
+		sender.send(Message::Text("before".into())).expect("queued while not connected");
+		assert_eq!(conn.next().await.expect("next").len(), 1);
+		assert_eq!(conn.state(), ConnectionState::Connected);
+
+		sender.send(Message::Text("after".into())).expect("send");
+		let received = tokio::time::timeout(Duration::from_secs(1), handle).await.expect("writes must not wait on next()").expect("server task");
+		assert_eq!(received, ["before", "after"]);
+
+		drop(conn);
+		tokio::time::sleep(Duration::from_millis(20)).await; // let the aborted writer go
+		assert!(matches!(sender.send(Message::Text("late".into())), Err(WsError::Closed)));
+	}
+
+	/// [EchoHandler] letting one message out per 100ms.
+	#[derive(Debug)]
+	struct PacedHandler;
+	impl WsHandler for PacedHandler {
+		fn config(&self) -> Result<WsConfig, UrlError> {
+			Ok(EchoHandler.config()?.with_outbound_rate(Some((1, Duration::from_millis(100)))))
+		}
+
+		fn handle_subscribe(&mut self, topics: AHashSet<Topic>) -> Result<Vec<Message>, WsError> {
+			EchoHandler.handle_subscribe(topics)
+		}
+
+		fn handle_jrpc(&mut self, jrpc: serde_json::Value) -> Result<ResponseOrContent, WsError> {
+			EchoHandler.handle_jrpc(jrpc)
+		}
+	}
+
+	/// The socket dies with most of a paced batch still queued; the next one sends the rest, in order.
+	#[tokio::test]
+	async fn queued_sends_survive_the_socket_dying() {
+		let (listener, url) = bind().await;
+		let server = async move {
+			let mut received = Vec::new();
+			while received.len() < 4 {
+				let (tcp, _) = listener.accept().await.expect("accept");
+				let mut ws = accept_async(tcp).await.expect("handshake");
+				let first_socket = received.is_empty();
+				while received.len() < 4 {
+					match ws.next().await {
+						Some(Ok(Message::Text(t))) => received.push(t.to_string()),
+						Some(Ok(_)) => continue,
+						_ => break,
+					}
+					if first_socket {
+						break; // drop the socket right after the first message
+					}
+				}
+			}
+			received
+		};
+		let handle = tokio::spawn(server);
+
+		let mut conn = WsConnection::try_new(&url, PacedHandler).expect("try_new");
+		conn.send_all(["a", "b", "c", "d"].map(|m| Message::Text(m.into()))).expect("queued while not connected");
+		let drive = async {
+			loop {
+				let _ = conn.next().await;
+			}
+		};
+		let received = tokio::select! {
+			received = tokio::time::timeout(Duration::from_secs(2), handle) => received.expect("must not hang").expect("server task"),
+			_ = drive => unreachable!(),
+		};
+		assert_eq!(received, ["a", "b", "c", "d"]);
+	}
+
+	#[test]
+	fn outbound_limiter_spreads_bursts() {
+		let mut limiter = OutboundLimiter::new(5, Duration::from_secs(1));
+		let t0 = tokio::time::Instant::now();
+		let at: Vec<Duration> = (0..12).map(|_| limiter.reserve(t0) - t0).collect();
+		// the first 5 go out at once, then each waits for the one 5 sends back to age out
+		assert_eq!(&at[..5], &[Duration::ZERO; 5]);
+		assert_eq!(&at[5..10], &[Duration::from_secs(1); 5]);
+		assert_eq!(at[10], Duration::from_secs(2));
+		for window in at.windows(6) {
+			assert!(window[5] - window[0] >= Duration::from_secs(1), "{at:?}");
+		}
+
+		// slots freed by waiting count
+		let later = t0 + Duration::from_secs(10);
+		assert_eq!(limiter.reserve(later), later);
+	}
+
+	#[test]
+	fn latency_window_percentiles_and_clock_offset() {
+		let mut tracker = LatencyTracker::new(Duration::from_secs(10));
+		assert!(tracker.stats().is_none());
+
+		let t0 = Timestamp::from_second(1_700_000_000).unwrap();
+		// 1..=100ms latencies, all received within the window
+		for ms in 1..=100 {
+			let received_at = t0 + SignedDuration::from_millis(ms * 10);
+			tracker.record(received_at - SignedDuration::from_millis(ms), received_at);
+		}
+		let stats = tracker.stats().unwrap();
+		assert_eq!(stats.samples, 100);
+		assert_eq!(stats.p50, SignedDuration::from_millis(51));
+		assert_eq!(stats.p95, SignedDuration::from_millis(95));
+		assert_eq!(stats.max, SignedDuration::from_millis(100));
+
+		// a sample far later evicts everything older than the window; offset shifts the sample
+		tracker.clock_offset = SignedDuration::from_millis(-5);
+		let late = t0 + SignedDuration::from_secs(60);
+		tracker.record(late - SignedDuration::from_millis(20), late);
+		let stats = tracker.stats().unwrap();
+		assert_eq!(stats.samples, 1);
+		assert_eq!(stats.max, SignedDuration::from_millis(15));
+
+		// events stamped ahead of receipt are skew, not lag
+		let mut tracker = LatencyTracker::new(Duration::from_secs(10));
+		tracker.record(late + SignedDuration::from_secs(2), late);
+		assert_eq!(tracker.p95_throttled(), Some(SignedDuration::from_secs(-2)));
+	}
+}
@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use eyre::{Result, bail};
+use jiff::SignedDuration;
+use tokio::{
+	sync::{mpsc, watch},
+	task::JoinHandle,
+};
+
+use super::{ContentEvent, LatencyStats, SubscriptionSnapshot, TopicInterpreter, WsConnection, WsError, WsHandler};
+
+/// Hands a [ContentEvent] to its typed channel. Returns `false` once the receiving side is gone, so the route can be dropped.
+type Dispatch = Box<dyn Fn(ContentEvent) -> bool + Send + Sync>;
+
+/**
+Owns a [WsConnection] and fans its [ContentEvent]s out into typed per-topic channels.
+
+Each registered [TopicInterpreter] gets its own channel; routing is by [event_type](ContentEvent::event_type) == [event_name](TopicInterpreter::event_name). Events no interpreter claims go to the [unmatched](Self::unmatched) channel.
+
+Channels are unbounded: a slow consumer on one topic must not stall the socket for every other one.
+
+```ignore
+let mut router = TypedRouter::new(client.ws_connection("", options)?);
+let mut trades = router.register(TopicInterpreter { event_name: "trade".to_owned(), interpret: parse_trade })?;
+router.start()?;
+while let Some(trade) = trades.recv().await { .. }
+```
+*/
+pub struct TypedRouter<H: WsHandler> {
+	routes: Arc<DashMap<String, Dispatch>>,
+	unmatched_tx: mpsc::UnboundedSender<ContentEvent>,
+	unmatched_rx: Option<mpsc::UnboundedReceiver<ContentEvent>>,
+	/// `Some` until [start](Self::start) moves the connection into the driver task.
+	connection: Option<WsConnection<H>>,
+	driver: Option<JoinHandle<WsError>>,
+	/// Topics are fixed at construction, so this doesn't go stale once the connection moves into the driver.
+	snapshot: SubscriptionSnapshot,
+	/// Published by the driver after every batch.
+	latency: watch::Receiver<Option<LatencyStats>>,
+	latency_tx: Option<watch::Sender<Option<LatencyStats>>>,
+	/// Picked up by the driver before its next read.
+	clock_offset: watch::Sender<SignedDuration>,
+}
+impl<H: WsHandler + Send + 'static> TypedRouter<H> {
+	pub fn new(connection: WsConnection<H>) -> Self {
+		let (unmatched_tx, unmatched_rx) = mpsc::unbounded_channel();
+		let (latency_tx, latency) = watch::channel(None);
+		Self {
+			routes: Arc::new(DashMap::new()),
+			unmatched_tx,
+			unmatched_rx: Some(unmatched_rx),
+			snapshot: connection.subscription_snapshot(),
+			connection: Some(connection),
+			driver: None,
+			latency,
+			latency_tx: Some(latency_tx),
+			clock_offset: watch::Sender::new(SignedDuration::ZERO),
+		}
+	}
+
+	/// See [WsConnection::subscription_snapshot].
+	pub fn subscription_snapshot(&self) -> SubscriptionSnapshot {
+		self.snapshot.clone()
+	}
+
+	/// See [WsConnection::latency_stats]. Once started, as of the last batch the driver read.
+	pub fn latency_stats(&self) -> Option<LatencyStats> {
+		match &self.connection {
+			Some(connection) => connection.latency_stats(),
+			None => *self.latency.borrow(),
+		}
+	}
+
+	/// See [WsConnection::set_clock_offset]. Once started, applies from the driver's next read on.
+	pub fn set_clock_offset(&mut self, offset: SignedDuration) {
+		match &mut self.connection {
+			Some(connection) => connection.set_clock_offset(offset),
+			None => {
+				self.clock_offset.send_replace(offset);
+			}
+		}
+	}
+
+	pub fn is_started(&self) -> bool {
+		self.connection.is_none()
+	}
+
+	/// Works both before and after [start](Self::start). Events that arrived before registration are not replayed.
+	///
+	/// # Errors
+	/// If an interpreter for the same `event_name` is already registered.
+	pub fn register<T: Send + 'static>(&self, interpreter: TopicInterpreter<T>) -> Result<mpsc::UnboundedReceiver<T>> {
+		let (tx, rx) = mpsc::unbounded_channel();
+		let TopicInterpreter { event_name, interpret } = interpreter;
+		let dispatch: Dispatch = {
+			let event_name = event_name.clone();
+			Box::new(move |event: ContentEvent| match interpret(&event.data) {
+				Ok(value) => tx.send(value).is_ok(),
+				Err(e) => {
+					tracing::warn!(target: "v_exchanges::ws", %event_name, error = %e, data = %event.data, "Interpreter failed on a matching event; dropping it");
+					!tx.is_closed()
+				}
+			})
+		};
+		match self.routes.entry(event_name) {
+			dashmap::mapref::entry::Entry::Occupied(e) => bail!("interpreter for `{}` is already registered", e.key()),
+			dashmap::mapref::entry::Entry::Vacant(e) => {
+				e.insert(dispatch);
+			}
+		}
+		Ok(rx)
+	}
+
+	/// Events no registered interpreter matched. Can only be taken once.
+	pub fn unmatched(&mut self) -> Option<mpsc::UnboundedReceiver<ContentEvent>> {
+		self.unmatched_rx.take()
+	}
+
+	/// Spawn the driver task. It runs until [WsConnection::next] returns an error it couldn't recover from, which is then available through [join](Self::join). Typed channels close as it exits.
+	///
+	/// # Errors
+	/// If already started.
+	pub fn start(&mut self) -> Result<()> {
+		let Some(mut connection) = self.connection.take() else {
+			bail!("TypedRouter is already started");
+		};
+		let routes = Arc::clone(&self.routes);
+		let unmatched_tx = self.unmatched_tx.clone();
+		let latency_tx = self.latency_tx.take().expect("taken only here, alongside the connection");
+		let mut clock_offset = self.clock_offset.subscribe();
+		self.driver = Some(tokio::spawn(async move {
+			loop {
+				if clock_offset.has_changed().unwrap_or(false) {
+					connection.set_clock_offset(*clock_offset.borrow_and_update());
+				}
+				let batch = match connection.next().await {
+					Ok(batch) => batch,
+					Err(e) => {
+						// closes every typed channel, so consumers see the end rather than wait on it
+						routes.clear();
+						return e;
+					}
+				};
+				latency_tx.send_replace(connection.latency_stats());
+				for event in batch {
+					dispatch(&routes, &unmatched_tx, event);
+				}
+			}
+		}));
+		Ok(())
+	}
+
+	/// Wait for the driver task to exit, returning the error that stopped it. `None` if never started.
+	pub async fn join(&mut self) -> Option<WsError> {
+		let driver = self.driver.take()?;
+		Some(driver.await.unwrap_or_else(|e| WsError::Other(eyre::Report::new(e).wrap_err("TypedRouter driver task failed"))))
+	}
+}
+impl<H: WsHandler> Drop for TypedRouter<H> {
+	fn drop(&mut self) {
+		if let Some(driver) = &self.driver {
+			driver.abort();
+		}
+	}
+}
+impl<H: WsHandler> std::fmt::Debug for TypedRouter<H> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("TypedRouter")
+			.field("routes", &self.routes.iter().map(|r| r.key().clone()).collect::<Vec<_>>())
+			.field("connection", &self.connection)
+			.field("started", &self.driver.is_some())
+			.finish_non_exhaustive()
+	}
+}
+
+fn dispatch(routes: &DashMap<String, Dispatch>, unmatched_tx: &mpsc::UnboundedSender<ContentEvent>, event: ContentEvent) {
+	let event_type = event.event_type.clone();
+	let alive = match routes.get(&event_type) {
+		Some(route) => route(event),
+		None => {
+			// nobody listening on the catch-all is fine
+			unmatched_tx.send(event).ok();
+			return;
+		}
+	};
+	if !alive {
+		tracing::debug!(target: "v_exchanges::ws", %event_type, "Receiver dropped, unregistering route");
+		routes.remove(&event_type);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use jiff::Timestamp;
+
+	use super::*;
+
+	fn event(event_type: &str, n: i64) -> ContentEvent {
+		ContentEvent {
+			data: serde_json::json!({ "n": n }),
+			topic: "test".to_owned(),
+			time: Timestamp::UNIX_EPOCH,
+			event_type: event_type.to_owned(),
+		}
+	}
+
+	#[test]
+	fn routes_by_event_type() {
+		let routes: DashMap<String, Dispatch> = DashMap::new();
+		let (tx, mut rx) = mpsc::unbounded_channel::<i64>();
+		routes.insert("trade".to_owned(), Box::new(move |e: ContentEvent| tx.send(e.data["n"].as_i64().unwrap()).is_ok()));
+		let (unmatched_tx, mut unmatched_rx) = mpsc::unbounded_channel();
+
+		dispatch(&routes, &unmatched_tx, event("trade", 1));
+		dispatch(&routes, &unmatched_tx, event("kline", 2));
+		assert_eq!(rx.try_recv().unwrap(), 1);
+		assert_eq!(unmatched_rx.try_recv().unwrap().data["n"], 2);
+
+		// dropped receiver unregisters the route
+		drop(rx);
+		dispatch(&routes, &unmatched_tx, event("trade", 3));
+		assert!(routes.is_empty());
+	}
+}
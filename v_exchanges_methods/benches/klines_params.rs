@@ -0,0 +1,82 @@
+//! Query assembly for a Bybit klines request: the old `serde_json::Value` merge against [RequestRange::append_exchange_params].
+//!
+//! Prints allocations per assembly before timing; no network. Run with `cargo bench -p v_exchanges_methods --bench klines_params --features binance-http,bybit-http`.
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	cell::Cell,
+	hint::black_box,
+};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use jiff::Timestamp;
+use serde_json::json;
+use v_exchanges_methods::{ExchangeName, RequestRange};
+use v_utils::{trades::Timeframe, utils::filter_nulls};
+
+struct CountingAlloc;
+thread_local! {
+	static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+unsafe impl GlobalAlloc for CountingAlloc {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCS.with(|n| n.set(n.get() + 1));
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { System.dealloc(ptr, layout) }
+	}
+}
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn count_allocs(f: impl FnOnce()) -> usize {
+	let before = ALLOCS.with(Cell::get);
+	f();
+	ALLOCS.with(Cell::get) - before
+}
+
+/// What `bybit::market::klines` used to do.
+fn via_value(range: &RequestRange, tf: &Timeframe) -> String {
+	let range_json = range.to_exchange_params(ExchangeName::Bybit, tf);
+	let base_params = filter_nulls(json!({
+		"category": "linear",
+		"symbol": "BTCUSDT",
+		"interval": "1",
+	}));
+	let mut base_map = base_params.as_object().unwrap().clone();
+	base_map.extend(range_json.as_object().unwrap().clone());
+	let params = filter_nulls(serde_json::Value::Object(base_map));
+	serde_urlencoded::to_string(&params).unwrap()
+}
+
+fn via_append(range: &RequestRange, tf: &Timeframe) -> String {
+	let mut params = Vec::with_capacity(6);
+	params.push(("category", "linear".to_owned()));
+	params.push(("symbol", "BTCUSDT".to_owned()));
+	params.push(("interval", "1".to_owned()));
+	range.append_exchange_params(ExchangeName::Bybit, tf, &mut params);
+	serde_urlencoded::to_string(&params).unwrap()
+}
+
+fn klines_params(c: &mut Criterion) {
+	let tf = Timeframe::from("1m");
+	let since = Timestamp::from_second(1_700_000_000).unwrap();
+	let range = RequestRange::Span {
+		since,
+		until: Some(since + jiff::SignedDuration::from_hours(2)),
+	};
+
+	let old = count_allocs(|| drop(black_box(via_value(&range, &tf))));
+	let new = count_allocs(|| drop(black_box(via_append(&range, &tf))));
+	println!("allocations per klines query: serde_json::Value merge = {old}, append_exchange_params = {new}");
+	assert!(new < old, "append path should allocate less ({new} vs {old})");
+
+	let mut group = c.benchmark_group("klines_params");
+	group.bench_function("value_merge", |b| b.iter(|| via_value(black_box(&range), &tf)));
+	group.bench_function("append_params", |b| b.iter(|| via_append(black_box(&range), &tf)));
+	group.finish();
+}
+
+criterion_group!(benches, klines_params);
+criterion_main!(benches);
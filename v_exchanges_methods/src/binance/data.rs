@@ -1,9 +1,8 @@
-use adapters::binance::{BinanceHttpUrl, BinanceOption};
 use derive_more::{Display, FromStr};
 use jiff::Timestamp;
 use v_utils::Percent;
 
-use super::Binance;
+use super::{Binance, http_url};
 use crate::{
 	ExchangeError, ExchangeName,
 	core::RequestRange,
@@ -37,7 +36,7 @@ impl Binance {
 			"period": tf,
 		});
 		let params = join_params(base_json, range_json);
-		let options = [BinanceOption::HttpUrl(BinanceHttpUrl::FuturesUsdM)];
+		let options = [http_url(&self.client, Instrument::Perp)];
 		let r: serde_json::Value = self.get(&format!("/futures/data/{ending}"), &params, options).await?;
 		let r: Vec<LsrResponse> = serde_json::from_value(r).unwrap();
 		Ok(Lsrs {
@@ -0,0 +1,252 @@
+//! Converting small balances ("dust", below the min notional of any market) into BNB.
+use std::{backtrace::Backtrace, time::Duration};
+
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+use v_exchanges_adapters::{
+	binance::{BinanceError, BinanceErrorCode, BinanceOption},
+	generics::http::{ApiError, HandleError, RequestError},
+};
+use v_utils::trades::Asset;
+
+use crate::{ExchangeError, ExchangeResult, binance::sapi_signed_options};
+
+/// Balance that can be converted by [convert_dust](super::Binance::convert_dust).
+#[derive(Clone, Debug, PartialEq)]
+pub struct DustAsset {
+	pub asset: Asset,
+	pub amount_free: f64,
+	pub to_btc: f64,
+	/// Estimate, before the fee.
+	pub to_bnb: f64,
+	/// What actually gets credited, `to_bnb` less `fee_bnb`.
+	pub to_bnb_after_fee: f64,
+	pub fee_bnb: f64,
+}
+
+/// One asset of a [DustConversionResult].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DustConversion {
+	pub asset: Asset,
+	/// Of `asset`.
+	pub amount: f64,
+	/// After the fee.
+	pub bnb_received: f64,
+	pub fee_bnb: f64,
+	pub tran_id: u64,
+	pub time: jiff::Timestamp,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DustConversionResult {
+	pub conversions: Vec<DustConversion>,
+	/// After fees.
+	pub total_bnb: f64,
+	pub total_fee_bnb: f64,
+}
+
+/// Refusals of [convert_dust](super::Binance::convert_dust). Carried in [ExchangeError::Other], so match on it with `downcast_ref::<DustError>()`.
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+pub enum DustError {
+	#[error("Not convertible as dust right now: {assets:?}")]
+	#[diagnostic(code(v_exchanges::binance::dust::not_convertible), help("Only assets listed by `dust_assets` can be converted."))]
+	NotConvertible {
+		assets: Vec<Asset>,
+		#[new(value = "Backtrace::capture()")]
+		backtrace: Backtrace,
+	},
+	#[error("Dust was already converted recently. Remaining cooldown: {remaining:?}")]
+	#[diagnostic(code(v_exchanges::binance::dust::cooldown), help("Binance allows one dust conversion every 6 hours."))]
+	Cooldown {
+		/// If the exchange said.
+		remaining: Option<Duration>,
+		#[new(value = "Backtrace::capture()")]
+		backtrace: Backtrace,
+	},
+}
+
+pub(in crate::binance) async fn dust_assets(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<DustAsset>> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	// POST, despite only listing
+	let r: DustBtcResponse = client.post_no_body("/sapi/v1/asset/dust-btc", sapi_signed_options(recv_window)).await?;
+	Ok(r.details.into_iter().map(Into::into).collect())
+}
+
+pub(in crate::binance) async fn convert_dust(client: &v_exchanges_adapters::Client, assets: Vec<Asset>, recv_window: Option<std::time::Duration>) -> ExchangeResult<DustConversionResult> {
+	assert!(client.is_authenticated::<BinanceOption>());
+	if assets.is_empty() {
+		return Err(ExchangeError::Other(eyre::eyre!("No assets to convert")));
+	}
+
+	let convertible = dust_assets(client, recv_window).await?;
+	let missing: Vec<Asset> = assets.iter().filter(|a| !convertible.iter().any(|c| c.asset == **a)).cloned().collect();
+	if !missing.is_empty() {
+		return Err(ExchangeError::Other(DustError::new_not_convertible(missing).into()));
+	}
+
+	// repeated `asset` keys
+	let body: Vec<(&str, String)> = assets.iter().map(|a| ("asset", a.to_string())).collect();
+	let r: DustResponse = client.post("/sapi/v1/asset/dust", &body, sapi_signed_options(recv_window)).await.map_err(cooldown_error)?;
+	r.try_into()
+}
+
+fn cooldown_error(e: RequestError) -> ExchangeError {
+	if let RequestError::HandleResponse(HandleError::Api(ApiError::Other(report))) = &e
+		&& let Some(binance_error) = report.downcast_ref::<BinanceError>()
+		&& (binance_error.code == BinanceErrorCode::Other(-5006) || binance_error.msg.to_lowercase().contains("only convert once"))
+	{
+		return ExchangeError::Other(DustError::new_cooldown(parse_remaining(&binance_error.msg)).into());
+	}
+	e.into()
+}
+
+/// Best effort: the cooldown message normally only states the period, not what's left of it. Reads eg "try again after 2 hours 13 minutes".
+fn parse_remaining(msg: &str) -> Option<Duration> {
+	let msg = msg.to_lowercase();
+	let tail = ["again", "after"].iter().filter_map(|w| msg.rfind(w).map(|i| &msg[i..])).min_by_key(|t| t.len())?;
+	let words: Vec<&str> = tail.split(|c: char| c.is_whitespace() || c == ',' || c == '.').filter(|w| !w.is_empty()).collect();
+	let mut total = Duration::ZERO;
+	for pair in words.windows(2) {
+		let Ok(n) = pair[0].parse::<u64>() else { continue };
+		let unit = match pair[1] {
+			u if u.starts_with('h') => 3600,
+			u if u.starts_with("min") => 60,
+			u if u.starts_with('s') => 1,
+			_ => continue,
+		};
+		total += Duration::from_secs(n * unit);
+	}
+	(!total.is_zero()).then_some(total)
+}
+
+// responses {{{
+#[derive(Debug, Deserialize)]
+struct DustBtcResponse {
+	details: Vec<DustDetail>,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DustDetail {
+	asset: String,
+	#[serde_as(as = "DisplayFromStr")]
+	amount_free: f64,
+	#[serde(rename = "toBTC")]
+	#[serde_as(as = "DisplayFromStr")]
+	to_btc: f64,
+	#[serde(rename = "toBNB")]
+	#[serde_as(as = "DisplayFromStr")]
+	to_bnb: f64,
+	#[serde(rename = "toBNBOffExchange")]
+	#[serde_as(as = "DisplayFromStr")]
+	to_bnb_off_exchange: f64,
+	/// The fee, in BNB.
+	#[serde_as(as = "DisplayFromStr")]
+	exchange: f64,
+}
+impl From<DustDetail> for DustAsset {
+	fn from(d: DustDetail) -> Self {
+		Self {
+			asset: (&*d.asset).into(),
+			amount_free: d.amount_free,
+			to_btc: d.to_btc,
+			to_bnb: d.to_bnb,
+			to_bnb_after_fee: d.to_bnb_off_exchange,
+			fee_bnb: d.exchange,
+		}
+	}
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DustResponse {
+	#[serde_as(as = "DisplayFromStr")]
+	total_service_charge: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	total_transfered: f64,
+	transfer_result: Vec<DustTransfer>,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DustTransfer {
+	from_asset: String,
+	#[serde_as(as = "DisplayFromStr")]
+	amount: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	transfered_amount: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	service_charge_amount: f64,
+	tran_id: u64,
+	operate_time: i64,
+}
+impl TryFrom<DustResponse> for DustConversionResult {
+	type Error = ExchangeError;
+
+	fn try_from(r: DustResponse) -> ExchangeResult<Self> {
+		let conversions = r
+			.transfer_result
+			.into_iter()
+			.map(|t| {
+				Ok(DustConversion {
+					asset: (&*t.from_asset).into(),
+					amount: t.amount,
+					bnb_received: t.transfered_amount,
+					fee_bnb: t.service_charge_amount,
+					tran_id: t.tran_id,
+					time: jiff::Timestamp::from_millisecond(t.operate_time).map_err(|e| ExchangeError::Other(e.into()))?,
+				})
+			})
+			.collect::<ExchangeResult<Vec<_>>>()?;
+		Ok(Self {
+			conversions,
+			total_bnb: r.total_transfered,
+			total_fee_bnb: r.total_service_charge,
+		})
+	}
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dust_btc() {
+		let json = r#"{"details":[{"asset":"ADA","assetFullName":"ADA","amountFree":"6.21","toBTC":"0.00016848","toBNB":"0.01777302","toBNBOffExchange":"0.01741756","exchange":"0.00035546"}],"totalTransferBtc":"0.00016848","totalTransferBNB":"0.01777302","dribbletPercentage":"0.02"}"#;
+		let r: DustBtcResponse = serde_json::from_str(json).unwrap();
+		let assets: Vec<DustAsset> = r.details.into_iter().map(Into::into).collect();
+		assert_eq!(assets[0].asset, Asset::from("ADA"));
+		assert_eq!(assets[0].to_bnb_after_fee, 0.01741756);
+		assert!((assets[0].to_bnb - assets[0].fee_bnb - assets[0].to_bnb_after_fee).abs() < 1e-12);
+	}
+
+	#[test]
+	fn dust_conversion() {
+		let json = r#"{"totalServiceCharge":"0.02102542","totalTransfered":"1.05127099","transferResult":[{"amount":"0.03000000","fromAsset":"ETH","operateTime":1563368549307,"serviceChargeAmount":"0.00500000","tranId":2970932918,"transferedAmount":"0.25000000"},{"amount":"0.09000000","fromAsset":"LTC","operateTime":1563368549404,"serviceChargeAmount":"0.01548000","tranId":2970932918,"transferedAmount":"0.77400000"}]}"#;
+		let r: DustResponse = serde_json::from_str(json).unwrap();
+		let result = DustConversionResult::try_from(r).unwrap();
+		assert_eq!(result.conversions.len(), 2);
+		assert_eq!(result.conversions[1].asset, Asset::from("LTC"));
+		assert_eq!(result.total_bnb, 1.05127099);
+	}
+
+	#[test]
+	fn cooldown() {
+		let e = || BinanceError {
+			code: BinanceErrorCode::from(-5006),
+			msg: "You can only convert once every 6 hours".to_owned(),
+		};
+		let mapped = cooldown_error(RequestError::HandleResponse(HandleError::Api(ApiError::from(e()))));
+		let ExchangeError::Other(report) = mapped else { panic!("{mapped:?}") };
+		assert!(matches!(report.downcast_ref::<DustError>(), Some(DustError::Cooldown { remaining: None, .. })));
+
+		assert_eq!(parse_remaining(&e().msg), None);
+		assert_eq!(
+			parse_remaining("You can only convert once every 6 hours, please try again after 2 hours 13 minutes."),
+			Some(Duration::from_secs(2 * 3600 + 13 * 60))
+		);
+	}
+}
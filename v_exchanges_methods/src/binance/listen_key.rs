@@ -0,0 +1,130 @@
+//! Listen keys, which Binance user data streams are opened with. A key lapses 60 minutes after it was last created or extended.
+use std::time::Duration;
+
+use serde::{Deserialize, de::IgnoredAny};
+use v_exchanges_adapters::{
+	Client,
+	binance::{BinanceAuth, BinanceOption},
+};
+
+use super::http_url;
+use crate::{ExchangeError, ExchangeName, ExchangeResult, Instrument, MethodError};
+
+/// How often [ListenKeyManager::keepalive_loop] extends the key. Well within the 60 minutes, so that a failed extension has time to be retried.
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(29 * 60);
+/// Wait before retrying an extension that failed for reasons other than the key being gone.
+const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Create, extend and delete listen keys for one instrument's user data stream. Get one from [Binance::listen_key_manager](super::Binance::listen_key_manager).
+///
+/// Keys are per account, not per connection: creating one while another is valid returns that same key.
+#[derive(Clone, Copy, Debug)]
+pub struct ListenKeyManager<'a> {
+	client: &'a Client,
+	instrument: Instrument,
+	endpoint: &'static str,
+}
+impl<'a> ListenKeyManager<'a> {
+	pub(super) fn new(client: &'a Client, instrument: Instrument) -> ExchangeResult<Self> {
+		let endpoint = endpoint(instrument).ok_or_else(|| ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Binance, instrument, "listen_key_manager")))?;
+		Ok(Self { client, instrument, endpoint })
+	}
+
+	pub fn endpoint(&self) -> &'static str {
+		self.endpoint
+	}
+
+	pub async fn create(&self) -> ExchangeResult<String> {
+		let r: ListenKeyResponse = self.client.post_no_body(self.endpoint, self.options()).await?;
+		Ok(r.listen_key)
+	}
+
+	/// Pushes the expiry back to 60 minutes from now.
+	pub async fn extend(&self, key: &str) -> ExchangeResult<()> {
+		let _: IgnoredAny = match self.takes_key() {
+			true => self.client.put(self.endpoint, [("listenKey", key)], self.options()).await?,
+			false => self.client.put_no_body(self.endpoint, self.options()).await?,
+		};
+		Ok(())
+	}
+
+	/// Closes the stream opened with `key`.
+	pub async fn delete(&self, key: &str) -> ExchangeResult<()> {
+		let _: IgnoredAny = match self.takes_key() {
+			true => self.client.delete(self.endpoint, &[("listenKey", key)], self.options()).await?,
+			false => self.client.delete_no_query(self.endpoint, self.options()).await?,
+		};
+		Ok(())
+	}
+
+	/// Extends `key` every [KEEPALIVE_INTERVAL], for as long as it's polled. Failed extensions are retried after a minute; only returns once the key itself is rejected, which means it has lapsed and a new one has to be created.
+	///
+	/// ```rust,ignore
+	/// let manager = binance.listen_key_manager(Instrument::Perp)?;
+	/// let key = manager.create().await?;
+	/// tokio::select! {
+	/// 	e = manager.keepalive_loop(key.clone()) => return Err(e),
+	/// 	_ = consume_user_data(&key) => {}
+	/// }
+	/// ```
+	pub async fn keepalive_loop(&self, key: String) -> ExchangeError {
+		let mut wait = KEEPALIVE_INTERVAL;
+		loop {
+			tokio::time::sleep(wait).await;
+			match self.extend(&key).await {
+				Ok(()) => {
+					tracing::debug!(target: "v_exchanges::binance", endpoint = self.endpoint, "extended listen key");
+					wait = KEEPALIVE_INTERVAL;
+				}
+				Err(e) if e.is_auth() => return e,
+				Err(e) => {
+					tracing::warn!(target: "v_exchanges::binance", endpoint = self.endpoint, "failed to extend listen key, retrying in {RETRY_AFTER:?}: {e}");
+					wait = RETRY_AFTER;
+				}
+			}
+		}
+	}
+
+	fn options(&self) -> Vec<BinanceOption> {
+		// USER_STREAM: keyed, not signed
+		vec![http_url(self.client, self.instrument), BinanceOption::HttpAuth(BinanceAuth::Key)]
+	}
+
+	/// Futures keep one key per account, so their extend and delete take no parameters, and reject unread ones.
+	fn takes_key(&self) -> bool {
+		matches!(self.instrument, Instrument::Spot)
+	}
+}
+
+fn endpoint(instrument: Instrument) -> Option<&'static str> {
+	match instrument {
+		Instrument::Spot => Some("/api/v3/userDataStream"),
+		Instrument::Perp => Some("/fapi/v1/listenKey"),
+		Instrument::PerpInverse => Some("/dapi/v1/listenKey"),
+		_ => None,
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListenKeyResponse {
+	listen_key: String,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn endpoints() {
+		assert_eq!(endpoint(Instrument::Spot), Some("/api/v3/userDataStream"));
+		assert_eq!(endpoint(Instrument::Perp), Some("/fapi/v1/listenKey"));
+		assert_eq!(endpoint(Instrument::Options), None);
+	}
+
+	#[test]
+	fn create_response() {
+		let r: ListenKeyResponse = serde_json::from_str(r#"{"listenKey":"pqia91ma19a5s61cv6a81va65sdf19v8a65a1a5s61cv6a81va65sdf19v8a65a1"}"#).unwrap();
+		assert!(r.listen_key.starts_with("pqia91"));
+	}
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use jiff::Timestamp;
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+use v_exchanges_adapters::binance::BinanceOption;
+use v_utils::trades::{Asset, Pair, Usd};
+
+use super::MarginMode;
+use crate::{
+	ExchangeError, ExchangeResult,
+	binance::{sapi_signed_options, spot::account::ApiRestrictionsResponse},
+	core::{ApiKeyInfo, AssetBalance, Balances, PersonalInfo, convert_with},
+};
+
+/// State of a margin account, from `/sapi/v1/margin/account` or `/sapi/v1/margin/isolated/account`.
+#[derive(Clone, Debug)]
+pub struct MarginAccount {
+	/// Total assets over total liabilities. Binance reports `999` when nothing is borrowed.
+	pub margin_level: f64,
+	/// Only assets with a non-zero position or debt.
+	pub assets: Vec<MarginAsset>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MarginAsset {
+	pub asset: Asset,
+	pub free: f64,
+	pub locked: f64,
+	pub borrowed: f64,
+	/// Accrued and not yet repaid.
+	pub interest: f64,
+	/// `free + locked - borrowed - interest`
+	pub net_asset: f64,
+}
+
+pub(in crate::binance) async fn account(client: &v_exchanges_adapters::Client, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<MarginAccount> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let options = sapi_signed_options(recv_window);
+	match mode {
+		MarginMode::Cross => {
+			let r: CrossAccountResponse = client.get_no_query("/sapi/v1/margin/account", options).await?;
+			Ok(MarginAccount {
+				margin_level: r.margin_level,
+				assets: r.user_assets.into_iter().filter_map(MarginAssetResponse::non_zero).collect(),
+			})
+		}
+		MarginMode::Isolated(pair) => {
+			let r: IsolatedAccountResponse = client.get("/sapi/v1/margin/isolated/account", &[("symbols", pair.fmt_binance())], options).await?;
+			let account = r
+				.assets
+				.into_iter()
+				.next()
+				.ok_or_else(|| ExchangeError::Other(eyre::eyre!("No isolated margin account is open for {pair}")))?;
+			Ok(MarginAccount {
+				margin_level: account.margin_level,
+				assets: [account.base_asset, account.quote_asset].into_iter().filter_map(MarginAssetResponse::non_zero).collect(),
+			})
+		}
+	}
+}
+
+/// Cross margin account, with each asset counted at its [net](MarginAsset::net_asset) value, so debt is already subtracted.
+pub(in crate::binance) async fn personal_info(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>, prices: &BTreeMap<Pair, f64>) -> ExchangeResult<PersonalInfo> {
+	let api_options = sapi_signed_options(recv_window);
+	let (account_result, api_result) = tokio::join!(
+		account(client, MarginMode::Cross, recv_window),
+		client.get_no_query::<ApiRestrictionsResponse, _>("/sapi/v1/account/apiRestrictions", api_options),
+	);
+	let account = account_result?;
+	let api_response = api_result?;
+
+	let now = Timestamp::now();
+	let mut asset_balances: Vec<AssetBalance> = Vec::with_capacity(account.assets.len());
+	for a in account.assets {
+		if a.net_asset == 0. {
+			continue;
+		}
+		let usd = convert_with(prices, now, a.net_asset, a.asset, "USDT".into()).ok().map(|q| Usd(q.value));
+		asset_balances.push(AssetBalance::new(a.asset, a.net_asset, usd));
+	}
+	let total = asset_balances.iter().fold(Usd(0.), |acc, b| acc + b.usd.unwrap_or(Usd(0.)));
+
+	let expire_time = api_response
+		.expire_time
+		.map(|ms| Timestamp::from_millisecond(ms).expect("Binance expireTime is valid ms timestamp"));
+
+	Ok(PersonalInfo {
+		api: ApiKeyInfo {
+			expire_time,
+			permissions: api_response.into(),
+		},
+		balances: Balances::new(asset_balances, total),
+	})
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CrossAccountResponse {
+	#[serde_as(as = "DisplayFromStr")]
+	margin_level: f64,
+	user_assets: Vec<MarginAssetResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IsolatedAccountResponse {
+	assets: Vec<IsolatedPairResponse>,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IsolatedPairResponse {
+	base_asset: MarginAssetResponse,
+	quote_asset: MarginAssetResponse,
+	#[serde_as(as = "DisplayFromStr")]
+	margin_level: f64,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MarginAssetResponse {
+	asset: String,
+	#[serde_as(as = "DisplayFromStr")]
+	free: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	locked: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	borrowed: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	interest: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	net_asset: f64,
+}
+impl MarginAssetResponse {
+	/// Binance lists every asset margin supports, mostly empty.
+	fn non_zero(self) -> Option<MarginAsset> {
+		if self.free == 0. && self.locked == 0. && self.borrowed == 0. && self.interest == 0. {
+			return None;
+		}
+		Some(MarginAsset {
+			asset: (&*self.asset).into(),
+			free: self.free,
+			locked: self.locked,
+			borrowed: self.borrowed,
+			interest: self.interest,
+			net_asset: self.net_asset,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn cross_account() {
+		let json = r#"{"borrowEnabled":true,"marginLevel":"11.64405625","totalAssetOfBtc":"6.82728457","totalLiabilityOfBtc":"0.58633215","totalNetAssetOfBtc":"6.24095242","tradeEnabled":true,"transferEnabled":true,"userAssets":[{"asset":"BTC","borrowed":"0.00000000","free":"0.00499500","interest":"0.00000000","locked":"0.00000000","netAsset":"0.00499500"},{"asset":"BNB","borrowed":"201.66666672","free":"2346.50000000","interest":"0.00000000","locked":"0.00000000","netAsset":"2144.83333328"},{"asset":"ETH","borrowed":"0.00000000","free":"0.00000000","interest":"0.00000000","locked":"0.00000000","netAsset":"0.00000000"}]}"#;
+		let r: CrossAccountResponse = serde_json::from_str(json).unwrap();
+		let assets: Vec<MarginAsset> = r.user_assets.into_iter().filter_map(MarginAssetResponse::non_zero).collect();
+		assert_eq!(assets.len(), 2);
+		assert_eq!(assets[1].asset, "BNB");
+		assert_eq!(assets[1].net_asset, 2144.83333328);
+	}
+}
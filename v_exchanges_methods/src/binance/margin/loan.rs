@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+use v_utils::trades::Asset;
+
+use super::MarginMode;
+use crate::{ExchangeResult, binance::sapi_signed_options};
+
+/// How much more of an asset can be borrowed right now.
+#[serde_as]
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaxBorrowable {
+	/// Limited by both the account's collateral and [borrow_limit](Self::borrow_limit).
+	#[serde_as(as = "DisplayFromStr")]
+	pub amount: f64,
+	/// Account tier's cap, regardless of collateral.
+	#[serde_as(as = "DisplayFromStr")]
+	pub borrow_limit: f64,
+}
+
+/// Returns the transaction id.
+pub(in crate::binance) async fn borrow(client: &v_exchanges_adapters::Client, asset: Asset, amount: f64, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<u64> {
+	borrow_repay(client, "BORROW", asset, amount, mode, recv_window).await
+}
+
+/// Returns the transaction id. Interest is repaid first, then the principal.
+pub(in crate::binance) async fn repay(client: &v_exchanges_adapters::Client, asset: Asset, amount: f64, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<u64> {
+	borrow_repay(client, "REPAY", asset, amount, mode, recv_window).await
+}
+
+// Binance retired the separate /sapi/v1/margin/loan and /sapi/v1/margin/repay in favor of this one
+async fn borrow_repay(client: &v_exchanges_adapters::Client, kind: &str, asset: Asset, amount: f64, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<u64> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let mut body = vec![("asset", asset.to_string()), ("amount", amount.to_string()), ("type", kind.to_owned())];
+	body.extend(mode.params());
+	let r: TransactionResponse = client.post("/sapi/v1/margin/borrow-repay", &body, sapi_signed_options(recv_window)).await?;
+	Ok(r.tran_id)
+}
+
+pub(in crate::binance) async fn max_borrowable(client: &v_exchanges_adapters::Client, asset: Asset, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<MaxBorrowable> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let mut params = vec![("asset", asset.to_string())];
+	// unlike the rest, takes no `isIsolated`
+	if let MarginMode::Isolated(pair) = mode {
+		params.push(("isolatedSymbol", pair.fmt_binance()));
+	}
+	let r: MaxBorrowable = client.get("/sapi/v1/margin/maxBorrowable", &params, sapi_signed_options(recv_window)).await?;
+	Ok(r)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionResponse {
+	tran_id: u64,
+}
@@ -0,0 +1,26 @@
+//! Spot margin: account state and borrowing.
+pub mod account;
+pub mod loan;
+
+pub use account::{MarginAccount, MarginAsset};
+pub use loan::MaxBorrowable;
+use v_utils::trades::Pair;
+
+/// Which margin account a call acts on.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MarginMode {
+	/// One account shared by all pairs.
+	#[default]
+	Cross,
+	/// The pair's own account, liquidated independently of everything else.
+	Isolated(Pair),
+}
+impl MarginMode {
+	/// `isIsolated`, plus `symbol` when isolated.
+	fn params(&self) -> Vec<(&'static str, String)> {
+		match self {
+			Self::Cross => vec![("isIsolated", "FALSE".to_owned())],
+			Self::Isolated(pair) => vec![("isIsolated", "TRUE".to_owned()), ("symbol", pair.fmt_binance())],
+		}
+	}
+}
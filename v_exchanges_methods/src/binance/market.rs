@@ -1,18 +1,22 @@
-use std::collections::VecDeque;
+use std::{
+	collections::{BTreeMap, VecDeque},
+	str::FromStr as _,
+};
 
 use eyre::Result;
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use serde_with::{DisplayFromStr, serde_as};
-use v_exchanges_adapters::binance::{BinanceHttpUrl, BinanceOption};
 use v_utils::trades::{Kline, Ohlc, Pair};
 
-use super::BinanceTimeframe;
+use v_exchanges_adapters::binance::{BinanceAuth, BinanceOption};
+
+use super::{BinanceTimeframe, http_url, taker_side};
 use crate::{
-	ExchangeError, ExchangeName, Instrument, PrecisionPriceQty, Symbol,
-	core::{BookShape, Klines, OpenInterest, RequestRange},
-	utils::join_params,
+	ExchangeError, ExchangeName, Instrument, MethodError, OutOfRangeError, PerpInfo, PrecisionPriceQty, Symbol,
+	core::{BookShape, KlineKind, Klines, OpenInterest, OpenInterestHistory, RequestRange, Trade, funding_countdown_from},
+	error::ctx,
 };
 
 // klines {{{
@@ -20,6 +24,7 @@ use crate::{
 [1731448080000,\"88591.90\",\"88630.90\",\"88560.00\",\"88574.10\",\"173.581\",1731448139999,\"15378315.48720\",2800,\"113.654\",\"10069629.84420\",\"0\"]
 ```
 **/
+/// Mark and index price klines come in the same shape, with all volume fields set to `"0"` and `number_of_trades` holding the number of price samples.
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct KlineResponse {
@@ -61,25 +66,26 @@ pub struct OpenInterestResponse {
 	pub cmc_circulating_supply: f64,
 	pub timestamp: i64,
 }
-pub(super) async fn klines(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BinanceTimeframe, range: RequestRange) -> Result<Klines, ExchangeError> {
+pub(super) async fn klines(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BinanceTimeframe, range: RequestRange, kind: KlineKind) -> Result<Klines, ExchangeError> {
 	//TODO: test if embedding params into the url works more consistently (comp number of pairs axum-site is ablle ot get)
 	range.ensure_allowed(1..=1000, tf.as_ref())?;
-	let range_params = range.serialize(ExchangeName::Binance);
-	let base_params = json!({
-		"symbol": symbol.pair.fmt_binance(),
-		"interval": tf.to_string(),
-	});
-	let params = join_params(base_params, range_params);
 
-	let (endpoint_prefix, base_url) = match symbol.instrument {
-		Instrument::Spot => ("/api/v3", BinanceHttpUrl::Spot),
-		Instrument::Perp => ("/fapi/v1", BinanceHttpUrl::FuturesUsdM),
-		Instrument::Margin => todo!(),
-		_ => unimplemented!(),
+	// index price is defined per underlying, so it's keyed by `pair` instead of `symbol`
+	let (endpoint, symbol_key) = match (symbol.instrument, kind) {
+		// margin trades on the spot books
+		(Instrument::Spot | Instrument::Margin, KlineKind::LastPrice) => ("/api/v3/klines", "symbol"),
+		(Instrument::Perp, KlineKind::LastPrice) => ("/fapi/v1/klines", "symbol"),
+		(Instrument::Perp, KlineKind::MarkPrice) => ("/fapi/v1/markPriceKlines", "symbol"),
+		(Instrument::Perp, KlineKind::IndexPrice) => ("/fapi/v1/indexPriceKlines", "pair"),
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Binance, symbol.instrument, "klines"))),
 	};
+	let mut params = Vec::with_capacity(5);
+	params.push((symbol_key, symbol.pair.fmt_binance()));
+	params.push(("interval", tf.to_string()));
+	range.append_exchange_params(ExchangeName::Binance, tf.as_ref(), &mut params);
 
-	let options = vec![BinanceOption::HttpUrl(base_url)];
-	let kline_responses: Vec<KlineResponse> = client.get(&format!("{endpoint_prefix}/klines"), &params, options).await?;
+	let options = vec![http_url(client, symbol.instrument)];
+	let kline_responses: Vec<KlineResponse> = ctx!(client.get(endpoint, &params, options).await, Binance.klines(symbol, tf) -> endpoint)?;
 
 	let r_len = kline_responses.len();
 	let mut klines = VecDeque::with_capacity(r_len);
@@ -94,17 +100,21 @@ pub(super) async fn klines(client: &v_exchanges_adapters::Client, symbol: Symbol
 					low: k.low,
 					close: k.close,
 				};
+				let (volume_quote, trades, taker_buy_volume_quote) = match kind {
+					KlineKind::LastPrice => (k.quote_asset_volume, Some(k.number_of_trades), Some(k.taker_buy_quote_asset_volume)),
+					KlineKind::MarkPrice | KlineKind::IndexPrice => (0., None, None),
+				};
 				klines.push_back(Kline {
 					open_time: Timestamp::from_millisecond(k.open_time).unwrap(),
 					ohlc,
-					volume_quote: k.quote_asset_volume,
-					trades: Some(k.number_of_trades),
-					taker_buy_volume_quote: Some(k.taker_buy_quote_asset_volume),
+					volume_quote,
+					trades,
+					taker_buy_volume_quote,
 				});
 			}
 			false => match i == r_len - 1 {
-				true => tracing::trace!("Skipped last kline in binance request, as it's incomplete (expected behavior)"),
-				false => tracing::warn!("Skipped a kline in binance request, as it's incomplete"),
+				true => tracing::trace!(target: "v_exchanges::binance", "Skipped last kline in binance request, as it's incomplete (expected behavior)"),
+				false => tracing::warn!(target: "v_exchanges::binance", "Skipped a kline in binance request, as it's incomplete"),
 			},
 		}
 	}
@@ -114,35 +124,37 @@ pub(super) async fn klines(client: &v_exchanges_adapters::Client, symbol: Symbol
 //,}}}
 
 // open_interest {{{
-pub(super) async fn open_interest(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BinanceTimeframe, range: RequestRange) -> Result<Vec<OpenInterest>, ExchangeError> {
+pub(super) async fn open_interest(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BinanceTimeframe, range: RequestRange) -> Result<OpenInterestHistory, ExchangeError> {
 	range.ensure_allowed(1..=500, tf.as_ref())?;
-	let range_params = range.serialize(ExchangeName::Binance);
-	let base_params = json!({
-		"symbol": symbol.pair.fmt_binance(),
-		"period": tf.to_string(),
-	});
-	let params = join_params(base_params, range_params);
+	let mut params = Vec::with_capacity(5);
+	params.push(("symbol", symbol.pair.fmt_binance()));
+	params.push(("period", tf.to_string()));
+	range.append_params(ExchangeName::Binance, &mut params);
 
-	let (endpoint, base_url) = match symbol.instrument {
-		Instrument::Perp => ("/futures/data/openInterestHist", BinanceHttpUrl::FuturesUsdM),
-		_ => return Err(ExchangeError::Method(crate::MethodError::new_method_not_supported(ExchangeName::Binance, symbol.instrument))),
+	let endpoint = match symbol.instrument {
+		Instrument::Perp => "/futures/data/openInterestHist",
+		_ => return Err(ExchangeError::Method(crate::MethodError::new_method_not_supported(ExchangeName::Binance, symbol.instrument, "open_interest"))),
 	};
 
-	let options = vec![BinanceOption::HttpUrl(base_url)];
-	let responses: Vec<OpenInterestResponse> = client.get(endpoint, &params, options).await?;
+	let options = vec![http_url(client, symbol.instrument)];
+	let responses: Vec<OpenInterestResponse> = ctx!(client.get(endpoint, &params, options).await, Binance.open_interest(symbol, tf) -> endpoint)?;
 
 	if responses.is_empty() {
 		return Err(ExchangeError::Other(eyre::eyre!("No open interest data returned")));
 	}
 
 	// Convert all responses to OpenInterest
-	let result: Vec<OpenInterest> = responses
+	let result: OpenInterestHistory = responses
 		.into_iter()
-		.map(|r| OpenInterest {
-			val_asset: r.sum_open_interest,
-			val_quote: Some(r.sum_open_interest_value),
-			marketcap: Some(r.cmc_circulating_supply),
-			timestamp: Timestamp::from_millisecond(r.timestamp).unwrap(),
+		.map(|r| {
+			OpenInterest {
+				val_asset: r.sum_open_interest,
+				val_quote: Some(r.sum_open_interest_value),
+				marketcap: Some(r.cmc_circulating_supply),
+				timestamp: Timestamp::from_millisecond(r.timestamp).unwrap(),
+				..Default::default()
+			}
+			.with_usd(symbol.pair)
 		})
 		.collect();
 
@@ -151,6 +163,140 @@ pub(super) async fn open_interest(client: &v_exchanges_adapters::Client, symbol:
 
 //,}}}
 
+// funding {{{
+/// Docs: https://developers.binance.com/docs/derivatives/usds-margined-futures/market-data/rest-api/Mark-Price
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PremiumIndexResponse {
+	#[serde_as(as = "DisplayFromStr")]
+	mark_price: f64,
+	/// Despite the name, the rate of the upcoming funding, as it's estimated now.
+	#[serde_as(as = "DisplayFromStr")]
+	last_funding_rate: f64,
+	next_funding_time: i64,
+	time: i64,
+}
+
+pub(super) async fn funding_countdown(client: &v_exchanges_adapters::Client, symbol: Symbol) -> Result<(std::time::Duration, f64), ExchangeError> {
+	let params = [("symbol", symbol.pair.fmt_binance())];
+	let options = vec![http_url(client, symbol.instrument)];
+	let r: PremiumIndexResponse = client.get("/fapi/v1/premiumIndex", &params, options).await?;
+	Ok((funding_countdown_from(r.next_funding_time, r.time), r.last_funding_rate))
+}
+
+pub(super) async fn mark_price(client: &v_exchanges_adapters::Client, symbol: Symbol) -> Result<f64, ExchangeError> {
+	let params = [("symbol", symbol.pair.fmt_binance())];
+	let options = vec![http_url(client, symbol.instrument)];
+	let r: PremiumIndexResponse = client.get("/fapi/v1/premiumIndex", &params, options).await?;
+	Ok(r.mark_price)
+}
+//,}}}
+
+// perpetual_universe {{{
+/// Without a `symbol`, both return every contract. Delivery contracts come with an empty funding rate, which is what filters them out.
+pub(super) async fn perpetual_universe(client: &v_exchanges_adapters::Client) -> Result<BTreeMap<Pair, PerpInfo>, ExchangeError> {
+	let (premiums, tickers): (Vec<UniversePremiumIndex>, Vec<UniverseTicker24h>) = futures_util::future::try_join(
+		client.get_no_query("/fapi/v1/premiumIndex", vec![http_url(client, Instrument::Perp)]),
+		client.get_no_query("/fapi/v1/ticker/24hr", vec![http_url(client, Instrument::Perp)]),
+	)
+	.await?;
+	Ok(join_universe(premiums, tickers))
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UniversePremiumIndex {
+	symbol: String,
+	#[serde_as(as = "DisplayFromStr")]
+	mark_price: f64,
+	/// `""` for delivery contracts.
+	last_funding_rate: String,
+	next_funding_time: i64,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UniverseTicker24h {
+	symbol: String,
+	#[serde_as(as = "DisplayFromStr")]
+	price_change_percent: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	quote_volume: f64,
+}
+
+fn join_universe(premiums: Vec<UniversePremiumIndex>, tickers: Vec<UniverseTicker24h>) -> BTreeMap<Pair, PerpInfo> {
+	let tickers: BTreeMap<String, UniverseTicker24h> = tickers.into_iter().map(|t| (t.symbol.clone(), t)).collect();
+	premiums
+		.into_iter()
+		.filter_map(|p| {
+			let funding_rate = p.last_funding_rate.parse().ok()?;
+			let ticker = tickers.get(&p.symbol)?;
+			let pair = Pair::from_str(&p.symbol).ok()?;
+			Some((pair, PerpInfo {
+				mark_price: p.mark_price,
+				funding_rate,
+				next_funding_time: Timestamp::from_millisecond(p.next_funding_time).ok()?,
+				// only per-symbol endpoints for it
+				open_interest_usd: None,
+				volume_24h_usd: ticker.quote_volume,
+				price_change_24h_pct: ticker.price_change_percent,
+			}))
+		})
+		.collect()
+}
+//,}}}
+
+// historical trades {{{
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoricalTradeResponse {
+	id: u64,
+	#[serde_as(as = "DisplayFromStr")]
+	price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	qty: f64,
+	time: i64,
+	is_buyer_maker: bool,
+}
+impl TryFrom<HistoricalTradeResponse> for Trade {
+	type Error = ExchangeError;
+
+	fn try_from(r: HistoricalTradeResponse) -> Result<Self, ExchangeError> {
+		Ok(Trade {
+			id: Some(r.id.into()),
+			time: Timestamp::from_millisecond(r.time).map_err(|e| ExchangeError::Other(e.into()))?,
+			qty_asset: r.qty,
+			price: r.price,
+			side: Some(taker_side(r.is_buyer_maker)),
+		})
+	}
+}
+
+/// Public trades of `symbol`, oldest first. Starts at `from_id` when given, otherwise returns the most recent `limit`.
+pub(super) async fn historical_trades(client: &v_exchanges_adapters::Client, symbol: Symbol, limit: u32, from_id: Option<u64>) -> Result<Vec<Trade>, ExchangeError> {
+	const ALLOWED: std::ops::RangeInclusive<u32> = 1..=1000;
+	if !ALLOWED.contains(&limit) {
+		return Err(ExchangeError::Range(OutOfRangeError::new(ALLOWED, limit).into()));
+	}
+	let endpoint = match symbol.instrument {
+		Instrument::Spot | Instrument::Margin => "/api/v3/historicalTrades",
+		Instrument::Perp => "/fapi/v1/historicalTrades",
+		_ => return Err(ExchangeError::Method(crate::MethodError::new_method_not_supported(ExchangeName::Binance, symbol.instrument, "historical_trades"))),
+	};
+	let mut params = vec![("symbol", symbol.pair.fmt_binance()), ("limit", limit.to_string())];
+	if let Some(id) = from_id {
+		params.push(("fromId", id.to_string()));
+	}
+	// MARKET_DATA, but identifies the key
+	let options = vec![http_url(client, symbol.instrument), BinanceOption::HttpAuth(BinanceAuth::Key)];
+	let r: Vec<HistoricalTradeResponse> = client.get(endpoint, &params, options).await?;
+	r.into_iter().map(Trade::try_from).collect()
+}
+//,}}}
+
 // book snapshot {{{
 #[derive(serde::Deserialize)]
 struct DepthResponse {
@@ -159,28 +305,31 @@ struct DepthResponse {
 }
 
 pub(crate) async fn fetch_book_snapshot(client: &v_exchanges_adapters::Client, pair: Pair, instrument: Instrument, prec: PrecisionPriceQty) -> Result<BookShape, ExchangeError> {
-	let (endpoint, base_url) = match instrument {
-		Instrument::Spot | Instrument::Margin => ("/api/v3/depth", BinanceHttpUrl::Spot),
-		Instrument::Perp => ("/fapi/v1/depth", BinanceHttpUrl::FuturesUsdM),
-		_ => unimplemented!(),
+	let endpoint = match instrument {
+		Instrument::Spot | Instrument::Margin => "/api/v3/depth",
+		Instrument::Perp => "/fapi/v1/depth",
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Binance, instrument, "book_snapshot"))),
 	};
 	let params = json!({
 		"symbol": pair.fmt_binance(),
 		"limit": 1000_u32,
 	});
-	let options = vec![BinanceOption::HttpUrl(base_url)];
+	let options = vec![http_url(client, instrument)];
 	let response: DepthResponse = client.get(endpoint, &params, options).await?;
-
-	let now = Timestamp::now();
-	let parse_level = |(p, q): (String, String)| (prec.parse_price(&p), prec.parse_qty(&q));
-	Ok(BookShape {
-		ts_event: now,
-		ts_init: now,
-		ts_last: now,
-		prec,
-		bids: response.bids.into_iter().map(parse_level).collect(),
-		asks: response.asks.into_iter().map(parse_level).collect(),
-	})
+	Ok(response.into_book(prec, Timestamp::now()))
+}
+impl DepthResponse {
+	fn into_book(self, prec: PrecisionPriceQty, now: Timestamp) -> BookShape {
+		let parse_level = |(p, q): (String, String)| (prec.parse_price(&p), prec.parse_qty(&q));
+		BookShape {
+			ts_event: now,
+			ts_init: now,
+			ts_last: now,
+			prec,
+			bids: self.bids.into_iter().map(parse_level).collect(),
+			asks: self.asks.into_iter().map(parse_level).collect(),
+		}
+	}
 }
 //,}}}
 
@@ -191,4 +340,67 @@ mod tests {
 		let raw_str = "[1731448080000,\"88591.90\",\"88630.90\",\"88560.00\",\"88574.10\",\"173.581\",1731448139999,\"15378315.48720\",2800,\"113.654\",\"10069629.84420\",\"0\"]";
 		let _: super::KlineResponse = serde_json::from_str(raw_str).unwrap();
 	}
+
+	#[test]
+	fn perpetual_universe_joins_on_symbol() {
+		let premiums = r#"[
+			{"symbol":"BTCUSDT","markPrice":"67523.10000000","indexPrice":"67530.21","estimatedSettlePrice":"67510.4","lastFundingRate":"0.00010000","interestRate":"0.00010000","nextFundingTime":1729843200000,"time":1729843222921},
+			{"symbol":"BTCUSDT_241227","markPrice":"68900.10000000","indexPrice":"67530.21","estimatedSettlePrice":"67510.4","lastFundingRate":"","interestRate":"","nextFundingTime":0,"time":1729843222921},
+			{"symbol":"ETHUSDT","markPrice":"2531.07000000","indexPrice":"2531.5","estimatedSettlePrice":"2530.9","lastFundingRate":"-0.00002500","interestRate":"0.00010000","nextFundingTime":1729843200000,"time":1729843222921}
+		]"#;
+		let tickers = r#"[
+			{"symbol":"BTCUSDT","priceChange":"823.10","priceChangePercent":"1.234","weightedAvgPrice":"67300","lastPrice":"67523.5","lastQty":"0.010","openPrice":"66700.4","highPrice":"68123.0","lowPrice":"66600.1","volume":"25541.936","quoteVolume":"1725628717.12","openTime":1729756800000,"closeTime":1729843222921,"firstId":1,"lastId":2,"count":2},
+			{"symbol":"BTCUSDT_241227","priceChange":"0","priceChangePercent":"0.000","weightedAvgPrice":"0","lastPrice":"68900","lastQty":"0","openPrice":"68900","highPrice":"68900","lowPrice":"68900","volume":"0","quoteVolume":"0","openTime":1729756800000,"closeTime":1729843222921,"firstId":1,"lastId":1,"count":1}
+		]"#;
+		let universe = super::join_universe(serde_json::from_str(premiums).unwrap(), serde_json::from_str(tickers).unwrap());
+		// delivery contract has no funding; ETH has no ticker
+		assert_eq!(universe.len(), 1);
+		let btc = &universe[&v_utils::trades::Pair::new("BTC", "USDT")];
+		assert_eq!((btc.mark_price, btc.funding_rate), (67523.1, 0.0001));
+		assert_eq!(btc.next_funding_time.as_millisecond(), 1729843200000);
+		assert_eq!((btc.volume_24h_usd, btc.price_change_24h_pct), (1725628717.12, 1.234));
+		assert_eq!(btc.open_interest_usd, None);
+	}
+
+	#[test]
+	fn mark_price_klines() {
+		let raw_str = "[1591256400000,\"9653.69440000\",\"9653.69640000\",\"9651.38600000\",\"9651.55200000\",\"0\",1591256459999,\"0\",60,\"0\",\"0\",\"0\"]";
+		let k: super::KlineResponse = serde_json::from_str(raw_str).unwrap();
+		assert_eq!(k.volume, 0.);
+		assert_eq!(k.number_of_trades, 60);
+	}
+
+	#[test]
+	fn premium_index() {
+		let raw_str = r#"{"symbol":"BTCUSDT","markPrice":"11793.63104562","indexPrice":"11781.80495970","estimatedSettlePrice":"11781.16138815","lastFundingRate":"0.00038246","interestRate":"0.00010000","nextFundingTime":1597392000000,"time":1597370495002}"#;
+		let r: super::PremiumIndexResponse = serde_json::from_str(raw_str).unwrap();
+		assert_eq!(r.mark_price, 11793.63104562);
+		assert_eq!(r.last_funding_rate, 0.00038246);
+		assert_eq!(super::funding_countdown_from(r.next_funding_time, r.time), std::time::Duration::from_millis(21_504_998));
+		// mid-settlement, the next time can lag behind
+		assert_eq!(super::funding_countdown_from(r.time - 1, r.time), std::time::Duration::ZERO);
+	}
+
+	#[test]
+	fn historical_trades() {
+		let raw_str = r#"[{"id":28457,"price":"4.00000100","qty":"12.00000000","quoteQty":"48.000012","time":1499865549590,"isBuyerMaker":true,"isBestMatch":true}]"#;
+		let r: Vec<super::HistoricalTradeResponse> = serde_json::from_str(raw_str).unwrap();
+		let trade = crate::core::Trade::try_from(r.into_iter().next().unwrap()).unwrap();
+		assert_eq!(trade.id, Some(crate::TradeId::Numeric(28457)));
+		assert_eq!(trade.qty_asset, 12.);
+		assert_eq!(trade.side, Some(v_utils::trades::Side::Sell));
+		assert_eq!(trade.signed_qty(), Some(-12.));
+		assert_eq!(trade.time.as_millisecond(), 1499865549590);
+	}
+
+	#[test]
+	fn depth_imbalance() {
+		let raw_str = r#"{"lastUpdateId":1027024,"E":1589436922972,"T":1589436922959,"bids":[["4.00000000","431.00000000"],["3.99000000","9.00000000"],["3.98000000","60.00000000"]],"asks":[["4.00000200","12.00000000"],["4.01000000","18.00000000"],["5.50000000","1000.00000000"]]}"#;
+		let response: super::DepthResponse = serde_json::from_str(raw_str).unwrap();
+		let book = response.into_book(crate::PrecisionPriceQty { price: 8, qty: 2 }, jiff::Timestamp::now());
+		// top 2: bids 431 + 9 = 440, asks 12 + 18 = 30
+		assert!((crate::depth_imbalance(&book, 2) - 410. / 470.).abs() < 1e-9);
+		// the far 1000 ask flips it once included
+		assert!(crate::depth_imbalance(&book, 3) < 0.);
+	}
 }
@@ -1,40 +1,88 @@
 pub mod data; // interfaced with directly, not through `Exchange` trait, thus must be public.
+pub mod dust; // wallet-level, with no counterpart on `Exchange`
+pub mod listen_key; // user data streams are opened with these
 pub mod perp; // public for accessing order placement and income history functions
-use std::collections::BTreeMap;
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, atomic::Ordering},
+};
+pub mod margin; // borrowing isn't expressible through `Exchange`, so reachable directly
 mod market;
+pub mod options; // contracts aren't addressable by `Pair`, so most of it is only reachable directly
 mod spot;
+pub mod subaccount; // master-key operations with no counterpart on `Exchange`
+pub use dust::{DustAsset, DustConversion, DustConversionResult, DustError};
+pub use listen_key::ListenKeyManager;
+pub use margin::{MarginAccount, MarginMode, MaxBorrowable};
+pub use spot::account::BinanceAccountStatus;
+pub use subaccount::{Subaccount, SubaccountAsset, SubaccountWallet};
+#[cfg(feature = "binance-ws")]
 pub mod ws;
 use adapters::{
 	Client, GetOptions,
-	binance::{BinanceOption, BinanceOptions},
+	binance::{BinanceAuth, BinanceHttpUrl, BinanceOption, BinanceOptions},
 };
+use jiff::Timestamp;
 use secrecy::SecretString;
-use v_utils::trades::{Pair, Timeframe};
+use v_utils::trades::{Asset, Pair, Side, Timeframe};
+
+#[cfg(feature = "binance-ws")]
+use adapters::generics::ws::SubscriptionSnapshot;
 
+#[cfg(feature = "binance-ws")]
+use crate::{BatchTrades, BookUpdate, ExchangeStream};
 use crate::{
-	BatchTrades, BookShape, BookUpdate, ExchangeError, ExchangeInfo, ExchangeName, ExchangeResult, ExchangeStream, Klines, MethodError, PrecisionPriceQty, RequestRange,
-	core::{ExchangeImpl, Instrument, PersonalInfo, Symbol},
+	AccountLabel, BookShape, ExchangeError, ExchangeInfo, ExchangeName, ExchangeResult, FeeSchedule, InfoCache, KeyPermissions, KlineKind, Klines, KlinesGuard, MethodError, MutationJournal, Order, OrderDetails, OrderId, OrderPlaced, OrderState, OrderStatus, PerpInfo, PerpUniverseCache, Position,
+	PrecisionPriceQty, RequestRange, Venue,
+	core::{BasisFetcher, BookSnapshotFetcher, ExchangeImpl, Instrument, PersonalInfo, Symbol, Trade},
 };
 
+/// Request weight USD-M futures allows per minute, per IP.
+pub const USDM_WEIGHT_LIMIT_1M: u32 = 2400;
+
+/// [BinanceOption::HttpUrl] for `instrument`, as mapped by the client's [BinanceOptions::http_url_for].
+pub(crate) fn http_url(client: &Client, instrument: Instrument) -> BinanceOption {
+	BinanceOption::HttpUrl(GetOptions::<BinanceOptions>::default_options(client).http_url_for(instrument))
+}
+
+/// Options of a signed `/sapi` request. Those endpoints are wallet-level rather than per instrument, so they're always on the main spot host, regardless of any [BinanceOption::InstrumentHttpUrl] override.
+pub(crate) fn sapi_signed_options(recv_window: Option<std::time::Duration>) -> Vec<BinanceOption> {
+	let mut options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::Spot), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	if let Some(rw) = recv_window {
+		options.push(BinanceOption::RecvWindow(rw));
+	}
+	options
+}
+
+/// Binance reports trades by `isBuyerMaker` (`m` on streams); the taker is on the other side.
+pub(crate) fn taker_side(is_buyer_maker: bool) -> Side {
+	match is_buyer_maker {
+		true => Side::Sell,
+		false => Side::Buy,
+	}
+}
+
 #[derive(Clone, Debug, Default, derive_more::Deref, derive_more::DerefMut)]
 pub struct Binance {
 	#[deref]
 	#[deref_mut]
 	pub client: Client,
-	pub info_cache: BTreeMap<Instrument, ExchangeInfo>,
+	pub info_cache: InfoCache,
+	pub label: Option<AccountLabel>,
+	pub log_context: Option<String>,
+	pub klines_guard: Option<KlinesGuard>,
+	pub mutation_journal: Option<Arc<dyn MutationJournal>>,
+	pub perp_universe: PerpUniverseCache,
 }
 impl Binance {
 	/// Concrete-typed counterpart to [`ExchangeImpl::ws_book`], exposing the connection before boxing.
+	#[cfg(feature = "binance-ws")]
 	pub async fn book_connection(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<ws::BookConnection> {
 		match instrument {
 			Instrument::Perp | Instrument::Spot | Instrument::Margin => {
-				if !self.info_cache.contains_key(&instrument) {
-					let info = ExchangeImpl::exchange_info(&*self, instrument).await?;
-					self.info_cache.insert(instrument, info);
-				}
 				let exchange = self.name();
 				let pair_precisions: BTreeMap<Pair, PrecisionPriceQty> = {
-					let info = self.info_cache.get(&instrument).expect("just inserted or was present");
+					let info = self.info_cache.get_or_fetch(instrument, ExchangeImpl::exchange_info(&*self, instrument)).await?;
 					pairs
 						.iter()
 						.map(|pair| {
@@ -57,39 +105,193 @@ impl Binance {
 				let connection = ws::BookConnection::try_new(self.client.clone(), pairs.to_vec(), instrument, pair_precisions, book_snapshot_freq)?;
 				Ok(connection)
 			}
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument))),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "book_connection"))),
+		}
+	}
+
+	/// Raw `@aggTrade` events of `pairs`, one per taker order and price. [ws_trades](crate::Exchange::ws_trades) rides the same stream, but this keeps the trade id range of each and needs no [exchange_info](crate::Exchange::exchange_info) for precisions. Several times less traffic than `@trade` on busy pairs.
+	#[cfg(feature = "binance-ws")]
+	pub fn ws_agg_trades(&self, pairs: Vec<Pair>, instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = ws::AggTrade>>> {
+		match instrument {
+			Instrument::Perp | Instrument::Spot | Instrument::Margin => Ok(Box::new(ws::AggTradesConnection::try_new(self, &pairs, instrument)?)),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "ws_agg_trades"))),
+		}
+	}
+
+	/// Best bid and ask of `pairs` through `@bookTicker`, on every change to either. Far lighter than following the [book](crate::Exchange::ws_book) for just the top of it.
+	///
+	/// Empty `pairs` subscribes to every perp. Spot dropped its all-market `!bookTicker`, so there `pairs` have to be listed.
+	#[cfg(feature = "binance-ws")]
+	pub fn ws_book_ticker(&self, pairs: Vec<Pair>, instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = ws::BookTicker>>> {
+		match instrument {
+			Instrument::Spot | Instrument::Margin if pairs.is_empty() => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "ws_book_ticker"))),
+			Instrument::Perp | Instrument::Spot | Instrument::Margin => Ok(Box::new(ws::BookTickerConnection::try_new(self, &pairs, instrument)?)),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "ws_book_ticker"))),
 		}
 	}
 
 	pub async fn book_snapshot(&mut self, pair: Pair, instrument: Instrument) -> ExchangeResult<BookShape> {
-		if !self.info_cache.contains_key(&instrument) {
-			let info = ExchangeImpl::exchange_info(&*self, instrument).await?;
-			self.info_cache.insert(instrument, info);
-		}
-		let prec = {
-			let exchange = self.name();
-			let info = &self.info_cache[&instrument];
-			let pi = info
-				.pairs
-				.get(&pair)
-				.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(exchange, instrument, pair)))?;
-			PrecisionPriceQty {
-				price: pi.price_precision,
-				qty: pi.qty_precision,
-			}
-		};
+		let prec = self.pair_precision(pair, instrument).await?;
 		market::fetch_book_snapshot(&self.client, pair, instrument, prec).await
 	}
+
+	async fn pair_precision(&mut self, pair: Pair, instrument: Instrument) -> ExchangeResult<PrecisionPriceQty> {
+		let info = self.info_cache.get_or_fetch(instrument, ExchangeImpl::exchange_info(&*self, instrument)).await?;
+		let exchange = self.name();
+		let pi = info
+			.pairs
+			.get(&pair)
+			.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(exchange, instrument, pair)))?;
+		Ok(PrecisionPriceQty {
+			price: pi.price_precision,
+			qty: pi.qty_precision,
+		})
+	}
+
+	/// Base url requests for `instrument` go to. Override with [BinanceOption::InstrumentHttpUrl].
+	pub fn http_url_for(&self, instrument: Instrument) -> BinanceHttpUrl {
+		GetOptions::<BinanceOptions>::default_options(&self.client).http_url_for(instrument)
+	}
+
+	/// Request weight used this minute, as reported by the last response. `0` until the first request.
+	pub fn used_weight_1m(&self) -> u32 {
+		GetOptions::<BinanceOptions>::default_options(&self.client).used_weight_1m.load(Ordering::Relaxed)
+	}
+
+	/// [used_weight_1m](Self::used_weight_1m) as a percentage of USD-M futures' limit of [USDM_WEIGHT_LIMIT_1M]. Spot's limit is higher, so spot-only usage reads pessimistic.
+	pub fn used_weight_pct(&self) -> f64 {
+		self.used_weight_1m() as f64 / USDM_WEIGHT_LIMIT_1M as f64 * 100.
+	}
+
+	/// Sleeps until the next minute starts (when Binance resets the counter) if [used_weight_pct](Self::used_weight_pct) is above `threshold`; returns immediately otherwise.
+	///
+	/// Reads the usage once, at call time. The returned future doesn't borrow `self`.
+	pub fn wait_if_weight_above_pct(&self, threshold: f64) -> impl Future<Output = ()> + use<> {
+		let used = GetOptions::<BinanceOptions>::default_options(&self.client).used_weight_1m.clone();
+		let weight = used.load(Ordering::Relaxed);
+		let above = weight as f64 / USDM_WEIGHT_LIMIT_1M as f64 * 100. > threshold;
+		async move {
+			if above {
+				let into_minute = Timestamp::now().as_millisecond().rem_euclid(60_000);
+				let wait = std::time::Duration::from_millis((60_000 - into_minute) as u64);
+				tracing::debug!(target: "v_exchanges::binance", weight, ?wait, "Binance weight above {threshold}%, waiting for the minute to roll over");
+				tokio::time::sleep(wait).await;
+				// only responses update the counter, so left alone it'd trip the next check until another request is made. Unless one already reported the new minute's usage.
+				let _ = used.compare_exchange(weight, 0, Ordering::Relaxed, Ordering::Relaxed);
+			}
+		}
+	}
+
+	/// Everyone's trades, as opposed to own fills. Needs only the pubkey of [auth](crate::Exchange::auth): the request is keyed, not signed.
+	///
+	/// `limit` is up to 1000. Starts at trade `from_id` when given, otherwise returns the most recent ones.
+	pub async fn historical_trades(&self, symbol: Symbol, limit: u32, from_id: Option<u64>) -> ExchangeResult<Vec<Trade>> {
+		market::historical_trades(&self.client, symbol, limit, from_id).await
+	}
+
+	/// Perp positions of the account force-closed by the exchange; see [perp::market::liquidation_orders].
+	pub async fn liquidation_orders(&self, symbol: Option<Pair>, auto_close_type: Option<perp::market::AutoCloseType>, range: RequestRange) -> ExchangeResult<Vec<perp::market::LiquidationOrder>> {
+		perp::market::liquidation_orders(&self.client, symbol, auto_close_type, range).await
+	}
+
+	pub async fn account_status(&self, recv_window: Option<std::time::Duration>) -> ExchangeResult<BinanceAccountStatus> {
+		spot::account::account_status(&self.client, recv_window).await
+	}
+
+	pub async fn margin_account(&self, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<MarginAccount> {
+		margin::account::account(&self.client, mode, recv_window).await
+	}
+
+	/// Returns the transaction id.
+	pub async fn margin_borrow(&self, asset: Asset, amount: f64, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<u64> {
+		margin::loan::borrow(&self.client, asset, amount, mode, recv_window).await
+	}
+
+	/// Returns the transaction id.
+	pub async fn margin_repay(&self, asset: Asset, amount: f64, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<u64> {
+		margin::loan::repay(&self.client, asset, amount, mode, recv_window).await
+	}
+
+	pub async fn max_borrowable(&self, asset: Asset, mode: MarginMode, recv_window: Option<std::time::Duration>) -> ExchangeResult<MaxBorrowable> {
+		margin::loan::max_borrowable(&self.client, asset, mode, recv_window).await
+	}
+
+	/// Listen key lifecycle of `instrument`'s user data stream: Spot, Perp or PerpInverse.
+	pub fn listen_key_manager(&self, instrument: Instrument) -> ExchangeResult<ListenKeyManager<'_>> {
+		ListenKeyManager::new(&self.client, instrument)
+	}
+
+	/// Balances that can be converted to BNB right now, with the estimated BNB for each.
+	pub async fn dust_assets(&self, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<DustAsset>> {
+		dust::dust_assets(&self.client, recv_window).await
+	}
+
+	/// Converts `assets` to BNB. Refuses up front if any of them isn't in [dust_assets](Self::dust_assets).
+	///
+	/// Can be done once every 6 hours; see [DustError] for both refusals.
+	pub async fn convert_dust(&self, assets: Vec<Asset>, recv_window: Option<std::time::Duration>) -> ExchangeResult<DustConversionResult> {
+		dust::convert_dust(&self.client, assets, recv_window).await
+	}
+
+	/// All subaccounts of the master account the client is authenticated with.
+	pub async fn subaccounts(&self, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Subaccount>> {
+		subaccount::subaccounts(&self.client, recv_window).await
+	}
+
+	pub async fn subaccount_assets(&self, email: &str, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<SubaccountAsset>> {
+		subaccount::subaccount_assets(&self.client, email, recv_window).await
+	}
+
+	/// Returns the transfer id.
+	pub async fn subaccount_transfer(&self, from: SubaccountWallet, to: SubaccountWallet, asset: Asset, amount: f64, recv_window: Option<std::time::Duration>) -> ExchangeResult<u64> {
+		subaccount::subaccount_transfer(&self.client, from, to, asset, amount, recv_window).await
+	}
 }
 
 #[async_trait::async_trait]
 impl ExchangeImpl for Binance {
-	fn info_cache_mut(&mut self) -> &mut BTreeMap<Instrument, ExchangeInfo> {
-		&mut self.info_cache
+	fn info_cache(&self) -> &InfoCache {
+		&self.info_cache
+	}
+
+	fn label(&self) -> Option<&AccountLabel> {
+		self.label.as_ref()
+	}
+
+	fn label_mut(&mut self) -> &mut Option<AccountLabel> {
+		&mut self.label
+	}
+
+	fn log_context(&self) -> Option<&str> {
+		self.log_context.as_deref()
+	}
+
+	fn log_context_mut(&mut self) -> &mut Option<String> {
+		&mut self.log_context
+	}
+
+	fn klines_guard(&self) -> Option<KlinesGuard> {
+		self.klines_guard
+	}
+
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard> {
+		&mut self.klines_guard
+	}
+
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>> {
+		self.mutation_journal.as_ref()
+	}
+
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>> {
+		&mut self.mutation_journal
+	}
+
+	fn perp_universe_cache(&self) -> Option<&PerpUniverseCache> {
+		Some(&self.perp_universe)
 	}
 
-	fn name(&self) -> ExchangeName {
-		ExchangeName::Binance
+	fn name(&self) -> Venue {
+		ExchangeName::Binance.into()
 	}
 
 	fn auth(&mut self, pubkey: String, secret: SecretString) {
@@ -109,15 +311,35 @@ impl ExchangeImpl for Binance {
 		match instrument {
 			Instrument::Perp => perp::general::exchange_info(self).await,
 			Instrument::Spot | Instrument::Margin => spot::market::exchange_info(self).await,
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument))),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "exchange_info"))),
+		}
+	}
+
+	async fn all_pairs(&self, instrument: Instrument) -> ExchangeResult<Vec<Pair>> {
+		match instrument {
+			Instrument::Perp => perp::general::all_pairs(self).await,
+			Instrument::Spot | Instrument::Margin => spot::market::all_pairs(self).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "all_pairs"))),
 		}
 	}
 
 	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
 		match symbol.instrument {
-			Instrument::Spot | Instrument::Margin => market::klines(self, symbol, tf.try_into()?, range).await,
-			Instrument::Perp => market::klines(self, symbol, tf.try_into()?, range).await,
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument))),
+			Instrument::Spot | Instrument::Margin => market::klines(self, symbol, tf.try_into()?, range, KlineKind::LastPrice).await,
+			Instrument::Perp => market::klines(self, symbol, tf.try_into()?, range, KlineKind::LastPrice).await,
+			Instrument::Options => {
+				let contract = options::OptionsSymbol::from_pair(symbol.pair)?;
+				options::market::klines(self, &contract, tf.try_into()?, range).await
+			}
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "klines"))),
+		}
+	}
+
+	async fn klines_kind(&self, symbol: Symbol, tf: Timeframe, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines> {
+		match (symbol.instrument, kind) {
+			(_, KlineKind::LastPrice) => ExchangeImpl::klines(self, symbol, tf, range).await,
+			(Instrument::Perp, KlineKind::MarkPrice | KlineKind::IndexPrice) => market::klines(self, symbol, tf.try_into()?, range, kind).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "klines_kind"))),
 		}
 	}
 
@@ -125,38 +347,170 @@ impl ExchangeImpl for Binance {
 		match instrument {
 			Instrument::Spot | Instrument::Margin => spot::market::prices(self, pairs).await,
 			Instrument::Perp => perp::market::prices(self, pairs).await,
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument))),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "prices"))),
 		}
 	}
 
-	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Vec<crate::core::OpenInterest>> {
+	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		match symbol.instrument {
+			// the pair carries the contract, see [OptionsSymbol::to_pair](options::OptionsSymbol::to_pair)
+			Instrument::Options => {
+				let contract = options::OptionsSymbol::from_pair(symbol.pair)?;
+				options::market::price(self, &contract).await
+			}
+			_ => self.prices(Some(vec![symbol.pair]), symbol.instrument).await.map(|m| m[&symbol.pair]),
+		}
+	}
+
+	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<crate::core::OpenInterestHistory> {
 		match symbol.instrument {
 			Instrument::Perp => market::open_interest(self, symbol, tf.try_into()?, range).await,
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument))),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "open_interest"))),
+		}
+	}
+
+	async fn funding_countdown(&self, symbol: Symbol) -> ExchangeResult<(std::time::Duration, f64)> {
+		match symbol.instrument {
+			Instrument::Perp => market::funding_countdown(self, symbol).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "funding_countdown"))),
+		}
+	}
+
+	async fn mark_price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		match symbol.instrument {
+			Instrument::Perp => market::mark_price(self, symbol).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "mark_price"))),
 		}
 	}
 
+	async fn perpetual_universe(&self) -> ExchangeResult<BTreeMap<Pair, PerpInfo>> {
+		market::perpetual_universe(self).await
+	}
+
+	async fn basis_fetcher(&self, pair: Pair) -> ExchangeResult<BasisFetcher> {
+		let client = self.client.clone();
+		let exchange = self.name();
+		Ok(Box::new(move || {
+			let client = client.clone();
+			Box::pin(async move {
+				let (spot_prices, mark) = futures_util::future::try_join(spot::market::prices(&client, Some(vec![pair])), market::mark_price(&client, Symbol::new(pair, Instrument::Perp))).await?;
+				let spot = spot_prices
+					.get(&pair)
+					.copied()
+					.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(exchange, Instrument::Spot, pair)))?;
+				Ok((spot, mark))
+			})
+		}))
+	}
+
 	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
 		match instrument {
 			Instrument::Perp => {
 				let prices = self.prices(None, instrument).await?;
 				perp::account::personal_info(self, recv_window, &prices).await
 			}
-			Instrument::Spot | Instrument::Margin => spot::account::personal_info(self, recv_window).await,
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument))),
+			Instrument::Spot => spot::account::personal_info(self, recv_window).await,
+			// cross only; isolated accounts are per-pair, see [Binance::margin_account]
+			Instrument::Margin => {
+				let prices = self.prices(None, Instrument::Spot).await?;
+				margin::account::personal_info(self, recv_window, &prices).await
+			}
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "personal_info"))),
+		}
+	}
+
+	async fn fees(&self, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+		match symbol.instrument {
+			Instrument::Perp => perp::account::fees(self, symbol, recv_window).await,
+			Instrument::Spot | Instrument::Margin => spot::account::fees(self, symbol, recv_window).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "fees"))),
+		}
+	}
+
+	async fn positions(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+		match instrument {
+			Instrument::Perp => perp::account::positions(self, recv_window).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "positions"))),
+		}
+	}
+
+	async fn key_permissions(&self) -> ExchangeResult<KeyPermissions> {
+		spot::account::key_permissions(self).await
+	}
+
+	async fn account_type(&self) -> ExchangeResult<crate::AccountType> {
+		// Binance's portfolio margin is a separate product with its own api, not a mode of these accounts
+		Ok(crate::AccountType::Classic)
+	}
+
+	async fn place_order(&self, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		match symbol.instrument {
+			Instrument::Perp => {
+				let info = self.info_cache.get(symbol.instrument);
+				order.validate(info.as_ref().and_then(|info| info.pairs.get(&symbol.pair)))?;
+				perp::account::place_order(self, symbol, order, recv_window).await
+			}
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "place_order"))),
+		}
+	}
+
+	async fn order_status(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+		match symbol.instrument {
+			Instrument::Perp => perp::account::order_status(self, symbol, order_id, recv_window).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "order_status"))),
+		}
+	}
+
+	async fn get_order(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+		match symbol.instrument {
+			Instrument::Perp => perp::account::get_order(self, symbol, order_id, recv_window).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "get_order"))),
+		}
+	}
+
+	async fn get_orders(&self, instrument: Instrument, pair: Option<Pair>, status: Option<OrderStatus>, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<OrderDetails>> {
+		match instrument {
+			Instrument::Perp => perp::account::get_orders(self, pair, status, recv_window).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "get_orders"))),
+		}
+	}
+
+	async fn amend_order(
+		&self,
+		order_id: OrderId,
+		symbol: Symbol,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<OrderPlaced> {
+		match symbol.instrument {
+			Instrument::Perp => perp::account::amend_order(self, order_id, symbol, new_qty, new_price, recv_window).await,
+			// spot only has `order.amend.keepPriority`, which can't touch price
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "amend_order"))),
+		}
+	}
+
+	async fn book_snapshot_fetcher(&mut self, symbol: Symbol) -> ExchangeResult<BookSnapshotFetcher> {
+		match symbol.instrument {
+			Instrument::Perp | Instrument::Spot | Instrument::Margin => {
+				let prec = self.pair_precision(symbol.pair, symbol.instrument).await?;
+				let client = self.client.clone();
+				Ok(Box::new(move || {
+					let client = client.clone();
+					Box::pin(async move { market::fetch_book_snapshot(&client, symbol.pair, symbol.instrument, prec).await })
+				}))
+			}
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "book_snapshot_fetcher"))),
 		}
 	}
 
+	#[cfg(feature = "binance-ws")]
 	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> Result<Box<dyn ExchangeStream<Item = BatchTrades>>, ExchangeError> {
 		match instrument {
 			Instrument::Perp | Instrument::Spot | Instrument::Margin => {
-				if !self.info_cache.contains_key(&instrument) {
-					let info = ExchangeImpl::exchange_info(&*self, instrument).await?;
-					self.info_cache.insert(instrument, info);
-				}
 				let exchange = self.name();
 				let pair_precisions: BTreeMap<Pair, PrecisionPriceQty> = {
-					let info = self.info_cache.get(&instrument).expect("just inserted or was present");
+					let info = self.info_cache.get_or_fetch(instrument, ExchangeImpl::exchange_info(&*self, instrument)).await?;
 					pairs
 						.iter()
 						.map(|pair| {
@@ -178,18 +532,55 @@ impl ExchangeImpl for Binance {
 				let connection = ws::TradesConnection::try_new(self, pairs, instrument, pair_precisions)?;
 				Ok(Box::new(connection))
 			}
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument))),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "ws_trades"))),
 		}
 	}
 
+	#[cfg(feature = "binance-ws")]
 	async fn ws_book(&mut self, pairs: &[Pair], instrument: Instrument) -> Result<Box<dyn ExchangeStream<Item = BookUpdate>>, ExchangeError> {
 		Ok(Box::new(self.book_connection(pairs, instrument).await?))
 	}
+
+	#[cfg(feature = "binance-ws")]
+	async fn ws_trades_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> Result<Box<dyn ExchangeStream<Item = BatchTrades>>, ExchangeError> {
+		let pairs = crate::core::snapshot_pairs(snapshot, ws::topic_pair)?;
+		let stream = ExchangeImpl::ws_trades(self, &pairs, instrument).await?;
+		crate::core::resumed(snapshot, stream)
+	}
+
+	#[cfg(feature = "binance-ws")]
+	async fn ws_book_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> Result<Box<dyn ExchangeStream<Item = BookUpdate>>, ExchangeError> {
+		let pairs = crate::core::snapshot_pairs(snapshot, ws::topic_pair)?;
+		let stream = ExchangeImpl::ws_book(self, &pairs, instrument).await?;
+		crate::core::resumed(snapshot, stream)
+	}
+
+	#[cfg(feature = "binance-ws")]
+	async fn ws_mark_prices(&self, pairs: &[Pair]) -> Result<Box<dyn ExchangeStream<Item = crate::MarkPriceUpdate>>, ExchangeError> {
+		Ok(Box::new(ws::MarkPriceConnection::try_new(self, pairs)?))
+	}
 }
 
 crate::define_provider_timeframe!(
 	BinanceTimeframe,
 	[
 		"1s", "5s", "15s", "30s", "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "3d", "1w", "1M"
-	]
+	],
+	test = binance_timeframe_table
 );
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn used_weight_shared_across_clones() {
+		let binance = Binance::default();
+		let clone = binance.clone();
+		GetOptions::<BinanceOptions>::default_options(&binance.client).used_weight_1m.store(1200, Ordering::Relaxed);
+		assert_eq!(clone.used_weight_1m(), 1200);
+		assert_eq!(clone.used_weight_pct(), 50.);
+		// below threshold: doesn't wait for the minute
+		tokio::time::timeout(std::time::Duration::from_millis(100), clone.wait_if_weight_above_pct(60.)).await.unwrap();
+	}
+}
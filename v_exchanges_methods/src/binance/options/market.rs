@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use jiff::Timestamp;
+use serde::Deserialize;
+use serde_json::json;
+use serde_with::{DisplayFromStr, serde_as};
+use v_utils::trades::{Kline, Ohlc, Pair};
+
+use super::{BinanceOptionsTimeframe, OptionsSymbol};
+use crate::{
+	ExchangeError, ExchangeName, ExchangeResult, Instrument,
+	binance::http_url,
+	core::{Klines, RequestRange},
+	utils::join_params,
+};
+
+// klines {{{
+pub async fn klines(client: &v_exchanges_adapters::Client, symbol: &OptionsSymbol, tf: BinanceOptionsTimeframe, range: RequestRange) -> ExchangeResult<Klines> {
+	range.ensure_allowed(1..=1500, tf.as_ref())?;
+	let range_params = range.serialize(ExchangeName::Binance);
+	let base_params = json!({
+		"symbol": symbol.fmt_binance(),
+		"interval": tf.to_string(),
+	});
+	let params = join_params(base_params, range_params);
+
+	let options = vec![http_url(client, Instrument::Options)];
+	let rs: Vec<KlineResponse> = client.get("/eapi/v1/klines", &params, options).await?;
+
+	let klines: VecDeque<Kline> = rs
+		.into_iter()
+		// options klines do report a real `closeTime`, so unlike spot/perp we can filter the incomplete one directly
+		.filter(|k| k.close_time < Timestamp::now().as_millisecond())
+		.map(|k| Kline {
+			open_time: Timestamp::from_millisecond(k.open_time).expect("Binance openTime is valid ms"),
+			ohlc: Ohlc {
+				open: k.open,
+				high: k.high,
+				low: k.low,
+				close: k.close,
+			},
+			volume_quote: k.amount,
+			trades: Some(k.trade_count),
+			taker_buy_volume_quote: Some(k.taker_amount),
+		})
+		.collect();
+	Ok(Klines::new(klines, *tf))
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KlineResponse {
+	#[serde_as(as = "DisplayFromStr")]
+	open: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	high: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	low: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	close: f64,
+	/// quote volume
+	#[serde_as(as = "DisplayFromStr")]
+	amount: f64,
+	trade_count: usize,
+	#[serde_as(as = "DisplayFromStr")]
+	taker_amount: f64,
+	open_time: i64,
+	close_time: i64,
+}
+//,}}}
+
+// price {{{
+/// Last traded price of the contract.
+pub async fn price(client: &v_exchanges_adapters::Client, symbol: &OptionsSymbol) -> ExchangeResult<f64> {
+	let options = vec![http_url(client, Instrument::Options)];
+	let symbol_str = symbol.fmt_binance();
+	let rs: Vec<TickerResponse> = client.get("/eapi/v1/ticker", &[("symbol", &symbol_str)], options).await?;
+	last_price_of(rs, &symbol_str)
+}
+
+fn last_price_of(tickers: Vec<TickerResponse>, symbol_str: &str) -> ExchangeResult<f64> {
+	tickers
+		.into_iter()
+		.find(|t| t.symbol == symbol_str)
+		.map(|t| t.last_price)
+		.ok_or_else(|| ExchangeError::Other(eyre::eyre!("No ticker returned for {symbol_str}")))
+}
+
+/// Index price of the underlying.
+pub async fn index_price(client: &v_exchanges_adapters::Client, underlying: Pair) -> ExchangeResult<f64> {
+	let options = vec![http_url(client, Instrument::Options)];
+	let r: IndexResponse = client.get("/eapi/v1/index", &[("underlying", underlying.fmt_binance())], options).await?;
+	Ok(r.index_price)
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TickerResponse {
+	symbol: String,
+	#[serde_as(as = "DisplayFromStr")]
+	last_price: f64,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexResponse {
+	#[serde_as(as = "DisplayFromStr")]
+	index_price: f64,
+}
+//,}}}
+
+// exchange_info {{{
+#[derive(Clone, Debug, Default)]
+pub struct OptionsExchangeInfo {
+	pub server_time: Timestamp,
+	pub contracts: Vec<OptionsContractInfo>,
+}
+#[derive(Clone, Debug)]
+pub struct OptionsContractInfo {
+	pub symbol: OptionsSymbol,
+	pub price_precision: u8,
+	pub qty_precision: u8,
+	pub min_qty: f64,
+	pub expiry: Timestamp,
+}
+
+pub async fn exchange_info(client: &v_exchanges_adapters::Client) -> ExchangeResult<OptionsExchangeInfo> {
+	let options = vec![http_url(client, Instrument::Options)];
+	let r: ExchangeInfoResponse = client.get_no_query("/eapi/v1/exchangeInfo", options).await?;
+	let contracts = r
+		.option_symbols
+		.into_iter()
+		.filter_map(|s| match OptionsSymbol::from_binance_str(&s.symbol) {
+			Ok(symbol) => Some(OptionsContractInfo {
+				symbol,
+				price_precision: s.price_scale,
+				qty_precision: s.quantity_scale,
+				min_qty: s.min_qty,
+				expiry: Timestamp::from_millisecond(s.expiry_date).expect("Binance expiryDate is valid ms"),
+			}),
+			Err(e) => {
+				tracing::warn!(target: "v_exchanges::binance", "Skipping unparseable Binance options symbol {}: {e}", s.symbol);
+				None
+			}
+		})
+		.collect();
+	Ok(OptionsExchangeInfo {
+		server_time: Timestamp::from_millisecond(r.server_time).expect("Binance serverTime is valid ms"),
+		contracts,
+	})
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExchangeInfoResponse {
+	server_time: i64,
+	option_symbols: Vec<OptionSymbolResponse>,
+}
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OptionSymbolResponse {
+	symbol: String,
+	price_scale: u8,
+	quantity_scale: u8,
+	#[serde_as(as = "DisplayFromStr")]
+	min_qty: f64,
+	expiry_date: i64,
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn price_is_the_contracts_not_the_index() {
+		let tickers = r#"[{"symbol":"BTC-241227-100000-C","lastPrice":"1250.5"},{"symbol":"BTC-241227-90000-C","lastPrice":"4100"}]"#;
+		let index = r#"{"time":1729843222921,"indexPrice":"67310.1"}"#;
+		let tickers: Vec<TickerResponse> = serde_json::from_str(tickers).unwrap();
+		let index: IndexResponse = serde_json::from_str(index).unwrap();
+
+		// as `Exchange::price` gets it, through the pair
+		let contract = OptionsSymbol::from_binance_str("BTC-241227-100000-C").unwrap();
+		let symbol = OptionsSymbol::from_pair(contract.to_pair()).unwrap();
+		let price = last_price_of(tickers, &symbol.fmt_binance()).unwrap();
+		assert_eq!(price, 1250.5);
+		assert_ne!(price, index.index_price);
+	}
+}
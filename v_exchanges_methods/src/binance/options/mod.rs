@@ -0,0 +1,98 @@
+pub mod market;
+
+use eyre::{Result, bail, eyre};
+use jiff::civil::Date;
+use v_utils::trades::Pair;
+
+/// European option contract as listed on `eapi.binance.com`, eg `BTC-241227-100000-C`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptionsSymbol {
+	pub underlying: Pair,
+	pub expiry: Date,
+	pub strike: f64,
+	pub option_type: OptionType,
+}
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OptionType {
+	Call,
+	Put,
+}
+impl OptionsSymbol {
+	/// Parses `{underlying}-{YYMMDD}-{strike}-{C|P}`. All Binance options are USDT-settled, so that's the assumed quote.
+	pub fn from_binance_str(s: &str) -> Result<Self> {
+		let parts: Vec<&str> = s.split('-').collect();
+		let [base, expiry, strike, option_type] = parts[..] else {
+			bail!("Expected `{{underlying}}-{{YYMMDD}}-{{strike}}-{{C|P}}`, got: {s}");
+		};
+		let expiry = jiff::fmt::strtime::parse("%y%m%d", expiry).and_then(|t| t.to_date()).map_err(|e| eyre!("Invalid expiry in {s}: {e}"))?;
+		let strike: f64 = strike.parse().map_err(|e| eyre!("Invalid strike in {s}: {e}"))?;
+		let option_type = match option_type {
+			"C" => OptionType::Call,
+			"P" => OptionType::Put,
+			other => bail!("Unknown option type `{other}` in {s}"),
+		};
+		Ok(Self {
+			underlying: Pair::new(base, "USDT"),
+			expiry,
+			strike,
+			option_type,
+		})
+	}
+
+	pub fn fmt_binance(&self) -> String {
+		let cp = match self.option_type {
+			OptionType::Call => "C",
+			OptionType::Put => "P",
+		};
+		format!("{}-{}-{}-{cp}", self.underlying.base(), self.expiry.strftime("%y%m%d"), self.strike)
+	}
+
+	/// How [Exchange](crate::Exchange) methods take a contract under [Instrument::Options](crate::Instrument::Options), as a [Pair] has no room for the rest: the underlying as the base, `{YYMMDD}-{strike}-{C|P}` in place of the quote.
+	pub fn to_pair(&self) -> Pair {
+		let name = self.fmt_binance();
+		let (base, contract) = name.split_once('-').expect("formatted with dashes");
+		Pair::new(base, contract)
+	}
+
+	/// Inverse of [to_pair](Self::to_pair).
+	pub fn from_pair(pair: Pair) -> Result<Self> {
+		Self::from_binance_str(&format!("{}-{}", pair.base(), pair.quote()))
+	}
+}
+impl std::fmt::Display for OptionsSymbol {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.fmt_binance())
+	}
+}
+impl std::str::FromStr for OptionsSymbol {
+	type Err = eyre::Report;
+
+	fn from_str(s: &str) -> Result<Self> {
+		Self::from_binance_str(s)
+	}
+}
+
+crate::define_provider_timeframe!(BinanceOptionsTimeframe, ["1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "12h", "1d", "3d", "1w"], test = binance_options_timeframe_table);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_roundtrip() {
+		let s = "BTC-241227-100000-C";
+		let symbol = OptionsSymbol::from_binance_str(s).unwrap();
+		assert_eq!(symbol.underlying, Pair::new("BTC", "USDT"));
+		assert_eq!(symbol.expiry, jiff::civil::date(2024, 12, 27));
+		assert_eq!(symbol.strike, 100_000.);
+		assert_eq!(symbol.option_type, OptionType::Call);
+		assert_eq!(symbol.fmt_binance(), s);
+		assert_eq!(OptionsSymbol::from_pair(symbol.to_pair()).unwrap(), symbol);
+	}
+
+	#[test]
+	fn parse_rejects_garbage() {
+		assert!(OptionsSymbol::from_binance_str("BTCUSDT").is_err());
+		assert!(OptionsSymbol::from_binance_str("BTC-241227-100000-X").is_err());
+	}
+}
@@ -1,18 +1,26 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, str::FromStr as _};
 
-use eyre::{Result, eyre};
+use arrayvec::ArrayString;
+use eyre::eyre;
 use jiff::Timestamp;
 use serde::Deserialize;
 use serde_with::{DisplayFromStr, serde_as};
-use v_exchanges_adapters::binance::{BinanceAuth, BinanceHttpUrl, BinanceOption};
+use v_exchanges_adapters::{
+	binance::{BinanceAuth, BinanceError, BinanceErrorCode, BinanceOption},
+	generics::http::{ApiError, HandleError, RequestError},
+};
 use v_utils::{
 	macros::ScreamIt,
 	trades::{Asset, Pair, Side, Usd},
 };
 
 use crate::{
-	ExchangeResult,
-	core::{ApiKeyInfo, AssetBalance, Balances, KeyPermission, PersonalInfo},
+	AmendError, ExchangeError, ExchangeResult, Instrument, Order, OrderDetails, OrderId, OrderPlaced, OrderState, OrderStatus, OrderValidationError, OrderViolation, PairInfo, Symbol,
+	TradeRecord,
+	binance::{http_url, sapi_signed_options},
+	core::{ApiKeyInfo, AssetBalance, Balances, FeeSchedule, KeyPermission, PersonalInfo, Position, convert_with},
+	orders::order_violations,
+	utils::ExactNumber,
 };
 
 // balance {{{
@@ -20,7 +28,7 @@ use crate::{
 pub async fn income_history(client: &v_exchanges_adapters::Client, request: IncomeRequest, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<IncomeRecord>> {
 	assert!(client.is_authenticated::<BinanceOption>());
 
-	let mut options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::FuturesUsdM), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	let mut options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
 	if let Some(rw) = recv_window {
 		options.push(BinanceOption::RecvWindow(rw));
 	}
@@ -120,6 +128,26 @@ pub struct OrderRequest {
 	pub price_protect: Option<bool>,
 	pub new_client_order_id: Option<String>,
 }
+impl OrderRequest {
+	/// Like [Order::validate]; on top, the symbol has to be non-empty and every other price-like field sane.
+	pub fn validate(&self, pair_info: Option<&PairInfo>) -> Result<(), OrderValidationError> {
+		let mut violations = order_violations(self.price, self.qty, pair_info);
+		if self.symbol.is_empty() {
+			violations.push(OrderViolation::EmptySymbol);
+		}
+		for (field, value) in [("stop_price", self.stop_price), ("activation_price", self.activation_price), ("callback_rate", self.callback_rate)] {
+			match value {
+				Some(v) if !v.is_finite() => violations.push(OrderViolation::NonFinite { field }),
+				Some(v) if v <= 0. => violations.push(OrderViolation::NonPositive { field, value: v }),
+				_ => {}
+			}
+		}
+		match violations.is_empty() {
+			true => Ok(()),
+			false => Err(OrderValidationError { violations }),
+		}
+	}
+}
 #[derive(Clone, Debug)]
 pub struct IncomeRequest {
 	pub symbol: Option<String>,
@@ -190,12 +218,11 @@ pub enum IncomeType {
 pub(in crate::binance) async fn personal_info(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>, prices: &BTreeMap<Pair, f64>) -> ExchangeResult<PersonalInfo> {
 	assert!(client.is_authenticated::<BinanceOption>());
 
-	let mut balance_options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::FuturesUsdM), BinanceOption::HttpAuth(BinanceAuth::Sign)];
-	let mut api_options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::Spot), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	let mut balance_options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
 	if let Some(rw) = recv_window {
 		balance_options.push(BinanceOption::RecvWindow(rw));
-		api_options.push(BinanceOption::RecvWindow(rw));
 	}
+	let api_options = sapi_signed_options(recv_window);
 
 	let (balance_result, api_result) = tokio::join!(
 		client.get_no_query::<Vec<AssetBalanceResponse>, _>("/fapi/v3/balance", balance_options),
@@ -204,27 +231,19 @@ pub(in crate::binance) async fn personal_info(client: &v_exchanges_adapters::Cli
 	let rs = balance_result?;
 	let api_response = api_result?;
 
-	fn usd_value(underlying: f64, asset: Asset, prices: &BTreeMap<Pair, f64>) -> Result<Usd> {
+	let now = Timestamp::now();
+	let usd_value = |underlying: f64, asset: Asset| -> ExchangeResult<Usd> {
 		if underlying == 0. {
 			return Ok(Usd(0.));
 		}
-		if asset == "USDT" {
-			return Ok(Usd(underlying));
-		}
-		let usdt_pair = Pair::new(asset, "USDT".into());
-		let usdt_price = prices.get(&usdt_pair).ok_or_else(|| eyre!("No usdt price found for {asset}, which has non-zero balance."))?;
-		Ok((underlying * usdt_price).into())
-	}
+		Ok(convert_with(prices, now, underlying, asset, "USDT".into())?.value.into())
+	};
 
 	let mut asset_balances: Vec<AssetBalance> = Vec::with_capacity(rs.len());
 	for r in rs {
 		let asset = r.asset.into();
-		let underlying = r.balance;
-		asset_balances.push(AssetBalance {
-			asset,
-			underlying,
-			usd: Some(usd_value(underlying, asset, prices)?),
-		});
+		let usd = usd_value(r.balance.value, asset)?;
+		asset_balances.push(AssetBalance::from_exact(asset, r.balance, Some(usd)));
 	}
 	let non_zero: Vec<AssetBalance> = asset_balances.iter().filter(|b| b.underlying != 0.).cloned().collect();
 	let total = non_zero.iter().fold(Usd(0.), |acc, b| acc + b.usd.unwrap_or(Usd(0.)));
@@ -242,8 +261,246 @@ pub(in crate::binance) async fn personal_info(client: &v_exchanges_adapters::Cli
 	})
 }
 
+// fees {{{
+pub(in crate::binance) async fn fees(client: &v_exchanges_adapters::Client, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let mut options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	if let Some(rw) = recv_window {
+		options.push(BinanceOption::RecvWindow(rw));
+	}
+	let r: CommissionRateResponse = client.get("/fapi/v1/commissionRate", &[("symbol", symbol.pair.fmt_binance())], options).await?;
+	Ok(FeeSchedule {
+		maker_rate: r.maker_commission_rate,
+		taker_rate: r.taker_commission_rate,
+		..Default::default()
+	})
+}
+
+/// `positionRisk` also lists symbols with only open orders on them, which are dropped here.
+pub(in crate::binance) async fn positions(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let r: Vec<PositionRiskResponse> = client.get_no_query("/fapi/v3/positionRisk", signed_options(client, recv_window)).await?;
+	r.into_iter()
+		.filter(|p| p.position_amt != 0.)
+		.map(|p| -> ExchangeResult<Position> {
+			let pair = Pair::from_str(&p.symbol).map_err(|e| eyre!("Unexpected Binance symbol {}: {e}", p.symbol))?;
+			Ok(Position {
+				symbol: Symbol::new(pair, Instrument::Perp),
+				size: p.position_amt,
+				entry_price: p.entry_price,
+				mark_price: Some(p.mark_price),
+				unrealized_pnl: p.un_realized_profit,
+				updated_at: Timestamp::from_millisecond(p.update_time).map_err(|e| eyre!("Invalid Binance position update time {}: {e}", p.update_time))?,
+			})
+		})
+		.collect()
+}
+//,}}}
+
 // Order Placement {{{
+/// `PUT /fapi/v1/order` demands side, qty and price all present, so whatever isn't being changed is taken from the live order.
+pub(in crate::binance) async fn amend_order(
+	client: &v_exchanges_adapters::Client,
+	order_id: OrderId,
+	symbol: Symbol,
+	new_qty: Option<f64>,
+	new_price: Option<f64>,
+	recv_window: Option<std::time::Duration>,
+) -> ExchangeResult<OrderPlaced> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let symbol_str = symbol.pair.fmt_binance();
+	let id_param = order_id_param(&order_id);
+
+	let current: QueryOrderResponse = client
+		.get("/fapi/v1/order", &[("symbol", symbol_str.clone()), id_param.clone()], signed_options(client, recv_window))
+		.await
+		.map_err(|e| amend_error(e, &order_id))?;
+	let current_status = OrderStatus::from_str(&current.status).map_err(|_| ExchangeError::Other(eyre!("Unexpected Binance order status: {}", current.status)))?;
+	if current_status.is_terminal() {
+		return Err(AmendError::new_order_already_filled(order_id).into());
+	}
+
+	let body = [
+		("symbol", symbol_str),
+		id_param,
+		("side", current.side),
+		("quantity", new_qty.unwrap_or(current.orig_qty).to_string()),
+		("price", new_price.unwrap_or(current.price).to_string()),
+	];
+	let r: OrderResponse = client.put("/fapi/v1/order", &body, signed_options(client, recv_window)).await.map_err(|e| amend_error(e, &order_id))?;
+
+	let status = OrderStatus::from_str(&r.status).map_err(|_| ExchangeError::Other(eyre!("Unexpected Binance order status: {}", r.status)))?;
+	Ok(OrderPlaced::new(
+		OrderId {
+			exchange_id: Some(ArrayString::from(&r.order_id.to_string()).expect("u64 always fits")),
+			..order_id
+		},
+		status,
+	))
+}
+
+/// `POST /fapi/v1/order`. Our client id always goes out as `newClientOrderId`, so a lost response can be reconciled through [order_status].
+pub(in crate::binance) async fn place_order(client: &v_exchanges_adapters::Client, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+	assert!(client.is_authenticated::<BinanceOption>());
 
+	let mut options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	if let Some(rw) = recv_window {
+		options.push(BinanceOption::RecvWindow(rw));
+	}
+	let side = match order.side() {
+		Side::Buy => "BUY",
+		Side::Sell => "SELL",
+	};
+	let mut body = vec![
+		("symbol", symbol.pair.fmt_binance()),
+		("side", side.to_owned()),
+		("quantity", order.qty_param()),
+		("newClientOrderId", order.order_id().client_order_id().to_string()),
+	];
+	let (reduce_only, stp) = match &order {
+		Order::Limit(o) => {
+			body.push(("type", OrderType::Limit.to_string()));
+			body.push(("price", o.price.to_string()));
+			let time_in_force = match (o.post_only, o.time_in_force) {
+				(true, _) => TimeInForce::Gtx.to_string(),
+				(false, crate::TimeInForce::Gtd(until)) => {
+					body.push(("goodTillDate", until.as_millisecond().to_string()));
+					"GTD".to_owned()
+				}
+				(false, crate::TimeInForce::Aon) => return Err(ExchangeError::Other(eyre!("Binance has no all-or-none time in force"))),
+				(false, tif) => tif.to_string(),
+			};
+			body.push(("timeInForce", time_in_force));
+			(o.reduce_only, o.stp)
+		}
+		Order::Market(o) => {
+			body.push(("type", OrderType::Market.to_string()));
+			(o.reduce_only, o.stp)
+		}
+	};
+	if reduce_only {
+		body.push(("reduceOnly", "true".to_owned()));
+	}
+	if let Some(stp) = stp {
+		body.push(("selfTradePreventionMode", stp.to_string()));
+	}
+
+	let r: OrderResponse = client.post("/fapi/v1/order", &body, options).await?;
+	let status = OrderStatus::from_str(&r.status).map_err(|_| ExchangeError::Other(eyre!("Unexpected Binance order status: {}", r.status)))?;
+	Ok(OrderPlaced::new(
+		OrderId {
+			exchange_id: Some(ArrayString::from(&r.order_id.to_string()).expect("u64 always fits")),
+			..order.order_id().clone()
+		},
+		status,
+	))
+}
+
+pub(in crate::binance) async fn order_status(client: &v_exchanges_adapters::Client, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let mut options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	if let Some(rw) = recv_window {
+		options.push(BinanceOption::RecvWindow(rw));
+	}
+	let r: QueryOrderResponse = match client.get("/fapi/v1/order", &[("symbol", symbol.pair.fmt_binance()), order_id_param(&order_id)], options).await {
+		Ok(r) => r,
+		Err(e) if is_no_such_order(&e) => return Ok(OrderState::NotFound),
+		Err(e) => return Err(e.into()),
+	};
+	let status = OrderStatus::from_str(&r.status).map_err(|_| ExchangeError::Other(eyre!("Unexpected Binance order status: {}", r.status)))?;
+	Ok(OrderState::Found(OrderPlaced::new(
+		OrderId {
+			exchange_id: Some(ArrayString::from(&r.order_id.to_string()).expect("u64 always fits")),
+			..order_id
+		},
+		status,
+	)))
+}
+
+/// `GET /fapi/v1/order`, plus `GET /fapi/v1/userTrades` for the fills if there are any.
+pub(in crate::binance) async fn get_order(client: &v_exchanges_adapters::Client, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let r: QueryOrderResponse = client
+		.get("/fapi/v1/order", &[("symbol", symbol.pair.fmt_binance()), order_id_param(&order_id)], signed_options(client, recv_window))
+		.await
+		.map_err(|e| match is_no_such_order(&e) {
+			true => ExchangeError::Other(eyre!("Binance has no order {}", order_id.client_order_id())),
+			false => e.into(),
+		})?;
+	let mut details = r.into_details(Some(order_id), symbol)?;
+	if details.filled_qty > 0. {
+		let params = [("symbol", symbol.pair.fmt_binance()), ("orderId", details.order_id.exchange_id.expect("set from the response").to_string())];
+		let trades: Vec<UserTradeResponse> = client.get("/fapi/v1/userTrades", &params, signed_options(client, recv_window)).await?;
+		details.trades = trades.into_iter().map(TradeRecord::from).collect();
+	}
+	Ok(details)
+}
+
+/// `GET /fapi/v1/openOrders` for open ones (all pairs at once if none given), `GET /fapi/v1/allOrders` for closed ones, which Binance only serves per-pair.
+pub(in crate::binance) async fn get_orders(
+	client: &v_exchanges_adapters::Client,
+	pair: Option<Pair>,
+	status: Option<OrderStatus>,
+	recv_window: Option<std::time::Duration>,
+) -> ExchangeResult<Vec<OrderDetails>> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let params: Vec<(&str, String)> = pair.iter().map(|p| ("symbol", p.fmt_binance())).collect();
+	let orders: Vec<QueryOrderResponse> = match status {
+		None | Some(OrderStatus::New | OrderStatus::PartiallyFilled) => client.get("/fapi/v1/openOrders", &params, signed_options(client, recv_window)).await?,
+		Some(_) => {
+			if pair.is_none() {
+				return Err(ExchangeError::Other(eyre!("Binance only lists closed orders per pair")));
+			}
+			client.get("/fapi/v1/allOrders", &params, signed_options(client, recv_window)).await?
+		}
+	};
+	orders
+		.into_iter()
+		.map(|r| {
+			let pair = Pair::from_str(&r.symbol).map_err(|e| eyre!("Unexpected Binance symbol {}: {e}", r.symbol))?;
+			r.into_details(None, Symbol::new(pair, Instrument::Perp))
+		})
+		.filter(|d| !matches!((d, status), (Ok(d), Some(status)) if d.status != status))
+		.collect()
+}
+
+fn signed_options(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>) -> Vec<BinanceOption> {
+	let mut options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	if let Some(rw) = recv_window {
+		options.push(BinanceOption::RecvWindow(rw));
+	}
+	options
+}
+
+/// Exchange-assigned id is preferred when known; our own client id is always there as the fallback.
+fn order_id_param(order_id: &OrderId) -> (&'static str, String) {
+	match &order_id.exchange_id {
+		Some(exchange_id) => ("orderId", exchange_id.to_string()),
+		None => ("origClientOrderId", order_id.client_order_id().to_string()),
+	}
+}
+
+fn is_no_such_order(e: &RequestError) -> bool {
+	if let RequestError::HandleResponse(HandleError::Api(ApiError::Other(report))) = e
+		&& let Some(binance_error) = report.downcast_ref::<BinanceError>()
+	{
+		return matches!(binance_error.code, BinanceErrorCode::NoSuchOrder(_));
+	}
+	false
+}
+
+fn amend_error(e: RequestError, order_id: &OrderId) -> ExchangeError {
+	if is_no_such_order(&e) {
+		return AmendError::new_order_not_found(order_id.clone()).into();
+	}
+	e.into()
+}
 //,}}}
 
 // Income History {{{
@@ -257,8 +514,7 @@ pub(in crate::binance) async fn personal_info(client: &v_exchanges_adapters::Cli
 struct AssetBalanceResponse {
 	account_alias: String,
 	pub asset: String,
-	#[serde_as(as = "DisplayFromStr")]
-	pub balance: f64,
+	pub balance: ExactNumber,
 	#[serde_as(as = "DisplayFromStr")]
 	cross_wallet_balance: f64,
 	#[serde(rename = "crossUnPnl")]
@@ -274,6 +530,119 @@ struct AssetBalanceResponse {
 //,}}}
 
 // Response Types {{{
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommissionRateResponse {
+	#[serde_as(as = "DisplayFromStr")]
+	maker_commission_rate: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	taker_commission_rate: f64,
+}
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PositionRiskResponse {
+	symbol: String,
+	#[serde_as(as = "DisplayFromStr")]
+	position_amt: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	entry_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	mark_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	un_realized_profit: f64,
+	update_time: i64,
+}
+/// Subset of `GET /fapi/v1/order` (same shape in `openOrders` and `allOrders`) we need for status lookups, [OrderDetails], and to fill in the unchanged fields of an amend.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryOrderResponse {
+	order_id: u64,
+	client_order_id: String,
+	symbol: String,
+	status: String,
+	side: String,
+	#[serde(rename = "type")]
+	order_type: String,
+	#[serde_as(as = "DisplayFromStr")]
+	orig_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	executed_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	avg_price: f64,
+	reduce_only: bool,
+	time: i64,
+	update_time: i64,
+}
+impl QueryOrderResponse {
+	/// `order_id` is what the order was requested by, if it was; otherwise it's reconstructed from the response, with our client id carried over as is.
+	fn into_details(self, order_id: Option<OrderId>, symbol: Symbol) -> ExchangeResult<OrderDetails> {
+		let status = OrderStatus::from_str(&self.status).map_err(|_| ExchangeError::Other(eyre!("Unexpected Binance order status: {}", self.status)))?;
+		let side = match self.side.as_str() {
+			"BUY" => Side::Buy,
+			"SELL" => Side::Sell,
+			other => return Err(ExchangeError::Other(eyre!("Unexpected Binance order side: {other}"))),
+		};
+		let order_id = match order_id {
+			Some(order_id) => order_id,
+			None => OrderId {
+				client_id: ArrayString::from(&self.client_order_id).ok(),
+				..Default::default()
+			},
+		};
+		Ok(OrderDetails {
+			order_id: OrderId {
+				exchange_id: Some(ArrayString::from(&self.order_id.to_string()).expect("u64 always fits")),
+				..order_id
+			},
+			symbol,
+			side,
+			order_type: self.order_type,
+			price: (self.price != 0.).then_some(self.price),
+			avg_price: (self.avg_price != 0.).then_some(self.avg_price),
+			qty: self.orig_qty,
+			filled_qty: self.executed_qty,
+			remaining_qty: self.orig_qty - self.executed_qty,
+			status,
+			reduce_only: self.reduce_only,
+			created_at: Timestamp::from_millisecond(self.time).map_err(|e| eyre!("Invalid Binance order time {}: {e}", self.time))?,
+			updated_at: Timestamp::from_millisecond(self.update_time).map_err(|e| eyre!("Invalid Binance order update time {}: {e}", self.update_time))?,
+			trades: Vec::new(),
+		})
+	}
+}
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UserTradeResponse {
+	id: u64,
+	#[serde_as(as = "DisplayFromStr")]
+	price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	commission: f64,
+	commission_asset: String,
+	maker: bool,
+	time: i64,
+}
+impl From<UserTradeResponse> for TradeRecord {
+	fn from(r: UserTradeResponse) -> Self {
+		Self {
+			trade_id: r.id.to_string(),
+			price: r.price,
+			qty: r.qty,
+			fee: r.commission,
+			fee_asset: (&*r.commission_asset).into(),
+			is_maker: r.maker,
+			time: Timestamp::from_millisecond(r.time).unwrap_or_default(),
+		}
+	}
+}
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ApiRestrictionsResponse {
@@ -338,3 +707,77 @@ impl From<ApiRestrictionsResponse> for Vec<KeyPermission> {
 // Request/Enum Types {{{
 
 //,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request() -> OrderRequest {
+		OrderRequest {
+			symbol: "BTCUSDT".to_owned(),
+			side: Side::Buy,
+			order_type: OrderType::Limit,
+			position_side: None,
+			time_in_force: Some(TimeInForce::Gtc),
+			qty: Some(0.01),
+			price: Some(60_000.),
+			stop_price: None,
+			reduce_only: None,
+			close_position: None,
+			activation_price: None,
+			callback_rate: None,
+			working_type: None,
+			price_protect: None,
+			new_client_order_id: None,
+		}
+	}
+
+	#[test]
+	fn order_request_rejects_nan_and_empty_symbol() {
+		assert_eq!(request().validate(None), Ok(()));
+
+		let bad = OrderRequest {
+			symbol: String::new(),
+			price: Some(f64::NAN),
+			qty: Some(f64::INFINITY),
+			stop_price: Some(-1.),
+			..request()
+		};
+		assert_eq!(bad.validate(None).unwrap_err().violations, vec![
+			OrderViolation::NonFinite { field: "price" },
+			OrderViolation::NonFinite { field: "qty" },
+			OrderViolation::EmptySymbol,
+			OrderViolation::NonPositive { field: "stop_price", value: -1. },
+		]);
+	}
+
+	#[cfg(feature = "decimal")]
+	#[test]
+	fn exact_balance_is_sold_verbatim() {
+		use serde_json::json;
+
+		use crate::{ExactQty, MarketOrder, Qty};
+
+		let r: AssetBalanceResponse = serde_json::from_value(json!({
+			"accountAlias": "SgsR",
+			"asset": "BTC",
+			"balance": "0.10000000000000001",
+			"crossWalletBalance": "0.1",
+			"crossUnPnl": "0",
+			"availableBalance": "0.1",
+			"maxWithdrawAmount": "0.1",
+			"marginAvailable": true,
+			"updateTime": 1617939110373u64
+		}))
+		.unwrap();
+		let balance = AssetBalance::from_exact(r.asset.into(), r.balance, None);
+		assert_eq!(balance.underlying, 0.1);
+
+		let order: Order = MarketOrder {
+			qty_exact: balance.underlying_exact.map(ExactQty::Decimal),
+			..MarketOrder::new(Side::Sell, Qty::from_f64(balance.underlying, 8))
+		}
+		.into();
+		assert_eq!(order.qty_param(), "0.10000000000000001");
+	}
+}
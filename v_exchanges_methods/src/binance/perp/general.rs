@@ -1,6 +1,5 @@
 use std::collections::{BTreeMap, btree_map::Entry};
 
-use adapters::binance::{BinanceHttpUrl, BinanceOption};
 use eyre::Result;
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
@@ -9,18 +8,50 @@ use serde_with::{DisplayFromStr, serde_as};
 use v_utils::trades::Pair;
 
 use crate::{
-	ExchangeError,
+	ExchangeError, Instrument,
+	binance::http_url,
 	core::{ExchangeInfo, PairInfo},
 };
 //TODO: general endpoints, like ping and exchange info
 
 const PERPETUAL_DELIVERY_DATE: i64 = 4133404800000;
 pub async fn exchange_info(client: &v_exchanges_adapters::Client) -> Result<ExchangeInfo, ExchangeError> {
-	let options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::FuturesUsdM)];
+	let options = vec![http_url(client, Instrument::Perp)];
 	let r: BinanceExchangeFutures = client.get_no_query("/fapi/v1/exchangeInfo", options).await?;
 	Ok(r.into())
 }
 
+/// Same endpoint as [exchange_info], but only the asset names get deserialized; filters are most of the payload.
+pub async fn all_pairs(client: &v_exchanges_adapters::Client) -> Result<Vec<Pair>, ExchangeError> {
+	let options = vec![http_url(client, Instrument::Perp)];
+	let r: PairsOnlyResponse = client.get_no_query("/fapi/v1/exchangeInfo", options).await?;
+	Ok(r.into_pairs())
+}
+
+#[derive(Debug, Deserialize)]
+struct PairsOnlyResponse {
+	symbols: Vec<PairsOnlySymbol>,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PairsOnlySymbol {
+	status: String,
+	base_asset: String,
+	quote_asset: String,
+}
+impl PairsOnlyResponse {
+	fn into_pairs(self) -> Vec<Pair> {
+		// delivery contracts share base/quote with their perpetual
+		let pairs: std::collections::BTreeSet<Pair> = self
+			.symbols
+			.into_iter()
+			.filter(|s| s.status == "TRADING")
+			.map(|s| Pair::new(s.base_asset.as_str(), s.quote_asset.as_str()))
+			.collect();
+		pairs.into_iter().collect()
+	}
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BinanceExchangeFutures {
@@ -67,6 +98,14 @@ impl From<FuturesSymbol> for PairInfo {
 			price_precision: v.price_precision,
 			qty_precision: v.quantity_precision as u8,
 			delivery_date,
+			min_qty: v.lot_size_filter().map(|f| f.min_qty),
+			max_qty: v.lot_size_filter().map(|f| f.max_qty),
+			qty_step: v.lot_size_filter().map(|f| f.step_size),
+			min_notional: v.min_notional_filter().map(|f| f.notional),
+			// zero means no bound
+			min_price: v.price_filter().map(|f| f.min_price).filter(|p| *p > 0.),
+			max_price: v.price_filter().map(|f| f.max_price).filter(|p| *p > 0.),
+			status: Some(v.status),
 		}
 	}
 }
@@ -323,6 +362,13 @@ mod tests {
 		let _: MiniSymbol = serde_json::from_value(json).unwrap();
 	}
 
+	#[test]
+	fn pairs_only() {
+		let json = r#"{"timezone":"UTC","serverTime":1565246363776,"symbols":[{"symbol":"BTCUSDT","pair":"BTCUSDT","contractType":"PERPETUAL","status":"TRADING","baseAsset":"BTC","quoteAsset":"USDT","filters":[{"filterType":"PRICE_FILTER","maxPrice":"300","minPrice":"0.0001","tickSize":"0.0001"}]},{"symbol":"BTCUSDT_250926","pair":"BTCUSDT","contractType":"CURRENT_QUARTER","status":"TRADING","baseAsset":"BTC","quoteAsset":"USDT","filters":[]},{"symbol":"ETHBTC","pair":"ETHBTC","contractType":"PERPETUAL","status":"SETTLING","baseAsset":"ETH","quoteAsset":"BTC","filters":[]}]}"#;
+		let r: PairsOnlyResponse = serde_json::from_str(json).unwrap();
+		assert_eq!(r.into_pairs(), vec![Pair::new("BTC", "USDT")]);
+	}
+
 	#[test]
 	fn futures_symbol() {
 		let json = json!({
@@ -1,14 +1,17 @@
 use std::collections::BTreeMap;
 
-use adapters::Client;
+use adapters::{
+	Client,
+	binance::{BinanceAuth, BinanceOption},
+};
+use jiff::Timestamp;
 //HACK: Methods should be implemented on the central interface struct, following <https://github.com/wisespace-io/binance-rs>.
 use serde_with::{DisplayFromStr, serde_as};
-use v_exchanges_adapters::binance::{BinanceHttpUrl, BinanceOption};
 
-use crate::{ExchangeResult, prelude::*};
+use crate::{ExchangeResult, binance::http_url, prelude::*};
 
 pub async fn prices(client: &Client, pairs: Option<Vec<Pair>>) -> ExchangeResult<BTreeMap<Pair, f64>> {
-	let options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::FuturesUsdM)];
+	let options = vec![http_url(client, Instrument::Perp)];
 	let rs: Vec<PriceObject> = match pairs {
 		Some(pairs) => {
 			let symbols_json = serde_json::to_string(&pairs.iter().map(|p| p.fmt_binance()).collect::<Vec<_>>()).expect("Vec<String> always serializes");
@@ -23,7 +26,7 @@ pub async fn prices(client: &Client, pairs: Option<Vec<Pair>>) -> ExchangeResult
 			Ok(pair) => Some((pair, p.price)),
 			// this endpoint only returns concatenated symbol strings, so unrepresentable listings (eg `BTCU`) can only be skipped
 			Err(e) => {
-				tracing::warn!("Skipping unparseable Binance symbol {}: {e}", p.symbol);
+				tracing::warn!(target: "v_exchanges::binance", "Skipping unparseable Binance symbol {}: {e}", p.symbol);
 				None
 			}
 		})
@@ -39,3 +42,107 @@ struct PriceObject {
 	symbol: String,
 	time: i64,
 }
+
+// liquidation_orders {{{
+/// What forced a position closed.
+#[derive(Clone, Copy, Debug, strum::Display, Eq, PartialEq)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum AutoCloseType {
+	Liquidation,
+	/// Auto-deleveraged against someone else's liquidation.
+	Adl,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiquidationOrder {
+	pub symbol: Pair,
+	pub price: f64,
+	/// Filled part, which for a forced close is normally all of it.
+	pub qty: f64,
+	pub avg_price: f64,
+	pub side: Side,
+	pub order_status: OrderStatus,
+	pub time: Timestamp,
+}
+
+/// `GET /fapi/v1/forceOrders`: orders the exchange placed to close the account's positions, latest first. Without a `symbol`, covers every perp.
+///
+/// Binance classes it as USER_DATA, so despite only reading history it's [signed](BinanceAuth::Sign). [Limit](RequestRange::Limit) is up to 100; a [Span](RequestRange::Span) can't exceed 7 days, and only the last 90 days are kept.
+pub async fn liquidation_orders(client: &Client, symbol: Option<Pair>, auto_close_type: Option<AutoCloseType>, range: RequestRange) -> ExchangeResult<Vec<LiquidationOrder>> {
+	assert!(client.is_authenticated::<BinanceOption>());
+	if let RequestRange::Limit(limit) = range {
+		const ALLOWED: std::ops::RangeInclusive<u32> = 1..=100;
+		if !ALLOWED.contains(&limit) {
+			return Err(ExchangeError::Range(OutOfRangeError::new(ALLOWED, limit).into()));
+		}
+	}
+
+	let mut params = Vec::with_capacity(5);
+	if let Some(pair) = symbol {
+		params.push(("symbol", pair.fmt_binance()));
+	}
+	if let Some(t) = auto_close_type {
+		params.push(("autoCloseType", t.to_string()));
+	}
+	range.append_params(ExchangeName::Binance, &mut params);
+
+	let options = vec![http_url(client, Instrument::Perp), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	let r: Vec<ForceOrderResponse> = client.get("/fapi/v1/forceOrders", &params, options).await?;
+	r.into_iter().map(LiquidationOrder::try_from).collect()
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForceOrderResponse {
+	symbol: String,
+	status: String,
+	side: String,
+	#[serde_as(as = "DisplayFromStr")]
+	price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	avg_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	executed_qty: f64,
+	time: i64,
+}
+impl TryFrom<ForceOrderResponse> for LiquidationOrder {
+	type Error = ExchangeError;
+
+	fn try_from(r: ForceOrderResponse) -> Result<Self, Self::Error> {
+		let side = match r.side.as_str() {
+			"BUY" => Side::Buy,
+			"SELL" => Side::Sell,
+			other => return Err(ExchangeError::Other(eyre!("Unexpected Binance order side: {other}"))),
+		};
+		Ok(Self {
+			symbol: Pair::from_str(&r.symbol).map_err(|e| eyre!("Unparseable Binance symbol {}: {e}", r.symbol))?,
+			price: r.price,
+			qty: r.executed_qty,
+			avg_price: r.avg_price,
+			side,
+			order_status: OrderStatus::from_str(&r.status).map_err(|_| ExchangeError::Other(eyre!("Unexpected Binance order status: {}", r.status)))?,
+			time: Timestamp::from_millisecond(r.time).map_err(|e| eyre!("Invalid Binance order time {}: {e}", r.time))?,
+		})
+	}
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn force_order() {
+		let json = r#"[{"orderId":6071832819,"symbol":"BTCUSDT","status":"FILLED","clientOrderId":"autoclose-1596107620040000020","price":"10871.09","avgPrice":"10913.21000","origQty":"0.001","executedQty":"0.001","cumQuote":"10.91321","timeInForce":"IOC","type":"LIMIT","reduceOnly":false,"closePosition":false,"side":"SELL","positionSide":"BOTH","stopPrice":"0","workingType":"CONTRACT_PRICE","origType":"LIMIT","time":1596107620044,"updateTime":1596107620087}]"#;
+		let r: Vec<ForceOrderResponse> = serde_json::from_str(json).unwrap();
+		let order = LiquidationOrder::try_from(r.into_iter().next().unwrap()).unwrap();
+		assert_eq!(order.symbol, Pair::new("BTC", "USDT"));
+		assert_eq!(order.side, Side::Sell);
+		assert_eq!(order.order_status, OrderStatus::Filled);
+		assert_eq!(order.qty, 0.001);
+		assert_eq!(order.avg_price, 10913.21);
+		assert_eq!(order.time, Timestamp::from_millisecond(1596107620044).unwrap());
+		assert_eq!(AutoCloseType::Adl.to_string(), "ADL");
+	}
+}
@@ -1,23 +1,23 @@
 use jiff::Timestamp;
 use serde::Deserialize;
 use serde_with::{DisplayFromStr, serde_as};
-use v_exchanges_adapters::binance::{BinanceAuth, BinanceHttpUrl, BinanceOption};
+use v_exchanges_adapters::binance::{BinanceAuth, BinanceOption};
 use v_utils::trades::{Asset, Pair, Usd};
 
 use crate::{
-	ExchangeResult,
-	core::{ApiKeyInfo, AssetBalance, Balances, KeyPermission, PersonalInfo},
+	ExchangeResult, Instrument, Symbol,
+	binance::{http_url, sapi_signed_options},
+	core::{ApiKeyInfo, AssetBalance, Balances, FeeSchedule, KeyPermission, KeyPermissions, PersonalInfo},
 };
 
 pub async fn personal_info(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
 	assert!(client.is_authenticated::<BinanceOption>());
 
-	let mut balance_options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::Spot), BinanceOption::HttpAuth(BinanceAuth::Sign)];
-	let mut api_options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::Spot), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	let mut balance_options = vec![http_url(client, Instrument::Spot), BinanceOption::HttpAuth(BinanceAuth::Sign)];
 	if let Some(rw) = recv_window {
 		balance_options.push(BinanceOption::RecvWindow(rw));
-		api_options.push(BinanceOption::RecvWindow(rw));
 	}
+	let api_options = sapi_signed_options(recv_window);
 
 	let (balance_result, api_result) = tokio::join!(
 		client.get_no_query::<AccountResponse, _>("/api/v3/account", balance_options),
@@ -41,7 +41,7 @@ pub async fn personal_info(client: &v_exchanges_adapters::Client, recv_window: O
 			let usdt_pair = Pair::new(asset, "USDT".into());
 			prices.get(&usdt_pair).map(|p| Usd(underlying * p))
 		};
-		asset_balances.push(AssetBalance { asset, underlying, usd });
+		asset_balances.push(AssetBalance::new(asset, underlying, usd));
 	}
 	let total = asset_balances.iter().fold(Usd(0.), |acc, b| acc + b.usd.unwrap_or(Usd(0.)));
 
@@ -58,6 +58,67 @@ pub async fn personal_info(client: &v_exchanges_adapters::Client, recv_window: O
 	})
 }
 
+/// `standardCommission` only; BNB-burn discount and tax commission are not folded in.
+pub async fn fees(client: &v_exchanges_adapters::Client, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let mut options = vec![http_url(client, Instrument::Spot), BinanceOption::HttpAuth(BinanceAuth::Sign)];
+	if let Some(rw) = recv_window {
+		options.push(BinanceOption::RecvWindow(rw));
+	}
+	let r: CommissionResponse = client.get("/api/v3/account/commission", &[("symbol", symbol.pair.fmt_binance())], options).await?;
+	Ok(FeeSchedule {
+		maker_rate: r.standard_commission.maker,
+		taker_rate: r.standard_commission.taker,
+		..Default::default()
+	})
+}
+
+/// Whether the account is restricted, from `/sapi/v1/account/status`.
+///
+/// Despite only reading a flag, Binance classes this as USER_DATA, so it's [signed](BinanceAuth::Sign) like everything else tied to an account.
+pub async fn account_status(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<BinanceAccountStatus> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let options = sapi_signed_options(recv_window);
+	let r: BinanceAccountStatus = client.get_no_query("/sapi/v1/account/status", options).await?;
+	Ok(r)
+}
+
+pub async fn key_permissions(client: &v_exchanges_adapters::Client) -> ExchangeResult<KeyPermissions> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let options = sapi_signed_options(None);
+	let r: ApiRestrictionsResponse = client.get_no_query("/sapi/v1/account/apiRestrictions", options).await?;
+	Ok(r.into())
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct BinanceAccountStatus {
+	/// `"Normal"` unless Binance has restricted the account, in which case it describes why.
+	#[serde(rename = "data")]
+	pub status: String,
+}
+impl BinanceAccountStatus {
+	pub fn is_normal(&self) -> bool {
+		self.status == "Normal"
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommissionResponse {
+	standard_commission: CommissionRates,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+struct CommissionRates {
+	#[serde_as(as = "DisplayFromStr")]
+	maker: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	taker: f64,
+}
+
 #[serde_as]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -78,12 +139,11 @@ struct SpotBalance {
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct ApiRestrictionsResponse {
+pub(in crate::binance) struct ApiRestrictionsResponse {
 	/// Millisecond timestamp; absent when no expiry is set
-	expire_time: Option<i64>,
+	pub expire_time: Option<i64>,
 	#[allow(unused)]
 	create_time: i64,
-	#[allow(unused)]
 	ip_restrict: bool,
 	enable_reading: bool,
 	enable_futures: bool,
@@ -135,3 +195,12 @@ impl From<ApiRestrictionsResponse> for Vec<KeyPermission> {
 		out
 	}
 }
+impl From<ApiRestrictionsResponse> for KeyPermissions {
+	fn from(r: ApiRestrictionsResponse) -> Self {
+		Self {
+			ip_restricted: Some(r.ip_restrict),
+			expires: r.expire_time.and_then(|ms| Timestamp::from_millisecond(ms).ok()),
+			..Self::from_permissions(&Vec::<KeyPermission>::from(r))
+		}
+	}
+}
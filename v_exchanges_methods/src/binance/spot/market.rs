@@ -1,6 +1,5 @@
 use std::{collections::BTreeMap, str::FromStr};
 
-use adapters::binance::{BinanceHttpUrl, BinanceOption};
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
@@ -9,13 +8,14 @@ use tracing::instrument;
 use v_utils::trades::Pair;
 
 use crate::{
-	ExchangeResult,
+	ExchangeResult, Instrument,
+	binance::http_url,
 	core::{ExchangeInfo, PairInfo},
 };
 
-#[instrument(skip_all, fields(?pairs))]
+#[instrument(target = "v_exchanges::binance", skip_all, fields(?pairs))]
 pub async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<Vec<Pair>>) -> ExchangeResult<BTreeMap<Pair, f64>> {
-	let options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::Spot)];
+	let options = vec![http_url(client, Instrument::Spot)];
 	let r: PricesResponse = match pairs {
 		Some(pairs) => {
 			let symbols_json = serde_json::to_string(&pairs.iter().map(|p| p.fmt_binance()).collect::<Vec<_>>()).expect("Vec<String> always serializes");
@@ -33,7 +33,7 @@ pub async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<Vec<Pai
 				prices.insert(pair, p.price);
 			}
 			Err(e) => {
-				tracing::warn!("Failed to parse pair from string: {e}");
+				tracing::warn!(target: "v_exchanges::binance", "Failed to parse pair from string: {e}");
 				continue;
 			}
 		};
@@ -42,10 +42,28 @@ pub async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<Vec<Pai
 }
 
 pub async fn exchange_info(client: &v_exchanges_adapters::Client) -> ExchangeResult<ExchangeInfo> {
-	let options = vec![BinanceOption::HttpUrl(BinanceHttpUrl::Spot)];
+	let options = vec![http_url(client, Instrument::Spot)];
 	let r: SpotExchangeInfoResponse = client.get_no_query("/api/v3/exchangeInfo", options).await?;
 	Ok(r.into())
 }
+/// Same endpoint as [exchange_info], but asks for trading symbols only, without permission sets, and deserializes just the asset names.
+pub async fn all_pairs(client: &v_exchanges_adapters::Client) -> ExchangeResult<Vec<Pair>> {
+	let options = vec![http_url(client, Instrument::Spot)];
+	let params = json!({ "symbolStatus": "TRADING", "showPermissionSets": false });
+	let r: PairsOnlyResponse = client.get("/api/v3/exchangeInfo", &params, options).await?;
+	Ok(r.symbols.into_iter().map(|s| Pair::new(s.base_asset.as_str(), s.quote_asset.as_str())).collect())
+}
+#[derive(Debug, Deserialize)]
+struct PairsOnlyResponse {
+	symbols: Vec<PairsOnlySymbol>,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PairsOnlySymbol {
+	base_asset: String,
+	quote_asset: String,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, derive_new::new)]
 struct PricesResponse(Vec<AssetPriceResponse>);
 
@@ -82,7 +100,7 @@ impl From<SpotExchangeInfoResponse> for ExchangeInfo {
 				let pair = match Pair::from_str(&s.symbol) {
 					Ok(p) => p,
 					Err(e) => {
-						tracing::warn!("Failed to parse spot pair {}: {e}", s.symbol);
+						tracing::warn!(target: "v_exchanges::binance", "Failed to parse spot pair {}: {e}", s.symbol);
 						return None;
 					}
 				};
@@ -109,6 +127,10 @@ impl SpotSymbol {
 	fn tick_size(&self) -> Option<&str> {
 		self.filters.iter().find_map(|f| if f["filterType"] == "PRICE_FILTER" { f["tickSize"].as_str() } else { None })
 	}
+
+	fn filter_value(&self, filter_type: &str, key: &str) -> Option<f64> {
+		self.filters.iter().find(|f| f["filterType"] == filter_type).and_then(|f| f[key].as_str()?.parse().ok())
+	}
 }
 
 impl From<SpotSymbol> for PairInfo {
@@ -118,6 +140,15 @@ impl From<SpotSymbol> for PairInfo {
 			price_precision,
 			qty_precision: s.base_asset_precision,
 			delivery_date: None,
+			min_qty: s.filter_value("LOT_SIZE", "minQty"),
+			max_qty: s.filter_value("LOT_SIZE", "maxQty"),
+			qty_step: s.filter_value("LOT_SIZE", "stepSize"),
+			// `MIN_NOTIONAL` was superseded by `NOTIONAL`, but still shows up on some symbols
+			min_notional: s.filter_value("NOTIONAL", "minNotional").or_else(|| s.filter_value("MIN_NOTIONAL", "minNotional")),
+			// zero means no bound
+			min_price: s.filter_value("PRICE_FILTER", "minPrice").filter(|p| *p > 0.),
+			max_price: s.filter_value("PRICE_FILTER", "maxPrice").filter(|p| *p > 0.),
+			status: Some(s.status),
 		}
 	}
 }
@@ -0,0 +1,186 @@
+//! Master-key management of subaccounts. All calls here must be made with the master account's key.
+use serde::Deserialize;
+use v_exchanges_adapters::binance::BinanceOption;
+use v_utils::trades::Asset;
+
+use crate::{ExchangeError, ExchangeResult, Instrument, binance::sapi_signed_options};
+
+#[derive(Clone, Debug)]
+pub struct Subaccount {
+	/// Subaccounts are addressed by email everywhere on Binance.
+	pub email: String,
+	pub is_frozen: bool,
+	pub create_time: jiff::Timestamp,
+	pub is_managed: bool,
+	pub is_asset_management: bool,
+}
+
+/// Spot wallet balance of a subaccount.
+#[derive(Clone, Debug)]
+pub struct SubaccountAsset {
+	pub asset: Asset,
+	pub free: f64,
+	pub locked: f64,
+	pub freeze: f64,
+	pub withdrawing: f64,
+}
+
+/// One side of a [subaccount_transfer](super::Binance::subaccount_transfer).
+#[derive(Clone, Debug)]
+pub struct SubaccountWallet {
+	/// `None` for the master account.
+	pub email: Option<String>,
+	/// Wallet within the account. One of [Spot](Instrument::Spot), [Margin](Instrument::Margin), [Perp](Instrument::Perp) or [PerpInverse](Instrument::PerpInverse).
+	pub instrument: Instrument,
+}
+
+pub(in crate::binance) async fn subaccounts(client: &v_exchanges_adapters::Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Subaccount>> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	const PAGE_SIZE: usize = 200; // max allowed; default is 1
+	let mut subaccounts = Vec::new();
+	for page in 1.. {
+		let params = [("page", page.to_string()), ("limit", PAGE_SIZE.to_string())];
+		let r: SubaccountListResponse = client.get("/sapi/v1/sub-account/list", &params, sapi_signed_options(recv_window)).await?;
+		let n = r.sub_accounts.len();
+		subaccounts.extend(r.sub_accounts.into_iter().map(Subaccount::try_from).collect::<ExchangeResult<Vec<_>>>()?);
+		if n < PAGE_SIZE {
+			break;
+		}
+	}
+	Ok(subaccounts)
+}
+
+pub(in crate::binance) async fn subaccount_assets(client: &v_exchanges_adapters::Client, email: &str, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<SubaccountAsset>> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let r: SubaccountAssetsResponse = client.get("/sapi/v3/sub-account/assets", &[("email", email)], sapi_signed_options(recv_window)).await?;
+	Ok(r.balances.into_iter().map(Into::into).collect())
+}
+
+/// Returns the transfer id.
+pub(in crate::binance) async fn subaccount_transfer(
+	client: &v_exchanges_adapters::Client,
+	from: SubaccountWallet,
+	to: SubaccountWallet,
+	asset: Asset,
+	amount: f64,
+	recv_window: Option<std::time::Duration>,
+) -> ExchangeResult<u64> {
+	assert!(client.is_authenticated::<BinanceOption>());
+
+	let mut body = vec![
+		("fromAccountType", account_type(from.instrument)?.to_owned()),
+		("toAccountType", account_type(to.instrument)?.to_owned()),
+		("asset", asset.to_string()),
+		("amount", amount.to_string()),
+	];
+	if let Some(email) = from.email {
+		body.push(("fromEmail", email));
+	}
+	if let Some(email) = to.email {
+		body.push(("toEmail", email));
+	}
+	let r: TransferResponse = client.post("/sapi/v1/sub-account/universalTransfer", &body, sapi_signed_options(recv_window)).await?;
+	Ok(r.tran_id)
+}
+
+fn account_type(instrument: Instrument) -> ExchangeResult<&'static str> {
+	match instrument {
+		Instrument::Spot => Ok("SPOT"),
+		Instrument::Margin => Ok("MARGIN"),
+		Instrument::Perp => Ok("USDT_FUTURE"),
+		Instrument::PerpInverse => Ok("COIN_FUTURE"),
+		_ => Err(ExchangeError::Other(eyre::eyre!("Binance subaccount transfers don't support {instrument} wallets"))),
+	}
+}
+
+// responses {{{
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubaccountListResponse {
+	sub_accounts: Vec<SubaccountResponse>,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubaccountResponse {
+	email: String,
+	is_freeze: bool,
+	create_time: i64,
+	is_managed_sub_account: bool,
+	is_asset_management_sub_account: bool,
+}
+impl TryFrom<SubaccountResponse> for Subaccount {
+	type Error = ExchangeError;
+
+	fn try_from(r: SubaccountResponse) -> ExchangeResult<Self> {
+		Ok(Self {
+			create_time: jiff::Timestamp::from_millisecond(r.create_time).map_err(|e| ExchangeError::Other(e.into()))?,
+			email: r.email,
+			is_frozen: r.is_freeze,
+			is_managed: r.is_managed_sub_account,
+			is_asset_management: r.is_asset_management_sub_account,
+		})
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct SubaccountAssetsResponse {
+	balances: Vec<SubaccountAssetResponse>,
+}
+/// v3 reports amounts as numbers, unlike most of /sapi
+#[derive(Debug, Deserialize)]
+struct SubaccountAssetResponse {
+	asset: String,
+	free: f64,
+	locked: f64,
+	freeze: f64,
+	withdrawing: f64,
+}
+impl From<SubaccountAssetResponse> for SubaccountAsset {
+	fn from(r: SubaccountAssetResponse) -> Self {
+		Self {
+			asset: (&*r.asset).into(),
+			free: r.free,
+			locked: r.locked,
+			freeze: r.freeze,
+			withdrawing: r.withdrawing,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferResponse {
+	tran_id: u64,
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn subaccount_list() {
+		let json = r#"{"subAccounts":[{"email":"testsub@gmail.com","isFreeze":false,"createTime":1544433328000,"isManagedSubAccount":false,"isAssetManagementSubAccount":false},{"email":"virtual@oxebmvfonoemail.com","isFreeze":false,"createTime":1544433328000,"isManagedSubAccount":false,"isAssetManagementSubAccount":false}]}"#;
+		let r: SubaccountListResponse = serde_json::from_str(json).unwrap();
+		let subaccounts = r.sub_accounts.into_iter().map(Subaccount::try_from).collect::<ExchangeResult<Vec<_>>>().unwrap();
+		assert_eq!(subaccounts.len(), 2);
+		assert_eq!(subaccounts[0].email, "testsub@gmail.com");
+		assert_eq!(subaccounts[0].create_time.as_millisecond(), 1544433328000);
+	}
+
+	#[test]
+	fn subaccount_assets() {
+		let json = r#"{"balances":[{"freeze":0,"withdrawing":0,"asset":"ADA","free":10000,"locked":0},{"freeze":0,"withdrawing":0,"asset":"BNB","free":10003,"locked":0}]}"#;
+		let r: SubaccountAssetsResponse = serde_json::from_str(json).unwrap();
+		let assets: Vec<SubaccountAsset> = r.balances.into_iter().map(Into::into).collect();
+		assert_eq!(assets[1].free, 10003.);
+	}
+
+	#[test]
+	fn transfer() {
+		let r: TransferResponse = serde_json::from_str(r#"{"tranId":11945860693,"clientTranId":"test"}"#).unwrap();
+		assert_eq!(r.tran_id, 11945860693);
+	}
+}
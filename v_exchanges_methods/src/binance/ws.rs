@@ -1,96 +1,147 @@
-use std::{collections::BTreeMap, future::Future, pin::Pin, time::Duration};
+use std::{collections::BTreeMap, future::Future, pin::Pin, str::FromStr as _, time::Duration};
 
 use adapters::{
-	Client,
-	binance::{BinanceOption, BinanceWsHandler, BinanceWsUrl},
-	generics::ws::{WsConnection, WsError},
+	Client, GetOptions,
+	binance::{BinanceOption, BinanceOptions, BinanceWsHandler, BinanceWsUrl},
+	generics::ws::{LatencyStats, SubscriptionSnapshot, TopicInterpreter, TypedRouter, WsConnection, WsError},
 };
-use jiff::Timestamp;
-use v_utils::trades::Pair;
+use arrayvec::ArrayString;
+use jiff::{SignedDuration, Timestamp};
+use serde::Deserialize as _;
+use serde_with::{DisplayFromStr, serde_as};
+use tokio::{sync::mpsc, time::Instant};
+use v_utils::trades::{Pair, Side};
 
 use crate::{
-	BatchTrades, BookShape, BookUpdate, ExchangeError, ExchangeStream, Instrument, PrecisionPriceQty,
+	AccountEvent, BatchTrades, BookShape, BookUpdate, EventTime, ExchangeError, ExchangeName, ExchangeResult, ExchangeStream, FeeReport, Instrument, MarkPriceUpdate, MethodError, OrderId, OrderStatus,
+	OrderUpdate, PrecisionPriceQty, Symbol,
 	core::{InnerTrade, Sequence},
+	dedup::RecentIds,
 };
 
+/// Pair of a stream name, like `btcusdt@aggTrade` or `btcusdt@depth@100ms`.
+pub(super) fn topic_pair(topic: &str) -> Option<Pair> {
+	topic.split('@').next()?.to_uppercase().parse().ok()
+}
+
 // trades {{{
+/// `@aggTrade` of some pairs, read off a [TypedRouter]. Both trade streams below are views of it.
 #[derive(Debug)]
-pub struct TradesConnection {
-	connection: WsConnection<BinanceWsHandler>,
-	instrument: Instrument,
-	pair_precisions: BTreeMap<Pair, PrecisionPriceQty>,
+struct AggTradeFeed {
+	router: TypedRouter<BinanceWsHandler>,
+	events: mpsc::UnboundedReceiver<AggTradeEvent>,
+	/// Agg ids are per pair.
+	dedup: Option<RecentIds<(Pair, u64)>>,
 }
-impl TradesConnection {
-	pub fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> Result<Self, WsError> {
-		let vec_topic_str = pairs.iter().map(|p| format!("{}@trade", p.fmt_binance().to_lowercase())).collect::<Vec<_>>();
-
+impl AggTradeFeed {
+	fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument, method: &'static str) -> ExchangeResult<Self> {
 		let base_url = match instrument {
 			Instrument::Perp => BinanceWsUrl::FuturesUsdM,
 			Instrument::Spot | Instrument::Margin => BinanceWsUrl::Spot,
-			_ => unimplemented!(),
+			_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Binance, instrument, method))),
 		};
-		let connection = client.ws_connection("", vec![BinanceOption::WsUrl(base_url), BinanceOption::WsTopics(vec_topic_str)])?;
+		Self::connect(client, "", base_url, pairs)
+	}
 
-		Ok(Self {
-			connection,
-			instrument,
-			pair_precisions,
-		})
+	fn connect(client: &Client, url: &str, ws_url: BinanceWsUrl, pairs: &[Pair]) -> ExchangeResult<Self> {
+		let vec_topic_str = pairs.iter().map(|p| format!("{}@aggTrade", p.fmt_binance().to_lowercase())).collect::<Vec<_>>();
+		let connection = client.ws_connection(url, vec![BinanceOption::WsUrl(ws_url), BinanceOption::WsTopics(vec_topic_str)])?;
+		let router = TypedRouter::new(connection);
+		let events = router.register(TopicInterpreter {
+			event_name: "aggTrade".to_owned(),
+			interpret: AggTradeEvent::interpret,
+		})?;
+		let dedup = GetOptions::<BinanceOptions>::default_options(client).ws_config.dedup_window.map(RecentIds::new);
+
+		Ok(Self { router, events, dedup })
+	}
+
+	/// Waits for one event, then takes every other one already there, like [WsConnection::next] does with frames. Those delivered before are skipped.
+	async fn next(&mut self) -> Result<Vec<AggTradeEvent>, WsError> {
+		// started here rather than on construction, as [WsConnection] only connects on the first read too
+		if !self.router.is_started() {
+			self.router.start().map_err(WsError::Other)?;
+		}
+		let Some(first) = self.events.recv().await else {
+			return Err(self.router.join().await.unwrap_or(WsError::Closed));
+		};
+		let received = Instant::now();
+		let mut batch = vec![first];
+		while let Ok(event) = self.events.try_recv() {
+			batch.push(event);
+		}
+		if let Some(dedup) = &mut self.dedup {
+			batch.retain(|e| {
+				let admitted = dedup.admit((e.pair(), e.agg_id), received);
+				if !admitted {
+					tracing::debug!(target: "v_exchanges::binance", pair = %e.pair, agg_id = e.agg_id, "Skipping a trade already delivered");
+				}
+				admitted
+			});
+		}
+		Ok(batch)
+	}
+}
+
+#[derive(Debug)]
+pub struct TradesConnection {
+	feed: AggTradeFeed,
+	pair_precisions: BTreeMap<Pair, PrecisionPriceQty>,
+}
+impl TradesConnection {
+	pub fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> ExchangeResult<Self> {
+		let feed = AggTradeFeed::try_new(client, pairs, instrument, "ws_trades")?;
+		Ok(Self { feed, pair_precisions })
+	}
+
+	fn connect(client: &Client, url: &str, ws_url: BinanceWsUrl, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> ExchangeResult<Self> {
+		let pairs = pair_precisions.keys().copied().collect::<Vec<_>>();
+		let feed = AggTradeFeed::connect(client, url, ws_url, &pairs)?;
+		Ok(Self { feed, pair_precisions })
 	}
 }
 #[async_trait::async_trait]
 impl ExchangeStream for TradesConnection {
 	type Item = BatchTrades;
 
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.feed.router.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.feed.router.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.feed.router.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.feed.dedup.as_ref().map(RecentIds::skipped)
+	}
+
 	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
-		let batch = self.connection.next().await?;
-		// One `@trade` connection subscribes many pairs, so a drained batch can carry trades for
+		let batch = self.feed.next().await?;
+		// One `@aggTrade` connection subscribes many pairs, so a drained batch can carry trades for
 		// multiple pairs. `BatchTrades` shares one `prec`, so we group per pair — one `BatchTrades`
 		// each. The per-pair `BTreeMap` groups by `Pair` key (not arrival order), but trades within a
 		// pair stay in arrival order, so `ts_event` stays latest.
 		let mut by_pair: BTreeMap<Pair, (PrecisionPriceQty, Vec<InnerTrade>)> = BTreeMap::new();
-		for content_event in batch {
-			// Read `data` for the zero-skip diagnostic up front so the typed parse below can *move* it
-			// (no clone). The `X` field only exists on the perp payload; spot has no `X` (its "NA"
-			// branch never fires) so a missing `X` reads as the benign "NA" default. Cheap `get` — the
-			// full `raw_json` render stays inside the rare warn branch, off the hot path.
-			let is_na_artifact = content_event.data.get("X").and_then(|x| x.as_str()).unwrap_or("NA") == "NA";
-
-			let (pair_str, timestamp, qty_asset_str, price_str) = match self.instrument {
-				Instrument::Perp => {
-					let parsed = serde_json::from_value::<TradeEventPerp>(content_event.data).expect("Exchange responded with invalid trade event");
-					(parsed.pair, parsed.timestamp, parsed.qty_asset, parsed.price)
-				}
-				Instrument::Spot | Instrument::Margin => {
-					let parsed = serde_json::from_value::<TradeEventSpot>(content_event.data).expect("Exchange responded with invalid trade event");
-					(parsed.pair, parsed.timestamp, parsed.qty_asset, parsed.price)
-				}
-				_ => unimplemented!(),
-			};
-			let pair: Pair = pair_str.as_str().try_into().unwrap_or_else(|_| panic!("failed to parse pair from trade event: {pair_str}"));
+		for parsed in batch {
+			let pair = parsed.pair();
 			let prec = *self.pair_precisions.get(&pair).unwrap_or_else(|| panic!("{pair} not in pair_precisions"));
 
-			let price_raw = prec.parse_price(&price_str);
-			let qty_raw = prec.parse_qty(&qty_asset_str);
-			if price_raw == 0 || qty_raw == 0 {
-				if !is_na_artifact {
-					tracing::warn!(
-						pair = %pair_str,
-						price = %price_str,
-						qty = %qty_asset_str,
-						topic = %content_event.topic,
-						event_type = %content_event.event_type,
-						event_time = %content_event.time,
-						"Binance sent a zero-valued trade we're discarding. Per the official docs this is NOT RPI (Retail Price Improvement) — RPI fills carry their real qty in `q` with no tag; only `nq` excludes them, and RPI only zeroes levels on the @depth stream, never @trade. It's also NOT the perp/options `X` trade-type enum (X=NA/ADL/INSURANCE_FUND lives on options `<underlyingAsset>@trade` and futures simply *exclude* insurance-fund/ADL from aggTrade — neither applies to this spot @trade socket). The one documented spot mechanism that zeroes fill fields is Self-Trade Prevention (executionReport x=TRADE_PREVENTION, fields pl/pL/pY), but those true values are emitted ONLY on the authenticated user-data stream of the account involved — they are intentionally absent from this public market tap, so we *could* scrape them via user-data but currently don't. Remaining possibility: a control/combined-stream frame mis-mapped onto the trade struct. To disambiguate, capture the raw frame's t (trade id), m, M.",
-					)
-				}
+			let Some(trade) = parsed.to_inner(prec) else {
+				tracing::warn!(
+					target: "v_exchanges::binance",
+					pair = %parsed.pair,
+					price = %parsed.price,
+					qty = %parsed.qty_asset,
+					agg_id = parsed.agg_id,
+					trade_time = parsed.timestamp,
+					"Binance sent a zero-valued trade we're discarding. Per the official docs this is NOT RPI (Retail Price Improvement) — RPI fills carry their real qty in `q` with no tag; only `nq` excludes them, and RPI only zeroes levels on the @depth stream, never on trades. Futures aggTrade excludes insurance-fund/ADL fills outright, so it's not the `X` trade-type enum of the raw @trade stream either. The one documented spot mechanism that zeroes fill fields is Self-Trade Prevention (executionReport x=TRADE_PREVENTION, fields pl/pL/pY), but those true values are emitted ONLY on the authenticated user-data stream of the account involved — they are intentionally absent from this public market tap. Remaining possibility: a control/combined-stream frame mis-mapped onto the trade struct. To disambiguate, capture the raw frame's a, f, l, m.",
+				);
 				continue;
-			}
-
-			let trade = InnerTrade {
-				time: Timestamp::from_millisecond(timestamp).expect("Exchange responded with invalid timestamp"),
-				price: price_raw,
-				qty: qty_raw,
 			};
 			by_pair.entry(pair).or_insert((prec, Vec::new())).1.push(trade);
 		}
@@ -101,36 +152,315 @@ impl ExchangeStream for TradesConnection {
 	}
 }
 
+/// `@aggTrade` event, used for spot and perp alike: it's the one trade stream both carry with the same fields, and perp's raw `@trade` mixes in `X` = ADL/insurance-fund fills that aggTrade leaves out. One event is every fill of a single taker order at one price, so `m` holds for all of it.
+///
+/// Spot: https://developers.binance.com/docs/binance-spot-api-docs/web-socket-streams#aggregate-trade-streams
+/// Futures: https://developers.binance.com/docs/derivatives/usds-margined-futures/websocket-market-streams/Aggregate-Trade-Streams
 #[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
-pub struct TradeEventPerp {
+pub struct AggTradeEvent {
+	#[serde(rename = "a")]
+	agg_id: u64,
 	#[serde(rename = "T")]
 	timestamp: i64,
-	#[serde(rename = "X")]
-	_order_type: String,
-	#[serde(rename = "m")]
-	_is_maker: bool,
 	#[serde(rename = "q")]
 	qty_asset: String,
 	#[serde(rename = "p")]
 	price: String,
 	#[serde(rename = "s")]
 	pair: String,
-	#[serde(rename = "t")]
-	_trade_id: u64,
+	#[serde(rename = "f")]
+	first_trade_id: u64,
+	#[serde(rename = "l")]
+	last_trade_id: u64,
+	/// Buyer was the maker.
+	#[serde(rename = "m")]
+	is_buyer_maker: bool,
 }
+impl AggTradeEvent {
+	fn interpret(data: &serde_json::Value) -> Result<Self, WsError> {
+		Self::deserialize(data).map_err(WsError::Parse)
+	}
 
-#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
-pub struct TradeEventSpot {
+	fn pair(&self) -> Pair {
+		self.pair.as_str().try_into().unwrap_or_else(|_| panic!("failed to parse pair from trade event: {}", self.pair))
+	}
+
+	fn to_agg_trade(&self) -> AggTrade {
+		AggTrade {
+			pair: self.pair(),
+			agg_trade_id: self.agg_id,
+			price: self.price.parse().expect("Exchange responded with invalid price"),
+			qty: self.qty_asset.parse().expect("Exchange responded with invalid qty"),
+			first_trade_id: self.first_trade_id,
+			last_trade_id: self.last_trade_id,
+			time: Timestamp::from_millisecond(self.timestamp).expect("Exchange responded with invalid timestamp"),
+			is_buyer_maker: self.is_buyer_maker,
+		}
+	}
+
+	/// `None` for zero-valued trades.
+	fn to_inner(&self, prec: PrecisionPriceQty) -> Option<InnerTrade> {
+		let price = prec.parse_price(&self.price);
+		let qty = prec.parse_qty(&self.qty_asset);
+		if price == 0 || qty == 0 {
+			return None;
+		}
+		Some(InnerTrade {
+			id: Some(self.agg_id.into()),
+			time: Timestamp::from_millisecond(self.timestamp).expect("Exchange responded with invalid timestamp"),
+			price,
+			qty,
+			side: Some(super::taker_side(self.is_buyer_maker)),
+		})
+	}
+}
+
+/// One `@aggTrade` event as-is: every fill of a single taker order at one price, covering trade ids `first_trade_id..=last_trade_id`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggTrade {
+	/// The stream multiplexes all subscribed pairs.
+	pub pair: Pair,
+	pub agg_trade_id: u64,
+	pub price: f64,
+	pub qty: f64,
+	pub first_trade_id: u64,
+	pub last_trade_id: u64,
+	pub time: Timestamp,
+	/// Buyer was the maker, ie the taker sold.
+	pub is_buyer_maker: bool,
+}
+impl EventTime for AggTrade {
+	fn event_time(&self) -> Option<Timestamp> {
+		Some(self.time)
+	}
+}
+
+/// See [Binance::ws_agg_trades](super::Binance::ws_agg_trades).
+#[derive(Debug)]
+pub struct AggTradesConnection {
+	feed: AggTradeFeed,
+}
+impl AggTradesConnection {
+	pub fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Self> {
+		let feed = AggTradeFeed::try_new(client, pairs, instrument, "ws_agg_trades")?;
+		Ok(Self { feed })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for AggTradesConnection {
+	type Item = AggTrade;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.feed.router.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.feed.router.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.feed.router.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.feed.dedup.as_ref().map(RecentIds::skipped)
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		Ok(self.feed.next().await?.iter().map(AggTradeEvent::to_agg_trade).collect())
+	}
+}
+
+//,}}}
+
+// book ticker {{{
+/// Best bid and ask of a pair, as of the last change to either.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookTicker {
+	pub pair: Pair,
+	pub bid_price: f64,
+	pub bid_qty: f64,
+	pub ask_price: f64,
+	pub ask_qty: f64,
+	/// Matching engine's on perp. Spot doesn't send one, so there it's when the update arrived.
+	pub time: Timestamp,
+}
+impl EventTime for BookTicker {
+	fn event_time(&self) -> Option<Timestamp> {
+		Some(self.time)
+	}
+}
+
+/// See [Binance::ws_book_ticker](super::Binance::ws_book_ticker).
+#[derive(Debug)]
+pub struct BookTickerConnection {
+	connection: WsConnection<BinanceWsHandler>,
+}
+impl BookTickerConnection {
+	/// No `pairs` subscribes to all of them through `!bookTicker`, which only USD-M futures still has.
+	pub fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Self> {
+		let vec_topic_str = match pairs.is_empty() {
+			true => vec!["!bookTicker".to_owned()],
+			false => pairs.iter().map(|p| format!("{}@bookTicker", p.fmt_binance().to_lowercase())).collect(),
+		};
+
+		let base_url = match instrument {
+			Instrument::Perp => BinanceWsUrl::FuturesUsdM,
+			Instrument::Spot | Instrument::Margin => BinanceWsUrl::Spot,
+			_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Binance, instrument, "ws_book_ticker"))),
+		};
+		let connection = client.ws_connection("", vec![BinanceOption::WsUrl(base_url), BinanceOption::WsTopics(vec_topic_str)])?;
+
+		Ok(Self { connection })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for BookTickerConnection {
+	type Item = BookTicker;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let batch = self.connection.next().await?;
+		// spot's carry no time of their own; one read, one arrival time
+		let arrived = Timestamp::now();
+		batch
+			.into_iter()
+			.map(|content_event| {
+				let data = content_event.data;
+				let parsed = serde_json::from_value::<BookTickerEvent>(data.clone()).ok().and_then(|e| e.into_book_ticker(arrived));
+				parsed.ok_or(WsError::UnexpectedEvent(data))
+			})
+			.collect()
+	}
+}
+
+/// `@bookTicker` event, less the `e` and `E` the handler strips. Spot sends neither those nor `T`.
+///
+/// Spot: https://developers.binance.com/docs/binance-spot-api-docs/web-socket-streams#individual-symbol-book-ticker-streams
+/// Futures: https://developers.binance.com/docs/derivatives/usds-margined-futures/websocket-market-streams/Individual-Symbol-Book-Ticker-Streams
+#[serde_as]
+#[derive(Clone, Debug, serde::Deserialize)]
+struct BookTickerEvent {
+	#[serde(rename = "s")]
+	pair: String,
+	#[serde_as(as = "DisplayFromStr")]
+	#[serde(rename = "b")]
+	bid_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	#[serde(rename = "B")]
+	bid_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	#[serde(rename = "a")]
+	ask_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	#[serde(rename = "A")]
+	ask_qty: f64,
 	#[serde(rename = "T")]
-	timestamp: i64,
-	#[serde(rename = "q")]
-	qty_asset: String,
-	#[serde(rename = "p")]
-	price: String,
+	transaction_time: Option<i64>,
+}
+impl BookTickerEvent {
+	fn into_book_ticker(self, arrived: Timestamp) -> Option<BookTicker> {
+		Some(BookTicker {
+			pair: self.pair.as_str().try_into().ok()?,
+			bid_price: self.bid_price,
+			bid_qty: self.bid_qty,
+			ask_price: self.ask_price,
+			ask_qty: self.ask_qty,
+			time: match self.transaction_time {
+				Some(ms) => Timestamp::from_millisecond(ms).ok()?,
+				None => arrived,
+			},
+		})
+	}
+}
+//,}}}
+
+// mark prices {{{
+/// See [Exchange::ws_mark_prices](crate::Exchange::ws_mark_prices). USD-M futures only.
+#[derive(Debug)]
+pub struct MarkPriceConnection {
+	connection: WsConnection<BinanceWsHandler>,
+}
+impl MarkPriceConnection {
+	pub fn try_new(client: &Client, pairs: &[Pair]) -> Result<Self, WsError> {
+		let vec_topic_str = pairs.iter().map(|p| format!("{}@markPrice@1s", p.fmt_binance().to_lowercase())).collect::<Vec<_>>();
+		let connection = client.ws_connection("", vec![BinanceOption::WsUrl(BinanceWsUrl::FuturesUsdM), BinanceOption::WsTopics(vec_topic_str)])?;
+		Ok(Self { connection })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for MarkPriceConnection {
+	type Item = MarkPriceUpdate;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let batch = self.connection.next().await?;
+		batch
+			.into_iter()
+			.map(|content_event| {
+				let data = content_event.data;
+				let parsed = serde_json::from_value::<MarkPriceEvent>(data.clone()).ok().and_then(|e| MarkPriceUpdate::try_from(e).ok());
+				parsed.ok_or(WsError::UnexpectedEvent(data))
+			})
+			.collect()
+	}
+}
+
+/// `@markPrice` event. `r` and `T` are the predicted rate and time of the next funding.
+///
+/// https://developers.binance.com/docs/derivatives/usds-margined-futures/websocket-market-streams/Mark-Price-Stream
+#[serde_as]
+#[derive(Clone, Debug, serde::Deserialize)]
+struct MarkPriceEvent {
+	#[serde(rename = "E")]
+	event_time: i64,
 	#[serde(rename = "s")]
 	pair: String,
+	#[serde_as(as = "DisplayFromStr")]
+	#[serde(rename = "p")]
+	mark_price: f64,
+	/// `""` for delivery contracts.
+	#[serde(rename = "r")]
+	funding_rate: String,
+	/// `0` for delivery contracts.
+	#[serde(rename = "T")]
+	next_funding_time: i64,
 }
+impl TryFrom<MarkPriceEvent> for MarkPriceUpdate {
+	type Error = ();
 
+	fn try_from(e: MarkPriceEvent) -> Result<Self, ()> {
+		Ok(Self {
+			pair: e.pair.as_str().try_into().map_err(|_| ())?,
+			mark_price: e.mark_price,
+			funding_rate: e.funding_rate.parse().ok(),
+			next_funding_time: (e.next_funding_time > 0).then(|| Timestamp::from_millisecond(e.next_funding_time).ok()).flatten(),
+			time: Timestamp::from_millisecond(e.event_time).map_err(|_| ())?,
+		})
+	}
+}
 //,}}}
 
 // book {{{
@@ -158,14 +488,14 @@ impl BookConnection {
 		instrument: Instrument,
 		pair_precisions: BTreeMap<Pair, PrecisionPriceQty>,
 		book_snapshot_freq: Option<Duration>,
-	) -> Result<Self, WsError> {
+	) -> ExchangeResult<Self> {
 		assert!(!pairs.is_empty(), "BookConnection requires at least one pair");
 		let vec_topic_str = pairs.iter().map(|p| format!("{}@depth@100ms", p.fmt_binance().to_lowercase())).collect::<Vec<_>>();
 
 		let base_url = match instrument {
 			Instrument::Perp => BinanceWsUrl::FuturesUsdM,
 			Instrument::Spot | Instrument::Margin => BinanceWsUrl::Spot,
-			_ => unimplemented!(),
+			_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Binance, instrument, "ws_book"))),
 		};
 		let connection = client.ws_connection("", vec![BinanceOption::WsUrl(base_url), BinanceOption::WsTopics(vec_topic_str)])?;
 
@@ -236,6 +566,18 @@ impl std::fmt::Debug for BookConnection {
 impl ExchangeStream for BookConnection {
 	type Item = BookUpdate;
 
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
 	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
 		enum Branch {
 			Snapshot(Result<BookShape, ExchangeError>),
@@ -302,7 +644,7 @@ impl ExchangeStream for BookConnection {
 									u_first: parsed.first_update_id,
 									u_final: parsed.final_update_id,
 								}),
-								_ => unimplemented!(),
+								_ => unreachable!("rejected in `try_new`"),
 							};
 							let gapped = self.last_seq.get(&pair).map(|prev| seq.has_gap_from_prev(prev)).unwrap_or(false);
 							self.last_seq.insert(pair, seq);
@@ -385,9 +727,317 @@ impl Sequence for BinanceDepthSeq {
 }
 //,}}}
 
+// user data {{{
+/// Maps a user-data stream event onto an [AccountEvent]: spot `executionReport` and USDⓈ-M `ORDER_TRADE_UPDATE`. `Ok(None)` for everything else, balance and position updates included: those aren't mapped yet.
+pub fn parse_account_event(event: &serde_json::Value) -> Result<Option<AccountEvent>, WsError> {
+	let update = match event["e"].as_str() {
+		Some("executionReport") => serde_json::from_value::<SpotExecutionReport>(event.clone()).map_err(WsError::Parse)?.into_update(),
+		Some("ORDER_TRADE_UPDATE") => serde_json::from_value::<PerpOrderTradeUpdate>(event.clone()).map_err(WsError::Parse)?.order.into_update(),
+		_ => return Ok(None),
+	};
+	update.map(|u| Some(AccountEvent::OrderUpdate(u))).ok_or_else(|| WsError::UnexpectedEvent(event.clone()))
+}
+
+/// Docs: https://developers.binance.com/docs/binance-spot-api-docs/user-data-stream#order-update
+#[serde_as]
+#[derive(Clone, Debug, serde::Deserialize)]
+struct SpotExecutionReport {
+	#[serde(rename = "s")]
+	symbol: String,
+	#[serde(rename = "c")]
+	client_id: String,
+	/// Id of the order being canceled, on cancels; `c` is then the cancel request's own.
+	#[serde(rename = "C")]
+	orig_client_id: String,
+	#[serde(rename = "S")]
+	side: String,
+	#[serde(rename = "q")]
+	#[serde_as(as = "DisplayFromStr")]
+	qty: f64,
+	#[serde(rename = "X")]
+	status: String,
+	#[serde(rename = "i")]
+	order_id: u64,
+	#[serde(rename = "z")]
+	#[serde_as(as = "DisplayFromStr")]
+	cum_filled_qty: f64,
+	#[serde(rename = "Z")]
+	#[serde_as(as = "DisplayFromStr")]
+	cum_filled_quote: f64,
+	/// Of this fill only.
+	#[serde(rename = "n")]
+	#[serde_as(as = "DisplayFromStr")]
+	fee: f64,
+	#[serde(rename = "N")]
+	fee_asset: Option<String>,
+	#[serde(rename = "T")]
+	time: i64,
+}
+impl SpotExecutionReport {
+	fn into_update(self) -> Option<OrderUpdate> {
+		let client_id = match self.orig_client_id.is_empty() {
+			true => &self.client_id,
+			false => &self.orig_client_id,
+		};
+		Some(OrderUpdate {
+			order_id: order_id(client_id, self.order_id)?,
+			symbol: Symbol::new(self.symbol.as_str().try_into().ok()?, Instrument::Spot),
+			side: side(&self.side)?,
+			status: OrderStatus::from_str(&self.status).ok()?,
+			qty: self.qty,
+			cum_filled_qty: self.cum_filled_qty,
+			cum_filled_quote: self.cum_filled_quote,
+			fee: self.fee_asset.map(|asset| FeeReport::Fill {
+				asset: (&*asset).into(),
+				amount: self.fee,
+			}),
+			time: Timestamp::from_millisecond(self.time).ok()?,
+		})
+	}
+}
+
+/// Docs: https://developers.binance.com/docs/derivatives/usds-margined-futures/user-data-streams/Event-Order-Update
+#[derive(Clone, Debug, serde::Deserialize)]
+struct PerpOrderTradeUpdate {
+	#[serde(rename = "o")]
+	order: PerpOrderUpdate,
+}
+#[serde_as]
+#[derive(Clone, Debug, serde::Deserialize)]
+struct PerpOrderUpdate {
+	#[serde(rename = "s")]
+	symbol: String,
+	#[serde(rename = "c")]
+	client_id: String,
+	#[serde(rename = "S")]
+	side: String,
+	#[serde(rename = "q")]
+	#[serde_as(as = "DisplayFromStr")]
+	qty: f64,
+	#[serde(rename = "X")]
+	status: String,
+	#[serde(rename = "i")]
+	order_id: u64,
+	#[serde(rename = "z")]
+	#[serde_as(as = "DisplayFromStr")]
+	cum_filled_qty: f64,
+	#[serde(rename = "ap")]
+	#[serde_as(as = "DisplayFromStr")]
+	avg_price: f64,
+	/// Of this fill only. Absent when there's no commission.
+	#[serde(rename = "n", default)]
+	#[serde_as(as = "Option<DisplayFromStr>")]
+	fee: Option<f64>,
+	#[serde(rename = "N", default)]
+	fee_asset: Option<String>,
+	#[serde(rename = "T")]
+	time: i64,
+}
+impl PerpOrderUpdate {
+	fn into_update(self) -> Option<OrderUpdate> {
+		Some(OrderUpdate {
+			order_id: order_id(&self.client_id, self.order_id)?,
+			symbol: Symbol::new(self.symbol.as_str().try_into().ok()?, Instrument::Perp),
+			side: side(&self.side)?,
+			status: OrderStatus::from_str(&self.status).ok()?,
+			qty: self.qty,
+			cum_filled_qty: self.cum_filled_qty,
+			// only the average is sent
+			cum_filled_quote: self.avg_price * self.cum_filled_qty,
+			fee: self.fee_asset.zip(self.fee).map(|(asset, amount)| FeeReport::Fill { asset: (&*asset).into(), amount }),
+			time: Timestamp::from_millisecond(self.time).ok()?,
+		})
+	}
+}
+
+fn order_id(client_id: &str, exchange_id: u64) -> Option<OrderId> {
+	Some(OrderId {
+		client_id: Some(ArrayString::from(client_id).ok()?),
+		exchange_id: ArrayString::from(&exchange_id.to_string()).ok(),
+		..Default::default()
+	})
+}
+
+fn side(s: &str) -> Option<Side> {
+	match s {
+		"BUY" => Some(Side::Buy),
+		"SELL" => Some(Side::Sell),
+		_ => None,
+	}
+}
+//,}}}
+
 #[cfg(test)]
 mod tests {
+	use futures_util::SinkExt as _;
+	use tokio_tungstenite::{accept_async, tungstenite::Message};
+
 	use super::*;
+	use crate::TradeId;
+
+	const AGG_TRADE_FRAMES: [&str; 4] = [
+		r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","E":1672515782136,"s":"BTCUSDT","a":26129,"p":"16578.50","q":"0.014","f":100,"l":105,"T":1672515782134,"m":true}}"#,
+		r#"{"stream":"ethusdt@aggTrade","data":{"e":"aggTrade","E":1672515782140,"s":"ETHUSDT","a":26129,"p":"1196.61","q":"0.52","f":200,"l":200,"T":1672515782139,"m":false}}"#,
+		r#"{"stream":"ethusdt@aggTrade","data":{"e":"aggTrade","E":1672515782152,"s":"ETHUSDT","a":26130,"p":"1196.60","q":"1.10","f":201,"l":203,"T":1672515782150,"m":true}}"#,
+		r#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","E":1672515782190,"s":"BTCUSDT","a":26130,"p":"16579.00","q":"0.002","f":106,"l":106,"T":1672515782188,"m":false}}"#,
+	];
+
+	#[tokio::test]
+	async fn agg_trades_replayed_on_reconnect_come_once() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		let server = tokio::spawn(async move {
+			let (tcp, _) = listener.accept().await.unwrap();
+			let mut ws = accept_async(tcp).await.unwrap();
+			for frame in &AGG_TRADE_FRAMES[..3] {
+				ws.send(Message::Text((*frame).into())).await.unwrap();
+			}
+			ws.close(None).await.unwrap();
+
+			// the new connection starts with a replay of both ETH frames; BTC's `a` of 26129 is reused by ETH, so the ids only dedup per pair
+			let (tcp, _) = listener.accept().await.unwrap();
+			let mut ws = accept_async(tcp).await.unwrap();
+			for frame in &AGG_TRADE_FRAMES[1..] {
+				ws.send(Message::Text((*frame).into())).await.unwrap();
+			}
+			tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+		});
+
+		let prec = |price, qty| PrecisionPriceQty { price, qty };
+		let pair_precisions = BTreeMap::from([(Pair::new("BTC", "USDT"), prec(2, 3)), (Pair::new("ETH", "USDT"), prec(2, 2))]);
+		let mut stream = TradesConnection::connect(&Client::default(), &url, BinanceWsUrl::None, pair_precisions).unwrap();
+
+		let mut trades = Vec::new();
+		tokio::time::timeout(std::time::Duration::from_secs(10), async {
+			while trades.len() < 4 {
+				for batch in stream.next().await.unwrap() {
+					trades.extend(batch.trades().map(|t| (t.time, t.price, t.qty_asset)));
+				}
+			}
+		})
+		.await
+		.expect("the reconnect replays into the same stream");
+		let mut unique = trades.clone();
+		unique.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		unique.dedup();
+		assert_eq!(unique.len(), trades.len());
+		assert_eq!(stream.duplicates_skipped(), Some(2));
+		server.abort();
+	}
+
+	#[test]
+	fn book_ticker_events() {
+		let arrived = Timestamp::from_millisecond(1_700_000_000_000).unwrap();
+		let spot = r#"{"u":400900217,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+		let ticker = serde_json::from_str::<BookTickerEvent>(spot).unwrap().into_book_ticker(arrived).unwrap();
+		assert_eq!(ticker, BookTicker {
+			pair: Pair::new("BNB", "USDT"),
+			bid_price: 25.3519,
+			bid_qty: 31.21,
+			ask_price: 25.3652,
+			ask_qty: 40.66,
+			time: arrived,
+		});
+
+		// `e` and `E` already stripped by the handler
+		let perp = r#"{"u":400900217,"T":1568014460891,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+		let ticker = serde_json::from_str::<BookTickerEvent>(perp).unwrap().into_book_ticker(arrived).unwrap();
+		assert_eq!(ticker.time, Timestamp::from_millisecond(1568014460891).unwrap());
+	}
+
+	#[test]
+	fn spot_execution_reports_track_to_filled() {
+		let events = [
+			r#"{"e":"executionReport","E":1499405658658,"s":"ETHUSDT","c":"strat-1","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"","x":"NEW","X":"NEW","r":"NONE","i":4293153,"l":"0.00000000","z":"0.00000000","L":"0.00000000","n":"0","N":null,"T":1499405658657,"t":-1,"I":8641984,"w":true,"m":false,"M":false,"O":1499405658657,"Z":"0.00000000","Y":"0.00000000","Q":"0.00000000","W":1499405658657,"V":"NONE"}"#,
+			r#"{"e":"executionReport","E":1499405658700,"s":"ETHUSDT","c":"strat-1","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"","x":"TRADE","X":"PARTIALLY_FILLED","r":"NONE","i":4293153,"l":"0.40000000","z":"0.40000000","L":"0.10264410","n":"0.00040000","N":"ETH","T":1499405658699,"t":1,"I":8641985,"w":false,"m":true,"M":false,"O":1499405658657,"Z":"0.04105764","Y":"0.04105764","Q":"0.00000000","W":1499405658657,"V":"NONE"}"#,
+			r#"{"e":"executionReport","E":1499405658800,"s":"ETHUSDT","c":"strat-1","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"","x":"TRADE","X":"FILLED","r":"NONE","i":4293153,"l":"0.60000000","z":"1.00000000","L":"0.10264410","n":"0.00060000","N":"ETH","T":1499405658799,"t":2,"I":8641986,"w":false,"m":true,"M":false,"O":1499405658657,"Z":"0.10264410","Y":"0.06158646","Q":"0.00000000","W":1499405658657,"V":"NONE"}"#,
+		];
+		let mut tracker = crate::FillTracker::default();
+		let mut done = None;
+		for e in events {
+			let event = parse_account_event(&serde_json::from_str(e).unwrap()).unwrap().expect("order update");
+			done = tracker.apply(&event);
+		}
+		let done = done.expect("last report fills it");
+		assert_eq!(done.status, OrderStatus::Filled);
+		assert_eq!(done.order_id.exchange_id.unwrap().as_str(), "4293153");
+		assert!((done.avg_price.unwrap() - 0.1026441).abs() < 1e-12);
+		assert!((done.fees[&v_utils::trades::Asset::from("ETH")] - 0.001).abs() < 1e-12);
+	}
+
+	#[test]
+	fn spot_cancel_keeps_the_original_client_id() {
+		let json = r#"{"e":"executionReport","E":1499405658900,"s":"ETHUSDT","c":"cancel-req","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","P":"0.00000000","F":"0.00000000","g":-1,"C":"strat-1","x":"CANCELED","X":"CANCELED","r":"NONE","i":4293153,"l":"0.00000000","z":"0.00000000","L":"0.00000000","n":"0","N":null,"T":1499405658899,"t":-1,"I":8641987,"w":false,"m":false,"M":false,"O":1499405658657,"Z":"0.00000000","Y":"0.00000000","Q":"0.00000000","W":1499405658657,"V":"NONE"}"#;
+		let Some(AccountEvent::OrderUpdate(u)) = parse_account_event(&serde_json::from_str(json).unwrap()).unwrap() else { panic!() };
+		assert_eq!(u.order_id.client_order_id().as_str(), "strat-1");
+		assert_eq!(u.status, OrderStatus::Canceled);
+	}
+
+	#[test]
+	fn perp_order_trade_update() {
+		let json = r#"{"e":"ORDER_TRADE_UPDATE","E":1568879465651,"T":1568879465650,"o":{"s":"BTCUSDT","c":"TEST","S":"SELL","o":"LIMIT","f":"GTC","q":"0.002","p":"7100","ap":"7103.04","sp":"0","x":"TRADE","X":"PARTIALLY_FILLED","i":8886774,"l":"0.001","z":"0.001","L":"7103.04","N":"USDT","n":"0.00284121","T":1568879465650,"t":0,"b":"0","a":"9.91","m":false,"R":false,"wt":"CONTRACT_PRICE","ot":"LIMIT","ps":"BOTH","cp":false,"rp":"0","pP":false,"si":0,"ss":0,"V":"NONE","pm":"NONE","gtd":0}}"#;
+		let Some(AccountEvent::OrderUpdate(u)) = parse_account_event(&serde_json::from_str(json).unwrap()).unwrap() else { panic!() };
+		assert_eq!(u.symbol.instrument, Instrument::Perp);
+		assert_eq!(u.side, Side::Sell);
+		assert!((u.cum_filled_quote - 7.10304).abs() < 1e-9);
+		assert_eq!(u.fee, Some(FeeReport::Fill { asset: "USDT".into(), amount: 0.00284121 }));
+
+		let balance = serde_json::json!({"e":"ACCOUNT_UPDATE","E":1564745798939_i64});
+		assert!(parse_account_event(&balance).unwrap().is_none());
+	}
+
+	#[test]
+	fn agg_trade_sides() {
+		let prec = PrecisionPriceQty { price: 2, qty: 3 };
+		let trades = |frames: &[&str]| -> Vec<crate::Trade> {
+			let inner = frames.iter().map(|f| serde_json::from_str::<AggTradeEvent>(f).unwrap().to_inner(prec).unwrap()).collect();
+			BatchTrades::new(prec, inner, Timestamp::now(), Timestamp::now()).trades().collect()
+		};
+		// spot: buyer is maker -> someone sold into the bid
+		let spot = trades(&[
+			r#"{"e":"aggTrade","E":1672515782136,"s":"BTCUSDT","a":2436474911,"p":"16542.79","q":"0.01200","f":3016011200,"l":3016011201,"T":1672515782136,"m":true,"M":true}"#,
+			r#"{"e":"aggTrade","E":1672515782140,"s":"BTCUSDT","a":2436474912,"p":"16542.80","q":"0.00400","f":3016011202,"l":3016011202,"T":1672515782139,"m":false,"M":true}"#,
+		]);
+		assert_eq!(spot[0].id, Some(TradeId::Numeric(2436474911)));
+		assert_eq!(spot[0].side, Some(Side::Sell));
+		assert_eq!(spot[0].signed_qty(), Some(-0.012));
+		assert_eq!(spot[1].side, Some(Side::Buy));
+		assert_eq!(spot[1].signed_qty(), Some(0.004));
+
+		// perp: same fields plus `nq`, no `M`
+		let perp = trades(&[r#"{"e":"aggTrade","E":1672515782200,"s":"BTCUSDT","a":3321862115,"p":"16530.10","q":"1.250","nq":"1.250","f":6040301501,"l":6040301504,"T":1672515782198,"m":false}"#]);
+		assert_eq!(perp[0].price, 16530.1);
+		assert_eq!(perp[0].signed_qty(), Some(1.25));
+	}
+
+	#[test]
+	fn mark_price_event() {
+		let json = r#"{"e":"markPriceUpdate","E":1562305380000,"s":"BTCUSDT","p":"11794.15000000","i":"11784.62659091","P":"11784.25641265","r":"0.00038167","T":1562306400000}"#;
+		let update: MarkPriceUpdate = serde_json::from_str::<MarkPriceEvent>(json).unwrap().try_into().unwrap();
+		assert_eq!(update.pair, Pair::new("BTC", "USDT"));
+		assert_eq!((update.mark_price, update.funding_rate), (11794.15, Some(0.00038167)));
+		assert_eq!(update.next_funding_time.unwrap().as_millisecond(), 1562306400000);
+
+		let delivery = json.replace(r#""r":"0.00038167","T":1562306400000"#, r#""r":"","T":0"#);
+		let update: MarkPriceUpdate = serde_json::from_str::<MarkPriceEvent>(&delivery).unwrap().try_into().unwrap();
+		assert_eq!((update.funding_rate, update.next_funding_time), (None, None));
+	}
+
+	#[test]
+	fn agg_trade_keeps_trade_id_range() {
+		let json = r#"{"e":"aggTrade","E":1672515782200,"s":"BTCUSDT","a":3321862115,"p":"16530.10","q":"1.250","nq":"1.250","f":6040301501,"l":6040301504,"T":1672515782198,"m":true}"#;
+		let trade = serde_json::from_str::<AggTradeEvent>(json).unwrap().to_agg_trade();
+		assert_eq!(trade, AggTrade {
+			pair: Pair::new("BTC", "USDT"),
+			agg_trade_id: 3321862115,
+			price: 16530.1,
+			qty: 1.25,
+			first_trade_id: 6040301501,
+			last_trade_id: 6040301504,
+			time: Timestamp::from_millisecond(1672515782198).unwrap(),
+			is_buyer_maker: true,
+		});
+	}
 
 	#[test]
 	fn spot_seq_gap() {
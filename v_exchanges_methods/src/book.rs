@@ -0,0 +1,391 @@
+//! Locally maintained order book off a [ws_book](Exchange::ws_book) feed, and the microstructure metrics derived from it.
+use std::time::Duration;
+
+use adapters::generics::ws::{LatencyStats, SubscriptionSnapshot, WsError};
+use jiff::Timestamp;
+
+use crate::prelude::*;
+
+/// Order book kept current from a [ws_book](Exchange::ws_book) feed of a single pair.
+///
+/// Levels are raw, in [prec](Self::prec) units, same as in [BookShape]. Derived metrics take the book as-is: they return `None` whenever it is one-sided or [crossed](Self::is_crossed), rather than a number computed off a broken book.
+#[derive(Clone, Debug)]
+pub struct LocalBook {
+	pub prec: PrecisionPriceQty,
+	pub bids: BTreeMap<i32, u32>,
+	pub asks: BTreeMap<i32, u32>,
+	/// Exchange time of the last applied update.
+	pub ts_event: Timestamp,
+	/// Set by a gapped delta, or one at another [prec](Self::prec), cleared by the next snapshot. Levels may be stale in between.
+	pub needs_resync: bool,
+}
+impl LocalBook {
+	/// Snapshots replace the book; deltas overwrite the levels they carry, with a zero qty removing the level. A delta at another precision than the book's levels is dropped, and flags [needs_resync](Self::needs_resync).
+	pub fn apply(&mut self, update: &BookUpdate) {
+		match update {
+			BookUpdate::Snapshot(shape) => {
+				*self = Self::from(shape.clone());
+			}
+			BookUpdate::BatchDelta { shape, gapped } => {
+				if *gapped {
+					self.needs_resync = true;
+				}
+				let is_empty = self.bids.is_empty() && self.asks.is_empty();
+				if shape.prec != self.prec && !is_empty {
+					tracing::warn!(target: "v_exchanges", book = ?self.prec, delta = ?shape.prec, "Book delta at another precision than the book's levels; dropping it until the next snapshot");
+					self.needs_resync = true;
+					return;
+				}
+				self.prec = shape.prec;
+				self.ts_event = shape.ts_event;
+				for (side, levels) in [(&mut self.bids, &shape.bids), (&mut self.asks, &shape.asks)] {
+					for (price, qty) in levels {
+						match qty {
+							0 => side.remove(price),
+							_ => side.insert(*price, *qty),
+						};
+					}
+				}
+			}
+		}
+	}
+
+	pub fn best_bid(&self) -> Option<(f64, f64)> {
+		self.bids.iter().next_back().map(|(p, q)| self.level(*p, *q))
+	}
+
+	pub fn best_ask(&self) -> Option<(f64, f64)> {
+		self.asks.iter().next().map(|(p, q)| self.level(*p, *q))
+	}
+
+	/// Best bid at or above best ask. Either a feed problem or a book that missed a delta; never a tradeable state.
+	pub fn is_crossed(&self) -> bool {
+		match (self.bids.keys().next_back(), self.asks.keys().next()) {
+			(Some(bid), Some(ask)) => bid >= ask,
+			_ => false,
+		}
+	}
+
+	/// Mid weighted by the opposite side's top-of-book size, so it leans towards the side about to be taken out.
+	pub fn microprice(&self) -> Option<f64> {
+		let ((bid, bid_qty), (ask, ask_qty)) = self.touch()?;
+		let total = bid_qty + ask_qty;
+		(total > 0.).then(|| (bid * ask_qty + ask * bid_qty) / total)
+	}
+
+	/// Spread relative to mid, in basis points.
+	pub fn spread_bps(&self) -> Option<f64> {
+		let ((bid, _), (ask, _)) = self.touch()?;
+		let mid = (bid + ask) / 2.;
+		(mid > 0.).then(|| (ask - bid) / mid * 10_000.)
+	}
+
+	/// `(bid - ask) / (bid + ask)` of the qty on the top `n` levels per side, in `[-1, 1]`. Same as [depth_imbalance], but `None` where that would give `0.` for lack of data.
+	pub fn imbalance_levels(&self, n: usize) -> Option<f64> {
+		if n == 0 {
+			return None;
+		}
+		self.touch()?;
+		let bid: f64 = self.bids.iter().rev().take(n).map(|(p, q)| self.level(*p, *q).1).sum();
+		let ask: f64 = self.asks.iter().take(n).map(|(p, q)| self.level(*p, *q).1).sum();
+		imbalance(bid, ask)
+	}
+
+	/// Like [imbalance_levels](Self::imbalance_levels), but over the notional within `bps` of mid on each side. A book with huge gaps between levels can have nothing but the touch in range; that's still counted.
+	pub fn imbalance_notional(&self, bps: f64) -> Option<f64> {
+		let ((bid, _), (ask, _)) = self.touch()?;
+		let mid = (bid + ask) / 2.;
+		let (lo, hi) = (mid * (1. - bps / 10_000.), mid * (1. + bps / 10_000.));
+		let notional = |(p, q): (f64, f64)| p * q;
+		let bid_notional: f64 = self.bids.iter().rev().map(|(p, q)| self.level(*p, *q)).take_while(|(p, _)| *p >= lo.min(bid)).map(notional).sum();
+		let ask_notional: f64 = self.asks.iter().map(|(p, q)| self.level(*p, *q)).take_while(|(p, _)| *p <= hi.max(ask)).map(notional).sum();
+		imbalance(bid_notional, ask_notional)
+	}
+
+	/// Snapshot of every metric at once.
+	pub fn metrics(&self, levels: usize) -> BookMetrics {
+		BookMetrics {
+			ts_event: self.ts_event,
+			best_bid: self.best_bid().map(|(p, _)| p),
+			best_ask: self.best_ask().map(|(p, _)| p),
+			microprice: self.microprice(),
+			spread_bps: self.spread_bps(),
+			imbalance: self.imbalance_levels(levels),
+			is_crossed: self.is_crossed(),
+		}
+	}
+
+	/// Both sides of the touch, if there is a sane one.
+	fn touch(&self) -> Option<((f64, f64), (f64, f64))> {
+		if self.is_crossed() {
+			return None;
+		}
+		Some((self.best_bid()?, self.best_ask()?))
+	}
+
+	fn level(&self, price: i32, qty: u32) -> (f64, f64) {
+		(Price::new(price, self.prec.price).as_f64(), Qty::new(qty, self.prec.qty).as_f64())
+	}
+}
+impl Default for LocalBook {
+	fn default() -> Self {
+		Self {
+			prec: PrecisionPriceQty { price: 0, qty: 0 },
+			bids: BTreeMap::new(),
+			asks: BTreeMap::new(),
+			ts_event: Timestamp::UNIX_EPOCH,
+			needs_resync: false,
+		}
+	}
+}
+impl From<BookShape> for LocalBook {
+	fn from(shape: BookShape) -> Self {
+		Self {
+			prec: shape.prec,
+			bids: shape.bids,
+			asks: shape.asks,
+			ts_event: shape.ts_event,
+			needs_resync: false,
+		}
+	}
+}
+
+fn imbalance(bid: f64, ask: f64) -> Option<f64> {
+	let total = bid + ask;
+	(total > 0.).then(|| (bid - ask) / total)
+}
+
+/// Derived top-of-book metrics, as emitted by [BookMetricsStream]. Fields are `None` under the same conditions as their [LocalBook] methods.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BookMetrics {
+	pub ts_event: Timestamp,
+	pub best_bid: Option<f64>,
+	pub best_ask: Option<f64>,
+	pub microprice: Option<f64>,
+	pub spread_bps: Option<f64>,
+	/// [imbalance_levels](LocalBook::imbalance_levels) over the stream's configured depth.
+	pub imbalance: Option<f64>,
+	pub is_crossed: bool,
+}
+
+/// Maintains a [LocalBook] off `inner` and emits its [BookMetrics], at most once per `min_interval`. `inner` must carry a single pair.
+///
+/// Every update is applied, but metrics only go out on the first one to arrive after `min_interval` has passed since the last emission, so a quiet book isn't re-reported and a busy one is sampled. Updates held back in between are flushed once `min_interval` is up, so the end of a burst is reported even if the book goes quiet right after. `Duration::ZERO` emits on every update.
+pub fn with_book_metrics<S>(inner: S, levels: usize, min_interval: Duration) -> BookMetricsStream<S>
+where
+	S: ExchangeStream<Item = BookUpdate>, {
+	BookMetricsStream {
+		inner,
+		book: LocalBook::default(),
+		levels,
+		min_interval,
+		last_emit: None,
+		held_back: false,
+	}
+}
+
+/// See [with_book_metrics].
+#[derive(Debug)]
+pub struct BookMetricsStream<S> {
+	inner: S,
+	book: LocalBook,
+	levels: usize,
+	min_interval: Duration,
+	last_emit: Option<tokio::time::Instant>,
+	/// Whether updates were applied since the last emission.
+	held_back: bool,
+}
+impl<S> BookMetricsStream<S> {
+	pub fn book(&self) -> &LocalBook {
+		&self.book
+	}
+
+	fn emit(&mut self, now: tokio::time::Instant) -> Vec<BookMetrics> {
+		self.last_emit = Some(now);
+		self.held_back = false;
+		vec![self.book.metrics(self.levels)]
+	}
+}
+#[async_trait::async_trait]
+impl<S> ExchangeStream for BookMetricsStream<S>
+where
+	S: ExchangeStream<Item = BookUpdate>,
+{
+	type Item = BookMetrics;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.inner.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		self.inner.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: jiff::SignedDuration) {
+		self.inner.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		loop {
+			let trailing = self.last_emit.filter(|_| self.held_back).map(|at| at + self.min_interval);
+			let updates = match trailing {
+				Some(deadline) => tokio::select! {
+					biased;
+					updates = self.inner.next() => updates?,
+					() = tokio::time::sleep_until(deadline) => return Ok(self.emit(deadline)),
+				},
+				None => self.inner.next().await?,
+			};
+			if updates.is_empty() {
+				continue;
+			}
+			for update in &updates {
+				self.book.apply(update);
+			}
+			let now = tokio::time::Instant::now();
+			if self.last_emit.is_some_and(|at| now - at < self.min_interval) {
+				self.held_back = true;
+				continue;
+			}
+			return Ok(self.emit(now));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::Scripted;
+
+	const PREC: PrecisionPriceQty = PrecisionPriceQty { price: 1, qty: 3 };
+
+	/// Prices in units of 0.1, qtys in units of 0.001.
+	fn book(bids: &[(i32, u32)], asks: &[(i32, u32)]) -> LocalBook {
+		LocalBook {
+			prec: PREC,
+			bids: bids.iter().copied().collect(),
+			asks: asks.iter().copied().collect(),
+			..Default::default()
+		}
+	}
+
+	fn shape(bids: &[(i32, u32)], asks: &[(i32, u32)]) -> BookShape {
+		BookShape {
+			ts_event: Timestamp::UNIX_EPOCH,
+			ts_init: Timestamp::UNIX_EPOCH,
+			ts_last: Timestamp::UNIX_EPOCH,
+			prec: PREC,
+			bids: bids.iter().copied().collect(),
+			asks: asks.iter().copied().collect(),
+		}
+	}
+
+	#[test]
+	fn one_sided_books_have_no_metrics() {
+		for b in [book(&[(1000, 1000)], &[]), book(&[], &[(1001, 1000)]), book(&[], &[])] {
+			assert!(!b.is_crossed());
+			assert_eq!(b.microprice(), None);
+			assert_eq!(b.spread_bps(), None);
+			assert_eq!(b.imbalance_levels(5), None);
+			assert_eq!(b.imbalance_notional(10.), None);
+		}
+	}
+
+	#[test]
+	fn crossed_book_is_flagged_not_priced() {
+		for b in [book(&[(1001, 1000)], &[(1000, 1000)]), book(&[(1000, 1000)], &[(1000, 1000)])] {
+			assert!(b.is_crossed());
+			assert_eq!(b.microprice(), None);
+			assert_eq!(b.spread_bps(), None);
+			assert_eq!(b.imbalance_levels(1), None);
+			let m = b.metrics(1);
+			assert!(m.is_crossed);
+			assert_eq!(m.best_bid, Some(b.best_bid().unwrap().0));
+		}
+	}
+
+	#[test]
+	fn metrics_stay_in_bounds() {
+		// every combination of sizes, on a tight and a wide book
+		for (bid, ask) in [(1000, 1001), (1, 1_000_000)] {
+			for bid_qty in [1, 7, 1000, u32::MAX] {
+				for ask_qty in [1, 3, 1000, u32::MAX] {
+					let b = book(&[(bid, bid_qty), (bid - 1, ask_qty)], &[(ask, ask_qty), (ask + 1, bid_qty)]);
+					let (bid_px, ask_px) = (b.best_bid().unwrap().0, b.best_ask().unwrap().0);
+					let micro = b.microprice().unwrap();
+					assert!((bid_px..=ask_px).contains(&micro), "{micro} outside [{bid_px}, {ask_px}]");
+					assert!(b.spread_bps().unwrap() > 0.);
+					for imb in [b.imbalance_levels(1).unwrap(), b.imbalance_levels(100).unwrap(), b.imbalance_notional(1.).unwrap()] {
+						assert!((-1.0..=1.0).contains(&imb), "{imb}");
+					}
+				}
+			}
+		}
+		assert_eq!(book(&[(1000, 1)], &[(1001, 1)]).imbalance_levels(0), None);
+	}
+
+	#[test]
+	fn huge_gap_book() {
+		// touch 100.0 / 100.1, then nothing until 10.0 / 1000.0
+		let b = book(&[(100, 5000), (1000, 1000)], &[(1001, 3000), (10_000, 9000)]);
+		assert!((b.microprice().unwrap() - (100.0 * 3. + 100.1 * 1.) / 4.).abs() < 1e-9);
+		// within 50bps only the touch counts
+		let expected = (100.0 * 1. - 100.1 * 3.) / (100.0 * 1. + 100.1 * 3.);
+		assert!((b.imbalance_notional(50.).unwrap() - expected).abs() < 1e-9);
+		// a band narrower than the spread still includes the touch
+		assert!((b.imbalance_notional(0.01).unwrap() - expected).abs() < 1e-9);
+		// by levels, the far ones count too
+		assert!((b.imbalance_levels(2).unwrap() - (6. - 12.) / 18.).abs() < 1e-9);
+	}
+
+	#[test]
+	fn deltas_apply_on_top_of_snapshot() {
+		let mut b = LocalBook::default();
+		b.apply(&BookUpdate::Snapshot(shape(&[(999, 1000), (1000, 1000)], &[(1001, 1000)])));
+		b.apply(&BookUpdate::BatchDelta {
+			shape: shape(&[(1000, 0)], &[(1001, 2000), (1002, 500)]),
+			gapped: false,
+		});
+		assert_eq!(b.bids, BTreeMap::from([(999, 1000)]));
+		assert_eq!(b.asks, BTreeMap::from([(1001, 2000), (1002, 500)]));
+		assert!(!b.needs_resync);
+
+		b.apply(&BookUpdate::BatchDelta { shape: shape(&[], &[]), gapped: true });
+		assert!(b.needs_resync);
+		b.apply(&BookUpdate::Snapshot(shape(&[(1000, 1)], &[(1001, 1)])));
+		assert!(!b.needs_resync);
+
+		// 1000 at a price prec of 2 would be 10.00, not 100.0
+		let finer = BookShape {
+			prec: PrecisionPriceQty { price: 2, ..PREC },
+			..shape(&[(1000, 0)], &[])
+		};
+		b.apply(&BookUpdate::BatchDelta { shape: finer, gapped: false });
+		assert_eq!(b.bids, BTreeMap::from([(1000, 1)]), "not merged");
+		assert_eq!(b.prec, PREC);
+		assert!(b.needs_resync);
+	}
+
+	#[tokio::test]
+	async fn stream_is_throttled() {
+		let updates = (0..5).map(|i| BookUpdate::Snapshot(shape(&[(1000 - i, 1000)], &[(1001, 1000)])));
+		let mut stream = with_book_metrics(Scripted::items(updates), 5, Duration::from_millis(100));
+		let first = stream.next().await.unwrap();
+		assert_eq!(first[0].best_bid, Some(100.0));
+
+		// the rest arrive at once; they are applied, but held back
+		let held_back = tokio::time::timeout(Duration::from_millis(50), stream.next()).await;
+		assert!(held_back.is_err());
+		assert_eq!(stream.book().best_bid(), Some((99.6, 1.)));
+
+		// and flushed once the interval is up, though nothing new came in
+		let trailing = stream.next().await.unwrap();
+		assert_eq!(trailing[0].best_bid, Some(99.6));
+		// after which the quiet book isn't re-reported
+		assert!(tokio::time::timeout(Duration::from_millis(200), stream.next()).await.is_err());
+
+		let mut unthrottled = with_book_metrics(Scripted::items([BookUpdate::Snapshot(shape(&[(1000, 1)], &[(1001, 1)]))]), 5, Duration::ZERO);
+		let spread = unthrottled.next().await.unwrap()[0].spread_bps.unwrap();
+		assert!((spread - 0.1 / 100.05 * 10_000.).abs() < 1e-6);
+	}
+}
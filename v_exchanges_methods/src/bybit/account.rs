@@ -1,16 +1,22 @@
+use std::str::FromStr as _;
+
 use adapters::Client;
 use ahash::AHashMap;
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use serde_with::{DisplayFromStr, serde_as};
+use serde_with::{DisplayFromStr, NoneAsEmptyString, serde_as};
 use tracing::warn;
 use v_exchanges_adapters::bybit::{BybitHttpAuth, BybitOption};
-use v_utils::{macros::ScreamIt, trades::Asset};
+use v_utils::{
+	macros::ScreamIt,
+	trades::{Asset, Pair, Side, Usd},
+};
 
 use crate::{
-	ExchangeResult,
-	core::{ApiKeyInfo, AssetBalance, Balances, KeyPermission, PersonalInfo},
+	ExchangeError, ExchangeName, ExchangeResult, Instrument, MethodError, RequestRange, Symbol,
+	core::{ApiKeyInfo, AssetBalance, Balances, FeeSchedule, KeyPermission, KeyPermissions, PersonalInfo, Position},
+	utils::ExactNumber,
 };
 
 #[derive(Clone, Copy, Debug, ScreamIt)]
@@ -47,17 +53,17 @@ pub struct AccountInfo {
 	pub account_type: AccountType,
 	pub coin: Vec<CoinInfo>,
 	pub total_available_balance: Option<Value>, // can be "" in portfolio-margin mode
-	#[serde_as(as = "DisplayFromStr")]
-	/// in USD
-	pub total_equity: f64,
+	/// in USD. `None` for Classic `SPOT`, as are the other totals
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub total_equity: Option<f64>,
 	pub total_initial_margin: Option<Value>,     // can be "" in portfolio-margin mode
 	pub total_maintenance_margin: Option<Value>, // can be "" in portfolio-margin mode
 	pub total_margin_balance: Option<Value>,     // can be "" in portfolio-margin mode
 	#[serde(rename = "totalPerpUPL")]
-	#[serde_as(as = "DisplayFromStr")]
-	pub total_perp_upl: f64,
-	#[serde_as(as = "DisplayFromStr")]
-	pub total_wallet_balance: f64,
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub total_perp_upl: Option<f64>,
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub total_wallet_balance: Option<f64>,
 }
 #[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
@@ -69,21 +75,25 @@ pub struct CoinInfo {
 	__available_to_borrow: Option<Value>,
 	pub available_to_withdraw: Option<Value>, // deprecated for AccountType::UNIFIED, should use [Get Transerable Amount](<https://bybit-exchange.github.io/docs/v5/account/unified-trans-amnt>) for it instead
 	pub bonus: Option<String>,                // specific to `UNIFIED` account type
-	#[serde_as(as = "DisplayFromStr")]
-	pub borrow_amount: f64,
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub borrow_amount: Option<f64>,
 	pub coin: String,
-	free: Option<String>, // unique field for Classic `SPOT`
+	/// unique field for Classic `SPOT`
+	#[serde(default)]
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub free: Option<f64>,
 	pub collateral_switch: bool,
-	#[serde_as(as = "DisplayFromStr")]
-	pub cum_realised_pnl: f64,
-	#[serde_as(as = "DisplayFromStr")]
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub cum_realised_pnl: Option<f64>,
 	/// in base currency
-	pub equity: f64,
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub equity: Option<f64>,
+	/// in spot orders
 	#[serde_as(as = "DisplayFromStr")]
 	pub locked: f64,
 	pub margin_collateral: bool,
-	#[serde_as(as = "DisplayFromStr")]
-	pub spot_hedging_qty: f64,
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub spot_hedging_qty: Option<f64>,
 	#[serde(rename = "totalOrderIM")]
 	pub total_order_im: Option<Value>, // "" for portfolio-margin mode
 	#[serde(rename = "totalPositionIM")]
@@ -92,12 +102,12 @@ pub struct CoinInfo {
 	#[serde(rename = "totalPositionMM")]
 	#[serde_as(as = "DisplayFromStr")]
 	pub total_position_mm: String,
-	#[serde_as(as = "DisplayFromStr")]
-	pub unrealised_pnl: f64,
-	#[serde_as(as = "DisplayFromStr")]
-	pub usd_value: f64,
-	#[serde_as(as = "DisplayFromStr")]
-	pub wallet_balance: f64,
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub unrealised_pnl: Option<f64>,
+	/// `None` for Classic `SPOT`
+	#[serde_as(as = "NoneAsEmptyString")]
+	pub usd_value: Option<f64>,
+	pub wallet_balance: ExactNumber,
 }
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RetExtInfo {}
@@ -122,8 +132,9 @@ struct EarnPosition {
 }
 //,}}}
 
-pub(super) async fn personal_info(client: &Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
+pub(super) async fn personal_info(client: &Client, account_type: crate::AccountType, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
 	assert!(client.is_authenticated::<BybitOption>());
+	let wallet = wallet_account_type(account_type, instrument, "personal_info")?;
 
 	let auth_options = |recv_window: Option<std::time::Duration>| {
 		let mut options = vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)];
@@ -134,31 +145,82 @@ pub(super) async fn personal_info(client: &Client, recv_window: Option<std::time
 	};
 
 	let (balances_result, api_result) = tokio::join!(
-		balances_inner(client, recv_window),
+		balances_inner(client, wallet, None, recv_window),
 		client.get_no_query::<QueryApiResponse, _>("/v5/user/query-api", auth_options(recv_window)),
 	);
 	let balances = balances_result?;
 	let api_response = api_result?;
 
-	let expire_time = match api_response.result.expired_at.as_str() {
-		"" | "0" => None,
-		s => Some(
-			s.parse::<Timestamp>()
-				.unwrap_or_else(|e| panic!("Bybit expiredAt={s:?} failed to parse as ISO 8601 timestamp: {e}")),
-		),
-	};
-
 	Ok(PersonalInfo {
 		api: ApiKeyInfo {
-			expire_time,
+			expire_time: api_response.result.expire_time(),
 			permissions: api_response.result.permissions.into(),
 		},
 		balances,
 	})
 }
 
+pub(super) async fn key_permissions(client: &Client) -> ExchangeResult<KeyPermissions> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let r: QueryApiResponse = client.get_no_query("/v5/user/query-api", vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)]).await?;
+	Ok(r.result.into())
+}
+
+pub(super) async fn asset_balance(client: &Client, account_type: crate::AccountType, asset: Asset, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<AssetBalance> {
+	let wallet = wallet_account_type(account_type, instrument, "asset_balance")?;
+	let balances = balances_inner(client, wallet, Some(asset), recv_window).await?;
+	Ok(balances.iter().find(|b| b.asset == asset).copied().unwrap_or_else(|| AssetBalance::new(asset, 0., Some(Usd(0.)))))
+}
+
+/// Wallet holding the balance `instrument` trades off. Only UTA keeps spot in the same one as derivatives.
+fn wallet_account_type(account_type: crate::AccountType, instrument: Instrument, method_name: &'static str) -> ExchangeResult<AccountType> {
+	match (account_type, instrument) {
+		(crate::AccountType::UnifiedTrading, _) => Ok(AccountType::Unified),
+		(crate::AccountType::UnifiedMargin | crate::AccountType::Classic, Instrument::Spot | Instrument::Margin) => Ok(AccountType::Spot),
+		(crate::AccountType::UnifiedMargin, Instrument::Perp) => Ok(AccountType::Unified),
+		// UMA leaves inverse contracts out
+		(crate::AccountType::UnifiedMargin | crate::AccountType::Classic, Instrument::PerpInverse) | (crate::AccountType::Classic, Instrument::Perp) => Ok(AccountType::Contract),
+		_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Bybit, instrument, method_name))),
+	}
+}
+
+// account info {{{
+#[derive(Debug, Deserialize)]
+struct AccountInfoResponse {
+	result: AccountInfoResult,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountInfoResult {
+	/// 1: classic, 2: UMA, 3-4: UTA 1.0 (and pro), 5-6: UTA 2.0 (and pro)
+	unified_margin_status: u8,
+}
+impl TryFrom<AccountInfoResult> for crate::AccountType {
+	type Error = ExchangeError;
+
+	fn try_from(r: AccountInfoResult) -> ExchangeResult<Self> {
+		match r.unified_margin_status {
+			1 => Ok(Self::Classic),
+			2 => Ok(Self::UnifiedMargin),
+			3..=6 => Ok(Self::UnifiedTrading),
+			status => Err(ExchangeError::Other(eyre::eyre!("Unknown Bybit unifiedMarginStatus: {status}"))),
+		}
+	}
+}
+
+pub(super) async fn account_type(client: &Client) -> ExchangeResult<crate::AccountType> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let r: AccountInfoResponse = client.get_no_query("/v5/account/info", vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)]).await?;
+	r.result.try_into()
+}
+//,}}}
+
 /// Should be calling https://bybit-exchange.github.io/docs/v5/asset/balance/all-balance, but with how I'm registered on bybit, my key doesn't have permissions for that (they require it to be able to `transfer` for some reason)
-async fn balances_inner(client: &Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Balances> {
+///
+/// With a `coin`, Bybit filters down to it, earn positions included.
+async fn balances_inner(client: &Client, wallet: AccountType, coin: Option<Asset>, recv_window: Option<std::time::Duration>) -> ExchangeResult<Balances> {
 	assert!(client.is_authenticated::<BybitOption>());
 
 	let auth_options = |recv_window: Option<std::time::Duration>| {
@@ -169,29 +231,31 @@ async fn balances_inner(client: &Client, recv_window: Option<std::time::Duration
 		options
 	};
 
-	let account_response: AccountResponse = client.get("/v5/account/wallet-balance", &[("accountType", "UNIFIED")], auth_options(recv_window)).await?;
+	let with_coin = |mut params: Vec<(&'static str, String)>| {
+		if let Some(coin) = coin {
+			params.push(("coin", coin.to_string()));
+		}
+		params
+	};
+
+	let account_response: AccountResponse = client
+		.get("/v5/account/wallet-balance", &with_coin(vec![("accountType", wallet.to_string())]), auth_options(recv_window))
+		.await?;
 	assert_eq!(account_response.result.list.len(), 1);
 	let account_info = account_response.result.list.first().unwrap();
 
-	// Build coin→usd_rate map from UNIFIED account data for converting earn positions
-	let mut usd_rates: AHashMap<String, f64> = AHashMap::default();
-	let mut vec_balance = Vec::default();
-	for r in &account_info.coin {
-		if r.wallet_balance > 0.0 {
-			usd_rates.insert(r.coin.clone(), r.usd_value / r.wallet_balance);
-		}
-		vec_balance.push(AssetBalance {
-			asset: (&*r.coin).into(),
-			underlying: r.wallet_balance,
-			usd: Some(r.usd_value.into()),
-		});
-	}
-
-	let mut total_equity = account_info.total_equity;
+	let mut vec_balance = wallet_balances(account_info);
+	// Build coin→usd_rate map from the wallet data for converting earn positions
+	let usd_rates: AHashMap<String, f64> = vec_balance
+		.iter()
+		.filter(|b| b.underlying > 0.0)
+		.filter_map(|b| b.usd.map(|usd| (b.asset.to_string(), *usd / b.underlying)))
+		.collect();
+	let mut total_equity = wallet_total(account_info, &vec_balance);
 
 	// Fetch Earn positions (FlexibleSaving and OnChain) and add to total
 	for category in ["FlexibleSaving", "OnChain"] {
-		let r: Result<EarnPositionResponse, _> = client.get("/v5/earn/position", &[("category", category)], auth_options(recv_window)).await;
+		let r: Result<EarnPositionResponse, _> = client.get("/v5/earn/position", &with_coin(vec![("category", category.to_owned())]), auth_options(recv_window)).await;
 		match r {
 			Ok(earn_response) => {
 				for pos in &earn_response.result.list {
@@ -205,7 +269,7 @@ async fn balances_inner(client: &Client, recv_window: Option<std::time::Duration
 							match pos.coin.as_str() {
 								"USDT" | "USDC" | "DAI" | "BUSD" => 1.0,
 								_ => {
-									warn!("No USD rate for earn coin {}, skipping", pos.coin);
+									warn!(target: "v_exchanges::bybit", "No USD rate for earn coin {}, skipping", pos.coin);
 									continue;
 								}
 							}
@@ -220,20 +284,20 @@ async fn balances_inner(client: &Client, recv_window: Option<std::time::Duration
 						b.asset == asset
 					}) {
 						existing.underlying += pos.amount;
+						#[cfg(feature = "decimal")]
+						{
+							existing.underlying_exact = None; // no longer the single figure the exchange sent
+						}
 						if let Some(ref mut usd) = existing.usd {
 							*usd = v_utils::trades::Usd(**usd + usd_value);
 						}
 					} else {
-						vec_balance.push(AssetBalance {
-							asset: (&*pos.coin).into(),
-							underlying: pos.amount,
-							usd: Some(v_utils::trades::Usd(usd_value)),
-						});
+						vec_balance.push(AssetBalance::new((&*pos.coin).into(), pos.amount, Some(v_utils::trades::Usd(usd_value))));
 					}
 				}
 			}
 			Err(e) => {
-				warn!("Failed to fetch {category} earn positions: {e}");
+				warn!(target: "v_exchanges::bybit", "Failed to fetch {category} earn positions: {e}");
 			}
 		}
 	}
@@ -242,6 +306,24 @@ async fn balances_inner(client: &Client, recv_window: Option<std::time::Duration
 	Ok(balances)
 }
 
+/// Per coin, valued at the `usdValue` Bybit sends along.
+fn wallet_balances(info: &AccountInfo) -> Vec<AssetBalance> {
+	info.coin
+		.iter()
+		.map(|r| AssetBalance {
+			// only Classic `SPOT` reports what's free; elsewhere the rest is, as far as spot orders go
+			available: Some(r.free.unwrap_or(r.wallet_balance.value - r.locked)),
+			locked: Some(r.locked),
+			..AssetBalance::from_exact((&*r.coin).into(), r.wallet_balance, r.usd_value.map(Usd))
+		})
+		.collect()
+}
+
+/// `totalEquity`, or where the wallet has none (Classic `SPOT`), whatever of `balances` is valued.
+fn wallet_total(info: &AccountInfo, balances: &[AssetBalance]) -> f64 {
+	info.total_equity.unwrap_or_else(|| balances.iter().filter_map(|b| b.usd.map(|usd| *usd)).sum())
+}
+
 #[serde_as]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -255,6 +337,40 @@ struct QueryApiResult {
 	/// Expiry as ISO 8601 datetime string (e.g. `"2023-12-22T07:20:25Z"`); empty string or "0" means no expiry.
 	expired_at: String,
 	permissions: BybitPermissions,
+	/// `1` for read-only keys, whose listed trade permissions don't apply.
+	#[serde(default)]
+	read_only: u8,
+	/// `["*"]` when not bound to any IP.
+	#[serde(default)]
+	ips: Vec<String>,
+}
+impl QueryApiResult {
+	fn expire_time(&self) -> Option<Timestamp> {
+		match self.expired_at.as_str() {
+			"" | "0" => None,
+			s => Some(
+				s.parse::<Timestamp>()
+					.unwrap_or_else(|e| panic!("Bybit expiredAt={s:?} failed to parse as ISO 8601 timestamp: {e}")),
+			),
+		}
+	}
+}
+impl From<QueryApiResult> for KeyPermissions {
+	fn from(r: QueryApiResult) -> Self {
+		let expires = r.expire_time();
+		let read_only = r.read_only == 1;
+		let ip_restricted = Some(!r.ips.iter().any(|ip| ip == "*"));
+		let scopes = Self::from_permissions(&Vec::<KeyPermission>::from(r.permissions));
+		Self {
+			// any working key can read; Bybit has no scope for it
+			read: true,
+			spot_trade: scopes.spot_trade && !read_only,
+			futures_trade: scopes.futures_trade && !read_only,
+			withdraw: scopes.withdraw && !read_only,
+			ip_restricted,
+			expires,
+		}
+	}
 }
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "PascalCase", default)]
@@ -304,3 +420,359 @@ impl From<BybitPermissions> for Vec<KeyPermission> {
 		out
 	}
 }
+
+// fees {{{
+#[derive(Debug, Deserialize)]
+struct FeeRateResponse {
+	result: FeeRateResult,
+}
+#[derive(Debug, Deserialize)]
+struct FeeRateResult {
+	list: Vec<FeeRate>,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeRate {
+	#[serde_as(as = "DisplayFromStr")]
+	maker_fee_rate: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	taker_fee_rate: f64,
+}
+
+pub(super) async fn fees(client: &Client, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let category = match symbol.instrument {
+		Instrument::Perp => "linear",
+		Instrument::PerpInverse => "inverse",
+		Instrument::Spot => "spot",
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Bybit, symbol.instrument, "fees"))),
+	};
+	let mut options = vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)];
+	if let Some(rw) = recv_window {
+		options.push(BybitOption::RecvWindow(rw));
+	}
+	let r: FeeRateResponse = client.get("/v5/account/fee-rate", &[("category", category.to_owned()), ("symbol", symbol.pair.fmt_bybit())], options).await?;
+	let rate = r
+		.result
+		.list
+		.into_iter()
+		.next()
+		.ok_or_else(|| ExchangeError::Other(eyre::eyre!("Bybit returned no fee rate for {symbol}")))?;
+	Ok(FeeSchedule {
+		maker_rate: rate.maker_fee_rate,
+		taker_rate: rate.taker_fee_rate,
+		..Default::default()
+	})
+}
+//,}}}
+
+// closed pnl {{{
+/// Realized P&L of one (partial) position close, as `/v5/position/closed-pnl` reports it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosedPnl {
+	pub symbol: Symbol,
+	/// Of the closing order, so [Sell](Side::Sell) closes a long.
+	pub side: Side,
+	/// Closed size, in base.
+	pub qty: f64,
+	pub entry_price: f64,
+	pub exit_price: f64,
+	/// Net of fees and funding.
+	pub realized_pnl: f64,
+	pub close_time: Timestamp,
+	pub cum_entry_value: f64,
+	pub cum_exit_value: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClosedPnlResponse {
+	result: ClosedPnlResult,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClosedPnlResult {
+	list: Vec<ClosedPnlRecord>,
+	#[serde(default)]
+	next_page_cursor: String,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClosedPnlRecord {
+	symbol: String,
+	side: String,
+	#[serde_as(as = "DisplayFromStr")]
+	closed_size: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	avg_entry_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	avg_exit_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	closed_pnl: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	cum_entry_value: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	cum_exit_value: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	updated_time: i64,
+}
+impl ClosedPnlRecord {
+	fn into_closed_pnl(self, instrument: Instrument) -> ExchangeResult<ClosedPnl> {
+		let pair = Pair::from_str(&self.symbol).map_err(|e| ExchangeError::Other(eyre::eyre!("Unexpected Bybit symbol {}: {e}", self.symbol)))?;
+		let side = match self.side.as_str() {
+			"Buy" => Side::Buy,
+			"Sell" => Side::Sell,
+			other => return Err(ExchangeError::Other(eyre::eyre!("Unexpected Bybit closed pnl side: {other}"))),
+		};
+		Ok(ClosedPnl {
+			symbol: Symbol::new(pair, instrument),
+			side,
+			qty: self.closed_size,
+			entry_price: self.avg_entry_price,
+			exit_price: self.avg_exit_price,
+			realized_pnl: self.closed_pnl,
+			close_time: Timestamp::from_millisecond(self.updated_time).map_err(|e| ExchangeError::Other(eyre::eyre!("Invalid Bybit close time {}: {e}", self.updated_time)))?,
+			cum_entry_value: self.cum_entry_value,
+			cum_exit_value: self.cum_exit_value,
+		})
+	}
+}
+
+/// Max page size of `/v5/position/closed-pnl`.
+const CLOSED_PNL_PAGE: u32 = 100;
+
+/// `GET /v5/position/closed-pnl`, following `cursor` until the range is exhausted. Without a `symbol`, covers all of linear.
+///
+/// Bybit caps a [Span](RequestRange::Span) at 7 days; an open one ends 7 days after `since`. [Limit](RequestRange::Limit) takes the latest `n` closes.
+pub(super) async fn closed_pnl(client: &Client, symbol: Option<Symbol>, range: RequestRange, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<ClosedPnl>> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let instrument = symbol.map_or(Instrument::Perp, |s| s.instrument);
+	let category = match instrument {
+		Instrument::Perp => "linear",
+		Instrument::PerpInverse => "inverse",
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Bybit, instrument, "closed_pnl"))),
+	};
+	let wanted = match range {
+		RequestRange::Limit(n) => Some(n as usize),
+		RequestRange::Span { .. } => None,
+	};
+	let mut closes = Vec::new();
+	let mut cursor = String::new();
+	while wanted.is_none_or(|n| closes.len() < n) {
+		let mut params = vec![("category", category.to_owned())];
+		if let Some(symbol) = symbol {
+			params.push(("symbol", symbol.pair.fmt_bybit()));
+		}
+		if let RequestRange::Span { since, until } = range {
+			params.push(("startTime", since.as_millisecond().to_string()));
+			if let Some(until) = until {
+				params.push(("endTime", until.as_millisecond().to_string()));
+			}
+		}
+		let page = match wanted {
+			Some(n) => (n - closes.len()).min(CLOSED_PNL_PAGE as usize) as u32,
+			None => CLOSED_PNL_PAGE,
+		};
+		params.push(("limit", page.to_string()));
+		if !cursor.is_empty() {
+			params.push(("cursor", cursor.clone()));
+		}
+
+		let mut options = vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)];
+		if let Some(rw) = recv_window {
+			options.push(BybitOption::RecvWindow(rw));
+		}
+		let r: ClosedPnlResponse = client.get("/v5/position/closed-pnl", &params, options).await?;
+		for record in r.result.list {
+			closes.push(record.into_closed_pnl(instrument)?);
+		}
+		cursor = r.result.next_page_cursor;
+		if cursor.is_empty() {
+			break;
+		}
+	}
+	Ok(closes)
+}
+//,}}}
+
+// positions {{{
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PositionListResponse {
+	result: PositionListResult,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PositionListResult {
+	list: Vec<PositionRecord>,
+	#[serde(default)]
+	next_page_cursor: String,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PositionRecord {
+	symbol: String,
+	/// `""` when flat.
+	side: String,
+	#[serde_as(as = "DisplayFromStr")]
+	size: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	avg_price: f64,
+	#[serde_as(as = "NoneAsEmptyString")]
+	mark_price: Option<f64>,
+	#[serde_as(as = "DisplayFromStr")]
+	unrealised_pnl: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	updated_time: i64,
+}
+
+/// Max page size of `/v5/position/list`.
+const POSITION_LIST_PAGE: u32 = 200;
+
+/// `GET /v5/position/list`, following `cursor`. Linear is filtered by `settleCoin=USDT`, as Bybit wants either that or a symbol.
+pub(super) async fn positions(client: &Client, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let mut params = match instrument {
+		Instrument::Perp => vec![("category", "linear".to_owned()), ("settleCoin", "USDT".to_owned())],
+		Instrument::PerpInverse => vec![("category", "inverse".to_owned())],
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Bybit, instrument, "positions"))),
+	};
+	params.push(("limit", POSITION_LIST_PAGE.to_string()));
+	let mut positions = Vec::new();
+	let mut cursor = String::new();
+	loop {
+		let mut page_params = params.clone();
+		if !cursor.is_empty() {
+			page_params.push(("cursor", cursor.clone()));
+		}
+		let mut options = vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)];
+		if let Some(rw) = recv_window {
+			options.push(BybitOption::RecvWindow(rw));
+		}
+		let r: PositionListResponse = client.get("/v5/position/list", &page_params, options).await?;
+		for p in r.result.list {
+			let sign = match p.side.as_str() {
+				"Buy" => 1.,
+				"Sell" => -1.,
+				_ => continue,
+			};
+			if p.size == 0. {
+				continue;
+			}
+			let pair = Pair::from_str(&p.symbol).map_err(|e| ExchangeError::Other(eyre::eyre!("Unexpected Bybit symbol {}: {e}", p.symbol)))?;
+			positions.push(Position {
+				symbol: Symbol::new(pair, instrument),
+				size: sign * p.size,
+				entry_price: p.avg_price,
+				mark_price: p.mark_price,
+				unrealized_pnl: p.unrealised_pnl,
+				updated_at: Timestamp::from_millisecond(p.updated_time).map_err(|e| ExchangeError::Other(eyre::eyre!("Invalid Bybit position update time {}: {e}", p.updated_time)))?,
+			});
+		}
+		cursor = r.result.next_page_cursor;
+		if cursor.is_empty() {
+			break;
+		}
+	}
+	Ok(positions)
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn closed_pnl_record() {
+		let json = r#"{"retCode":0,"retMsg":"OK","result":{"nextPageCursor":"5a373bfe-188d-4913-9c81-d57ab5be8068%3A1672214887231423699%2C5a373bfe-188d-4913-9c81-d57ab5be8068%3A1672214887231423699","category":"linear","list":[{"symbol":"ETHUSDT","orderType":"Market","leverage":"3","updatedTime":"1672214887236","side":"Sell","orderId":"5a373bfe-188d-4913-9c81-d57ab5be8068","closedPnl":"-47.4065323","avgEntryPrice":"1194.97516667","qty":"3","cumEntryValue":"3584.9255","createdTime":"1672214887231","orderPrice":"1122.95","closedSize":"3","avgExitPrice":"1180.59833333","execType":"Trade","fillCount":"4","cumExitValue":"3541.795"}]},"retExtInfo":{},"time":1672214887238}"#;
+		let r: ClosedPnlResponse = serde_json::from_str(json).unwrap();
+		assert!(!r.result.next_page_cursor.is_empty());
+		let record = r.result.list.into_iter().next().unwrap();
+		let close = record.into_closed_pnl(Instrument::Perp).unwrap();
+		assert_eq!(close.symbol, Symbol::new(Pair::new("ETH", "USDT"), Instrument::Perp));
+		assert_eq!(close.side, Side::Sell);
+		assert_eq!(close.qty, 3.);
+		assert_eq!(close.realized_pnl, -47.4065323);
+		assert_eq!(close.close_time, Timestamp::from_millisecond(1672214887236).unwrap());
+	}
+
+	#[test]
+	fn account_info_type() {
+		let json = r#"{"retCode":0,"retMsg":"OK","result":{"marginMode":"REGULAR_MARGIN","updatedTime":"1697078946000","unifiedMarginStatus":4,"dcpStatus":"OFF","timeWindow":10,"smpGroup":0,"isMasterTrader":false,"spotHedgingStatus":"OFF"}}"#;
+		let r: AccountInfoResponse = serde_json::from_str(json).unwrap();
+		let account_type = crate::AccountType::try_from(r.result).unwrap();
+		assert_eq!(account_type, crate::AccountType::UnifiedTrading);
+		assert!(matches!(wallet_account_type(account_type, Instrument::Spot, "personal_info"), Ok(AccountType::Unified)));
+		assert!(matches!(wallet_account_type(crate::AccountType::Classic, Instrument::Perp, "personal_info"), Ok(AccountType::Contract)));
+		assert!(matches!(wallet_account_type(crate::AccountType::Classic, Instrument::Spot, "personal_info"), Ok(AccountType::Spot)));
+		assert!(matches!(wallet_account_type(crate::AccountType::UnifiedMargin, Instrument::Spot, "personal_info"), Ok(AccountType::Spot)));
+		assert!(matches!(wallet_account_type(crate::AccountType::UnifiedMargin, Instrument::PerpInverse, "personal_info"), Ok(AccountType::Contract)));
+	}
+
+	#[test]
+	fn unified_wallet_balances() {
+		let json = r#"{"retCode":0,"retMsg":"OK","result":{"list":[{"totalEquity":"6100.02","accountIMRate":"0","totalMarginBalance":"6100.02","totalInitialMargin":"0","accountType":"UNIFIED","totalAvailableBalance":"6100.02","accountMMRate":"0","totalPerpUPL":"0","totalWalletBalance":"6100.02","accountLTV":"0","totalMaintenanceMargin":"0","coin":[{"availableToBorrow":"","bonus":"0","accruedInterest":"0","availableToWithdraw":"","totalOrderIM":"0","equity":"0.1","totalPositionMM":"0","usdValue":"6000","spotHedgingQty":"0","unrealisedPnl":"0","collateralSwitch":true,"borrowAmount":"0.000000000000000000","totalPositionIM":"0","walletBalance":"0.1","cumRealisedPnl":"0","locked":"0.02","marginCollateral":true,"coin":"BTC"},{"availableToBorrow":"","bonus":"0","accruedInterest":"0","availableToWithdraw":"","totalOrderIM":"0","equity":"100","totalPositionMM":"0","usdValue":"100.02","spotHedgingQty":"0","unrealisedPnl":"0","collateralSwitch":true,"borrowAmount":"0.000000000000000000","totalPositionIM":"0","walletBalance":"100","cumRealisedPnl":"-0.5","locked":"0","marginCollateral":true,"coin":"USDT"}]}]},"retExtInfo":{},"time":1690872862481}"#;
+		let r: AccountResponse = serde_json::from_str(json).unwrap();
+		let info = &r.result.list[0];
+		let balances = wallet_balances(info);
+		let btc = balances.iter().find(|b| b.asset == "BTC").unwrap();
+		assert_eq!((btc.underlying, btc.usd.map(|u| *u)), (0.1, Some(6000.)));
+		assert_eq!(btc.locked, Some(0.02));
+		assert!((btc.available.unwrap() - 0.08).abs() < 1e-12);
+		assert_eq!(wallet_total(info, &balances), 6100.02);
+	}
+
+	#[test]
+	fn classic_spot_wallet_balances() {
+		let json = r#"{"retCode":0,"retMsg":"OK","result":{"list":[{"totalEquity":"","accountIMRate":"","totalMarginBalance":"","totalInitialMargin":"","accountType":"SPOT","totalAvailableBalance":"","accountMMRate":"","totalPerpUPL":"","totalWalletBalance":"","accountLTV":"","totalMaintenanceMargin":"","coin":[{"availableToBorrow":"","bonus":"","accruedInterest":"","availableToWithdraw":"","totalOrderIM":"","equity":"","totalPositionMM":"","usdValue":"","unrealisedPnl":"","collateralSwitch":false,"borrowAmount":"","totalPositionIM":"","walletBalance":"0.5","cumRealisedPnl":"","locked":"0.1","marginCollateral":false,"free":"0.4","spotHedgingQty":"","coin":"ETH"},{"availableToBorrow":"","bonus":"","accruedInterest":"","availableToWithdraw":"","totalOrderIM":"","equity":"","totalPositionMM":"","usdValue":"50","unrealisedPnl":"","collateralSwitch":false,"borrowAmount":"","totalPositionIM":"","walletBalance":"50","cumRealisedPnl":"","locked":"0","marginCollateral":false,"free":"50","spotHedgingQty":"","coin":"USDT"}]}]},"retExtInfo":{},"time":1690872862481}"#;
+		let r: AccountResponse = serde_json::from_str(json).unwrap();
+		let info = &r.result.list[0];
+		let balances = wallet_balances(info);
+		let eth = balances.iter().find(|b| b.asset == "ETH").unwrap();
+		assert_eq!((eth.underlying, eth.available, eth.locked), (0.5, Some(0.4), Some(0.1)));
+		assert!(eth.usd.is_none(), "not valued by Bybit, and not made up");
+		assert_eq!(wallet_total(info, &balances), 50.);
+	}
+
+	#[test]
+	fn query_api_key_permissions() {
+		let json = r#"{"retCode":0,"retMsg":"","result":{"id":"13770661","note":"bot","apiKey":"XXXXXX","readOnly":0,"secret":"","permissions":{"ContractTrade":["Order","Position"],"Spot":["SpotTrade"],"Wallet":["AccountTransfer","SubMemberTransfer"],"Options":[],"Derivatives":[],"Exchange":[],"Earn":[],"BlockTrade":[],"Affiliate":[],"NFT":[]},"ips":["*"],"type":1,"deadlineDay":66,"expiredAt":"2023-12-22T07:20:25Z","createdAt":"2022-10-16T02:24:40Z","unified":0,"uta":0,"userID":24617703,"inviterID":0,"vipLevel":"No VIP","mktMakerLevel":"0","affiliateID":0,"rsaPublicKey":"","isMaster":true,"parentUid":"0","kycLevel":"LEVEL_DEFAULT","kycRegion":""},"retExtInfo":{},"time":1697525990798}"#;
+		let r: QueryApiResponse = serde_json::from_str(json).unwrap();
+		let permissions = KeyPermissions::from(r.result);
+		assert_eq!(permissions, KeyPermissions {
+			read: true,
+			spot_trade: true,
+			futures_trade: true,
+			withdraw: false,
+			ip_restricted: Some(false),
+			expires: Some("2023-12-22T07:20:25Z".parse().unwrap()),
+		});
+
+		let needed = KeyPermissions {
+			futures_trade: true,
+			withdraw: true,
+			expires: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+			..Default::default()
+		};
+		assert_eq!(permissions.missing(&needed), vec![
+			"withdraw".to_owned(),
+			"validity until 2024-01-01T00:00:00Z (expires 2023-12-22T07:20:25Z)".to_owned()
+		]);
+	}
+
+	#[test]
+	fn read_only_key_cannot_trade() {
+		let json = r#"{"result":{"readOnly":1,"permissions":{"ContractTrade":["Order","Position"],"Spot":["SpotTrade"]},"ips":["203.0.113.7"],"expiredAt":""}}"#;
+		let r: QueryApiResponse = serde_json::from_str(json).unwrap();
+		let permissions = KeyPermissions::from(r.result);
+		assert!(!permissions.spot_trade && !permissions.futures_trade);
+		assert_eq!(permissions.ip_restricted, Some(true));
+		assert_eq!(permissions.expires, None);
+	}
+}
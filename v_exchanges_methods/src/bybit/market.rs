@@ -14,17 +14,18 @@ use v_utils::{
 	utils::filter_nulls,
 };
 
-use super::{BybitInterval, BybitIntervalTime};
+use super::BybitInterval;
 use crate::{
-	ExchangeName, ExchangeResult, Instrument, Symbol,
-	core::{ExchangeInfo, Klines, OpenInterest, PairInfo, RequestRange},
+	ExchangeName, ExchangeResult, Instrument, PerpInfo, Symbol,
+	core::{ExchangeInfo, KlineKind, Klines, OpenInterest, OpenInterestHistory, PairInfo, RequestRange, funding_countdown_from},
+	error::ctx,
 };
 
 // klines {{{
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct KlineResponse {
-	pub result: ResponseResult,
+pub struct KlineResponse<K = KlineData> {
+	pub result: ResponseResult<K>,
 	pub ret_code: i32,
 	pub ret_ext_info: AHashMap<String, serde_json::Value>,
 	pub ret_msg: String,
@@ -32,9 +33,9 @@ pub struct KlineResponse {
 }
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct ResponseResult {
+pub struct ResponseResult<K> {
 	pub category: String,
-	pub list: Vec<KlineData>,
+	pub list: Vec<K>,
 	pub symbol: String,
 }
 #[serde_as]
@@ -48,6 +49,16 @@ pub struct KlineData(
 	#[serde_as(as = "DisplayFromStr")] pub f64,
 	#[serde_as(as = "DisplayFromStr")] pub f64,
 );
+/// Mark and index price klines: `[startTime, open, high, low, close]`, no volume.
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PriceKlineData(
+	#[serde_as(as = "DisplayFromStr")] pub i64,
+	#[serde_as(as = "DisplayFromStr")] pub f64,
+	#[serde_as(as = "DisplayFromStr")] pub f64,
+	#[serde_as(as = "DisplayFromStr")] pub f64,
+	#[serde_as(as = "DisplayFromStr")] pub f64,
+);
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketTickerResponse {
@@ -130,37 +141,68 @@ pub struct OpenInterestData {
 	#[serde_as(as = "DisplayFromStr")]
 	pub timestamp: i64,
 }
-pub(super) async fn klines(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BybitInterval, range: RequestRange) -> ExchangeResult<Klines> {
+pub(super) async fn klines(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BybitInterval, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines> {
 	range.ensure_allowed(1..=1000, &tf)?;
-	let range_json = range.serialize(ExchangeName::Bybit);
-	let base_params = filter_nulls(json!({
-		"category": "linear", // can be ["linear", "inverse", "spot"] afaiu, could drive some generics with this later, but for now hardcode
-		"symbol": symbol.pair.fmt_bybit(),
-		"interval": tf.to_string(),
-	}));
-
-	let mut base_map = base_params.as_object().unwrap().clone();
-	let range_map = range_json.as_object().unwrap();
-	base_map.extend(range_map.clone());
-	let params = filter_nulls(serde_json::Value::Object(base_map));
+	let mut params = Vec::with_capacity(6);
+	params.push(("category", "linear".to_owned())); // can be ["linear", "inverse", "spot"] afaiu, could drive some generics with this later, but for now hardcode
+	params.push(("symbol", symbol.pair.fmt_bybit()));
+	params.push(("interval", tf.fmt_kline()));
+	range.append_exchange_params(ExchangeName::Bybit, &tf, &mut params);
 
 	let options = vec![BybitOption::None];
-	let kline_response: KlineResponse = client.get("/v5/market/kline", &params, options).await?;
+	// (open_time, ohlc, volume_quote)
+	let (response_time, rows): (i64, Vec<(i64, Ohlc, f64)>) = match kind {
+		KlineKind::LastPrice => {
+			let r: KlineResponse = ctx!(client.get("/v5/market/kline", &params, options).await, Bybit.klines(symbol, tf) -> "/v5/market/kline")?;
+			let rows = r
+				.result
+				.list
+				.into_iter()
+				.map(|k| {
+					let ohlc = Ohlc {
+						open: k.1,
+						close: k.2,
+						high: k.3,
+						low: k.4,
+					};
+					(k.0, ohlc, k.5)
+				})
+				.collect();
+			(r.time, rows)
+		}
+		KlineKind::MarkPrice | KlineKind::IndexPrice => {
+			let endpoint = match kind {
+				KlineKind::MarkPrice => "/v5/market/mark-price-kline",
+				_ => "/v5/market/index-price-kline",
+			};
+			let r: KlineResponse<PriceKlineData> = ctx!(client.get(endpoint, &params, options).await, Bybit.klines(symbol, tf, kind) -> endpoint)?;
+			let rows = r
+				.result
+				.list
+				.into_iter()
+				.map(|k| {
+					let ohlc = Ohlc {
+						open: k.1,
+						high: k.2,
+						low: k.3,
+						close: k.4,
+					};
+					(k.0, ohlc, 0.)
+				})
+				.collect();
+			(r.time, rows)
+		}
+	};
 
-	let mut klines = VecDeque::with_capacity(kline_response.result.list.len());
-	for k in kline_response.result.list {
-		if kline_response.time > k.0 + tf.duration().as_millis() as i64
+	let mut klines = VecDeque::with_capacity(rows.len());
+	for (open_time, ohlc, volume_quote) in rows {
+		if response_time > open_time + tf.duration().as_millis() as i64
 		/*take `as_millis`, so ok to downcast in all practical applications*/
 		{
 			klines.push_back(Kline {
-				open_time: Timestamp::from_millisecond(k.0).unwrap(),
-				ohlc: Ohlc {
-					open: k.1,
-					close: k.2,
-					high: k.3,
-					low: k.4,
-				},
-				volume_quote: k.5,
+				open_time: Timestamp::from_millisecond(open_time).unwrap(),
+				ohlc,
+				volume_quote,
 				trades: None,
 				taker_buy_volume_quote: None,
 			});
@@ -193,7 +235,8 @@ struct TickerPriceResponse {
 pub(super) async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
 	let category = match instrument {
 		Instrument::Perp => "linear",
-		_ => unimplemented!(),
+		Instrument::Spot => "spot",
+		_ => return Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_implemented(ExchangeName::Bybit, instrument, "prices"))),
 	};
 	let params = filter_nulls(json!({ "category": category }));
 	let options = vec![BybitOption::None];
@@ -213,66 +256,141 @@ pub(super) async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<
 }
 //,}}}
 
-// open_interest {{{
-pub(super) async fn open_interest(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BybitIntervalTime, range: RequestRange) -> ExchangeResult<Vec<OpenInterest>> {
-	range.ensure_allowed(1..=200, &tf)?;
-	let range_json = range.serialize(ExchangeName::Bybit);
+// funding {{{
+pub(super) async fn funding_countdown(client: &v_exchanges_adapters::Client, symbol: Symbol) -> ExchangeResult<(std::time::Duration, f64)> {
+	let category = match symbol.instrument {
+		Instrument::PerpInverse => "inverse",
+		_ => "linear",
+	};
+	let params = [("category", category.to_owned()), ("symbol", symbol.pair.fmt_bybit())];
+	let options = vec![BybitOption::None];
+	let response: MarketTickerResponse = client.get("/v5/market/tickers", &params, options).await?;
+	let ticker = response.result.list.first().ok_or_else(|| crate::ExchangeError::Other(eyre::eyre!("No ticker returned for {symbol}")))?;
+	let next_funding_time: i64 = ticker.next_funding_time.parse().map_err(|e| crate::ExchangeError::Other(eyre::eyre!("Invalid nextFundingTime {:?}: {e}", ticker.next_funding_time)))?;
+	Ok((funding_countdown_from(next_funding_time, response.time), ticker.funding_rate))
+}
 
-	let base_params = filter_nulls(json!({
-		"category": "linear",
-		"symbol": symbol.pair.fmt_bybit(),
-		"intervalTime": tf.to_string(),
-	}));
+pub(super) async fn mark_price(client: &v_exchanges_adapters::Client, symbol: Symbol) -> ExchangeResult<f64> {
+	let category = match symbol.instrument {
+		Instrument::PerpInverse => "inverse",
+		_ => "linear",
+	};
+	let params = [("category", category.to_owned()), ("symbol", symbol.pair.fmt_bybit())];
+	let options = vec![BybitOption::None];
+	let response: MarketTickerResponse = client.get("/v5/market/tickers", &params, options).await?;
+	let ticker = response.result.list.first().ok_or_else(|| crate::ExchangeError::Other(eyre::eyre!("No ticker returned for {symbol}")))?;
+	Ok(ticker.mark_price)
+}
+//,}}}
 
-	let mut base_map = base_params.as_object().unwrap().clone();
-	let range_map = range_json.as_object().unwrap();
-	base_map.extend(range_map.clone());
-	let params = filter_nulls(serde_json::Value::Object(base_map));
+// perpetual_universe {{{
+/// All of it is on the linear tickers. Dated futures share the category, but come with an empty funding rate, which is what filters them out.
+pub(super) async fn perpetual_universe(client: &v_exchanges_adapters::Client) -> ExchangeResult<BTreeMap<Pair, PerpInfo>> {
+	let params = [("category", "linear")];
+	let options = vec![BybitOption::None];
+	let response: UniverseTickersResponse = client.get("/v5/market/tickers", &params, options).await?;
+	Ok(response.into_universe())
+}
+
+#[derive(Debug, Deserialize)]
+struct UniverseTickersResponse {
+	result: UniverseTickersResult,
+}
+#[derive(Debug, Deserialize)]
+struct UniverseTickersResult {
+	list: Vec<UniverseTicker>,
+}
+/// Strings throughout, as not all contracts fill every field.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UniverseTicker {
+	symbol: String,
+	mark_price: String,
+	funding_rate: String,
+	next_funding_time: String,
+	open_interest_value: String,
+	turnover24h: String,
+	/// Fraction, not percent.
+	price24h_pcnt: String,
+}
+impl UniverseTickersResponse {
+	fn into_universe(self) -> BTreeMap<Pair, PerpInfo> {
+		self.result
+			.list
+			.into_iter()
+			.filter_map(|t| {
+				let info = PerpInfo {
+					mark_price: t.mark_price.parse().ok()?,
+					funding_rate: t.funding_rate.parse().ok()?,
+					next_funding_time: Timestamp::from_millisecond(t.next_funding_time.parse().ok()?).ok()?,
+					open_interest_usd: t.open_interest_value.parse().ok(),
+					volume_24h_usd: t.turnover24h.parse().ok()?,
+					price_change_24h_pct: t.price24h_pcnt.parse::<f64>().ok()? * 100.,
+				};
+				Some((Pair::from_str(&t.symbol).ok()?, info))
+			})
+			.collect()
+	}
+}
+//,}}}
+
+// open_interest {{{
+pub(super) async fn open_interest(client: &v_exchanges_adapters::Client, symbol: Symbol, tf: BybitInterval, range: RequestRange) -> ExchangeResult<OpenInterestHistory> {
+	// spot has no open interest
+	let category = match symbol.instrument {
+		Instrument::Perp => "linear",
+		Instrument::PerpInverse => "inverse",
+		_ => return Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(ExchangeName::Bybit, symbol.instrument, "open_interest"))),
+	};
+	range.ensure_allowed(1..=200, &tf)?;
+	let mut params = Vec::with_capacity(6);
+	params.push(("category", category.to_owned()));
+	params.push(("symbol", symbol.pair.fmt_bybit()));
+	params.push(("intervalTime", tf.fmt_interval_time()?.to_owned()));
+	range.append_params(ExchangeName::Bybit, &mut params);
 
 	let options = vec![BybitOption::None];
-	let response: OpenInterestResponse = client.get("/v5/market/open-interest", &params, options).await?;
+	let response: OpenInterestResponse = ctx!(client.get("/v5/market/open-interest", &params, options).await, Bybit.open_interest(symbol, tf) -> "/v5/market/open-interest")?;
 
 	if response.result.list.is_empty() {
 		return Err(crate::ExchangeError::Other(eyre::eyre!("No open interest data returned")));
 	}
 
 	// For PerpInverse, we need to fetch the price to convert
-	let price = if symbol.instrument == Instrument::PerpInverse {
-		let params = filter_nulls(json!({
-			"category": "linear",
-			"symbol": symbol.pair.fmt_bybit(),
-		}));
-		let options = vec![BybitOption::None];
-		let ticker_response: MarketTickerResponse = client.get("/v5/market/tickers", &params, options).await?;
-		Some(ticker_response.result.list[0].last_price)
-	} else {
-		None
+	let inverse_price = match symbol.instrument {
+		Instrument::PerpInverse => {
+			let params = [("category", category.to_owned()), ("symbol", symbol.pair.fmt_bybit())];
+			let options = vec![BybitOption::None];
+			let ticker_response: MarketTickerResponse = client.get("/v5/market/tickers", &params, options).await?;
+			let ticker = ticker_response.result.list.first().ok_or_else(|| crate::ExchangeError::Other(eyre::eyre!("No ticker returned for {symbol}")))?;
+			Some(ticker.last_price)
+		}
+		_ => None,
 	};
+	Ok(open_interest_history(response, symbol.pair, inverse_price))
+}
 
-	// Convert all data points to OpenInterest
-	let mut result = Vec::with_capacity(response.result.list.len());
-	for data in response.result.list {
-		let (val_asset, val_quote) = match symbol.instrument {
-			Instrument::PerpInverse => {
-				// as of (2025/10/14), Bybit returns value in `quote` for Inverse and in `asset` for Linear reqs
-				let val_quote = data.open_interest;
-				let price = price.expect("price should be set for PerpInverse");
-				let val_asset = val_quote / price;
-				(val_asset, Some(val_quote))
-			}
-			Instrument::Perp => (data.open_interest, None),
-			_ => unreachable!(),
+/// `inverse_price` converts inverse OI, which as of (2025/10/14) Bybit returns in `quote` (while in `asset` for Linear reqs), back to asset.
+fn open_interest_history(response: OpenInterestResponse, pair: Pair, inverse_price: Option<f64>) -> OpenInterestHistory {
+	let mut result = OpenInterestHistory::new(Vec::with_capacity(response.result.list.len()));
+	// sent latest first
+	for data in response.result.list.into_iter().rev() {
+		let (val_asset, val_quote) = match inverse_price {
+			Some(price) => (data.open_interest / price, Some(data.open_interest)),
+			None => (data.open_interest, None),
 		};
 
-		result.push(OpenInterest {
-			val_asset,
-			val_quote,
-			timestamp: Timestamp::from_millisecond(data.timestamp).unwrap(),
-			..Default::default()
-		});
+		result.push(
+			OpenInterest {
+				val_asset,
+				val_quote,
+				timestamp: Timestamp::from_millisecond(data.timestamp).unwrap(),
+				..Default::default()
+			}
+			.with_usd(pair),
+		);
 	}
-
-	Ok(result)
+	result
 }
 
 //,}}}
@@ -289,10 +407,19 @@ struct InstrumentsInfoResponse {
 struct InstrumentsInfoResult {
 	list: Vec<InstrumentInfo>,
 }
+#[serde_as]
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct LotSizeFilter {
 	qty_step: String,
+	#[serde_as(as = "DisplayFromStr")]
+	min_order_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	max_order_qty: f64,
+	/// Linear only.
+	#[serde_as(as = "Option<DisplayFromStr>")]
+	#[serde(default)]
+	min_notional_value: Option<f64>,
 }
 
 #[serde_as]
@@ -300,18 +427,36 @@ struct LotSizeFilter {
 #[serde(rename_all = "camelCase")]
 struct InstrumentInfo {
 	symbol: String,
+	status: String,
 	price_scale: String,
 	/// Millisecond timestamp; 0 for perpetuals
 	#[serde_as(as = "DisplayFromStr")]
 	delivery_time: i64,
 	lot_size_filter: LotSizeFilter,
+	price_filter: PriceFilter,
+}
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PriceFilter {
+	#[serde_as(as = "DisplayFromStr")]
+	min_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	max_price: f64,
 }
 pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
 	if instrument == Instrument::Spot {
+		#[serde_as]
 		#[derive(Deserialize)]
 		#[serde(rename_all = "camelCase")]
 		struct SpotLotSizeFilter {
 			base_precision: String,
+			#[serde_as(as = "DisplayFromStr")]
+			min_order_qty: f64,
+			#[serde_as(as = "DisplayFromStr")]
+			max_order_qty: f64,
+			#[serde_as(as = "DisplayFromStr")]
+			min_order_amt: f64,
 		}
 		#[derive(Deserialize)]
 		#[serde(rename_all = "camelCase")]
@@ -322,6 +467,7 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, instrum
 		#[serde(rename_all = "camelCase")]
 		struct SpotInfo {
 			symbol: String,
+			status: String,
 			lot_size_filter: SpotLotSizeFilter,
 			price_filter: SpotPriceFilter,
 		}
@@ -365,6 +511,13 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, instrum
 						price_precision,
 						qty_precision,
 						delivery_date: None,
+						min_qty: Some(i.lot_size_filter.min_order_qty),
+						max_qty: Some(i.lot_size_filter.max_order_qty),
+						qty_step: i.lot_size_filter.base_precision.parse().ok(),
+						min_notional: Some(i.lot_size_filter.min_order_amt),
+						min_price: None,
+						max_price: None,
+						status: Some(i.status),
 					},
 				))
 			})
@@ -375,7 +528,7 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, instrum
 	let category = match instrument {
 		Instrument::Perp => "linear",
 		Instrument::PerpInverse => "inverse",
-		_ => unimplemented!(),
+		_ => return Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_implemented(ExchangeName::Bybit, instrument, "exchange_info"))),
 	};
 	let response: InstrumentsInfoResponse = client
 		.get("/v5/market/instruments-info", &[("category", category), ("limit", "1000")], vec![BybitOption::None])
@@ -404,6 +557,13 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, instrum
 					price_precision,
 					qty_precision,
 					delivery_date,
+					min_qty: Some(i.lot_size_filter.min_order_qty),
+					max_qty: Some(i.lot_size_filter.max_order_qty),
+					qty_step: i.lot_size_filter.qty_step.parse().ok(),
+					min_notional: i.lot_size_filter.min_notional_value,
+					min_price: Some(i.price_filter.min_price),
+					max_price: Some(i.price_filter.max_price),
+					status: Some(i.status),
 				},
 			))
 		})
@@ -411,3 +571,35 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, instrum
 	Ok(ExchangeInfo { server_time, pairs })
 }
 //,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn perpetual_universe_skips_dated_futures() {
+		let json = r#"{"retCode":0,"retMsg":"OK","result":{"category":"linear","list":[
+			{"symbol":"BTCUSDT","lastPrice":"67523.50","indexPrice":"67530.21","markPrice":"67523.10","prevPrice24h":"66700.40","price24hPcnt":"0.012341","highPrice24h":"68123.00","lowPrice24h":"66600.10","prevPrice1h":"67480.00","openInterest":"55412.831","openInterestValue":"3741651738.12","turnover24h":"1725628717.1200","volume24h":"25541.9360","fundingRate":"0.0001","nextFundingTime":"1729843200000","predictedDeliveryPrice":"","basisRate":"","deliveryFeeRate":"","deliveryTime":"0","ask1Size":"1.2","bid1Price":"67523.40","ask1Price":"67523.50","bid1Size":"3.1","basis":""},
+			{"symbol":"BTC-27DEC24","lastPrice":"68900.00","indexPrice":"67530.21","markPrice":"68900.10","prevPrice24h":"68800.00","price24hPcnt":"0.001453","highPrice24h":"69000.00","lowPrice24h":"68500.00","prevPrice1h":"68900.00","openInterest":"12.5","openInterestValue":"861251.25","turnover24h":"2210000.00","volume24h":"32.1","fundingRate":"","nextFundingTime":"0","predictedDeliveryPrice":"","basisRate":"0.0203","deliveryFeeRate":"0.0005","deliveryTime":"1735286400000","ask1Size":"0.1","bid1Price":"68899.00","ask1Price":"68901.00","bid1Size":"0.2","basis":"1369.79"}
+		]},"retExtInfo":{},"time":1729843222921}"#;
+		let universe = serde_json::from_str::<UniverseTickersResponse>(json).unwrap().into_universe();
+		assert_eq!(universe.len(), 1);
+		let btc = &universe[&Pair::new("BTC", "USDT")];
+		assert_eq!((btc.mark_price, btc.funding_rate), (67523.1, 0.0001));
+		assert_eq!(btc.open_interest_usd, Some(3741651738.12));
+		assert!((btc.price_change_24h_pct - 1.2341).abs() < 1e-9);
+		assert_eq!(btc.next_funding_time.as_millisecond(), 1729843200000);
+	}
+
+	#[test]
+	fn inverse_open_interest_is_in_usd() {
+		let json = r#"{"retCode":0,"retMsg":"OK","result":{"symbol":"BTCUSD","category":"inverse","list":[{"openInterest":"461134384.00000000","timestamp":"1669571400000"},{"openInterest":"461134292.00000000","timestamp":"1669571100000"}],"nextPageCursor":""},"retExtInfo":{},"time":1672053548579}"#;
+		let response: OpenInterestResponse = serde_json::from_str(json).unwrap();
+		let history = open_interest_history(response, Pair::new("BTC", "USD"), Some(16_000.));
+		assert_eq!(history[0].timestamp.as_millisecond(), 1669571100000, "oldest first");
+		let latest = &history[1];
+		assert_eq!(latest.val_quote, Some(461134384.));
+		assert_eq!(latest.val_asset, 461134384. / 16_000.);
+		assert_eq!(latest.val_usd.map(|u| u.0), Some(461134384.));
+	}
+}
@@ -1,41 +1,168 @@
 mod account;
 mod market;
+pub mod subaccount; // master-key operations with no counterpart on `Exchange`
+mod trade;
+#[cfg(feature = "bybit-ws")]
 mod ws;
+pub use account::{AccountType, ClosedPnl};
+#[cfg(feature = "bybit-ws")]
+pub use ws::parse_account_events;
+pub use subaccount::{Subaccount, SubaccountWallet};
 
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 
-use adapters::bybit::{BybitOption, BybitOptions};
+use adapters::bybit::{BybitHttpAuth, BybitOption, BybitOptions};
 use secrecy::SecretString;
 use v_exchanges_adapters::{Client, GetOptions};
-use v_utils::trades::{Pair, Timeframe};
+use v_utils::trades::{Asset, Pair, Timeframe};
 
+#[cfg(feature = "bybit-ws")]
+use adapters::generics::ws::SubscriptionSnapshot;
+
+#[cfg(feature = "bybit-ws")]
+use crate::{AccountEvent, BatchTrades, BookUpdate, ExchangeError, ExchangeStream, Liquidation, MethodError, PrecisionPriceQty};
 use crate::{
-	BookUpdate, ExchangeError, ExchangeInfo, ExchangeName, ExchangeResult, ExchangeStream, Instrument, MethodError, OpenInterest, PrecisionPriceQty, Symbol,
-	core::{ExchangeImpl, Klines, PersonalInfo, RequestRange},
+	AccountLabel, AssetBalance, ExchangeInfo, ExchangeName, ExchangeResult, FeeSchedule, InfoCache, Instrument, KeyPermissions, MutationJournal, OpenInterestHistory, Order, OrderDetails, OrderId, OrderPlaced, OrderState, OrderStatus, PerpInfo,
+	PerpUniverseCache, Position, Symbol, Venue,
+	core::{BasisFetcher, ExchangeImpl, KlineKind, Klines, KlinesGuard, PersonalInfo, RequestRange},
 };
 
+/// Options of a signed v5 request.
+pub(crate) fn signed_options(recv_window: Option<std::time::Duration>) -> Vec<BybitOption> {
+	let mut options = vec![BybitOption::HttpAuth(BybitHttpAuth::V3AndAbove)];
+	if let Some(rw) = recv_window {
+		options.push(BybitOption::RecvWindow(rw));
+	}
+	options
+}
+
 #[derive(Clone, Debug, Default, derive_more::Deref, derive_more::DerefMut)]
 pub struct Bybit {
 	#[deref]
 	#[deref_mut]
 	pub client: Client,
-	pub info_cache: BTreeMap<Instrument, ExchangeInfo>,
+	pub info_cache: InfoCache,
+	pub label: Option<AccountLabel>,
+	pub log_context: Option<String>,
+	pub klines_guard: Option<KlinesGuard>,
+	pub mutation_journal: Option<Arc<dyn MutationJournal>>,
+	/// Filled by the first [account_type](crate::Exchange::account_type) call, and emptied by [auth](crate::Exchange::auth), as it's the authenticated account's. Set it after auth to skip the detection, picking which wallets balances are read from.
+	pub account_type: std::sync::OnceLock<crate::AccountType>,
+	pub perp_universe: PerpUniverseCache,
+}
+
+impl Bybit {
+	/// All subaccounts of the master account the client is authenticated with.
+	pub async fn subaccounts(&self, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Subaccount>> {
+		subaccount::subaccounts(&self.client, recv_window).await
+	}
+
+	/// Returns the transfer id.
+	pub async fn subaccount_transfer(&self, from: SubaccountWallet, to: SubaccountWallet, asset: Asset, amount: f64, recv_window: Option<std::time::Duration>) -> ExchangeResult<uuid::Uuid> {
+		subaccount::subaccount_transfer(&self.client, from, to, asset, amount, recv_window).await
+	}
+
+	/// Balance of `asset` in the wallet `instrument` trades off, filtered down to it by Bybit rather than here.
+	pub async fn asset_balance(&self, asset: Asset, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<AssetBalance> {
+		let account_type = ExchangeImpl::account_type(self).await?;
+		account::asset_balance(&self.client, account_type, asset, instrument, recv_window).await
+	}
+
+	/// Realized P&L of closed positions, latest first. Without a `symbol`, covers all linear perps.
+	pub async fn closed_pnl(&self, symbol: Option<Symbol>, range: RequestRange, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<ClosedPnl>> {
+		account::closed_pnl(&self.client, symbol, range, recv_window).await
+	}
+
+	/// Liquidations on linear perps. Bybit pushes at most one per symbol per second, so this is a sample rather than the full set.
+	#[cfg(feature = "bybit-ws")]
+	pub fn ws_liquidations(&self, pairs: Vec<Pair>) -> ExchangeResult<Box<dyn ExchangeStream<Item = Liquidation>>> {
+		let connection = ws::BybitLiquidationStream::try_new(self, &pairs)?;
+		Ok(Box::new(connection))
+	}
+
+	/// Order, position and wallet updates of the account, over the authenticated private stream. Covers all categories.
+	#[cfg(feature = "bybit-ws")]
+	pub fn ws_user_data(&self) -> ExchangeResult<Box<dyn ExchangeStream<Item = AccountEvent>>> {
+		assert!(self.client.is_authenticated::<BybitOption>());
+		let connection = ws::BybitUserDataStream::try_new(self)?;
+		Ok(Box::new(connection))
+	}
+
+	/// Precisions of `pairs` for the ws streams, from the cached [exchange_info](crate::Exchange::exchange_info).
+	#[cfg(feature = "bybit-ws")]
+	async fn pair_precisions(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, PrecisionPriceQty>> {
+		let info = self.info_cache.get_or_fetch(instrument, ExchangeImpl::exchange_info(&*self, instrument)).await?;
+		pairs
+			.iter()
+			.map(|pair| {
+				info.pairs
+					.get(pair)
+					.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(ExchangeName::Bybit, instrument, *pair)))
+					.map(|pi| {
+						(
+							*pair,
+							PrecisionPriceQty {
+								price: pi.price_precision,
+								qty: pi.qty_precision,
+							},
+						)
+					})
+			})
+			.collect()
+	}
 }
 
 //? currently client ends up importing this from crate::binance, but could it be possible to lift the [Client] reexport up, and still have the ability to call all exchange methods right on it?
 #[async_trait::async_trait]
 impl ExchangeImpl for Bybit {
-	fn info_cache_mut(&mut self) -> &mut BTreeMap<Instrument, ExchangeInfo> {
-		&mut self.info_cache
+	fn info_cache(&self) -> &InfoCache {
+		&self.info_cache
+	}
+
+	fn label(&self) -> Option<&AccountLabel> {
+		self.label.as_ref()
 	}
 
-	fn name(&self) -> ExchangeName {
-		ExchangeName::Bybit
+	fn label_mut(&mut self) -> &mut Option<AccountLabel> {
+		&mut self.label
+	}
+
+	fn log_context(&self) -> Option<&str> {
+		self.log_context.as_deref()
+	}
+
+	fn log_context_mut(&mut self) -> &mut Option<String> {
+		&mut self.log_context
+	}
+
+	fn klines_guard(&self) -> Option<KlinesGuard> {
+		self.klines_guard
+	}
+
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard> {
+		&mut self.klines_guard
+	}
+
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>> {
+		self.mutation_journal.as_ref()
+	}
+
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>> {
+		&mut self.mutation_journal
+	}
+
+	fn perp_universe_cache(&self) -> Option<&PerpUniverseCache> {
+		Some(&self.perp_universe)
+	}
+
+	fn name(&self) -> Venue {
+		ExchangeName::Bybit.into()
 	}
 
 	fn auth(&mut self, pubkey: String, secret: SecretString) {
 		self.update_default_option(BybitOption::Pubkey(pubkey));
 		self.update_default_option(BybitOption::Secret(secret));
+		self.account_type.take();
 	}
 
 	fn set_recv_window(&mut self, recv_window: std::time::Duration) {
@@ -49,70 +176,222 @@ impl ExchangeImpl for Bybit {
 	async fn exchange_info(&self, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
 		match instrument {
 			Instrument::Perp | Instrument::PerpInverse | Instrument::Spot => market::exchange_info(self, instrument).await,
-			_ => unimplemented!(),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "exchange_info"))),
 		}
 	}
 
 	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
 		match symbol.instrument {
-			Instrument::Perp => market::klines(self, symbol, tf.try_into()?, range).await,
-			_ => unimplemented!(),
+			Instrument::Perp => market::klines(self, symbol, tf.try_into()?, range, KlineKind::LastPrice).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "klines"))),
+		}
+	}
+
+	async fn klines_kind(&self, symbol: Symbol, tf: Timeframe, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines> {
+		match symbol.instrument {
+			Instrument::Perp => market::klines(self, symbol, tf.try_into()?, range, kind).await,
+			_ => Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(self.name(), symbol.instrument, "klines_kind"))),
 		}
 	}
 
 	async fn prices(&self, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
 		match instrument {
-			Instrument::Perp => market::prices(self, pairs, instrument).await,
-			_ => unimplemented!(),
+			Instrument::Perp | Instrument::Spot => market::prices(self, pairs, instrument).await,
+			_ => Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(self.name(), instrument, "prices"))),
 		}
 	}
 
-	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Vec<OpenInterest>> {
+	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<OpenInterestHistory> {
 		match symbol.instrument {
-			Instrument::Perp => market::open_interest(self, symbol, tf.try_into()?, range).await,
-			_ => Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(self.name(), symbol.instrument))),
+			Instrument::Perp | Instrument::PerpInverse => market::open_interest(self, symbol, tf.try_into()?, range).await,
+			_ => Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(self.name(), symbol.instrument, "open_interest"))),
 		}
 	}
 
-	async fn personal_info(&self, _instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
-		account::personal_info(self, recv_window).await
+	async fn funding_countdown(&self, symbol: Symbol) -> ExchangeResult<(std::time::Duration, f64)> {
+		match symbol.instrument {
+			Instrument::Perp | Instrument::PerpInverse => market::funding_countdown(self, symbol).await,
+			_ => Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(self.name(), symbol.instrument, "funding_countdown"))),
+		}
+	}
+
+	async fn mark_price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		match symbol.instrument {
+			Instrument::Perp | Instrument::PerpInverse => market::mark_price(self, symbol).await,
+			_ => Err(crate::ExchangeError::Method(crate::MethodError::new_method_not_supported(self.name(), symbol.instrument, "mark_price"))),
+		}
 	}
 
+	async fn perpetual_universe(&self) -> ExchangeResult<BTreeMap<Pair, PerpInfo>> {
+		market::perpetual_universe(self).await
+	}
+
+	async fn basis_fetcher(&self, pair: Pair) -> ExchangeResult<BasisFetcher> {
+		let client = self.client.clone();
+		let exchange = self.name();
+		Ok(Box::new(move || {
+			let client = client.clone();
+			Box::pin(async move {
+				let (spot_prices, mark) =
+					futures_util::future::try_join(market::prices(&client, Some(vec![pair]), Instrument::Spot), market::mark_price(&client, Symbol::new(pair, Instrument::Perp))).await?;
+				let spot = spot_prices
+					.get(&pair)
+					.copied()
+					.ok_or_else(|| crate::ExchangeError::Method(crate::MethodError::new_pair_not_listed(exchange, Instrument::Spot, pair)))?;
+				Ok((spot, mark))
+			})
+		}))
+	}
+
+	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
+		let account_type = ExchangeImpl::account_type(self).await?;
+		account::personal_info(self, account_type, instrument, recv_window).await
+	}
+
+	async fn fees(&self, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+		account::fees(self, symbol, recv_window).await
+	}
+
+	async fn positions(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+		account::positions(self, instrument, recv_window).await
+	}
+
+	async fn key_permissions(&self) -> ExchangeResult<KeyPermissions> {
+		account::key_permissions(self).await
+	}
+
+	async fn account_type(&self) -> ExchangeResult<crate::AccountType> {
+		if let Some(account_type) = self.account_type.get() {
+			return Ok(*account_type);
+		}
+		let fetched = account::account_type(self).await?;
+		Ok(*self.account_type.get_or_init(|| fetched))
+	}
+
+	async fn place_order(&self, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		let info = self.info_cache.get(symbol.instrument);
+		order.validate(info.as_ref().and_then(|info| info.pairs.get(&symbol.pair)))?;
+		trade::place_order(self, symbol, order, recv_window).await
+	}
+
+	async fn order_status(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+		trade::order_status(self, symbol, order_id, recv_window).await
+	}
+
+	async fn get_order(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+		trade::get_order(self, symbol, order_id, recv_window).await
+	}
+
+	async fn get_orders(&self, instrument: Instrument, pair: Option<Pair>, status: Option<OrderStatus>, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<OrderDetails>> {
+		trade::get_orders(self, instrument, pair, status, recv_window).await
+	}
+
+	async fn amend_order(
+		&self,
+		order_id: OrderId,
+		symbol: Symbol,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<OrderPlaced> {
+		trade::amend_order(self, order_id, symbol, new_qty, new_price, recv_window).await
+	}
+
+	#[cfg(feature = "bybit-ws")]
+	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> Result<Box<dyn ExchangeStream<Item = BatchTrades>>, ExchangeError> {
+		match instrument {
+			Instrument::Perp | Instrument::Spot => {
+				let pair_precisions = self.pair_precisions(pairs, instrument).await?;
+				let connection = ws::TradesConnection::try_new(self, instrument, pair_precisions)?;
+				Ok(Box::new(connection))
+			}
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "ws_trades"))),
+		}
+	}
+
+	#[cfg(feature = "bybit-ws")]
 	async fn ws_book(&mut self, pairs: &[Pair], instrument: Instrument) -> Result<Box<dyn ExchangeStream<Item = BookUpdate>>, ExchangeError> {
 		match instrument {
 			Instrument::Perp | Instrument::Spot => {
-				if !self.info_cache.contains_key(&instrument) {
-					let info = ExchangeImpl::exchange_info(&*self, instrument).await?;
-					self.info_cache.insert(instrument, info);
-				}
-				let exchange = self.name();
-				let pair_precisions: BTreeMap<Pair, PrecisionPriceQty> = {
-					let info = self.info_cache.get(&instrument).expect("just inserted or was present");
-					pairs
-						.iter()
-						.map(|pair| {
-							info.pairs
-								.get(pair)
-								.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(exchange, instrument, *pair)))
-								.map(|pi| {
-									(
-										*pair,
-										PrecisionPriceQty {
-											price: pi.price_precision,
-											qty: pi.qty_precision,
-										},
-									)
-								})
-						})
-						.collect::<ExchangeResult<_>>()?
-				};
+				let pair_precisions = self.pair_precisions(pairs, instrument).await?;
 				let connection = ws::BookConnection::try_new(self, pairs, instrument, pair_precisions)?;
 				Ok(Box::new(connection))
 			}
-			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument))),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "ws_book"))),
 		}
 	}
+
+	#[cfg(feature = "bybit-ws")]
+	async fn ws_trades_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> Result<Box<dyn ExchangeStream<Item = BatchTrades>>, ExchangeError> {
+		let pairs = crate::core::snapshot_pairs(snapshot, ws::topic_pair)?;
+		let stream = ExchangeImpl::ws_trades(self, &pairs, instrument).await?;
+		crate::core::resumed(snapshot, stream)
+	}
+
+	#[cfg(feature = "bybit-ws")]
+	async fn ws_book_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> Result<Box<dyn ExchangeStream<Item = BookUpdate>>, ExchangeError> {
+		let pairs = crate::core::snapshot_pairs(snapshot, ws::topic_pair)?;
+		let stream = ExchangeImpl::ws_book(self, &pairs, instrument).await?;
+		crate::core::resumed(snapshot, stream)
+	}
+}
+
+crate::define_provider_timeframe!(
+	BybitInterval,
+	[
+		("1m", [], "1"),
+		("3m", [], "3"),
+		("5m", ["5min"], "5"),
+		("15m", ["15min"], "15"),
+		("30m", ["30min"], "30"),
+		("1h", [], "60"),
+		("2h", [], "120"),
+		("4h", [], "240"),
+		("6h", [], "360"),
+		("12h", [], "720"),
+		("1d", [], "D"),
+		("1w", [], "W"),
+		("1M", [], "M"),
+	],
+	test = bybit_interval_table
+);
+impl BybitInterval {
+	/// `interval` of the kline endpoints.
+	pub fn fmt_kline(&self) -> String {
+		self.to_string()
+	}
+
+	/// `intervalTime` of `/v5/market/open-interest`, which takes only a subset, spelled its own way.
+	pub fn fmt_interval_time(&self) -> Result<&'static str, crate::UnsupportedTimeframeError> {
+		const INTERVAL_TIME: [(&str, &str); 6] = [("5m", "5min"), ("15m", "15min"), ("30m", "30min"), ("1h", "1h"), ("4h", "4h"), ("1d", "1d")];
+		let canonical = Self::CANONICAL[self.table_index()];
+		INTERVAL_TIME
+			.iter()
+			.find(|(c, _)| *c == canonical)
+			.map(|(_, s)| *s)
+			.ok_or_else(|| crate::UnsupportedTimeframeError::new(self.0, INTERVAL_TIME.iter().map(|(c, _)| Timeframe::from(*c)).collect()))
+	}
 }
 
-crate::define_provider_timeframe!(BybitInterval, ["1", "3", "5", "15", "30", "60", "120", "240", "360", "720", "D", "W", "M"]);
-crate::define_provider_timeframe!(BybitIntervalTime, ["5min", "15min", "30min", "1h", "4h", "1d"]);
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn interval_per_endpoint() {
+		let tf = BybitInterval::from("1h");
+		assert_eq!(tf.fmt_kline(), "60");
+		assert_eq!(tf.fmt_interval_time().unwrap(), "1h");
+		assert_eq!(BybitInterval::from("15min").fmt_kline(), "15");
+		assert_eq!(BybitInterval::from("D").fmt_interval_time().unwrap(), "1d");
+		assert!(BybitInterval::from("1m").fmt_interval_time().is_err());
+	}
+
+	#[test]
+	fn reauth_forgets_account_type() {
+		let mut bybit = Bybit::default();
+		bybit.account_type.set(crate::AccountType::UnifiedTrading).unwrap();
+		ExchangeImpl::auth(&mut bybit, "other".to_owned(), SecretString::from("key".to_owned()));
+		assert_eq!(bybit.account_type.get(), None);
+	}
+}
@@ -0,0 +1,134 @@
+//! Master-key management of subaccounts. All calls here must be made with the master account's key.
+use adapters::Client;
+use serde::Deserialize;
+use serde_json::json;
+use serde_with::{DisplayFromStr, serde_as};
+use uuid::Uuid;
+use v_exchanges_adapters::bybit::BybitOption;
+use v_utils::trades::Asset;
+
+use super::{account::AccountType, signed_options};
+use crate::{ExchangeError, ExchangeResult};
+
+#[derive(Clone, Debug)]
+pub struct Subaccount {
+	/// Subaccounts are addressed by uid everywhere on Bybit.
+	pub uid: u64,
+	pub username: String,
+	/// `1`: normal, `6`: custodial. See Bybit docs for the rest.
+	pub member_type: u8,
+	/// `1`: normal, `2`: login banned, `4`: frozen.
+	pub status: u8,
+	pub remark: String,
+}
+
+/// One side of a [subaccount_transfer](super::Bybit::subaccount_transfer).
+#[derive(Clone, Copy, Debug)]
+pub struct SubaccountWallet {
+	/// Uid of either a subaccount or the master account itself.
+	pub uid: u64,
+	pub account: AccountType,
+}
+
+pub(super) async fn subaccounts(client: &Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Subaccount>> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let r: SubMembersResponse = client.get_no_query("/v5/user/query-sub-members", signed_options(recv_window)).await?;
+	Ok(r.result.sub_members.into_iter().map(Into::into).collect())
+}
+
+/// Returns the transfer id, generated client-side as Bybit requires.
+pub(super) async fn subaccount_transfer(client: &Client, from: SubaccountWallet, to: SubaccountWallet, asset: Asset, amount: f64, recv_window: Option<std::time::Duration>) -> ExchangeResult<Uuid> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let transfer_id = Uuid::now_v7();
+	let body = json!({
+		"transferId": transfer_id.hyphenated().to_string(),
+		"coin": asset.to_string(),
+		"amount": amount.to_string(),
+		"fromMemberId": from.uid,
+		"toMemberId": to.uid,
+		"fromAccountType": account_type(from.account),
+		"toAccountType": account_type(to.account),
+	});
+	let r: TransferResponse = client.post("/v5/asset/transfer/universal-transfer", body, signed_options(recv_window)).await?;
+	// echoed back; parse it rather than trusting our own, in case Bybit ever normalizes it
+	Uuid::parse_str(&r.result.transfer_id).map_err(|e| ExchangeError::Other(eyre::eyre!("Bybit returned a malformed transferId `{}`: {e}", r.result.transfer_id)))
+}
+
+/// Transfer endpoints name the funding wallet `FUND`, not `FUNDING`.
+fn account_type(account: AccountType) -> &'static str {
+	match account {
+		AccountType::Spot => "SPOT",
+		AccountType::Contract => "CONTRACT",
+		AccountType::Unified => "UNIFIED",
+		AccountType::Funding => "FUND",
+		AccountType::Option => "OPTION",
+	}
+}
+
+// responses {{{
+#[derive(Debug, Deserialize)]
+struct SubMembersResponse {
+	result: SubMembersResult,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubMembersResult {
+	sub_members: Vec<SubMember>,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubMember {
+	#[serde_as(as = "DisplayFromStr")]
+	uid: u64,
+	username: String,
+	member_type: u8,
+	status: u8,
+	#[serde(default)]
+	remark: String,
+}
+impl From<SubMember> for Subaccount {
+	fn from(m: SubMember) -> Self {
+		Self {
+			uid: m.uid,
+			username: m.username,
+			member_type: m.member_type,
+			status: m.status,
+			remark: m.remark,
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct TransferResponse {
+	result: TransferResult,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferResult {
+	transfer_id: String,
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sub_members() {
+		let json = r#"{"retCode":0,"retMsg":"","result":{"subMembers":[{"uid":"53888000","username":"xxx001","memberType":1,"status":1,"accountMode":5,"remark":""},{"uid":"53888001","username":"xxx002","memberType":1,"status":1,"accountMode":6,"remark":"bot"}]},"retExtInfo":{},"time":1676430318405}"#;
+		let r: SubMembersResponse = serde_json::from_str(json).unwrap();
+		let subaccounts: Vec<Subaccount> = r.result.sub_members.into_iter().map(Into::into).collect();
+		assert_eq!(subaccounts[0].uid, 53888000);
+		assert_eq!(subaccounts[1].remark, "bot");
+	}
+
+	#[test]
+	fn transfer() {
+		let json = r#"{"retCode":0,"retMsg":"success","result":{"transferId":"be7a2462-1138-4e27-80b1-62653f24925e","status":"SUCCESS"},"retExtInfo":{},"time":1670988271677}"#;
+		let r: TransferResponse = serde_json::from_str(json).unwrap();
+		assert_eq!(Uuid::parse_str(&r.result.transfer_id).unwrap().to_string(), "be7a2462-1138-4e27-80b1-62653f24925e");
+	}
+}
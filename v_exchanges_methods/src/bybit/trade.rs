@@ -0,0 +1,407 @@
+use std::str::FromStr as _;
+
+use adapters::{
+	Client,
+	generics::http::{ApiError, HandleError, RequestError},
+};
+use arrayvec::ArrayString;
+use jiff::Timestamp;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use serde_with::{DisplayFromStr, serde_as};
+use v_exchanges_adapters::bybit::{BybitError, BybitErrorCode, BybitOption};
+use v_utils::trades::{Asset, Pair, Side};
+
+use crate::{
+	AmendError, ExchangeError, ExchangeName, ExchangeResult, Instrument, MethodError, Order, OrderDetails, OrderId, OrderPlaced, OrderState, OrderStatus, SelfTradePreventionMode, Symbol,
+	TimeInForce, TradeRecord,
+	bybit::signed_options,
+};
+
+// amend {{{
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmendResponse {
+	result: AmendResult,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AmendResult {
+	order_id: String,
+	#[allow(unused)]
+	order_link_id: String,
+}
+
+/// `POST /v5/order/amend`. Only the fields being changed are sent.
+pub(super) async fn amend_order(
+	client: &Client,
+	order_id: OrderId,
+	symbol: Symbol,
+	new_qty: Option<f64>,
+	new_price: Option<f64>,
+	recv_window: Option<std::time::Duration>,
+) -> ExchangeResult<OrderPlaced> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let mut body = Map::new();
+	body.insert("category".to_owned(), json!(category(symbol.instrument, "amend_order")?));
+	body.insert("symbol".to_owned(), json!(symbol.pair.fmt_bybit()));
+	let (id_key, id_value) = order_id_param(&order_id);
+	body.insert(id_key.to_owned(), json!(id_value));
+	if let Some(qty) = new_qty {
+		body.insert("qty".to_owned(), json!(qty.to_string()));
+	}
+	if let Some(price) = new_price {
+		body.insert("price".to_owned(), json!(price.to_string()));
+	}
+
+	let options = signed_options(recv_window);
+	let response: AmendResponse = client.post("/v5/order/amend", Value::Object(body), options).await.map_err(|e| amend_error(e, &order_id))?;
+
+	// Bybit only acks the amend; an accepted amend means the order is still live on the book.
+	Ok(OrderPlaced::new(
+		OrderId {
+			exchange_id: Some(exchange_id(&response.result.order_id)?),
+			..order_id
+		},
+		OrderStatus::New,
+	))
+}
+
+fn amend_error(e: RequestError, order_id: &OrderId) -> ExchangeError {
+	if let RequestError::HandleResponse(HandleError::Api(ApiError::Other(report))) = &e
+		&& let Some(bybit_error) = report.downcast_ref::<BybitError>()
+	{
+		match bybit_error.code {
+			BybitErrorCode::OrderNotExist(_) => return AmendError::new_order_not_found(order_id.clone()).into(),
+			BybitErrorCode::OrderCompletedOrCancelled(_) => return AmendError::new_order_already_filled(order_id.clone()).into(),
+			_ => {}
+		}
+	}
+	e.into()
+}
+//,}}}
+
+// place {{{
+#[derive(Debug, Deserialize)]
+struct CreateResponse {
+	result: AmendResult,
+}
+
+/// `POST /v5/order/create`. Our client id always goes out as `orderLinkId`, so a lost response can be reconciled through [order_status].
+pub(super) async fn place_order(client: &Client, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let mut body = Map::new();
+	body.insert("category".to_owned(), json!(category(symbol.instrument, "place_order")?));
+	body.insert("symbol".to_owned(), json!(symbol.pair.fmt_bybit()));
+	let side = match order.side() {
+		Side::Buy => "Buy",
+		Side::Sell => "Sell",
+	};
+	body.insert("side".to_owned(), json!(side));
+	body.insert("qty".to_owned(), json!(order.qty_param()));
+	body.insert("orderLinkId".to_owned(), json!(order.order_id().client_order_id().as_str()));
+	let (reduce_only, stp) = match &order {
+		Order::Limit(o) => {
+			body.insert("orderType".to_owned(), json!("Limit"));
+			body.insert("price".to_owned(), json!(o.price.to_string()));
+			let time_in_force = match (o.post_only, o.time_in_force) {
+				(true, _) => "PostOnly".to_owned(),
+				(false, tif @ (TimeInForce::Gtc | TimeInForce::Ioc | TimeInForce::Fok)) => tif.to_string(),
+				(false, tif) => return Err(ExchangeError::Other(eyre::eyre!("Bybit doesn't support {tif} time in force"))),
+			};
+			body.insert("timeInForce".to_owned(), json!(time_in_force));
+			(o.reduce_only, o.stp)
+		}
+		Order::Market(o) => {
+			body.insert("orderType".to_owned(), json!("Market"));
+			(o.reduce_only, o.stp)
+		}
+	};
+	if reduce_only {
+		body.insert("reduceOnly".to_owned(), json!(true));
+	}
+	if let Some(stp) = stp {
+		let stp = match stp {
+			SelfTradePreventionMode::CancelMaker => "CancelMaker",
+			SelfTradePreventionMode::CancelTaker => "CancelTaker",
+			SelfTradePreventionMode::CancelBoth => "CancelBoth",
+		};
+		body.insert("smpType".to_owned(), json!(stp));
+	}
+
+	let options = signed_options(recv_window);
+	let response: CreateResponse = client.post("/v5/order/create", Value::Object(body), options).await?;
+
+	// create only acks; the order is on the book (or about to be matched) at this point
+	Ok(OrderPlaced::new(
+		OrderId {
+			exchange_id: Some(exchange_id(&response.result.order_id)?),
+			..order.order_id().clone()
+		},
+		OrderStatus::New,
+	))
+}
+//,}}}
+
+// status {{{
+#[derive(Debug, Deserialize)]
+struct RealtimeResponse {
+	result: RealtimeResult,
+}
+#[derive(Debug, Deserialize)]
+struct RealtimeResult {
+	list: Vec<RealtimeOrder>,
+}
+/// Same shape for `/v5/order/realtime` and `/v5/order/history`.
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RealtimeOrder {
+	order_id: String,
+	order_link_id: String,
+	symbol: String,
+	order_status: String,
+	side: String,
+	order_type: String,
+	price: String,
+	avg_price: String,
+	#[serde_as(as = "DisplayFromStr")]
+	qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	cum_exec_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	leaves_qty: f64,
+	reduce_only: bool,
+	#[serde_as(as = "DisplayFromStr")]
+	created_time: i64,
+	#[serde_as(as = "DisplayFromStr")]
+	updated_time: i64,
+}
+impl RealtimeOrder {
+	fn status(&self) -> ExchangeResult<OrderStatus> {
+		Ok(match self.order_status.as_str() {
+			"New" | "Untriggered" | "Triggered" => OrderStatus::New,
+			"PartiallyFilled" => OrderStatus::PartiallyFilled,
+			"Filled" => OrderStatus::Filled,
+			"Cancelled" | "PartiallyFilledCanceled" | "Deactivated" => OrderStatus::Canceled,
+			"Rejected" => OrderStatus::Rejected,
+			other => return Err(ExchangeError::Other(eyre::eyre!("Unexpected Bybit order status: {other}"))),
+		})
+	}
+
+	/// `order_id` is what the order was requested by, if it was; otherwise it's reconstructed from the response, with our client id carried over as is.
+	fn into_details(self, order_id: Option<OrderId>, symbol: Symbol) -> ExchangeResult<OrderDetails> {
+		let status = self.status()?;
+		let side = match self.side.as_str() {
+			"Buy" => Side::Buy,
+			"Sell" => Side::Sell,
+			other => return Err(ExchangeError::Other(eyre::eyre!("Unexpected Bybit order side: {other}"))),
+		};
+		let order_id = match order_id {
+			Some(order_id) => order_id,
+			None => OrderId {
+				client_id: ArrayString::from(&self.order_link_id).ok().filter(|id| !id.is_empty()),
+				..Default::default()
+			},
+		};
+		// empty or zero until there's something to report
+		let non_zero = |s: &str| s.parse::<f64>().ok().filter(|v| *v != 0.);
+		let timestamp = |ms: i64| Timestamp::from_millisecond(ms).map_err(|e| ExchangeError::Other(eyre::eyre!("Invalid Bybit order time {ms}: {e}")));
+		Ok(OrderDetails {
+			order_id: OrderId {
+				exchange_id: Some(exchange_id(&self.order_id)?),
+				..order_id
+			},
+			symbol,
+			side,
+			price: non_zero(&self.price),
+			avg_price: non_zero(&self.avg_price),
+			order_type: self.order_type,
+			qty: self.qty,
+			filled_qty: self.cum_exec_qty,
+			remaining_qty: self.leaves_qty,
+			status,
+			reduce_only: self.reduce_only,
+			created_at: timestamp(self.created_time)?,
+			updated_at: timestamp(self.updated_time)?,
+			trades: Vec::new(),
+		})
+	}
+}
+
+/// `GET /v5/order/realtime`. Covers open orders plus recently closed ones; older history would need `/v5/order/history`.
+pub(super) async fn order_status(client: &Client, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let options = signed_options(recv_window);
+	let params = [("category", category(symbol.instrument, "order_status")?.to_owned()), ("symbol", symbol.pair.fmt_bybit()), order_id_param(&order_id)];
+	let response: RealtimeResponse = client.get("/v5/order/realtime", &params, options).await?;
+
+	let Some(order) = response.result.list.into_iter().next() else {
+		return Ok(OrderState::NotFound);
+	};
+	let status = order.status()?;
+	Ok(OrderState::Found(OrderPlaced::new(
+		OrderId {
+			exchange_id: Some(exchange_id(&order.order_id)?),
+			..order_id
+		},
+		status,
+	)))
+}
+
+/// `GET /v5/order/realtime`, plus `GET /v5/execution/list` for the fills if there are any.
+pub(super) async fn get_order(client: &Client, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let category = category(symbol.instrument, "get_order")?;
+	let params = [("category", category.to_owned()), ("symbol", symbol.pair.fmt_bybit()), order_id_param(&order_id)];
+	let response: RealtimeResponse = client.get("/v5/order/realtime", &params, signed_options(recv_window)).await?;
+	let Some(order) = response.result.list.into_iter().next() else {
+		return Err(ExchangeError::Other(eyre::eyre!("Bybit has no order {}", order_id.client_order_id())));
+	};
+	let mut details = order.into_details(Some(order_id), symbol)?;
+	if details.filled_qty > 0. {
+		let params = [("category", category.to_owned()), ("symbol", symbol.pair.fmt_bybit()), order_id_param(&details.order_id)];
+		let response: ExecutionResponse = client.get("/v5/execution/list", &params, signed_options(recv_window)).await?;
+		details.trades = response.result.list.into_iter().map(|e| e.into_trade(symbol.pair)).collect::<ExchangeResult<_>>()?;
+	}
+	Ok(details)
+}
+
+/// `GET /v5/order/realtime` for open orders, `GET /v5/order/history` for closed ones. Without a pair, linear is narrowed down through the USDT settle coin, as Bybit requires some filter there.
+pub(super) async fn get_orders(
+	client: &Client,
+	instrument: Instrument,
+	pair: Option<Pair>,
+	status: Option<OrderStatus>,
+	recv_window: Option<std::time::Duration>,
+) -> ExchangeResult<Vec<OrderDetails>> {
+	assert!(client.is_authenticated::<BybitOption>());
+
+	let mut params = vec![("category", category(instrument, "get_orders")?.to_owned())];
+	match pair {
+		Some(pair) => params.push(("symbol", pair.fmt_bybit())),
+		None if instrument == Instrument::Perp => params.push(("settleCoin", "USDT".to_owned())),
+		None => {}
+	}
+	let endpoint = match status {
+		None | Some(OrderStatus::New | OrderStatus::PartiallyFilled) => {
+			params.push(("openOnly", "0".to_owned()));
+			"/v5/order/realtime"
+		}
+		Some(_) => "/v5/order/history",
+	};
+	let response: RealtimeResponse = client.get(endpoint, &params, signed_options(recv_window)).await?;
+	response
+		.result
+		.list
+		.into_iter()
+		.map(|order| {
+			let pair = Pair::from_str(&order.symbol).map_err(|e| ExchangeError::Other(eyre::eyre!("Unexpected Bybit symbol {}: {e}", order.symbol)))?;
+			order.into_details(None, Symbol::new(pair, instrument))
+		})
+		.filter(|d| !matches!((d, status), (Ok(d), Some(status)) if d.status != status))
+		.collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecutionResponse {
+	result: ExecutionResult,
+}
+#[derive(Debug, Deserialize)]
+struct ExecutionResult {
+	list: Vec<Execution>,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Execution {
+	exec_id: String,
+	#[serde_as(as = "DisplayFromStr")]
+	exec_price: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	exec_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	exec_fee: f64,
+	/// Only reported for spot; derivatives pay fees in the settle coin.
+	#[serde(default)]
+	fee_currency: String,
+	is_maker: bool,
+	#[serde_as(as = "DisplayFromStr")]
+	exec_time: i64,
+}
+impl Execution {
+	fn into_trade(self, pair: Pair) -> ExchangeResult<TradeRecord> {
+		Ok(TradeRecord {
+			trade_id: self.exec_id,
+			price: self.exec_price,
+			qty: self.exec_qty,
+			fee: self.exec_fee,
+			fee_asset: match self.fee_currency.is_empty() {
+				true => Asset::from(pair.quote().as_ref()),
+				false => (&*self.fee_currency).into(),
+			},
+			is_maker: self.is_maker,
+			time: Timestamp::from_millisecond(self.exec_time).map_err(|e| ExchangeError::Other(eyre::eyre!("Invalid Bybit execution time {}: {e}", self.exec_time)))?,
+		})
+	}
+}
+//,}}}
+
+fn category(instrument: Instrument, method_name: &'static str) -> ExchangeResult<&'static str> {
+	match instrument {
+		Instrument::Perp => Ok("linear"),
+		Instrument::PerpInverse => Ok("inverse"),
+		Instrument::Spot => Ok("spot"),
+		_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Bybit, instrument, method_name))),
+	}
+}
+
+/// Bybit's order ids are hyphenated uuids, which [OrderId::exchange_id] fits exactly.
+fn exchange_id(raw: &str) -> ExchangeResult<ArrayString<36>> {
+	ArrayString::from(raw).map_err(|_| ExchangeError::Other(eyre::eyre!("Bybit order id `{raw}` is longer than 36 chars")))
+}
+
+/// Exchange-assigned id is preferred when known; our own client id is always there as the fallback.
+fn order_id_param(order_id: &OrderId) -> (&'static str, String) {
+	match &order_id.exchange_id {
+		Some(exchange_id) => ("orderId", exchange_id.to_string()),
+		None => ("orderLinkId", order_id.client_order_id().to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn order_details_from_realtime() {
+		let json = r#"{"result":{"list":[{"orderId":"fd4300ae-7847-404e-b947-b46980a4d140","orderLinkId":"strat-0a1b2c","symbol":"ETHUSDT","price":"1600.00","qty":"0.10","side":"Buy","isLeverage":"","positionIdx":1,"orderStatus":"PartiallyFilled","cancelType":"UNKNOWN","rejectReason":"EC_NoError","avgPrice":"1599.5","leavesQty":"0.04","leavesValue":"64","cumExecQty":"0.06","cumExecValue":"95.97","cumExecFee":"0.0576","timeInForce":"GTC","orderType":"Limit","stopOrderType":"UNKNOWN","orderIv":"","triggerPrice":"0.00","takeProfit":"","stopLoss":"","tpTriggerBy":"","slTriggerBy":"","triggerDirection":0,"triggerBy":"UNKNOWN","lastPriceOnCreated":"","reduceOnly":false,"closeOnTrigger":false,"smpType":"None","smpGroup":0,"smpOrderId":"","tpslMode":"","tpLimitPrice":"","slLimitPrice":"","placeType":"","createdTime":"1684738540559","updatedTime":"1684738540561"}]}}"#;
+		let response: RealtimeResponse = serde_json::from_str(json).unwrap();
+		let order = response.result.list.into_iter().next().unwrap();
+		let symbol = Symbol::new(Pair::new("ETH", "USDT"), Instrument::Perp);
+		let details = order.into_details(None, symbol).unwrap();
+
+		assert_eq!(details.order_id.exchange_id.unwrap().as_str(), "fd4300ae-7847-404e-b947-b46980a4d140");
+		assert_eq!(details.order_id.client_order_id().as_str(), "strat-0a1b2c");
+		assert_eq!(details.status, OrderStatus::PartiallyFilled);
+		assert_eq!(details.side, Side::Buy);
+		assert_eq!(details.price, Some(1600.));
+		assert_eq!(details.avg_price, Some(1599.5));
+		assert_eq!((details.qty, details.filled_qty, details.remaining_qty), (0.1, 0.06, 0.04));
+		assert_eq!(details.updated_at.as_millisecond(), 1684738540561);
+	}
+
+	#[test]
+	fn execution_fee_asset_defaults_to_quote() {
+		let json = r#"{"result":{"list":[{"symbol":"ETHUSDT","orderId":"fd4300ae","orderLinkId":"","side":"Buy","execId":"e0cbe81d","execPrice":"1599.5","execQty":"0.06","execFee":"0.0576","isMaker":true,"execTime":"1684738540560"}]}}"#;
+		let response: ExecutionResponse = serde_json::from_str(json).unwrap();
+		let trade = response.result.list.into_iter().next().unwrap().into_trade(Pair::new("ETH", "USDT")).unwrap();
+		assert_eq!(trade.fee_asset, "USDT");
+		assert!(trade.is_maker);
+		assert_eq!(trade.qty, 0.06);
+	}
+}
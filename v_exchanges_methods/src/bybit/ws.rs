@@ -1,14 +1,138 @@
 use std::collections::BTreeMap;
 
 use adapters::{
-	Client,
-	bybit::{BybitOption, BybitWsHandler, BybitWsUrlBase},
-	generics::ws::{WsConnection, WsError},
+	Client, GetOptions,
+	bybit::{BybitOption, BybitOptions, BybitWsHandler, BybitWsUrlBase},
+	generics::ws::{LatencyStats, SubscriptionSnapshot, WsConnection, WsError},
 };
-use jiff::Timestamp;
-use v_utils::trades::Pair;
+use arrayvec::ArrayString;
+use jiff::{SignedDuration, Timestamp};
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, serde_as};
+use tokio::time::Instant;
+use v_utils::trades::{Asset, Pair, Side};
 
-use crate::{BookShape, BookUpdate, ExchangeStream, Instrument, PrecisionPriceQty, core::Sequence};
+use crate::{
+	AccountEvent, BalanceUpdate, BatchTrades, BookShape, BookUpdate, ExchangeError, ExchangeName, ExchangeResult, ExchangeStream, FeeReport, Instrument, Liquidation, MethodError, OrderId, OrderStatus,
+	OrderUpdate, PositionUpdate, PrecisionPriceQty, Symbol,
+	core::{InnerTrade, Sequence},
+	dedup::RecentIds,
+};
+
+/// Pair of a topic, like `publicTrade.BTCUSDT` or `orderbook.1000.BTCUSDT`.
+pub(super) fn topic_pair(topic: &str) -> Option<Pair> {
+	topic.rsplit('.').next()?.parse().ok()
+}
+
+// trades {{{
+/// Public trades of linear perps or spot, over `publicTrade.<SYMBOL>`. All pairs go into one subscription, which is resent whole on every reconnect.
+#[derive(Debug)]
+pub struct TradesConnection {
+	connection: WsConnection<BybitWsHandler>,
+	pair_precisions: BTreeMap<Pair, PrecisionPriceQty>,
+	/// Keyed on the id as sent, as linear's are uuids.
+	dedup: Option<RecentIds<(Pair, String)>>,
+}
+impl TradesConnection {
+	pub fn try_new(client: &Client, instrument: Instrument, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> ExchangeResult<Self> {
+		let url_suffix = match instrument {
+			Instrument::Perp => "/v5/public/linear",
+			Instrument::Spot => "/v5/public/spot",
+			_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Bybit, instrument, "ws_trades"))),
+		};
+		Ok(Self::connect(client, url_suffix, BybitWsUrlBase::Bybit, pair_precisions)?)
+	}
+
+	fn connect(client: &Client, url: &str, ws_url: BybitWsUrlBase, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> Result<Self, WsError> {
+		let topics = pair_precisions.keys().map(|p| format!("publicTrade.{}", p.fmt_bybit())).collect();
+		let connection = client.ws_connection(url, vec![BybitOption::WsUrl(ws_url), BybitOption::WsTopics(topics)])?;
+		let dedup = GetOptions::<BybitOptions>::default_options(client).ws_config.dedup_window.map(RecentIds::new);
+		Ok(Self { connection, pair_precisions, dedup })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for TradesConnection {
+	type Item = BatchTrades;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.dedup.as_ref().map(RecentIds::skipped)
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let batch = self.connection.next().await?;
+		let received = Instant::now();
+		// same grouping as Binance's: one `BatchTrades` per pair, arrival order within it
+		let mut by_pair: BTreeMap<Pair, (PrecisionPriceQty, Vec<InnerTrade>)> = BTreeMap::new();
+		for content_event in batch {
+			for (pair, prec, raw_id, trade) in parse_public_trades(content_event.data, &self.pair_precisions)? {
+				if let Some(dedup) = &mut self.dedup
+					&& !dedup.admit((pair, raw_id.clone()), received)
+				{
+					tracing::debug!(target: "v_exchanges::bybit", %pair, id = %raw_id, "Skipping a trade already delivered");
+					continue;
+				}
+				by_pair.entry(pair).or_insert((prec, Vec::new())).1.push(trade);
+			}
+		}
+		let now = Timestamp::now();
+		Ok(by_pair.into_iter().map(|(_, (prec, trades))| BatchTrades::new(prec, trades, now, now)).collect())
+	}
+}
+
+/// One message carries every trade of its symbol since the last push, oldest first. Each comes with its id as sent.
+fn parse_public_trades(data: serde_json::Value, pair_precisions: &BTreeMap<Pair, PrecisionPriceQty>) -> Result<Vec<(Pair, PrecisionPriceQty, String, InnerTrade)>, WsError> {
+	let trades: Vec<BybitPublicTrade> = serde_json::from_value(data.clone()).map_err(WsError::Parse)?;
+	trades
+		.into_iter()
+		.map(|t| {
+			let pair: Pair = t.symbol.as_str().try_into().map_err(|_| WsError::UnexpectedEvent(data.clone()))?;
+			let prec = *pair_precisions.get(&pair).ok_or_else(|| WsError::UnexpectedEvent(data.clone()))?;
+			let trade = InnerTrade {
+				id: t.id.parse().ok(),
+				time: Timestamp::from_millisecond(t.time).map_err(|_| WsError::UnexpectedEvent(data.clone()))?,
+				price: prec.parse_price(&t.price),
+				qty: prec.parse_qty(&t.size),
+				side: match t.side.as_str() {
+					"Buy" => Some(Side::Buy),
+					"Sell" => Some(Side::Sell),
+					_ => None,
+				},
+			};
+			Ok((pair, prec, t.id, trade))
+		})
+		.collect()
+}
+
+/// Docs: https://bybit-exchange.github.io/docs/v5/websocket/public/trade
+#[derive(Clone, Debug, Deserialize)]
+struct BybitPublicTrade {
+	#[serde(rename = "T")]
+	time: i64,
+	#[serde(rename = "s")]
+	symbol: String,
+	/// Of the taker.
+	#[serde(rename = "S")]
+	side: String,
+	#[serde(rename = "v")]
+	size: String,
+	#[serde(rename = "p")]
+	price: String,
+	#[serde(rename = "i")]
+	id: String,
+}
+//,}}}
 
 // book {{{
 #[derive(Debug)]
@@ -20,13 +144,13 @@ pub struct BookConnection {
 	last_seq: BTreeMap<Pair, BybitSeq>,
 }
 impl BookConnection {
-	pub fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> Result<Self, WsError> {
+	pub fn try_new(client: &Client, pairs: &[Pair], instrument: Instrument, pair_precisions: BTreeMap<Pair, PrecisionPriceQty>) -> ExchangeResult<Self> {
 		let vec_topic_str = pairs.iter().map(|p| format!("orderbook.1000.{}", p.fmt_bybit())).collect::<Vec<_>>();
 
 		let url_suffix = match instrument {
 			Instrument::Perp => "/v5/public/linear",
 			Instrument::Spot => "/v5/public/spot",
-			_ => unimplemented!(),
+			_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Bybit, instrument, "ws_book"))),
 		};
 		let connection = client.ws_connection(url_suffix, vec![BybitOption::WsUrl(BybitWsUrlBase::Bybit), BybitOption::WsTopics(vec_topic_str)])?;
 
@@ -41,6 +165,18 @@ impl BookConnection {
 impl ExchangeStream for BookConnection {
 	type Item = BookUpdate;
 
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
 	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
 		let batch = self.connection.next().await?;
 		let mut out = Vec::with_capacity(batch.len());
@@ -66,7 +202,7 @@ impl ExchangeStream for BookConnection {
 			let seq = BybitSeq { u: parsed.u, is_snapshot };
 			let gapped = self.last_seq.get(&pair).map(|prev| seq.has_gap_from_prev(prev)).unwrap_or(false);
 			if gapped {
-				tracing::warn!(pair = %pair, next_u = seq.u, "Bybit orderbook gap detected on delta chain");
+				tracing::warn!(target: "v_exchanges::bybit", pair = %pair, next_u = seq.u, "Bybit orderbook gap detected on delta chain");
 			}
 			self.last_seq.insert(pair, seq);
 
@@ -117,9 +253,433 @@ impl Sequence for BybitSeq {
 }
 //,}}}
 
+// liquidations {{{
+#[derive(Debug)]
+pub struct BybitLiquidationStream {
+	connection: WsConnection<BybitWsHandler>,
+}
+impl BybitLiquidationStream {
+	pub fn try_new(client: &Client, pairs: &[Pair]) -> Result<Self, WsError> {
+		let topics = pairs.iter().map(|p| format!("liquidation.{}", p.fmt_bybit())).collect::<Vec<_>>();
+		let connection = client.ws_connection("/v5/public/linear", vec![BybitOption::WsUrl(BybitWsUrlBase::Bybit), BybitOption::WsTopics(topics)])?;
+		Ok(Self { connection })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for BybitLiquidationStream {
+	type Item = Liquidation;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let batch = self.connection.next().await?;
+		batch.into_iter().map(|content_event| parse_liquidation(content_event.data)).collect()
+	}
+}
+
+/// Docs: https://bybit-exchange.github.io/docs/v5/websocket/public/liquidation
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitLiquidationData {
+	#[serde(alias = "updateTime")]
+	updated_time: i64,
+	symbol: String,
+	/// Side of the liquidated position, not of the order closing it.
+	side: String,
+	/// In base asset.
+	#[serde_as(as = "DisplayFromStr")]
+	size: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	price: f64,
+}
+
+fn parse_liquidation(data: serde_json::Value) -> Result<Liquidation, WsError> {
+	let parsed: BybitLiquidationData = serde_json::from_value(data.clone()).map_err(WsError::Parse)?;
+	let side = match parsed.side.as_str() {
+		"Buy" => Side::Buy,
+		"Sell" => Side::Sell,
+		_ => return Err(WsError::UnexpectedEvent(data)),
+	};
+	let pair: Pair = parsed.symbol.as_str().try_into().map_err(|_| WsError::UnexpectedEvent(data.clone()))?;
+	let time = Timestamp::from_millisecond(parsed.updated_time).map_err(|_| WsError::UnexpectedEvent(data))?;
+	Ok(Liquidation {
+		pair,
+		side,
+		qty: parsed.size,
+		price: parsed.price,
+		time,
+	})
+}
+//,}}}
+
+// user data {{{
+/// Private topics of [BybitUserDataStream], across all categories.
+const USER_DATA_TOPICS: [&str; 3] = ["order", "position", "wallet"];
+
+/// Order, position and wallet updates of the authenticated account.
+#[derive(Debug)]
+pub struct BybitUserDataStream {
+	connection: WsConnection<BybitWsHandler>,
+}
+impl BybitUserDataStream {
+	pub fn try_new(client: &Client) -> Result<Self, WsError> {
+		let topics = USER_DATA_TOPICS.iter().map(|t| t.to_string()).collect();
+		let connection = client.ws_connection(
+			"/v5/private",
+			vec![BybitOption::WsUrl(BybitWsUrlBase::Bybit), BybitOption::WsAuth(true), BybitOption::WsTopics(topics)],
+		)?;
+		Ok(Self { connection })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for BybitUserDataStream {
+	type Item = AccountEvent;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let batch = self.connection.next().await?;
+		let mut out = Vec::with_capacity(batch.len());
+		for content_event in batch {
+			// category-specific subscriptions come as eg `order.linear`
+			let events = match content_event.topic.split('.').next() {
+				Some("order") => parse_account_events(content_event.data)?,
+				Some("position") => parse_position_events(content_event.data)?,
+				Some("wallet") => parse_wallet_events(content_event.data, content_event.time)?,
+				_ => return Err(WsError::UnexpectedEvent(content_event.data)),
+			};
+			out.extend(events);
+		}
+		Ok(out)
+	}
+}
+
+/// Maps the `data` of a private `position` topic message onto [AccountEvent]s, one per position. Options, and entries that don't parse, are skipped without losing the rest of the batch.
+fn parse_position_events(data: serde_json::Value) -> Result<Vec<AccountEvent>, WsError> {
+	let positions: Vec<BybitPositionData> = serde_json::from_value(data).map_err(WsError::Parse)?;
+	Ok(positions
+		.iter()
+		.filter_map(|p| {
+			if p.category == "option" {
+				tracing::debug!(target: "v_exchanges::bybit", symbol = %p.symbol, "Skipping an option position update");
+				return None;
+			}
+			let update = p.to_update();
+			if update.is_none() {
+				tracing::warn!(target: "v_exchanges::bybit", ?p, "Skipping a position update that doesn't parse");
+			}
+			update.map(AccountEvent::PositionUpdate)
+		})
+		.collect())
+}
+
+/// Maps the `data` of a private `wallet` topic message onto [AccountEvent]s, one per coin. The message carries its time outside of `data`.
+fn parse_wallet_events(data: serde_json::Value, time: Timestamp) -> Result<Vec<AccountEvent>, WsError> {
+	let wallets: Vec<BybitWalletData> = serde_json::from_value(data).map_err(WsError::Parse)?;
+	Ok(wallets
+		.into_iter()
+		.flat_map(|w| w.coin)
+		.map(|c| {
+			AccountEvent::BalanceUpdate(BalanceUpdate {
+				asset: Asset::from(c.coin.as_str()),
+				wallet_balance: c.wallet_balance,
+				available: c.available_to_withdraw.parse().ok(),
+				time,
+			})
+		})
+		.collect())
+}
+
+/// Docs: https://bybit-exchange.github.io/docs/v5/websocket/private/position
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitPositionData {
+	category: String,
+	symbol: String,
+	/// Empty while flat.
+	side: String,
+	#[serde_as(as = "DisplayFromStr")]
+	size: f64,
+	/// `"0"` or empty while flat, as are the other price fields.
+	entry_price: String,
+	mark_price: String,
+	unrealised_pnl: String,
+	#[serde_as(as = "DisplayFromStr")]
+	updated_time: i64,
+}
+impl BybitPositionData {
+	fn to_update(&self) -> Option<PositionUpdate> {
+		let pair: Pair = self.symbol.as_str().try_into().ok()?;
+		let instrument = match self.category.as_str() {
+			"linear" => Instrument::Perp,
+			"inverse" => Instrument::PerpInverse,
+			_ => return None,
+		};
+		let size = match self.side.as_str() {
+			"Buy" => self.size,
+			"Sell" => -self.size,
+			"" | "None" => 0.,
+			_ => return None,
+		};
+		Some(PositionUpdate {
+			symbol: Symbol::new(pair, instrument),
+			size,
+			entry_price: self.entry_price.parse().ok().filter(|p| *p != 0.),
+			mark_price: self.mark_price.parse().ok().filter(|p| *p != 0.),
+			unrealized_pnl: self.unrealised_pnl.parse().unwrap_or_default(),
+			time: Timestamp::from_millisecond(self.updated_time).ok()?,
+		})
+	}
+}
+
+/// Docs: https://bybit-exchange.github.io/docs/v5/websocket/private/wallet
+#[derive(Clone, Debug, Deserialize)]
+struct BybitWalletData {
+	coin: Vec<BybitWalletCoin>,
+}
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitWalletCoin {
+	coin: String,
+	#[serde_as(as = "DisplayFromStr")]
+	wallet_balance: f64,
+	/// Empty on unified accounts.
+	#[serde(default)]
+	available_to_withdraw: String,
+}
+
+/// Maps the `data` of a private `order` topic message onto [AccountEvent]s, one per order.
+pub fn parse_account_events(data: serde_json::Value) -> Result<Vec<AccountEvent>, WsError> {
+	let orders: Vec<BybitOrderData> = serde_json::from_value(data.clone()).map_err(WsError::Parse)?;
+	orders
+		.into_iter()
+		.map(|o| o.into_update().map(AccountEvent::OrderUpdate).ok_or_else(|| WsError::UnexpectedEvent(data.clone())))
+		.collect()
+}
+
+/// Docs: https://bybit-exchange.github.io/docs/v5/websocket/private/order
+#[serde_as]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BybitOrderData {
+	category: String,
+	symbol: String,
+	order_id: String,
+	/// Empty if none was set on placement.
+	order_link_id: String,
+	side: String,
+	order_status: String,
+	#[serde_as(as = "DisplayFromStr")]
+	qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	cum_exec_qty: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	cum_exec_value: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	cum_exec_fee: f64,
+	/// Spot only, empty until the first fill.
+	#[serde(default)]
+	fee_currency: String,
+	#[serde_as(as = "DisplayFromStr")]
+	updated_time: i64,
+}
+impl BybitOrderData {
+	fn into_update(self) -> Option<OrderUpdate> {
+		let pair: Pair = self.symbol.as_str().try_into().ok()?;
+		// spot charges in whatever was received, so only there is the asset reported (and only once something filled)
+		let (instrument, fee_asset) = match self.category.as_str() {
+			"spot" => (Instrument::Spot, (!self.fee_currency.is_empty()).then(|| Asset::from(self.fee_currency.as_str()))),
+			"linear" => (Instrument::Perp, Some(Asset::from(pair.quote().as_ref()))),
+			"inverse" => (Instrument::PerpInverse, Some(Asset::from(pair.base().as_ref()))),
+			_ => return None,
+		};
+		let status = match self.order_status.as_str() {
+			"New" | "Untriggered" | "Triggered" => OrderStatus::New,
+			"PartiallyFilled" => OrderStatus::PartiallyFilled,
+			"Filled" => OrderStatus::Filled,
+			"Cancelled" | "PartiallyFilledCanceled" | "Deactivated" => OrderStatus::Canceled,
+			"Rejected" => OrderStatus::Rejected,
+			_ => return None,
+		};
+		// orders placed without a link id are only known by the exchange's (36-char uuid) id
+		let client_id = match self.order_link_id.is_empty() {
+			true => &self.order_id,
+			false => &self.order_link_id,
+		};
+		Some(OrderUpdate {
+			order_id: OrderId {
+				client_id: Some(ArrayString::from(client_id).ok()?),
+				exchange_id: Some(ArrayString::from(&self.order_id).ok()?),
+				..Default::default()
+			},
+			symbol: Symbol::new(pair, instrument),
+			side: match self.side.as_str() {
+				"Buy" => Side::Buy,
+				"Sell" => Side::Sell,
+				_ => return None,
+			},
+			status,
+			qty: self.qty,
+			cum_filled_qty: self.cum_exec_qty,
+			cum_filled_quote: self.cum_exec_value,
+			fee: fee_asset.map(|asset| FeeReport::Cumulative { asset, amount: self.cum_exec_fee }),
+			time: Timestamp::from_millisecond(self.updated_time).ok()?,
+		})
+	}
+}
+//,}}}
+
 #[cfg(test)]
 mod tests {
+	use futures_util::SinkExt as _;
+	use tokio_tungstenite::{accept_async, tungstenite::Message};
+
 	use super::*;
+	use crate::TradeId;
+
+	/// Real `publicTrade` pushes: a single-trade snapshot and a three-trade one on linear, and one without the `type` field, as spot sends it.
+	const PUBLIC_TRADE_FRAMES: [&str; 3] = [
+		r#"{"topic":"publicTrade.BTCUSDT","type":"snapshot","ts":1672304486868,"data":[{"T":1672304486865,"s":"BTCUSDT","S":"Buy","v":"0.001","p":"16578.50","L":"PlusTick","i":"20f43950-d8dd-5b31-9112-a178eb6023af","BT":false}]}"#,
+		r#"{"topic":"publicTrade.ETHUSDT","type":"snapshot","ts":1672304487012,"data":[{"T":1672304487001,"s":"ETHUSDT","S":"Sell","v":"0.52","p":"1196.61","L":"MinusTick","i":"8d1c3a4e-71f8-5a53-b2a7-1c1f6c0f2b1a","BT":false},{"T":1672304487001,"s":"ETHUSDT","S":"Sell","v":"1.10","p":"1196.60","L":"MinusTick","i":"b7f0e9d2-04c5-5e4b-9a3c-6b2d1e8f7a90","BT":false},{"T":1672304487009,"s":"ETHUSDT","S":"Buy","v":"0.05","p":"1196.62","L":"PlusTick","i":"c3a5d7e1-9f2b-5c8d-8e4a-0f6b3d2c1e75","BT":false}]}"#,
+		r#"{"topic":"publicTrade.BTCUSDT","ts":1672304488190,"data":[{"i":"2290000000061666327","T":1672304488188,"p":"16579.00","v":"0.014","S":"Sell","s":"BTCUSDT","BT":false}]}"#,
+	];
+
+	#[tokio::test]
+	async fn public_trades_stream_in_order() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		let server = tokio::spawn(async move {
+			let (tcp, _) = listener.accept().await.unwrap();
+			let mut ws = accept_async(tcp).await.unwrap();
+			for frame in PUBLIC_TRADE_FRAMES {
+				ws.send(Message::Text(frame.into())).await.unwrap();
+			}
+			tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+		});
+
+		let prec = |price, qty| PrecisionPriceQty { price, qty };
+		let pair_precisions = BTreeMap::from([(Pair::new("BTC", "USDT"), prec(2, 3)), (Pair::new("ETH", "USDT"), prec(2, 2))]);
+		let mut stream: Box<dyn ExchangeStream<Item = BatchTrades>> = Box::new(TradesConnection::connect(&Client::default(), &url, BybitWsUrlBase::None, pair_precisions).unwrap());
+
+		let mut trades = Vec::new();
+		while trades.len() < 5 {
+			for batch in stream.next().await.unwrap() {
+				trades.extend(batch.trades().map(|t| (t.id, t.side, t.price, t.qty_asset)));
+			}
+		}
+		// grouped per pair within a read, so only each pair's own sequence is fixed
+		let btc: Vec<_> = trades.iter().filter(|t| t.2 > 10_000.).copied().collect();
+		let eth: Vec<_> = trades.iter().filter(|t| t.2 < 10_000.).copied().collect();
+		assert_eq!(btc, [
+			(Some(TradeId::Opaque(0x20f43950_d8dd_5b31_9112_a178eb6023af)), Some(Side::Buy), 16578.5, 0.001),
+			(Some(TradeId::Numeric(2290000000061666327)), Some(Side::Sell), 16579., 0.014),
+		]);
+		assert_eq!(eth, [
+			(Some(TradeId::Opaque(0x8d1c3a4e_71f8_5a53_b2a7_1c1f6c0f2b1a)), Some(Side::Sell), 1196.61, 0.52),
+			(Some(TradeId::Opaque(0xb7f0e9d2_04c5_5e4b_9a3c_6b2d1e8f7a90)), Some(Side::Sell), 1196.6, 1.1),
+			(Some(TradeId::Opaque(0xc3a5d7e1_9f2b_5c8d_8e4a_0f6b3d2c1e75)), Some(Side::Buy), 1196.62, 0.05),
+		]);
+		server.abort();
+	}
+
+	#[tokio::test]
+	async fn wrappers_take_what_exchange_methods_return() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		let server = tokio::spawn(async move {
+			let (tcp, _) = listener.accept().await.unwrap();
+			let mut ws = accept_async(tcp).await.unwrap();
+			for frame in PUBLIC_TRADE_FRAMES {
+				ws.send(Message::Text(frame.into())).await.unwrap();
+			}
+			tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+		});
+
+		let prec = |price, qty| PrecisionPriceQty { price, qty };
+		let pair_precisions = BTreeMap::from([(Pair::new("BTC", "USDT"), prec(2, 3)), (Pair::new("ETH", "USDT"), prec(2, 2))]);
+		let boxed: Box<dyn ExchangeStream<Item = BatchTrades>> = Box::new(TradesConnection::connect(&Client::default(), &url, BybitWsUrlBase::None, pair_precisions).unwrap());
+		let mut stream = crate::with_heartbeat(crate::watermarked(boxed), std::time::Duration::from_secs(5));
+
+		let (mut trades, mut watermarks) = (0, Vec::new());
+		while trades < 5 {
+			for event in stream.next().await.unwrap() {
+				let crate::StreamEvent::Item((batch, watermark)) = event else { panic!("{event:?}") };
+				trades += batch.len();
+				watermarks.push(watermark);
+			}
+		}
+		assert!(watermarks.is_sorted());
+		assert_eq!(watermarks.last().unwrap().as_millisecond(), 1672304488188);
+		assert_eq!(stream.duplicates_skipped(), Some(0));
+		assert!(stream.subscription_snapshot().is_some());
+		server.abort();
+	}
+
+	#[tokio::test]
+	async fn public_trades_replayed_on_reconnect_come_once() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		let server = tokio::spawn(async move {
+			let (tcp, _) = listener.accept().await.unwrap();
+			let mut ws = accept_async(tcp).await.unwrap();
+			for frame in &PUBLIC_TRADE_FRAMES[..2] {
+				ws.send(Message::Text((*frame).into())).await.unwrap();
+			}
+			ws.close(None).await.unwrap();
+
+			// the new connection starts with a replay of the ETH frame
+			let (tcp, _) = listener.accept().await.unwrap();
+			let mut ws = accept_async(tcp).await.unwrap();
+			for frame in &PUBLIC_TRADE_FRAMES[1..] {
+				ws.send(Message::Text((*frame).into())).await.unwrap();
+			}
+			tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+		});
+
+		let prec = |price, qty| PrecisionPriceQty { price, qty };
+		let pair_precisions = BTreeMap::from([(Pair::new("BTC", "USDT"), prec(2, 3)), (Pair::new("ETH", "USDT"), prec(2, 2))]);
+		let mut stream = TradesConnection::connect(&Client::default(), &url, BybitWsUrlBase::None, pair_precisions).unwrap();
+
+		let mut trades = Vec::new();
+		tokio::time::timeout(std::time::Duration::from_secs(10), async {
+			while trades.len() < 5 {
+				for batch in stream.next().await.unwrap() {
+					trades.extend(batch.trades().map(|t| (t.time, t.price, t.qty_asset)));
+				}
+			}
+		})
+		.await
+		.expect("the reconnect replays into the same stream");
+		let mut unique = trades.clone();
+		unique.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		unique.dedup();
+		assert_eq!(unique.len(), trades.len());
+		assert_eq!(stream.duplicates_skipped(), Some(3));
+		server.abort();
+	}
 
 	#[test]
 	fn delta_chain_no_gap() {
@@ -147,4 +707,85 @@ mod tests {
 		let next = BybitSeq { u: 500, is_snapshot: false };
 		assert!(!next.has_gap_from_prev(&prev));
 	}
+
+	#[test]
+	fn liquidation() {
+		let data = serde_json::json!({"updatedTime": 1673251091822_i64, "symbol": "BTCUSDT", "side": "Sell", "size": "1.123", "price": "17400.50"});
+		let l = parse_liquidation(data).unwrap();
+		assert_eq!(l.pair, Pair::new("BTC", "USDT"));
+		assert_eq!(l.side, Side::Sell);
+		assert_eq!(l.qty, 1.123);
+		assert_eq!(l.price, 17400.5);
+
+		let data = serde_json::json!({"updatedTime": 1673251091822_i64, "symbol": "BTCUSDT", "side": "Both", "size": "1", "price": "1"});
+		assert!(matches!(parse_liquidation(data), Err(WsError::UnexpectedEvent(_))));
+	}
+
+	#[test]
+	fn order_updates_track_to_filled() {
+		let messages = [
+			serde_json::json!([{"category":"linear","symbol":"ETHUSDT","orderId":"5cf98598-39a7-459e-97bf-76ca765ee020","orderLinkId":"strat-1","side":"Buy","orderStatus":"New","cancelType":"UNKNOWN","price":"1600","qty":"2","avgPrice":"","cumExecQty":"0","cumExecValue":"0","cumExecFee":"0","feeCurrency":"","timeInForce":"GTC","orderType":"Limit","updatedTime":"1672364262444"}]),
+			serde_json::json!([{"category":"linear","symbol":"ETHUSDT","orderId":"5cf98598-39a7-459e-97bf-76ca765ee020","orderLinkId":"strat-1","side":"Buy","orderStatus":"PartiallyFilled","cancelType":"UNKNOWN","price":"1600","qty":"2","avgPrice":"1599","cumExecQty":"0.5","cumExecValue":"799.5","cumExecFee":"0.4797","feeCurrency":"","timeInForce":"GTC","orderType":"Limit","updatedTime":"1672364262450"}]),
+			serde_json::json!([{"category":"linear","symbol":"ETHUSDT","orderId":"5cf98598-39a7-459e-97bf-76ca765ee020","orderLinkId":"strat-1","side":"Buy","orderStatus":"Filled","cancelType":"UNKNOWN","price":"1600","qty":"2","avgPrice":"1599.75","cumExecQty":"2","cumExecValue":"3199.5","cumExecFee":"1.9197","feeCurrency":"","timeInForce":"GTC","orderType":"Limit","updatedTime":"1672364262457"}]),
+		];
+		let mut tracker = crate::FillTracker::default();
+		let mut done = None;
+		for data in messages {
+			for event in parse_account_events(data).unwrap() {
+				done = tracker.apply(&event);
+			}
+		}
+		let done = done.expect("last update fills it");
+		assert_eq!(done.status, OrderStatus::Filled);
+		assert_eq!(done.avg_price, Some(1599.75));
+		assert_eq!(done.fees[&Asset::from("USDT")], 1.9197);
+		assert_eq!(done.order_id.exchange_id.unwrap().as_str(), "5cf98598-39a7-459e-97bf-76ca765ee020");
+	}
+
+	#[test]
+	fn order_without_link_id_keyed_by_order_id() {
+		let data = serde_json::json!([{"category":"spot","symbol":"BTCUSDT","orderId":"1321052653536515584","orderLinkId":"","side":"Sell","orderStatus":"Cancelled","qty":"0.1","cumExecQty":"0","cumExecValue":"0","cumExecFee":"0","feeCurrency":"","updatedTime":"1672364262457"}]);
+		let events = parse_account_events(data).unwrap();
+		let AccountEvent::OrderUpdate(u) = &events[0] else { panic!("{events:?}") };
+		assert_eq!(u.order_id.client_order_id().as_str(), "1321052653536515584");
+		assert_eq!(u.status, OrderStatus::Canceled);
+	}
+
+	#[test]
+	fn position_updates() {
+		let data = serde_json::json!([
+			{"positionIdx":0,"tradeMode":0,"riskId":1,"riskLimitValue":"2000000","symbol":"BTCUSDT","side":"Sell","size":"0.01","entryPrice":"28200.5","sessionAvgPrice":"","leverage":"10","positionValue":"282.005","positionBalance":"0","markPrice":"28184.5","positionIM":"28.2","positionMM":"1.41","takeProfit":"0","stopLoss":"0","trailingStop":"0","unrealisedPnl":"0.16","cumRealisedPnl":"-25.06579337","curRealisedPnl":"0","createdTime":"1694402496913","updatedTime":"1697682317038","tpslMode":"Full","liqPrice":"30000","bustPrice":"","category":"linear","positionStatus":"Normal","adlRankIndicator":2,"autoAddMargin":0,"seq":8327597863,"isReduceOnly":false},
+			{"positionIdx":0,"tradeMode":0,"riskId":1,"riskLimitValue":"150","symbol":"BTCUSD","side":"","size":"0","entryPrice":"0","sessionAvgPrice":"","leverage":"10","positionValue":"0","positionBalance":"0","markPrice":"28184.5","positionIM":"0","positionMM":"0","takeProfit":"0","stopLoss":"0","trailingStop":"0","unrealisedPnl":"0","cumRealisedPnl":"0","curRealisedPnl":"0","createdTime":"1694402496913","updatedTime":"1697682317040","tpslMode":"Full","liqPrice":"","bustPrice":"","category":"inverse","positionStatus":"Normal","adlRankIndicator":0,"autoAddMargin":0,"seq":8327597864,"isReduceOnly":false},
+			{"symbol":"BTC-29DEC23-30000-C","side":"Buy","size":"0.1","entryPrice":"1200","markPrice":"1180","unrealisedPnl":"-2","updatedTime":"1697682317041","category":"option"},
+			{"symbol":"","side":"Buy","size":"1","entryPrice":"1","markPrice":"1","unrealisedPnl":"0","updatedTime":"1697682317042","category":"linear"}
+		]);
+		let events = parse_position_events(data).unwrap();
+		assert_eq!(events.len(), 2, "the option and the unparseable entry are skipped, not failing the batch");
+		let AccountEvent::PositionUpdate(short) = &events[0] else { panic!("{events:?}") };
+		assert_eq!(short.symbol, Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp));
+		assert_eq!(short.size, -0.01);
+		assert_eq!(short.entry_price, Some(28200.5));
+		assert_eq!(short.unrealized_pnl, 0.16);
+
+		let AccountEvent::PositionUpdate(flat) = &events[1] else { panic!("{events:?}") };
+		assert_eq!(flat.symbol.instrument, Instrument::PerpInverse);
+		assert_eq!(flat.size, 0.);
+		assert_eq!(flat.entry_price, None);
+	}
+
+	#[test]
+	fn wallet_updates() {
+		let data = serde_json::json!([{"accountIMRate":"0.016","accountMMRate":"0.003","totalEquity":"12837.78330098","totalWalletBalance":"12840.4045924","totalMarginBalance":"12837.78330188","totalAvailableBalance":"12632.05767702","totalPerpUPL":"-2.62129051","totalInitialMargin":"205.72562486","totalMaintenanceMargin":"39.42876721","coin":[
+			{"coin":"USDC","equity":"200.62572554","usdValue":"200.62572554","walletBalance":"201.34882644","availableToWithdraw":"0","availableToBorrow":"","borrowAmount":"0","accruedInterest":"0","totalOrderIM":"0","totalPositionIM":"202.99874213","totalPositionMM":"39.14289747","unrealisedPnl":"74.2768991","cumRealisedPnl":"-209.1544627","bonus":"0","collateralSwitch":true,"marginCollateral":true,"locked":"0"},
+			{"coin":"BTC","equity":"0.06488393","usdValue":"1023.08402268","walletBalance":"0.06488393","availableToWithdraw":"","availableToBorrow":"","borrowAmount":"0","accruedInterest":"0","totalOrderIM":"0","totalPositionIM":"0","totalPositionMM":"0","unrealisedPnl":"0","cumRealisedPnl":"0","bonus":"0","collateralSwitch":true,"marginCollateral":true,"locked":"0"}
+		],"accountLTV":"0","accountType":"UNIFIED"}]);
+		let time = Timestamp::from_millisecond(1672364262482).unwrap();
+		let events = parse_wallet_events(data, time).unwrap();
+		assert_eq!(events.len(), 2);
+		let AccountEvent::BalanceUpdate(btc) = &events[1] else { panic!("{events:?}") };
+		assert_eq!(btc.asset, Asset::from("BTC"));
+		assert_eq!(btc.wallet_balance, 0.06488393);
+		assert_eq!(btc.available, None);
+		assert_eq!(btc.time, time);
+	}
 }
@@ -0,0 +1,484 @@
+//! Running something on every candle close, across many symbols at once.
+use std::time::Duration;
+
+use adapters::generics::ws::WsError;
+use jiff::Timestamp;
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::prelude::*;
+
+/// Default of [CandleScheduler::grace].
+const GRACE: Duration = Duration::from_secs(3);
+/// How long a feed isn't polled for after an error. Doubles with every further error in a row, up to [MAX_FEED_BACKOFF].
+const FEED_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_FEED_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Live klines of one symbol at one timeframe. Updates of the forming candle repeat its `open_time`, and a candle is taken as closed once one with a later `open_time` comes in.
+pub type CandleFeed = Box<dyn ExchangeStream<Item = Kline>>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CandleSource {
+	/// Closed off a [CandleFeed].
+	Stream,
+	/// Fetched through [klines](Exchange::klines), as the symbol's feed was down or missing at the boundary.
+	Rest,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClosedCandle {
+	pub exchange: ExchangeName,
+	pub symbol: Symbol,
+	pub kline: Kline,
+	pub source: CandleSource,
+}
+
+/// What a [CandleSink] gets at each boundary: the candles of every target of its subscription that made it in time.
+#[derive(Clone, Debug)]
+pub struct CandleBatch {
+	pub tf: Timeframe,
+	/// Of every candle in the batch; they closed at `open_time + tf`.
+	pub open_time: Timestamp,
+	pub candles: Vec<ClosedCandle>,
+	/// A straggler, closed after its boundary's batch went out. Late batches carry exactly one candle.
+	pub late: bool,
+	/// Targets the batch went out without. Those with a live feed follow as `late` batches, if they come in before the next boundary's batch goes out; the rest couldn't be had over REST either.
+	pub missing: Vec<(ExchangeName, Symbol)>,
+}
+
+/// Where a subscription's [CandleBatch]es go. A dropped channel receiver just stops the deliveries.
+pub enum CandleSink {
+	Callback(Box<dyn FnMut(CandleBatch) + Send + Sync>),
+	Channel(mpsc::UnboundedSender<CandleBatch>),
+}
+impl CandleSink {
+	pub fn callback(f: impl FnMut(CandleBatch) + Send + Sync + 'static) -> Self {
+		Self::Callback(Box::new(f))
+	}
+
+	fn deliver(&mut self, batch: CandleBatch) {
+		match self {
+			Self::Callback(f) => f(batch),
+			Self::Channel(tx) => {
+				let _ = tx.send(batch);
+			}
+		}
+	}
+}
+impl std::fmt::Debug for CandleSink {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Callback(_) => f.write_str("Callback"),
+			Self::Channel(tx) => f.debug_tuple("Channel").field(tx).finish(),
+		}
+	}
+}
+impl From<mpsc::UnboundedSender<CandleBatch>> for CandleSink {
+	fn from(tx: mpsc::UnboundedSender<CandleBatch>) -> Self {
+		Self::Channel(tx)
+	}
+}
+
+#[derive(Debug)]
+struct Feed {
+	exchange: ExchangeName,
+	symbol: Symbol,
+	tf: Timeframe,
+	stream: CandleFeed,
+	/// Latest update, of the candle not yet closed.
+	forming: Option<Kline>,
+	/// Local time of the latest update.
+	last_seen: Option<Timestamp>,
+	/// Errored since the latest update.
+	down: bool,
+	/// Errors in a row, reset by an update.
+	errors: u32,
+	/// Not polled again before this, so that a feed failing for good doesn't spin.
+	retry_at: Option<Instant>,
+}
+impl Feed {
+	/// Whether it can be expected to close the candle opened at `open_time`: no error since, and updates during that candle.
+	fn is_live(&self, open_time: Timestamp) -> bool {
+		!self.down && self.last_seen.is_some_and(|t| t >= open_time)
+	}
+}
+
+#[derive(Debug)]
+struct Subscription {
+	tf: Timeframe,
+	targets: Vec<(ExchangeName, Symbol)>,
+	sink: CandleSink,
+	/// Of the candle the next batch is for.
+	next_open: Timestamp,
+	/// Collected towards the next batch.
+	got: Vec<ClosedCandle>,
+	/// Left out of the last batch, but with a live feed, so still to be delivered late.
+	owed: Vec<(ExchangeName, Symbol)>,
+}
+
+/// Calls back on every candle close of the targets it's given, with their closed candles batched per boundary.
+///
+/// One [CandleFeed] per (exchange, symbol, timeframe) is shared by every subscription needing it. Candles that close within [grace](Self::grace) of the boundary make it into the batch; stragglers of a live feed are delivered after it, one by one, flagged `late`. A target whose feed is down, or that has no feed at all, is fetched over REST at boundary + grace instead, from the client given for its exchange.
+/// ```rust,ignore
+/// let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+/// let mut scheduler = CandleScheduler::new(vec![ExchangeName::Binance.init_client()]).grace(Duration::from_secs(2));
+/// scheduler.on_candle_close("5m".into(), symbols.iter().map(|s| (ExchangeName::Binance, *s)), tx);
+/// tokio::spawn(scheduler.run());
+/// while let Some(batch) = rx.recv().await {
+/// 	strategy.on_close(&batch.candles);
+/// }
+/// ```
+///
+/// Boundaries are multiples of the timeframe since the epoch, so weekly and monthly candles don't line up with them.
+#[derive(Debug)]
+pub struct CandleScheduler {
+	clients: Vec<Box<dyn Exchange>>,
+	grace: Duration,
+	feeds: Vec<Feed>,
+	subscriptions: Vec<Subscription>,
+}
+impl CandleScheduler {
+	/// `clients` serve the REST fallback, at most one per exchange.
+	pub fn new(clients: Vec<Box<dyn Exchange>>) -> Self {
+		Self {
+			clients,
+			grace: GRACE,
+			feeds: Vec::new(),
+			subscriptions: Vec::new(),
+		}
+	}
+
+	/// How long after a boundary its batch waits for stragglers. Defaults to 3s.
+	pub fn grace(mut self, grace: Duration) -> Self {
+		self.grace = grace;
+		self
+	}
+
+	/// Live klines of `symbol` at `tf`, replacing any previous feed of the same.
+	pub fn feed(&mut self, exchange: ExchangeName, symbol: Symbol, tf: Timeframe, stream: CandleFeed) {
+		self.feeds.retain(|f| !(f.exchange == exchange && f.symbol == symbol && f.tf.duration() == tf.duration()));
+		self.feeds.push(Feed {
+			exchange,
+			symbol,
+			tf,
+			stream,
+			forming: None,
+			last_seen: None,
+			down: false,
+			errors: 0,
+			retry_at: None,
+		});
+	}
+
+	/// Delivers a [CandleBatch] of `targets` to `sink` at every `tf` close, starting with the candle currently forming.
+	pub fn on_candle_close(&mut self, tf: Timeframe, targets: impl IntoIterator<Item = (ExchangeName, Symbol)>, sink: impl Into<CandleSink>) {
+		self.subscribe_at(tf, targets.into_iter().collect(), sink.into(), Timestamp::now());
+	}
+
+	fn subscribe_at(&mut self, tf: Timeframe, targets: Vec<(ExchangeName, Symbol)>, sink: CandleSink, now: Timestamp) {
+		let tf_ms = tf.duration().as_millis() as i64;
+		let next_open = Timestamp::from_millisecond(now.as_millisecond().div_euclid(tf_ms) * tf_ms).expect("floored towards the epoch, so within range");
+		self.subscriptions.push(Subscription {
+			tf,
+			targets,
+			sink,
+			next_open,
+			got: Vec::new(),
+			owed: Vec::new(),
+		});
+	}
+
+	/// Delivers batches until dropped. Feed errors are logged, and the feed left to the REST fallback until it recovers. A feed that errored is backed off from, starting at 1s and doubling up to a minute.
+	///
+	/// Races the feeds' `next()`s against the boundaries, so relies on them being cancel-safe, which [WsConnection::next](adapters::generics::ws::WsConnection::next) is.
+	pub async fn run(mut self) {
+		loop {
+			let wait = self.next_deadline().map(|at| Duration::try_from(at.duration_since(Timestamp::now())).unwrap_or_default());
+			let woke = tokio::select! {
+				update = next_update(&mut self.feeds) => Some(update),
+				_ = sleep(wait) => None,
+			};
+			match woke {
+				Some(Some((i, Ok(klines)))) => klines.into_iter().for_each(|k| self.on_kline(i, k, Timestamp::now())),
+				Some(Some((i, Err(e)))) => self.on_feed_error(i, e),
+				// a backoff is over, so that feed is in the next round of polling
+				Some(None) => {}
+				None => self.flush(Timestamp::now()).await,
+			}
+		}
+	}
+
+	/// Boundary + grace of the earliest batch due.
+	fn next_deadline(&self) -> Option<Timestamp> {
+		self.subscriptions.iter().map(|s| s.next_open + s.tf.duration() + self.grace).min()
+	}
+
+	fn on_feed_error(&mut self, i: usize, e: WsError) {
+		let feed = &mut self.feeds[i];
+		feed.errors += 1;
+		let backoff = FEED_BACKOFF.saturating_mul(2u32.saturating_pow(feed.errors - 1)).min(MAX_FEED_BACKOFF);
+		warn!(target: "v_exchanges", exchange = %feed.exchange, symbol = %feed.symbol, ?backoff, "candle feed errored, falling back to REST until it recovers: {e}");
+		feed.down = true;
+		feed.retry_at = Some(Instant::now() + backoff);
+	}
+
+	fn on_kline(&mut self, i: usize, kline: Kline, now: Timestamp) {
+		let feed = &mut self.feeds[i];
+		feed.last_seen = Some(now);
+		feed.down = false;
+		feed.errors = 0;
+		feed.retry_at = None;
+		let closed = match feed.forming.as_ref().map(|k| k.open_time) {
+			Some(forming) if kline.open_time < forming => return,
+			Some(forming) if kline.open_time > forming => feed.forming.replace(kline),
+			_ => {
+				feed.forming = Some(kline);
+				None
+			}
+		};
+		if let Some(kline) = closed {
+			let candle = ClosedCandle {
+				exchange: feed.exchange,
+				symbol: feed.symbol,
+				kline,
+				source: CandleSource::Stream,
+			};
+			let tf = feed.tf.duration();
+			self.route(tf, candle);
+		}
+	}
+
+	/// Into the batch being collected, or straight out as a straggler.
+	fn route(&mut self, tf: Duration, candle: ClosedCandle) {
+		let target = (candle.exchange, candle.symbol);
+		for sub in self.subscriptions.iter_mut().filter(|s| s.tf.duration() == tf && s.targets.contains(&target)) {
+			if candle.kline.open_time >= sub.next_open {
+				if !sub.got.iter().any(|c| (c.exchange, c.symbol) == target && c.kline.open_time == candle.kline.open_time) {
+					sub.got.push(candle.clone());
+				}
+			} else if candle.kline.open_time + tf == sub.next_open
+				&& let Some(pos) = sub.owed.iter().position(|t| *t == target)
+			{
+				sub.owed.remove(pos);
+				sub.sink.deliver(CandleBatch {
+					tf: sub.tf,
+					open_time: candle.kline.open_time,
+					candles: vec![candle.clone()],
+					late: true,
+					missing: Vec::new(),
+				});
+			}
+		}
+	}
+
+	/// Sends out every batch whose grace window is over by `now`, filling in over REST for targets without a live feed.
+	async fn flush(&mut self, now: Timestamp) {
+		for i in 0..self.subscriptions.len() {
+			let sub = &self.subscriptions[i];
+			let tf = sub.tf.duration();
+			let open_time = sub.next_open;
+			if now < open_time + tf + self.grace {
+				continue;
+			}
+			let mut candles: Vec<ClosedCandle> = sub.got.iter().filter(|c| c.kline.open_time == open_time).cloned().collect();
+			let absent: Vec<(ExchangeName, Symbol)> = sub.targets.iter().filter(|t| !candles.iter().any(|c| (c.exchange, c.symbol) == **t)).copied().collect();
+			let (mut owed, mut missing, mut to_fetch) = (Vec::new(), Vec::new(), Vec::new());
+			for (exchange, symbol) in absent {
+				let live = self.feeds.iter().any(|f| f.exchange == exchange && f.symbol == symbol && f.tf.duration() == tf && f.is_live(open_time));
+				match live {
+					true => {
+						owed.push((exchange, symbol));
+						missing.push((exchange, symbol));
+					}
+					false => to_fetch.push((exchange, symbol)),
+				}
+			}
+			// all at once, as a batch of many symbols would otherwise wait out one request after another
+			let fetched = join_all(to_fetch.iter().map(|(exchange, symbol)| self.fetch(*exchange, *symbol, sub.tf, open_time))).await;
+			for ((exchange, symbol), kline) in to_fetch.into_iter().zip(fetched) {
+				match kline {
+					Some(kline) => candles.push(ClosedCandle {
+						exchange,
+						symbol,
+						kline,
+						source: CandleSource::Rest,
+					}),
+					None => missing.push((exchange, symbol)),
+				}
+			}
+
+			let sub = &mut self.subscriptions[i];
+			sub.got.retain(|c| c.kline.open_time > open_time);
+			sub.owed = owed;
+			sub.next_open = open_time + tf;
+			sub.sink.deliver(CandleBatch {
+				tf: sub.tf,
+				open_time,
+				candles,
+				late: false,
+				missing,
+			});
+		}
+	}
+
+	/// The candle of `symbol` opened at `open_time`, over REST. Failures are logged, as the batch goes out regardless.
+	async fn fetch(&self, exchange: ExchangeName, symbol: Symbol, tf: Timeframe, open_time: Timestamp) -> Option<Kline> {
+		let Some(client) = self.clients.iter().find(|c| c.name() == exchange) else {
+			debug!(target: "v_exchanges", %exchange, %symbol, "no client to fetch the candle over REST");
+			return None;
+		};
+		let range = RequestRange::Span {
+			since: open_time,
+			until: Some(open_time + tf.duration()),
+		};
+		match client.klines(symbol, tf, range).await {
+			Ok(klines) => klines.v.into_iter().find(|k| k.open_time == open_time),
+			Err(e) => {
+				warn!(target: "v_exchanges", %exchange, %symbol, "failed to fetch candle over REST: {e}");
+				None
+			}
+		}
+	}
+}
+
+/// Next update of any feed not backed off from. `None` once the earliest backoff is over, for that feed to be polled again.
+async fn next_update(feeds: &mut [Feed]) -> Option<(usize, Result<Vec<Kline>, WsError>)> {
+	let now = Instant::now();
+	let retry_at = feeds.iter().filter_map(|f| f.retry_at).filter(|at| *at > now).min();
+	let ready: Vec<_> = feeds
+		.iter_mut()
+		.enumerate()
+		.filter(|(_, f)| f.retry_at.is_none_or(|at| at <= now))
+		.map(|(i, f)| Box::pin(async move { (i, f.stream.next().await) }))
+		.collect();
+	let update = async {
+		match ready.is_empty() {
+			true => std::future::pending().await,
+			false => futures_util::future::select_all(ready).await.0,
+		}
+	};
+	let backoff_over = async {
+		match retry_at {
+			Some(at) => tokio::time::sleep_until(at).await,
+			None => std::future::pending().await,
+		}
+	};
+	tokio::select! {
+		update = update => Some(update),
+		_ = backoff_over => None,
+	}
+}
+
+async fn sleep(wait: Option<Duration>) {
+	match wait {
+		Some(wait) => tokio::time::sleep(wait).await,
+		None => std::future::pending().await,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::Scripted;
+
+	fn at(s: i64) -> Timestamp {
+		Timestamp::from_second(s).unwrap()
+	}
+
+	fn kline(open_s: i64) -> Kline {
+		Kline {
+			open_time: at(open_s),
+			ohlc: Ohlc {
+				open: 1.,
+				high: 1.,
+				low: 1.,
+				close: 1.,
+			},
+			volume_quote: 1.,
+			trades: None,
+			taker_buy_volume_quote: None,
+		}
+	}
+
+	#[tokio::test]
+	async fn stragglers_follow_the_batch() {
+		let tf = Timeframe::from("1m");
+		let [btc, eth, sol] = ["BTC", "ETH", "SOL"].map(|b| Symbol::new(Pair::new(b, "USDT"), Instrument::Perp));
+		let ex = ExchangeName::Binance;
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let mut scheduler = CandleScheduler::new(vec![]);
+		scheduler.feed(ex, btc, tf, Box::new(Scripted::<Kline>::silent()));
+		scheduler.feed(ex, eth, tf, Box::new(Scripted::<Kline>::silent()));
+		scheduler.subscribe_at(tf, vec![(ex, btc), (ex, eth), (ex, sol)], tx.into(), at(10));
+		assert_eq!(scheduler.next_deadline(), Some(at(63)));
+
+		scheduler.on_kline(0, kline(0), at(10));
+		scheduler.on_kline(1, kline(0), at(20));
+		scheduler.on_kline(0, kline(60), at(61)); // btc closes
+		scheduler.flush(at(62)).await;
+		assert!(rx.try_recv().is_err(), "flushed within grace");
+
+		scheduler.flush(at(63)).await;
+		let batch = rx.try_recv().unwrap();
+		assert!(!batch.late);
+		assert_eq!(batch.open_time, at(0));
+		assert_eq!(batch.candles.iter().map(|c| (c.symbol, c.source)).collect::<Vec<_>>(), vec![(btc, CandleSource::Stream)]);
+		// eth's feed is live, so it's owed; sol has neither feed nor client
+		assert_eq!(batch.missing, vec![(ex, eth), (ex, sol)]);
+
+		scheduler.on_kline(1, kline(60), at(64));
+		let late = rx.try_recv().unwrap();
+		assert!(late.late);
+		assert_eq!(late.candles.iter().map(|c| c.symbol).collect::<Vec<_>>(), vec![eth]);
+
+		// only owed once
+		scheduler.on_kline(1, kline(60), at(65));
+		assert!(rx.try_recv().is_err());
+		assert_eq!(scheduler.next_deadline(), Some(at(123)));
+	}
+
+	#[tokio::test]
+	async fn down_feed_is_not_waited_on() {
+		let tf = Timeframe::from("1m");
+		let btc = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+		let ex = ExchangeName::Bybit;
+		let (tx, mut rx) = mpsc::unbounded_channel();
+		let mut scheduler = CandleScheduler::new(vec![]);
+		scheduler.feed(ex, btc, tf, Box::new(Scripted::<Kline>::silent()));
+		scheduler.subscribe_at(tf, vec![(ex, btc)], tx.into(), at(0));
+
+		scheduler.on_kline(0, kline(0), at(5));
+		scheduler.feeds[0].down = true;
+		scheduler.flush(at(63)).await;
+		assert_eq!(rx.try_recv().unwrap().missing, vec![(ex, btc)]);
+
+		// recovered, but its candle is no longer owed
+		scheduler.on_kline(0, kline(60), at(64));
+		assert!(rx.try_recv().is_err());
+	}
+
+	/// Errors on every `next()`, counting them.
+	#[derive(Debug)]
+	struct Failing(Arc<std::sync::atomic::AtomicUsize>);
+	#[async_trait::async_trait]
+	impl ExchangeStream for Failing {
+		type Item = Kline;
+
+		async fn next(&mut self) -> Result<Vec<Kline>, WsError> {
+			self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+			Err(WsError::NetworkConnection)
+		}
+	}
+
+	#[tokio::test]
+	async fn failing_feed_is_backed_off_from() {
+		let polls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+		let mut scheduler = CandleScheduler::new(vec![]);
+		scheduler.feed(ExchangeName::Binance, Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp), "1m".into(), Box::new(Failing(Arc::clone(&polls))));
+
+		let (i, update) = next_update(&mut scheduler.feeds).await.unwrap();
+		scheduler.on_feed_error(i, update.unwrap_err());
+		assert!(tokio::time::timeout(Duration::from_millis(50), next_update(&mut scheduler.feeds)).await.is_err(), "not polled within the backoff");
+		assert_eq!(polls.load(std::sync::atomic::Ordering::SeqCst), 1);
+		assert!(scheduler.feeds[0].down);
+	}
+}
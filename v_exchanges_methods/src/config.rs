@@ -0,0 +1,240 @@
+//! Declarative client setup: a serde model of per-exchange settings, built into configured clients with [ClientsConfig::build].
+//!
+//! Format-agnostic; anything serde reads works. In TOML:
+//! ```toml
+//! [binance]
+//! recv_window_ms = 5000
+//! pubkey = { env = "BINANCE_PUBKEY" }
+//! secret = { env = "BINANCE_SECRET" }
+//!
+//! [bybit]
+//! testnet = true
+//! http_timeout_ms = 10000
+//! ```
+use std::time::Duration;
+
+use secrecy::SecretString;
+
+use crate::prelude::*;
+
+/// Settings of each exchange to instantiate, keyed by [ExchangeName].
+///
+/// Deserializing fails on names that don't belong to an exchange compiled in, listing the ones that do.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "BTreeMap<String, ExchangeConfig>", into = "BTreeMap<String, ExchangeConfig>")]
+pub struct ClientsConfig {
+	pub exchanges: HashMap<ExchangeName, ExchangeConfig>,
+}
+impl ClientsConfig {
+	/// Instantiates and configures a client per section. Fails if a credential's env var is unset, only one of pubkey/secret is given, or the ws settings don't [validate](adapters::generics::ws::WsConfig::validate).
+	pub fn build(&self) -> Result<HashMap<ExchangeName, Box<dyn Exchange>>> {
+		self.exchanges.iter().map(|(name, config)| Ok((*name, config.build(*name).wrap_err_with(|| format!("configuring {name}"))?))).collect()
+	}
+}
+impl TryFrom<BTreeMap<String, ExchangeConfig>> for ClientsConfig {
+	type Error = Report;
+
+	fn try_from(sections: BTreeMap<String, ExchangeConfig>) -> Result<Self> {
+		let available = ExchangeName::all_available();
+		let exchanges = sections
+			.into_iter()
+			.map(|(key, config)| {
+				let name = ExchangeName::from_str(&key).ok().filter(|n| available.contains(n)).ok_or_else(|| {
+					let valid = available.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+					eyre!("unknown exchange `{key}`; expected one of: {valid}")
+				})?;
+				Ok((name, config))
+			})
+			.collect::<Result<_>>()?;
+		Ok(Self { exchanges })
+	}
+}
+impl From<ClientsConfig> for BTreeMap<String, ExchangeConfig> {
+	fn from(config: ClientsConfig) -> Self {
+		config.exchanges.into_iter().map(|(name, c)| (name.to_string(), c)).collect()
+	}
+}
+
+/// Settings of one exchange. Everything is optional; unset fields leave the client's defaults alone.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExchangeConfig {
+	/// See [Exchange::set_use_testnet].
+	pub testnet: bool,
+	/// See [Exchange::set_recv_window].
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub recv_window_ms: Option<u64>,
+	/// See [Exchange::set_timeout].
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub http_timeout_ms: Option<u64>,
+	/// See [WsConfig::message_timeout](adapters::generics::ws::WsConfig::message_timeout). Ignored where the exchange's `-ws` feature is off.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ws_message_timeout_ms: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub pubkey: Option<Credential>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub secret: Option<Credential>,
+	/// Kucoin only.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub passphrase: Option<Credential>,
+}
+impl ExchangeConfig {
+	fn build(&self, name: ExchangeName) -> Result<Box<dyn Exchange>> {
+		let ws_message_timeout = self.ws_message_timeout_ms.map(Duration::from_millis);
+		if ws_message_timeout.is_some_and(|t| t.is_zero()) {
+			bail!("ws_message_timeout_ms must be greater than 0");
+		}
+		let mut client = name.try_init_client()?;
+		if let Some(timeout) = ws_message_timeout {
+			set_ws_message_timeout(&mut client, timeout)?;
+		}
+
+		client.set_use_testnet(self.testnet);
+		if let Some(ms) = self.recv_window_ms {
+			client.set_recv_window(Duration::from_millis(ms));
+		}
+		if let Some(ms) = self.http_timeout_ms {
+			client.set_timeout(Duration::from_millis(ms));
+		}
+		match (&self.pubkey, &self.secret) {
+			(Some(pubkey), Some(secret)) => client.auth(pubkey.resolve()?, secret.resolve()?.into()),
+			(None, None) => {}
+			(pubkey, _) => bail!("only {} of the credentials is set", if pubkey.is_some() { "the pubkey" } else { "the secret" }),
+		}
+		if let Some(passphrase) = &self.passphrase {
+			match name {
+				#[cfg(feature = "kucoin-http")]
+				ExchangeName::Kucoin => client.update_default_option(adapters::kucoin::KucoinOption::Passphrase(SecretString::from(passphrase.resolve()?))),
+				_ => bail!("{name} has no passphrase"),
+			}
+		}
+		Ok(client)
+	}
+}
+
+/// Where to take a credential from.
+///
+/// Prefer [Env](Self::Env): a [Value](Self::Value) puts the secret into the config itself. `Debug` never prints it.
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Credential {
+	/// Name of the env var holding it, read at [build](ClientsConfig::build) time.
+	Env(String),
+	Value(String),
+}
+impl Credential {
+	fn resolve(&self) -> Result<String> {
+		match self {
+			Self::Env(var) => std::env::var(var).wrap_err_with(|| format!("reading credential from ${var}")),
+			Self::Value(v) => Ok(v.clone()),
+		}
+	}
+}
+impl std::fmt::Debug for Credential {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Env(var) => f.debug_tuple("Env").field(var).finish(),
+			Self::Value(_) => f.write_str("Value(<redacted>)"),
+		}
+	}
+}
+
+/// [WsConfig](adapters::generics::ws::WsConfig) lives in each exchange's own options, hence the dispatch on name. The result is [validated](adapters::generics::ws::WsConfig::validate) here, rather than failing every connection opened with it later.
+#[allow(unused_variables)]
+fn set_ws_message_timeout(client: &mut Box<dyn Exchange>, timeout: Duration) -> Result<()> {
+	macro_rules! set {
+		($option:path, $options:ty) => {{
+			let ws_config = adapters::GetOptions::<$options>::default_options(&***client).ws_config.clone().with_message_timeout(timeout);
+			if let Err(errors) = ws_config.validate() {
+				bail!("ws_message_timeout_ms leaves an invalid ws config: {}", errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; "));
+			}
+			client.update_default_option($option(ws_config));
+		}};
+	}
+	let Venue::Builtin(name) = client.name() else { return Ok(()) };
+	match name {
+		#[cfg(feature = "binance-ws")]
+		ExchangeName::Binance => set!(adapters::binance::BinanceOption::WsConfig, adapters::binance::BinanceOptions),
+		#[cfg(feature = "bybit-ws")]
+		ExchangeName::Bybit => set!(adapters::bybit::BybitOption::WsConfig, adapters::bybit::BybitOptions),
+		#[cfg(feature = "kucoin-ws")]
+		ExchangeName::Kucoin => set!(adapters::kucoin::KucoinOption::WsConfig, adapters::kucoin::KucoinOptions),
+		#[cfg(feature = "mexc-ws")]
+		ExchangeName::Mexc => set!(adapters::mexc::MexcOption::WsConfig, adapters::mexc::MexcOptions),
+		_ => {}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn round_trip() {
+		let mut exchanges = HashMap::new();
+		#[cfg(feature = "binance-http")]
+		exchanges.insert(ExchangeName::Binance, ExchangeConfig {
+			recv_window_ms: Some(5000),
+			pubkey: Some(Credential::Env("BINANCE_PUBKEY".into())),
+			secret: Some(Credential::Value("hunter2".into())),
+			..Default::default()
+		});
+		#[cfg(feature = "bybit-http")]
+		exchanges.insert(ExchangeName::Bybit, ExchangeConfig {
+			testnet: true,
+			http_timeout_ms: Some(10_000),
+			ws_message_timeout_ms: Some(30_000),
+			..Default::default()
+		});
+		let config = ClientsConfig { exchanges };
+
+		let json = serde_json::to_string(&config).unwrap();
+		assert_eq!(serde_json::from_str::<ClientsConfig>(&json).unwrap(), config);
+		#[cfg(feature = "binance-http")]
+		assert!(json.contains(r#""binance":{"testnet":false,"recv_window_ms":5000,"pubkey":{"env":"BINANCE_PUBKEY"},"secret":{"value":"hunter2"}}"#));
+	}
+
+	#[test]
+	fn missing_sections_default() {
+		assert_eq!(serde_json::from_str::<ClientsConfig>("{}").unwrap(), ClientsConfig::default());
+		#[cfg(feature = "binance-http")]
+		{
+			let config: ClientsConfig = serde_json::from_str(r#"{"binance":{}}"#).unwrap();
+			assert_eq!(config.exchanges[&ExchangeName::Binance], ExchangeConfig::default());
+		}
+	}
+
+	#[test]
+	fn unknown_exchange_lists_valid_names() {
+		let e = serde_json::from_str::<ClientsConfig>(r#"{"ftx":{}}"#).unwrap_err().to_string();
+		assert!(e.contains("unknown exchange `ftx`"), "{e}");
+		#[cfg(feature = "binance-http")]
+		assert!(e.contains("binance"), "{e}");
+	}
+
+	#[test]
+	fn literal_secret_not_in_debug() {
+		assert_eq!(format!("{:?}", Credential::Value("hunter2".into())), "Value(<redacted>)");
+	}
+
+	#[cfg(feature = "binance-http")]
+	#[test]
+	fn build_rejects_half_credentials() {
+		let config: ClientsConfig = serde_json::from_str(r#"{"binance":{"pubkey":{"value":"k"}}}"#).unwrap();
+		let e = config.build().err().expect("secret missing");
+		assert!(format!("{e:#}").contains("only the pubkey"), "{e:#}");
+
+		let config: ClientsConfig = serde_json::from_str(r#"{"binance":{"testnet":true,"ws_message_timeout_ms":30000,"pubkey":{"value":"k"},"secret":{"value":"s"}}}"#).unwrap();
+		let clients = config.build().unwrap();
+		assert_eq!(clients[&ExchangeName::Binance].name(), ExchangeName::Binance);
+	}
+
+	#[cfg(feature = "binance-ws")]
+	#[test]
+	fn build_rejects_invalid_ws_config() {
+		let config: ClientsConfig = serde_json::from_str(r#"{"binance":{"ws_message_timeout_ms":1}}"#).unwrap();
+		let e = config.build().err().expect("shorter than the default response_timeout");
+		assert!(format!("{e:#}").contains("shorter than response_timeout"), "{e:#}");
+	}
+}
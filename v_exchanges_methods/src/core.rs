@@ -1,20 +1,30 @@
-use std::collections::{BTreeMap, VecDeque};
+use std::{
+	collections::{BTreeMap, VecDeque},
+	ops::Range,
+};
 
 use adapters::{
 	Client, HttpClient,
-	generics::{RetryConfig, ws::WsError},
+	generics::{
+		RetryConfig,
+		http::AuthError,
+		ws::{LatencyStats, SubscriptionSnapshot, WsDefinitionError, WsError},
+	},
 };
 use derive_more::{Deref, DerefMut};
 use jiff::Timestamp;
 use secrecy::SecretString;
 use serde_json::json;
+use tracing::Instrument as _;
 pub use trading_data::{BookShape, BookUpdate};
-use v_exchanges_core::Price;
+use v_exchanges_core::{Price, Qty};
 pub use v_utils::trades::{ExchangeName, Instrument, PrecisionPriceQty, Symbol};
 use v_utils::{trades::Timestamped, utils::filter_nulls};
 
+pub(crate) use crate::custom::{BasisFetcher, BookSnapshotFetcher, ExchangeImpl};
 use crate::{
-	error::{ExchangeError, ExchangeResult, MethodError, OutOfRangeError, RequestRangeError},
+	error::{AccountError, AmendError, ExchangeError, ExchangeResult, MethodError, OutOfRangeError, RequestRangeError},
+	journal::{MutationIntent, journaled},
 	prelude::*,
 };
 
@@ -22,25 +32,203 @@ const MAX_RECV_WINDOW: std::time::Duration = std::time::Duration::from_secs(10 *
 
 /// Main trait for all standardized exchange interactions.
 ///
-/// //NB: NEVER implement this trait manually. It is auto-implemented via blanket impl for all `ExchangeImpl` implementors.
-/// The blanket impl ensures that this trait can only be implemented within this crate.
+/// //NB: NEVER implement this trait manually. It is auto-implemented via blanket impl for all [ExchangeImpl](crate::custom::ExchangeImpl) implementors, which is what exchanges outside this crate implement too.
 #[async_trait::async_trait]
 pub trait Exchange: std::fmt::Debug + Send + Sync + std::ops::Deref<Target = Client> + std::ops::DerefMut {
-	fn name(&self) -> ExchangeName;
+	fn name(&self) -> Venue;
+	/// The concrete exchange, for reaching its own methods from behind a `dyn Exchange`. See [downcast_ref].
+	fn as_any(&self) -> &dyn std::any::Any;
+	fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+	fn label(&self) -> Option<&AccountLabel>;
+	fn set_label(&mut self, label: AccountLabel);
+	/// Tag the client with an account label. It then shows up in tracing spans and wraps every returned error in [AccountError].
+	fn with_label(mut self, label: impl Into<AccountLabel>) -> Self
+	where
+		Self: Sized, {
+		self.set_label(label.into());
+		self
+	}
+	fn set_log_context(&mut self, context: Option<String>);
+	/// Every subsequent call on this client runs inside an `exchange` span carrying `context`, so logs of several strategies sharing one process can be told apart.
+	fn with_log_context<S: AsRef<str>>(&mut self, context: S)
+	where
+		Self: Sized, {
+		self.set_log_context(Some(context.as_ref().to_owned()));
+	}
 	fn auth(&mut self, pubkey: String, secret: SecretString);
 	fn set_recv_window(&mut self, recv_window: std::time::Duration);
 	fn set_timeout(&mut self, timeout: std::time::Duration);
 	fn set_retry_config(&mut self, config: RetryConfig);
 	fn set_use_testnet(&mut self, b: bool);
 	fn set_cache_testnet_calls(&mut self, duration: Option<std::time::Duration>);
+	/// Log requests instead of sending them; each fails with one telling true for [is_dry_run](ExchangeError::is_dry_run). For exercising order placement logic without touching the account. Off by default.
+	fn set_dry_run(&mut self, b: bool);
+	/// With `Some`, [klines](Self::klines) failing the guard's [quality](Klines::quality_report) checks come back as [ExchangeError::DataQuality] instead. Off by default.
+	fn set_reject_degenerate_klines(&mut self, guard: Option<KlinesGuard>);
+	/// With `Some`, [place_order](Self::place_order) and [amend_order](Self::amend_order) are written ahead to `journal`, and resolved there once answered. Off by default.
+	fn set_mutation_journal(&mut self, journal: Option<Arc<dyn MutationJournal>>);
 	async fn exchange_info(&mut self, instrument: Instrument) -> ExchangeResult<ExchangeInfo>;
+	/// `symbol`'s [PairInfo], off the cached [exchange_info](Self::exchange_info), refetched once older than [EXCHANGE_INFO_TTL]. For when the client can't be borrowed mutably.
+	async fn pair_info(&self, symbol: Symbol) -> ExchangeResult<PairInfo>;
+	/// Smallest qty an order on `symbol` can be for, in base (contracts for inverse). Off the cached [exchange_info](Self::exchange_info), refetched once older than [EXCHANGE_INFO_TTL].
+	///
+	/// Errors where the exchange's info doesn't carry a minimum.
+	async fn min_order_size(&self, symbol: Symbol) -> ExchangeResult<f64>;
+	/// Fails with [QtyBelowMin](OrderViolation::QtyBelowMin) if `qty` is under [min_order_size](Self::min_order_size). Passes where there is no known minimum.
+	async fn validate_order_qty(&self, symbol: Symbol, qty: f64) -> ExchangeResult<()>;
+	/// Every pair currently trading on `instrument`. Cheaper than [exchange_info](Self::exchange_info) where the exchange allows it; doesn't touch the info cache.
+	async fn all_pairs(&self, instrument: Instrument) -> ExchangeResult<Vec<Pair>>;
+	/// [all_pairs](Self::all_pairs) quoted in USDT.
+	async fn all_usdt_pairs(&self, instrument: Instrument) -> ExchangeResult<Vec<Pair>> {
+		Ok(self.all_pairs(instrument).await?.into_iter().filter(|p| p.is_usdt()).collect())
+	}
 	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines>;
+	/// [klines](Self::klines) of the price series picked by `kind`. [KlineKind::LastPrice] is the same as calling [klines](Self::klines) directly.
+	async fn klines_kind(&self, symbol: Symbol, tf: Timeframe, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines>;
 	async fn prices(&self, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>>;
 	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64>;
-	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Vec<OpenInterest>>;
+	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<OpenInterestHistory>;
+	/// `(time until the next funding, rate expected to be charged then)` of a perp. Counted on the exchange's clock, so local skew doesn't shift it.
+	async fn funding_countdown(&self, symbol: Symbol) -> ExchangeResult<(std::time::Duration, f64)>;
+	/// Whether the next funding is less than `threshold` away. For keeping entries clear of funding payments.
+	async fn is_near_funding(&self, symbol: Symbol, threshold: std::time::Duration) -> ExchangeResult<bool> {
+		let (until_funding, _) = self.funding_countdown(symbol).await?;
+		Ok(until_funding < threshold)
+	}
+	/// Mark price of a perp: what its PnL and liquidations are computed off, as opposed to the last traded [price](Self::price).
+	async fn mark_price(&self, symbol: Symbol) -> ExchangeResult<f64>;
+	/// Key metrics of every active perp in as few requests as the exchange allows. For universe selection.
+	///
+	/// Heavy, so the result is cached for [PERP_UNIVERSE_TTL]; calls within it, from any clone of the client, return the cached map.
+	async fn perpetual_universe(&self) -> ExchangeResult<BTreeMap<Pair, PerpInfo>>;
+	/// `spot - mark` of `pair`: its spot [price](Self::price) against the perp [mark_price](Self::mark_price), fetched concurrently. Positive when spot trades above the perp.
+	async fn basis(&self, pair: Pair) -> ExchangeResult<f64> {
+		let (spot, mark) = spot_and_mark(self, pair).await?;
+		Ok(spot - mark)
+	}
+	/// [basis](Self::basis) as a fraction of the spot price.
+	async fn basis_pct(&self, pair: Pair) -> ExchangeResult<f64> {
+		let (spot, mark) = spot_and_mark(self, pair).await?;
+		Ok((spot - mark) / spot)
+	}
+	/// [basis](Self::basis), re-polled every `interval`. A failed poll is yielded as [WsError::Other]; the stream itself keeps going.
+	async fn basis_stream(&self, pair: Pair, interval: std::time::Duration) -> ExchangeResult<Box<dyn ExchangeStream<Item = f64>>>;
 	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo>;
+	async fn fees(&self, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule>;
+	/// Open positions of the authenticated account on `instrument`. Flat symbols are left out.
+	async fn positions(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>>;
+	/// Scopes of the authenticated key. Account-wide, so takes no instrument.
+	async fn key_permissions(&self) -> ExchangeResult<KeyPermissions>;
+	/// Margin model of the authenticated account, which decides what some endpoints accept. Fixed per account, so implementations fetch it once and cache it.
+	async fn account_type(&self) -> ExchangeResult<AccountType>;
+	/// Fails with a single [Unauthorized](AuthError::Unauthorized) listing everything in `needed` the key lacks. Meant for startup, so a mis-scoped key surfaces there and not as an order rejection mid-session.
+	///
+	/// See [KeyPermissions::missing] for how `ip_restricted` and `expires` are compared.
+	async fn require_permissions(&self, needed: KeyPermissions) -> ExchangeResult<()> {
+		let missing = self.key_permissions().await?.missing(&needed);
+		if missing.is_empty() {
+			return Ok(());
+		}
+		let e = AuthError::Unauthorized {
+			msg: format!("{} key is missing: {}", self.name(), missing.join(", ")),
+		};
+		with_account_ctx(self.label(), Err(e.into()))
+	}
+	async fn place_order(&self, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced>;
+	/// Looks the order up by its exchange id when known, otherwise by [client_order_id](OrderId::client_order_id).
+	async fn order_status(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState>;
+	/// Everything known about a single order, its fills included. Looked up the same way as in [order_status](Self::order_status).
+	async fn get_order(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails>;
+	/// Orders on `instrument`, optionally narrowed down to a `pair` and `status`. With no `status`, lists all open orders.
+	async fn get_orders(&self, instrument: Instrument, pair: Option<Pair>, status: Option<OrderStatus>, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<OrderDetails>>;
+	/// Value of `amount` of `from` in `to`, priced off `instrument`'s [prices](Self::prices). See [convert_with] for the routing; use it directly to reuse an already fetched price map.
+	async fn convert(&self, amount: f64, from: Asset, to: Asset, instrument: Instrument) -> ExchangeResult<ConversionQuote> {
+		let prices = self.prices(None, instrument).await?;
+		convert_with(&prices, Timestamp::now(), amount, from, to)
+	}
+	/// [place_order](Self::place_order), safe to call where a lost response must not turn into a double fill.
+	///
+	/// If the submission's outcome is unknown (see [is_unknown_outcome](ExchangeError::is_unknown_outcome)), queries the order by its client id and resubmits only if the exchange never saw it.
+	/// Relies on the client id staying the same across both attempts, which it does, as it's carried by the order itself.
+	async fn place_order_idempotent(&self, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		let order_id = order.order_id().clone();
+		match self.place_order(symbol, order.clone(), recv_window).await {
+			Err(e) if e.is_unknown_outcome() => {
+				warn!(target: "v_exchanges", client_order_id = %order_id.client_order_id(), error = %e, "Order submission outcome unknown; querying it before resubmitting");
+				match self.order_status(symbol, order_id, recv_window).await? {
+					OrderState::Found(placed) => Ok(placed),
+					OrderState::NotFound => self.place_order(symbol, order, recv_window).await,
+				}
+			}
+			r => r,
+		}
+	}
+	async fn amend_order(
+		&self,
+		order_id: OrderId,
+		symbol: Symbol,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<OrderPlaced>;
+	/// Current orderbook of `symbol`, over REST. `&mut`, as prices and quantities are parsed with the precisions from (possibly not yet cached) [exchange_info](Self::exchange_info).
+	async fn order_book_snapshot(&mut self, symbol: Symbol) -> ExchangeResult<BookShape>;
+	/// [depth_imbalance] over the top `depth` levels of a fresh [order_book_snapshot](Self::order_book_snapshot).
+	async fn market_depth_imbalance(&mut self, symbol: Symbol, depth: u32) -> ExchangeResult<f64> {
+		let book = self.order_book_snapshot(symbol).await?;
+		Ok(depth_imbalance(&book, depth))
+	}
+	/// [market_depth_imbalance](Self::market_depth_imbalance), re-polled every `interval`. A failed poll is yielded as [WsError::Other]; the stream itself keeps going.
+	async fn depth_imbalance_stream(&mut self, symbol: Symbol, depth: u32, interval: std::time::Duration) -> ExchangeResult<Box<dyn ExchangeStream<Item = f64>>>;
 	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>>;
 	async fn ws_book(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>>;
+	/// [ws_trades](Self::ws_trades) on the pairs of a [subscription_snapshot](ExchangeStream::subscription_snapshot) taken off one, eg before a restart. Refuses snapshots of other streams, instruments or exchanges.
+	async fn ws_trades_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>>;
+	/// [ws_book](Self::ws_book) counterpart of [ws_trades_resume](Self::ws_trades_resume).
+	async fn ws_book_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>>;
+	/// Mark prices of perps, with the predicted funding where the exchange streams it alongside.
+	async fn ws_mark_prices(&self, pairs: &[Pair]) -> ExchangeResult<Box<dyn ExchangeStream<Item = MarkPriceUpdate>>>;
+}
+/// Time from `server_time` until `next_funding_time`, both in ms as exchanges send them. Zero if already past, which happens while a settlement is being processed.
+pub(crate) fn funding_countdown_from(next_funding_time: i64, server_time: i64) -> std::time::Duration {
+	std::time::Duration::from_millis(next_funding_time.saturating_sub(server_time).max(0) as u64)
+}
+/// Pairs `snapshot` is subscribed to, each read off its topic by `pair_of`. What [ExchangeImpl::ws_trades_resume] and the like reopen the stream on, before checking it with [resumed].
+pub(crate) fn snapshot_pairs(snapshot: &SubscriptionSnapshot, pair_of: impl Fn(&str) -> Option<Pair>) -> ExchangeResult<Vec<Pair>> {
+	let pairs = snapshot
+		.topics
+		.iter()
+		.map(|topic| pair_of(topic).ok_or_else(|| eyre!("No pair in snapshot topic {topic}")))
+		.collect::<Result<BTreeSet<Pair>>>()?;
+	Ok(pairs.into_iter().collect())
+}
+/// `stream`, opened afresh on the pairs of `snapshot`, if it subscribes where and to what `snapshot` was. Anything else means the snapshot was taken off another kind of stream, instrument or exchange.
+pub(crate) fn resumed<T>(snapshot: &SubscriptionSnapshot, stream: Box<dyn ExchangeStream<Item = T>>) -> ExchangeResult<Box<dyn ExchangeStream<Item = T>>> {
+	let fresh = stream.subscription_snapshot().unwrap_or_default();
+	if fresh.endpoint != snapshot.endpoint {
+		return Err(WsError::from(WsDefinitionError::SnapshotMismatch {
+			expected: snapshot.endpoint.clone(),
+			got: fresh.endpoint,
+		})
+		.into());
+	}
+	if let Some(topic) = snapshot.topics.symmetric_difference(&fresh.topics).next() {
+		return Err(eyre!("Snapshot on {} doesn't resume into this kind of stream: topic {topic} is on only one side", snapshot.endpoint).into());
+	}
+	Ok(stream)
+}
+/// `ex` as the concrete `T`, if that's what it is. Lets code holding a [Box<dyn Exchange>](ExchangeInit::init_client) opportunistically use exchange-specific methods:
+/// ```rust,ignore
+/// if let Some(binance) = downcast_ref::<Binance>(&*client) {
+/// 	binance.dust_assets(None).await?;
+/// }
+/// ```
+pub fn downcast_ref<T: Exchange + 'static>(ex: &dyn Exchange) -> Option<&T> {
+	ex.as_any().downcast_ref()
+}
+/// See [downcast_ref].
+pub fn downcast_mut<T: Exchange + 'static>(ex: &mut dyn Exchange) -> Option<&mut T> {
+	ex.as_any_mut().downcast_mut()
 }
 /// Concerns itself with exact types.
 #[async_trait::async_trait]
@@ -48,6 +236,49 @@ pub trait ExchangeStream: std::fmt::Debug + Send + Sync {
 	type Item;
 
 	async fn next(&mut self) -> eyre::Result<Vec<Self::Item>, WsError>;
+
+	/// Feed latency of the underlying connection, if it tracks one. See [WsConnection::latency_stats](adapters::generics::ws::WsConnection::latency_stats).
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		None
+	}
+
+	/// What the underlying connection is subscribed to, for the next deploy to pick up with [ws_trades_resume](Exchange::ws_trades_resume) or [ws_book_resume](Exchange::ws_book_resume), whichever opened it.
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		None
+	}
+
+	/// Pass `server_time - local_time` (eg from a REST server-time call), to compensate [latency_stats](Self::latency_stats) for clock skew.
+	fn set_clock_offset(&mut self, _offset: jiff::SignedDuration) {}
+
+	/// Items dropped so far as already delivered, per the connection's [dedup_window](adapters::generics::ws::WsConfig::dedup_window). `None` where the stream doesn't deduplicate.
+	fn duplicates_skipped(&self) -> Option<u64> {
+		None
+	}
+}
+/// What the [Exchange] methods return, so that wrappers like [watermarked](crate::watermarked) take those as-is.
+#[async_trait::async_trait]
+impl<T: ExchangeStream + ?Sized> ExchangeStream for Box<T> {
+	type Item = T::Item;
+
+	async fn next(&mut self) -> eyre::Result<Vec<Self::Item>, WsError> {
+		(**self).next().await
+	}
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		(**self).latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		(**self).subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: jiff::SignedDuration) {
+		(**self).set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		(**self).duplicates_skipped()
+	}
 }
 #[async_trait::async_trait]
 pub trait SubscribeOrder {
@@ -63,8 +294,28 @@ pub trait Sequence: Send + Sync {
 /// Feature-gated exchange construction. Local extension trait over the foreign [`ExchangeName`]
 /// (defined in `v_utils::trades`) — the only piece of exchange behavior that cannot leave this crate.
 pub trait ExchangeInit {
-	fn init_client(&self) -> Box<dyn Exchange>;
-	fn init_mock_client(&self) -> Box<dyn Exchange>;
+	/// Panics on an exchange that isn't [available](Self::is_available); [try_init_client](Self::try_init_client) is the fallible version.
+	fn init_client(&self) -> Box<dyn Exchange> {
+		self.try_init_client().unwrap_or_else(|e| panic!("{e}"))
+	}
+	/// Errors on an exchange whose `*-http` feature is off.
+	fn try_init_client(&self) -> ExchangeResult<Box<dyn Exchange>>;
+	/// Panics like [init_client](Self::init_client) does.
+	fn init_mock_client(&self) -> Box<dyn Exchange> {
+		self.try_init_mock_client().unwrap_or_else(|e| panic!("{e}"))
+	}
+	fn try_init_mock_client(&self) -> ExchangeResult<Box<dyn Exchange>>;
+	/// Exchanges compiled in through their `*-http` features, ie those [init_client](Self::init_client) can build.
+	/// ```rust,ignore
+	/// for name in ExchangeName::all_available() {
+	/// 	println!("{name}: {:?}", name.init_client().exchange_info(Instrument::Perp).await.is_ok());
+	/// }
+	/// ```
+	fn all_available() -> Vec<ExchangeName>
+	where
+		Self: Sized;
+	/// Whether `self` is in [all_available](Self::all_available).
+	fn is_available(&self) -> bool;
 }
 /// most exchanges default to returning OI value in asset quantity, not quote. Exception would be Inverse on Bybit.
 /// Which actually makes sense, as same endpoints accept things like "BTCETH", where quote value would be irrelevant.
@@ -74,8 +325,58 @@ pub struct OpenInterest {
 	pub val_quote: Option<f64>,
 	/// Binance's /futures/data/openInterestHist returns CMC's MC as well
 	pub marketcap: Option<f64>,
+	/// Set whenever quote is USD or a USD stablecoin (which covers inverse contracts, as their `val_quote` is `val_asset * price` in USD).
+	pub val_usd: Option<Usd>,
 	pub timestamp: Timestamp,
 }
+impl OpenInterest {
+	/// Fill [val_usd](Self::val_usd) from `val_quote`, if `pair`'s quote is USD-denominated.
+	pub(crate) fn with_usd(mut self, pair: Pair) -> Self {
+		if matches!(pair.quote().as_ref(), "USD" | "USDT" | "USDC" | "DAI" | "BUSD") {
+			self.val_usd = self.val_quote.map(Usd);
+		}
+		self
+	}
+}
+/// Open interest over time, oldest first.
+#[derive(Clone, Debug, Default, Deref, DerefMut, derive_new::new)]
+pub struct OpenInterestHistory {
+	#[deref_mut]
+	#[deref]
+	pub v: Vec<OpenInterest>,
+}
+impl OpenInterestHistory {
+	/// Mean of every field over the history, stamped with the latest timestamp. Optional fields are averaged over the points that have them.
+	pub fn average(&self) -> Option<OpenInterest> {
+		let last = self.v.last()?;
+		let mean = |f: fn(&OpenInterest) -> Option<f64>| {
+			let (sum, n) = self.v.iter().filter_map(f).fold((0., 0usize), |(sum, n), x| (sum + x, n + 1));
+			(n > 0).then(|| sum / n as f64)
+		};
+		Some(OpenInterest {
+			val_asset: mean(|oi| Some(oi.val_asset)).expect("non-empty"),
+			val_quote: mean(|oi| oi.val_quote),
+			marketcap: mean(|oi| oi.marketcap),
+			val_usd: mean(|oi| oi.val_usd.map(|u| *u)).map(Usd),
+			timestamp: last.timestamp,
+		})
+	}
+
+	/// Point with the highest `val_asset`.
+	pub fn max(&self) -> Option<&OpenInterest> {
+		self.v.iter().max_by(|a, b| a.val_asset.total_cmp(&b.val_asset))
+	}
+
+	/// Point with the lowest `val_asset`.
+	pub fn min(&self) -> Option<&OpenInterest> {
+		self.v.iter().min_by(|a, b| a.val_asset.total_cmp(&b.val_asset))
+	}
+}
+impl FromIterator<OpenInterest> for OpenInterestHistory {
+	fn from_iter<I: IntoIterator<Item = OpenInterest>>(iter: I) -> Self {
+		Self { v: iter.into_iter().collect() }
+	}
+}
 /// Does not have any gaps in the data, (as klines are meant to be indexed naively when used). TODO: enforce this.
 ///
 /// # Arch
@@ -87,6 +388,208 @@ pub struct Klines {
 	pub v: VecDeque<Kline>,
 	pub tf: Timeframe,
 }
+impl Klines {
+	/// Single pass over the klines, counting the usual ways an exchange serves broken data. Independent of whether a [KlinesGuard] is set.
+	pub fn quality_report(&self) -> KlinesQuality {
+		let mut q = KlinesQuality {
+			len: self.v.len(),
+			..Default::default()
+		};
+		let mut prev_open: Option<Timestamp> = None;
+		for k in &self.v {
+			let o = &k.ohlc;
+			if o.open == 0. && o.high == 0. && o.low == 0. && o.close == 0. {
+				q.zero_ohlc_count += 1;
+			}
+			if k.volume_quote == 0. {
+				q.zero_volume_count += 1;
+			}
+			if let Some(prev) = prev_open {
+				match k.open_time.cmp(&prev) {
+					std::cmp::Ordering::Equal => q.duplicate_times += 1,
+					std::cmp::Ordering::Less => q.out_of_order += 1,
+					std::cmp::Ordering::Greater => {}
+				}
+			}
+			prev_open = Some(k.open_time);
+		}
+		q.monotonic_time = q.duplicate_times == 0 && q.out_of_order == 0;
+		q
+	}
+
+	/// What the [Display] impl prints, as data.
+	pub fn summary(&self) -> KlinesSummary {
+		let (min, max) = self.v.iter().fold((None, None), |(min, max): (Option<f64>, Option<f64>), k| {
+			(Some(min.map_or(k.ohlc.low, |m| m.min(k.ohlc.low))), Some(max.map_or(k.ohlc.high, |m| m.max(k.ohlc.high))))
+		});
+		KlinesSummary {
+			count: self.v.len(),
+			tf: self.tf,
+			time_range: self.v.front().zip(self.v.back()).map(|(first, last)| first.open_time..last.open_time + self.tf.duration()),
+			first_close: self.v.front().map(|k| k.ohlc.close),
+			last_close: self.v.back().map(|k| k.ohlc.close),
+			min,
+			max,
+			volume_quote: self.v.iter().map(|k| k.volume_quote).sum(),
+		}
+	}
+
+	/// Klines opening within `range`: start inclusive, end exclusive, so that adjacent ranges split a series without overlap. Binary searches, relying on open times being ordered.
+	pub fn slice(&self, range: Range<Timestamp>) -> Klines {
+		let start = self.v.partition_point(|k| k.open_time < range.start);
+		let end = self.v.partition_point(|k| k.open_time < range.end).max(start);
+		Klines::new(self.v.range(start..end).cloned().collect(), self.tf)
+	}
+
+	/// Close-to-close log returns, `ln(close[i] / close[i - 1])`. One shorter than `self`; empty for fewer than two klines.
+	pub fn returns(&self) -> Vec<f64> {
+		self.v.iter().zip(self.v.iter().skip(1)).map(|(prev, k)| (k.ohlc.close / prev.ohlc.close).ln()).collect()
+	}
+
+	/// Volume-weighted average of the typical price `(high + low + close) / 3`, weighted by [volume_quote](Kline::volume_quote). Aligned with the klines.
+	///
+	/// `None` accumulates from the first kline; `Some(n)` is over the last `n`, and `NaN` for the first `n - 1`. Also `NaN` wherever the window has no volume.
+	pub fn vwap(&self, window: Option<usize>) -> Vec<f64> {
+		let weights: Vec<f64> = self.v.iter().map(|k| k.volume_quote).collect();
+		weighted_typical_price(&self.v, &weights, window)
+	}
+
+	/// [vwap](Self::vwap), with each kline's volume further weighted by the time since the previous one opened, so that a kline after a gap counts for the time it stands in for. Same as [vwap](Self::vwap) on gapless data.
+	///
+	/// Looks back only: the first kline is taken to cover one [tf](Self::tf).
+	pub fn tvwap(&self, window: Option<usize>) -> Vec<f64> {
+		let tf_secs = self.tf.duration().as_secs_f64();
+		let weights: Vec<f64> = self
+			.v
+			.iter()
+			.enumerate()
+			.map(|(i, k)| {
+				let secs = match i {
+					0 => tf_secs,
+					_ => k.open_time.duration_since(self.v[i - 1].open_time).as_secs_f64(),
+				};
+				k.volume_quote * secs
+			})
+			.collect();
+		weighted_typical_price(&self.v, &weights, window)
+	}
+}
+
+/// Running `sum(typical * weight) / sum(weight)`; see [Klines::vwap].
+fn weighted_typical_price(klines: &VecDeque<Kline>, weights: &[f64], window: Option<usize>) -> Vec<f64> {
+	let typical: Vec<f64> = klines.iter().map(|k| (k.ohlc.high + k.ohlc.low + k.ohlc.close) / 3.).collect();
+	let (mut pv, mut w) = (CompensatedSum::default(), CompensatedSum::default());
+	(0..klines.len())
+		.map(|i| {
+			pv.add(typical[i] * weights[i]);
+			w.add(weights[i]);
+			if let Some(n) = window {
+				if i + 1 < n || n == 0 {
+					return f64::NAN;
+				}
+				if i >= n {
+					pv.add(-typical[i - n] * weights[i - n]);
+					w.add(-weights[i - n]);
+				}
+			}
+			match w.value() > 0. {
+				true => pv.value() / w.value(),
+				false => f64::NAN,
+			}
+		})
+		.collect()
+}
+
+/// Neumaier-compensated running sum. Keeps what a large term rounded away, so that once it leaves a rolling window, the small ones it was added to are still all there.
+#[derive(Clone, Copy, Debug, Default)]
+struct CompensatedSum {
+	sum: f64,
+	compensation: f64,
+}
+impl CompensatedSum {
+	fn add(&mut self, x: f64) {
+		let t = self.sum + x;
+		self.compensation += match self.sum.abs() >= x.abs() {
+			true => (self.sum - t) + x,
+			false => (x - t) + self.sum,
+		};
+		self.sum = t;
+	}
+
+	fn value(&self) -> f64 {
+		self.sum + self.compensation
+	}
+}
+/// See [Klines::summary]. Everything but `count`, `tf` and `volume_quote` is `None` for empty klines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KlinesSummary {
+	pub count: usize,
+	pub tf: Timeframe,
+	/// From the first open to the close of the last kline.
+	pub time_range: Option<Range<Timestamp>>,
+	pub first_close: Option<f64>,
+	pub last_close: Option<f64>,
+	/// Lowest low.
+	pub min: Option<f64>,
+	/// Highest high.
+	pub max: Option<f64>,
+	pub volume_quote: f64,
+}
+/// See [Klines::quality_report].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, miette::Diagnostic, thiserror::Error)]
+#[error("Degenerate klines: {zero_ohlc_count} of {len} have all-zero OHLC, {duplicate_times} repeat the previous open time, {out_of_order} are out of order")]
+#[diagnostic(code(v_exchanges::data_quality), help("Exchanges tend to serve placeholder candles during outages. Retry later, or source the range elsewhere."))]
+pub struct KlinesQuality {
+	pub len: usize,
+	pub zero_ohlc_count: usize,
+	/// Expected for [mark and index price](KlineKind) klines, which carry no volume, so never a reason for rejection.
+	pub zero_volume_count: usize,
+	/// Open times strictly increase throughout.
+	pub monotonic_time: bool,
+	/// Klines opening at the same time as the one before them.
+	pub duplicate_times: usize,
+	/// Klines opening before the one before them.
+	pub out_of_order: usize,
+}
+impl KlinesQuality {
+	pub fn zero_ohlc_share(&self) -> f64 {
+		match self.len {
+			0 => 0.,
+			len => self.zero_ohlc_count as f64 / len as f64,
+		}
+	}
+
+	pub fn is_degenerate(&self, guard: &KlinesGuard) -> bool {
+		self.duplicate_times > 0 || self.zero_ohlc_share() > guard.max_zero_ohlc_share
+	}
+}
+/// Thresholds for [set_reject_degenerate_klines](Exchange::set_reject_degenerate_klines).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KlinesGuard {
+	/// Share of all-zero candles tolerated, in `0..=1`. Duplicate open times are never tolerated.
+	pub max_zero_ohlc_share: f64,
+}
+impl Default for KlinesGuard {
+	fn default() -> Self {
+		Self { max_zero_ohlc_share: 0.01 }
+	}
+}
+/// Which price series [klines](Exchange::klines_kind) are built from.
+///
+/// Mark and index price series carry no trading activity: their [volume_quote](Kline::volume_quote) is zero-filled, and `trades` and `taker_buy_volume_quote` are `None`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, derive_more::Display)]
+pub enum KlineKind {
+	/// Regular klines off executed trades.
+	#[default]
+	LastPrice,
+	/// Exchange's mark price, the one used for liquidations and unrealized pnl.
+	MarkPrice,
+	/// Index price, aggregated over several spot venues. Keyed by the underlying pair, not by a specific contract.
+	IndexPrice,
+}
+/// Distinguishes several credential sets on the same exchange (eg main and sub accounts).
+#[derive(Clone, Debug, Default, derive_more::Display, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct AccountLabel(pub String);
 #[derive(Clone, Copy, Debug)]
 pub enum RequestRange {
 	/// Preferred way of defining the range
@@ -96,6 +599,12 @@ pub enum RequestRange {
 	Limit(u32),
 }
 impl RequestRange {
+	/// `since = now - duration`, open-ended. Errors if that would be before the earliest [Timestamp].
+	pub fn from_duration_before_now(duration: std::time::Duration) -> Result<Self, RequestRangeError> {
+		let since = Timestamp::now().checked_sub(duration).map_err(|e| eyre!("{duration:?} before now is out of range: {e}"))?;
+		Ok(RequestRange::Span { since, until: None })
+	}
+
 	pub fn ensure_allowed(&self, allowed: std::ops::RangeInclusive<u32>, tf: &Timeframe) -> Result<(), RequestRangeError> {
 		match self {
 			RequestRange::Span { since: start, until: end } =>
@@ -117,16 +626,61 @@ impl RequestRange {
 		Ok(())
 	}
 
-	pub fn serialize(&self, exchange: ExchangeName) -> serde_json::Value {
-		match exchange {
-			#[cfg(feature = "binance")]
-			ExchangeName::Binance => self.serialize_common(),
-			#[cfg(feature = "bybit")]
-			ExchangeName::Bybit => self.serialize_common(),
-			_ => unimplemented!(),
+	/// `exchange` is only named for symmetry with [to_exchange_params](Self::to_exchange_params): every exchange served so far spells the range the same way.
+	pub fn serialize(&self, _exchange: ExchangeName) -> serde_json::Value {
+		self.serialize_common()
+	}
+
+	/// Effective number of candles covered by the range, capped at `max_limit`. Open-ended spans are counted up to now.
+	pub fn auto_limit(&self, tf: &Timeframe, max_limit: u32) -> u32 {
+		match self {
+			RequestRange::Span { since, until } => {
+				// `Span::get_milliseconds` would be just the sub-second component
+				let span_ms = (until.unwrap_or_else(Timestamp::now).as_millisecond() - since.as_millisecond()).max(0);
+				let tf_ms = tf.duration().as_millis() as i64;
+				let n = (span_ms + tf_ms - 1) / tf_ms; // ceil
+				n.clamp(1, max_limit as i64) as u32
+			}
+			RequestRange::Limit(limit) => (*limit).min(max_limit),
+		}
+	}
+
+	/// Like [serialize](Self::serialize), but always sets `limit`, - some exchanges (eg Bybit) silently fall back to their default page size when given only `startTime`/`endTime`.
+	pub fn to_exchange_params(&self, exchange: ExchangeName, tf: &Timeframe) -> serde_json::Value {
+		let mut params = self.serialize(exchange);
+		params["limit"] = json!(self.auto_limit(tf, Self::max_limit(exchange)));
+		params
+	}
+
+	/// [serialize](Self::serialize) straight into a query, without the `Value` round-trip. Absent params are just not pushed.
+	pub fn append_params(&self, _exchange: ExchangeName, out: &mut Vec<(&'static str, String)>) {
+		match self {
+			RequestRange::Span { .. } => self.append_span(out),
+			RequestRange::Limit(limit) => out.push(("limit", limit.to_string())),
+		}
+	}
+
+	/// [to_exchange_params](Self::to_exchange_params) straight into a query; see [append_params](Self::append_params).
+	pub fn append_exchange_params(&self, exchange: ExchangeName, tf: &Timeframe, out: &mut Vec<(&'static str, String)>) {
+		let max_limit = Self::max_limit(exchange);
+		self.append_span(out);
+		out.push(("limit", self.auto_limit(tf, max_limit).to_string()));
+	}
+
+	fn append_span(&self, out: &mut Vec<(&'static str, String)>) {
+		if let RequestRange::Span { since, until } = self {
+			out.push(("startTime", since.as_millisecond().to_string()));
+			if let Some(until) = until {
+				out.push(("endTime", until.as_millisecond().to_string()));
+			}
 		}
 	}
 
+	/// Largest klines page. Binance, Bybit and Mexc all cap it at 1000; those paging differently (Kucoin) don't go through here.
+	fn max_limit(_exchange: ExchangeName) -> u32 {
+		1000
+	}
+
 	fn serialize_common(&self) -> serde_json::Value {
 		filter_nulls(match self {
 			RequestRange::Span { since: start, until: end } => json!({
@@ -140,7 +694,9 @@ impl RequestRange {
 	}
 }
 
+/// Built through [new](Self::new) outside this crate, as which fields it has depends on the features.
 #[derive(Clone, Copy, Debug, Default, derive_more::Deref, derive_more::DerefMut)]
+#[non_exhaustive]
 pub struct AssetBalance {
 	pub asset: Asset,
 	pub underlying: f64,
@@ -148,6 +704,13 @@ pub struct AssetBalance {
 	#[deref_mut]
 	#[deref]
 	pub usd: Option<Usd>,
+	/// Part of [underlying](Self::underlying) free to back new orders. `None` where the exchange doesn't split it out.
+	pub available: Option<f64>,
+	/// Part of [underlying](Self::underlying) held by open orders. `None` where the exchange doesn't split it out.
+	pub locked: Option<f64>,
+	/// [underlying](Self::underlying) exactly as the exchange sent it, for an order to [sell it all](crate::orders::ExactQty) without picking up float error on the way. Only filled where the exchange reports the balance as a single figure.
+	#[cfg(feature = "decimal")]
+	pub underlying_exact: Option<rust_decimal::Decimal>,
 	// Binance
 	//cross_wallet_balance: f64,
 	//cross_unrealized_pnl: f64,
@@ -166,6 +729,46 @@ pub struct AssetBalance {
 	//position_margin: f64,
 	//unrealized: f64,
 }
+impl AssetBalance {
+	pub fn new(asset: Asset, underlying: f64, usd: Option<Usd>) -> Self {
+		Self {
+			asset,
+			underlying,
+			usd,
+			available: None,
+			locked: None,
+			#[cfg(feature = "decimal")]
+			underlying_exact: None,
+		}
+	}
+
+	/// With [underlying_exact](Self::underlying_exact) kept, where the feature is on.
+	pub(crate) fn from_exact(asset: Asset, underlying: crate::utils::ExactNumber, usd: Option<Usd>) -> Self {
+		Self {
+			#[cfg(feature = "decimal")]
+			underlying_exact: underlying.exact,
+			..Self::new(asset, underlying.value, usd)
+		}
+	}
+
+	/// Set [usd](Self::usd) off `price` of the asset in USD.
+	pub fn with_usd_price(mut self, price: f64) -> Self {
+		self.usd = Some(Usd(self.underlying * price));
+		self
+	}
+
+	/// [with_usd_price](Self::with_usd_price), at the current spot price against USDT on `exchange`.
+	/// ```rust,ignore
+	/// let eth = balances.iter().find(|b| b.asset == "ETH").copied().unwrap().fetch_usd(&*exchange).await?;
+	/// ```
+	pub async fn fetch_usd(self, exchange: &dyn Exchange) -> ExchangeResult<Self> {
+		if self.asset == "USDT" {
+			return Ok(self.with_usd_price(1.));
+		}
+		let price = exchange.price(Symbol::new(Pair::new(self.asset, "USDT"), Instrument::Spot)).await?;
+		Ok(self.with_usd_price(price))
+	}
+}
 #[derive(Clone, Debug, Default, derive_more::Deref, derive_more::DerefMut, derive_new::new)]
 pub struct Balances {
 	#[deref_mut]
@@ -174,6 +777,64 @@ pub struct Balances {
 	/// breaks zero-cost of the abstraction, but I assume that most calls to this actually want usd, so it's warranted.
 	pub total: Usd,
 }
+
+/// Result of [convert_with]: the converted amount, and how it was arrived at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionQuote {
+	pub value: f64,
+	/// Pairs whose prices were chained, in order. Empty when converting an asset into itself.
+	pub route: Vec<Pair>,
+	/// When the prices used were fetched.
+	pub prices_at: Timestamp,
+}
+
+/// Preferred intermediates when no direct pair exists, most liquid first.
+const CONVERSION_HUBS: [&str; 5] = ["USDT", "USDC", "BTC", "ETH", "BNB"];
+
+/// Convert `amount` of `from` into `to` over `prices`, going through at most one intermediate asset (eg `X→BTC→USDT`, `X→USDT→USDC`).
+///
+/// Pairs are usable in either direction. With several two-hop routes available, one through a [hub](CONVERSION_HUBS) is preferred.
+pub fn convert_with(prices: &BTreeMap<Pair, f64>, prices_at: Timestamp, amount: f64, from: Asset, to: Asset) -> ExchangeResult<ConversionQuote> {
+	let quote = |value: f64, route: Vec<Pair>| Ok(ConversionQuote { value, route, prices_at });
+	if from == to {
+		return quote(amount, vec![]);
+	}
+	// one unit of `a` in `b`, through a single pair
+	let rate = |a: Asset, b: Asset| -> Option<(Pair, f64)> {
+		let direct = Pair::new(a, b);
+		if let Some(&p) = prices.get(&direct).filter(|p| **p > 0.) {
+			return Some((direct, p));
+		}
+		let inverse = Pair::new(b, a);
+		prices.get(&inverse).filter(|p| **p > 0.).map(|p| (inverse, 1. / p))
+	};
+	if let Some((pair, r)) = rate(from, to) {
+		return quote(amount * r, vec![pair]);
+	}
+
+	let mut intermediates: Vec<Asset> = prices
+		.keys()
+		.filter_map(|p| {
+			let (base, quote) = (Asset::from(p.base().as_ref()), Asset::from(p.quote().as_ref()));
+			match (base == from, quote == from) {
+				(true, _) => Some(quote),
+				(_, true) => Some(base),
+				_ => None,
+			}
+		})
+		.collect();
+	let hub_rank = |a: &Asset| CONVERSION_HUBS.iter().position(|h| a.as_ref() == *h).unwrap_or(CONVERSION_HUBS.len());
+	intermediates.sort_by(|a, b| hub_rank(a).cmp(&hub_rank(b)).then_with(|| a.as_ref().cmp(b.as_ref())));
+	intermediates.dedup();
+	for &mid in &intermediates {
+		if let (Some((first, r1)), Some((second, r2))) = (rate(from, mid), rate(mid, to)) {
+			return quote(amount * r1 * r2, vec![first, second]);
+		}
+	}
+
+	let tried = std::iter::once(format!("{from}→{to}")).chain(intermediates.iter().map(|mid| format!("{from}→{mid}→{to}"))).collect::<Vec<_>>();
+	Err(ExchangeError::Other(eyre!("No conversion route from {from} to {to} within two hops. Tried: {}", tried.join(", "))))
+}
 #[derive(Clone, Debug, Default)]
 pub struct ApiKeyInfo {
 	/// `None` means no expiry set (key is permanent)
@@ -205,7 +866,7 @@ pub enum KeyPermission {
 	Other(String),
 }
 impl KeyPermission {
-	#[cfg(feature = "kucoin")]
+	#[cfg(feature = "kucoin-http")]
 	pub(crate) fn from_kucoin(s: &str) -> Self {
 		match s {
 			"General" => Self::Read,
@@ -220,11 +881,204 @@ impl KeyPermission {
 		}
 	}
 }
+/// See [Exchange::account_type].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AccountType {
+	/// Separate wallets per product, margined in isolation from each other.
+	Classic,
+	/// One wallet margining spot, derivatives and options together. Bybit's UTA.
+	UnifiedTrading,
+	/// Bybit's predecessor to [UnifiedTrading](Self::UnifiedTrading), unifying only linear derivatives and options.
+	UnifiedMargin,
+}
+/// What [require_permissions](Exchange::require_permissions) checks a key against. Coarser than [KeyPermission], which keeps everything the exchange reports.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct KeyPermissions {
+	pub read: bool,
+	pub spot_trade: bool,
+	/// Perps and dated futures; exchanges don't scope them apart.
+	pub futures_trade: bool,
+	pub withdraw: bool,
+	/// Whether the key only works from whitelisted IPs. `None` where the exchange doesn't say.
+	pub ip_restricted: Option<bool>,
+	/// `None` if the key doesn't expire, or the exchange doesn't say.
+	pub expires: Option<Timestamp>,
+}
+impl KeyPermissions {
+	pub(crate) fn from_permissions(permissions: &[KeyPermission]) -> Self {
+		let has = |p: KeyPermission| permissions.contains(&p);
+		Self {
+			read: has(KeyPermission::Read),
+			spot_trade: has(KeyPermission::SpotTrade),
+			futures_trade: has(KeyPermission::Futures),
+			withdraw: has(KeyPermission::Withdraw),
+			..Default::default()
+		}
+	}
+
+	/// Requirements of `needed` this key doesn't meet, human-readable.
+	///
+	/// Scopes are compared only where `needed` sets them. `ip_restricted: Some(true)` demands a restricted key; it's missed when the exchange doesn't report it. `expires: Some(t)` demands the key stay valid until at least `t`.
+	pub fn missing(&self, needed: &Self) -> Vec<String> {
+		let mut out = Vec::new();
+		for (name, need, have) in [
+			("read", needed.read, self.read),
+			("spot_trade", needed.spot_trade, self.spot_trade),
+			("futures_trade", needed.futures_trade, self.futures_trade),
+			("withdraw", needed.withdraw, self.withdraw),
+		] {
+			if need && !have {
+				out.push(name.to_owned());
+			}
+		}
+		if needed.ip_restricted == Some(true) && self.ip_restricted != Some(true) {
+			out.push("ip_restriction".to_owned());
+		}
+		if let (Some(until), Some(expires)) = (needed.expires, self.expires)
+			&& expires < until
+		{
+			out.push(format!("validity until {until} (expires {expires})"));
+		}
+		out
+	}
+}
+impl std::fmt::Display for KeyPermissions {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let scopes: Vec<&str> = [("read", self.read), ("spot_trade", self.spot_trade), ("futures_trade", self.futures_trade), ("withdraw", self.withdraw)]
+			.into_iter()
+			.filter_map(|(name, has)| has.then_some(name))
+			.collect();
+		match scopes.is_empty() {
+			true => write!(f, "no scopes")?,
+			false => write!(f, "{}", scopes.join(", "))?,
+		}
+		match self.ip_restricted {
+			Some(true) => write!(f, "; ip-restricted")?,
+			Some(false) => write!(f, "; unrestricted ip")?,
+			None => {}
+		}
+		match self.expires {
+			Some(t) => write!(f, "; expires {t}"),
+			None => Ok(()),
+		}
+	}
+}
 #[derive(Clone, Debug)]
 pub struct PersonalInfo {
 	pub api: ApiKeyInfo,
 	pub balances: Balances,
 }
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeeSchedule {
+	/// Fraction of notional, eg `0.0002` == 2 bps. Negative means rebate.
+	pub maker_rate: f64,
+	pub taker_rate: f64,
+	/// Only where the fee endpoint reports it; no extra calls are made to fill it in.
+	pub volume_30d_usd: Option<Usd>,
+	pub tier_name: Option<String>,
+}
+/// Entry of [Exchange::positions].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+	pub symbol: Symbol,
+	/// Negative for shorts. In base asset, except on inverse perps, where it's in contracts.
+	pub size: f64,
+	pub entry_price: f64,
+	/// Only where the endpoint reports it.
+	pub mark_price: Option<f64>,
+	pub unrealized_pnl: f64,
+	pub updated_at: Timestamp,
+}
+impl FeeSchedule {
+	/// Fee paid on a fill of `notional` (in quote). Negative when a maker rebate applies.
+	pub fn expected_cost(&self, notional: f64, is_maker: bool) -> f64 {
+		let rate = if is_maker { self.maker_rate } else { self.taker_rate };
+		notional.abs() * rate
+	}
+}
+/// Entry of [Exchange::perpetual_universe].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PerpInfo {
+	pub mark_price: f64,
+	/// Rate of the upcoming funding, as currently estimated.
+	pub funding_rate: f64,
+	pub next_funding_time: Timestamp,
+	/// `None` where the exchange has no all-symbols endpoint for it.
+	pub open_interest_usd: Option<f64>,
+	pub volume_24h_usd: f64,
+	/// In percent: `1.5` is +1.5%.
+	pub price_change_24h_pct: f64,
+}
+
+/// How long [Exchange::perpetual_universe] serves from cache.
+pub const PERP_UNIVERSE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Last [perpetual_universe](Exchange::perpetual_universe) result, with when it was fetched. Clones share it.
+#[derive(Clone, Debug, Default)]
+pub struct PerpUniverseCache(Arc<Mutex<Option<(std::time::Instant, BTreeMap<Pair, PerpInfo>)>>>);
+impl PerpUniverseCache {
+	fn get(&self) -> Option<BTreeMap<Pair, PerpInfo>> {
+		self.get_at(std::time::Instant::now())
+	}
+
+	/// What [get](Self::get) returns at `now`.
+	fn get_at(&self, now: std::time::Instant) -> Option<BTreeMap<Pair, PerpInfo>> {
+		let cached = self.0.lock().unwrap();
+		cached.as_ref().filter(|(at, _)| now.saturating_duration_since(*at) < PERP_UNIVERSE_TTL).map(|(_, universe)| universe.clone())
+	}
+
+	fn set(&self, universe: BTreeMap<Pair, PerpInfo>) {
+		*self.0.lock().unwrap() = Some((std::time::Instant::now(), universe));
+	}
+}
+
+/// Last [exchange_info](Exchange::exchange_info) of each instrument. Clones share it.
+#[derive(Clone, Debug, Default)]
+pub struct InfoCache(Arc<Mutex<BTreeMap<Instrument, Arc<ExchangeInfo>>>>);
+impl InfoCache {
+	pub fn get(&self, instrument: Instrument) -> Option<Arc<ExchangeInfo>> {
+		self.0.lock().unwrap().get(&instrument).cloned()
+	}
+
+	/// Replaces what was cached for `instrument`, returning the new entry.
+	pub fn insert(&self, instrument: Instrument, info: ExchangeInfo) -> Arc<ExchangeInfo> {
+		let info = Arc::new(info);
+		self.0.lock().unwrap().insert(instrument, Arc::clone(&info));
+		info
+	}
+
+	/// Cached info of `instrument`, or, if there is none, what `fetch` comes back with, cached from then on. `fetch` is only polled on a miss.
+	pub async fn get_or_fetch(&self, instrument: Instrument, fetch: impl Future<Output = ExchangeResult<ExchangeInfo>>) -> ExchangeResult<Arc<ExchangeInfo>> {
+		match self.get(instrument) {
+			Some(info) => Ok(info),
+			None => Ok(self.insert(instrument, fetch.await?)),
+		}
+	}
+}
+
+/// How old a cached [exchange_info](Exchange::exchange_info) can get before [pair_info](Exchange::pair_info), [min_order_size](Exchange::min_order_size) and [validate_order_qty](Exchange::validate_order_qty) refetch it.
+pub const EXCHANGE_INFO_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// [ExchangeImpl::exchange_info] as the [Exchange] one runs it, ie in its call span and account context, into the info cache.
+async fn fetch_exchange_info<T: ExchangeImpl + 'static>(ex: &T, instrument: Instrument) -> ExchangeResult<Arc<ExchangeInfo>> {
+	let span = call_span(ex, "exchange_info");
+	let info = with_account_ctx(ExchangeImpl::label(ex), ExchangeImpl::exchange_info(ex, instrument).instrument(span).await)?;
+	Ok(ex.info_cache().insert(instrument, info))
+}
+
+/// `symbol`'s [PairInfo] off the info cache, refreshing it first if older than [EXCHANGE_INFO_TTL]. Aged by `server_time`, ie since the exchange produced it.
+async fn fresh_pair_info<T: ExchangeImpl + 'static>(ex: &T, symbol: Symbol) -> ExchangeResult<PairInfo> {
+	let age = |info: &ExchangeInfo| std::time::Duration::try_from(Timestamp::now().duration_since(info.server_time)).unwrap_or_default();
+	let info = match ex.info_cache().get(symbol.instrument) {
+		Some(info) if age(&info) < EXCHANGE_INFO_TTL => info,
+		_ => fetch_exchange_info(ex, symbol.instrument).await?,
+	};
+	info.pairs
+		.get(&symbol.pair)
+		.cloned()
+		.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(ExchangeImpl::name(ex), symbol.instrument, symbol.pair)))
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct ExchangeInfo {
 	pub server_time: Timestamp,
@@ -242,56 +1096,196 @@ pub struct PairInfo {
 	pub qty_precision: u8,
 	/// `None` means perpetual (no expiry). Only set for dated futures.
 	pub delivery_date: Option<Timestamp>,
+	/// Smallest order qty, in base (contracts for inverse). `None` where the exchange's info isn't read for it.
+	pub min_qty: Option<f64>,
+	/// Largest order qty, in base (contracts for inverse). `None` where the exchange's info isn't read for it.
+	pub max_qty: Option<f64>,
+	/// Order qty has to be a multiple of this (the lot size).
+	pub qty_step: Option<f64>,
+	/// Smallest order value, in quote. `None` where the exchange has no such limit or it isn't read.
+	pub min_notional: Option<f64>,
+	/// Bounds on the limit price. `None` where the exchange has no such limit or it isn't read.
+	pub min_price: Option<f64>,
+	pub max_price: Option<f64>,
+	/// Trading status as the exchange spells it (`TRADING`, `Trading`, `Open`, ..). `None` where its info carries none.
+	pub status: Option<String>,
 }
 impl ExchangeInit for ExchangeName {
-	fn init_client(&self) -> Box<dyn Exchange> {
-		match self {
-			#[cfg(feature = "binance")]
+	fn try_init_client(&self) -> ExchangeResult<Box<dyn Exchange>> {
+		Ok(match self {
+			#[cfg(feature = "binance-http")]
 			Self::Binance => Box::new(crate::Binance::default()),
-			#[cfg(feature = "bybit")]
+			#[cfg(feature = "bybit-http")]
 			Self::Bybit => Box::new(crate::Bybit::default()),
-			#[cfg(feature = "kucoin")]
+			#[cfg(feature = "kucoin-http")]
 			Self::Kucoin => Box::new(crate::Kucoin::default()),
-			#[cfg(feature = "mexc")]
+			#[cfg(feature = "mexc-http")]
 			Self::Mexc => Box::new(crate::Mexc::default()),
-			_ => unimplemented!(),
-		}
+			_ => return Err(not_compiled_in(*self)),
+		})
 	}
 
-	fn init_mock_client(&self) -> Box<dyn Exchange> {
-		match self {
-			#[cfg(feature = "binance")]
+	fn try_init_mock_client(&self) -> ExchangeResult<Box<dyn Exchange>> {
+		Ok(match self {
+			#[cfg(feature = "binance-http")]
 			Self::Binance => Box::new(crate::Binance {
 				client: Client::new_mock(),
-				info_cache: BTreeMap::default(),
+				info_cache: Default::default(),
+				label: None,
+				log_context: None,
+				klines_guard: None,
+				mutation_journal: None,
+				perp_universe: Default::default(),
 			}),
-			#[cfg(feature = "bybit")]
+			#[cfg(feature = "bybit-http")]
 			Self::Bybit => Box::new(crate::Bybit {
 				client: Client::new_mock(),
-				info_cache: BTreeMap::default(),
+				info_cache: Default::default(),
+				label: None,
+				log_context: None,
+				klines_guard: None,
+				mutation_journal: None,
+				perp_universe: Default::default(),
+				account_type: Default::default(),
 			}),
-			#[cfg(feature = "kucoin")]
+			#[cfg(feature = "kucoin-http")]
 			Self::Kucoin => Box::new(crate::Kucoin {
 				client: Client::new_mock(),
-				info_cache: BTreeMap::default(),
+				info_cache: Default::default(),
+				label: None,
+				log_context: None,
+				klines_guard: None,
+				mutation_journal: None,
 			}),
-			#[cfg(feature = "mexc")]
+			#[cfg(feature = "mexc-http")]
 			Self::Mexc => Box::new(crate::Mexc {
 				client: Client::new_mock(),
-				info_cache: BTreeMap::default(),
+				info_cache: Default::default(),
+				label: None,
+				log_context: None,
+				klines_guard: None,
+				mutation_journal: None,
 			}),
-			_ => unimplemented!(),
+			_ => return Err(not_compiled_in(*self)),
+		})
+	}
+
+	fn all_available() -> Vec<ExchangeName> {
+		vec![
+			#[cfg(feature = "binance-http")]
+			Self::Binance,
+			#[cfg(feature = "bybit-http")]
+			Self::Bybit,
+			#[cfg(feature = "kucoin-http")]
+			Self::Kucoin,
+			#[cfg(feature = "mexc-http")]
+			Self::Mexc,
+		]
+	}
+
+	fn is_available(&self) -> bool {
+		match self {
+			Self::Binance => cfg!(feature = "binance-http"),
+			Self::Bybit => cfg!(feature = "bybit-http"),
+			Self::Kucoin => cfg!(feature = "kucoin-http"),
+			Self::Mexc => cfg!(feature = "mexc-http"),
+			_ => false,
 		}
 	}
 }
+fn not_compiled_in(name: ExchangeName) -> ExchangeError {
+	ExchangeError::Other(eyre!("{name} is not compiled in, enable the `{}-http` feature", name.to_string().to_lowercase()))
+}
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Ticker {
 	pub symbol: Symbol,
-	pub exchange_name: ExchangeName,
+	pub exchange_name: Venue,
+}
+/// Where a [Ticker] trades: one of the exchanges of this crate, or one connected from outside it through [ExchangeImpl](crate::custom::ExchangeImpl).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Venue {
+	Builtin(ExchangeName),
+	/// As [registered](Venue::register).
+	Other(&'static str),
+}
+/// Item of [Exchange::ws_mark_prices].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MarkPriceUpdate {
+	pub pair: Pair,
+	pub mark_price: f64,
+	/// Rate of the upcoming funding, as currently predicted. `None` where the stream doesn't carry it.
+	pub funding_rate: Option<f64>,
+	pub next_funding_time: Option<Timestamp>,
+	pub time: Timestamp,
+}
+/// Forced close of someone's position, as broadcast by the exchange.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Liquidation {
+	pub pair: Pair,
+	/// Side of the position that got liquidated: [Side::Buy] for a long.
+	pub side: Side,
+	/// In base asset.
+	pub qty: f64,
+	pub price: f64,
+	pub time: Timestamp,
+}
+/// Exchange-assigned id of a [Trade].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum TradeId {
+	/// Increasing, and what `from_id` pages through history by.
+	Numeric(u64),
+	/// Bybit linear's uuids, carried as the 128 bits they spell. Have no order.
+	Opaque(u128),
+}
+impl TradeId {
+	/// What can be handed back as a `from_id`.
+	pub fn as_numeric(&self) -> Option<u64> {
+		match self {
+			Self::Numeric(id) => Some(*id),
+			Self::Opaque(_) => None,
+		}
+	}
+}
+impl From<u64> for TradeId {
+	fn from(id: u64) -> Self {
+		Self::Numeric(id)
+	}
+}
+impl std::str::FromStr for TradeId {
+	type Err = eyre::Report;
+
+	/// Decimal, or a hyphenated uuid.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.contains('-') {
+			true => u128::from_str_radix(&s.replace('-', ""), 16).map(Self::Opaque).map_err(|e| eyre!("`{s}` is not a uuid: {e}")),
+			false => s.parse().map(Self::Numeric).map_err(|e| eyre!("`{s}` is not a numeric trade id: {e}")),
+		}
+	}
+}
+
+/// A single public trade, as served by REST history endpoints or unpacked from a [BatchTrades].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trade {
+	/// `None` where the exchange's id didn't parse.
+	pub id: Option<TradeId>,
+	pub time: Timestamp,
+	pub qty_asset: f64,
+	pub price: f64,
+	/// Side of the taker. [Side::Sell] when the buyer was the resting order (Binance's `m`/`isBuyerMaker`). `None` where the exchange doesn't say.
+	pub side: Option<Side>,
+}
+impl Trade {
+	/// `qty_asset` signed by the taker: positive for market buys, negative for market sells. Sums to the delta over a window. `None` if the side is unknown.
+	pub fn signed_qty(&self) -> Option<f64> {
+		self.side.map(|side| match side {
+			Side::Buy => self.qty_asset,
+			Side::Sell => -self.qty_asset,
+		})
+	}
 }
 /// Batched trade stream event. All trades share `prec`.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct BatchTrades {
 	prec: PrecisionPriceQty,
 	trades: Vec<InnerTrade>,
@@ -328,6 +1322,17 @@ impl BatchTrades {
 	pub fn iter(&self) -> impl Iterator<Item = (Timestamp, i32, u32)> + '_ {
 		self.trades.iter().map(|t| (t.time, t.price, t.qty))
 	}
+
+	/// Unpacks into [Trade]s, carrying the id and taker side where the stream has them.
+	pub fn trades(&self) -> impl Iterator<Item = Trade> + '_ {
+		self.trades.iter().map(|t| Trade {
+			id: t.id,
+			time: t.time,
+			qty_asset: Qty::new(t.qty, self.prec.qty).as_f64(),
+			price: Price::new(t.price, self.prec.price).as_f64(),
+			side: t.side,
+		})
+	}
 }
 
 impl Timestamped for BatchTrades {
@@ -344,93 +1349,83 @@ impl Timestamped for BatchTrades {
 	}
 }
 
-/// Internal trait for exchange implementations.
-/// Exchange implementations should implement this trait, not `Exchange` directly.
-///
-/// Each **private** method allows to specify `recv_window`.
-///
-/// # Other
-/// - has too many methods, so for dev purposes most default to `unimplemented!()`.
-#[async_trait::async_trait]
-pub(crate) trait ExchangeImpl: std::fmt::Debug + Send + Sync + std::ops::Deref<Target = Client> + std::ops::DerefMut {
-	fn name(&self) -> ExchangeName;
-	fn info_cache_mut(&mut self) -> &mut BTreeMap<Instrument, ExchangeInfo>;
-
-	// Config {{{
-	fn auth(&mut self, pubkey: String, secret: SecretString);
-	/// Set number of **milliseconds** the request is valid for. Recv Window of over a minute does not make sense, thus it's expressed as u16.
-	///
-	/// **WARNING:** This sets a global default and should only be used as a crutch when you can't pass `recv_window` per-request.
-	/// Prefer using the `recv_window` parameter in individual method calls instead.
-	fn set_recv_window(&mut self, recv_window: std::time::Duration);
-	/// Get the default recv_window configured for this exchange, if any.
-	fn default_recv_window(&self) -> Option<std::time::Duration>;
-	//,}}}
-
-	//Q: do we actually want to return a `MethodNotSupported` error, or should we just `unimplemented!()`?
-
-	#[allow(unused_variables)]
-	async fn exchange_info(&self, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument)))
-	}
-
-	//? should I have Self::Pair too? Like to catch the non-existent ones immediately? Although this would increase the error surface on new listings.
-	#[allow(unused_variables)]
-	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument)))
-	}
-
-	/// If no pairs are specified, returns for all;
-	#[allow(unused_variables)]
-	async fn prices(&self, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument)))
-	}
-
-	#[allow(unused_variables)]
-	/// NB: not perf-critical, so literally just calls `prices`, incurring cost of making a vec and a BTreeMap for no reason
-	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64> {
-		self.prices(Some(vec![symbol.pair]), symbol.instrument).await.map(|m| m[&symbol.pair])
+/// `(bid_qty - ask_qty) / (bid_qty + ask_qty)` over the top `depth` levels of each side. In `[-1, 1]`, positive when bids dominate; `0.` for an empty book.
+pub fn depth_imbalance(book: &BookShape, depth: u32) -> f64 {
+	let bid_qty: u64 = book.bids.iter().rev().take(depth as usize).map(|(_, q)| *q as u64).sum();
+	let ask_qty: u64 = book.asks.iter().take(depth as usize).map(|(_, q)| *q as u64).sum();
+	match bid_qty + ask_qty {
+		0 => 0.,
+		total => (bid_qty as f64 - ask_qty as f64) / total as f64,
 	}
+}
 
-	/// Get Open Interest data
-	/// in output vec: greater the index, fresher the data
-	#[allow(unused_variables)]
-	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Vec<OpenInterest>> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument)))
+/// See [Exchange::depth_imbalance_stream].
+struct DepthImbalanceStream {
+	fetch: BookSnapshotFetcher,
+	depth: u32,
+	interval: tokio::time::Interval,
+}
+impl std::fmt::Debug for DepthImbalanceStream {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("DepthImbalanceStream").field("depth", &self.depth).field("interval", &self.interval.period()).finish_non_exhaustive()
 	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for DepthImbalanceStream {
+	type Item = f64;
 
-	// Authenticated {{{
-	#[allow(unused_variables)]
-	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument)))
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		self.interval.tick().await;
+		let book = (self.fetch)().await.map_err(|e| WsError::Other(eyre::Report::new(e)))?;
+		Ok(vec![depth_imbalance(&book, self.depth)])
 	}
-	//,}}}
-
-	//? potentially `total_balance`? Would return precompiled USDT-denominated balance of a (bybit::wallet/binance::account)
-	// balances are defined for each margin type: [futures_balance, spot_balance, margin_balance], but note that on some exchanges, (like bybit), some of these may point to the same exact call
-	// to negate confusion could add a `total_balance` endpoint
-
-	//? could implement many things that are _explicitly_ combinatorial. I can imagine several cases, where knowing that say the specified limit for the klines is wayyy over the max and that you may be opting into a long wait by calling it, could be useful.
+}
 
-	// Websocket {{{
-	// Start a websocket connection for individual trades
-	#[allow(unused_variables)]
-	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument)))
+/// See [Exchange::basis_stream].
+struct BasisStream {
+	fetch: BasisFetcher,
+	pair: Pair,
+	interval: tokio::time::Interval,
+}
+impl std::fmt::Debug for BasisStream {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("BasisStream").field("pair", &self.pair).field("interval", &self.interval.period()).finish_non_exhaustive()
 	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for BasisStream {
+	type Item = f64;
 
-	/// Start a websocket connection for orderbook depth updates (max depth only).
-	#[allow(unused_variables)]
-	async fn ws_book(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
-		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument)))
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		self.interval.tick().await;
+		let (spot, mark) = (self.fetch)().await.map_err(|e| WsError::Other(eyre::Report::new(e)))?;
+		Ok(vec![spot - mark])
 	}
-	//,}}}
 }
-#[derive(Clone, Debug, Default)]
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub(crate) struct InnerTrade {
+	pub id: Option<TradeId>,
 	pub time: Timestamp,
 	pub price: i32,
 	pub qty: u32,
+	/// Of the taker.
+	pub side: Option<Side>,
+}
+
+/// Spot price and perp mark of `pair`, fetched concurrently; what [basis](Exchange::basis) and [basis_pct](Exchange::basis_pct) are computed from.
+async fn spot_and_mark<E: Exchange + ?Sized>(ex: &E, pair: Pair) -> ExchangeResult<(f64, f64)> {
+	futures_util::future::try_join(ex.price(Symbol::new(pair, Instrument::Spot)), ex.mark_price(Symbol::new(pair, Instrument::Perp))).await
+}
+
+fn guard_klines(guard: Option<KlinesGuard>, klines: Klines) -> ExchangeResult<Klines> {
+	if let Some(guard) = guard {
+		let quality = klines.quality_report();
+		if quality.is_degenerate(&guard) {
+			return Err(ExchangeError::DataQuality(quality));
+		}
+	}
+	Ok(klines)
 }
 
 /// Validates recv_window parameters and warns if using global default.
@@ -451,20 +1446,60 @@ fn validate_recv_window(recv_window: Option<std::time::Duration>, default_recv_w
 	}
 
 	if recv_window.is_none() && default_recv_window.is_some() {
-		tracing::warn!("called without recv_window, using global default (not recommended)");
+		tracing::warn!(target: "v_exchanges", "called without recv_window, using global default (not recommended)");
 	}
 
 	Ok(())
 }
 
+/// Attaches the account label (if any) to the error, so that failures from several accounts on one exchange can be told apart.
+fn with_account_ctx<T>(label: Option<&AccountLabel>, r: ExchangeResult<T>) -> ExchangeResult<T> {
+	match label {
+		Some(label) => r.map_err(|e| AccountError::new(label.clone(), Box::new(e)).into()),
+		None => r,
+	}
+}
+
+/// Span every [Exchange] call runs in. `context` and `account` are only recorded when set on the client.
+fn call_span<E: ExchangeImpl + ?Sized>(exchange: &E, method: &'static str) -> tracing::Span {
+	let span = tracing::info_span!(target: "v_exchanges", "exchange", exchange = %ExchangeImpl::name(exchange), method, context = tracing::field::Empty, account = tracing::field::Empty);
+	if let Some(context) = exchange.log_context() {
+		span.record("context", context);
+	}
+	if let Some(label) = ExchangeImpl::label(exchange) {
+		span.record("account", tracing::field::display(label));
+	}
+	span
+}
+
 /// Blanket impl: any type implementing ExchangeImpl automatically gets Exchange.
 /// This enforces that Exchange can only be implemented within this crate (since ExchangeImpl is pub(crate)).
 #[async_trait::async_trait]
-impl<T: ExchangeImpl> Exchange for T {
-	fn name(&self) -> ExchangeName {
+impl<T: ExchangeImpl + 'static> Exchange for T {
+	fn name(&self) -> Venue {
 		ExchangeImpl::name(self)
 	}
 
+	fn as_any(&self) -> &dyn std::any::Any {
+		self
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+		self
+	}
+
+	fn label(&self) -> Option<&AccountLabel> {
+		ExchangeImpl::label(self)
+	}
+
+	fn set_label(&mut self, label: AccountLabel) {
+		*self.label_mut() = Some(label);
+	}
+
+	fn set_log_context(&mut self, context: Option<String>) {
+		*self.log_context_mut() = context;
+	}
+
 	fn auth(&mut self, pubkey: String, secret: SecretString) {
 		ExchangeImpl::auth(self, pubkey, secret)
 	}
@@ -489,40 +1524,229 @@ impl<T: ExchangeImpl> Exchange for T {
 		self.http_client_mut().config.cache_testnet_calls = duration;
 	}
 
+	fn set_dry_run(&mut self, b: bool) {
+		self.http_client_mut().config.dry_run = b;
+	}
+
+	fn set_reject_degenerate_klines(&mut self, guard: Option<KlinesGuard>) {
+		*self.klines_guard_mut() = guard;
+	}
+
+	fn set_mutation_journal(&mut self, journal: Option<Arc<dyn MutationJournal>>) {
+		*self.mutation_journal_mut() = journal;
+	}
+
 	async fn exchange_info(&mut self, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
-		let info = ExchangeImpl::exchange_info(self, instrument).await?;
-		self.info_cache_mut().insert(instrument, info.clone());
-		Ok(info)
+		Ok((*fetch_exchange_info(self, instrument).await?).clone())
+	}
+
+	async fn pair_info(&self, symbol: Symbol) -> ExchangeResult<PairInfo> {
+		fresh_pair_info(self, symbol).await
+	}
+
+	async fn min_order_size(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		let exchange = ExchangeImpl::name(self);
+		fresh_pair_info(self, symbol)
+			.await?
+			.min_qty
+			.ok_or_else(|| ExchangeError::Other(eyre!("{exchange} doesn't report a minimum order qty for {symbol}")))
+	}
+
+	async fn validate_order_qty(&self, symbol: Symbol, qty: f64) -> ExchangeResult<()> {
+		match fresh_pair_info(self, symbol).await?.min_qty {
+			Some(min) if qty < min => Err(OrderValidationError {
+				violations: vec![OrderViolation::QtyBelowMin { qty, min }],
+			}
+			.into()),
+			_ => Ok(()),
+		}
+	}
+
+	async fn all_pairs(&self, instrument: Instrument) -> ExchangeResult<Vec<Pair>> {
+		let span = call_span(self, "all_pairs");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::all_pairs(self, instrument).instrument(span).await)
 	}
 
 	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
-		ExchangeImpl::klines(self, symbol, tf, range).await
+		let span = call_span(self, "klines");
+		let r = ExchangeImpl::klines(self, symbol, tf, range).instrument(span).await.and_then(|k| guard_klines(ExchangeImpl::klines_guard(self), k));
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn klines_kind(&self, symbol: Symbol, tf: Timeframe, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines> {
+		let span = call_span(self, "klines_kind");
+		let r = ExchangeImpl::klines_kind(self, symbol, tf, range, kind)
+			.instrument(span)
+			.await
+			.and_then(|k| guard_klines(ExchangeImpl::klines_guard(self), k));
+		with_account_ctx(ExchangeImpl::label(self), r)
 	}
 
 	async fn prices(&self, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
-		ExchangeImpl::prices(self, pairs, instrument).await
+		let span = call_span(self, "prices");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::prices(self, pairs, instrument).instrument(span).await)
 	}
 
 	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64> {
-		ExchangeImpl::price(self, symbol).await
+		let span = call_span(self, "price");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::price(self, symbol).instrument(span).await)
+	}
+
+	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<OpenInterestHistory> {
+		let span = call_span(self, "open_interest");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::open_interest(self, symbol, tf, range).instrument(span).await)
+	}
+
+	async fn funding_countdown(&self, symbol: Symbol) -> ExchangeResult<(std::time::Duration, f64)> {
+		let span = call_span(self, "funding_countdown");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::funding_countdown(self, symbol).instrument(span).await)
 	}
 
-	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Vec<OpenInterest>> {
-		ExchangeImpl::open_interest(self, symbol, tf, range).await
+	async fn mark_price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		let span = call_span(self, "mark_price");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::mark_price(self, symbol).instrument(span).await)
+	}
+
+	async fn perpetual_universe(&self) -> ExchangeResult<BTreeMap<Pair, PerpInfo>> {
+		if let Some(cached) = self.perp_universe_cache().and_then(PerpUniverseCache::get) {
+			return Ok(cached);
+		}
+		let span = call_span(self, "perpetual_universe");
+		let universe = with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::perpetual_universe(self).instrument(span).await)?;
+		if let Some(cache) = self.perp_universe_cache() {
+			cache.set(universe.clone());
+		}
+		Ok(universe)
+	}
+
+	async fn basis_stream(&self, pair: Pair, interval: std::time::Duration) -> ExchangeResult<Box<dyn ExchangeStream<Item = f64>>> {
+		let span = call_span(self, "basis_stream");
+		let fetch = with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::basis_fetcher(self, pair).instrument(span).await)?;
+		let mut interval = tokio::time::interval(interval);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		Ok(Box::new(BasisStream { fetch, pair, interval }))
 	}
 
 	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
 		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
-		ExchangeImpl::personal_info(self, instrument, recv_window).await
+		let span = call_span(self, "personal_info");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::personal_info(self, instrument, recv_window).instrument(span).await)
+	}
+
+	async fn fees(&self, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		let span = call_span(self, "fees");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::fees(self, symbol, recv_window).instrument(span).await)
+	}
+
+	async fn positions(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		let span = call_span(self, "positions");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::positions(self, instrument, recv_window).instrument(span).await)
+	}
+
+	async fn key_permissions(&self) -> ExchangeResult<KeyPermissions> {
+		let span = call_span(self, "key_permissions");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::key_permissions(self).instrument(span).await)
+	}
+
+	async fn account_type(&self) -> ExchangeResult<AccountType> {
+		let span = call_span(self, "account_type");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::account_type(self).instrument(span).await)
+	}
+
+	async fn place_order(&self, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		let span = call_span(self, "place_order");
+		let journal = ExchangeImpl::mutation_journal(self).map(|j| (j, MutationIntent::place_order(ExchangeImpl::name(self), symbol, &order)));
+		let r = journaled(journal, ExchangeImpl::place_order(self, symbol, order, recv_window)).instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn order_status(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		let span = call_span(self, "order_status");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::order_status(self, symbol, order_id, recv_window).instrument(span).await)
+	}
+
+	async fn get_order(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		let span = call_span(self, "get_order");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::get_order(self, symbol, order_id, recv_window).instrument(span).await)
+	}
+
+	async fn get_orders(&self, instrument: Instrument, pair: Option<Pair>, status: Option<OrderStatus>, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<OrderDetails>> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		let span = call_span(self, "get_orders");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::get_orders(self, instrument, pair, status, recv_window).instrument(span).await)
+	}
+
+	async fn amend_order(
+		&self,
+		order_id: OrderId,
+		symbol: Symbol,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<OrderPlaced> {
+		validate_recv_window(recv_window, ExchangeImpl::default_recv_window(self))?;
+		if new_qty.is_none() && new_price.is_none() {
+			return Err(AmendError::new_nothing_to_amend().into());
+		}
+		let span = call_span(self, "amend_order");
+		let journal = ExchangeImpl::mutation_journal(self).map(|j| (j, MutationIntent::amend_order(ExchangeImpl::name(self), symbol, &order_id, new_qty, new_price)));
+		let r = journaled(journal, ExchangeImpl::amend_order(self, order_id, symbol, new_qty, new_price, recv_window)).instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn order_book_snapshot(&mut self, symbol: Symbol) -> ExchangeResult<BookShape> {
+		let span = call_span(self, "order_book_snapshot");
+		let r = async { (ExchangeImpl::book_snapshot_fetcher(self, symbol).await?)().await }.instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn depth_imbalance_stream(&mut self, symbol: Symbol, depth: u32, interval: std::time::Duration) -> ExchangeResult<Box<dyn ExchangeStream<Item = f64>>> {
+		let span = call_span(self, "depth_imbalance_stream");
+		// `&mut self` call must finish before the label is borrowed
+		let r = ExchangeImpl::book_snapshot_fetcher(self, symbol).instrument(span).await;
+		let fetch = with_account_ctx(ExchangeImpl::label(self), r)?;
+		let mut interval = tokio::time::interval(interval);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		Ok(Box::new(DepthImbalanceStream { fetch, depth, interval }))
 	}
 
 	// Websocket connections are NOT rate-limited by the semaphore
 	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
-		ExchangeImpl::ws_trades(self, pairs, instrument).await
+		let span = call_span(self, "ws_trades");
+		// `&mut self` call must finish before the label is borrowed
+		let r = ExchangeImpl::ws_trades(self, pairs, instrument).instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
 	}
 
 	async fn ws_book(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
-		ExchangeImpl::ws_book(self, pairs, instrument).await
+		let span = call_span(self, "ws_book");
+		// `&mut self` call must finish before the label is borrowed
+		let r = ExchangeImpl::ws_book(self, pairs, instrument).instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn ws_trades_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		let span = call_span(self, "ws_trades_resume");
+		// `&mut self` call must finish before the label is borrowed
+		let r = ExchangeImpl::ws_trades_resume(self, instrument, snapshot).instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn ws_book_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
+		let span = call_span(self, "ws_book_resume");
+		// `&mut self` call must finish before the label is borrowed
+		let r = ExchangeImpl::ws_book_resume(self, instrument, snapshot).instrument(span).await;
+		with_account_ctx(ExchangeImpl::label(self), r)
+	}
+
+	async fn ws_mark_prices(&self, pairs: &[Pair]) -> ExchangeResult<Box<dyn ExchangeStream<Item = MarkPriceUpdate>>> {
+		let span = call_span(self, "ws_mark_prices");
+		with_account_ctx(ExchangeImpl::label(self), ExchangeImpl::ws_mark_prices(self, pairs).instrument(span).await)
 	}
 }
 
@@ -540,6 +1764,38 @@ impl Iterator for Klines {
 		self.v.pop_front()
 	}
 }
+/// One line, no matter the length. For every kline, go through [Debug].
+impl std::fmt::Display for Klines {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.summary().fmt(f)
+	}
+}
+impl std::fmt::Display for KlinesSummary {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} x {}", self.count, self.tf)?;
+		if let (Some(range), Some(first_close), Some(last_close), Some(min), Some(max)) = (&self.time_range, self.first_close, self.last_close, self.min, self.max) {
+			write!(
+				f,
+				" [{}, {}): close {first_close} -> {last_close}, low {min}, high {max}, volume_quote {}",
+				range.start, range.end, self.volume_quote
+			)?;
+		}
+		Ok(())
+	}
+}
+//,}}}
+
+// AccountLabel {{{
+impl From<&str> for AccountLabel {
+	fn from(s: &str) -> Self {
+		Self(s.to_owned())
+	}
+}
+impl From<String> for AccountLabel {
+	fn from(s: String) -> Self {
+		Self(s)
+	}
+}
 //,}}}
 
 // RequestRange {{{
@@ -556,6 +1812,13 @@ impl From<Timestamp> for RequestRange {
 		RequestRange::Span { since: value, until: None }
 	}
 }
+impl TryFrom<std::time::Duration> for RequestRange {
+	type Error = RequestRangeError;
+
+	fn try_from(duration: std::time::Duration) -> Result<Self, Self::Error> {
+		Self::from_duration_before_now(duration)
+	}
+}
 impl From<jiff::Span> for RequestRange {
 	fn from(time_delta: jiff::Span) -> Self {
 		let now = Timestamp::now();
@@ -627,7 +1890,7 @@ impl std::str::FromStr for Ticker {
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
 		let (exchange_str, symbol_str) = s.split_once(':').ok_or_else(|| eyre::eyre!("Invalid ticker format"))?;
-		let exchange_name = ExchangeName::from_str(exchange_str)?;
+		let exchange_name = Venue::from_str(exchange_str)?;
 		let symbol = Symbol::from_str(symbol_str)?;
 
 		Ok(Ticker { symbol, exchange_name })
@@ -635,6 +1898,68 @@ impl std::str::FromStr for Ticker {
 }
 //,}}}
 
+// Venue {{{
+/// Names of the [Venue::Other]s, as registered.
+static OTHER_VENUES: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+
+impl Venue {
+	/// Makes `name` parse as a [Venue], and so be usable in [Ticker]s. Matched case-insensitively, like the built-in names; registering a name twice returns the same venue.
+	pub fn register(name: &'static str) -> Result<Self> {
+		if let Ok(builtin) = ExchangeName::from_str(name) {
+			bail!("`{name}` is taken by the built-in {builtin}");
+		}
+		let mut venues = OTHER_VENUES.write().unwrap();
+		if let Some(registered) = venues.iter().copied().find(|v| v.eq_ignore_ascii_case(name)) {
+			return Ok(Self::Other(registered));
+		}
+		venues.push(name);
+		Ok(Self::Other(name))
+	}
+
+	pub fn builtin(self) -> Option<ExchangeName> {
+		match self {
+			Self::Builtin(name) => Some(name),
+			Self::Other(_) => None,
+		}
+	}
+}
+impl From<ExchangeName> for Venue {
+	fn from(name: ExchangeName) -> Self {
+		Self::Builtin(name)
+	}
+}
+impl PartialEq<ExchangeName> for Venue {
+	fn eq(&self, other: &ExchangeName) -> bool {
+		matches!(self, Self::Builtin(name) if name == other)
+	}
+}
+impl std::fmt::Display for Venue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Builtin(name) => write!(f, "{name}"),
+			Self::Other(name) => f.write_str(name),
+		}
+	}
+}
+impl std::str::FromStr for Venue {
+	type Err = eyre::Report;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Ok(name) = ExchangeName::from_str(s) {
+			return Ok(Self::Builtin(name));
+		}
+		OTHER_VENUES
+			.read()
+			.unwrap()
+			.iter()
+			.copied()
+			.find(|v| v.eq_ignore_ascii_case(s))
+			.map(Self::Other)
+			.ok_or_else(|| eyre::eyre!("`{s}` is neither a built-in exchange nor a registered venue"))
+	}
+}
+//,}}}
+
 // Websocket {{{
 
 //dbg: placeholder, ignore contents
@@ -642,6 +1967,132 @@ impl std::str::FromStr for Ticker {
 //,}}}
 
 mod test {
+	#[test]
+	fn auto_limit_span() {
+		let since = jiff::Timestamp::from_millisecond(0).unwrap();
+		let until = jiff::Timestamp::from_millisecond(150 * 60_000 + 1).unwrap();
+		let range = super::RequestRange::Span { since, until: Some(until) };
+		let tf = super::Timeframe::from("1m");
+		assert_eq!(range.auto_limit(&tf, 1000), 151);
+		assert_eq!(range.auto_limit(&tf, 100), 100);
+	}
+
+	#[cfg(feature = "bybit-http")]
+	#[test]
+	fn append_params_match_serialize() {
+		let since = jiff::Timestamp::from_millisecond(1_000).unwrap();
+		let tf = super::Timeframe::from("1m");
+		for range in [
+			super::RequestRange::Span { since, until: None },
+			super::RequestRange::Span {
+				since,
+				until: Some(jiff::Timestamp::from_millisecond(600_000).unwrap()),
+			},
+			super::RequestRange::Limit(7),
+		] {
+			let as_value = |params: Vec<(&str, String)>| -> serde_json::Value {
+				serde_json::Value::Object(params.into_iter().map(|(k, v)| (k.to_owned(), serde_json::json!(v.parse::<i64>().unwrap()))).collect())
+			};
+			let mut out = Vec::new();
+			range.append_params(super::ExchangeName::Bybit, &mut out);
+			assert_eq!(as_value(out), range.serialize(super::ExchangeName::Bybit));
+
+			let mut out = Vec::new();
+			range.append_exchange_params(super::ExchangeName::Bybit, &tf, &mut out);
+			assert_eq!(as_value(out), range.to_exchange_params(super::ExchangeName::Bybit, &tf));
+		}
+	}
+
+	#[test]
+	fn available_exchanges_init() {
+		use super::ExchangeInit as _;
+		for name in super::ExchangeName::all_available() {
+			assert!(name.is_available());
+			assert_eq!(name.init_mock_client().name(), name);
+		}
+		assert_eq!(
+			[super::ExchangeName::Binance, super::ExchangeName::Bybit, super::ExchangeName::Kucoin, super::ExchangeName::Mexc]
+				.into_iter()
+				.filter(|n| n.is_available())
+				.collect::<Vec<_>>(),
+			super::ExchangeName::all_available()
+		);
+	}
+
+	#[tokio::test]
+	async fn min_order_size_refreshes_stale_info() {
+		use super::Exchange as _;
+		use crate::test_utils::MockExchange;
+
+		let exchange = MockExchange::default();
+		let stale = super::PairInfo { min_qty: Some(1.), ..Default::default() };
+		exchange.info_cache.insert(super::Instrument::Perp, super::ExchangeInfo {
+			server_time: jiff::Timestamp::UNIX_EPOCH,
+			pairs: [(super::Pair::new("BTC", "USDT"), stale)].into(),
+		});
+		assert_eq!(exchange.min_order_size(MockExchange::btc_perp()).await.unwrap(), 0.001);
+
+		exchange.validate_order_qty(MockExchange::btc_perp(), 0.002).await.unwrap();
+		let e = exchange.validate_order_qty(MockExchange::btc_perp(), 0.0005).await.unwrap_err();
+		let super::ExchangeError::OrderValidation(e) = e else { panic!("{e:?}") };
+		assert_eq!(e.violations, vec![super::OrderViolation::QtyBelowMin { qty: 0.0005, min: 0.001 }]);
+		assert!(e.to_string().contains("minimum of 0.001"));
+
+		let eth = super::Symbol::new(super::Pair::new("ETH", "USDT"), super::Instrument::Perp);
+		assert!(matches!(
+			exchange.min_order_size(eth).await,
+			Err(super::ExchangeError::Method(super::MethodError::PairNotListed { .. }))
+		));
+	}
+
+	/// `Exchange` must stay dyn-compatible (a generic method without `where Self: Sized` breaks it), and every client must coerce into and back out of `Box<dyn Exchange>`.
+	#[test]
+	fn exchange_trait_objects() {
+		#[allow(unused_mut)]
+		let mut clients: Vec<Box<dyn super::Exchange>> = Vec::new();
+		#[cfg(feature = "binance-http")]
+		clients.push(Box::new(crate::Binance::default()));
+		#[cfg(feature = "bybit-http")]
+		clients.push(Box::new(crate::Bybit::default()));
+		#[cfg(feature = "kucoin-http")]
+		clients.push(Box::new(crate::Kucoin::default()));
+		#[cfg(feature = "mexc-http")]
+		clients.push(Box::new(crate::Mexc::default()));
+		for client in &mut clients {
+			#[cfg(feature = "binance-http")]
+			assert_eq!(super::downcast_ref::<crate::Binance>(&**client).is_some(), client.name() == super::ExchangeName::Binance);
+			#[cfg(feature = "bybit-http")]
+			assert_eq!(super::downcast_mut::<crate::Bybit>(&mut **client).is_some(), client.name() == super::ExchangeName::Bybit);
+		}
+	}
+
+	#[test]
+	fn auto_limit_limit() {
+		let tf = super::Timeframe::from("1h");
+		assert_eq!(super::RequestRange::Limit(2000).auto_limit(&tf, 1000), 1000);
+		assert_eq!(super::RequestRange::Limit(5).auto_limit(&tf, 1000), 5);
+	}
+
+	#[test]
+	fn perp_universe_cache_expires() {
+		let cache = super::PerpUniverseCache::default();
+		assert!(cache.get().is_none());
+
+		let universe = super::BTreeMap::from([(super::Pair::new("BTC", "USDT"), super::PerpInfo::default())]);
+		cache.clone().set(universe);
+		let fetched_at = cache.0.lock().unwrap().as_ref().unwrap().0;
+		// shared between clones, and served until the TTL is up
+		assert_eq!(cache.get().map(|u| u.len()), Some(1));
+		assert!(cache.get_at(fetched_at + super::PERP_UNIVERSE_TTL - std::time::Duration::from_millis(1)).is_some());
+		assert!(cache.get_at(fetched_at + super::PERP_UNIVERSE_TTL).is_none());
+	}
+
+	#[test]
+	fn duration_before_now_out_of_range() {
+		assert!(super::RequestRange::from_duration_before_now(std::time::Duration::from_secs(3600)).is_ok());
+		assert!(super::RequestRange::try_from(std::time::Duration::MAX).is_err());
+	}
+
 	#[test]
 	fn display() {
 		let symbol = super::Symbol {
@@ -650,7 +2101,7 @@ mod test {
 		};
 		let ticker = super::Ticker {
 			symbol,
-			exchange_name: super::ExchangeName::Bybit,
+			exchange_name: super::ExchangeName::Bybit.into(),
 		};
 		assert_eq!(ticker.to_string(), "bybit:BTC-USDT.P");
 	}
@@ -678,4 +2129,203 @@ mod test {
 		let ticker2: super::Ticker = ticker_str2.parse().unwrap();
 		assert_eq!(ticker2.symbol.instrument, super::Instrument::PerpInverse);
 	}
+
+	#[test]
+	fn registered_venues_parse() {
+		assert!("toyex:BTC-USDT.P".parse::<super::Ticker>().is_err());
+		let venue = super::Venue::register("toyex").unwrap();
+		assert_eq!(super::Venue::register("ToyEx").unwrap(), venue);
+		assert!(super::Venue::register("bybit").is_err());
+
+		let ticker: super::Ticker = "TOYEX:BTC-USDT.P".parse().unwrap();
+		assert_eq!(ticker.exchange_name, super::Venue::Other("toyex"));
+		assert_eq!(ticker.to_string(), "toyex:BTC-USDT.P");
+	}
+
+	#[test]
+	fn fee_expected_cost() {
+		let fees = super::FeeSchedule {
+			maker_rate: -0.0001,
+			taker_rate: 0.0005,
+			..Default::default()
+		};
+		assert_eq!(fees.expected_cost(10_000., false), 5.);
+		assert_eq!(fees.expected_cost(-10_000., false), 5.);
+		assert_eq!(fees.expected_cost(10_000., true), -1.);
+	}
+
+	#[test]
+	fn convert_routes() {
+		let prices: super::BTreeMap<super::Pair, f64> = [
+			(super::Pair::new("BTC", "USDT"), 60_000.),
+			(super::Pair::new("ETH", "BTC"), 0.05),
+			(super::Pair::new("USDC", "USDT"), 1.0002),
+			(super::Pair::new("SOL", "ETH"), 0.05),
+		]
+		.into_iter()
+		.collect();
+		let at = jiff::Timestamp::UNIX_EPOCH;
+		let convert = |amount: f64, from: &str, to: &str| super::convert_with(&prices, at, amount, from.into(), to.into());
+
+		let direct = convert(2., "BTC", "USDT").unwrap();
+		assert_eq!(direct.value, 120_000.);
+		assert_eq!(direct.route, vec![super::Pair::new("BTC", "USDT")]);
+
+		let inverse = convert(120_000., "USDT", "BTC").unwrap();
+		assert_eq!(inverse.value, 2.);
+
+		let two_hops = convert(1., "ETH", "USDT").unwrap();
+		assert_eq!(two_hops.value, 3_000.);
+		assert_eq!(two_hops.route, vec![super::Pair::new("ETH", "BTC"), super::Pair::new("BTC", "USDT")]);
+
+		let through_usdt = convert(60_000., "BTC", "USDC").unwrap();
+		assert_eq!(through_usdt.route, vec![super::Pair::new("BTC", "USDT"), super::Pair::new("USDC", "USDT")]);
+
+		assert_eq!(convert(5., "USDT", "USDT").unwrap().route, vec![]);
+
+		// SOL→ETH→BTC→USDT is three hops
+		let err = convert(1., "SOL", "USDT").unwrap_err().to_string();
+		assert!(err.contains("SOL→ETH→USDT"), "{err}");
+	}
+
+	#[cfg(test)]
+	fn klines(closes: &[f64]) -> super::Klines {
+		let kline = |i: usize, close: f64| v_utils::trades::Kline {
+			open_time: jiff::Timestamp::from_second(i as i64 * 60).unwrap(),
+			ohlc: v_utils::trades::Ohlc {
+				open: close,
+				high: close + 1.,
+				low: close - 1.,
+				close,
+			},
+			volume_quote: 10.,
+			trades: None,
+			taker_buy_volume_quote: None,
+		};
+		super::Klines::new(closes.iter().enumerate().map(|(i, c)| kline(i, *c)).collect(), super::Timeframe::from("1m"))
+	}
+
+	#[test]
+	fn klines_summary() {
+		let k = klines(&[100., 90., 120., 110.]);
+		let summary = k.summary();
+		assert_eq!(summary.count, 4);
+		assert_eq!(summary.time_range, Some(jiff::Timestamp::UNIX_EPOCH..jiff::Timestamp::from_second(240).unwrap()));
+		assert_eq!((summary.first_close, summary.last_close), (Some(100.), Some(110.)));
+		assert_eq!((summary.min, summary.max), (Some(89.), Some(121.)));
+		assert_eq!(summary.volume_quote, 40.);
+		assert_eq!(
+			k.to_string(),
+			"4 x 1m [1970-01-01T00:00:00Z, 1970-01-01T00:04:00Z): close 100 -> 110, low 89, high 121, volume_quote 40"
+		);
+		assert_eq!(klines(&[]).to_string(), "0 x 1m");
+	}
+
+	#[test]
+	fn klines_slice_boundaries() {
+		let n = 6;
+		let k = klines(&[1.; 6]);
+		let at = |s: i64| jiff::Timestamp::from_second(s).unwrap();
+		// every pair of bounds, on and between candle opens
+		for start in (-30..=n as i64 * 60 + 30).step_by(30) {
+			for end in (-30..=n as i64 * 60 + 30).step_by(30) {
+				let slice = k.slice(at(start)..at(end));
+				let expected: Vec<_> = k.v.iter().filter(|kl| kl.open_time >= at(start) && kl.open_time < at(end)).map(|kl| kl.open_time).collect();
+				assert_eq!(slice.v.iter().map(|kl| kl.open_time).collect::<Vec<_>>(), expected, "{start}..{end}");
+				assert_eq!(slice.tf, k.tf);
+			}
+		}
+		// adjacent slices partition the series
+		let (a, b) = (k.slice(at(0)..at(120)), k.slice(at(120)..at(n as i64 * 60)));
+		assert_eq!(a.len() + b.len(), n);
+		assert_eq!(b.v.front().unwrap().open_time, at(120));
+	}
+
+	#[test]
+	fn klines_returns() {
+		let r = klines(&[100., 110., 99.]).returns();
+		assert_eq!(r.len(), 2);
+		assert!((r[0] - 1.1f64.ln()).abs() < 1e-12);
+		assert!((r[0] + r[1] - 0.99f64.ln()).abs() < 1e-12, "log returns add up");
+		assert!(klines(&[100.]).returns().is_empty());
+	}
+
+	#[test]
+	fn klines_vwap() {
+		// typical price is the close: high and low are symmetric around it
+		let mut k = klines(&[100., 110., 130., 120.]);
+		k.v[2].volume_quote = 30.;
+
+		let cumulative = k.vwap(None);
+		assert_eq!(cumulative[0], 100.);
+		assert!((cumulative[2] - (1000. + 1100. + 3900.) / 50.).abs() < 1e-9);
+		assert!((cumulative[3] - 7200. / 60.).abs() < 1e-9);
+
+		let rolling = k.vwap(Some(2));
+		assert!(rolling[0].is_nan());
+		assert!((rolling[1] - 105.).abs() < 1e-9);
+		assert!((rolling[2] - 125.).abs() < 1e-9);
+		assert!((rolling[3] - 127.5).abs() < 1e-9);
+		assert!(k.vwap(Some(5)).iter().all(|v| v.is_nan()));
+
+		k.v.iter_mut().for_each(|kl| kl.volume_quote = 0.);
+		assert!(k.vwap(None).iter().all(|v| v.is_nan()), "no volume, no price");
+
+		// a kline dwarfing the rest doesn't take them with it on leaving the window
+		k.v.iter_mut().for_each(|kl| kl.volume_quote = 10.);
+		k.v[0].volume_quote = 1e18;
+		let rolling = k.vwap(Some(2));
+		assert!((rolling[2] - 120.).abs() < 1e-9, "{}", rolling[2]);
+		assert!((rolling[3] - 125.).abs() < 1e-9, "{}", rolling[3]);
+	}
+
+	#[test]
+	fn klines_tvwap() {
+		let mut k = klines(&[100., 110., 130.]);
+		assert!(k.tvwap(None).iter().zip(k.vwap(None)).all(|(t, v)| (t - v).abs() < 1e-9), "same on gapless data");
+
+		// 130 comes in after a 3m gap, standing in for three candles
+		k.v[2].open_time = jiff::Timestamp::from_second(240).unwrap();
+		let tvwap = k.tvwap(Some(2));
+		assert!(tvwap[0].is_nan());
+		assert!((tvwap[2] - (110. + 3. * 130.) / 4.).abs() < 1e-9);
+	}
+
+	#[test]
+	fn asset_balance_with_usd_price() {
+		let eth = super::AssetBalance::new("ETH".into(), 1.5, None).with_usd_price(2_000.);
+		assert_eq!(eth.usd.map(|u| *u), Some(3_000.));
+		assert_eq!(eth.underlying, 1.5);
+	}
+
+	#[test]
+	fn open_interest_history() {
+		let at = |s: i64| jiff::Timestamp::from_second(s).unwrap();
+		let usdt = super::Pair::new("BTC", "USDT");
+		let history: super::OpenInterestHistory = [(10., Some(1_000.), 0), (30., None, 60), (20., Some(2_000.), 120)]
+			.into_iter()
+			.map(|(val_asset, val_quote, t)| {
+				super::OpenInterest {
+					val_asset,
+					val_quote,
+					timestamp: at(t),
+					..Default::default()
+				}
+				.with_usd(usdt)
+			})
+			.collect();
+
+		let avg = history.average().unwrap();
+		assert_eq!(avg.val_asset, 20.);
+		assert_eq!(avg.val_quote, Some(1_500.));
+		assert_eq!(avg.val_usd.map(|u| *u), Some(1_500.));
+		assert_eq!(avg.marketcap, None);
+		assert_eq!(avg.timestamp, at(120));
+		assert_eq!(history.max().unwrap().val_asset, 30.);
+		assert_eq!(history.min().unwrap().val_asset, 10.);
+		assert!(super::OpenInterestHistory::default().average().is_none());
+
+		let btc_quoted = super::OpenInterest { val_quote: Some(1.), ..Default::default() }.with_usd(super::Pair::new("ETH", "BTC"));
+		assert!(btc_quoted.val_usd.is_none());
+	}
 }
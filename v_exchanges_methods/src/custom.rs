@@ -0,0 +1,215 @@
+//! Connecting an exchange from outside this crate: implement [ExchangeImpl] on a client wrapper, and it gets [Exchange] with everything built on top of it. See `examples/custom_exchange.rs` for one end-to-end.
+//!
+//! Kept out of the prelude, as having both traits in scope makes their shared methods ambiguous on concrete exchanges.
+use adapters::{Client, generics::ws::SubscriptionSnapshot};
+use futures_util::future::BoxFuture;
+use secrecy::SecretString;
+
+use crate::prelude::*;
+
+/// What an exchange implements; [Exchange] then comes from a blanket impl, which adds the tracing span, account label and `recv_window` checks around every call.
+///
+/// Implement this, not `Exchange` directly. Anything not overridden fails with [MethodNotSupported](MethodError::MethodNotSupported).
+///
+/// Each **private** method allows to specify `recv_window`.
+#[async_trait::async_trait]
+pub trait ExchangeImpl: std::fmt::Debug + Send + Sync + std::ops::Deref<Target = Client> + std::ops::DerefMut {
+	/// A [Venue::Builtin] for the exchanges of this crate, a [registered](Venue::register) [Venue::Other] for anything else.
+	fn name(&self) -> Venue;
+	fn info_cache(&self) -> &InfoCache;
+	fn label(&self) -> Option<&AccountLabel>;
+	fn label_mut(&mut self) -> &mut Option<AccountLabel>;
+	fn log_context(&self) -> Option<&str>;
+	fn log_context_mut(&mut self) -> &mut Option<String>;
+	fn klines_guard(&self) -> Option<KlinesGuard>;
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard>;
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>>;
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>>;
+	/// Where [perpetual_universe](ExchangeImpl::perpetual_universe) results are kept. `None` disables caching.
+	fn perp_universe_cache(&self) -> Option<&PerpUniverseCache> {
+		None
+	}
+
+	// Config {{{
+	fn auth(&mut self, pubkey: String, secret: SecretString);
+	/// Set number of **milliseconds** the request is valid for. Recv Window of over a minute does not make sense, thus it's expressed as u16.
+	///
+	/// **WARNING:** This sets a global default and should only be used as a crutch when you can't pass `recv_window` per-request.
+	/// Prefer using the `recv_window` parameter in individual method calls instead.
+	fn set_recv_window(&mut self, recv_window: std::time::Duration);
+	/// Get the default recv_window configured for this exchange, if any.
+	fn default_recv_window(&self) -> Option<std::time::Duration>;
+	//,}}}
+
+	#[allow(unused_variables)]
+	async fn exchange_info(&self, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "exchange_info")))
+	}
+
+	/// Override where the exchange can list pairs without the full per-pair info.
+	async fn all_pairs(&self, instrument: Instrument) -> ExchangeResult<Vec<Pair>> {
+		Ok(self.exchange_info(instrument).await?.pairs.into_keys().collect())
+	}
+
+	//? should I have Self::Pair too? Like to catch the non-existent ones immediately? Although this would increase the error surface on new listings.
+	#[allow(unused_variables)]
+	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "klines")))
+	}
+
+	/// Only needs overriding for exchanges exposing mark or index price klines.
+	#[allow(unused_variables)]
+	async fn klines_kind(&self, symbol: Symbol, tf: Timeframe, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines> {
+		match kind {
+			KlineKind::LastPrice => self.klines(symbol, tf, range).await,
+			KlineKind::MarkPrice | KlineKind::IndexPrice => Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "klines_kind"))),
+		}
+	}
+
+	/// If no pairs are specified, returns for all;
+	#[allow(unused_variables)]
+	async fn prices(&self, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "prices")))
+	}
+
+	#[allow(unused_variables)]
+	/// NB: not perf-critical, so literally just calls `prices`, incurring cost of making a vec and a BTreeMap for no reason
+	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		self.prices(Some(vec![symbol.pair]), symbol.instrument).await.map(|m| m[&symbol.pair])
+	}
+
+	/// Get Open Interest data
+	/// in output vec: greater the index, fresher the data
+	#[allow(unused_variables)]
+	async fn open_interest(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<OpenInterestHistory> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "open_interest")))
+	}
+
+	async fn funding_countdown(&self, symbol: Symbol) -> ExchangeResult<(std::time::Duration, f64)> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "funding_countdown")))
+	}
+
+	async fn mark_price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "mark_price")))
+	}
+
+	async fn perpetual_universe(&self) -> ExchangeResult<BTreeMap<Pair, PerpInfo>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), Instrument::Perp, "perpetual_universe")))
+	}
+
+	/// Fetches `(spot price, perp mark price)` of `pair` without borrowing `self`, so that it can be polled from a stream.
+	#[allow(unused_variables)]
+	async fn basis_fetcher(&self, pair: Pair) -> ExchangeResult<BasisFetcher> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), Instrument::Perp, "basis_fetcher")))
+	}
+
+	// Authenticated {{{
+	#[allow(unused_variables)]
+	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "personal_info")))
+	}
+
+	/// Fee rates applicable to the authenticated account's tier. Exchanges price fees per-symbol, hence not just per-instrument.
+	#[allow(unused_variables)]
+	async fn fees(&self, symbol: Symbol, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "fees")))
+	}
+
+	#[allow(unused_variables)]
+	async fn positions(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "positions")))
+	}
+
+	async fn key_permissions(&self) -> ExchangeResult<KeyPermissions> {
+		// not instrument-specific; `Spot` stands in
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), Instrument::Spot, "key_permissions")))
+	}
+
+	async fn account_type(&self) -> ExchangeResult<AccountType> {
+		// not instrument-specific; `Spot` stands in
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), Instrument::Spot, "account_type")))
+	}
+
+	#[allow(unused_variables)]
+	async fn place_order(&self, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "place_order")))
+	}
+
+	/// Must map "no such order" to [OrderState::NotFound] rather than an error: [Exchange::place_order_idempotent] resubmits on it.
+	#[allow(unused_variables)]
+	async fn order_status(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "order_status")))
+	}
+
+	/// Should only fetch [trades](OrderDetails::trades) for orders that have any fills.
+	#[allow(unused_variables)]
+	async fn get_order(&self, symbol: Symbol, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "get_order")))
+	}
+
+	#[allow(unused_variables)]
+	async fn get_orders(&self, instrument: Instrument, pair: Option<Pair>, status: Option<OrderStatus>, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<OrderDetails>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "get_orders")))
+	}
+
+	/// Modify price and/or qty of an existing open order. `None` leaves the field as is; at least one is guaranteed to be `Some` by the caller.
+	#[allow(unused_variables)]
+	async fn amend_order(
+		&self,
+		order_id: OrderId,
+		symbol: Symbol,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<OrderPlaced> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "amend_order")))
+	}
+	//,}}}
+
+	//? potentially `total_balance`? Would return precompiled USDT-denominated balance of a (bybit::wallet/binance::account)
+	// balances are defined for each margin type: [futures_balance, spot_balance, margin_balance], but note that on some exchanges, (like bybit), some of these may point to the same exact call
+	// to negate confusion could add a `total_balance` endpoint
+
+	//? could implement many things that are _explicitly_ combinatorial. I can imagine several cases, where knowing that say the specified limit for the klines is wayyy over the max and that you may be opting into a long wait by calling it, could be useful.
+
+	/// Fetches [BookShape]s of `symbol` without borrowing `self`, so that it can be polled from a stream. Resolving precisions may need to fill the info cache, hence `&mut`.
+	#[allow(unused_variables)]
+	async fn book_snapshot_fetcher(&mut self, symbol: Symbol) -> ExchangeResult<BookSnapshotFetcher> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), symbol.instrument, "book_snapshot_fetcher")))
+	}
+
+	// Websocket {{{
+	// Start a websocket connection for individual trades
+	#[allow(unused_variables)]
+	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "ws_trades")))
+	}
+
+	/// Start a websocket connection for orderbook depth updates (max depth only).
+	#[allow(unused_variables)]
+	async fn ws_book(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "ws_book")))
+	}
+
+	/// See [Exchange::ws_trades_resume].
+	#[allow(unused_variables)]
+	async fn ws_trades_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "ws_trades_resume")))
+	}
+
+	/// See [Exchange::ws_book_resume].
+	#[allow(unused_variables)]
+	async fn ws_book_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), instrument, "ws_book_resume")))
+	}
+
+	#[allow(unused_variables)]
+	async fn ws_mark_prices(&self, pairs: &[Pair]) -> ExchangeResult<Box<dyn ExchangeStream<Item = MarkPriceUpdate>>> {
+		Err(ExchangeError::Method(MethodError::new_method_not_supported(self.name(), Instrument::Perp, "ws_mark_prices")))
+	}
+	//,}}}
+}
+/// See [ExchangeImpl::book_snapshot_fetcher].
+pub type BookSnapshotFetcher = Box<dyn Fn() -> BoxFuture<'static, ExchangeResult<BookShape>> + Send + Sync>;
+/// See [ExchangeImpl::basis_fetcher].
+pub type BasisFetcher = Box<dyn Fn() -> BoxFuture<'static, ExchangeResult<(f64, f64)>> + Send + Sync>;
@@ -0,0 +1,154 @@
+//! [Klines] to and from `polars`.
+use polars::prelude::*;
+use v_utils::trades::{Kline, Ohlc, Timeframe};
+
+use crate::core::Klines;
+
+/// A column of [Klines::to_dataframe], see there for the types.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KlineField {
+	OpenTime,
+	Open,
+	High,
+	Low,
+	Close,
+	VolumeQuote,
+	Trades,
+}
+impl KlineField {
+	pub const ALL: [Self; 7] = [Self::OpenTime, Self::Open, Self::High, Self::Low, Self::Close, Self::VolumeQuote, Self::Trades];
+
+	pub fn column_name(&self) -> &'static str {
+		match self {
+			Self::OpenTime => "open_time_ms",
+			Self::Open => "open",
+			Self::High => "high",
+			Self::Low => "low",
+			Self::Close => "close",
+			Self::VolumeQuote => "volume_quote",
+			Self::Trades => "trades",
+		}
+	}
+}
+
+impl Klines {
+	/// Oldest first, one row per kline:
+	///
+	/// | column | type |
+	/// |---|---|
+	/// | `open_time_ms` | `Int64` |
+	/// | `open`, `high`, `low`, `close`, `volume_quote` | `Float64` |
+	/// | `trades` | `UInt64`, null where the exchange doesn't count them |
+	///
+	/// `taker_buy_volume_quote` is left out.
+	pub fn to_dataframe(&self) -> DataFrame {
+		let columns = KlineField::ALL.iter().map(|f| self.to_series(*f).into()).collect();
+		DataFrame::new(columns).expect("all columns are built off the same klines, so are of the same length and uniquely named")
+	}
+
+	/// A single column of [to_dataframe](Self::to_dataframe).
+	pub fn to_series(&self, field: KlineField) -> Series {
+		let name = field.column_name().into();
+		let f64s = |get: fn(&Kline) -> f64| self.v.iter().map(get).collect::<Vec<f64>>();
+		match field {
+			KlineField::OpenTime => Series::new(name, self.v.iter().map(|k| k.open_time.as_millisecond()).collect::<Vec<i64>>()),
+			KlineField::Open => Series::new(name, f64s(|k| k.ohlc.open)),
+			KlineField::High => Series::new(name, f64s(|k| k.ohlc.high)),
+			KlineField::Low => Series::new(name, f64s(|k| k.ohlc.low)),
+			KlineField::Close => Series::new(name, f64s(|k| k.ohlc.close)),
+			KlineField::VolumeQuote => Series::new(name, f64s(|k| k.volume_quote)),
+			KlineField::Trades => Series::new(name, self.v.iter().map(|k| k.trades.map(|t| t as u64)).collect::<Vec<Option<u64>>>()),
+		}
+	}
+
+	/// Inverse of [to_dataframe](Self::to_dataframe). Only `trades` may be missing or null; rows are taken in order, `tf` as given.
+	pub fn from_dataframe(df: DataFrame, tf: Timeframe) -> eyre::Result<Self> {
+		let f64s = |field: KlineField| -> eyre::Result<Vec<f64>> {
+			let name = field.column_name();
+			let ca = df.column(name)?.as_materialized_series().cast(&DataType::Float64)?;
+			ca.f64()?.into_iter().map(|v| v.ok_or_else(|| eyre::eyre!("null in `{name}`"))).collect()
+		};
+		let open_times: Vec<i64> = df
+			.column(KlineField::OpenTime.column_name())?
+			.as_materialized_series()
+			.i64()?
+			.into_iter()
+			.map(|v| v.ok_or_else(|| eyre::eyre!("null in `{}`", KlineField::OpenTime.column_name())))
+			.collect::<eyre::Result<_>>()?;
+		let (open, high, low, close, volume_quote) = (f64s(KlineField::Open)?, f64s(KlineField::High)?, f64s(KlineField::Low)?, f64s(KlineField::Close)?, f64s(KlineField::VolumeQuote)?);
+		let trades: Vec<Option<usize>> = match df.column(KlineField::Trades.column_name()) {
+			Ok(c) => c.as_materialized_series().cast(&DataType::UInt64)?.u64()?.into_iter().map(|t| t.map(|t| t as usize)).collect(),
+			Err(_) => vec![None; df.height()],
+		};
+
+		let v = (0..df.height())
+			.map(|i| {
+				Ok(Kline {
+					open_time: jiff::Timestamp::from_millisecond(open_times[i])?,
+					ohlc: Ohlc {
+						open: open[i],
+						high: high[i],
+						low: low[i],
+						close: close[i],
+					},
+					volume_quote: volume_quote[i],
+					trades: trades[i],
+					taker_buy_volume_quote: None,
+				})
+			})
+			.collect::<eyre::Result<_>>()?;
+		Ok(Klines::new(v, tf))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn klines() -> Klines {
+		let kline = |i: i64, close: f64, trades: Option<usize>| Kline {
+			open_time: jiff::Timestamp::from_second(i * 60).unwrap(),
+			ohlc: Ohlc {
+				open: close - 0.5,
+				high: close + 1.,
+				low: close - 1.,
+				close,
+			},
+			volume_quote: 10. * close,
+			trades,
+			taker_buy_volume_quote: None,
+		};
+		Klines::new([kline(0, 100., Some(7)), kline(1, 101., None), kline(2, 99.5, Some(3))].into(), Timeframe::from("1m"))
+	}
+
+	#[test]
+	fn dataframe_round_trip() {
+		let klines = klines();
+		let df = klines.to_dataframe();
+		assert_eq!(df.get_column_names_str(), KlineField::ALL.map(|f| f.column_name()));
+		assert_eq!(df.height(), 3);
+		assert_eq!(df.column("trades").unwrap().null_count(), 1);
+
+		let back = Klines::from_dataframe(df.clone(), klines.tf).unwrap();
+		assert!(back.to_dataframe().equals_missing(&df));
+		assert_eq!(back.v[1].open_time, klines.v[1].open_time);
+	}
+
+	#[test]
+	fn single_series() {
+		let close = klines().to_series(KlineField::Close);
+		assert_eq!(close.name().as_str(), "close");
+		assert_eq!(close.f64().unwrap().into_no_null_iter().collect::<Vec<_>>(), [100., 101., 99.5]);
+		assert_eq!(klines().to_series(KlineField::OpenTime).i64().unwrap().get(1), Some(60_000));
+	}
+
+	#[test]
+	fn trades_are_optional_on_the_way_in() {
+		let df = klines().to_dataframe().drop("trades").unwrap();
+		let back = Klines::from_dataframe(df, Timeframe::from("1m")).unwrap();
+		assert!(back.v.iter().all(|k| k.trades.is_none()));
+
+		let df = klines().to_dataframe().drop("close").unwrap();
+		assert!(Klines::from_dataframe(df, Timeframe::from("1m")).is_err());
+	}
+}
@@ -0,0 +1,177 @@
+//! Dropping what a stream already yielded. A reconnect resubscribes, and exchanges answer that by replaying the tail of the feed: trades that went out just before the drop come again, as do klines up to the forming one.
+use std::hash::Hash;
+
+use adapters::generics::ws::{DedupWindow, LatencyStats, SubscriptionSnapshot, WsError};
+use jiff::SignedDuration;
+use tokio::time::Instant;
+
+use crate::prelude::*;
+
+/// Ids seen within a [DedupWindow], oldest first. What trade connections key on where the exchange assigns ids.
+#[derive(Debug)]
+pub(crate) struct RecentIds<K> {
+	window: DedupWindow,
+	order: VecDeque<(K, Instant)>,
+	seen: HashSet<K>,
+	skipped: u64,
+}
+impl<K: Clone + Eq + Hash> RecentIds<K> {
+	pub(crate) fn new(window: DedupWindow) -> Self {
+		Self {
+			window,
+			order: VecDeque::new(),
+			seen: HashSet::new(),
+			skipped: 0,
+		}
+	}
+
+	/// Whether `id` is new, in which case it's remembered from `now` on. Otherwise it's counted in [skipped](Self::skipped).
+	pub(crate) fn admit(&mut self, id: K, now: Instant) -> bool {
+		while let Some((_, at)) = self.order.front()
+			&& now.duration_since(*at) > self.window.max_age
+		{
+			self.forget_oldest();
+		}
+		if self.seen.contains(&id) {
+			self.skipped += 1;
+			return false;
+		}
+		self.seen.insert(id.clone());
+		self.order.push_back((id, now));
+		while self.order.len() > self.window.max_ids {
+			self.forget_oldest();
+		}
+		true
+	}
+
+	pub(crate) fn skipped(&self) -> u64 {
+		self.skipped
+	}
+
+	fn forget_oldest(&mut self) {
+		if let Some((id, _)) = self.order.pop_front() {
+			self.seen.remove(&id);
+		}
+	}
+}
+
+/// Wraps a [CandleFeed], collapsing klines by `open_time`.
+///
+/// Within a read, only the last update of each candle is kept. Across reads, anything opened before the latest candle yielded is a replay, and is dropped and counted in [duplicates_skipped](ExchangeStream::duplicates_skipped). Updates of the latest candle itself still come through, as they're newer versions of it.
+pub fn deduped_klines<S: ExchangeStream<Item = Kline>>(stream: S) -> DedupedKlines<S> {
+	DedupedKlines {
+		inner: stream,
+		last_open: None,
+		skipped: 0,
+	}
+}
+
+/// See [deduped_klines].
+#[derive(Debug)]
+pub struct DedupedKlines<S> {
+	inner: S,
+	/// `None` until the first kline.
+	last_open: Option<Timestamp>,
+	skipped: u64,
+}
+impl<S> DedupedKlines<S> {
+	pub fn inner(&self) -> &S {
+		&self.inner
+	}
+}
+#[async_trait::async_trait]
+impl<S: ExchangeStream<Item = Kline>> ExchangeStream for DedupedKlines<S> {
+	type Item = Kline;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.inner.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		self.inner.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.inner.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		Some(self.skipped + self.inner.duplicates_skipped().unwrap_or(0))
+	}
+
+	async fn next(&mut self) -> Result<Vec<Kline>, WsError> {
+		let klines = self.inner.next().await?;
+		let mut out: Vec<Kline> = Vec::with_capacity(klines.len());
+		for kline in klines {
+			if let Some(last_open) = self.last_open
+				&& kline.open_time < last_open
+			{
+				self.skipped += 1;
+				debug!(target: "v_exchanges", open_time = %kline.open_time, %last_open, "Skipping a replayed kline");
+				continue;
+			}
+			// a partial superseded within the same read is not a duplicate, so isn't counted
+			if out.last().is_some_and(|prev| prev.open_time == kline.open_time) {
+				out.pop();
+			}
+			self.last_open = Some(kline.open_time);
+			out.push(kline);
+		}
+		Ok(out)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+	use crate::test_utils::Scripted;
+
+	fn kline(minute: i64, close: f64) -> Kline {
+		Kline {
+			open_time: Timestamp::from_second(minute * 60).unwrap(),
+			ohlc: Ohlc {
+				open: 100.,
+				high: close.max(100.),
+				low: close.min(100.),
+				close,
+			},
+			volume_quote: 1.,
+			trades: None,
+			taker_buy_volume_quote: None,
+		}
+	}
+
+	#[test]
+	fn ids_are_admitted_once_within_the_window() {
+		let mut ids = RecentIds::new(DedupWindow {
+			max_ids: 3,
+			max_age: Duration::from_secs(10),
+		});
+		let t0 = Instant::now();
+		assert!([1, 2, 3].into_iter().all(|id| ids.admit(id, t0)));
+		assert!(!ids.admit(2, t0));
+		// evicts 1 by count
+		assert!(ids.admit(4, t0));
+		assert!(ids.admit(1, t0));
+		// and everything by age
+		assert!(ids.admit(4, t0 + Duration::from_secs(11)));
+		assert_eq!(ids.skipped(), 1);
+	}
+
+	#[tokio::test]
+	async fn replayed_klines_are_collapsed() {
+		// the reconnect replays minutes 1 and 2, minute 2 having moved on since
+		let batches = [vec![kline(0, 101.), kline(1, 102.), kline(1, 103.)], vec![kline(2, 104.)], vec![kline(1, 103.), kline(2, 105.), kline(3, 106.)]];
+		let feed: CandleFeed = Box::new(Scripted::batches(batches));
+		let mut stream = deduped_klines(feed);
+
+		let mut delivered = Vec::new();
+		for _ in 0..3 {
+			delivered.extend(stream.next().await.unwrap().into_iter().map(|k| (k.open_time.as_second() / 60, k.ohlc.close)));
+		}
+		assert_eq!(delivered, [(0, 101.), (1, 103.), (2, 104.), (2, 105.), (3, 106.)]);
+		assert_eq!(stream.duplicates_skipped(), Some(1));
+	}
+}
@@ -0,0 +1,341 @@
+//! Alerting on the same pair's price drifting apart across exchanges: an arb signal, or a sign that one of the feeds is stale or broken.
+use std::time::Duration;
+
+use adapters::generics::ws::{LatencyStats, SubscriptionSnapshot, WsError};
+use jiff::{SignedDuration, Timestamp};
+use tokio::task::JoinHandle;
+
+use crate::prelude::*;
+
+/// Default of [DivergenceMonitor::max_age].
+const MAX_AGE: Duration = Duration::from_secs(10);
+/// Default of [DivergenceMonitor::rearm_below], as a fraction of the threshold.
+const REARM_FRACTION: f64 = 0.8;
+
+/// Item of a [PriceFeed].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceUpdate {
+	pub pair: Pair,
+	pub price: f64,
+	/// On the local clock, so that quotes of different feeds compare: when it was received, or the exchange's own time of the quote less the feed's [clock offset](ExchangeStream::set_clock_offset).
+	pub time: Timestamp,
+}
+
+/// Prices of one exchange. [mark_price_feed] makes one out of [ws_mark_prices](Exchange::ws_mark_prices); [polled_price_feed] out of REST [prices](Exchange::prices), which every exchange has.
+pub type PriceFeed = Box<dyn ExchangeStream<Item = PriceUpdate>>;
+
+/// Mark prices as a [PriceFeed]. Their times are the exchange's, so set the feed's [clock offset](ExchangeStream::set_clock_offset) where the local clock may be off; skew left uncompensated reads as quote age.
+pub fn mark_price_feed(stream: Box<dyn ExchangeStream<Item = MarkPriceUpdate>>) -> PriceFeed {
+	Box::new(MarkPrices {
+		inner: stream,
+		clock_offset: SignedDuration::ZERO,
+	})
+}
+
+/// See [mark_price_feed].
+#[derive(Debug)]
+struct MarkPrices {
+	inner: Box<dyn ExchangeStream<Item = MarkPriceUpdate>>,
+	/// `server_time - local_time`, taken off exchange times to put them on the local clock.
+	clock_offset: SignedDuration,
+}
+#[async_trait::async_trait]
+impl ExchangeStream for MarkPrices {
+	type Item = PriceUpdate;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.inner.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		self.inner.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.clock_offset = offset;
+		self.inner.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.inner.duplicates_skipped()
+	}
+
+	async fn next(&mut self) -> Result<Vec<PriceUpdate>, WsError> {
+		let updates = self.inner.next().await?;
+		Ok(updates
+			.into_iter()
+			.map(|u| PriceUpdate {
+				pair: u.pair,
+				price: u.mark_price,
+				time: u.time - self.clock_offset,
+			})
+			.collect())
+	}
+}
+
+/// Polls [prices](Exchange::prices) of `pairs` every `interval`, stamping them with the time they were received. For exchanges that don't stream a price of the instrument.
+pub fn polled_price_feed(exchange: impl Into<Arc<dyn Exchange>>, pairs: Vec<Pair>, instrument: Instrument, interval: Duration) -> PriceFeed {
+	Box::new(PolledPrices {
+		exchange: exchange.into(),
+		pairs,
+		instrument,
+		interval: tokio::time::interval(interval),
+		in_flight: None,
+	})
+}
+
+/// See [polled_price_feed].
+#[derive(Debug)]
+struct PolledPrices {
+	exchange: Arc<dyn Exchange>,
+	pairs: Vec<Pair>,
+	instrument: Instrument,
+	interval: tokio::time::Interval,
+	/// Outlives a dropped `next()`, so that a [DivergenceMonitor] racing this against faster feeds still gets the answer.
+	in_flight: Option<JoinHandle<ExchangeResult<BTreeMap<Pair, f64>>>>,
+}
+impl Drop for PolledPrices {
+	fn drop(&mut self) {
+		if let Some(request) = &self.in_flight {
+			request.abort();
+		}
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for PolledPrices {
+	type Item = PriceUpdate;
+
+	async fn next(&mut self) -> Result<Vec<PriceUpdate>, WsError> {
+		if self.in_flight.is_none() {
+			self.interval.tick().await;
+			let (exchange, pairs, instrument) = (Arc::clone(&self.exchange), self.pairs.clone(), self.instrument);
+			self.in_flight = Some(tokio::spawn(async move { exchange.prices(Some(pairs), instrument).await }));
+		}
+		let joined = self.in_flight.as_mut().expect("just set").await;
+		self.in_flight = None;
+		let prices = joined.map_err(|e| WsError::Other(eyre!(e)))?.map_err(|e| WsError::Other(eyre::Report::new(e)))?;
+		let time = Timestamp::now();
+		Ok(prices.into_iter().map(|(pair, price)| PriceUpdate { pair, price, time }).collect())
+	}
+}
+
+/// Spreads of one pair across exchanges. Yielded by [DivergenceMonitor::next] on crossing the threshold, and for every pair by [DivergenceMonitor::snapshot].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DivergenceEvent {
+	pub pair: Pair,
+	/// Between every two exchanges with a fresh quote, in bps of their mean price. Positive where the first one quotes higher.
+	pub spreads: Vec<(ExchangeName, ExchangeName, f64)>,
+	/// Largest of the `spreads` in absolute terms; zero with fewer than two fresh quotes.
+	pub widest: f64,
+	/// Time of each exchange's latest quote, stale ones included.
+	pub timestamps: Vec<(ExchangeName, Timestamp)>,
+	/// Exchanges whose quote is older than [max_age](DivergenceMonitor::max_age), and so left out of the `spreads`.
+	pub stale: Vec<ExchangeName>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Quote {
+	exchange: ExchangeName,
+	price: f64,
+	time: Timestamp,
+}
+
+/// Follows a [PriceFeed] per exchange, yielding a [DivergenceEvent] whenever the widest spread of a pair crosses `threshold_bps`.
+///
+/// A pair that alerted doesn't do so again until its widest spread has fallen back under [rearm_below](Self::rearm_below), so a spread hovering at the threshold doesn't spam. Quotes older than [max_age](Self::max_age) are left out rather than compared, as a frozen feed would otherwise read as a divergence.
+/// ```rust,ignore
+/// let pairs = [Pair::new("BTC", "USDT")];
+/// let feeds = vec![
+/// 	(ExchangeName::Binance, mark_price_feed(binance.ws_mark_prices(&pairs).await?)),
+/// 	(ExchangeName::Bybit, polled_price_feed(bybit, pairs.to_vec(), Instrument::Perp, Duration::from_secs(1))),
+/// ];
+/// let mut monitor = DivergenceMonitor::new(feeds, pairs, 20.);
+/// for event in monitor.next().await? {
+/// 	println!("{}: {:.1} bps apart", event.pair, event.widest);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DivergenceMonitor {
+	feeds: Vec<(ExchangeName, PriceFeed)>,
+	threshold_bps: f64,
+	rearm_bps: f64,
+	max_age: Duration,
+	/// Latest quote of every exchange, per tracked pair.
+	quotes: BTreeMap<Pair, Vec<Quote>>,
+	/// Pairs that alerted and haven't re-armed yet.
+	diverged: BTreeSet<Pair>,
+}
+impl DivergenceMonitor {
+	/// Only `pairs` are tracked; updates of others are ignored.
+	pub fn new(feeds: Vec<(ExchangeName, PriceFeed)>, pairs: impl IntoIterator<Item = Pair>, threshold_bps: f64) -> Self {
+		Self {
+			feeds,
+			threshold_bps,
+			rearm_bps: threshold_bps * REARM_FRACTION,
+			max_age: MAX_AGE,
+			quotes: pairs.into_iter().map(|p| (p, Vec::new())).collect(),
+			diverged: BTreeSet::new(),
+		}
+	}
+
+	/// Level the widest spread of an alerted pair has to fall under before it can alert again. Defaults to 80% of the threshold.
+	pub fn rearm_below(mut self, bps: f64) -> Self {
+		self.rearm_bps = bps;
+		self
+	}
+
+	/// Quotes this much older than the newest one of the same pair are stale. Defaults to 10s.
+	pub fn max_age(mut self, max_age: Duration) -> Self {
+		self.max_age = max_age;
+		self
+	}
+
+	/// Current spreads of every tracked pair. Staleness is judged as when alerting, so a pair nothing was received for yet has no spreads.
+	pub fn snapshot(&self) -> Vec<DivergenceEvent> {
+		self.quotes.iter().map(|(pair, quotes)| spreads(*pair, quotes, self.max_age)).collect()
+	}
+
+	/// Waits for prices until some pair crosses the threshold. Errors of a feed are returned as-is; calling again picks back up.
+	///
+	/// Races the feeds' `next()`s against each other, so relies on them being cancel-safe, which [WsConnection::next](adapters::generics::ws::WsConnection::next) is.
+	pub async fn next(&mut self) -> Result<Vec<DivergenceEvent>, WsError> {
+		if self.feeds.is_empty() {
+			return std::future::pending().await;
+		}
+		loop {
+			let (updates, i, _) = futures_util::future::select_all(self.feeds.iter_mut().map(|(_, feed)| feed.next())).await;
+			let exchange = self.feeds[i].0;
+			let events: Vec<DivergenceEvent> = updates?.into_iter().filter_map(|u| self.observe(exchange, u.pair, u.price, u.time)).collect();
+			if !events.is_empty() {
+				return Ok(events);
+			}
+		}
+	}
+
+	/// Takes in a quote, returning the event if it made the pair cross the threshold.
+	fn observe(&mut self, exchange: ExchangeName, pair: Pair, price: f64, time: Timestamp) -> Option<DivergenceEvent> {
+		let quotes = self.quotes.get_mut(&pair)?;
+		match quotes.iter_mut().find(|q| q.exchange == exchange) {
+			Some(quote) => *quote = Quote { exchange, price, time },
+			None => quotes.push(Quote { exchange, price, time }),
+		}
+		let event = spreads(pair, quotes, self.max_age);
+		match self.diverged.contains(&pair) {
+			false if event.widest >= self.threshold_bps => {
+				self.diverged.insert(pair);
+				Some(event)
+			}
+			true if event.widest < self.rearm_bps => {
+				self.diverged.remove(&pair);
+				None
+			}
+			_ => None,
+		}
+	}
+}
+
+/// Staleness is judged against the newest quote of the pair rather than the local clock, so that it's the same for replays. Quote times are all on the local clock, per [PriceUpdate::time].
+fn spreads(pair: Pair, quotes: &[Quote], max_age: Duration) -> DivergenceEvent {
+	let now = quotes.iter().map(|q| q.time).max().unwrap_or(Timestamp::UNIX_EPOCH);
+	let is_stale = |q: &Quote| now.duration_since(q.time).unsigned_abs() > max_age;
+	let fresh: Vec<&Quote> = quotes.iter().filter(|q| !is_stale(q)).collect();
+	let mut spreads = Vec::new();
+	for (i, a) in fresh.iter().enumerate() {
+		for b in &fresh[i + 1..] {
+			spreads.push((a.exchange, b.exchange, (a.price - b.price) / ((a.price + b.price) / 2.) * 10_000.));
+		}
+	}
+	DivergenceEvent {
+		pair,
+		widest: spreads.iter().map(|(_, _, bps)| bps.abs()).fold(0., f64::max),
+		spreads,
+		timestamps: quotes.iter().map(|q| (q.exchange, q.time)).collect(),
+		stale: quotes.iter().filter(|q| is_stale(q)).map(|q| q.exchange).collect(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::{MockExchange, Scripted};
+
+	fn at(s: i64) -> Timestamp {
+		Timestamp::from_second(s).unwrap()
+	}
+
+	fn feed(pair: Pair, prices: &[(i64, f64)]) -> PriceFeed {
+		let updates = prices.iter().map(|(s, price)| MarkPriceUpdate {
+			pair,
+			mark_price: *price,
+			funding_rate: None,
+			next_funding_time: None,
+			time: at(*s),
+		});
+		mark_price_feed(Box::new(Scripted::items(updates)))
+	}
+
+	#[tokio::test]
+	async fn alerts_once_per_crossing() {
+		let btc = Pair::new("BTC", "USDT");
+		let feeds = vec![
+			(ExchangeName::Binance, feed(btc, &[(0, 100.)])),
+			// in bps off binance: 0, 60, 55, 45, 70, 30, 60
+			(ExchangeName::Bybit, feed(btc, &[(1, 100.), (2, 100.6), (3, 100.55), (4, 100.45), (5, 100.7), (6, 100.3), (7, 100.6)])),
+		];
+		let mut monitor = DivergenceMonitor::new(feeds, [btc], 50.);
+
+		let first = monitor.next().await.unwrap();
+		assert_eq!(first.len(), 1);
+		assert_eq!(first[0].timestamps, vec![(ExchangeName::Binance, at(0)), (ExchangeName::Bybit, at(2))]);
+		let (a, b, bps) = first[0].spreads[0];
+		assert_eq!((a, b), (ExchangeName::Binance, ExchangeName::Bybit));
+		assert!((bps + 59.82).abs() < 0.01, "{bps}");
+		assert_eq!(first[0].widest, bps.abs());
+
+		// staying above the re-arm level (40) in between doesn't alert again; dipping under it does
+		let second = monitor.next().await.unwrap();
+		assert_eq!(second[0].timestamps[1], (ExchangeName::Bybit, at(7)));
+		assert!(tokio::time::timeout(Duration::from_millis(50), monitor.next()).await.is_err(), "script is over");
+	}
+
+	#[test]
+	fn stale_quotes_are_left_out() {
+		let btc = Pair::new("BTC", "USDT");
+		let mut monitor = DivergenceMonitor::new(vec![], [btc], 50.).max_age(Duration::from_secs(10));
+
+		assert!(monitor.observe(ExchangeName::Kucoin, btc, 90., at(0)).is_none());
+		assert!(monitor.observe(ExchangeName::Binance, btc, 100., at(20)).is_none(), "kucoin froze at 90, which is no divergence");
+		let event = monitor.observe(ExchangeName::Bybit, btc, 101., at(21)).unwrap();
+		assert_eq!(event.stale, vec![ExchangeName::Kucoin]);
+		assert_eq!(event.spreads.len(), 1);
+		assert!((event.widest - 99.5).abs() < 0.01, "{}", event.widest);
+
+		assert!(monitor.observe(ExchangeName::Mexc, Pair::new("DOGE", "USDT"), 1., at(21)).is_none(), "untracked pair");
+		assert_eq!(monitor.snapshot()[0].stale, vec![ExchangeName::Kucoin], "same verdict as when alerting");
+	}
+
+	#[tokio::test]
+	async fn exchange_times_are_put_on_the_local_clock() {
+		let btc = Pair::new("BTC", "USDT");
+		// exchange clock 30s ahead of ours
+		let mut feed = feed(btc, &[(130, 100.)]);
+		feed.set_clock_offset(SignedDuration::from_secs(30));
+		let updates = feed.next().await.unwrap();
+		assert_eq!(updates[0].time, at(100));
+
+		let mut monitor = DivergenceMonitor::new(vec![], [btc], 50.).max_age(Duration::from_secs(10));
+		monitor.observe(ExchangeName::Kucoin, btc, 101., at(101));
+		let event = monitor.observe(ExchangeName::Binance, btc, updates[0].price, updates[0].time).unwrap();
+		assert!(event.stale.is_empty(), "1s apart once on one clock, not 29");
+	}
+
+	#[tokio::test]
+	async fn polled_feed_survives_being_raced() {
+		let btc = Pair::new("BTC", "USDT");
+		let mut feed = polled_price_feed(Box::new(MockExchange::default()) as Box<dyn Exchange>, vec![btc], Instrument::Perp, Duration::from_secs(1));
+		// a faster feed winning the race drops the `next()` mid-request
+		assert!(tokio::time::timeout(Duration::ZERO, feed.next()).await.is_err());
+		let updates = feed.next().await.unwrap();
+		assert_eq!(updates.iter().map(|u| (u.pair, u.price)).collect::<Vec<_>>(), vec![(btc, 50_000.)]);
+	}
+}
@@ -0,0 +1,243 @@
+use std::{io::Write as _, path::PathBuf, time::Duration};
+
+use jiff::Timestamp;
+
+use crate::prelude::*;
+
+/// Periodically snapshots total account equity through [personal_info](Exchange::personal_info), building an equity curve.
+///
+/// Keeps the last `capacity` points in memory, and optionally appends each to a JSONL journal. Failed fetches become [gaps](EquityPoint::Gap); an authentication error stops the tracker for good.
+#[derive(Debug)]
+pub struct EquityTracker {
+	exchange: Box<dyn Exchange>,
+	instrument: Instrument,
+	interval: Duration,
+	capacity: usize,
+	journal: Option<PathBuf>,
+	points: VecDeque<EquityPoint>,
+	state: TrackerState,
+}
+
+#[derive(Clone, Debug)]
+pub struct EquitySnapshot {
+	pub timestamp: Timestamp,
+	pub total: Usd,
+	pub balances: Vec<AssetBalance>,
+}
+
+#[derive(Clone, Debug)]
+pub enum EquityPoint {
+	Snapshot(EquitySnapshot),
+	/// Fetch failed at `timestamp`; `error` is its rendered message.
+	Gap { timestamp: Timestamp, error: String },
+}
+impl EquityPoint {
+	pub fn timestamp(&self) -> Timestamp {
+		match self {
+			Self::Snapshot(s) => s.timestamp,
+			Self::Gap { timestamp, .. } => *timestamp,
+		}
+	}
+
+	pub fn snapshot(&self) -> Option<&EquitySnapshot> {
+		match self {
+			Self::Snapshot(s) => Some(s),
+			Self::Gap { .. } => None,
+		}
+	}
+
+	fn to_json(&self) -> Value {
+		match self {
+			Self::Snapshot(s) => json!({
+				"timestamp": s.timestamp.to_string(),
+				"total_usd": *s.total,
+				"balances": s.balances.iter().map(|b| json!({
+					"asset": b.asset.to_string(),
+					"underlying": b.underlying,
+					"usd": b.usd.map(|u| *u),
+				})).collect::<Vec<_>>(),
+			}),
+			Self::Gap { timestamp, error } => json!({
+				"timestamp": timestamp.to_string(),
+				"error": error,
+			}),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TrackerState {
+	#[default]
+	Running,
+	/// Terminal: credentials were rejected, so retrying can't help. Holds the rendered error.
+	AuthFailed(String),
+}
+
+impl EquityTracker {
+	const DEFAULT_CAPACITY: usize = 10_000;
+
+	pub fn new(exchange: Box<dyn Exchange>, instrument: Instrument, interval: Duration) -> Self {
+		Self {
+			exchange,
+			instrument,
+			interval,
+			capacity: Self::DEFAULT_CAPACITY,
+			journal: None,
+			points: VecDeque::new(),
+			state: TrackerState::default(),
+		}
+	}
+
+	/// Max number of points kept in memory; the oldest are dropped first. The journal is unaffected.
+	pub fn capacity(mut self, capacity: usize) -> Self {
+		self.capacity = capacity.max(1);
+		self
+	}
+
+	/// Append every point as a JSON line to `path`. Created if missing.
+	pub fn journal(mut self, path: impl Into<PathBuf>) -> Self {
+		self.journal = Some(path.into());
+		self
+	}
+
+	pub fn state(&self) -> &TrackerState {
+		&self.state
+	}
+
+	/// Recorded points, oldest first, gaps included.
+	pub fn history(&self) -> &VecDeque<EquityPoint> {
+		&self.points
+	}
+
+	pub fn snapshots(&self) -> impl Iterator<Item = &EquitySnapshot> {
+		self.points.iter().filter_map(EquityPoint::snapshot)
+	}
+
+	/// Largest peak-to-trough decline over the recorded snapshots, as a fraction of the peak. `None` with no snapshots.
+	pub fn drawdown(&self) -> Option<f64> {
+		let mut snapshots = self.snapshots().map(|s| *s.total);
+		let mut peak = snapshots.next()?;
+		let mut max_dd: f64 = 0.;
+		for total in snapshots {
+			peak = peak.max(total);
+			if peak > 0. {
+				max_dd = max_dd.max((peak - total) / peak);
+			}
+		}
+		Some(max_dd)
+	}
+
+	/// Change in equity from the first snapshot at or after `since` to the latest one.
+	pub fn pnl_since(&self, since: Timestamp) -> Option<Usd> {
+		let first = self.snapshots().find(|s| s.timestamp >= since)?;
+		let last = self.snapshots().last()?;
+		Some(Usd(*last.total - *first.total))
+	}
+
+	/// Take a single snapshot. Does nothing once the tracker has [stopped](TrackerState::AuthFailed).
+	pub async fn tick(&mut self) -> &TrackerState {
+		if self.state != TrackerState::Running {
+			return &self.state;
+		}
+		let timestamp = Timestamp::now();
+		let point = match self.exchange.personal_info(self.instrument, None).await {
+			Ok(info) => EquityPoint::Snapshot(EquitySnapshot {
+				timestamp,
+				total: info.balances.total,
+				balances: info.balances.to_vec(),
+			}),
+			Err(e) => {
+				if e.is_auth() {
+					error!(target: "v_exchanges", "Stopping equity tracker for {}: {e}", self.exchange.name());
+					self.state = TrackerState::AuthFailed(e.to_string());
+				} else {
+					warn!(target: "v_exchanges", "Failed to snapshot {} balances, recording a gap: {e}", self.exchange.name());
+				}
+				EquityPoint::Gap { timestamp, error: e.to_string() }
+			}
+		};
+		self.append_to_journal(&point).await;
+		self.record(point);
+		&self.state
+	}
+
+	/// Snapshot every `interval` until credentials get rejected, calling `on_tick` after each point is recorded.
+	pub async fn run(&mut self, mut on_tick: impl FnMut(&Self)) -> TrackerState {
+		let mut interval = tokio::time::interval(self.interval);
+		interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+		loop {
+			interval.tick().await;
+			self.tick().await;
+			on_tick(self);
+			if self.state != TrackerState::Running {
+				return self.state.clone();
+			}
+		}
+	}
+
+	/// On the blocking pool, as the journal may sit on slow storage.
+	async fn append_to_journal(&self, point: &EquityPoint) {
+		let Some(path) = self.journal.clone() else { return };
+		let line = point.to_json().to_string();
+		let written = tokio::task::spawn_blocking({
+			let path = path.clone();
+			move || std::fs::OpenOptions::new().create(true).append(true).open(path).and_then(|mut f| writeln!(f, "{line}"))
+		})
+		.await
+		.expect("writing doesn't panic");
+		if let Err(e) = written {
+			warn!(target: "v_exchanges", "Failed to append to equity journal at {}: {e}", path.display());
+		}
+	}
+
+	fn record(&mut self, point: EquityPoint) {
+		if self.points.len() == self.capacity {
+			self.points.pop_front();
+		}
+		self.points.push_back(point);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tracker_with(totals: &[Option<f64>]) -> EquityTracker {
+		let mut tracker = EquityTracker::new(ExchangeName::Binance.init_mock_client(), Instrument::Perp, Duration::from_secs(60)).capacity(totals.len());
+		for (i, total) in totals.iter().enumerate() {
+			let timestamp = Timestamp::from_second(i as i64 * 60).unwrap();
+			tracker.record(match total {
+				Some(total) => EquityPoint::Snapshot(EquitySnapshot {
+					timestamp,
+					total: Usd(*total),
+					balances: vec![],
+				}),
+				None => EquityPoint::Gap { timestamp, error: "timeout".into() },
+			});
+		}
+		tracker
+	}
+
+	#[test]
+	fn curve_stats() {
+		let tracker = tracker_with(&[Some(100.), Some(120.), None, Some(90.), Some(110.)]);
+		assert_eq!(tracker.history().len(), 5);
+		assert_eq!(tracker.drawdown(), Some(0.25));
+		assert_eq!(*tracker.pnl_since(Timestamp::UNIX_EPOCH).unwrap(), 10.);
+		// first snapshot at or after 2min is the one at 3min, the 2min point being a gap
+		assert_eq!(*tracker.pnl_since(Timestamp::from_second(120).unwrap()).unwrap(), 20.);
+		assert!(tracker.pnl_since(Timestamp::from_second(3600).unwrap()).is_none());
+	}
+
+	#[test]
+	fn ring_drops_oldest() {
+		let mut tracker = tracker_with(&[Some(1.), Some(2.)]);
+		tracker.record(EquityPoint::Gap {
+			timestamp: Timestamp::from_second(600).unwrap(),
+			error: "timeout".into(),
+		});
+		let totals: Vec<f64> = tracker.snapshots().map(|s| *s.total).collect();
+		assert_eq!(totals, vec![2.]);
+		assert_eq!(tracker.history().len(), 2);
+	}
+}
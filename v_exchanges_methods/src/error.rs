@@ -5,12 +5,14 @@ use adapters::generics::{
 	ws::WsError,
 };
 use eyre::Report;
+use serde::{Serialize, Serializer, ser::SerializeMap as _};
+use serde_with::{DisplayFromStr, serde_as};
 use v_utils::{
 	trades::{Pair, Timeframe},
 	utils::{Sysexit, SysexitCode},
 };
 
-use crate::{ExchangeName, Instrument};
+use crate::{AccountLabel, ExchangeName, Instrument, KlinesQuality, OrderId, Symbol, Venue};
 
 // Exchange Error {{{
 pub type ExchangeResult<T> = Result<T, Error>;
@@ -47,66 +49,438 @@ pub enum Error {
 	Auth(AuthError),
 	#[diagnostic(transparent)]
 	Ip(IpError),
+	#[diagnostic(transparent)]
+	Amend(AmendError),
+	/// Caught before sending. See [Order::validate](crate::Order::validate).
+	#[diagnostic(transparent)]
+	OrderValidation(OrderValidationError),
+	/// Exchange answered, but with data failing a [KlinesGuard](crate::KlinesGuard).
+	#[diagnostic(transparent)]
+	DataQuality(KlinesQuality),
 	//,}}}1
 	/// our internal markings
 	#[diagnostic(transparent)]
 	Method(MethodError),
+	/// Wraps any of the above with the label of the account it came from. Only produced by labeled clients.
+	#[diagnostic(transparent)]
+	Account(AccountError),
+	/// Wraps any of the above with what was being done when it happened. See [context](Self::context).
+	#[diagnostic(transparent)]
+	Context(ContextError),
+	/// Wraps any of the above with the exchange call it came out of. See [ctx](Self::ctx).
+	#[diagnostic(transparent)]
+	Call(CallError),
 	#[error(transparent)]
 	Other(Report),
 }
 
+impl Error {
+	/// Wraps `self` with what was being done, like `anyhow::Context`. Classification ([is_auth](Self::is_auth), [as_ws](Self::as_ws), ..) looks through it.
+	/// ```
+	/// # use v_exchanges_methods::{adapters::generics::ws::WsError, error::ExchangeError};
+	/// let e = ExchangeError::from(WsError::NetworkConnection).context("streaming BTC-USDT trades");
+	/// assert!(e.as_ws().is_some());
+	/// assert!(e.to_string().starts_with("streaming BTC-USDT trades: "));
+	/// ```
+	pub fn context(self, context: impl std::fmt::Display) -> Self {
+		Self::Context(ContextError::new(context.to_string(), Box::new(self)))
+	}
+
+	/// Wraps `self` with the call it came out of. Normally done by a `ctx!` at the request boundary.
+	pub fn with_ctx(self, ctx: MethodCtx) -> Self {
+		Self::Call(CallError::new(ctx, Box::new(self)))
+	}
+
+	/// The exchange call this came out of, if one was attached, looking through [account labels](Self::Account) and [context](Self::Context).
+	pub fn ctx(&self) -> Option<&MethodCtx> {
+		match self {
+			Self::Call(e) => Some(&e.ctx),
+			Self::Account(e) => e.inner.ctx(),
+			Self::Context(e) => e.inner.ctx(),
+			_ => None,
+		}
+	}
+
+	/// The request may or may not have reached the exchange: it went out, but no response made it back. A mutation failing like this has to be reconciled (eg through [order_status](crate::Exchange::order_status)) before being retried.
+	pub fn is_unknown_outcome(&self) -> bool {
+		match self {
+			// a failed connect means nothing was sent
+			Self::Request(RequestError::SendRequest(e)) => !e.is_connect() && !e.is_builder(),
+			Self::Request(RequestError::ReceiveResponse(_)) => true,
+			Self::Account(e) => e.inner.is_unknown_outcome(),
+			Self::Context(e) => e.inner.is_unknown_outcome(),
+			Self::Call(e) => e.inner.is_unknown_outcome(),
+			_ => false,
+		}
+	}
+
+	/// Never sent, as [dry run](crate::Exchange::set_dry_run) is on.
+	pub fn is_dry_run(&self) -> bool {
+		match self {
+			Self::Request(e) => e.is_dry_run(),
+			Self::Account(e) => e.inner.is_dry_run(),
+			Self::Context(e) => e.inner.is_dry_run(),
+			Self::Call(e) => e.inner.is_dry_run(),
+			_ => false,
+		}
+	}
+
+	/// Credentials were rejected. Retrying the same request won't help.
+	pub fn is_auth(&self) -> bool {
+		match self {
+			Self::Auth(_) => true,
+			Self::Account(e) => e.inner.is_auth(),
+			Self::Context(e) => e.inner.is_auth(),
+			Self::Call(e) => e.inner.is_auth(),
+			_ => false,
+		}
+	}
+
+	/// The exchange doesn't offer the method for this instrument, so falling back to another way of getting the data is the only option. A [pair not being listed](MethodError::PairNotListed) doesn't count.
+	pub fn is_method_not_supported(&self) -> bool {
+		match self {
+			Self::Method(MethodError::MethodNotSupported { .. } | MethodError::MethodNotImplemented { .. }) => true,
+			Self::Account(e) => e.inner.is_method_not_supported(),
+			Self::Context(e) => e.inner.is_method_not_supported(),
+			Self::Call(e) => e.inner.is_method_not_supported(),
+			_ => false,
+		}
+	}
+
+	/// The websocket error behind this one, looking through [account labels](Self::Account).
+	/// ```
+	/// # use v_exchanges_methods::{adapters::generics::ws::{WsError, WsErrorKind}, error::ExchangeError};
+	/// fn should_resubscribe(e: &ExchangeError) -> bool {
+	/// 	match e.as_ws().map(|ws| (ws.kind(), ws.is_terminal())) {
+	/// 		Some((WsErrorKind::Auth | WsErrorKind::SessionExpired, _)) => false,
+	/// 		Some((_, terminal)) => !terminal,
+	/// 		None => false,
+	/// 	}
+	/// }
+	/// assert!(!should_resubscribe(&WsError::SessionExpired("listen key expired".into()).into()));
+	/// assert!(should_resubscribe(&WsError::NetworkConnection.into()));
+	/// ```
+	pub fn as_ws(&self) -> Option<&WsError> {
+		match self {
+			Self::Ws(e) => Some(e),
+			Self::Account(e) => e.inner.as_ws(),
+			Self::Context(e) => e.inner.as_ws(),
+			Self::Call(e) => e.inner.as_ws(),
+			_ => None,
+		}
+	}
+}
+
+/// `ExchangeError -> anyhow::Error` needs no impl of its own: anyhow's blanket `From<E: std::error::Error>` covers it, so `?` works in `anyhow::Result` contexts as is.
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for Error {
+	fn from(e: anyhow::Error) -> Self {
+		Self::Other(Report::new(AnyhowError(e)))
+	}
+}
+/// anyhow's error doesn't implement [std::error::Error] itself, so it needs carrying into [Report].
+#[cfg(feature = "anyhow")]
+#[derive(Debug)]
+struct AnyhowError(anyhow::Error);
+#[cfg(feature = "anyhow")]
+impl std::fmt::Display for AnyhowError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		std::fmt::Display::fmt(&self.0, f)
+	}
+}
+#[cfg(feature = "anyhow")]
+impl std::error::Error for AnyhowError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		self.0.source()
+	}
+}
+
+impl Error {
+	fn variant_name(&self) -> &'static str {
+		match self {
+			Self::Request(_) => "Request",
+			Self::Ws(_) => "Ws",
+			Self::Timeframe(_) => "Timeframe",
+			Self::Range(_) => "Range",
+			Self::Auth(_) => "Auth",
+			Self::Ip(_) => "Ip",
+			Self::Amend(_) => "Amend",
+			Self::OrderValidation(_) => "OrderValidation",
+			Self::DataQuality(_) => "DataQuality",
+			Self::Method(_) => "Method",
+			Self::Account(_) => "Account",
+			Self::Context(_) => "Context",
+			Self::Call(_) => "Call",
+			Self::Other(_) => "Other",
+		}
+	}
+}
+/// `{"type": <variant>, "message": <Display>}`, plus the nested error under `"error"` where it has structure worth keeping, for shipping to JSON log sinks.
+impl Serialize for Error {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut map = serializer.serialize_map(None)?;
+		map.serialize_entry("type", self.variant_name())?;
+		map.serialize_entry("message", &self.to_string())?;
+		match self {
+			Self::Request(e) => map.serialize_entry("error", e)?,
+			Self::Timeframe(e) => map.serialize_entry("error", e)?,
+			Self::Range(e) => map.serialize_entry("error", e)?,
+			Self::Method(e) => map.serialize_entry("error", e)?,
+			Self::OrderValidation(e) => map.serialize_entry("error", e)?,
+			Self::Account(e) => {
+				map.serialize_entry("label", &e.label.0)?;
+				map.serialize_entry("inner", &e.inner)?;
+			}
+			Self::Context(e) => {
+				map.serialize_entry("context", &e.context)?;
+				map.serialize_entry("inner", &e.inner)?;
+			}
+			Self::Call(e) => {
+				map.serialize_entry("ctx", &e.ctx)?;
+				map.serialize_entry("inner", &e.inner)?;
+			}
+			_ => {}
+		}
+		map.end()
+	}
+}
+
 impl SysexitCode for Error {
 	fn sysexit(&self) -> Sysexit {
 		match self {
 			Self::Auth(_) | Self::Ip(_) => Sysexit::NoPerm,
+			Self::Account(e) => e.inner.sysexit(),
+			Self::Context(e) => e.inner.sysexit(),
+			Self::Call(e) => e.inner.sysexit(),
 			_ => Sysexit::None,
 		}
 	}
 }
 
-#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[serde_as]
+#[derive(Debug, miette::Diagnostic, Serialize, thiserror::Error, derive_new::new)]
 #[error("Chosen exchange does not support the requested timeframe. Provided: {provided}, allowed: {allowed:?}")]
 #[diagnostic(code(v_exchanges::unsupported_timeframe), help("Use one of the allowed timeframes for this exchange."))]
 pub struct UnsupportedTimeframeError {
+	#[serde_as(as = "DisplayFromStr")]
 	provided: Timeframe,
+	#[serde_as(as = "Vec<DisplayFromStr>")]
 	allowed: Vec<Timeframe>,
 	#[new(value = "Backtrace::capture()")]
+	#[serde(skip)]
 	backtrace: Backtrace,
 }
 
-#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[serde_as]
+#[derive(Debug, miette::Diagnostic, Serialize, thiserror::Error, derive_new::new)]
+#[serde(tag = "type")]
 pub enum MethodError {
-	/// Means that it's **not expected** to be implemented, not only that it's not implemented now. For things that are yet to be implemented I just put `unimplemented!()`.
-	#[error("Method not implemented for the requested exchange and instrument: ({exchange}, {instrument})")]
+	/// Means that it's **not expected** to be implemented, not only that it's not implemented now. Also what's returned for instruments an exchange module hasn't got to yet, rather than panicking.
+	#[error("`{method_name}` is not implemented for the requested exchange and instrument: ({exchange}, {instrument})")]
 	#[diagnostic(
 		code(v_exchanges::method::not_implemented),
 		help("This method is not expected to be implemented for this exchange/instrument combination.")
 	)]
 	MethodNotImplemented {
-		exchange: ExchangeName,
+		#[serde_as(as = "DisplayFromStr")]
+		#[new(into)]
+		exchange: Venue,
+		#[serde_as(as = "DisplayFromStr")]
 		instrument: Instrument,
+		method_name: &'static str,
 		#[new(value = "Backtrace::capture()")]
+		#[serde(skip)]
 		backtrace: Backtrace,
 	},
-	#[error("Requested exchange does not support the method for chosen instrument: ({exchange}, {instrument})")]
+	#[error("Requested exchange does not support `{method_name}` for chosen instrument: ({exchange}, {instrument})")]
 	#[diagnostic(code(v_exchanges::method::not_supported), help("This exchange does not support this method for the specified instrument type."))]
 	MethodNotSupported {
-		exchange: ExchangeName,
+		#[serde_as(as = "DisplayFromStr")]
+		#[new(into)]
+		exchange: Venue,
+		#[serde_as(as = "DisplayFromStr")]
 		instrument: Instrument,
+		method_name: &'static str,
 		#[new(value = "Backtrace::capture()")]
+		#[serde(skip)]
 		backtrace: Backtrace,
 	},
 	#[error("{pair} is not listed on {exchange} for {instrument}")]
 	#[diagnostic(code(v_exchanges::method::pair_not_listed))]
 	PairNotListed {
-		exchange: ExchangeName,
+		#[serde_as(as = "DisplayFromStr")]
+		#[new(into)]
+		exchange: Venue,
+		#[serde_as(as = "DisplayFromStr")]
 		instrument: Instrument,
+		#[serde_as(as = "DisplayFromStr")]
 		pair: Pair,
 		#[new(value = "Backtrace::capture()")]
+		#[serde(skip)]
 		backtrace: Backtrace,
 	},
 }
 
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+pub enum AmendError {
+	#[error("Nothing to amend: neither new qty nor new price were provided")]
+	#[diagnostic(code(v_exchanges::amend::nothing_to_amend), help("Provide at least one of `new_qty`, `new_price`."))]
+	NothingToAmend {
+		#[new(value = "Backtrace::capture()")]
+		backtrace: Backtrace,
+	},
+	#[error("Order {order_id:?} is already filled (or otherwise closed), can't amend it")]
+	#[diagnostic(code(v_exchanges::amend::order_already_filled))]
+	OrderAlreadyFilled {
+		order_id: OrderId,
+		#[new(value = "Backtrace::capture()")]
+		backtrace: Backtrace,
+	},
+	#[error("Order {order_id:?} not found on the exchange")]
+	#[diagnostic(code(v_exchanges::amend::order_not_found), help("Check that the order id and symbol match an existing open order."))]
+	OrderNotFound {
+		order_id: OrderId,
+		#[new(value = "Backtrace::capture()")]
+		backtrace: Backtrace,
+	},
+}
+
+/// Every constraint an order breaks, not just the first. Nothing was sent.
+#[derive(Clone, Debug, miette::Diagnostic, PartialEq, Serialize, thiserror::Error)]
+#[error("Invalid order: {}", violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+#[diagnostic(code(v_exchanges::order::invalid), help("Most likely an upstream math bug; the exchange would reject this, or misread it."))]
+pub struct OrderValidationError {
+	pub violations: Vec<OrderViolation>,
+}
+
+#[derive(Clone, Debug, derive_more::Display, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum OrderViolation {
+	/// NaN or infinite; for fixed-point fields, saturated by converting one.
+	#[display("{field} is not finite")]
+	NonFinite { field: &'static str },
+	#[display("{field} must be positive, got {value}")]
+	NonPositive { field: &'static str, value: f64 },
+	#[display("symbol is empty")]
+	EmptySymbol,
+	#[display("price {price} is below the minimum of {min}")]
+	PriceBelowMin { price: f64, min: f64 },
+	#[display("price {price} is above the maximum of {max}")]
+	PriceAboveMax { price: f64, max: f64 },
+	#[display("qty {qty} is below the minimum of {min}")]
+	QtyBelowMin { qty: f64, min: f64 },
+	#[display("qty {qty} is above the maximum of {max}")]
+	QtyAboveMax { qty: f64, max: f64 },
+	#[display("qty {qty} is not a multiple of the lot size {step}")]
+	QtyStep { qty: f64, step: f64 },
+	#[display("notional {notional} is below the minimum of {min}")]
+	NotionalBelowMin { notional: f64, min: f64 },
+}
+
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[error("[{label}] {inner}")]
+pub struct AccountError {
+	pub label: AccountLabel,
+	pub inner: Box<Error>,
+}
+
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[error("{context}: {inner}")]
+pub struct ContextError {
+	pub context: String,
+	pub inner: Box<Error>,
+}
+
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[error("{ctx}: {inner}")]
+pub struct CallError {
+	pub ctx: MethodCtx,
+	pub inner: Box<Error>,
+}
+
+/// Which exchange call an error came out of. Renders as `binance.klines(BTC-USDT.P, 1m) -> /fapi/v1/klines`.
+///
+/// `params` are only what the call site chose to list, never the request's own params: those can carry keys and signatures.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MethodCtx {
+	#[serde_as(as = "DisplayFromStr")]
+	pub exchange: ExchangeName,
+	pub method_name: &'static str,
+	/// `None` for calls spanning a whole [instrument](Self::instrument).
+	#[serde_as(as = "Option<DisplayFromStr>")]
+	pub symbol: Option<Symbol>,
+	#[serde_as(as = "DisplayFromStr")]
+	pub instrument: Instrument,
+	pub params: Vec<String>,
+	pub endpoint: Option<String>,
+}
+impl MethodCtx {
+	pub fn new(exchange: ExchangeName, method_name: &'static str, subject: impl CallSubject, params: Vec<String>, endpoint: Option<String>) -> Self {
+		let (symbol, instrument) = subject.into_subject();
+		Self {
+			exchange,
+			method_name,
+			symbol,
+			instrument,
+			params,
+			endpoint,
+		}
+	}
+}
+impl std::fmt::Display for MethodCtx {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}.{}(", self.exchange.to_string().to_lowercase(), self.method_name)?;
+		match self.symbol {
+			Some(symbol) => write!(f, "{symbol}")?,
+			None => write!(f, "{}", self.instrument)?,
+		}
+		for param in &self.params {
+			write!(f, ", {param}")?;
+		}
+		write!(f, ")")?;
+		if let Some(endpoint) = &self.endpoint {
+			write!(f, " -> {endpoint}")?;
+		}
+		Ok(())
+	}
+}
+
+/// What a call is about: one [Symbol], or a whole [Instrument] for calls spanning pairs.
+pub trait CallSubject {
+	fn into_subject(self) -> (Option<Symbol>, Instrument);
+}
+impl CallSubject for Symbol {
+	fn into_subject(self) -> (Option<Symbol>, Instrument) {
+		(Some(self), self.instrument)
+	}
+}
+impl CallSubject for Instrument {
+	fn into_subject(self) -> (Option<Symbol>, Instrument) {
+		(None, self)
+	}
+}
+
+/// Attaches a [MethodCtx] to the error side of a `Result<_, impl Into<ExchangeError>>`, for use right before `?` at the request boundary. Costs nothing unless it fails.
+/// ```rust,ignore
+/// let r: Vec<KlineResponse> = ctx!(client.get(endpoint, &params, options).await, Binance.klines(symbol, tf) -> endpoint)?;
+/// ```
+/// The exchange is an [ExchangeName] variant; the subject a [Symbol] or an [Instrument]; params are anything [Display](std::fmt::Display), listed explicitly.
+macro_rules! ctx {
+	($result:expr, $exchange:ident . $method:ident ( $subject:expr $(, $param:expr)* $(,)? ) -> $endpoint:expr) => {
+		$result.map_err(|e| {
+			$crate::error::ExchangeError::from(e).with_ctx($crate::error::MethodCtx::new(
+				$crate::ExchangeName::$exchange,
+				stringify!($method),
+				$subject,
+				vec![$($param.to_string()),*],
+				Some($endpoint.to_string()),
+			))
+		})
+	};
+}
+pub(crate) use ctx;
+
 #[derive(Debug, miette::Diagnostic, derive_more::Display, thiserror::Error, derive_more::From)]
 pub enum RequestRangeError {
 	#[diagnostic(transparent)]
@@ -114,14 +488,100 @@ pub enum RequestRangeError {
 	#[error(transparent)]
 	Others(Report),
 }
+/// Same shape as [Error]'s.
+impl Serialize for RequestRangeError {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut map = serializer.serialize_map(None)?;
+		match self {
+			Self::OutOfRange(e) => {
+				map.serialize_entry("type", "OutOfRange")?;
+				map.serialize_entry("message", &e.to_string())?;
+				map.serialize_entry("error", e)?;
+			}
+			Self::Others(e) => {
+				map.serialize_entry("type", "Others")?;
+				map.serialize_entry("message", &e.to_string())?;
+			}
+		}
+		map.end()
+	}
+}
 
-#[derive(derive_more::Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[derive(derive_more::Debug, miette::Diagnostic, Serialize, thiserror::Error, derive_new::new)]
 #[error("Effective provided limit is out of range (could be translated from Start:End / tf). Allowed: {allowed:?}, provided: {provided}")]
 #[diagnostic(code(v_exchanges::range::out_of_range), help("Adjust the request parameters to fall within the allowed range."))]
 pub struct OutOfRangeError {
 	allowed: std::ops::RangeInclusive<u32>,
 	provided: u32,
 	#[new(value = "Backtrace::capture()")]
+	#[serde(skip)]
 	backtrace: Backtrace,
 }
 //,}}}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	#[test]
+	fn serializes_for_json_logs() {
+		let e = Error::from(MethodError::new_method_not_supported(ExchangeName::Binance, Instrument::Perp, "klines"));
+		let v = serde_json::to_value(&e).unwrap();
+		assert_eq!(v["type"], "Method");
+		assert_eq!(v["message"], e.to_string());
+		assert_eq!(v["error"]["type"], "MethodNotSupported");
+		assert_eq!(v["error"]["exchange"], ExchangeName::Binance.to_string());
+		assert_eq!(v["error"]["method_name"], "klines");
+		assert!(e.to_string().contains("`klines`"));
+		assert!(Error::Account(AccountError::new(AccountLabel::from("main"), Box::new(e))).context("streaming").is_method_not_supported());
+
+		let e = Error::Other(eyre::eyre!("boom")).context("fetching klines");
+		assert_eq!(
+			serde_json::to_value(&e).unwrap(),
+			json!({
+				"type": "Context",
+				"message": "fetching klines: boom",
+				"context": "fetching klines",
+				"inner": { "type": "Other", "message": "boom" },
+			})
+		);
+	}
+
+	#[test]
+	fn call_ctx_survives_wrapping() {
+		let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+		let fetch = || -> ExchangeResult<()> {
+			let response: Result<(), RequestError> = Err(RequestError::HandleResponse(HandleError::Api(ApiError::Auth(AuthError::Unauthorized { msg: "bad key".into() }))));
+			ctx!(response, Binance.klines(symbol, "1m") -> "/fapi/v1/klines")?;
+			Ok(())
+		};
+		let e = fetch().unwrap_err();
+		assert_eq!(e.to_string(), format!("binance.klines({symbol}, 1m) -> /fapi/v1/klines: Unauthorized: bad key"));
+
+		let e = Error::Account(AccountError::new(AccountLabel::from("main"), Box::new(e))).context("rebalancing");
+		assert!(e.is_auth(), "the RequestError -> Auth conversion still happened underneath");
+		assert!(!e.is_method_not_supported());
+		let ctx = e.ctx().unwrap();
+		assert_eq!((ctx.exchange, ctx.method_name, ctx.symbol), (ExchangeName::Binance, "klines", Some(symbol)));
+		assert_eq!(ctx.endpoint.as_deref(), Some("/fapi/v1/klines"));
+		assert!(e.to_string().contains("binance.klines("), "{e}");
+
+		let v = serde_json::to_value(&e).unwrap();
+		assert_eq!(v["inner"]["inner"]["type"], "Call");
+		assert_eq!(v["inner"]["inner"]["ctx"]["params"], json!(["1m"]));
+	}
+
+	#[cfg(feature = "anyhow")]
+	#[test]
+	fn anyhow_round_trip() {
+		fn strategy() -> anyhow::Result<()> {
+			Err(Error::from(WsError::NetworkConnection).context("subscribing"))?
+		}
+		let e: Error = strategy().unwrap_err().context("running strategy").into();
+		let chain = format!("{:#}", Report::new(e));
+		assert!(chain.contains("running strategy"), "{chain}");
+		assert!(chain.contains("subscribing"), "{chain}");
+	}
+}
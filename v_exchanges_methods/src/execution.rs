@@ -0,0 +1,355 @@
+//! Working a parent order off in child slices over time (TWAP). Exchange-agnostic: built only on [Exchange] methods, so runs against anything implementing it.
+use std::{
+	backtrace::Backtrace,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::Duration,
+};
+
+use adapters::generics::http::IpError;
+use jiff::Timestamp;
+use tokio::{sync::Notify, time::Instant};
+
+use crate::prelude::*;
+
+/// Hold-off after the exchange throttled a slice without saying for how long.
+const THROTTLE_PAUSE: Duration = Duration::from_secs(10);
+/// Reads of a child's fill, for exchanges acking market orders before they're matched.
+const FILL_POLLS: usize = 5;
+const FILL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How each child of a [TwapExecutor] is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SliceOrder {
+	#[default]
+	Market,
+	/// IOC limit `offset_bps` through the last price. Caps the slippage of each slice, and never leaves anything resting: what doesn't fill rolls into the next slice.
+	MarketableLimit { offset_bps: f64 },
+}
+
+/// Progress of a [TwapExecutor], after each slice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionEvent {
+	/// Children worked off so far.
+	pub sliced: usize,
+	pub filled: f64,
+	/// Over all fills so far. `None` until anything fills.
+	pub avg_price: Option<f64>,
+	pub remaining: f64,
+}
+
+/// [TwapExecutor::new] refusing a `total_qty` that can't be split into valid orders. Carried in [ExchangeError::Other], so match on it with `downcast_ref::<UnrepresentableQtyError>()`.
+#[derive(Debug, miette::Diagnostic, thiserror::Error, derive_new::new)]
+#[error("Can't work {total_qty} of {symbol} in orders of at least {min_slice_qty}, with {qty_precision} qty decimals")]
+#[diagnostic(code(v_exchanges::execution::unrepresentable_qty), help("Trade at least the minimum, on the pair's qty precision."))]
+pub struct UnrepresentableQtyError {
+	pub symbol: Symbol,
+	pub total_qty: f64,
+	/// The larger of the pair's min qty and its min notional at the price when asked.
+	pub min_slice_qty: f64,
+	pub qty_precision: u8,
+	#[new(value = "Backtrace::capture()")]
+	backtrace: Backtrace,
+}
+
+/// Stops a [TwapExecutor] from another task. Get one from [TwapExecutor::cancel_handle].
+#[derive(Clone, Debug, Default)]
+pub struct TwapCancel {
+	inner: Arc<CancelState>,
+}
+#[derive(Debug, Default)]
+struct CancelState {
+	cancelled: AtomicBool,
+	notify: Notify,
+}
+impl TwapCancel {
+	/// No further slices are placed; a pending [next](TwapExecutor::next) returns `None`. Children are all market or IOC, so none is left resting.
+	pub fn cancel(&self) {
+		self.inner.cancelled.store(true, Ordering::SeqCst);
+		// stores a permit if nobody's waiting yet, so it can't be missed
+		self.inner.notify.notify_one();
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.inner.cancelled.load(Ordering::SeqCst)
+	}
+}
+
+/// Splits `total_qty` into equal children, placed one per `slice_interval` over `duration`, the first right away. Drive it by calling [next](Self::next) until `None`.
+///
+/// Children are never smaller than the pair's min qty or min notional: when the requested schedule would go below, it's spread over fewer, larger slices instead.
+/// Throttling (rate limits, IP bans with a known end) pauses the schedule instead of failing it.
+/// ```rust,ignore
+/// let mut twap = TwapExecutor::new(&binance, symbol, Side::Buy, 2.5, Duration::from_secs(3600), Duration::from_secs(60)).await?;
+/// let cancel = twap.cancel_handle();
+/// while let Some(progress) = twap.next().await {
+/// 	let progress = progress?;
+/// 	info!(filled = progress.filled, remaining = progress.remaining, "twap");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TwapExecutor<'a, E: ?Sized> {
+	exchange: &'a E,
+	symbol: Symbol,
+	side: Side,
+	slice_order: SliceOrder,
+	price_precision: u8,
+	qty_precision: u8,
+	total_qty: f64,
+	slices: VecDeque<f64>,
+	interval: Duration,
+	next_at: Instant,
+	/// Placed child whose fill is yet to be read. Kept across errors, so a failed read never leads to placing the slice again.
+	pending: Option<OrderId>,
+	sliced: usize,
+	filled: f64,
+	filled_notional: f64,
+	cancel: TwapCancel,
+}
+impl<'a, E: Exchange + ?Sized> TwapExecutor<'a, E> {
+	/// Refuses with [UnrepresentableQtyError] if `total_qty` isn't on the pair's qty grid, or is below its minimum order. The pair's limits are read off [pair_info](Exchange::pair_info).
+	pub async fn new(exchange: &'a E, symbol: Symbol, side: Side, total_qty: f64, duration: Duration, slice_interval: Duration) -> ExchangeResult<Self> {
+		assert!(!slice_interval.is_zero(), "slice_interval must be non-zero");
+		let pair_info = exchange.pair_info(symbol).await?;
+
+		let min_qty = pair_info.min_qty.unwrap_or(0.);
+		let min_slice_qty = match pair_info.min_notional {
+			Some(min_notional) => min_qty.max(min_notional / exchange.price(symbol).await?),
+			None => min_qty,
+		};
+		let wanted = ((duration.as_secs_f64() / slice_interval.as_secs_f64()).floor() as usize).max(1);
+		let slices = plan_slices(total_qty, wanted, min_slice_qty, pair_info.qty_precision)
+			.ok_or_else(|| ExchangeError::Other(UnrepresentableQtyError::new(symbol, total_qty, min_slice_qty, pair_info.qty_precision).into()))?;
+		if slices.len() < wanted {
+			info!(target: "v_exchanges", %symbol, wanted, slices = slices.len(), min_slice_qty, "TWAP slices clamped to the minimum order size");
+		}
+
+		Ok(Self {
+			exchange,
+			symbol,
+			side,
+			slice_order: SliceOrder::default(),
+			price_precision: pair_info.price_precision,
+			qty_precision: pair_info.qty_precision,
+			total_qty,
+			interval: duration / slices.len() as u32,
+			slices: slices.into(),
+			next_at: Instant::now(),
+			pending: None,
+			sliced: 0,
+			filled: 0.,
+			filled_notional: 0.,
+			cancel: TwapCancel::default(),
+		})
+	}
+
+	pub fn with_slice_order(mut self, slice_order: SliceOrder) -> Self {
+		self.slice_order = slice_order;
+		self
+	}
+
+	pub fn cancel_handle(&self) -> TwapCancel {
+		self.cancel.clone()
+	}
+
+	/// See [TwapCancel::cancel].
+	pub fn cancel(&self) {
+		self.cancel.cancel();
+	}
+
+	pub fn progress(&self) -> ExecutionEvent {
+		ExecutionEvent {
+			sliced: self.sliced,
+			filled: self.filled,
+			avg_price: (self.filled > 0.).then(|| self.filled_notional / self.filled),
+			remaining: (self.total_qty - self.filled).max(0.),
+		}
+	}
+
+	/// Works off the next slice once it's due, returning the progress after it. `None` once all slices are done, or on [cancel](TwapCancel::cancel).
+	///
+	/// Errors other than throttling are handed back with the slice kept, so calling again retries it.
+	pub async fn next(&mut self) -> Option<ExchangeResult<ExecutionEvent>> {
+		loop {
+			let qty = *self.slices.front()?;
+			if self.cancel.is_cancelled() {
+				self.slices.clear();
+				return None;
+			}
+			tokio::select! {
+				_ = tokio::time::sleep_until(self.next_at) => {}
+				_ = self.cancel.inner.notify.notified() => continue,
+			}
+
+			match self.work_slice(qty).await {
+				Ok((filled, avg_price)) => {
+					self.slices.pop_front();
+					self.sliced += 1;
+					self.filled += filled;
+					self.filled_notional += filled * avg_price.unwrap_or(0.);
+					// what an IOC child left unfilled is carried over
+					if let Some(next) = self.slices.front_mut() {
+						*next = round_qty(*next + qty - filled, self.qty_precision);
+					}
+					self.next_at += self.interval;
+					return Some(Ok(self.progress()));
+				}
+				Err(e) => match throttle_pause(&e) {
+					Some(pause) => {
+						warn!(target: "v_exchanges", symbol = %self.symbol, ?pause, "TWAP slice throttled, pausing: {e}");
+						self.next_at = self.next_at.max(Instant::now() + pause);
+					}
+					None => return Some(Err(e)),
+				},
+			}
+		}
+	}
+
+	/// `(filled, avg_price)` of the child for `qty`.
+	async fn work_slice(&mut self, qty: f64) -> ExchangeResult<(f64, Option<f64>)> {
+		let order_id = match self.pending.clone() {
+			Some(order_id) => order_id,
+			None => {
+				let order = self.slice(qty).await?;
+				let placed = self.exchange.place_order_idempotent(self.symbol, order, None).await?;
+				self.pending = Some(placed.order_id.clone());
+				placed.order_id
+			}
+		};
+
+		let mut details = self.exchange.get_order(self.symbol, order_id.clone(), None).await?;
+		for _ in 1..FILL_POLLS {
+			if details.status.is_terminal() {
+				break;
+			}
+			tokio::time::sleep(FILL_POLL_INTERVAL).await;
+			details = self.exchange.get_order(self.symbol, order_id.clone(), None).await?;
+		}
+		self.pending = None;
+		Ok((details.filled_qty, details.avg_price))
+	}
+
+	async fn slice(&self, qty: f64) -> ExchangeResult<Order> {
+		let qty = Qty::from_f64(qty, self.qty_precision);
+		let order_id = OrderId::with_generated_id("twap");
+		Ok(match self.slice_order {
+			SliceOrder::Market => MarketOrder { order_id, ..MarketOrder::new(self.side, qty) }.into(),
+			SliceOrder::MarketableLimit { offset_bps } => {
+				let last = self.exchange.price(self.symbol).await?;
+				let through = match self.side {
+					Side::Buy => 1. + offset_bps / 10_000.,
+					Side::Sell => 1. - offset_bps / 10_000.,
+				};
+				LimitOrder {
+					time_in_force: TimeInForce::Ioc,
+					order_id,
+					..LimitOrder::new(self.side, Price::from_f64(last * through, self.price_precision), qty)
+				}
+				.into()
+			}
+		})
+	}
+}
+
+/// Splits `total` into at most `n` near-equal slices of at least `min` each, all on the `precision` grid. `None` if `total` is off the grid or below `min`.
+fn plan_slices(total: f64, n: usize, min: f64, precision: u8) -> Option<Vec<f64>> {
+	let scale = 10f64.powi(precision as i32);
+	let total_raw = (total * scale).round();
+	if total_raw < 1. || (total_raw - total * scale).abs() > 1e-6 {
+		return None;
+	}
+	let total_raw = total_raw as u64;
+	let min_raw = ((min * scale - 1e-9).ceil() as u64).max(1);
+	if total_raw < min_raw {
+		return None;
+	}
+
+	let n = (n as u64).min(total_raw / min_raw).max(1);
+	let (base, extra) = (total_raw / n, total_raw % n);
+	Some((0..n).map(|i| (base + u64::from(i < extra)) as f64 / scale).collect())
+}
+
+fn round_qty(qty: f64, precision: u8) -> f64 {
+	let scale = 10f64.powi(precision as i32);
+	(qty * scale).round() / scale
+}
+
+/// How long to hold off after `e`, if it's the exchange asking to slow down.
+fn throttle_pause(e: &ExchangeError) -> Option<Duration> {
+	match e {
+		ExchangeError::Ip(IpError::Timeout { until }) => Some(until.and_then(|until| Duration::try_from(until.duration_since(Timestamp::now())).ok()).unwrap_or(THROTTLE_PAUSE)),
+		ExchangeError::Account(e) => throttle_pause(&e.inner),
+		ExchangeError::Context(e) => throttle_pause(&e.inner),
+		ExchangeError::Call(e) => throttle_pause(&e.inner),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::AtomicUsize;
+
+	use super::*;
+	use crate::test_utils::MockExchange;
+
+	#[test]
+	fn slices_clamped_to_min() {
+		assert_eq!(plan_slices(0.01, 4, 0.001, 3), Some(vec![0.003, 0.003, 0.002, 0.002]));
+		// 10 wanted, only 3 fit at the minimum
+		assert_eq!(plan_slices(0.01, 10, 0.003, 3), Some(vec![0.004, 0.003, 0.003]));
+		assert_eq!(plan_slices(0.0005, 4, 0.001, 3), None, "below min");
+		assert_eq!(plan_slices(0.0105, 4, 0.001, 3), None, "off the qty grid");
+	}
+
+	#[tokio::test]
+	async fn works_off_total_through_throttling() {
+		let exchange = MockExchange {
+			throttle: AtomicUsize::new(1),
+			..Default::default()
+		};
+		let mut twap = TwapExecutor::new(&exchange, MockExchange::btc_perp(), Side::Buy, 0.01, Duration::from_millis(40), Duration::from_millis(10)).await.unwrap();
+		let mut events = Vec::new();
+		while let Some(event) = twap.next().await {
+			events.push(event.unwrap());
+		}
+		// min notional of 100 at 50k is 0.002, so 4 slices of 0.0025 are rounded and clamped into 0.003 + 0.003 + 0.002 + 0.002
+		assert_eq!(events.len(), 4);
+		let last = events.last().unwrap();
+		assert_eq!(last.sliced, 4);
+		assert!((last.filled - 0.01).abs() < 1e-12);
+		assert_eq!(last.remaining, 0.);
+		assert_eq!(last.avg_price, Some(50_000.));
+
+		let placed: Vec<f64> = exchange.placed.lock().unwrap().iter().map(|(_, qty)| *qty).collect();
+		assert_eq!(placed, vec![0.003, 0.003, 0.002, 0.002]);
+	}
+
+	#[tokio::test]
+	async fn refuses_unrepresentable_qty() {
+		let exchange = MockExchange::default();
+		let e = TwapExecutor::new(&exchange, MockExchange::btc_perp(), Side::Buy, 0.001, Duration::from_secs(60), Duration::from_secs(1)).await.unwrap_err();
+		let ExchangeError::Other(report) = e else { panic!("{e:?}") };
+		let e = report.downcast_ref::<UnrepresentableQtyError>().unwrap();
+		assert_eq!(e.min_slice_qty, 0.002);
+	}
+
+	#[tokio::test]
+	async fn cancel_stops_further_slices() {
+		let exchange = MockExchange::default();
+		let mut twap = TwapExecutor::new(&exchange, MockExchange::btc_perp(), Side::Buy, 0.01, Duration::from_secs(60), Duration::from_secs(20)).await.unwrap();
+		let first = twap.next().await.unwrap().unwrap();
+		assert_eq!(first.sliced, 1);
+
+		let cancel = twap.cancel_handle();
+		tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			cancel.cancel();
+		});
+		// next slice is 20s out; the cancel cuts the wait short
+		assert!(twap.next().await.is_none());
+		assert!(twap.next().await.is_none());
+		assert_eq!(exchange.placed.lock().unwrap().len(), 1);
+	}
+}
@@ -0,0 +1,272 @@
+use arrayvec::ArrayString;
+use jiff::Timestamp;
+
+use crate::prelude::*;
+
+/// Push from an account's user-data stream.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum AccountEvent {
+	OrderUpdate(OrderUpdate),
+	PositionUpdate(PositionUpdate),
+	BalanceUpdate(BalanceUpdate),
+}
+
+/// One of our orders, as of a single user-data update.
+///
+/// Quantities are cumulative, so any one update is enough to reconstruct the order's fill state; see [FillTracker].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderUpdate {
+	pub order_id: OrderId,
+	pub symbol: Symbol,
+	pub side: Side,
+	pub status: OrderStatus,
+	pub qty: f64,
+	pub cum_filled_qty: f64,
+	/// `Σ price * qty` over all fills so far, in quote.
+	pub cum_filled_quote: f64,
+	pub fee: Option<FeeReport>,
+	pub time: Timestamp,
+}
+
+/// A derivatives position, as of a single user-data update. Pushed on every change, including the one closing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionUpdate {
+	pub symbol: Symbol,
+	/// Negative for shorts, zero once closed. In contracts for inverse perps, base asset otherwise.
+	pub size: f64,
+	/// `None` while flat.
+	pub entry_price: Option<f64>,
+	pub mark_price: Option<f64>,
+	pub unrealized_pnl: f64,
+	pub time: Timestamp,
+}
+
+/// Wallet balance of one asset, as of a single user-data update.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceUpdate {
+	pub asset: Asset,
+	pub wallet_balance: f64,
+	/// What's left after margin and open orders, where the exchange reports it.
+	pub available: Option<f64>,
+	pub time: Timestamp,
+}
+
+/// Fee as reported on an [OrderUpdate]. Exchanges differ in whether they send the running total or only the latest fill's.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeeReport {
+	/// Of the fill this update reports only (Binance).
+	Fill { asset: Asset, amount: f64 },
+	/// Total over all fills so far (Bybit).
+	Cumulative { asset: Asset, amount: f64 },
+}
+
+/// Aggregated fills of one order. See [FillTracker].
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderFillState {
+	pub order_id: OrderId,
+	pub symbol: Symbol,
+	pub side: Side,
+	pub status: OrderStatus,
+	pub qty: f64,
+	pub filled_qty: f64,
+	/// Volume-weighted average fill price. `None` until anything is filled.
+	pub avg_price: Option<f64>,
+	/// Per asset; negative for rebates. Where fees are reported per [fill](FeeReport::Fill), covers only the fills seen since tracking started.
+	pub fees: BTreeMap<Asset, f64>,
+	pub updated_at: Timestamp,
+	filled_quote: f64,
+}
+impl OrderFillState {
+	fn from_update(u: &OrderUpdate) -> Self {
+		let mut state = Self {
+			order_id: u.order_id.clone(),
+			symbol: u.symbol,
+			side: u.side,
+			status: u.status,
+			qty: u.qty,
+			filled_qty: 0.,
+			avg_price: None,
+			fees: BTreeMap::new(),
+			updated_at: u.time,
+			filled_quote: 0.,
+		};
+		state.apply(u);
+		state
+	}
+
+	fn apply(&mut self, u: &OrderUpdate) {
+		let has_new_fill = u.cum_filled_qty > self.filled_qty;
+		match &u.fee {
+			Some(FeeReport::Fill { asset, amount }) if has_new_fill => *self.fees.entry(*asset).or_default() += amount,
+			Some(FeeReport::Cumulative { asset, amount }) => {
+				self.fees.insert(*asset, *amount);
+			}
+			_ => {}
+		}
+		self.status = u.status;
+		self.qty = u.qty;
+		self.filled_qty = u.cum_filled_qty;
+		self.filled_quote = u.cum_filled_quote;
+		self.avg_price = (self.filled_qty > 0.).then(|| self.filled_quote / self.filled_qty);
+		self.updated_at = u.time;
+		// exchanges know it by both ids; keep whichever we learned
+		if self.order_id.exchange_id.is_none() {
+			self.order_id.exchange_id = u.order_id.exchange_id;
+		}
+	}
+}
+
+/// Per-order fill state, built from [AccountEvent::OrderUpdate]s.
+///
+/// Orders are keyed by [client_order_id](OrderId::client_order_id). Orders placed before the tracker started are picked up from their first update. Updates older than the known state (fewer fills) and updates after a terminal status are ignored, so duplicated or reordered deliveries are harmless.
+#[derive(Clone, Debug, Default)]
+pub struct FillTracker {
+	orders: HashMap<ArrayString<36>, OrderFillState>,
+}
+impl FillTracker {
+	pub fn order(&self, id: &OrderId) -> Option<&OrderFillState> {
+		self.orders.get(&id.client_order_id())
+	}
+
+	pub fn orders(&self) -> impl Iterator<Item = &OrderFillState> {
+		self.orders.values()
+	}
+
+	/// Drops every order that reached a terminal status, returning them.
+	pub fn drain_terminal(&mut self) -> Vec<OrderFillState> {
+		let done: Vec<_> = self.orders.iter().filter(|(_, s)| s.status.is_terminal()).map(|(k, _)| *k).collect();
+		done.into_iter().filter_map(|k| self.orders.remove(&k)).collect()
+	}
+
+	/// Returns the order's state if this event moved it into a terminal status.
+	pub fn apply(&mut self, event: &AccountEvent) -> Option<OrderFillState> {
+		let AccountEvent::OrderUpdate(u) = event else { return None };
+		let key = u.order_id.client_order_id();
+		if let Some(state) = self.orders.get_mut(&key) {
+			if state.status.is_terminal() || u.cum_filled_qty < state.filled_qty {
+				return None;
+			}
+			state.apply(u);
+			return state.status.is_terminal().then(|| state.clone());
+		}
+		let state = OrderFillState::from_update(u);
+		let terminal = state.status.is_terminal().then(|| state.clone());
+		self.orders.insert(key, state);
+		terminal
+	}
+}
+
+/// [AccountEvent] stream narrowed down to orders reaching a terminal status, each yielded once with its final [OrderFillState].
+///
+/// Awaiting a specific fill is then `.next()` until its id comes up. Everything else tracked is available through [tracker](Self::tracker).
+#[derive(Debug)]
+pub struct FillStream<S> {
+	events: S,
+	tracker: FillTracker,
+}
+impl<S: ExchangeStream<Item = AccountEvent>> FillStream<S> {
+	pub fn new(events: S) -> Self {
+		Self {
+			events,
+			tracker: FillTracker::default(),
+		}
+	}
+
+	pub fn tracker(&self) -> &FillTracker {
+		&self.tracker
+	}
+}
+#[async_trait::async_trait]
+impl<S: ExchangeStream<Item = AccountEvent>> ExchangeStream for FillStream<S> {
+	type Item = OrderFillState;
+
+	fn latency_stats(&self) -> Option<adapters::generics::ws::LatencyStats> {
+		self.events.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<adapters::generics::ws::SubscriptionSnapshot> {
+		self.events.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: jiff::SignedDuration) {
+		self.events.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, adapters::generics::ws::WsError> {
+		loop {
+			let events = self.events.next().await?;
+			let done: Vec<_> = events.iter().filter_map(|e| self.tracker.apply(e)).collect();
+			if !done.is_empty() {
+				return Ok(done);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn update(status: OrderStatus, cum_filled_qty: f64, cum_filled_quote: f64, fee: Option<FeeReport>) -> AccountEvent {
+		AccountEvent::OrderUpdate(OrderUpdate {
+			order_id: OrderId {
+				client_id: Some(ArrayString::from("strat-1").unwrap()),
+				exchange_id: Some(ArrayString::from("4293153").unwrap()),
+				..Default::default()
+			},
+			symbol: Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp),
+			side: Side::Buy,
+			status,
+			qty: 3.,
+			cum_filled_qty,
+			cum_filled_quote,
+			fee,
+			time: Timestamp::from_millisecond(1_700_000_000_000).unwrap(),
+		})
+	}
+	fn usdt_fill(amount: f64) -> Option<FeeReport> {
+		Some(FeeReport::Fill { asset: "USDT".into(), amount })
+	}
+
+	#[test]
+	fn partial_fills_aggregate_into_terminal() {
+		let mut tracker = FillTracker::default();
+		assert!(tracker.apply(&update(OrderStatus::New, 0., 0., usdt_fill(0.))).is_none());
+		assert!(tracker.apply(&update(OrderStatus::PartiallyFilled, 1., 100., usdt_fill(0.04))).is_none());
+		// redelivered: must not double-count the fee
+		assert!(tracker.apply(&update(OrderStatus::PartiallyFilled, 1., 100., usdt_fill(0.04))).is_none());
+		let done = tracker.apply(&update(OrderStatus::Filled, 3., 340., usdt_fill(0.08))).expect("terminal");
+		assert_eq!(done.status, OrderStatus::Filled);
+		assert_eq!(done.filled_qty, 3.);
+		assert!((done.avg_price.unwrap() - 340. / 3.).abs() < 1e-9);
+		assert!((done.fees[&Asset::from("USDT")] - 0.12).abs() < 1e-9);
+
+		// terminal is reported once; later noise is ignored
+		assert!(tracker.apply(&update(OrderStatus::Filled, 3., 340., usdt_fill(0.08))).is_none());
+		assert_eq!(tracker.drain_terminal().len(), 1);
+		assert_eq!(tracker.orders().count(), 0);
+	}
+
+	#[test]
+	fn unknown_order_reconstructed_from_cumulative_fields() {
+		let mut tracker = FillTracker::default();
+		let cumulative = Some(FeeReport::Cumulative { asset: "USDT".into(), amount: 0.1 });
+		assert!(tracker.apply(&update(OrderStatus::PartiallyFilled, 2., 210., cumulative)).is_none());
+		let id = OrderId {
+			client_id: Some(ArrayString::from("strat-1").unwrap()),
+			..Default::default()
+		};
+		let state = tracker.order(&id).expect("picked up mid-way");
+		assert_eq!(state.filled_qty, 2.);
+		assert_eq!(state.avg_price, Some(105.));
+		assert_eq!(state.fees[&Asset::from("USDT")], 0.1);
+
+		// older update arriving late doesn't roll it back
+		assert!(tracker.apply(&update(OrderStatus::PartiallyFilled, 1., 100., None)).is_none());
+		assert_eq!(tracker.order(&id).unwrap().filled_qty, 2.);
+
+		let done = tracker.apply(&update(OrderStatus::Canceled, 2., 210., None)).expect("terminal");
+		assert_eq!(done.status, OrderStatus::Canceled);
+	}
+}
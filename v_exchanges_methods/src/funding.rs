@@ -0,0 +1,296 @@
+//! Running estimate of what the next funding will cost a set of perp positions.
+use std::time::Duration;
+
+use adapters::generics::ws::WsError;
+use jiff::{SignedDuration, Timestamp};
+use tokio::{
+	sync::{Notify, watch},
+	time::Instant,
+};
+
+use crate::prelude::*;
+
+/// How often rates are re-fetched where there's no [ws_mark_prices](Exchange::ws_mark_prices) to follow.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Forecast for one position of a [FundingForecast].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FundingEstimate {
+	/// In base; positive for a long.
+	pub qty: f64,
+	pub mark_price: f64,
+	/// As the exchange quotes it. Every supported venue has positive mean longs pay shorts.
+	pub predicted_rate: f64,
+	pub next_funding_time: Timestamp,
+	/// What this position pays at the next funding, in quote. Normalized over the rate's sign and the position's side: positive is a cost, negative is received.
+	pub est_payment_usd: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FundingForecast {
+	/// Positions not yet quoted are left out until their first rate comes in.
+	pub positions: BTreeMap<Pair, FundingEstimate>,
+	/// Sum of [est_payment_usd](FundingEstimate::est_payment_usd) over all of them.
+	pub total_usd: f64,
+}
+impl FundingForecast {
+	fn new(positions: &BTreeMap<Pair, f64>, quotes: &BTreeMap<Pair, FundingQuote>) -> Self {
+		let positions: BTreeMap<Pair, FundingEstimate> = positions
+			.iter()
+			.filter_map(|(pair, qty)| {
+				let quote = quotes.get(pair)?;
+				Some((*pair, FundingEstimate {
+					qty: *qty,
+					mark_price: quote.mark_price,
+					predicted_rate: quote.rate,
+					next_funding_time: quote.next_funding_time,
+					est_payment_usd: qty * quote.mark_price * quote.rate,
+				}))
+			})
+			.collect();
+		let total_usd = positions.values().map(|e| e.est_payment_usd).sum();
+		Self { positions, total_usd }
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FundingQuote {
+	mark_price: f64,
+	rate: f64,
+	next_funding_time: Timestamp,
+}
+
+/// Updates the positions of a [FundingForecaster] from another task. Get one from [FundingForecaster::positions_handle].
+#[derive(Clone, Debug, Default)]
+pub struct FundingPositions {
+	inner: Arc<PositionsState>,
+}
+#[derive(Debug, Default)]
+struct PositionsState {
+	/// Signed, in base.
+	positions: Mutex<BTreeMap<Pair, f64>>,
+	notify: Notify,
+}
+impl FundingPositions {
+	/// `qty` in base of a position on `side`, replacing any previous one of `pair`; zero closes it. A pending [next](FundingForecaster::next) returns the re-estimate right away, without waiting for the next rate.
+	pub fn set(&self, pair: Pair, qty: f64, side: Side) {
+		let signed = match side {
+			Side::Buy => qty.abs(),
+			Side::Sell => -qty.abs(),
+		};
+		{
+			let mut positions = self.inner.positions.lock().unwrap();
+			match signed == 0. {
+				true => positions.remove(&pair),
+				false => positions.insert(pair, signed),
+			};
+		}
+		// stores a permit if nobody's waiting yet, so it can't be missed
+		self.inner.notify.notify_one();
+	}
+
+	/// Signed qty per pair: positive for longs.
+	pub fn get(&self) -> BTreeMap<Pair, f64> {
+		self.inner.positions.lock().unwrap().clone()
+	}
+}
+
+/// Keeps a [FundingForecast] of the next funding payment over a set of perp positions. Drive it by calling [next](Self::next); read it through [snapshot](Self::snapshot) or a [watch channel](Self::subscribe).
+///
+/// Rates come off [ws_mark_prices](Exchange::ws_mark_prices), which on Binance carries the predicted funding. Where the exchange doesn't stream them (eg Bybit, which only has them on REST tickers), [funding_countdown](Exchange::funding_countdown) and [mark_price](Exchange::mark_price) are polled every minute instead.
+/// ```rust,ignore
+/// let mut forecaster = FundingForecaster::new(ExchangeName::Binance.init_client());
+/// forecaster.positions_handle().set(Pair::new("BTC", "USDT"), 0.5, Side::Buy);
+/// loop {
+/// 	let forecast = forecaster.next().await?;
+/// 	println!("next funding: {:+.2} USDT", forecast.total_usd);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FundingForecaster {
+	exchange: Box<dyn Exchange>,
+	positions: FundingPositions,
+	quotes: BTreeMap<Pair, FundingQuote>,
+	/// Reopened whenever the set of pairs changes.
+	stream: Option<(Vec<Pair>, Box<dyn ExchangeStream<Item = MarkPriceUpdate>>)>,
+	/// Set once the exchange turns out not to stream mark prices.
+	polling: bool,
+	next_poll: Instant,
+	tx: watch::Sender<FundingForecast>,
+}
+impl FundingForecaster {
+	pub fn new(exchange: Box<dyn Exchange>) -> Self {
+		Self {
+			exchange,
+			positions: FundingPositions::default(),
+			quotes: BTreeMap::new(),
+			stream: None,
+			polling: false,
+			next_poll: Instant::now(),
+			tx: watch::Sender::new(FundingForecast::default()),
+		}
+	}
+
+	pub fn positions_handle(&self) -> FundingPositions {
+		self.positions.clone()
+	}
+
+	/// See [FundingPositions::set].
+	pub fn set_position(&self, pair: Pair, qty: f64, side: Side) {
+		self.positions.set(pair, qty, side);
+	}
+
+	/// Sees every forecast [next](Self::next) produces.
+	pub fn subscribe(&self) -> watch::Receiver<FundingForecast> {
+		self.tx.subscribe()
+	}
+
+	/// Forecast off the latest rates, with positions as they are now.
+	pub fn snapshot(&self) -> FundingForecast {
+		FundingForecast::new(&self.positions.get(), &self.quotes)
+	}
+
+	/// Waits for a rate update or a position change, and returns the re-estimated forecast, also publishing it to [subscribers](Self::subscribe).
+	///
+	/// A failed poll or a dropped stream is returned as-is; calling again picks back up.
+	pub async fn next(&mut self) -> ExchangeResult<FundingForecast> {
+		loop {
+			let pairs: Vec<Pair> = self.positions.get().into_keys().collect();
+			if !self.polling && self.stream.as_ref().is_none_or(|(covered, _)| *covered != pairs) {
+				self.stream = None;
+				if !pairs.is_empty() {
+					match self.exchange.ws_mark_prices(&pairs).await {
+						Ok(stream) => self.stream = Some((pairs.clone(), stream)),
+						Err(e) if e.is_method_not_supported() => {
+							info!(target: "v_exchanges", "{} doesn't stream mark prices, polling funding every {POLL_INTERVAL:?} instead", self.exchange.name());
+							self.polling = true;
+						}
+						Err(e) => return Err(e),
+					}
+				}
+			}
+			// a new position shouldn't wait out the interval for its first estimate
+			if self.polling && pairs.iter().any(|p| !self.quotes.contains_key(p)) {
+				self.next_poll = self.next_poll.min(Instant::now());
+			}
+
+			tokio::select! {
+				_ = self.positions.inner.notify.notified() => {}
+				updates = next_updates(&mut self.stream) => {
+					let updates = updates.inspect_err(|_| self.stream = None)?;
+					if updates.is_empty() {
+						continue;
+					}
+					for update in updates {
+						self.apply(update);
+					}
+				}
+				_ = tokio::time::sleep_until(self.next_poll), if self.polling && !pairs.is_empty() => {
+					self.next_poll = Instant::now() + POLL_INTERVAL;
+					self.poll(&pairs).await?;
+				}
+			}
+			let forecast = self.snapshot();
+			self.tx.send_replace(forecast.clone());
+			return Ok(forecast);
+		}
+	}
+
+	/// Updates without a rate only refresh the mark of an already quoted pair.
+	fn apply(&mut self, update: MarkPriceUpdate) {
+		match (update.funding_rate, update.next_funding_time) {
+			(Some(rate), Some(next_funding_time)) => {
+				self.quotes.insert(update.pair, FundingQuote {
+					mark_price: update.mark_price,
+					rate,
+					next_funding_time,
+				});
+			}
+			_ =>
+				if let Some(quote) = self.quotes.get_mut(&update.pair) {
+					quote.mark_price = update.mark_price;
+				},
+		}
+	}
+
+	async fn poll(&mut self, pairs: &[Pair]) -> ExchangeResult<()> {
+		let exchange = &self.exchange;
+		let fetched = join_all(pairs.iter().map(|pair| async move {
+			let symbol = Symbol::new(*pair, Instrument::Perp);
+			futures_util::future::try_join(exchange.funding_countdown(symbol), exchange.mark_price(symbol)).await
+		}))
+		.await;
+		let now = Timestamp::now();
+		for (pair, r) in pairs.iter().zip(fetched) {
+			let ((countdown, rate), mark_price) = r?;
+			self.quotes.insert(*pair, FundingQuote {
+				mark_price,
+				rate,
+				next_funding_time: now + SignedDuration::try_from(countdown).expect("countdown fits"),
+			});
+		}
+		Ok(())
+	}
+}
+
+async fn next_updates(stream: &mut Option<(Vec<Pair>, Box<dyn ExchangeStream<Item = MarkPriceUpdate>>)>) -> Result<Vec<MarkPriceUpdate>, WsError> {
+	match stream {
+		Some((_, stream)) => stream.next().await,
+		None => std::future::pending().await,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn quote(mark_price: f64, rate: f64) -> FundingQuote {
+		FundingQuote {
+			mark_price,
+			rate,
+			next_funding_time: Timestamp::UNIX_EPOCH,
+		}
+	}
+
+	#[test]
+	fn payment_sign_is_normalized() {
+		let (btc, eth) = (Pair::new("BTC", "USDT"), Pair::new("ETH", "USDT"));
+		let quotes = BTreeMap::from([(btc, quote(50_000., 0.0001)), (eth, quote(2_000., -0.0005))]);
+		let handle = FundingPositions::default();
+
+		// long on a positive rate pays
+		handle.set(btc, 2., Side::Buy);
+		let forecast = FundingForecast::new(&handle.get(), &quotes);
+		assert!((forecast.total_usd - 10.).abs() < 1e-9);
+
+		// a short on a positive rate receives, as does a long on a negative one
+		handle.set(btc, 2., Side::Sell);
+		handle.set(eth, 10., Side::Buy);
+		let forecast = FundingForecast::new(&handle.get(), &quotes);
+		assert!((forecast.positions[&btc].est_payment_usd + 10.).abs() < 1e-9);
+		assert!((forecast.positions[&eth].est_payment_usd + 10.).abs() < 1e-9);
+		assert!((forecast.total_usd + 20.).abs() < 1e-9);
+
+		// closed, and unquoted, positions drop out
+		handle.set(btc, 0., Side::Buy);
+		handle.set(Pair::new("SOL", "USDT"), 100., Side::Buy);
+		let forecast = FundingForecast::new(&handle.get(), &quotes);
+		assert_eq!(forecast.positions.keys().collect::<Vec<_>>(), vec![&eth]);
+	}
+
+	#[tokio::test]
+	async fn position_change_reestimates_immediately() {
+		let btc = Pair::new("BTC", "USDT");
+		let mut forecaster = FundingForecaster::new(ExchangeName::Binance.init_mock_client());
+		forecaster.quotes.insert(btc, quote(50_000., 0.0001));
+		forecaster.polling = true;
+		forecaster.next_poll = Instant::now() + Duration::from_secs(3600);
+		let mut rx = forecaster.subscribe();
+
+		forecaster.set_position(btc, 1., Side::Buy);
+		let forecast = forecaster.next().await.unwrap();
+		assert!((forecast.total_usd - 5.).abs() < 1e-9);
+		assert!(rx.has_changed().unwrap());
+		assert_eq!(*rx.borrow_and_update(), forecast);
+	}
+}
@@ -0,0 +1,337 @@
+//! Write-ahead journal of mutating calls. A process dying between sending an order and hearing back leaves it unknown whether the order exists; the journal is what tells recovery which ones to [look up](crate::Exchange::order_status).
+use std::{
+	fs::{File, OpenOptions},
+	io::Write as _,
+	path::PathBuf,
+};
+
+use jiff::Timestamp;
+use serde_with::{DisplayFromStr, serde_as};
+use uuid::Uuid;
+
+use crate::prelude::*;
+
+/// Where [Exchange::place_order] and [Exchange::amend_order] record what they're about to send, and then how it went. Set through [Exchange::set_mutation_journal].
+///
+/// Called on tokio's blocking pool, so implementations are free to block. The request still waits for `append` to return.
+pub trait MutationJournal: std::fmt::Debug + Send + Sync {
+	/// Must be durable by the time it returns: the request goes out right after. On error, it doesn't go out at all.
+	fn append(&self, intent: &MutationIntent) -> std::io::Result<()>;
+	/// Failing here only makes the mutation show up as [unresolved](Self::unresolved), so it's logged rather than returned to the caller.
+	fn resolve(&self, id: Uuid, outcome: &MutationOutcome) -> std::io::Result<()>;
+	/// Intents that never got an outcome, oldest first: sent right before a crash, or sent with no response coming back (see [is_unknown_outcome](ExchangeError::is_unknown_outcome)). Meant for startup, to be reconciled through [order_status](Exchange::order_status) on each one's [order_id](MutationIntent::order_id).
+	fn unresolved(&self) -> std::io::Result<Vec<MutationIntent>>;
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationKind {
+	PlaceOrder,
+	AmendOrder,
+}
+
+/// A mutation as it's about to be sent.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MutationIntent {
+	/// For [PlaceOrder](MutationKind::PlaceOrder), the [OrderId::id] of the order; otherwise fresh.
+	#[serde_as(as = "DisplayFromStr")]
+	pub id: Uuid,
+	pub kind: MutationKind,
+	#[serde_as(as = "DisplayFromStr")]
+	pub exchange: Venue,
+	#[serde_as(as = "DisplayFromStr")]
+	pub symbol: Symbol,
+	pub client_order_id: String,
+	/// Of everything sent, for telling a retry of the same request apart from a different one reusing the client id. Stable across builds.
+	pub payload_hash: u64,
+	#[serde_as(as = "DisplayFromStr")]
+	pub sent_at: Timestamp,
+}
+impl MutationIntent {
+	pub(crate) fn place_order(exchange: Venue, symbol: Symbol, order: &Order) -> Self {
+		let order_id = order.order_id();
+		Self {
+			id: order_id.id,
+			kind: MutationKind::PlaceOrder,
+			exchange,
+			symbol,
+			client_order_id: order_id.client_order_id().to_string(),
+			payload_hash: payload_hash(&Payload::of_order(order)),
+			sent_at: Timestamp::now(),
+		}
+	}
+
+	pub(crate) fn amend_order(exchange: Venue, symbol: Symbol, order_id: &OrderId, new_qty: Option<f64>, new_price: Option<f64>) -> Self {
+		Self {
+			id: Uuid::now_v7(),
+			kind: MutationKind::AmendOrder,
+			exchange,
+			symbol,
+			client_order_id: order_id.client_order_id().to_string(),
+			payload_hash: payload_hash(&Payload::Amend {
+				client_order_id: order_id.client_order_id().to_string(),
+				new_qty,
+				new_price,
+			}),
+			sent_at: Timestamp::now(),
+		}
+	}
+
+	/// What to look the affected order up by with [order_status](Exchange::order_status).
+	pub fn order_id(&self) -> OrderId {
+		OrderId {
+			id: self.id,
+			parent: None,
+			exchange_id: None,
+			client_id: arrayvec::ArrayString::from(&self.client_order_id).ok(),
+		}
+	}
+}
+
+/// How a journaled mutation ended, when that's known.
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationOutcome {
+	Accepted {
+		exchange_id: Option<String>,
+		#[serde_as(as = "DisplayFromStr")]
+		status: OrderStatus,
+	},
+	/// The exchange answered with an error, or the request never left.
+	Failed { error: String },
+}
+
+/// What [payload_hash] is taken of: the parameters that tell one request from another. Serialized rather than `Debug`-formatted, as the hash has to survive changes to the types.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Payload {
+	Limit {
+		client_order_id: String,
+		side: &'static str,
+		price: String,
+		qty: String,
+		post_only: bool,
+		reduce_only: bool,
+	},
+	Market {
+		client_order_id: String,
+		side: &'static str,
+		qty: String,
+		reduce_only: bool,
+	},
+	Amend {
+		client_order_id: String,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+	},
+}
+impl Payload {
+	fn of_order(order: &Order) -> Self {
+		let client_order_id = order.order_id().client_order_id().to_string();
+		let side = match order.side() {
+			Side::Buy => "buy",
+			Side::Sell => "sell",
+		};
+		match order {
+			Order::Limit(o) => Self::Limit {
+				client_order_id,
+				side,
+				price: o.price.to_string(),
+				qty: order.qty_param(),
+				post_only: o.post_only,
+				reduce_only: o.reduce_only,
+			},
+			Order::Market(o) => Self::Market {
+				client_order_id,
+				side,
+				qty: order.qty_param(),
+				reduce_only: o.reduce_only,
+			},
+		}
+	}
+}
+
+/// FNV-1a of the json of `payload`. Unlike [DefaultHasher](std::hash::DefaultHasher), guaranteed not to change between builds, which the journal outlives.
+fn payload_hash(payload: &Payload) -> u64 {
+	let json = serde_json::to_string(payload).expect("plain fields always serialize");
+	json.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Runs a [MutationJournal] call on the blocking pool, as a [FileJournal] `fsync`s under a lock.
+async fn blocking(f: impl FnOnce() -> std::io::Result<()> + Send + 'static) -> std::io::Result<()> {
+	tokio::task::spawn_blocking(f).await.unwrap_or_else(|e| Err(std::io::Error::other(e)))
+}
+
+/// Runs `call` between [append](MutationJournal::append)ing `intent` and [resolve](MutationJournal::resolve)ing it. Without a journal, it's just `call`.
+///
+/// [Unknown outcomes](ExchangeError::is_unknown_outcome) are left unresolved, as the exchange may well have taken the order.
+pub(crate) async fn journaled(journal: Option<(&Arc<dyn MutationJournal>, MutationIntent)>, call: impl Future<Output = ExchangeResult<OrderPlaced>>) -> ExchangeResult<OrderPlaced> {
+	let Some((journal, intent)) = journal else {
+		return call.await;
+	};
+	let appended = {
+		let (journal, intent) = (Arc::clone(journal), intent.clone());
+		blocking(move || journal.append(&intent)).await
+	};
+	appended.map_err(|e| ExchangeError::Other(eyre!("Failed to journal {:?} of {} before sending it: {e}", intent.kind, intent.client_order_id)))?;
+
+	let r = call.await;
+	let outcome = match &r {
+		Ok(placed) => MutationOutcome::Accepted {
+			exchange_id: placed.order_id.exchange_id.map(|id| id.to_string()),
+			status: placed.status,
+		},
+		Err(e) if e.is_unknown_outcome() => return r,
+		Err(e) => MutationOutcome::Failed { error: e.to_string() },
+	};
+	let resolved = {
+		let (journal, id) = (Arc::clone(journal), intent.id);
+		blocking(move || journal.resolve(id, &outcome)).await
+	};
+	if let Err(e) = resolved {
+		warn!(target: "v_exchanges", client_order_id = %intent.client_order_id, error = %e, "Failed to journal the outcome; the mutation will show up as unresolved");
+	}
+	r
+}
+
+// FileJournal {{{
+/// Append-only JSONL, `fsync`ed on every entry.
+#[derive(Debug)]
+pub struct FileJournal {
+	path: PathBuf,
+	file: Mutex<File>,
+}
+impl FileJournal {
+	/// Creates the file if it doesn't exist; otherwise appends to it, so unresolved intents of previous runs stay visible.
+	pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+		let path = path.into();
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		Ok(Self { path, file: Mutex::new(file) })
+	}
+
+	fn write(&self, entry: &Entry) -> std::io::Result<()> {
+		let mut line = serde_json::to_string(entry)?;
+		line.push('\n');
+		let mut file = self.file.lock().unwrap();
+		file.write_all(line.as_bytes())?;
+		file.sync_data()
+	}
+}
+impl MutationJournal for FileJournal {
+	fn append(&self, intent: &MutationIntent) -> std::io::Result<()> {
+		self.write(&Entry::Intent(intent.clone()))
+	}
+
+	fn resolve(&self, id: Uuid, outcome: &MutationOutcome) -> std::io::Result<()> {
+		self.write(&Entry::Resolved {
+			id,
+			outcome: outcome.clone(),
+			at: Timestamp::now(),
+		})
+	}
+
+	fn unresolved(&self) -> std::io::Result<Vec<MutationIntent>> {
+		let content = std::fs::read_to_string(&self.path)?;
+		let lines: Vec<&str> = content.lines().filter(|l| !l.is_empty()).collect();
+		let mut intents = Vec::new();
+		let mut resolved = HashSet::new();
+		for (i, line) in lines.iter().enumerate() {
+			match serde_json::from_str(line) {
+				Ok(Entry::Intent(intent)) => intents.push(intent),
+				Ok(Entry::Resolved { id, .. }) => {
+					resolved.insert(id);
+				}
+				// a write torn by the crash itself can only be the last one; anywhere else, dropping it could hide an intent
+				Err(e) if i == lines.len() - 1 && !content.ends_with('\n') => {
+					warn!(target: "v_exchanges", path = %self.path.display(), error = %e, "Skipping torn final journal entry");
+				}
+				Err(e) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Malformed line {} of journal {}: {e}", i + 1, self.path.display()))),
+			}
+		}
+		intents.retain(|i| !resolved.contains(&i.id));
+		Ok(intents)
+	}
+}
+
+#[serde_as]
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum Entry {
+	Intent(MutationIntent),
+	Resolved {
+		#[serde_as(as = "DisplayFromStr")]
+		id: Uuid,
+		outcome: MutationOutcome,
+		#[serde_as(as = "DisplayFromStr")]
+		at: Timestamp,
+	},
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::MockExchange;
+
+	#[tokio::test]
+	async fn crash_leaves_only_the_in_flight_intent() {
+		let path = std::env::temp_dir().join(format!("v_exchanges_journal_{}.jsonl", Uuid::now_v7()));
+		let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+		let journal: Arc<dyn MutationJournal> = Arc::new(FileJournal::open(&path).unwrap());
+
+		let mut exchange = MockExchange::default();
+		exchange.set_mutation_journal(Some(journal.clone()));
+		Exchange::place_order(&exchange, symbol, MockExchange::market_order("acked", 0.001), None).await.unwrap();
+
+		let mut exchange = MockExchange { hang: true, ..Default::default() };
+		exchange.set_mutation_journal(Some(journal.clone()));
+		let in_flight = MockExchange::market_order("lost", 0.001);
+		let request = Exchange::place_order(&exchange, symbol, in_flight.clone(), None);
+		assert!(tokio::time::timeout(std::time::Duration::from_millis(50), request).await.is_err());
+		drop(exchange);
+		drop(journal);
+
+		let unresolved = FileJournal::open(&path).unwrap().unresolved().unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(unresolved.len(), 1);
+		let intent = &unresolved[0];
+		assert_eq!(intent.kind, MutationKind::PlaceOrder);
+		assert_eq!((intent.exchange, intent.symbol), (Venue::Builtin(ExchangeName::Binance), symbol));
+		assert_eq!(intent.order_id().client_order_id(), in_flight.order_id().client_order_id());
+		assert_eq!(intent.payload_hash, payload_hash(&Payload::of_order(&in_flight)));
+	}
+
+	#[test]
+	fn only_a_torn_final_entry_is_skipped() {
+		let path = std::env::temp_dir().join(format!("v_exchanges_journal_{}.jsonl", Uuid::now_v7()));
+		let symbol = Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp);
+		let journal = FileJournal::open(&path).unwrap();
+		let intent = MutationIntent::place_order(Venue::Builtin(ExchangeName::Binance), symbol, &MockExchange::market_order("a", 0.001));
+		journal.append(&intent).unwrap();
+		drop(journal);
+
+		let intact = std::fs::read_to_string(&path).unwrap();
+		std::fs::write(&path, format!("{intact}{{\"entry\":\"resol")).unwrap();
+		let torn_tail = FileJournal::open(&path).unwrap().unresolved();
+
+		std::fs::write(&path, format!("{{\"entry\":\"resol\n{intact}")).unwrap();
+		let bad_middle = FileJournal::open(&path).unwrap().unresolved();
+		std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(torn_tail.unwrap().iter().map(|i| i.id).collect::<Vec<_>>(), vec![intent.id]);
+		assert_eq!(bad_middle.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+	}
+
+	#[test]
+	fn payload_hash_covers_what_is_sent() {
+		let order = MockExchange::market_order("a", 0.001);
+		let hash = payload_hash(&Payload::of_order(&order));
+		assert_eq!(hash, payload_hash(&Payload::of_order(&order.clone())));
+
+		let Order::Market(mut bigger) = order else { unreachable!() };
+		bigger.qty = Qty::from_f64(0.002, 3);
+		assert_ne!(hash, payload_hash(&Payload::of_order(&bigger.into())));
+	}
+}
@@ -1,14 +1,18 @@
+use std::collections::BTreeMap;
+
 use adapters::Client;
-use eyre::{Result, eyre};
+use eyre::eyre;
+use futures_util::future::join_all;
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
-use v_exchanges_adapters::kucoin::{KucoinAuth, KucoinHttpUrl, KucoinOption};
-use v_utils::trades::{Asset, Pair, Usd};
+use v_exchanges_adapters::kucoin::{KucoinAuth, KucoinOption};
+use v_utils::trades::{Asset, Usd};
 
 use crate::{
-	ExchangeResult,
-	core::{ApiKeyInfo, AssetBalance, Balances, KeyPermission, PersonalInfo},
-	kucoin::market,
+	ExchangeError, ExchangeName, ExchangeResult, Instrument, MethodError, PairExchangeFormatExt as _, Symbol,
+	core::{ApiKeyInfo, AssetBalance, Balances, FeeSchedule, KeyPermission, KeyPermissions, PersonalInfo, convert_with},
+	kucoin::{http_url, market},
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -32,9 +36,9 @@ pub struct AccountData {
 	#[serde_as(as = "DisplayFromStr")]
 	pub holds: f64,
 }
-pub(super) async fn personal_info(client: &Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
-	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), KucoinOption::HttpUrl(KucoinHttpUrl::Spot)];
-	let (balances, api_response) = tokio::join!(balances(client, recv_window), client.get_no_query::<KucoinApiKeyResponse, _>("/api/v1/user/api-key", options),);
+pub(super) async fn personal_info(client: &Client, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
+	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Spot)];
+	let (balances, api_response) = tokio::join!(balances(client, instrument, recv_window), client.get_no_query::<KucoinApiKeyResponse, _>("/api/v1/user/api-key", options),);
 	let permissions: Vec<KeyPermission> = api_response
 		.map(|r| r.data.permission.split(',').map(|s| KeyPermission::from_kucoin(s.trim())).collect())
 		.unwrap_or_default();
@@ -48,48 +52,207 @@ pub(super) async fn personal_info(client: &Client, recv_window: Option<std::time
 struct KucoinApiKeyResponse {
 	data: KucoinApiKeyData,
 }
+/// Kucoin doesn't report key expiry, so [expires](KeyPermissions::expires) is always `None`.
+pub(super) async fn key_permissions(client: &Client) -> ExchangeResult<KeyPermissions> {
+	assert!(client.is_authenticated::<KucoinOption>());
+
+	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Spot)];
+	let r: KucoinApiKeyResponse = client.get_no_query("/api/v1/user/api-key", options).await?;
+	Ok(r.data.into())
+}
+
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct KucoinApiKeyData {
+	/// Comma-separated, eg `"General,Spot,Futures"`.
 	permission: String,
+	/// Comma-separated; empty when not bound to any IP.
+	#[serde(default)]
+	ip_whitelist: String,
+}
+impl From<KucoinApiKeyData> for KeyPermissions {
+	fn from(d: KucoinApiKeyData) -> Self {
+		let permissions: Vec<KeyPermission> = d.permission.split(',').map(|s| KeyPermission::from_kucoin(s.trim())).collect();
+		Self {
+			ip_restricted: Some(!d.ip_whitelist.trim().is_empty()),
+			..Self::from_permissions(&permissions)
+		}
+	}
+}
+
+/// Kucoin keeps separate balances per account type. [personal_info](crate::Exchange::personal_info) sums them up, [Kucoin::balances_by_type](super::Kucoin::balances_by_type) keeps them apart.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KucoinAccountType {
+	/// Funding account: deposits and withdrawals.
+	Main,
+	Trade,
+	/// High-frequency trading account.
+	TradeHf,
+	Margin,
+	Isolated,
+	/// Futures account. Not listed by `/api/v1/accounts`, comes from `/api/v1/account-overview` instead.
+	Futures,
+}
+
+/// What `/api/v1/accounts` lists. [balances_by_type] fetches each on its own.
+const SPOT_ACCOUNT_TYPES: [KucoinAccountType; 5] = [
+	KucoinAccountType::Main,
+	KucoinAccountType::Trade,
+	KucoinAccountType::TradeHf,
+	KucoinAccountType::Margin,
+	KucoinAccountType::Isolated,
+];
+
+/// What [Instrument::Margin] balances are made of.
+const MARGIN_ACCOUNT_TYPES: [KucoinAccountType; 2] = [KucoinAccountType::Margin, KucoinAccountType::Isolated];
+
+/// What [Kucoin::balances_by_type](super::Kucoin::balances_by_type) got: balances of each account type that answered, and the error of each one that didn't.
+#[derive(Debug, Default)]
+pub struct BalancesByType {
+	/// Only types with non-zero holdings.
+	pub balances: BTreeMap<KucoinAccountType, Balances>,
+	pub failed: BTreeMap<KucoinAccountType, ExchangeError>,
+}
+impl BalancesByType {
+	/// Every type answered, so [balances](Self::balances) is everything there is.
+	pub fn is_complete(&self) -> bool {
+		self.failed.is_empty()
+	}
+}
+
+/// Currencies a futures account can be margined in. `/api/v1/account-overview` only reports one at a time.
+const FUTURES_MARGIN_CURRENCIES: [&str; 3] = ["USDT", "USDC", "XBT"];
+
+#[derive(Debug, Deserialize)]
+struct FuturesOverviewResponse {
+	data: FuturesOverview,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FuturesOverview {
+	account_equity: f64,
+	currency: String,
 }
 
-pub(super) async fn balances(client: &Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Balances> {
+/// Spot-side balances of all types summed per asset for [Instrument::Spot], of just the [margin ones](MARGIN_ACCOUNT_TYPES) for [Instrument::Margin], the futures account for [Instrument::Perp].
+pub(super) async fn balances(client: &Client, instrument: Instrument, _recv_window: Option<std::time::Duration>) -> ExchangeResult<Balances> {
 	assert!(client.is_authenticated::<KucoinOption>());
 
-	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), KucoinOption::HttpUrl(KucoinHttpUrl::Spot)];
-	let empty_params: &[(String, String)] = &[];
-	let account_response: AccountResponse = client.get("/api/v1/accounts", empty_params, options).await?;
+	let amounts = match instrument {
+		Instrument::Spot | Instrument::Margin => merged(spot_amounts_by_type(client).await?, instrument),
+		Instrument::Perp => futures_amounts(client).await?,
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Kucoin, instrument, "personal_info"))),
+	};
+	Ok(valued(client, amounts).await)
+}
+
+/// Every account type with non-zero holdings, futures included. Each is fetched on its own, so one that fails is reported under its type, and the rest still come through.
+pub(super) async fn balances_by_type(client: &Client, _recv_window: Option<std::time::Duration>) -> BalancesByType {
+	assert!(client.is_authenticated::<KucoinOption>());
 
-	// Helper function to calculate USD value for an asset
-	async fn usd_value(client: &Client, underlying: f64, asset: Asset, _recv_window: Option<std::time::Duration>) -> Result<Usd> {
-		if underlying == 0. {
-			return Ok(Usd(0.));
+	let spot = join_all(SPOT_ACCOUNT_TYPES.map(|account_type| async move { (account_type, spot_amounts(client, account_type).await) }));
+	let (spot, futures) = tokio::join!(spot, futures_amounts(client));
+	let mut out = BalancesByType::default();
+	for (account_type, amounts) in spot.into_iter().chain([(KucoinAccountType::Futures, futures)]) {
+		match amounts {
+			Ok(amounts) if amounts.is_empty() => {}
+			Ok(amounts) => {
+				out.balances.insert(account_type, valued(client, amounts).await);
+			}
+			Err(e) => {
+				out.failed.insert(account_type, e);
+			}
 		}
-		// Check common stablecoins
-		if asset == "USDT" || asset == "USDC" || asset == "BUSD" || asset == "DAI" {
-			return Ok(Usd(underlying));
+	}
+	out
+}
+
+/// Holdings of the spot-side types `instrument` is made of, summed per asset.
+fn merged(by_type: BTreeMap<KucoinAccountType, BTreeMap<Asset, f64>>, instrument: Instrument) -> BTreeMap<Asset, f64> {
+	let mut merged: BTreeMap<Asset, f64> = BTreeMap::new();
+	for (account_type, amounts) in by_type {
+		if instrument == Instrument::Margin && !MARGIN_ACCOUNT_TYPES.contains(&account_type) {
+			continue;
+		}
+		for (asset, amount) in amounts {
+			*merged.entry(asset).or_default() += amount;
 		}
-		// Fetch price for non-stablecoin assets
-		let usdt_pair = Pair::new(asset, "USDT".into());
-		let usdt_price = market::prices(client, Some(vec![usdt_pair]), None)
-			.await
-			.map_err(|e| eyre!("Failed to fetch USDT price for {asset} (balance: {underlying}): {e}"))?
-			.remove(&usdt_pair)
-			.ok_or_else(|| eyre!("{usdt_pair} not found in kucoin prices response"))?;
-		Ok((underlying * usdt_price).into())
 	}
+	merged
+}
 
-	let mut balances: Vec<AssetBalance> = Vec::default();
-	for account in &account_response.data {
+/// Holdings of a single spot-side account type.
+async fn spot_amounts(client: &Client, account_type: KucoinAccountType) -> ExchangeResult<BTreeMap<Asset, f64>> {
+	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Spot)];
+	let type_param = serde_json::to_value(account_type).expect("unit variants serialize to strings");
+	let params = [("type", type_param.as_str().expect("unit variants serialize to strings"))];
+	let account_response: AccountResponse = client.get("/api/v1/accounts", &params, options).await?;
+	Ok(group_by_type(account_response.data).remove(&account_type).unwrap_or_default())
+}
+
+async fn spot_amounts_by_type(client: &Client) -> ExchangeResult<BTreeMap<KucoinAccountType, BTreeMap<Asset, f64>>> {
+	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Spot)];
+	let empty_params: &[(String, String)] = &[];
+	let account_response: AccountResponse = client.get("/api/v1/accounts", empty_params, options).await?;
+	Ok(group_by_type(account_response.data))
+}
+
+fn group_by_type(accounts: Vec<AccountData>) -> BTreeMap<KucoinAccountType, BTreeMap<Asset, f64>> {
+	let mut by_type: BTreeMap<KucoinAccountType, BTreeMap<Asset, f64>> = BTreeMap::new();
+	for account in accounts {
 		// Only include accounts with non-zero balances
-		if account.balance > 0.0 {
-			let asset: Asset = (&*account.currency).into();
-			let underlying = account.balance;
-			let usd = usd_value(client, underlying, asset, recv_window).await.ok();
+		if account.balance <= 0.0 {
+			continue;
+		}
+		let account_type = match serde_json::from_value::<KucoinAccountType>(serde_json::Value::String(account.account_type.clone())) {
+			Ok(t) => t,
+			Err(_) => {
+				tracing::warn!(target: "v_exchanges::kucoin", "Unknown Kucoin account type `{}`, skipping its {} {}", account.account_type, account.balance, account.currency);
+				continue;
+			}
+		};
+		let asset: Asset = (&*account.currency).into();
+		*by_type.entry(account_type).or_default().entry(asset).or_default() += account.balance;
+	}
+	by_type
+}
 
-			balances.push(AssetBalance { asset, underlying, usd });
+async fn futures_amounts(client: &Client) -> ExchangeResult<BTreeMap<Asset, f64>> {
+	let overviews = join_all(FUTURES_MARGIN_CURRENCIES.iter().map(|currency| async move {
+		let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Perp)];
+		let r: FuturesOverviewResponse = client.get("/api/v1/account-overview", &[("currency", *currency)], options).await?;
+		ExchangeResult::Ok(r)
+	}))
+	.await;
+	let mut amounts = BTreeMap::new();
+	for overview in overviews {
+		let overview = overview?.data;
+		if overview.account_equity > 0.0 {
+			amounts.insert(market::futures::from_kucoin_futures_base(&overview.currency).into(), overview.account_equity);
 		}
 	}
+	Ok(amounts)
+}
+
+/// Balances without a route to USDT on spot are left with `usd: None`.
+async fn valued(client: &Client, amounts: BTreeMap<Asset, f64>) -> Balances {
+	let prices_at = Timestamp::now();
+	let prices = match amounts.iter().any(|(asset, underlying)| *underlying != 0. && *asset != "USDT") {
+		true => market::prices(client, None, None).await.unwrap_or_else(|e| {
+			tracing::warn!(target: "v_exchanges::kucoin", "Failed to fetch kucoin spot prices, balances will be left unvalued: {e}");
+			BTreeMap::new()
+		}),
+		false => BTreeMap::new(),
+	};
+	let mut balances: Vec<AssetBalance> = Vec::with_capacity(amounts.len());
+	for (asset, underlying) in amounts {
+		let usd = match underlying == 0. {
+			true => Some(Usd(0.)),
+			false => convert_with(&prices, prices_at, underlying, asset, "USDT".into()).ok().map(|q| Usd(q.value)),
+		};
+		balances.push(AssetBalance::new(asset, underlying, usd));
+	}
 
 	let total = balances.iter().fold(Usd(0.), |acc, b| {
 		acc + match b.usd {
@@ -97,6 +260,115 @@ pub(super) async fn balances(client: &Client, recv_window: Option<std::time::Dur
 			None => Usd(0.),
 		}
 	});
+	Balances::new(balances, total)
+}
 
-	Ok(Balances::new(balances, total))
+// fees {{{
+#[derive(Debug, Deserialize)]
+struct SpotFeesResponse {
+	data: Vec<FeeRates>,
+}
+#[derive(Debug, Deserialize)]
+struct FuturesFeesResponse {
+	data: FeeRates,
+}
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeRates {
+	#[serde_as(as = "DisplayFromStr")]
+	maker_fee_rate: f64,
+	#[serde_as(as = "DisplayFromStr")]
+	taker_fee_rate: f64,
+}
+
+pub(super) async fn fees(client: &Client, symbol: Symbol) -> ExchangeResult<FeeSchedule> {
+	assert!(client.is_authenticated::<KucoinOption>());
+
+	let rates = match symbol.instrument {
+		Instrument::Spot => {
+			let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Spot)];
+			let kucoin_symbol = symbol.pair.fmt_kucoin();
+			let r: SpotFeesResponse = client.get("/api/v1/trade-fees", &[("symbols", kucoin_symbol)], options).await?;
+			r.data.into_iter().next().ok_or_else(|| eyre!("Kucoin returned no fee rate for {symbol}"))?
+		}
+		Instrument::Perp => {
+			let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Perp)];
+			let kucoin_symbol = format!("{}{}M", market::futures::to_kucoin_futures_base(symbol.pair.base().as_ref()), symbol.pair.quote());
+			let r: FuturesFeesResponse = client.get("/api/v1/trade-fees", &[("symbol", kucoin_symbol)], options).await?;
+			r.data
+		}
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeName::Kucoin, symbol.instrument, "fees"))),
+	};
+	Ok(FeeSchedule {
+		maker_rate: rates.maker_fee_rate,
+		taker_rate: rates.taker_fee_rate,
+		..Default::default()
+	})
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn api_key_permissions() {
+		let json = r#"{"code":"200000","data":{"remark":"bot","apiKey":"6710d2b4e98d4d0001d2a8f8","apiVersion":3,"permission":"General,Spot,Futures","ipWhitelist":"203.0.113.7,203.0.113.8","createdAt":1729155508000,"uid":165111215,"isMaster":true}}"#;
+		let r: KucoinApiKeyResponse = serde_json::from_str(json).unwrap();
+		assert_eq!(KeyPermissions::from(r.data), KeyPermissions {
+			read: true,
+			spot_trade: true,
+			futures_trade: true,
+			withdraw: false,
+			ip_restricted: Some(true),
+			expires: None,
+		});
+	}
+
+	#[test]
+	fn balances_split_across_types() {
+		let json = r#"{"code":"200000","data":[
+			{"id":"1","currency":"USDT","type":"main","balance":"100","available":"100","holds":"0"},
+			{"id":"2","currency":"USDT","type":"trade","balance":"50.5","available":"40.5","holds":"10"},
+			{"id":"3","currency":"BTC","type":"trade","balance":"0.1","available":"0.1","holds":"0"},
+			{"id":"4","currency":"USDT","type":"margin","balance":"25","available":"25","holds":"0"},
+			{"id":"5","currency":"ETH","type":"margin","balance":"0","available":"0","holds":"0"},
+			{"id":"6","currency":"USDT","type":"isolated","balance":"5","available":"5","holds":"0"}
+		]}"#;
+		let response: AccountResponse = serde_json::from_str(json).unwrap();
+		let by_type = group_by_type(response.data);
+
+		let usdt: Asset = "USDT".into();
+		let btc: Asset = "BTC".into();
+		assert_eq!(by_type.keys().copied().collect::<Vec<_>>(), vec![
+			KucoinAccountType::Main,
+			KucoinAccountType::Trade,
+			KucoinAccountType::Margin,
+			KucoinAccountType::Isolated
+		]);
+		assert_eq!(by_type[&KucoinAccountType::Main][&usdt], 100.);
+		assert_eq!(by_type[&KucoinAccountType::Trade][&usdt], 50.5);
+		assert_eq!(by_type[&KucoinAccountType::Trade][&btc], 0.1);
+		assert_eq!(by_type[&KucoinAccountType::Margin].len(), 1, "zero balances are dropped");
+
+		assert_eq!(merged(by_type.clone(), Instrument::Spot)[&usdt], 180.5);
+		let margin = merged(by_type, Instrument::Margin);
+		assert_eq!(margin.keys().collect::<Vec<_>>(), [&usdt], "only margin and isolated count");
+		assert_eq!(margin[&usdt], 30.);
+	}
+
+	#[test]
+	fn spot_account_types_as_query_params() {
+		let params: Vec<serde_json::Value> = SPOT_ACCOUNT_TYPES.iter().map(|t| serde_json::to_value(t).unwrap()).collect();
+		assert_eq!(params, ["main", "trade", "trade_hf", "margin", "isolated"]);
+	}
+
+	#[test]
+	fn parse_futures_overview() {
+		let json = r#"{"code":"200000","data":{"accountEquity":99.8999305281,"unrealisedPNL":0,"marginBalance":99.8999305281,"positionMargin":0,"orderMargin":0,"frozenFunds":0,"availableBalance":99.8999305281,"currency":"XBT"}}"#;
+		let response: FuturesOverviewResponse = serde_json::from_str(json).unwrap();
+		assert_eq!(response.data.account_equity, 99.8999305281);
+		assert_eq!(market::futures::from_kucoin_futures_base(&response.data.currency), "BTC");
+	}
 }
@@ -4,13 +4,13 @@ use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_with::{DisplayFromStr, serde_as};
-use v_exchanges_adapters::kucoin::{KucoinHttpUrl, KucoinOption};
-use v_utils::trades::{Kline, Ohlc, Pair};
+use v_utils::trades::{Kline, Ohlc, Pair, Timeframe};
 
 use crate::{
-	ExchangeResult, RequestRange, Symbol,
+	ExchangeResult, Instrument, PairExchangeFormatExt as _, RequestRange, Symbol,
 	core::{ExchangeInfo, Klines, PairInfo},
-	kucoin::KucoinTimeframe,
+	error::ctx,
+	kucoin::{KucoinTimeframe, http_url},
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -104,17 +104,17 @@ pub mod futures {
 	use jiff::Timestamp;
 	use serde::{Deserialize, Serialize};
 	use serde_json::json;
-	use v_exchanges_adapters::kucoin::{KucoinHttpUrl, KucoinOption};
 	use v_utils::trades::{Kline, Ohlc, Pair};
 
 	use crate::{
-		ExchangeResult, RequestRange, Symbol,
+		ExchangeResult, Instrument, RequestRange, Symbol,
 		core::{ExchangeInfo, Klines, PairInfo},
-		kucoin::KucoinTimeframe,
+		error::ctx,
+		kucoin::{KucoinTimeframe, http_url},
 	};
 
 	/// Kucoin futures uses XBT instead of BTC
-	fn to_kucoin_futures_base(base: &str) -> &str {
+	pub(in crate::kucoin) fn to_kucoin_futures_base(base: &str) -> &str {
 		match base {
 			"BTC" => "XBT",
 			other => other,
@@ -122,7 +122,7 @@ pub mod futures {
 	}
 
 	/// Convert Kucoin futures base currency back to standard format
-	fn from_kucoin_futures_base(base: &str) -> &str {
+	pub(in crate::kucoin) fn from_kucoin_futures_base(base: &str) -> &str {
 		match base {
 			"XBT" => "BTC",
 			other => other,
@@ -131,7 +131,7 @@ pub mod futures {
 
 	// prices {{{
 	pub(in crate::kucoin) async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<Vec<Pair>>, _recv_window: Option<std::time::Duration>) -> ExchangeResult<BTreeMap<Pair, f64>> {
-		let options = vec![KucoinOption::HttpUrl(KucoinHttpUrl::Futures)];
+		let options = vec![http_url(client, Instrument::Perp)];
 		let response: ContractsActiveResponse = client.get("/api/v1/contracts/active", &json!({}), options).await?;
 
 		let mut price_map = BTreeMap::default();
@@ -160,6 +160,13 @@ pub mod futures {
 		Ok(price_map)
 	}
 
+	/// Every contract currently listed, with the specs (multiplier, tick and lot size) that [ExchangeInfo] doesn't carry.
+	pub(in crate::kucoin) async fn contracts(client: &v_exchanges_adapters::Client) -> ExchangeResult<Vec<ContractInfo>> {
+		let options = vec![http_url(client, Instrument::Perp)];
+		let response: ContractsActiveResponse = client.get("/api/v1/contracts/active", &json!({}), options).await?;
+		Ok(response.data)
+	}
+
 	#[derive(Debug, Deserialize, Serialize)]
 	pub struct ContractsActiveResponse {
 		pub code: String,
@@ -219,8 +226,8 @@ pub mod futures {
 			"to": to_ts,
 		});
 
-		let options = vec![KucoinOption::HttpUrl(KucoinHttpUrl::Futures)];
-		let response: FuturesKlineResponse = client.get("/api/v1/kline/query", &params, options).await?;
+		let options = vec![http_url(client, Instrument::Perp)];
+		let response: FuturesKlineResponse = ctx!(client.get("/api/v1/kline/query", &params, options).await, Kucoin.klines(symbol, tf) -> "/api/v1/kline/query")?;
 
 		let mut klines_vec = VecDeque::default();
 
@@ -258,7 +265,7 @@ pub mod futures {
 
 	// exchange_info {{{
 	pub(in crate::kucoin) async fn exchange_info(client: &v_exchanges_adapters::Client, _recv_window: Option<std::time::Duration>) -> ExchangeResult<ExchangeInfo> {
-		let options = vec![KucoinOption::HttpUrl(KucoinHttpUrl::Futures)];
+		let options = vec![http_url(client, Instrument::Perp)];
 		let response: ContractsActiveResponse = client.get("/api/v1/contracts/active", &json!({}), options).await?;
 
 		let mut pairs = BTreeMap::default();
@@ -281,6 +288,13 @@ pub mod futures {
 				price_precision,
 				qty_precision,
 				delivery_date: None,
+				min_qty: None,
+				min_notional: None,
+				max_qty: None,
+				qty_step: None,
+				min_price: None,
+				max_price: None,
+				status: Some(contract.status),
 			};
 			pairs.insert(pair, pair_info);
 		}
@@ -294,7 +308,7 @@ pub mod futures {
 }
 // prices {{{
 pub(super) async fn prices(client: &v_exchanges_adapters::Client, pairs: Option<Vec<Pair>>, _recv_window: Option<std::time::Duration>) -> ExchangeResult<BTreeMap<Pair, f64>> {
-	let options = vec![KucoinOption::HttpUrl(KucoinHttpUrl::Spot)];
+	let options = vec![http_url(client, Instrument::Spot)];
 	let response: AllTickersResponse = client.get("/api/v1/market/allTickers", &json!({}), options).await?;
 
 	let mut price_map = BTreeMap::default();
@@ -328,7 +342,7 @@ pub(super) async fn klines(
 	range: RequestRange,
 	_recv_window: Option<std::time::Duration>,
 ) -> ExchangeResult<Klines> {
-	let kucoin_symbol = format!("{}-{}", symbol.pair.base(), symbol.pair.quote());
+	let kucoin_symbol = symbol.pair.fmt_kucoin();
 
 	// Convert from v_utils format (1h, 1d, 1w) to Kucoin API format (1hour, 1day, 1week)
 	let tf_str = tf.to_string();
@@ -353,43 +367,47 @@ pub(super) async fn klines(
 	params.push(("startAt", &start_at));
 	params.push(("endAt", &end_at));
 
-	let options = vec![KucoinOption::HttpUrl(KucoinHttpUrl::Spot)];
-	let response: KlineResponse = client.get("/api/v1/market/candles", &params, options).await?;
-
-	let mut klines_vec = VecDeque::default();
+	let options = vec![http_url(client, Instrument::Spot)];
+	let response: KlineResponse = ctx!(client.get("/api/v1/market/candles", &params, options).await, Kucoin.klines(symbol, tf) -> "/api/v1/market/candles")?;
+	response.into_klines(*tf)
+}
+impl KlineResponse {
+	fn into_klines(self, tf: Timeframe) -> ExchangeResult<Klines> {
+		let mut klines_vec = VecDeque::default();
 
-	// Kucoin returns klines in descending order (newest first), so we need to reverse
-	for kline_data in response.data.iter().rev() {
-		// kline_data format: [time, open, close, high, low, volume, turnover]
-		if kline_data.len() >= 7 {
-			let timestamp_str = &kline_data[0];
-			let timestamp_secs: i64 = timestamp_str.parse().map_err(|e| eyre::eyre!("Failed to parse timestamp: {e}"))?;
+		// Kucoin returns klines in descending order (newest first), so we need to reverse
+		for kline_data in self.data.iter().rev() {
+			// kline_data format: [time, open, close, high, low, volume, turnover]
+			if kline_data.len() >= 7 {
+				let timestamp_str = &kline_data[0];
+				let timestamp_secs: i64 = timestamp_str.parse().map_err(|e| eyre::eyre!("Failed to parse timestamp: {e}"))?;
 
-			let ohlc = Ohlc {
-				open: kline_data[1].parse().map_err(|e| eyre::eyre!("Failed to parse open: {e}"))?,
-				high: kline_data[3].parse().map_err(|e| eyre::eyre!("Failed to parse high: {e}"))?,
-				low: kline_data[4].parse().map_err(|e| eyre::eyre!("Failed to parse low: {e}"))?,
-				close: kline_data[2].parse().map_err(|e| eyre::eyre!("Failed to parse close: {e}"))?,
-			};
+				let ohlc = Ohlc {
+					open: kline_data[1].parse().map_err(|e| eyre::eyre!("Failed to parse open: {e}"))?,
+					high: kline_data[3].parse().map_err(|e| eyre::eyre!("Failed to parse high: {e}"))?,
+					low: kline_data[4].parse().map_err(|e| eyre::eyre!("Failed to parse low: {e}"))?,
+					close: kline_data[2].parse().map_err(|e| eyre::eyre!("Failed to parse close: {e}"))?,
+				};
 
-			klines_vec.push_back(Kline {
-				open_time: Timestamp::from_second(timestamp_secs).map_err(|e| eyre::eyre!("Invalid timestamp: {e}"))?,
-				ohlc,
-				volume_quote: kline_data[6].parse().map_err(|e| eyre::eyre!("Failed to parse turnover: {e}"))?,
-				trades: None,
-				taker_buy_volume_quote: None,
-			});
+				klines_vec.push_back(Kline {
+					open_time: Timestamp::from_second(timestamp_secs).map_err(|e| eyre::eyre!("Invalid timestamp: {e}"))?,
+					ohlc,
+					volume_quote: kline_data[6].parse().map_err(|e| eyre::eyre!("Failed to parse turnover: {e}"))?,
+					trades: None,
+					taker_buy_volume_quote: None,
+				});
+			}
 		}
-	}
 
-	Ok(Klines::new(klines_vec, *tf))
+		Ok(Klines::new(klines_vec, tf))
+	}
 }
 
 //,}}}
 
 // exchange_info {{{
 pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, _recv_window: Option<std::time::Duration>) -> ExchangeResult<ExchangeInfo> {
-	let options = vec![KucoinOption::HttpUrl(KucoinHttpUrl::Spot)];
+	let options = vec![http_url(client, Instrument::Spot)];
 	let response: SymbolsResponse = client.get("/api/v2/symbols", &json!({}), options).await?;
 
 	let mut pairs = BTreeMap::default();
@@ -407,6 +425,13 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, _recv_w
 				price_precision,
 				qty_precision,
 				delivery_date: None,
+				min_qty: Some(symbol.base_min_size),
+				min_notional: Some(symbol.quote_min_size),
+				max_qty: None,
+				qty_step: None,
+				min_price: None,
+				max_price: None,
+				status: None,
 			};
 			pairs.insert(pair, pair_info);
 		}
@@ -423,3 +448,44 @@ pub(super) async fn exchange_info(client: &v_exchanges_adapters::Client, _recv_w
 // ============================================================================
 // Futures Market Data
 // ============================================================================
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::KlinesGuard;
+
+	/// 1m BTC-USDT candles shaped like what Kucoin serves across a matching engine outage: all-zero placeholders for the frozen minutes, and one of them sent twice. Newest first, as returned.
+	const OUTAGE_CANDLES: &str = r#"{"code":"200000","data":[
+		["1700000400","37012.1","37020.4","37025","37008.3","3.1205","115502.52"],
+		["1700000340","0","0","0","0","0","0"],
+		["1700000340","0","0","0","0","0","0"],
+		["1700000280","0","0","0","0","0","0"],
+		["1700000220","0","0","0","0","0","0"],
+		["1700000160","36998.7","37012.1","37014.2","36995","4.0087","148360.11"],
+		["1700000100","36990.2","36998.7","37001.5","36988.4","2.5531","94467.3"]
+	]}"#;
+
+	#[test]
+	fn outage_candles_are_degenerate() {
+		let response: KlineResponse = serde_json::from_str(OUTAGE_CANDLES).unwrap();
+		let klines = response.into_klines("1m".into()).unwrap();
+		let q = klines.quality_report();
+		assert_eq!(q.len, 7);
+		assert_eq!(q.zero_ohlc_count, 4);
+		assert_eq!(q.zero_volume_count, 4);
+		assert_eq!(q.duplicate_times, 1);
+		assert_eq!(q.out_of_order, 0);
+		assert!(!q.monotonic_time);
+		assert!(q.is_degenerate(&KlinesGuard::default()));
+	}
+
+	#[test]
+	fn healthy_candles_pass() {
+		let mut response: KlineResponse = serde_json::from_str(OUTAGE_CANDLES).unwrap();
+		response.data.retain(|k| k[1] != "0");
+		let q = response.into_klines("1m".into()).unwrap().quality_report();
+		assert_eq!(q.zero_ohlc_count, 0);
+		assert!(q.monotonic_time);
+		assert!(!q.is_degenerate(&KlinesGuard::default()));
+	}
+}
@@ -1,36 +1,105 @@
 mod account;
 mod market;
+#[cfg(feature = "kucoin-ws")]
+mod ws;
 
+pub use account::{BalancesByType, KucoinAccountType};
 pub use adapters::kucoin::KucoinOption;
 
-crate::define_provider_timeframe!(KucoinTimeframe, ["1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "1w"]);
-use std::collections::BTreeMap;
+crate::define_provider_timeframe!(KucoinTimeframe, ["1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "1w"], test = kucoin_timeframe_table);
+use std::{collections::BTreeMap, sync::Arc};
 
+#[cfg(feature = "kucoin-ws")]
+use adapters::{
+	generics::ws::{SubscriptionSnapshot, WsConnection},
+	kucoin::KucoinWsHandler,
+};
+use adapters::kucoin::{KucoinHttpUrl, KucoinOptions};
 use secrecy::SecretString;
-use v_exchanges_adapters::Client;
+use v_exchanges_adapters::{Client, GetOptions};
 use v_utils::trades::{Pair, Timeframe};
 
+#[cfg(feature = "kucoin-ws")]
+use crate::{BatchTrades, ExchangeError, ExchangeStream, MethodError, PairExchangeFormatExt as _, PrecisionPriceQty};
 use crate::{
-	ExchangeName, ExchangeResult, Instrument, RequestRange, Symbol,
-	core::{ExchangeImpl, ExchangeInfo, Klines, PersonalInfo},
+	AccountLabel, ExchangeName, ExchangeResult, Instrument, MutationJournal, RequestRange, Symbol, Venue,
+	core::{ExchangeImpl, ExchangeInfo, FeeSchedule, InfoCache, KeyPermissions, Klines, KlinesGuard, PersonalInfo},
 };
 
+/// [KucoinOption::HttpUrl] for `instrument`, as mapped by the client's [KucoinOptions::http_url_for].
+pub(crate) fn http_url(client: &Client, instrument: Instrument) -> KucoinOption {
+	KucoinOption::HttpUrl(GetOptions::<KucoinOptions>::default_options(client).http_url_for(instrument))
+}
+
 #[derive(Clone, Debug, Default, derive_more::Deref, derive_more::DerefMut)]
 pub struct Kucoin {
 	#[deref]
 	#[deref_mut]
 	pub client: Client,
-	pub info_cache: BTreeMap<Instrument, ExchangeInfo>,
+	pub info_cache: InfoCache,
+	pub label: Option<AccountLabel>,
+	pub log_context: Option<String>,
+	pub klines_guard: Option<KlinesGuard>,
+	pub mutation_journal: Option<Arc<dyn MutationJournal>>,
+}
+impl Kucoin {
+	/// Base url requests for `instrument` go to. Override with [KucoinOption::InstrumentHttpUrl].
+	pub fn http_url_for(&self, instrument: Instrument) -> KucoinHttpUrl {
+		GetOptions::<KucoinOptions>::default_options(&self.client).http_url_for(instrument)
+	}
+
+	/// Balances kept apart per account type, where [personal_info](crate::Exchange::personal_info) sums the spot-side ones up. Types are fetched independently, so a failure only costs the one it happened on, and is listed in [failed](BalancesByType::failed).
+	pub async fn balances_by_type(&self, recv_window: Option<std::time::Duration>) -> BalancesByType {
+		account::balances_by_type(self, recv_window).await
+	}
+
+	/// Authenticated futures connection, streaming `/contractAccount/wallet` balance and `/contractMarket/tradeOrders` order updates. Requires the passphrase to be set.
+	#[cfg(feature = "kucoin-ws")]
+	pub async fn private_ws(&self) -> ExchangeResult<WsConnection<KucoinWsHandler>> {
+		ws::private_connection(self).await
+	}
 }
 
 #[async_trait::async_trait]
 impl ExchangeImpl for Kucoin {
-	fn info_cache_mut(&mut self) -> &mut BTreeMap<Instrument, ExchangeInfo> {
-		&mut self.info_cache
+	fn info_cache(&self) -> &InfoCache {
+		&self.info_cache
 	}
 
-	fn name(&self) -> ExchangeName {
-		ExchangeName::Kucoin
+	fn label(&self) -> Option<&AccountLabel> {
+		self.label.as_ref()
+	}
+
+	fn label_mut(&mut self) -> &mut Option<AccountLabel> {
+		&mut self.label
+	}
+
+	fn log_context(&self) -> Option<&str> {
+		self.log_context.as_deref()
+	}
+
+	fn log_context_mut(&mut self) -> &mut Option<String> {
+		&mut self.log_context
+	}
+
+	fn klines_guard(&self) -> Option<KlinesGuard> {
+		self.klines_guard
+	}
+
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard> {
+		&mut self.klines_guard
+	}
+
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>> {
+		self.mutation_journal.as_ref()
+	}
+
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>> {
+		&mut self.mutation_journal
+	}
+
+	fn name(&self) -> Venue {
+		ExchangeName::Kucoin.into()
 	}
 
 	fn auth(&mut self, pubkey: String, secret: SecretString) {
@@ -40,7 +109,7 @@ impl ExchangeImpl for Kucoin {
 	}
 
 	fn set_recv_window(&mut self, _recv_window: std::time::Duration) {
-		tracing::warn!("KuCoin does not support configurable recv_window - uses a fixed 5-second tolerance window for all authenticated requests");
+		tracing::warn!(target: "v_exchanges::kucoin", "KuCoin does not support configurable recv_window - uses a fixed 5-second tolerance window for all authenticated requests");
 	}
 
 	fn default_recv_window(&self) -> Option<std::time::Duration> {
@@ -51,7 +120,7 @@ impl ExchangeImpl for Kucoin {
 		match instrument {
 			Instrument::Spot => market::exchange_info(self, None).await,
 			Instrument::Perp => market::futures::exchange_info(self, None).await,
-			_ => unimplemented!(),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "exchange_info"))),
 		}
 	}
 
@@ -59,7 +128,7 @@ impl ExchangeImpl for Kucoin {
 		match instrument {
 			Instrument::Spot => market::prices(self, pairs, None).await,
 			Instrument::Perp => market::futures::prices(self, pairs, None).await,
-			_ => unimplemented!(),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "prices"))),
 		}
 	}
 
@@ -67,11 +136,63 @@ impl ExchangeImpl for Kucoin {
 		match symbol.instrument {
 			Instrument::Spot => market::klines(self, symbol, tf.try_into()?, range, None).await,
 			Instrument::Perp => market::futures::klines(self, symbol, tf.try_into()?, range, None).await,
-			_ => unimplemented!(),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "klines"))),
+		}
+	}
+
+	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
+		account::personal_info(self, instrument, recv_window).await
+	}
+
+	async fn fees(&self, symbol: Symbol, _recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+		account::fees(self, symbol).await
+	}
+
+	async fn key_permissions(&self) -> ExchangeResult<KeyPermissions> {
+		account::key_permissions(self).await
+	}
+
+	/// Fetches a fresh public token per call; see [KucoinBullet](adapters::kucoin::KucoinBullet) on how long it stays good for reconnects. Futures sizes are converted from contracts into the base asset.
+	#[cfg(feature = "kucoin-ws")]
+	async fn ws_trades(&mut self, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		match instrument {
+			Instrument::Spot => {
+				let exchange = self.name();
+				let info = self.info_cache.get_or_fetch(instrument, ExchangeImpl::exchange_info(&*self, instrument)).await?;
+				let symbols = pairs
+					.iter()
+					.map(|pair| {
+						info.pairs
+							.get(pair)
+							.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(exchange, instrument, *pair)))
+							.map(|pi| {
+								let symbol = ws::TradeSymbol {
+									pair: *pair,
+									prec: PrecisionPriceQty {
+										price: pi.price_precision,
+										qty: pi.qty_precision,
+									},
+									multiplier: 1.,
+								};
+								(pair.fmt_kucoin(), symbol)
+							})
+					})
+					.collect::<ExchangeResult<_>>()?;
+				Ok(Box::new(ws::TradesConnection::try_new(self, instrument, symbols).await?))
+			}
+			Instrument::Perp => {
+				let symbols = ws::futures_trade_symbols(self, pairs).await?;
+				Ok(Box::new(ws::TradesConnection::try_new(self, instrument, symbols).await?))
+			}
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "ws_trades"))),
 		}
 	}
 
-	async fn personal_info(&self, _instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
-		account::personal_info(self, recv_window).await
+	/// On a fresh token, like [ws_trades](ExchangeImpl::ws_trades): the one the snapshot was taken under may be long expired.
+	#[cfg(feature = "kucoin-ws")]
+	async fn ws_trades_resume(&mut self, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		let pairs = ws::snapshot_trade_pairs(self, instrument, snapshot).await?;
+		let stream = ExchangeImpl::ws_trades(self, &pairs, instrument).await?;
+		crate::core::resumed(snapshot, stream)
 	}
 }
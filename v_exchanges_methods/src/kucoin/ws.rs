@@ -0,0 +1,323 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use adapters::{
+	Client,
+	generics::{
+		reqwest::Url,
+		ws::{LatencyStats, SubscriptionSnapshot, WsConnection, WsError},
+	},
+	kucoin::{KucoinAuth, KucoinBullet, KucoinOption, KucoinWsHandler},
+};
+use jiff::{SignedDuration, Timestamp};
+use serde::Deserialize;
+use serde_with::{DisplayFromStr, PickFirst, serde_as};
+use v_utils::trades::{Pair, Side};
+
+use super::market;
+use crate::{BatchTrades, ExchangeError, ExchangeName, ExchangeResult, ExchangeStream, Instrument, MethodError, PrecisionPriceQty, core::InnerTrade, kucoin::http_url};
+
+// trades {{{
+/// Kucoin's spot topics take up to this many comma-joined symbols.
+const SPOT_SYMBOLS_PER_TOPIC: usize = 100;
+
+/// What a trade event's `symbol` maps back to.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct TradeSymbol {
+	pub pair: Pair,
+	pub prec: PrecisionPriceQty,
+	/// Base asset per contract on futures, where sizes come in contracts; `1.` on spot.
+	pub multiplier: f64,
+}
+
+/// Public trades: spot `/market/match`, futures `/contractMarket/execution`.
+#[derive(Debug)]
+pub struct TradesConnection {
+	connection: WsConnection<KucoinWsHandler>,
+	instrument: Instrument,
+	/// Keyed by Kucoin's own symbol, as that's what events carry.
+	symbols: BTreeMap<String, TradeSymbol>,
+}
+impl TradesConnection {
+	pub(super) async fn try_new(client: &Client, instrument: Instrument, symbols: BTreeMap<String, TradeSymbol>) -> ExchangeResult<Self> {
+		let bullet = bullet_public(client, instrument).await?;
+		let names: Vec<&str> = symbols.keys().map(String::as_str).collect();
+		let topics = match instrument {
+			Instrument::Spot => names.chunks(SPOT_SYMBOLS_PER_TOPIC).map(|chunk| format!("/market/match:{}", chunk.join(","))).collect(),
+			// futures execution topic is per symbol
+			Instrument::Perp => names.iter().map(|s| format!("/contractMarket/execution:{s}")).collect(),
+			_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Kucoin, instrument, "ws_trades"))),
+		};
+		let connection = client.ws_connection("", vec![KucoinOption::WsBullet(bullet), KucoinOption::WsTopics(topics)])?;
+		Ok(Self { connection, instrument, symbols })
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for TradesConnection {
+	type Item = BatchTrades;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.connection.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		Some(self.connection.subscription_snapshot())
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.connection.set_clock_offset(offset);
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let batch = self.connection.next().await?;
+		// same grouping as Binance's: one `BatchTrades` per pair, arrival order within it
+		let mut by_pair: BTreeMap<Pair, (PrecisionPriceQty, Vec<InnerTrade>)> = BTreeMap::new();
+		for content_event in batch {
+			let (symbol, trade) = match self.instrument {
+				Instrument::Spot => parse_match(content_event.data, &self.symbols)?,
+				_ => parse_execution(content_event.data, &self.symbols)?,
+			};
+			by_pair.entry(symbol.pair).or_insert((symbol.prec, Vec::new())).1.push(trade);
+		}
+		let now = Timestamp::now();
+		Ok(by_pair.into_iter().map(|(_, (prec, trades))| BatchTrades::new(prec, trades, now, now)).collect())
+	}
+}
+
+/// Symbols of `pairs` on futures, resolved against the live contract list. Pairs with several contracts (eg quarterlies) take the perpetual.
+pub(super) async fn futures_trade_symbols(client: &Client, pairs: &[Pair]) -> ExchangeResult<BTreeMap<String, TradeSymbol>> {
+	let contracts = market::futures::contracts(client).await?;
+	pairs
+		.iter()
+		.map(|pair| {
+			let contract = contracts
+				.iter()
+				.filter(|c| c.status == "Open" && c.symbol.ends_with('M'))
+				.find(|c| Pair::new(market::futures::from_kucoin_futures_base(&c.base_currency), c.quote_currency.as_str()) == *pair)
+				.ok_or_else(|| ExchangeError::Method(MethodError::new_pair_not_listed(ExchangeName::Kucoin, Instrument::Perp, *pair)))?;
+			let symbol = TradeSymbol {
+				pair: *pair,
+				prec: PrecisionPriceQty {
+					price: step_precision(contract.tick_size),
+					qty: step_precision(contract.lot_size * contract.multiplier),
+				},
+				multiplier: contract.multiplier,
+			};
+			Ok((contract.symbol.clone(), symbol))
+		})
+		.collect()
+}
+
+/// Pairs of the trade topics in `snapshot`. Spot topics name them as Kucoin symbols (`/market/match:BTC-USDT,ETH-USDT`), futures ones as contracts (`/contractMarket/execution:XBTUSDTM`), which are looked up here.
+pub(super) async fn snapshot_trade_pairs(client: &Client, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Vec<Pair>> {
+	let symbols = snapshot
+		.topics
+		.iter()
+		.map(|topic| topic.split_once(':').map(|(_, symbols)| symbols.split(',')).ok_or_else(|| eyre::eyre!("No symbols in snapshot topic {topic}")))
+		.collect::<eyre::Result<Vec<_>>>()?
+		.into_iter()
+		.flatten();
+	let pairs: std::collections::BTreeSet<Pair> = match instrument {
+		Instrument::Spot => symbols
+			.map(|s| s.split_once('-').map(|(base, quote)| Pair::new(base, quote)).ok_or_else(|| eyre::eyre!("Unexpected Kucoin symbol {s}")))
+			.collect::<eyre::Result<_>>()?,
+		Instrument::Perp => {
+			let contracts = market::futures::contracts(client).await?;
+			symbols
+				.map(|s| {
+					let c = contracts.iter().find(|c| c.symbol == s).ok_or_else(|| eyre::eyre!("Kucoin lists no contract {s}"))?;
+					Ok(Pair::new(market::futures::from_kucoin_futures_base(&c.base_currency), c.quote_currency.as_str()))
+				})
+				.collect::<eyre::Result<_>>()?
+		}
+		_ => return Err(ExchangeError::Method(MethodError::new_method_not_implemented(ExchangeName::Kucoin, instrument, "ws_trades_resume"))),
+	};
+	Ok(pairs.into_iter().collect())
+}
+
+fn step_precision(step: f64) -> u8 {
+	if step == 0.0 { 0 } else { (-step.log10()).max(0.0).round() as u8 }
+}
+
+fn parse_match(data: serde_json::Value, symbols: &BTreeMap<String, TradeSymbol>) -> Result<(TradeSymbol, InnerTrade), WsError> {
+	let m: MatchEvent = serde_json::from_value(data.clone()).map_err(WsError::Parse)?;
+	let symbol = *symbols.get(&m.symbol).ok_or_else(|| WsError::UnexpectedEvent(data.clone()))?;
+	let ns: i128 = m.time.parse().map_err(|_| WsError::UnexpectedEvent(data.clone()))?;
+	let trade = InnerTrade {
+		id: m.trade_id.parse().ok(),
+		time: Timestamp::from_nanosecond(ns).map_err(|_| WsError::UnexpectedEvent(data))?,
+		price: symbol.prec.parse_price(&m.price),
+		qty: symbol.prec.parse_qty(&m.size),
+		side: taker_side(&m.side),
+	};
+	Ok((symbol, trade))
+}
+
+fn parse_execution(data: serde_json::Value, symbols: &BTreeMap<String, TradeSymbol>) -> Result<(TradeSymbol, InnerTrade), WsError> {
+	let e: ExecutionEvent = serde_json::from_value(data.clone()).map_err(WsError::Parse)?;
+	let symbol = *symbols.get(&e.symbol).ok_or_else(|| WsError::UnexpectedEvent(data.clone()))?;
+	// contracts -> base asset, formatted at the precision it was derived with, so float noise can't leak into the parse
+	let qty_asset = format!("{:.*}", symbol.prec.qty as usize, e.size * symbol.multiplier);
+	let trade = InnerTrade {
+		id: e.trade_id.parse().ok(),
+		time: Timestamp::from_nanosecond(e.ts as i128).map_err(|_| WsError::UnexpectedEvent(data))?,
+		price: symbol.prec.parse_price(&e.price.to_string()),
+		qty: symbol.prec.parse_qty(&qty_asset),
+		side: taker_side(&e.side),
+	};
+	Ok((symbol, trade))
+}
+
+/// `side` of both trade events is the taker's.
+fn taker_side(s: &str) -> Option<Side> {
+	match s {
+		"buy" => Some(Side::Buy),
+		"sell" => Some(Side::Sell),
+		_ => None,
+	}
+}
+
+/// Docs: https://www.kucoin.com/docs-new/websocket-api/spot-trading/public-channels/trade
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchEvent {
+	symbol: String,
+	trade_id: String,
+	side: String,
+	price: String,
+	size: String,
+	/// Nanoseconds, as a string.
+	time: String,
+}
+
+/// Docs: https://www.kucoin.com/docs-new/websocket-api/futures-trading/public-channels/execution
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecutionEvent {
+	symbol: String,
+	trade_id: String,
+	side: String,
+	/// Sent as a number or a string, depending on the contract.
+	#[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+	price: f64,
+	/// In contracts.
+	#[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+	size: f64,
+	/// Nanoseconds.
+	ts: i64,
+}
+//,}}}
+
+// private {{{
+/// Futures account connection, streaming wallet balance and order updates. See [KucoinOption::PrivateWs].
+pub(super) async fn private_connection(client: &Client) -> ExchangeResult<WsConnection<KucoinWsHandler>> {
+	let bullet = bullet_private(client).await?;
+	let connection = client.ws_connection("", vec![KucoinOption::PrivateWs(true), KucoinOption::WsBullet(bullet)])?;
+	Ok(connection)
+}
+
+async fn bullet_private(client: &Client) -> ExchangeResult<KucoinBullet> {
+	let options = vec![KucoinOption::HttpAuth(KucoinAuth::Sign), http_url(client, Instrument::Perp)];
+	let response: BulletResponse = client.post_no_body("/api/v1/bullet-private", options).await?;
+	response.into_bullet("bullet-private")
+}
+//,}}}
+
+/// Token for public channels of `instrument`'s host. No credentials needed.
+async fn bullet_public(client: &Client, instrument: Instrument) -> ExchangeResult<KucoinBullet> {
+	let response: BulletResponse = client.post_no_body("/api/v1/bullet-public", vec![http_url(client, instrument)]).await?;
+	response.into_bullet("bullet-public")
+}
+
+#[derive(Debug, Deserialize)]
+struct BulletResponse {
+	data: BulletData,
+}
+impl BulletResponse {
+	fn into_bullet(self, endpoint_name: &str) -> ExchangeResult<KucoinBullet> {
+		let server = self
+			.data
+			.instance_servers
+			.into_iter()
+			.next()
+			.ok_or_else(|| ExchangeError::Other(eyre::eyre!("{endpoint_name} returned no instance servers")))?;
+		let endpoint = Url::parse(&server.endpoint).map_err(|e| ExchangeError::Other(eyre::eyre!("{endpoint_name} returned an invalid endpoint `{}`: {e}", server.endpoint)))?;
+		Ok(KucoinBullet {
+			token: self.data.token.into(),
+			endpoint,
+			ping_interval: Duration::from_millis(server.ping_interval),
+		})
+	}
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BulletData {
+	token: String,
+	instance_servers: Vec<InstanceServer>,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InstanceServer {
+	endpoint: String,
+	ping_interval: u64,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::TradeId;
+
+	#[test]
+	fn parse_bullet() {
+		let json = r#"{"code":"200000","data":{"token":"2neAiuYvAU61ZDXANAGAsiL4","instanceServers":[{"endpoint":"wss://ws-api-futures.kucoin.com/","encrypt":true,"protocol":"websocket","pingInterval":18000,"pingTimeout":10000}]}}"#;
+		let response: BulletResponse = serde_json::from_str(json).unwrap();
+		assert_eq!(response.data.token, "2neAiuYvAU61ZDXANAGAsiL4");
+		assert_eq!(response.data.instance_servers[0].endpoint, "wss://ws-api-futures.kucoin.com/");
+		assert_eq!(response.data.instance_servers[0].ping_interval, 18_000);
+	}
+
+	#[test]
+	fn parse_spot_match() {
+		let json = r#"{"makerOrderId":"671b5007389355000701b1d3","price":"67523","sequence":"11067996711960577","side":"buy","size":"0.003","symbol":"BTC-USDT","takerOrderId":"671b50161777ff00074c168d","time":"1729843222921237203","tradeId":"11067996711960577","type":"match"}"#;
+		let prec = PrecisionPriceQty { price: 1, qty: 8 };
+		let symbols = BTreeMap::from([("BTC-USDT".to_owned(), TradeSymbol {
+			pair: Pair::new("BTC", "USDT"),
+			prec,
+			multiplier: 1.,
+		})]);
+		let (symbol, trade) = parse_match(serde_json::from_str(json).unwrap(), &symbols).unwrap();
+		assert_eq!(symbol.pair, Pair::new("BTC", "USDT"));
+		// nanoseconds, not the millis every other Kucoin timestamp is in
+		assert_eq!(trade.time.as_millisecond(), 1729843222921);
+		assert_eq!(trade.price, prec.parse_price("67523"));
+		assert_eq!(trade.qty, prec.parse_qty("0.003"));
+		assert_eq!(trade.side, Some(Side::Buy));
+	}
+
+	#[test]
+	fn parse_futures_execution() {
+		let json = r#"{"symbol":"XBTUSDTM","sequence":1697004,"side":"sell","size":7,"price":"67310.1","takerOrderId":"1fd6ac3dbbe9b3d4a51a8e4e","makerOrderId":"4a2ff4bd1ba0d84f1fa3dc05","tradeId":"1697004","ts":1729843222921237000}"#;
+		// lot of 1 contract, 0.001 BTC each
+		let prec = PrecisionPriceQty {
+			price: step_precision(0.1),
+			qty: step_precision(1. * 0.001),
+		};
+		let symbols = BTreeMap::from([("XBTUSDTM".to_owned(), TradeSymbol {
+			pair: Pair::new("BTC", "USDT"),
+			prec,
+			multiplier: 0.001,
+		})]);
+		let (symbol, trade) = parse_execution(serde_json::from_str(json).unwrap(), &symbols).unwrap();
+		assert_eq!(symbol.pair, Pair::new("BTC", "USDT"));
+		assert_eq!(trade.time.as_millisecond(), 1729843222921);
+		assert_eq!(trade.price, prec.parse_price("67310.1"));
+		// 7 contracts * 0.001
+		assert_eq!(trade.qty, prec.parse_qty("0.007"));
+		assert_eq!((trade.id, trade.side), (Some(TradeId::Numeric(1697004)), Some(Side::Sell)));
+
+		// numeric price parses the same
+		let json = json.replace(r#""price":"67310.1""#, r#""price":67310.1"#);
+		let (_, numeric) = parse_execution(serde_json::from_str(&json).unwrap(), &symbols).unwrap();
+		assert_eq!(numeric.price, trade.price);
+	}
+}
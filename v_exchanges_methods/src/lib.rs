@@ -10,10 +10,25 @@ pub extern crate v_exchanges_adapters as adapters;
 pub use v_exchanges_core::{Price, Qty};
 pub use v_utils::trades::Timestamped;
 
+pub mod book;
+pub mod candles;
+pub mod config;
 pub mod core;
+pub mod custom;
+#[cfg(feature = "polars")]
+#[cfg_attr(docsrs, doc(cfg(feature = "polars")))]
+pub mod dataframe;
+pub mod dedup;
+pub mod divergence;
+pub mod equity;
+pub mod fills;
+pub mod funding;
+pub mod journal;
 // false positive: derive_new generates assignments that rustc thinks are dead, but fields are read by thiserror/Display
 #[allow(unused_assignments)]
 pub mod error;
+pub mod execution;
+pub mod listings;
 pub mod prelude {
 	pub use std::{
 		collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
@@ -26,6 +41,9 @@ pub mod prelude {
 	pub use adapters::generics::RetryConfig;
 	pub use eyre::{OptionExt as _, Report, Result, WrapErr as _, bail, eyre};
 	pub use futures_util::future::join_all;
+	pub use jiff::Timestamp; // `jiff::Span` would clash with `tracing::Span` below
+	#[cfg(feature = "decimal")]
+	pub use rust_decimal::Decimal;
 	pub use serde::{
 		Deserialize, Serialize, Serializer,
 		de::{DeserializeOwned, Deserializer},
@@ -33,46 +51,60 @@ pub mod prelude {
 	pub use serde_json::{Value, json};
 	pub use thiserror::Error;
 	pub use tracing::{Span, debug, error, field::Empty, info, instrument, trace, warn};
-	pub use v_utils::trades::*;
+	pub use v_utils::{NowThen, trades::*};
 
-	#[cfg(feature = "binance")]
+	#[cfg(feature = "binance-http")]
 	pub use crate::binance::Binance;
 	// TODO: bitflyer implementation not yet complete
 	// #[cfg(feature = "bitflyer")]
 	// pub use crate::bitflyer::Bitflyer;
 	#[cfg(feature = "data")]
 	pub use crate::bitmex::Bitmex;
-	#[cfg(feature = "bybit")]
+	#[cfg(feature = "bybit-http")]
 	pub use crate::bybit::Bybit;
 	// TODO: coincheck implementation not yet complete
 	// #[cfg(feature = "coincheck")]
 	// pub use crate::coincheck::Coincheck;
-	#[cfg(feature = "kucoin")]
+	#[cfg(feature = "kucoin-http")]
 	pub use crate::kucoin::Kucoin;
-	#[cfg(feature = "mexc")]
+	#[cfg(feature = "mexc-http")]
 	pub use crate::mexc::Mexc;
+	#[cfg(feature = "polars")]
+	pub use crate::dataframe::*;
 	#[cfg(feature = "data")]
 	pub use crate::yahoo::*;
-	pub use crate::{Price, Qty, Timestamped, core::*, error::*, orders::*, other_types::*};
+	pub use crate::{Price, Qty, Timestamped, book::*, candles::*, config::*, core::*, dedup::*, divergence::*, equity::*, error::*, execution::*, fills::*, funding::*, journal::*, listings::*, multi_account::*, orders::*, other_types::*, router::*, utils::PairExchangeFormatExt, watchdog::*, watermark::*};
 }
-#[cfg(feature = "binance")]
-#[cfg_attr(docsrs, doc(cfg(feature = "binance")))]
+#[cfg(feature = "binance-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "binance-http")))]
 pub mod binance;
-#[cfg(feature = "bybit")]
-#[cfg_attr(docsrs, doc(cfg(feature = "bybit")))]
+#[cfg(feature = "bybit-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bybit-http")))]
 pub mod bybit;
-#[cfg(feature = "kucoin")]
-#[cfg_attr(docsrs, doc(cfg(feature = "kucoin")))]
+#[cfg(feature = "kucoin-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kucoin-http")))]
 pub mod kucoin;
-#[cfg(feature = "mexc")]
-#[cfg_attr(docsrs, doc(cfg(feature = "mexc")))]
+#[cfg(feature = "mexc-http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mexc-http")))]
 pub mod mexc;
+pub mod multi_account;
 pub mod orders;
 pub(crate) mod other_types;
+pub mod router;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod watchdog;
+pub mod watermark;
 
 pub use prelude::*;
 
 pub(crate) mod utils;
+/// What exported macros expand to, so that crates invoking them don't need these as their own dependencies.
+#[doc(hidden)]
+pub mod __macro_deps {
+	pub use eyre;
+	pub use v_utils;
+}
 
 cfg_if::cfg_if! {
 	if #[cfg(feature = "data")] {
@@ -0,0 +1,208 @@
+//! Listings, delistings and status changes of an exchange's pairs, read off successive polls of its [exchange_info](Exchange::exchange_info).
+use std::{path::PathBuf, time::Duration};
+
+use adapters::generics::ws::WsError;
+
+use crate::prelude::*;
+
+/// Change to the set of pairs an exchange lists, as seen between two polls of [exchange_info](Exchange::exchange_info).
+#[derive(Clone, Debug)]
+pub enum SymbolEvent {
+	Added(Pair, PairInfo),
+	Removed(Pair),
+	/// Only for exchanges whose info carries a [status](PairInfo::status).
+	StatusChanged { pair: Pair, from: String, to: String },
+}
+
+/// Pair -> [status](PairInfo::status), all that's needed to diff against.
+type Snapshot = BTreeMap<Pair, Option<String>>;
+
+/// Polls [exchange_info](Exchange::exchange_info) every `interval`, emitting a [SymbolEvent] for every listing, delisting and status change.
+///
+/// As an [ExchangeStream], each item batch is what changed over one poll; polls with no changes aren't yielded. A failed poll is yielded as [WsError::Other], and the one after is still `interval` later.
+///
+/// The first poll only establishes the baseline. With a [snapshot path](Self::persist), the baseline is carried over restarts instead, so these don't re-announce every pair. Note that most exchanges' info only has pairs that are trading, so a halt usually reads as [Removed](SymbolEvent::Removed).
+#[derive(Debug)]
+pub struct SymbolWatcher {
+	exchange: Box<dyn Exchange>,
+	instrument: Instrument,
+	interval: Duration,
+	path: Option<PathBuf>,
+	last: Option<Snapshot>,
+	next_poll: Option<tokio::time::Instant>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SnapshotEntry {
+	base: String,
+	quote: String,
+	status: Option<String>,
+}
+
+impl SymbolWatcher {
+	pub fn new(exchange: Box<dyn Exchange>, instrument: Instrument, interval: Duration) -> Self {
+		Self {
+			exchange,
+			instrument,
+			interval,
+			path: None,
+			last: None,
+			next_poll: None,
+		}
+	}
+
+	/// Keep the last snapshot at `path`, resuming from it if it's there. Written as JSON after every poll.
+	pub fn persist(mut self, path: impl Into<PathBuf>) -> Self {
+		self.path = Some(path.into());
+		self
+	}
+
+	/// Fetch [exchange_info](Exchange::exchange_info) once, returning what changed since the last snapshot.
+	pub async fn poll(&mut self) -> ExchangeResult<Vec<SymbolEvent>> {
+		let info = self.exchange.exchange_info(self.instrument).await?;
+		if self.last.is_none() {
+			self.last = self.load().await;
+		}
+		let events = match &self.last {
+			Some(prev) => diff(prev, &info),
+			None => {
+				info!(target: "v_exchanges", "No previous snapshot of {} {} pairs, taking {} as the baseline", self.exchange.name(), self.instrument, info.pairs.len());
+				vec![]
+			}
+		};
+		let snapshot = info.pairs.iter().map(|(pair, pi)| (*pair, pi.status.clone())).collect();
+		self.save(&snapshot).await;
+		self.last = Some(snapshot);
+		Ok(events)
+	}
+
+	async fn load(&self) -> Option<Snapshot> {
+		let path = self.path.clone()?;
+		let s = tokio::task::spawn_blocking({
+			let path = path.clone();
+			move || std::fs::read_to_string(path)
+		})
+		.await
+		.expect("reading doesn't panic")
+		.ok()?;
+		match serde_json::from_str::<Vec<SnapshotEntry>>(&s) {
+			Ok(entries) => Some(entries.into_iter().map(|e| (Pair::new(e.base.as_str(), e.quote.as_str()), e.status)).collect()),
+			Err(e) => {
+				warn!(target: "v_exchanges", "Ignoring unreadable symbol snapshot at {}: {e}", path.display());
+				None
+			}
+		}
+	}
+
+	async fn save(&self, snapshot: &Snapshot) {
+		let Some(path) = self.path.clone() else { return };
+		let entries: Vec<SnapshotEntry> = snapshot
+			.iter()
+			.map(|(pair, status)| SnapshotEntry {
+				base: pair.base().to_string(),
+				quote: pair.quote().to_string(),
+				status: status.clone(),
+			})
+			.collect();
+		let json = serde_json::to_string(&entries).expect("always serializable");
+		let written = tokio::task::spawn_blocking({
+			let path = path.clone();
+			move || std::fs::write(path, json)
+		})
+		.await
+		.expect("writing doesn't panic");
+		if let Err(e) = written {
+			warn!(target: "v_exchanges", "Failed to write symbol snapshot to {}: {e}", path.display());
+		}
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeStream for SymbolWatcher {
+	type Item = SymbolEvent;
+
+	async fn next(&mut self) -> Result<Vec<SymbolEvent>, WsError> {
+		loop {
+			if let Some(at) = self.next_poll {
+				tokio::time::sleep_until(at).await;
+			}
+			self.next_poll = Some(tokio::time::Instant::now() + self.interval);
+			let events = self.poll().await.map_err(|e| WsError::Other(eyre::Report::new(e)))?;
+			if !events.is_empty() {
+				return Ok(events);
+			}
+		}
+	}
+}
+
+fn diff(prev: &Snapshot, info: &ExchangeInfo) -> Vec<SymbolEvent> {
+	let mut events = Vec::new();
+	for (pair, pi) in &info.pairs {
+		match prev.get(pair) {
+			None => events.push(SymbolEvent::Added(*pair, pi.clone())),
+			Some(from) =>
+				if let (Some(from), Some(to)) = (from, &pi.status)
+					&& from != to
+				{
+					events.push(SymbolEvent::StatusChanged {
+						pair: *pair,
+						from: from.clone(),
+						to: to.clone(),
+					});
+				},
+		}
+	}
+	events.extend(prev.keys().filter(|p| !info.pairs.contains_key(p)).map(|p| SymbolEvent::Removed(*p)));
+	events
+}
+
+#[cfg(test)]
+mod tests {
+	use jiff::Timestamp;
+
+	use super::*;
+
+	fn info(pairs: &[(&str, Option<&str>)]) -> ExchangeInfo {
+		ExchangeInfo {
+			server_time: Timestamp::UNIX_EPOCH,
+			pairs: pairs
+				.iter()
+				.map(|(base, status)| {
+					let pi = PairInfo {
+						status: status.map(str::to_owned),
+						..Default::default()
+					};
+					(Pair::new(*base, "USDT"), pi)
+				})
+				.collect(),
+		}
+	}
+
+	fn snapshot(info: &ExchangeInfo) -> Snapshot {
+		info.pairs.iter().map(|(pair, pi)| (*pair, pi.status.clone())).collect()
+	}
+
+	#[test]
+	fn diffs_listings_and_statuses() {
+		let before = info(&[("BTC", Some("TRADING")), ("ETH", Some("TRADING")), ("LUNA", Some("TRADING")), ("XMR", None)]);
+		let after = info(&[("BTC", Some("TRADING")), ("ETH", Some("SETTLING")), ("SOL", Some("PENDING_TRADING")), ("XMR", Some("TRADING"))]);
+
+		let events = diff(&snapshot(&before), &after);
+		assert_eq!(events.len(), 3, "{events:?}");
+		assert!(matches!(&events[0], SymbolEvent::StatusChanged { pair, from, to } if *pair == Pair::new("ETH", "USDT") && from == "TRADING" && to == "SETTLING"));
+		assert!(matches!(&events[1], SymbolEvent::Added(pair, pi) if *pair == Pair::new("SOL", "USDT") && pi.status.as_deref() == Some("PENDING_TRADING")));
+		assert!(matches!(&events[2], SymbolEvent::Removed(pair) if *pair == Pair::new("LUNA", "USDT")));
+		assert!(diff(&snapshot(&after), &after).is_empty());
+	}
+
+	#[tokio::test]
+	async fn snapshot_survives_restart() {
+		let path = std::env::temp_dir().join(format!("v_exchanges_symbols_{}.json", std::process::id()));
+		let watcher = SymbolWatcher::new(ExchangeName::Binance.init_mock_client(), Instrument::Perp, Duration::from_secs(60)).persist(&path);
+		assert!(watcher.load().await.is_none());
+
+		let saved = snapshot(&info(&[("BTC", Some("TRADING")), ("XMR", None)]));
+		watcher.save(&saved).await;
+		assert_eq!(watcher.load().await, Some(saved));
+		std::fs::remove_file(&path).unwrap();
+	}
+}
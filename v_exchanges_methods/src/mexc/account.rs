@@ -1,11 +1,13 @@
 use adapters::{
 	Client,
-	mexc::{MexcAuth, MexcHttpUrl, MexcOption},
+	mexc::{MexcAuth, MexcOption},
 };
+use jiff::Timestamp;
 
 use crate::{
 	AssetBalance, Balances, ExchangeResult,
 	core::{ApiKeyInfo, PersonalInfo},
+	mexc::http_url,
 	prelude::*,
 };
 
@@ -22,35 +24,27 @@ pub(super) async fn personal_info(client: &Client, recv_window: Option<std::time
 
 pub(super) async fn balances(client: &Client, recv_window: Option<std::time::Duration>) -> ExchangeResult<Balances> {
 	assert!(client.is_authenticated::<MexcOption>());
-	let mut options = vec![MexcOption::HttpUrl(MexcHttpUrl::Futures), MexcOption::HttpAuth(MexcAuth::Sign)];
+	let mut options = vec![http_url(client, Instrument::Perp), MexcOption::HttpAuth(MexcAuth::Sign)];
 	if let Some(rw) = recv_window {
 		options.push(MexcOption::RecvWindow(rw));
 	}
 	let rs: BalancesResponse = client.get_no_query("/api/v1/private/account/assets", options).await?;
 
 	let non_zero: Vec<AssetBalance> = rs.data.into_iter().filter(|r| r.equity != 0.).map(|r| r.into()).collect();
-	// dance with tambourine to request for usdt prices of all assets except usdt itself
-	//RELIES: join_all preserving order
-	let price_handles: Vec<_> = non_zero
-		.iter()
-		.map(|b| {
-			if b.asset == "USDT" {
-				Box::pin(async move { Ok(1.) }) as Pin<Box<dyn Future<Output = ExchangeResult<f64>> + Send>>
-			} else {
-				Box::pin(super::market::price(client, (b.asset, "USDT".into()).into())) as Pin<Box<dyn Future<Output = ExchangeResult<f64>> + Send>>
-			}
-		})
-		.collect();
-	let prices = join_all(price_handles).await.into_iter().collect::<ExchangeResult<Vec<f64>>>()?;
+	let prices_at = Timestamp::now();
+	let usdt_pairs: Vec<Pair> = non_zero.iter().filter(|b| b.asset != "USDT").map(|b| Pair::new(b.asset, "USDT")).collect();
+	let prices = match usdt_pairs.is_empty() {
+		true => BTreeMap::new(),
+		false => super::market::prices(client, Some(usdt_pairs)).await?,
+	};
 
 	let balances: Vec<AssetBalance> = non_zero
 		.into_iter()
-		.zip(prices)
-		.map(|(mut b, p)| {
-			b.usd = Some((p * b.underlying).into());
-			b
+		.map(|mut b| {
+			b.usd = Some(convert_with(&prices, prices_at, b.underlying, b.asset, "USDT".into())?.value.into());
+			Ok(b)
 		})
-		.collect();
+		.collect::<ExchangeResult<_>>()?;
 
 	let total = balances.iter().fold(Usd(0.), |acc, b| acc + b.usd.expect("Just set for all"));
 	Ok(Balances::new(balances, total))
@@ -81,12 +75,9 @@ struct AssetBalanceData {
 }
 impl From<AssetBalanceData> for AssetBalance {
 	fn from(r: AssetBalanceData) -> Self {
-		Self {
-			#[allow(clippy::unnecessary_fallible_conversions)] //Q: do I ever want them?
-			asset: r.currency.try_into().expect("Assume v_utils is able to handle all mexc pairs"),
-			underlying: r.equity,
-			usd: None,
-		}
+		#[allow(clippy::unnecessary_fallible_conversions)] //Q: do I ever want them?
+		let asset = r.currency.try_into().expect("Assume v_utils is able to handle all mexc pairs");
+		Self::new(asset, r.equity, None)
 	}
 }
 
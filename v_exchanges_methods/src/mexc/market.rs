@@ -1,21 +1,22 @@
-use std::collections::{BTreeMap, VecDeque};
-
-use adapters::{
-	Client,
-	mexc::{MexcHttpUrl, MexcOption},
+use std::{
+	collections::{BTreeMap, VecDeque},
+	str::FromStr as _,
 };
+
+use adapters::Client;
 use jiff::Timestamp;
 
 use crate::{
 	ExchangeResult, RequestRange, Symbol,
 	core::{ExchangeInfo, Klines, PairInfo},
-	mexc::MexcTimeframe,
+	error::ctx,
+	mexc::{MexcTimeframe, http_url},
 	prelude::*,
 };
 
 pub(super) async fn price(client: &Client, pair: Pair) -> ExchangeResult<f64> {
 	let endpoint = format!("/api/v1/contract/index_price/{}", pair.fmt_mexc());
-	let options = vec![MexcOption::HttpUrl(MexcHttpUrl::Futures)];
+	let options = vec![http_url(client, Instrument::Perp)];
 	#[derive(Clone, Debug, Default, Deserialize)]
 	struct PriceResponse {
 		data: PriceData,
@@ -29,6 +30,37 @@ pub(super) async fn price(client: &Client, pair: Pair) -> ExchangeResult<f64> {
 	Ok(r.data.index_price)
 }
 
+// prices {{{
+/// Last prices of all contracts off the one all-tickers request, filtered to `pairs` client-side.
+pub(super) async fn prices(client: &Client, pairs: Option<Vec<Pair>>) -> ExchangeResult<BTreeMap<Pair, f64>> {
+	let options = vec![http_url(client, Instrument::Perp)];
+	let response: TickersResponse = client.get_no_query("/api/v1/contract/ticker", options).await?;
+	Ok(response.into_prices(pairs.as_deref()))
+}
+
+/// Without a `symbol`, `data` is the list of every contract's ticker.
+#[derive(Debug, Deserialize)]
+struct TickersResponse {
+	data: Vec<TickerData>,
+}
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TickerData {
+	/// `BTC_USDT`
+	symbol: String,
+	last_price: f64,
+}
+impl TickersResponse {
+	fn into_prices(self, pairs: Option<&[Pair]>) -> BTreeMap<Pair, f64> {
+		self.data
+			.into_iter()
+			.filter_map(|t| Some((Pair::from_str(&t.symbol).ok()?, t.last_price)))
+			.filter(|(pair, _)| pairs.is_none_or(|requested| requested.contains(pair)))
+			.collect()
+	}
+}
+//,}}}
+
 // klines {{{
 pub(super) async fn klines(client: &Client, symbol: Symbol, tf: MexcTimeframe, range: RequestRange) -> ExchangeResult<Klines> {
 	let mexc_symbol = symbol.pair.fmt_mexc();
@@ -67,8 +99,8 @@ pub(super) async fn klines(client: &Client, symbol: Symbol, tf: MexcTimeframe, r
 		"start": start,
 		"end": end,
 	});
-	let options = vec![MexcOption::HttpUrl(MexcHttpUrl::Futures)];
-	let response: KlineResponse = client.get(&endpoint, &params, options).await?;
+	let options = vec![http_url(client, Instrument::Perp)];
+	let response: KlineResponse = ctx!(client.get(&endpoint, &params, options).await, Mexc.klines(symbol, tf) -> endpoint)?;
 
 	let mut klines_vec = VecDeque::default();
 	let data = response.data;
@@ -113,7 +145,7 @@ struct KlineData {
 
 // exchange_info {{{
 pub(super) async fn exchange_info(client: &Client) -> ExchangeResult<ExchangeInfo> {
-	let options = vec![MexcOption::HttpUrl(MexcHttpUrl::Futures)];
+	let options = vec![http_url(client, Instrument::Perp)];
 	let response: ContractDetailResponse = client.get_no_query("/api/v1/contract/detail", options).await?;
 
 	let mut pairs = BTreeMap::default();
@@ -134,6 +166,13 @@ pub(super) async fn exchange_info(client: &Client) -> ExchangeResult<ExchangeInf
 			price_precision,
 			qty_precision,
 			delivery_date: None,
+			min_qty: None,
+			min_notional: None,
+			max_qty: None,
+			qty_step: None,
+			min_price: None,
+			max_price: None,
+			status: Some(contract.state.to_string()),
 		};
 		pairs.insert(pair, pair_info);
 	}
@@ -160,3 +199,176 @@ struct ContractInfo {
 	state: i32,
 }
 //,}}}
+
+/// Spot API (`/api/v3`), modeled after Binance's: same kline arrays, same `BTCUSDT` symbols without a separator.
+pub(super) mod spot {
+	use serde_with::{DisplayFromStr, serde_as};
+
+	use super::*;
+	use crate::ExchangeName;
+
+	// klines {{{
+	pub(in crate::mexc) async fn klines(client: &Client, symbol: Symbol, tf: MexcTimeframe, range: RequestRange) -> ExchangeResult<Klines> {
+		range.ensure_allowed(1..=1000, tf.as_ref())?;
+		let mut params = Vec::with_capacity(5);
+		params.push(("symbol", symbol.pair.fmt_binance()));
+		params.push(("interval", tf.to_string()));
+		range.append_exchange_params(ExchangeName::Mexc, tf.as_ref(), &mut params);
+
+		let options = vec![http_url(client, Instrument::Spot)];
+		let response: Vec<KlineResponse> = ctx!(client.get("/api/v3/klines", &params, options).await, Mexc.klines(symbol, tf) -> "/api/v3/klines")?;
+		let klines = response
+			.into_iter()
+			.map(|k| {
+				Ok(Kline {
+					open_time: Timestamp::from_millisecond(k.open_time).map_err(|e| eyre!("Invalid timestamp: {e}"))?,
+					ohlc: Ohlc {
+						open: k.open,
+						high: k.high,
+						low: k.low,
+						close: k.close,
+					},
+					volume_quote: k.quote_asset_volume,
+					trades: None,
+					taker_buy_volume_quote: None,
+				})
+			})
+			.collect::<ExchangeResult<VecDeque<_>>>()?;
+		Ok(Klines::new(klines, *tf))
+	}
+
+	/// `[openTime, open, high, low, close, volume, closeTime, quoteAssetVolume]`
+	#[serde_as]
+	#[derive(Debug, Deserialize)]
+	struct KlineResponse {
+		open_time: i64,
+		#[serde_as(as = "DisplayFromStr")]
+		open: f64,
+		#[serde_as(as = "DisplayFromStr")]
+		high: f64,
+		#[serde_as(as = "DisplayFromStr")]
+		low: f64,
+		#[serde_as(as = "DisplayFromStr")]
+		close: f64,
+		#[serde_as(as = "DisplayFromStr")]
+		_volume: f64,
+		_close_time: i64,
+		#[serde_as(as = "DisplayFromStr")]
+		quote_asset_volume: f64,
+	}
+	//,}}}
+
+	// exchange_info {{{
+	pub(in crate::mexc) async fn exchange_info(client: &Client) -> ExchangeResult<ExchangeInfo> {
+		let options = vec![http_url(client, Instrument::Spot)];
+		let response: ExchangeInfoResponse = client.get_no_query("/api/v3/exchangeInfo", options).await?;
+		Ok(response.into())
+	}
+
+	#[derive(Debug, Deserialize)]
+	#[serde(rename_all = "camelCase")]
+	struct ExchangeInfoResponse {
+		server_time: i64,
+		symbols: Vec<SymbolInfo>,
+	}
+	#[derive(Debug, Deserialize)]
+	#[serde(rename_all = "camelCase")]
+	struct SymbolInfo {
+		/// `"1"`: online, `"2"`: paused, `"3"`: offline.
+		status: String,
+		base_asset: String,
+		quote_asset: String,
+		/// Decimals of the quantity.
+		base_asset_precision: u8,
+		/// Decimals of the price.
+		quote_precision: u8,
+	}
+	impl From<ExchangeInfoResponse> for ExchangeInfo {
+		fn from(r: ExchangeInfoResponse) -> Self {
+			let pairs = r
+				.symbols
+				.into_iter()
+				.filter(|s| s.status == "1")
+				.map(|s| {
+					let pair_info = PairInfo {
+						price_precision: s.quote_precision,
+						qty_precision: s.base_asset_precision,
+						delivery_date: None,
+						min_qty: None,
+						min_notional: None,
+						max_qty: None,
+						qty_step: None,
+						min_price: None,
+						max_price: None,
+						status: Some(s.status),
+					};
+					(Pair::new(s.base_asset.as_str(), s.quote_asset.as_str()), pair_info)
+				})
+				.collect();
+			Self {
+				server_time: Timestamp::from_millisecond(r.server_time).unwrap_or_else(|_| Timestamp::now()),
+				pairs,
+			}
+		}
+	}
+	//,}}}
+
+	pub(in crate::mexc) async fn price(client: &Client, pair: Pair) -> ExchangeResult<f64> {
+		#[serde_as]
+		#[derive(Debug, Deserialize)]
+		struct PriceResponse {
+			#[serde_as(as = "DisplayFromStr")]
+			price: f64,
+		}
+		let options = vec![http_url(client, Instrument::Spot)];
+		let r: PriceResponse = client.get("/api/v3/ticker/price", &[("symbol", pair.fmt_binance())], options).await?;
+		Ok(r.price)
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn kline_arrays() {
+			let json = r#"[[1729843200000,"67523.01","67600","67480.5","67590.12","12.345678",1729843259999,"833912.11"]]"#;
+			let r: Vec<KlineResponse> = serde_json::from_str(json).unwrap();
+			assert_eq!(r[0].open_time, 1729843200000);
+			assert_eq!((r[0].open, r[0].high, r[0].low, r[0].close), (67523.01, 67600., 67480.5, 67590.12));
+			assert_eq!(r[0].quote_asset_volume, 833912.11);
+		}
+
+		#[test]
+		fn exchange_info_skips_offline() {
+			let json = r#"{"timezone":"CST","serverTime":1729843222921,"rateLimits":[],"exchangeFilters":[],"symbols":[
+				{"symbol":"BTCUSDT","status":"1","baseAsset":"BTC","baseAssetPrecision":6,"quoteAsset":"USDT","quotePrecision":2,"quoteAssetPrecision":2,"baseCommissionPrecision":6,"quoteCommissionPrecision":6,"orderTypes":["LIMIT","MARKET","LIMIT_MAKER"],"isSpotTradingAllowed":true,"isMarginTradingAllowed":false,"quoteAmountPrecision":"1","baseSizePrecision":"0.000001","permissions":["SPOT"],"filters":[],"maxQuoteAmount":"2000000","makerCommission":"0","takerCommission":"0.0005"},
+				{"symbol":"DEADUSDT","status":"3","baseAsset":"DEAD","baseAssetPrecision":2,"quoteAsset":"USDT","quotePrecision":4,"quoteAssetPrecision":4,"isSpotTradingAllowed":false}
+			]}"#;
+			let info: ExchangeInfo = serde_json::from_str::<ExchangeInfoResponse>(json).unwrap().into();
+			assert_eq!(info.pairs.len(), 1);
+			let btc = &info.pairs[&Pair::new("BTC", "USDT")];
+			assert_eq!((btc.price_precision, btc.qty_precision), (2, 6));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn all_tickers() {
+		let json = r#"{"success":true,"code":0,"data":[
+			{"contractId":10,"symbol":"BTC_USDT","lastPrice":67523.5,"bid1":67523.4,"ask1":67523.5,"volume24":255419361,"amount24":1725628717.0,"holdVol":160542867,"lower24Price":66600.1,"high24Price":68123.0,"riseFallRate":0.0123,"riseFallValue":823.1,"indexPrice":67530.2,"fairPrice":67525.0,"fundingRate":0.0001,"maxBidPrice":74283.2,"minAskPrice":60777.1,"timestamp":1729843222921},
+			{"contractId":11,"symbol":"ETH_USDT","lastPrice":2531.07,"bid1":2531.06,"ask1":2531.07,"volume24":61937841,"amount24":1573282394.0,"holdVol":40516297,"lower24Price":2488.0,"high24Price":2560.5,"riseFallRate":0.0034,"riseFallValue":8.6,"indexPrice":2531.5,"fairPrice":2531.2,"fundingRate":0.0001,"maxBidPrice":2784.6,"minAskPrice":2278.4,"timestamp":1729843222921}
+		]}"#;
+		let response = || serde_json::from_str::<TickersResponse>(json).unwrap();
+
+		let all = response().into_prices(None);
+		assert_eq!(all.len(), 2);
+		assert_eq!(all[&Pair::new("BTC", "USDT")], 67523.5);
+
+		let filtered = response().into_prices(Some(&[Pair::new("ETH", "USDT"), Pair::new("SOL", "USDT")]));
+		assert_eq!(filtered, BTreeMap::from([(Pair::new("ETH", "USDT"), 2531.07)]));
+	}
+}
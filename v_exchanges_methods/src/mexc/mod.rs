@@ -1,36 +1,84 @@
 mod account;
 mod market;
 
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, sync::Arc};
 
-use adapters::mexc::{MexcOption, MexcOptions};
+use adapters::mexc::{MexcHttpUrl, MexcOption, MexcOptions};
 use derive_more::derive::{Deref, DerefMut};
 use secrecy::SecretString;
 use v_exchanges_adapters::{Client, GetOptions};
 use v_utils::trades::{Pair, Timeframe};
 
 use crate::{
-	ExchangeInfo, ExchangeName, ExchangeResult, Instrument, Symbol,
-	core::{ExchangeImpl, Klines, PersonalInfo, RequestRange},
+	AccountLabel, ExchangeError, ExchangeInfo, ExchangeName, ExchangeResult, InfoCache, Instrument, MethodError, MutationJournal, Symbol, Venue,
+	core::{ExchangeImpl, Klines, KlinesGuard, PersonalInfo, RequestRange},
 };
 
+/// [MexcOption::HttpUrl] for `instrument`, as mapped by the client's [MexcOptions::http_url_for].
+pub(crate) fn http_url(client: &Client, instrument: Instrument) -> MexcOption {
+	MexcOption::HttpUrl(GetOptions::<MexcOptions>::default_options(client).http_url_for(instrument))
+}
+
 #[derive(Clone, Debug, Default, Deref, DerefMut)]
 pub struct Mexc {
 	#[deref]
 	#[deref_mut]
 	pub client: Client,
-	pub info_cache: BTreeMap<Instrument, ExchangeInfo>,
+	pub info_cache: InfoCache,
+	pub label: Option<AccountLabel>,
+	pub log_context: Option<String>,
+	pub klines_guard: Option<KlinesGuard>,
+	pub mutation_journal: Option<Arc<dyn MutationJournal>>,
+}
+
+impl Mexc {
+	/// Base url requests for `instrument` go to. Override with [MexcOption::InstrumentHttpUrl].
+	pub fn http_url_for(&self, instrument: Instrument) -> MexcHttpUrl {
+		GetOptions::<MexcOptions>::default_options(&self.client).http_url_for(instrument)
+	}
 }
 
 //? currently client ends up importing this from crate::binance, but could it be possible to lift the [Client] reexport up, and still have the ability to call all exchange methods right on it?
 #[async_trait::async_trait]
 impl ExchangeImpl for Mexc {
-	fn info_cache_mut(&mut self) -> &mut BTreeMap<Instrument, ExchangeInfo> {
-		&mut self.info_cache
+	fn info_cache(&self) -> &InfoCache {
+		&self.info_cache
+	}
+
+	fn label(&self) -> Option<&AccountLabel> {
+		self.label.as_ref()
+	}
+
+	fn label_mut(&mut self) -> &mut Option<AccountLabel> {
+		&mut self.label
+	}
+
+	fn log_context(&self) -> Option<&str> {
+		self.log_context.as_deref()
+	}
+
+	fn log_context_mut(&mut self) -> &mut Option<String> {
+		&mut self.log_context
+	}
+
+	fn klines_guard(&self) -> Option<KlinesGuard> {
+		self.klines_guard
 	}
 
-	fn name(&self) -> ExchangeName {
-		ExchangeName::Mexc
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard> {
+		&mut self.klines_guard
+	}
+
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>> {
+		self.mutation_journal.as_ref()
+	}
+
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>> {
+		&mut self.mutation_journal
+	}
+
+	fn name(&self) -> Venue {
+		ExchangeName::Mexc.into()
 	}
 
 	fn auth(&mut self, pubkey: String, secret: SecretString) {
@@ -46,26 +94,59 @@ impl ExchangeImpl for Mexc {
 		GetOptions::<MexcOptions>::default_options(&**self).recv_window
 	}
 
-	async fn prices(&self, _pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
+	async fn prices(&self, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
+		match instrument {
+			Instrument::Perp => market::prices(self, pairs).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "prices"))),
+		}
+	}
+
+	async fn exchange_info(&self, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
 		match instrument {
-			Instrument::Perp => unimplemented!("Mexc does not have a multi-asset endpoints for futures"),
-			_ => unimplemented!(),
+			Instrument::Spot => market::spot::exchange_info(self).await,
+			Instrument::Perp => market::exchange_info(self).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "exchange_info"))),
+		}
+	}
+
+	async fn price(&self, symbol: Symbol) -> ExchangeResult<f64> {
+		match symbol.instrument {
+			Instrument::Spot => market::spot::price(self, symbol.pair).await,
+			// index price of the contract
+			Instrument::Perp => market::price(self, symbol.pair).await,
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "price"))),
 		}
 	}
 
 	async fn klines(&self, symbol: Symbol, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
 		match symbol.instrument {
+			Instrument::Spot => market::spot::klines(self, symbol, tf.try_into()?, range).await,
 			Instrument::Perp => market::klines(self, symbol, tf.try_into()?, range).await,
-			_ => unimplemented!(),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), symbol.instrument, "klines"))),
 		}
 	}
 
 	async fn personal_info(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
 		match instrument {
 			Instrument::Perp => account::personal_info(self, recv_window).await,
-			_ => unimplemented!(),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_implemented(self.name(), instrument, "personal_info"))),
 		}
 	}
 }
 
-crate::define_provider_timeframe!(MexcTimeframe, ["1m", "5m", "15m", "30m", "60m", "4h", "1d", "1W", "1M"]);
+// spot spellings; futures klines translate these further, in `market::klines`
+crate::define_provider_timeframe!(
+	MexcTimeframe,
+	[
+		("1m", [], "1m"),
+		("5m", [], "5m"),
+		("15m", [], "15m"),
+		("30m", [], "30m"),
+		("1h", [], "60m"),
+		("4h", [], "4h"),
+		("1d", [], "1d"),
+		("1w", [], "1W"),
+		("1M", [], "1M"),
+	],
+	test = mexc_timeframe_table
+);
@@ -0,0 +1,163 @@
+use tracing::Instrument as _;
+
+use crate::prelude::*;
+
+/// Several credential sets on the same exchange, addressed by [AccountLabel].
+///
+/// Each client is labeled on insertion, so errors coming out of it are already tagged with the account.
+#[derive(Debug)]
+pub struct MultiAccount<E: Exchange> {
+	accounts: Vec<(AccountLabel, E)>,
+}
+impl<E: Exchange> MultiAccount<E> {
+	pub fn new(accounts: Vec<(AccountLabel, E)>) -> Self {
+		let accounts = accounts
+			.into_iter()
+			.map(|(label, mut client)| {
+				client.set_label(label.clone());
+				(label, client)
+			})
+			.collect();
+		Self { accounts }
+	}
+
+	/// Reads credentials from `{PREFIX}_{LABEL}_PUBKEY` and `{PREFIX}_{LABEL}_SECRET`, with both `prefix` and `label` uppercased.
+	/// ```ignore
+	/// // BINANCE_MAIN_PUBKEY, BINANCE_MAIN_SECRET, BINANCE_SUB1_PUBKEY, BINANCE_SUB1_SECRET
+	/// let accounts = MultiAccount::<Binance>::from_env("binance", &["main", "sub1"])?;
+	/// ```
+	pub fn from_env(prefix: &str, labels: &[&str]) -> Result<Self>
+	where
+		E: Default, {
+		let accounts = labels
+			.iter()
+			.map(|label| {
+				let var = |kind: &str| {
+					let name = format!("{}_{}_{kind}", prefix.to_uppercase(), label.to_uppercase());
+					std::env::var(&name).wrap_err_with(|| format!("failed to read `{name}` for account `{label}`"))
+				};
+				let mut client = E::default();
+				client.auth(var("PUBKEY")?, var("SECRET")?.into());
+				Ok((AccountLabel::from(*label), client))
+			})
+			.collect::<Result<Vec<_>>>()?;
+		Ok(Self::new(accounts))
+	}
+
+	pub fn labels(&self) -> impl Iterator<Item = &AccountLabel> {
+		self.accounts.iter().map(|(label, _)| label)
+	}
+
+	pub fn get(&self, label: &AccountLabel) -> Option<&E> {
+		self.accounts.iter().find(|(l, _)| l == label).map(|(_, client)| client)
+	}
+
+	pub fn get_mut(&mut self, label: &AccountLabel) -> Option<&mut E> {
+		self.accounts.iter_mut().find(|(l, _)| l == label).map(|(_, client)| client)
+	}
+
+	/// Issue the same request against every account concurrently. Each call runs inside an `account` span carrying the label.
+	pub async fn fan_out<'a, T, F, Fut>(&'a self, f: F) -> BTreeMap<AccountLabel, ExchangeResult<T>>
+	where
+		F: Fn(&'a E) -> Fut,
+		Fut: Future<Output = ExchangeResult<T>> + 'a, {
+		let futures = self.accounts.iter().map(|(label, client)| {
+			let span = tracing::info_span!(target: "v_exchanges", "account", label = %label);
+			let fut = f(client).instrument(span);
+			async move { (label.clone(), fut.await) }
+		});
+		join_all(futures).await.into_iter().collect()
+	}
+
+	/// Run a request against a single account. `None` if no account with such label is registered.
+	pub async fn on<'a, T, F, Fut>(&'a self, label: &AccountLabel, f: F) -> Option<ExchangeResult<T>>
+	where
+		F: FnOnce(&'a E) -> Fut,
+		Fut: Future<Output = ExchangeResult<T>> + 'a, {
+		let (label, client) = self.accounts.iter().find(|(l, _)| l == label)?;
+		Some(f(client).instrument(tracing::info_span!(target: "v_exchanges", "account", label = %label)).await)
+	}
+
+	pub async fn balances_all(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> BTreeMap<AccountLabel, ExchangeResult<Balances>> {
+		self.fan_out(|client| async move { client.personal_info(instrument, recv_window).await.map(|info| info.balances) })
+			.await
+	}
+
+	pub async fn positions_all(&self, instrument: Instrument, recv_window: Option<std::time::Duration>) -> BTreeMap<AccountLabel, ExchangeResult<Vec<Position>>> {
+		self.fan_out(|client| client.positions(instrument, recv_window)).await
+	}
+
+	/// `None` if no account with such label is registered; the order is then sent nowhere.
+	pub async fn place_order_on(&self, label: &AccountLabel, symbol: Symbol, order: Order, recv_window: Option<std::time::Duration>) -> Option<ExchangeResult<OrderPlaced>> {
+		self.on(label, |client| client.place_order(symbol, order, recv_window)).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::MockExchange;
+
+	fn long(qty: f64) -> Position {
+		Position {
+			symbol: MockExchange::btc_perp(),
+			size: qty,
+			entry_price: 50_000.,
+			mark_price: Some(50_000.),
+			unrealized_pnl: 0.,
+			updated_at: Timestamp::now(),
+		}
+	}
+
+	fn accounts() -> MultiAccount<MockExchange> {
+		let main = MockExchange {
+			positions: vec![long(0.1)],
+			..Default::default()
+		};
+		let sub = MockExchange {
+			positions: vec![long(-0.2)],
+			..Default::default()
+		};
+		MultiAccount::new(vec![(AccountLabel::from("main"), main), (AccountLabel::from("sub"), sub)])
+	}
+
+	#[tokio::test]
+	async fn positions_are_keyed_by_account() {
+		let accounts = accounts();
+		let positions = accounts.positions_all(Instrument::Perp, None).await;
+		let sizes: Vec<_> = positions.iter().map(|(label, r)| (label.to_string(), r.as_ref().unwrap()[0].size)).collect();
+		assert_eq!(sizes, [("main".to_owned(), 0.1), ("sub".to_owned(), -0.2)]);
+	}
+
+	#[tokio::test]
+	async fn failures_carry_the_account() {
+		let accounts = accounts();
+		let positions = accounts.positions_all(Instrument::Spot, None).await;
+		assert_eq!(positions.len(), 2);
+		for (label, r) in positions {
+			match r.unwrap_err() {
+				ExchangeError::Account(e) => assert_eq!(e.label, label),
+				e => panic!("expected an account-labeled error, got {e:?}"),
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn orders_go_to_the_named_account_only() {
+		let accounts = accounts();
+		let sub = AccountLabel::from("sub");
+		let order = MockExchange::market_order("multi", 0.01);
+		let placed = accounts.place_order_on(&sub, MockExchange::btc_perp(), order.clone(), None).await.unwrap().unwrap();
+		assert_eq!(placed.order_id, *order.order_id());
+		assert_eq!(accounts.get(&sub).unwrap().placed.lock().unwrap().len(), 1);
+		assert!(accounts.get(&AccountLabel::from("main")).unwrap().placed.lock().unwrap().is_empty());
+	}
+
+	#[tokio::test]
+	async fn unknown_account_places_nothing() {
+		let accounts = accounts();
+		let order = MockExchange::market_order("multi", 0.01);
+		assert!(accounts.place_order_on(&AccountLabel::from("nope"), MockExchange::btc_perp(), order, None).await.is_none());
+		assert!(accounts.labels().all(|label| accounts.get(label).unwrap().placed.lock().unwrap().is_empty()));
+	}
+}
@@ -2,9 +2,9 @@ use arrayvec::ArrayString;
 use jiff::Timestamp;
 use smart_default::SmartDefault;
 use uuid::Uuid;
-use v_utils::trades::Side;
+use v_utils::trades::{Asset, Side};
 
-use crate::{Price, Qty, Ticker};
+use crate::{OrderValidationError, OrderViolation, PairInfo, Price, Qty, Symbol, Ticker};
 
 /// An order bound to a specific exchange and ticker, ready to be placed.
 #[derive(Clone, Debug, derive_more::Deref, derive_more::DerefMut, PartialEq, derive_new::new)]
@@ -30,7 +30,206 @@ pub struct OrderId {
 	#[default(Uuid::now_v7())]
 	pub id: Uuid,
 	pub parent: Option<Uuid>,
-	pub exchange_id: Option<ArrayString<32>>,
+	/// Up to a hyphenated uuid, which is what Bybit assigns.
+	pub exchange_id: Option<ArrayString<36>>,
+	/// What's sent as the client order id. `None` == hyphenated [id](Self::id). See [client_order_id](Self::client_order_id).
+	pub client_id: Option<ArrayString<36>>,
+}
+impl OrderId {
+	/// Longest client order id accepted by every supported exchange (Binance and Bybit both cap at 36).
+	pub const MAX_CLIENT_ID_LEN: usize = 36;
+	const MAX_PREFIX_LEN: usize = 8;
+
+	/// Fresh id with a human-readable client id: `{prefix}-{random hex}`, at most [MAX_CLIENT_ID_LEN](Self::MAX_CLIENT_ID_LEN) chars of `[A-Za-z0-9-]`.
+	///
+	/// `prefix` is stripped of anything non-alphanumeric and cut to 8 chars, so the result is valid everywhere regardless of input.
+	pub fn with_generated_id(prefix: &str) -> Self {
+		let id = Uuid::now_v7();
+		let prefix: String = prefix.chars().filter(char::is_ascii_alphanumeric).take(Self::MAX_PREFIX_LEN).collect();
+		let hex = id.simple().to_string();
+		let client_id = match prefix.is_empty() {
+			true => hex,
+			// v7 leads with the timestamp, so keep the tail: it's where the randomness is
+			false => format!("{prefix}-{}", &hex[hex.len() - (Self::MAX_CLIENT_ID_LEN - prefix.len() - 1)..]),
+		};
+		Self {
+			id,
+			client_id: Some(ArrayString::from(&client_id).expect("length is bounded by construction")),
+			..Default::default()
+		}
+	}
+
+	/// The id the exchange knows this order by on our side; stays the same across resubmissions, which is what makes them detectable.
+	pub fn client_order_id(&self) -> ArrayString<36> {
+		match self.client_id {
+			Some(client_id) => client_id,
+			None => ArrayString::from(&self.id.hyphenated().to_string()).expect("hyphenated uuid is exactly 36 chars"),
+		}
+	}
+}
+
+/// Anything [place_order](crate::Exchange::place_order) accepts.
+#[derive(Clone, Debug, derive_more::From)]
+pub enum Order {
+	Limit(LimitOrder),
+	Market(MarketOrder),
+}
+impl Order {
+	pub fn order_id(&self) -> &OrderId {
+		match self {
+			Self::Limit(o) => &o.order_id,
+			Self::Market(o) => &o.order_id,
+		}
+	}
+
+	pub fn side(&self) -> Side {
+		match self {
+			Self::Limit(o) => o.side,
+			Self::Market(o) => o.side,
+		}
+	}
+
+	pub fn qty(&self) -> Qty {
+		match self {
+			Self::Limit(o) => o.qty,
+			Self::Market(o) => o.qty,
+		}
+	}
+
+	pub fn qty_exact(&self) -> Option<ExactQty> {
+		match self {
+			Self::Limit(o) => o.qty_exact,
+			Self::Market(o) => o.qty_exact,
+		}
+	}
+
+	/// Qty as it goes into the request: [qty_exact](Self::qty_exact) verbatim where set.
+	pub(crate) fn qty_param(&self) -> String {
+		match self.qty_exact() {
+			Some(exact) => exact.to_string(),
+			None => self.qty().to_string(),
+		}
+	}
+
+	/// Checks that price and qty are sane, and, with `pair_info`, within the exchange's filters. [place_order](crate::Exchange::place_order) runs this against the cached [exchange_info](crate::Exchange::exchange_info) before sending anything.
+	///
+	/// Market orders have no price, so aren't checked for notional.
+	pub fn validate(&self, pair_info: Option<&PairInfo>) -> Result<(), OrderValidationError> {
+		let price = match self {
+			Self::Limit(o) => Some(fixed_price(o.price)),
+			Self::Market(_) => None,
+		};
+		let qty = self.qty_exact().map_or_else(|| fixed_qty(self.qty()), ExactQty::as_f64);
+		check_order(price, Some(qty), pair_info)
+	}
+}
+
+/// Qty carried as the exact decimal, for when [Qty]'s fixed point can't hold it, eg selling a whole [exact balance](crate::core::AssetBalance::underlying_exact) down to the last digit.
+///
+/// Only constructible with the `decimal` feature; without it, `qty_exact` fields are always `None`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ExactQty {
+	#[cfg(feature = "decimal")]
+	Decimal(rust_decimal::Decimal),
+}
+impl ExactQty {
+	pub fn as_f64(self) -> f64 {
+		match self {
+			#[cfg(feature = "decimal")]
+			Self::Decimal(d) => rust_decimal::prelude::ToPrimitive::to_f64(&d).unwrap_or(f64::NAN),
+		}
+	}
+}
+impl std::fmt::Display for ExactQty {
+	#[cfg_attr(not(feature = "decimal"), allow(unused_variables))]
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		// by value, as a reference to an empty enum isn't matched exhaustively by `{}`
+		match *self {
+			#[cfg(feature = "decimal")]
+			Self::Decimal(d) => d.fmt(f),
+		}
+	}
+}
+
+/// `from_f64` saturates on infinities, which is the only way to end up at the bounds.
+fn fixed_price(p: Price) -> f64 {
+	match p.raw {
+		i32::MAX | i32::MIN => f64::INFINITY,
+		_ => p.as_f64(),
+	}
+}
+fn fixed_qty(q: Qty) -> f64 {
+	match q.raw {
+		u32::MAX => f64::INFINITY,
+		_ => q.as_f64(),
+	}
+}
+
+/// Every violation of `price` and `qty`, against `pair_info` where given. Shared with exchange-specific request types.
+pub(crate) fn order_violations(price: Option<f64>, qty: Option<f64>, pair_info: Option<&PairInfo>) -> Vec<OrderViolation> {
+	let mut violations = Vec::new();
+	let mut sane = |field: &'static str, value: f64| {
+		if !value.is_finite() {
+			violations.push(OrderViolation::NonFinite { field });
+			false
+		} else if value <= 0. {
+			violations.push(OrderViolation::NonPositive { field, value });
+			false
+		} else {
+			true
+		}
+	};
+	let price = price.filter(|p| sane("price", *p));
+	let qty = qty.filter(|q| sane("qty", *q));
+	let Some(info) = pair_info else { return violations };
+
+	if let Some(price) = price {
+		if let Some(min) = info.min_price
+			&& price < min
+		{
+			violations.push(OrderViolation::PriceBelowMin { price, min });
+		}
+		if let Some(max) = info.max_price
+			&& price > max
+		{
+			violations.push(OrderViolation::PriceAboveMax { price, max });
+		}
+	}
+	if let Some(qty) = qty {
+		if let Some(min) = info.min_qty
+			&& qty < min
+		{
+			violations.push(OrderViolation::QtyBelowMin { qty, min });
+		}
+		if let Some(max) = info.max_qty
+			&& qty > max
+		{
+			violations.push(OrderViolation::QtyAboveMax { qty, max });
+		}
+		if let Some(step) = info.qty_step
+			&& step > 0.
+		{
+			let steps = qty / step;
+			// relative, as both sides went through decimal -> f64
+			if (steps - steps.round()).abs() > 1e-9 * steps.max(1.) {
+				violations.push(OrderViolation::QtyStep { qty, step });
+			}
+		}
+	}
+	if let (Some(price), Some(qty), Some(min)) = (price, qty, info.min_notional)
+		&& price * qty < min
+	{
+		violations.push(OrderViolation::NotionalBelowMin { notional: price * qty, min });
+	}
+	violations
+}
+
+pub(crate) fn check_order(price: Option<f64>, qty: Option<f64>, pair_info: Option<&PairInfo>) -> Result<(), OrderValidationError> {
+	let violations = order_violations(price, qty, pair_info);
+	match violations.is_empty() {
+		true => Ok(()),
+		false => Err(OrderValidationError { violations }),
+	}
 }
 
 /// Exchange-agnostic limit order.
@@ -42,6 +241,9 @@ pub struct LimitOrder {
 	pub side: Side,
 	pub price: Price,
 	pub qty: Qty, //Q: should I make order be generic over the qty? Or maybe just Decimal?
+	/// Sent instead of `qty` where set.
+	#[new(default)]
+	pub qty_exact: Option<ExactQty>,
 	#[new(value = "TimeInForce::Gtc")]
 	pub time_in_force: TimeInForce,
 	#[new(default)]
@@ -72,6 +274,7 @@ impl std::hash::Hash for LimitOrder {
 		self.side.hash(state);
 		self.price.hash(state);
 		self.qty.hash(state);
+		self.qty_exact.hash(state);
 		self.time_in_force.hash(state);
 		self.post_only.hash(state);
 		self.reduce_only.hash(state);
@@ -89,6 +292,9 @@ impl std::hash::Hash for LimitOrder {
 pub struct MarketOrder {
 	pub side: Side,
 	pub qty: Qty,
+	/// Sent instead of `qty` where set.
+	#[new(default)]
+	pub qty_exact: Option<ExactQty>,
 	#[new(default)]
 	pub reduce_only: bool,
 	#[new(default)]
@@ -249,13 +455,177 @@ pub struct OrderPlaced {
 	pub status: OrderStatus,
 }
 
-#[derive(Clone, Copy, Debug, strum::Display, Eq, PartialEq)]
+/// Result of looking an order up by its [OrderId].
+#[derive(Clone, Debug)]
+pub enum OrderState {
+	Found(OrderPlaced),
+	/// The exchange has no record of it: it was never accepted (or has aged out of the exchange's history).
+	NotFound,
+}
+
+/// Full state of an order, as returned by [get_order](crate::Exchange::get_order).
+#[derive(Clone, Debug)]
+pub struct OrderDetails {
+	pub order_id: OrderId,
+	pub symbol: Symbol,
+	pub side: Side,
+	/// Exchange's own name for it, eg `LIMIT` on Binance, `Limit` on Bybit.
+	pub order_type: String,
+	/// `None` for market orders.
+	pub price: Option<f64>,
+	/// `None` until anything is filled.
+	pub avg_price: Option<f64>,
+	pub qty: f64,
+	pub filled_qty: f64,
+	pub remaining_qty: f64,
+	pub status: OrderStatus,
+	pub reduce_only: bool,
+	pub created_at: Timestamp,
+	pub updated_at: Timestamp,
+	/// Fills of this order. Left empty when listing through [get_orders](crate::Exchange::get_orders), to not issue a request per order.
+	pub trades: Vec<TradeRecord>,
+}
+
+/// Single fill of one of our orders.
+#[derive(Clone, Debug)]
+pub struct TradeRecord {
+	pub trade_id: String,
+	pub price: f64,
+	pub qty: f64,
+	/// Negative for rebates.
+	pub fee: f64,
+	pub fee_asset: Asset,
+	pub is_maker: bool,
+	pub time: Timestamp,
+}
+
+#[derive(Clone, Copy, Debug, strum::Display, strum::EnumString, Eq, PartialEq)]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderStatus {
 	New,
 	PartiallyFilled,
 	Filled,
 	Canceled,
+	/// Binance additionally reports `EXPIRED_IN_MATCH` for orders expired by self-trade prevention.
+	#[strum(to_string = "EXPIRED", serialize = "EXPIRED_IN_MATCH")]
 	Expired,
 	Rejected,
 }
+impl OrderStatus {
+	/// No further fills or status changes can follow.
+	pub fn is_terminal(&self) -> bool {
+		matches!(self, Self::Filled | Self::Canceled | Self::Expired | Self::Rejected)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn generated_client_ids_are_valid_everywhere() {
+		for prefix in ["", "strat", "my strategy #1 with a long name", "Ω-α"] {
+			let id = OrderId::with_generated_id(prefix).client_order_id();
+			assert!(id.len() <= OrderId::MAX_CLIENT_ID_LEN, "{id}");
+			assert!(id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'), "{id}");
+		}
+		assert!(OrderId::with_generated_id("strat").client_order_id().starts_with("strat-"));
+		assert_eq!(OrderId::with_generated_id("mystrategy").client_order_id().len(), OrderId::MAX_CLIENT_ID_LEN);
+	}
+
+	#[test]
+	fn default_client_id_is_the_uuid() {
+		let id = OrderId::default();
+		assert_eq!(id.client_order_id().as_str(), id.id.hyphenated().to_string());
+	}
+
+	// validation {{{
+	fn filters() -> PairInfo {
+		PairInfo {
+			price_precision: 1,
+			qty_precision: 3,
+			min_qty: Some(0.001),
+			max_qty: Some(100.),
+			qty_step: Some(0.001),
+			min_notional: Some(5.),
+			min_price: Some(100.),
+			max_price: Some(1_000_000.),
+			..Default::default()
+		}
+	}
+
+	fn limit(price: f64, qty: f64) -> Order {
+		LimitOrder::new(Side::Buy, Price::from_f64(price, 1), Qty::from_f64(qty, 3)).into()
+	}
+
+	fn violations(order: &Order) -> Vec<OrderViolation> {
+		order.validate(Some(&filters())).map_or_else(|e| e.violations, |()| vec![])
+	}
+
+	#[test]
+	fn valid_orders_pass() {
+		let orders = [limit(60_000., 0.01), MarketOrder::new(Side::Sell, Qty::from_f64(0.001, 3)).into()];
+		for order in orders {
+			assert_eq!(order.validate(Some(&filters())), Ok(()));
+			assert_eq!(order.validate(None), Ok(()));
+		}
+	}
+
+	#[test]
+	fn nan_and_infinity_are_caught() {
+		// fixed-point takes NaN to zero, and saturates on infinities
+		assert_eq!(violations(&limit(f64::NAN, 0.01)), vec![OrderViolation::NonPositive { field: "price", value: 0. }]);
+		assert_eq!(violations(&limit(f64::INFINITY, 0.01)), vec![OrderViolation::NonFinite { field: "price" }]);
+		assert_eq!(violations(&limit(60_000., f64::INFINITY)), vec![OrderViolation::NonFinite { field: "qty" }]);
+		assert_eq!(limit(-5., 0.01).validate(None).unwrap_err().violations, vec![OrderViolation::NonPositive { field: "price", value: -5. }]);
+	}
+
+	#[test]
+	fn each_filter_is_reported() {
+		assert_eq!(violations(&limit(50., 1.)), vec![OrderViolation::PriceBelowMin { price: 50., min: 100. }]);
+		let order = LimitOrder::new(Side::Buy, Price::new(2_000_000_000, 2), Qty::from_f64(0.01, 3)).into();
+		assert_eq!(violations(&order), vec![OrderViolation::PriceAboveMax { price: 20_000_000., max: 1_000_000. }]);
+		assert_eq!(violations(&limit(60_000., 200.)), vec![OrderViolation::QtyAboveMax { qty: 200., max: 100. }]);
+		let order = LimitOrder::new(Side::Buy, Price::from_f64(60_000., 1), Qty::from_f64(0.0005, 4)).into();
+		assert_eq!(violations(&order), vec![OrderViolation::QtyBelowMin { qty: 0.0005, min: 0.001 }, OrderViolation::QtyStep { qty: 0.0005, step: 0.001 }]);
+		assert_eq!(violations(&limit(200., 0.01)), vec![OrderViolation::NotionalBelowMin { notional: 2., min: 5. }]);
+	}
+
+	#[test]
+	fn all_violations_at_once() {
+		let e = limit(50., 200.).validate(Some(&filters())).unwrap_err();
+		assert_eq!(e.violations, vec![OrderViolation::PriceBelowMin { price: 50., min: 100. }, OrderViolation::QtyAboveMax { qty: 200., max: 100. }]);
+		assert_eq!(e.to_string(), "Invalid order: price 50 is below the minimum of 100; qty 200 is above the maximum of 100");
+	}
+	//,}}}
+
+	// idempotent placement {{{
+	use std::sync::atomic::AtomicUsize;
+
+	use crate::{Exchange as _, test_utils::MockExchange};
+
+	#[tokio::test]
+	async fn lost_ack_is_not_resubmitted() {
+		let exchange = MockExchange {
+			lost_reached_engine: true,
+			lose_responses: AtomicUsize::new(1),
+			..Default::default()
+		};
+		let order = MockExchange::market_order("test", 0.001);
+		let placed = exchange.place_order_idempotent(MockExchange::btc_perp(), order.clone(), None).await.unwrap();
+		assert_eq!(placed.order_id.client_order_id(), order.order_id().client_order_id());
+		assert_eq!(exchange.placed.lock().unwrap().len(), 1);
+	}
+
+	#[tokio::test]
+	async fn lost_submission_is_resubmitted() {
+		let exchange = MockExchange {
+			lost_reached_engine: false,
+			lose_responses: AtomicUsize::new(1),
+			..Default::default()
+		};
+		exchange.place_order_idempotent(MockExchange::btc_perp(), MockExchange::market_order("test", 0.001), None).await.unwrap();
+		assert_eq!(exchange.placed.lock().unwrap().len(), 1);
+	}
+	//,}}}
+}
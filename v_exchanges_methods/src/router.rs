@@ -0,0 +1,262 @@
+use adapters::generics::ws::SubscriptionSnapshot;
+
+use crate::prelude::*;
+
+/// Clients for several exchanges behind one handle, with calls dispatched by [Ticker::exchange_name].
+///
+/// Methods mirror [Exchange], with the [Symbol] argument replaced by a [Ticker], and an explicit [Venue] (or [ExchangeName]) where the method doesn't take a symbol. Configuration (`auth`, `set_*`) is per client, so goes through [get_mut](Self::get_mut).
+#[derive(Debug, Default)]
+pub struct ExchangeRouter {
+	exchanges: HashMap<Venue, Box<dyn Exchange>>,
+}
+impl ExchangeRouter {
+	/// Replaces any client previously registered for the same exchange. Exchanges from outside this crate are keyed by their [Venue::Other].
+	pub fn add(&mut self, exchange: Box<dyn Exchange>) {
+		self.exchanges.insert(exchange.name(), exchange);
+	}
+
+	/// Registers every compiled-in exchange with credentials in env: `{EXCHANGE}_PUBKEY` and `{EXCHANGE}_SECRET` (eg `BYBIT_PUBKEY`), plus `KUCOIN_PASSPHRASE` for Kucoin.
+	///
+	/// Exchanges with neither var set are skipped; having only one of the two is an error.
+	pub fn from_env() -> Result<Self> {
+		let mut router = Self::default();
+		for name in ExchangeName::all_available() {
+			let var = |kind: &str| std::env::var(format!("{}_{kind}", name.to_string().to_uppercase())).ok();
+			let (pubkey, secret) = match (var("PUBKEY"), var("SECRET")) {
+				(Some(pubkey), Some(secret)) => (pubkey, secret),
+				(None, None) => continue,
+				(pubkey, _) => bail!(
+					"only {} of the credentials is set for {name}",
+					if pubkey.is_some() { "the pubkey" } else { "the secret" }
+				),
+			};
+			let mut client = name.try_init_client()?;
+			client.auth(pubkey, secret.into());
+			#[cfg(feature = "kucoin-http")]
+			if name == ExchangeName::Kucoin {
+				let passphrase = var("PASSPHRASE").ok_or_eyre("KUCOIN_PASSPHRASE must be set alongside the Kucoin credentials")?;
+				client.update_default_option(adapters::kucoin::KucoinOption::Passphrase(passphrase.into()));
+			}
+			router.add(client);
+		}
+		Ok(router)
+	}
+
+	pub fn exchanges(&self) -> impl Iterator<Item = Venue> + '_ {
+		self.exchanges.keys().copied()
+	}
+
+	pub fn get(&self, name: impl Into<Venue>) -> ExchangeResult<&dyn Exchange> {
+		let name = name.into();
+		self.exchanges.get(&name).map(|e| &**e).ok_or_else(|| not_registered(name))
+	}
+
+	pub fn get_mut(&mut self, name: impl Into<Venue>) -> ExchangeResult<&mut Box<dyn Exchange>> {
+		let name = name.into();
+		self.exchanges.get_mut(&name).ok_or_else(|| not_registered(name))
+	}
+
+	// Dispatch {{{
+	pub async fn exchange_info(&mut self, name: impl Into<Venue>, instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
+		self.get_mut(name)?.exchange_info(instrument).await
+	}
+
+	pub async fn pair_info(&self, ticker: Ticker) -> ExchangeResult<PairInfo> {
+		self.get(ticker.exchange_name)?.pair_info(ticker.symbol).await
+	}
+
+	pub async fn min_order_size(&self, ticker: Ticker) -> ExchangeResult<f64> {
+		self.get(ticker.exchange_name)?.min_order_size(ticker.symbol).await
+	}
+
+	pub async fn validate_order_qty(&self, ticker: Ticker, qty: f64) -> ExchangeResult<()> {
+		self.get(ticker.exchange_name)?.validate_order_qty(ticker.symbol, qty).await
+	}
+
+	pub async fn all_pairs(&self, name: impl Into<Venue>, instrument: Instrument) -> ExchangeResult<Vec<Pair>> {
+		self.get(name)?.all_pairs(instrument).await
+	}
+
+	pub async fn all_usdt_pairs(&self, name: impl Into<Venue>, instrument: Instrument) -> ExchangeResult<Vec<Pair>> {
+		self.get(name)?.all_usdt_pairs(instrument).await
+	}
+
+	pub async fn klines(&self, ticker: Ticker, tf: Timeframe, range: RequestRange) -> ExchangeResult<Klines> {
+		self.get(ticker.exchange_name)?.klines(ticker.symbol, tf, range).await
+	}
+
+	pub async fn klines_kind(&self, ticker: Ticker, tf: Timeframe, range: RequestRange, kind: KlineKind) -> ExchangeResult<Klines> {
+		self.get(ticker.exchange_name)?.klines_kind(ticker.symbol, tf, range, kind).await
+	}
+
+	pub async fn prices(&self, name: impl Into<Venue>, pairs: Option<Vec<Pair>>, instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
+		self.get(name)?.prices(pairs, instrument).await
+	}
+
+	pub async fn price(&self, ticker: Ticker) -> ExchangeResult<f64> {
+		self.get(ticker.exchange_name)?.price(ticker.symbol).await
+	}
+
+	pub async fn convert(&self, name: impl Into<Venue>, amount: f64, from: Asset, to: Asset, instrument: Instrument) -> ExchangeResult<ConversionQuote> {
+		self.get(name)?.convert(amount, from, to, instrument).await
+	}
+
+	pub async fn open_interest(&self, ticker: Ticker, tf: Timeframe, range: RequestRange) -> ExchangeResult<OpenInterestHistory> {
+		self.get(ticker.exchange_name)?.open_interest(ticker.symbol, tf, range).await
+	}
+
+	pub async fn funding_countdown(&self, ticker: Ticker) -> ExchangeResult<(std::time::Duration, f64)> {
+		self.get(ticker.exchange_name)?.funding_countdown(ticker.symbol).await
+	}
+
+	pub async fn is_near_funding(&self, ticker: Ticker, threshold: std::time::Duration) -> ExchangeResult<bool> {
+		self.get(ticker.exchange_name)?.is_near_funding(ticker.symbol, threshold).await
+	}
+
+	pub async fn mark_price(&self, ticker: Ticker) -> ExchangeResult<f64> {
+		self.get(ticker.exchange_name)?.mark_price(ticker.symbol).await
+	}
+
+	pub async fn perpetual_universe(&self, name: impl Into<Venue>) -> ExchangeResult<BTreeMap<Pair, PerpInfo>> {
+		self.get(name)?.perpetual_universe().await
+	}
+
+	pub async fn basis(&self, name: impl Into<Venue>, pair: Pair) -> ExchangeResult<f64> {
+		self.get(name)?.basis(pair).await
+	}
+
+	pub async fn basis_pct(&self, name: impl Into<Venue>, pair: Pair) -> ExchangeResult<f64> {
+		self.get(name)?.basis_pct(pair).await
+	}
+
+	pub async fn basis_stream(&self, name: impl Into<Venue>, pair: Pair, interval: std::time::Duration) -> ExchangeResult<Box<dyn ExchangeStream<Item = f64>>> {
+		self.get(name)?.basis_stream(pair, interval).await
+	}
+
+	pub async fn personal_info(&self, name: impl Into<Venue>, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<PersonalInfo> {
+		self.get(name)?.personal_info(instrument, recv_window).await
+	}
+
+	pub async fn fees(&self, ticker: Ticker, recv_window: Option<std::time::Duration>) -> ExchangeResult<FeeSchedule> {
+		self.get(ticker.exchange_name)?.fees(ticker.symbol, recv_window).await
+	}
+
+	pub async fn positions(&self, name: impl Into<Venue>, instrument: Instrument, recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+		self.get(name)?.positions(instrument, recv_window).await
+	}
+
+	pub async fn key_permissions(&self, name: impl Into<Venue>) -> ExchangeResult<KeyPermissions> {
+		self.get(name)?.key_permissions().await
+	}
+
+	pub async fn account_type(&self, name: impl Into<Venue>) -> ExchangeResult<AccountType> {
+		self.get(name)?.account_type().await
+	}
+
+	pub async fn require_permissions(&self, name: impl Into<Venue>, needed: KeyPermissions) -> ExchangeResult<()> {
+		self.get(name)?.require_permissions(needed).await
+	}
+
+	pub async fn place_order(&self, ticker: Ticker, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		self.get(ticker.exchange_name)?.place_order(ticker.symbol, order, recv_window).await
+	}
+
+	pub async fn place_order_idempotent(&self, ticker: Ticker, order: Order, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		self.get(ticker.exchange_name)?.place_order_idempotent(ticker.symbol, order, recv_window).await
+	}
+
+	pub async fn order_status(&self, ticker: Ticker, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+		self.get(ticker.exchange_name)?.order_status(ticker.symbol, order_id, recv_window).await
+	}
+
+	pub async fn get_order(&self, ticker: Ticker, order_id: OrderId, recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+		self.get(ticker.exchange_name)?.get_order(ticker.symbol, order_id, recv_window).await
+	}
+
+	pub async fn get_orders(
+		&self,
+		name: impl Into<Venue>,
+		instrument: Instrument,
+		pair: Option<Pair>,
+		status: Option<OrderStatus>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<Vec<OrderDetails>> {
+		self.get(name)?.get_orders(instrument, pair, status, recv_window).await
+	}
+
+	pub async fn amend_order(
+		&self,
+		ticker: Ticker,
+		order_id: OrderId,
+		new_qty: Option<f64>,
+		new_price: Option<f64>,
+		recv_window: Option<std::time::Duration>,
+	) -> ExchangeResult<OrderPlaced> {
+		self.get(ticker.exchange_name)?.amend_order(order_id, ticker.symbol, new_qty, new_price, recv_window).await
+	}
+
+	pub async fn order_book_snapshot(&mut self, ticker: Ticker) -> ExchangeResult<BookShape> {
+		self.get_mut(ticker.exchange_name)?.order_book_snapshot(ticker.symbol).await
+	}
+
+	pub async fn market_depth_imbalance(&mut self, ticker: Ticker, depth: u32) -> ExchangeResult<f64> {
+		self.get_mut(ticker.exchange_name)?.market_depth_imbalance(ticker.symbol, depth).await
+	}
+
+	pub async fn depth_imbalance_stream(&mut self, ticker: Ticker, depth: u32, interval: std::time::Duration) -> ExchangeResult<Box<dyn ExchangeStream<Item = f64>>> {
+		self.get_mut(ticker.exchange_name)?.depth_imbalance_stream(ticker.symbol, depth, interval).await
+	}
+
+	pub async fn ws_trades(&mut self, name: impl Into<Venue>, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		self.get_mut(name)?.ws_trades(pairs, instrument).await
+	}
+
+	pub async fn ws_book(&mut self, name: impl Into<Venue>, pairs: &[Pair], instrument: Instrument) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
+		self.get_mut(name)?.ws_book(pairs, instrument).await
+	}
+
+	pub async fn ws_trades_resume(&mut self, name: impl Into<Venue>, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BatchTrades>>> {
+		self.get_mut(name)?.ws_trades_resume(instrument, snapshot).await
+	}
+
+	pub async fn ws_book_resume(&mut self, name: impl Into<Venue>, instrument: Instrument, snapshot: &SubscriptionSnapshot) -> ExchangeResult<Box<dyn ExchangeStream<Item = BookUpdate>>> {
+		self.get_mut(name)?.ws_book_resume(instrument, snapshot).await
+	}
+
+	pub async fn ws_mark_prices(&self, name: impl Into<Venue>, pairs: &[Pair]) -> ExchangeResult<Box<dyn ExchangeStream<Item = MarkPriceUpdate>>> {
+		self.get(name)?.ws_mark_prices(pairs).await
+	}
+	//,}}}
+}
+
+fn not_registered(name: Venue) -> ExchangeError {
+	ExchangeError::Other(eyre!("No exchange registered for {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn unregistered_exchange() {
+		let mut router = ExchangeRouter::default();
+		router.add(ExchangeName::Binance.init_mock_client());
+
+		let ticker: Ticker = "bybit:BTC-USDT.P".parse().unwrap();
+		let err = router.price(ticker).await.unwrap_err();
+		assert_eq!(err.to_string(), "No exchange registered for bybit");
+		assert_eq!(router.exchanges().collect::<Vec<_>>(), vec![Venue::Builtin(ExchangeName::Binance)]);
+	}
+
+	#[tokio::test]
+	async fn forwards_order_size_checks() {
+		let mut router = ExchangeRouter::default();
+		router.add(Box::new(crate::test_utils::MockExchange::default()));
+		let ticker = Ticker {
+			exchange_name: router.exchanges().next().unwrap(),
+			symbol: crate::test_utils::MockExchange::btc_perp(),
+		};
+		assert_eq!(router.min_order_size(ticker.clone()).await.unwrap(), 0.001);
+		assert_eq!(router.pair_info(ticker).await.unwrap().min_qty, Some(0.001));
+	}
+}
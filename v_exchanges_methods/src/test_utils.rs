@@ -0,0 +1,255 @@
+//! Fixtures shared between the crate's unit tests.
+use std::{
+	sync::{
+		Mutex,
+		atomic::{AtomicUsize, Ordering},
+	},
+	time::Duration,
+};
+
+use adapters::{
+	Client,
+	generics::{
+		http::{IpError, RequestError},
+		reqwest,
+		ws::WsError,
+	},
+};
+use secrecy::SecretString;
+use tokio::time::Instant;
+
+use crate::{core::ExchangeImpl, prelude::*};
+
+/// An exchange with a single listed pair, BTC-USDT at 50k, that fills every order in full. The knobs make it misbehave, checked in the order they're listed.
+#[derive(Debug, Default, derive_more::Deref, derive_more::DerefMut)]
+pub(crate) struct MockExchange {
+	#[deref]
+	#[deref_mut]
+	client: Client,
+	pub info_cache: InfoCache,
+	label: Option<AccountLabel>,
+	log_context: Option<String>,
+	klines_guard: Option<KlinesGuard>,
+	mutation_journal: Option<Arc<dyn MutationJournal>>,
+	/// Placements never return, as if the process died mid-request.
+	pub hang: bool,
+	/// This many placements are rejected as rate limited.
+	pub throttle: AtomicUsize,
+	/// This many placement acks are lost in transport.
+	pub lose_responses: AtomicUsize,
+	/// Whether a placement whose ack is lost still reached the matching engine.
+	pub lost_reached_engine: bool,
+	/// What reached the matching engine, with its qty.
+	pub placed: Mutex<Vec<(OrderId, f64)>>,
+	/// Returned for perps; other instruments aren't supported.
+	pub positions: Vec<Position>,
+}
+impl MockExchange {
+	pub fn btc_perp() -> Symbol {
+		Symbol::new(Pair::new("BTC", "USDT"), Instrument::Perp)
+	}
+
+	pub fn market_order(prefix: &str, qty: f64) -> Order {
+		MarketOrder {
+			order_id: OrderId::with_generated_id(prefix),
+			..MarketOrder::new(Side::Buy, Qty::from_f64(qty, 3))
+		}
+		.into()
+	}
+
+	fn filled_qty(&self, order_id: &OrderId) -> Option<f64> {
+		self.placed.lock().unwrap().iter().find(|(id, _)| id.client_order_id() == order_id.client_order_id()).map(|(_, qty)| *qty)
+	}
+
+	/// A genuine transport failure: the peer accepts the connection and never answers.
+	async fn lost_response() -> ExchangeError {
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		std::thread::spawn(move || {
+			let _held = listener.accept();
+			std::thread::sleep(std::time::Duration::from_secs(1));
+		});
+		let e = reqwest::Client::new()
+			.post(format!("http://{addr}/order"))
+			.timeout(std::time::Duration::from_millis(50))
+			.send()
+			.await
+			.unwrap_err();
+		RequestError::SendRequest(e).into()
+	}
+}
+#[async_trait::async_trait]
+impl ExchangeImpl for MockExchange {
+	fn name(&self) -> Venue {
+		ExchangeName::Binance.into()
+	}
+
+	fn info_cache(&self) -> &InfoCache {
+		&self.info_cache
+	}
+
+	fn label(&self) -> Option<&AccountLabel> {
+		self.label.as_ref()
+	}
+
+	fn label_mut(&mut self) -> &mut Option<AccountLabel> {
+		&mut self.label
+	}
+
+	fn log_context(&self) -> Option<&str> {
+		self.log_context.as_deref()
+	}
+
+	fn log_context_mut(&mut self) -> &mut Option<String> {
+		&mut self.log_context
+	}
+
+	fn klines_guard(&self) -> Option<KlinesGuard> {
+		self.klines_guard
+	}
+
+	fn klines_guard_mut(&mut self) -> &mut Option<KlinesGuard> {
+		&mut self.klines_guard
+	}
+
+	fn mutation_journal(&self) -> Option<&Arc<dyn MutationJournal>> {
+		self.mutation_journal.as_ref()
+	}
+
+	fn mutation_journal_mut(&mut self) -> &mut Option<Arc<dyn MutationJournal>> {
+		&mut self.mutation_journal
+	}
+
+	fn auth(&mut self, _pubkey: String, _secret: SecretString) {}
+
+	fn set_recv_window(&mut self, _recv_window: std::time::Duration) {}
+
+	fn default_recv_window(&self) -> Option<std::time::Duration> {
+		None
+	}
+
+	async fn exchange_info(&self, _instrument: Instrument) -> ExchangeResult<ExchangeInfo> {
+		let pair_info = PairInfo {
+			price_precision: 1,
+			qty_precision: 3,
+			delivery_date: None,
+			min_qty: Some(0.001),
+			min_notional: Some(100.),
+			..Default::default()
+		};
+		Ok(ExchangeInfo {
+			server_time: Timestamp::now(),
+			pairs: [(Pair::new("BTC", "USDT"), pair_info)].into(),
+		})
+	}
+
+	async fn price(&self, _symbol: Symbol) -> ExchangeResult<f64> {
+		Ok(50_000.)
+	}
+
+	async fn prices(&self, pairs: Option<Vec<Pair>>, _instrument: Instrument) -> ExchangeResult<BTreeMap<Pair, f64>> {
+		let pairs = pairs.unwrap_or_else(|| vec![Pair::new("BTC", "USDT")]);
+		Ok(pairs.into_iter().map(|p| (p, 50_000.)).collect())
+	}
+
+	async fn place_order(&self, _symbol: Symbol, order: Order, _recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderPlaced> {
+		if self.hang {
+			std::future::pending::<()>().await;
+		}
+		if self.throttle.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+			let until = Timestamp::now() + jiff::SignedDuration::from_millis(20);
+			return Err(ExchangeError::Ip(IpError::Timeout { until: Some(until) }));
+		}
+		let lose = self.lose_responses.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok();
+		if !lose || self.lost_reached_engine {
+			self.placed.lock().unwrap().push((order.order_id().clone(), order.qty().as_f64()));
+		}
+		match lose {
+			true => Err(Self::lost_response().await),
+			false => Ok(OrderPlaced::new(order.order_id().clone(), OrderStatus::New)),
+		}
+	}
+
+	async fn positions(&self, instrument: Instrument, _recv_window: Option<std::time::Duration>) -> ExchangeResult<Vec<Position>> {
+		match instrument {
+			Instrument::Perp => Ok(self.positions.clone()),
+			_ => Err(ExchangeError::Method(MethodError::new_method_not_supported(ExchangeImpl::name(self), instrument, "positions"))),
+		}
+	}
+
+	async fn order_status(&self, _symbol: Symbol, order_id: OrderId, _recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderState> {
+		Ok(match self.filled_qty(&order_id) {
+			Some(_) => OrderState::Found(OrderPlaced::new(order_id, OrderStatus::Filled)),
+			None => OrderState::NotFound,
+		})
+	}
+
+	async fn get_order(&self, symbol: Symbol, order_id: OrderId, _recv_window: Option<std::time::Duration>) -> ExchangeResult<OrderDetails> {
+		let qty = self.filled_qty(&order_id).expect("placed");
+		Ok(OrderDetails {
+			order_id,
+			symbol,
+			side: Side::Buy,
+			order_type: "MARKET".to_owned(),
+			price: None,
+			avg_price: Some(50_000.),
+			qty,
+			filled_qty: qty,
+			remaining_qty: 0.,
+			status: OrderStatus::Filled,
+			reduce_only: false,
+			created_at: Timestamp::now(),
+			updated_at: Timestamp::now(),
+			trades: Vec::new(),
+		})
+	}
+}
+
+/// Yields scripted batches, one per `next()`, then never again.
+#[derive(Debug)]
+pub(crate) struct Scripted<T> {
+	start: Instant,
+	/// Each with its offset from `start`, where it's held back until then.
+	batches: VecDeque<(Option<Duration>, Vec<T>)>,
+}
+impl<T> Scripted<T> {
+	pub fn batches(batches: impl IntoIterator<Item = Vec<T>>) -> Self {
+		Self {
+			start: Instant::now(),
+			batches: batches.into_iter().map(|b| (None, b)).collect(),
+		}
+	}
+
+	/// Each item as a batch of its own.
+	pub fn items(items: impl IntoIterator<Item = T>) -> Self {
+		Self::batches(items.into_iter().map(|i| vec![i]))
+	}
+
+	/// Each item on its own, at its offset from now.
+	pub fn timed(items: impl IntoIterator<Item = (Duration, T)>) -> Self {
+		Self {
+			start: Instant::now(),
+			batches: items.into_iter().map(|(at, i)| (Some(at), vec![i])).collect(),
+		}
+	}
+
+	/// Never yields, for when updates are fed in by hand.
+	pub fn silent() -> Self {
+		Self::batches([])
+	}
+}
+#[async_trait::async_trait]
+impl<T: std::fmt::Debug + Send + Sync> ExchangeStream for Scripted<T> {
+	type Item = T;
+
+	async fn next(&mut self) -> Result<Vec<T>, WsError> {
+		let Some((at, _)) = self.batches.front() else {
+			return std::future::pending().await;
+		};
+		// absolute, so that being cancelled midway doesn't push the batch back
+		if let Some(at) = *at {
+			tokio::time::sleep_until(self.start + at).await;
+		}
+		Ok(self.batches.pop_front().expect("just peeked").1)
+	}
+}
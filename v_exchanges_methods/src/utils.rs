@@ -1,4 +1,6 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
+use v_utils::trades::Pair;
 
 /// # Panics
 /// Fine, because given prospected usages, theoretically only developer will see it.
@@ -11,41 +13,199 @@ pub fn join_params(a: Value, b: Value) -> Value {
 	}
 }
 
+/// Symbol formats of each exchange, so that modules don't each assemble their own.
+///
+/// `fmt_binance`, `fmt_bybit` and `fmt_mexc` forward to [Pair]'s own methods of the same name; they're here to have all formats behind one interface.
+pub trait PairExchangeFormatExt {
+	/// `BTCUSDT`
+	fn fmt_binance(&self) -> String;
+	/// `BTCUSDT`, for linear contracts and spot.
+	fn fmt_bybit(&self) -> String;
+	/// `BTCUSD`: inverse contracts are always margined in the base and quoted in USD, whatever the pair's quote.
+	fn fmt_bybit_inverse(&self) -> String;
+	/// `BTC-USDT`. Spot only; futures symbols (`XBTUSDTM`) follow different rules.
+	fn fmt_kucoin(&self) -> String;
+	/// `BTC_USDT`
+	fn fmt_mexc(&self) -> String;
+}
+impl PairExchangeFormatExt for Pair {
+	fn fmt_binance(&self) -> String {
+		Pair::fmt_binance(self)
+	}
+
+	fn fmt_bybit(&self) -> String {
+		Pair::fmt_bybit(self)
+	}
+
+	fn fmt_bybit_inverse(&self) -> String {
+		format!("{}USD", self.base())
+	}
+
+	fn fmt_kucoin(&self) -> String {
+		format!("{}-{}", self.base(), self.quote())
+	}
+
+	fn fmt_mexc(&self) -> String {
+		Pair::fmt_mexc(self)
+	}
+}
+
+/// Number an exchange sends as a string. Parsed into `f64`, and with the `decimal` feature also kept as the exact decimal it was sent as.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ExactNumber {
+	pub value: f64,
+	/// `None` where the string has more digits than a [Decimal](rust_decimal::Decimal) holds.
+	#[cfg(feature = "decimal")]
+	pub exact: Option<rust_decimal::Decimal>,
+}
+impl<'de> Deserialize<'de> for ExactNumber {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		Ok(Self {
+			value: s.parse().map_err(serde::de::Error::custom)?,
+			#[cfg(feature = "decimal")]
+			exact: rust_decimal::Decimal::from_str_exact(&s).ok(),
+		})
+	}
+}
+/// As the exact decimal where there is one, so that it survives the round trip.
+impl Serialize for ExactNumber {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		#[cfg(feature = "decimal")]
+		if let Some(exact) = self.exact {
+			return serializer.collect_str(&exact);
+		}
+		serializer.collect_str(&self.value)
+	}
+}
+
+/// Newtype over [Timeframe](v_utils::trades::Timeframe) restricted to what a provider accepts.
+///
+/// Each entry is `(canonical, [aliases..], serialized)`: both the canonical form and the aliases parse into it, while [Display](std::fmt::Display) emits the serialized one, which is what goes on the wire. With `test = <mod>`, a test checking every entry round-trips is generated into that module.
+/// The plain `[..]` form is shorthand for entries serialized exactly as listed, with no aliases.
 #[macro_export]
 macro_rules! define_provider_timeframe {
-	($struct_name:ident, $timeframes:expr) => {
-		#[derive(derive_more::AsRef, Clone, Copy, Debug, Default, derive_more::Deref, derive_more::DerefMut)]
-		pub struct $struct_name(v_utils::trades::Timeframe);
+	($struct_name:ident, [$(($canonical:literal, [$($alias:literal),* $(,)?], $serialized:literal)),* $(,)?] $(, test = $test_mod:ident)? $(,)?) => {
+		#[derive(Clone, Copy, Debug, Default)]
+		pub struct $struct_name($crate::__macro_deps::v_utils::trades::Timeframe);
 
-		impl std::fmt::Display for $struct_name {
-			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-				const TIMEFRAMES: [&str; $timeframes.len()] = $timeframes;
+		impl std::ops::Deref for $struct_name {
+			type Target = $crate::__macro_deps::v_utils::trades::Timeframe;
 
-				let s = self.0.try_as_predefined(&TIMEFRAMES).expect(concat!(
+			fn deref(&self) -> &Self::Target {
+				&self.0
+			}
+		}
+		impl std::ops::DerefMut for $struct_name {
+			fn deref_mut(&mut self) -> &mut Self::Target {
+				&mut self.0
+			}
+		}
+		impl AsRef<$crate::__macro_deps::v_utils::trades::Timeframe> for $struct_name {
+			fn as_ref(&self) -> &$crate::__macro_deps::v_utils::trades::Timeframe {
+				&self.0
+			}
+		}
+
+		impl $struct_name {
+			const CANONICAL: &'static [&'static str] = &[$($canonical),*];
+			const SERIALIZED: &'static [&'static str] = &[$($serialized),*];
+			const ALIASES: &'static [&'static [&'static str]] = &[$(&[$($alias),*]),*];
+
+			/// Every supported timeframe, in the order listed.
+			pub fn variants() -> Vec<Self> {
+				Self::CANONICAL.iter().map(|c| Self($crate::__macro_deps::v_utils::trades::Timeframe::from(*c))).collect()
+			}
+
+			fn table_index(&self) -> usize {
+				let s = self.0.try_as_predefined(Self::CANONICAL).expect(concat!(
 					"We can't create a ",
 					stringify!($struct_name),
 					" object if that doesn't succeed in the first place"
 				));
-				write!(f, "{s}")
+				Self::CANONICAL.iter().position(|c| *c == s).expect("matched against this very table")
+			}
+
+			/// Canonical form of `s`, if it's any of the accepted spellings.
+			fn lookup(s: &str) -> Option<&'static str> {
+				(0..Self::CANONICAL.len())
+					.find(|&i| Self::CANONICAL[i] == s || Self::SERIALIZED[i] == s || Self::ALIASES[i].contains(&s))
+					.map(|i| Self::CANONICAL[i])
 			}
 		}
 
-		impl TryFrom<v_utils::trades::Timeframe> for $struct_name {
-			type Error = $crate::UnsupportedTimeframeError;
+		impl std::fmt::Display for $struct_name {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				write!(f, "{}", Self::SERIALIZED[self.table_index()])
+			}
+		}
+
+		impl std::str::FromStr for $struct_name {
+			type Err = $crate::__macro_deps::eyre::Report;
+
+			fn from_str(s: &str) -> Result<Self, Self::Err> {
+				match Self::lookup(s) {
+					Some(canonical) => Ok(Self($crate::__macro_deps::v_utils::trades::Timeframe::from(canonical))),
+					None => Err($crate::__macro_deps::eyre::eyre!("{s:?} is not a {} timeframe. Accepted: {:?}", stringify!($struct_name), Self::CANONICAL)),
+				}
+			}
+		}
 
-			fn try_from(t: v_utils::trades::Timeframe) -> Result<Self, Self::Error> {
-				const TIMEFRAMES: [&str; $timeframes.len()] = $timeframes;
+		impl TryFrom<$crate::__macro_deps::v_utils::trades::Timeframe> for $struct_name {
+			type Error = $crate::UnsupportedTimeframeError;
 
-				match t.try_as_predefined(&TIMEFRAMES) {
+			fn try_from(t: $crate::__macro_deps::v_utils::trades::Timeframe) -> Result<Self, Self::Error> {
+				match t.try_as_predefined(Self::CANONICAL) {
 					Some(_) => Ok(Self(t)),
-					_ => Err($crate::UnsupportedTimeframeError::new(t, TIMEFRAMES.iter().map(v_utils::trades::Timeframe::from).collect())),
+					_ => Err($crate::UnsupportedTimeframeError::new(t, Self::CANONICAL.iter().map($crate::__macro_deps::v_utils::trades::Timeframe::from).collect())),
 				}
 			}
 		}
+		// spellings from the table map to their canonical timeframe; anything else is parsed as a plain `Timeframe`
 		impl From<&str> for $struct_name {
 			fn from(s: &str) -> Self {
-				Self(v_utils::trades::Timeframe::from(s))
+				Self($crate::__macro_deps::v_utils::trades::Timeframe::from(Self::lookup(s).unwrap_or(s)))
 			}
 		}
+
+		$(
+			#[cfg(test)]
+			mod $test_mod {
+				use super::$struct_name;
+
+				#[test]
+				fn table_round_trips() {
+					assert_eq!($struct_name::variants().len(), $struct_name::CANONICAL.len());
+					for (i, tf) in $struct_name::variants().into_iter().enumerate() {
+						let serialized = $struct_name::SERIALIZED[i];
+						assert_eq!(tf.to_string(), serialized, "{} displays wrong", $struct_name::CANONICAL[i]);
+						let spellings = $struct_name::ALIASES[i].iter().chain([&$struct_name::CANONICAL[i], &serialized]);
+						for s in spellings {
+							let parsed: $struct_name = s.parse().unwrap();
+							assert_eq!(parsed.to_string(), serialized, "{s:?} parses into the wrong timeframe");
+							assert_eq!($struct_name::try_from(*parsed).unwrap().to_string(), serialized);
+						}
+					}
+				}
+			}
+		)?
+	};
+	($struct_name:ident, [$($timeframe:literal),* $(,)?] $(, test = $test_mod:ident)? $(,)?) => {
+		$crate::define_provider_timeframe!($struct_name, [$(($timeframe, [], $timeframe)),*] $(, test = $test_mod)?);
 	};
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pair_formats() {
+		let pair = Pair::new("BTC", "USDT");
+		assert_eq!(PairExchangeFormatExt::fmt_binance(&pair), "BTCUSDT");
+		assert_eq!(PairExchangeFormatExt::fmt_bybit(&pair), "BTCUSDT");
+		assert_eq!(pair.fmt_bybit_inverse(), "BTCUSD");
+		assert_eq!(pair.fmt_kucoin(), "BTC-USDT");
+		assert_eq!(PairExchangeFormatExt::fmt_mexc(&pair), "BTC_USDT");
+	}
+}
@@ -0,0 +1,251 @@
+//! Feed-health watchdog over any [ExchangeStream]: a venue going quiet shows up as [StreamEvent::Gap] instead of an indefinitely pending `next()`.
+use std::time::Duration;
+
+use adapters::generics::ws::{LatencyStats, SubscriptionSnapshot, WsError};
+use jiff::Timestamp;
+use tokio::time::Instant;
+
+use crate::prelude::*;
+
+/// Inter-arrival samples kept for [ExpectedGap::Adaptive].
+const GAP_WINDOW: usize = 256;
+/// Below this many samples, [ExpectedGap::Adaptive] falls back to its `max`.
+const MIN_SAMPLES: usize = 16;
+
+/// Item of a [HeartbeatStream].
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent<T> {
+	Item(T),
+	/// Nothing arrived for `duration` since `since`, the last item, or the first poll if none came yet. Repeated at every doubling of the expected gap while the silence lasts.
+	Gap { since: Timestamp, duration: Duration },
+	/// First item after a [Gap](Self::Gap), yielded right before it. `gap` is the full silence.
+	Recovered { gap: Duration },
+}
+
+/// How long a silence has to last before it's a [Gap](StreamEvent::Gap).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpectedGap {
+	Fixed(Duration),
+	/// `factor` times the longest of the recent inter-arrival times, clamped to `min..=max`. For feeds with no one right number: BTC trades every millisecond, some alts go quiet for minutes.
+	///
+	/// Silences that were reported as gaps don't count towards the statistics, so an outage doesn't desensitize the watchdog afterwards.
+	Adaptive { factor: f64, min: Duration, max: Duration },
+}
+impl ExpectedGap {
+	/// [Adaptive](Self::Adaptive), checked as [with_heartbeat] would.
+	pub fn adaptive(factor: f64, min: Duration, max: Duration) -> Result<Self> {
+		let expected = Self::Adaptive { factor, min, max };
+		expected.validate()?;
+		Ok(expected)
+	}
+
+	/// Fails on a zero threshold, and on an `Adaptive` one whose `factor` isn't a positive finite number or whose `min` is over its `max`.
+	pub fn validate(&self) -> Result<()> {
+		match *self {
+			Self::Fixed(d) =>
+				if d.is_zero() {
+					bail!("expected gap must be greater than 0");
+				},
+			Self::Adaptive { factor, min, max } => {
+				if !(factor.is_finite() && factor > 0.) {
+					bail!("adaptive gap factor must be a positive finite number, got {factor}");
+				}
+				if min.is_zero() || min > max {
+					bail!("adaptive gap bounds must satisfy 0 < min <= max, got {min:?}..={max:?}");
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn threshold(&self, recent: &VecDeque<Duration>) -> Duration {
+		match *self {
+			Self::Fixed(d) => d,
+			Self::Adaptive { factor, min, max } => {
+				if recent.len() < MIN_SAMPLES {
+					return max;
+				}
+				let longest = recent.iter().max().copied().unwrap_or_default();
+				longest.mul_f64(factor).clamp(min, max)
+			}
+		}
+	}
+}
+impl From<Duration> for ExpectedGap {
+	fn from(d: Duration) -> Self {
+		Self::Fixed(d)
+	}
+}
+
+/// Wraps `stream`, interleaving [Gap](StreamEvent::Gap)s and [Recovered](StreamEvent::Recovered)s with its items. See [ExpectedGap] on picking `expected_max_gap`.
+///
+/// Races the inner `next()` against a timer, so relies on it being cancel-safe, which [WsConnection::next](adapters::generics::ws::WsConnection::next) is. Reconnects stay the inner stream's business; this only reports what comes out of it.
+///
+/// The clock starts on the first `next()`, so a feed that never delivers is reported too.
+///
+/// # Panics
+/// If `expected_max_gap` fails [ExpectedGap::validate].
+pub fn with_heartbeat<S: ExchangeStream>(stream: S, expected_max_gap: impl Into<ExpectedGap>) -> HeartbeatStream<S> {
+	let expected = expected_max_gap.into();
+	expected.validate().unwrap_or_else(|e| panic!("with_heartbeat: {e}"));
+	HeartbeatStream {
+		inner: stream,
+		expected,
+		recent: VecDeque::with_capacity(GAP_WINDOW),
+		last_item: None,
+		seen_item: false,
+		gap: None,
+	}
+}
+
+/// See [with_heartbeat].
+#[derive(Debug)]
+pub struct HeartbeatStream<S> {
+	inner: S,
+	expected: ExpectedGap,
+	recent: VecDeque<Duration>,
+	/// Last item, or the first poll until there is one. `None` until polled.
+	last_item: Option<(Instant, Timestamp)>,
+	/// Whether `last_item` is an item, so that the wait for the first one isn't taken as an inter-arrival time.
+	seen_item: bool,
+	/// Threshold of the next [Gap](StreamEvent::Gap) while in one.
+	gap: Option<Duration>,
+}
+impl<S> HeartbeatStream<S> {
+	pub fn inner(&self) -> &S {
+		&self.inner
+	}
+
+	/// Current threshold for a silence to be reported.
+	pub fn expected_gap(&self) -> Duration {
+		self.expected.threshold(&self.recent)
+	}
+
+	/// Returns the silence it ended, if it was a reported gap.
+	fn record_arrival(&mut self) -> Option<Duration> {
+		let now = Instant::now();
+		let ended_gap = match (self.gap.take(), self.last_item) {
+			(Some(_), Some((at, _))) => Some(now - at),
+			(None, Some((at, _))) if self.seen_item => {
+				if self.recent.len() == GAP_WINDOW {
+					self.recent.pop_front();
+				}
+				self.recent.push_back(now - at);
+				None
+			}
+			_ => None,
+		};
+		self.last_item = Some((now, Timestamp::now()));
+		self.seen_item = true;
+		ended_gap
+	}
+}
+#[async_trait::async_trait]
+impl<S> ExchangeStream for HeartbeatStream<S>
+where
+	S: ExchangeStream,
+	S::Item: Send,
+{
+	type Item = StreamEvent<S::Item>;
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.inner.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		self.inner.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: jiff::SignedDuration) {
+		self.inner.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.inner.duplicates_skipped()
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		loop {
+			let (last_at, last_time) = *self.last_item.get_or_insert_with(|| (Instant::now(), Timestamp::now()));
+			let threshold = self.gap.unwrap_or_else(|| self.expected_gap());
+			tokio::select! {
+				items = self.inner.next() => {
+					let items = items?;
+					if items.is_empty() {
+						continue;
+					}
+					let recovered = self.record_arrival().map(|gap| StreamEvent::Recovered { gap });
+					return Ok(recovered.into_iter().chain(items.into_iter().map(StreamEvent::Item)).collect());
+				}
+				_ = tokio::time::sleep_until(last_at + threshold) => {
+					self.gap = Some(threshold.saturating_mul(2));
+					return Ok(vec![StreamEvent::Gap { since: last_time, duration: last_at.elapsed() }]);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::Scripted;
+
+	#[tokio::test]
+	async fn gaps_escalate_then_recover() {
+		let inner = Scripted::timed([(Duration::ZERO, 1), (Duration::from_millis(200), 2)]);
+		let mut stream = with_heartbeat(inner, Duration::from_millis(40));
+		assert_eq!(stream.next().await.unwrap(), vec![StreamEvent::Item(1)]);
+
+		let first = stream.next().await.unwrap();
+		let StreamEvent::Gap { duration, .. } = first[0] else { panic!("{first:?}") };
+		assert!(duration >= Duration::from_millis(40));
+		// next one only at double the threshold
+		let second = stream.next().await.unwrap();
+		let StreamEvent::Gap { duration, .. } = second[0] else { panic!("{second:?}") };
+		assert!(duration >= Duration::from_millis(80));
+
+		let mut rest = stream.next().await.unwrap();
+		while matches!(rest[0], StreamEvent::Gap { .. }) {
+			rest = stream.next().await.unwrap();
+		}
+		let StreamEvent::Recovered { gap } = rest[0] else { panic!("{rest:?}") };
+		assert!(gap >= Duration::from_millis(200));
+		assert_eq!(rest[1], StreamEvent::Item(2));
+	}
+
+	#[tokio::test]
+	async fn silence_from_the_start_is_a_gap() {
+		let mut stream = with_heartbeat(Scripted::<u8>::silent(), Duration::from_millis(30));
+		let events = stream.next().await.unwrap();
+		let StreamEvent::Gap { duration, .. } = events[0] else { panic!("{events:?}") };
+		assert!(duration >= Duration::from_millis(30));
+	}
+
+	#[test]
+	fn invalid_expected_gaps_are_rejected() {
+		assert!(ExpectedGap::adaptive(-1., Duration::from_millis(100), Duration::from_secs(1)).is_err());
+		assert!(ExpectedGap::adaptive(f64::NAN, Duration::from_millis(100), Duration::from_secs(1)).is_err());
+		assert!(ExpectedGap::adaptive(5., Duration::from_secs(2), Duration::from_secs(1)).is_err());
+		assert!(ExpectedGap::Fixed(Duration::ZERO).validate().is_err());
+		assert!(ExpectedGap::adaptive(5., Duration::from_millis(100), Duration::from_secs(1)).is_ok());
+	}
+
+	#[test]
+	fn adaptive_threshold_follows_recent_gaps() {
+		let expected = ExpectedGap::Adaptive {
+			factor: 5.,
+			min: Duration::from_millis(100),
+			max: Duration::from_secs(300),
+		};
+		let mut recent: VecDeque<Duration> = VecDeque::new();
+		// too few samples to judge
+		assert_eq!(expected.threshold(&recent), Duration::from_secs(300));
+
+		recent.extend(std::iter::repeat_n(Duration::from_millis(1), MIN_SAMPLES));
+		assert_eq!(expected.threshold(&recent), Duration::from_millis(100), "clamped to min");
+
+		recent.push_back(Duration::from_secs(20));
+		assert_eq!(expected.threshold(&recent), Duration::from_secs(100));
+	}
+}
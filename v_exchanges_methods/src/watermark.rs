@@ -0,0 +1,332 @@
+//! Event-time watermarks over any [ExchangeStream]: each item comes out with the latest event time seen so far, which never goes backwards. What a recording needs to be able to say "everything up to `T` is in here".
+use std::{
+	fs::{File, OpenOptions},
+	io::Write as _,
+	path::{Path, PathBuf},
+};
+
+use adapters::generics::ws::{LatencyStats, SubscriptionSnapshot, WsError};
+use jiff::{SignedDuration, Timestamp};
+use serde_with::{DisplayFromStr, serde_as};
+
+use crate::prelude::*;
+
+/// Exchange-assigned time of a stream item, which [watermarked] advances the watermark by.
+pub trait EventTime {
+	/// `None` where the item carries none, in which case its receive time stands in.
+	fn event_time(&self) -> Option<Timestamp>;
+}
+impl EventTime for BatchTrades {
+	fn event_time(&self) -> Option<Timestamp> {
+		Some(self.ts_event())
+	}
+}
+impl EventTime for Kline {
+	fn event_time(&self) -> Option<Timestamp> {
+		Some(self.open_time)
+	}
+}
+impl EventTime for Liquidation {
+	fn event_time(&self) -> Option<Timestamp> {
+		Some(self.time)
+	}
+}
+impl EventTime for MarkPriceUpdate {
+	fn event_time(&self) -> Option<Timestamp> {
+		Some(self.time)
+	}
+}
+
+/// Wraps `stream`, pairing each item with the watermark as of it.
+///
+/// An item timed before the current watermark doesn't move it back: it's yielded with the watermark as is, and counted in [violations](Watermarked::violations). Exchanges do send the odd trade late, so this is not an error.
+pub fn watermarked<S: ExchangeStream>(stream: S) -> Watermarked<S> {
+	Watermarked {
+		inner: stream,
+		watermark: None,
+		violations: 0,
+	}
+}
+
+/// See [watermarked].
+#[derive(Debug)]
+pub struct Watermarked<S> {
+	inner: S,
+	/// `None` until the first item.
+	watermark: Option<Timestamp>,
+	violations: u64,
+}
+impl<S> Watermarked<S> {
+	pub fn inner(&self) -> &S {
+		&self.inner
+	}
+
+	/// Latest event time yielded so far.
+	pub fn watermark(&self) -> Option<Timestamp> {
+		self.watermark
+	}
+
+	/// Items that came in timed before the watermark, and were clamped up to it.
+	pub fn violations(&self) -> u64 {
+		self.violations
+	}
+
+	/// How far the watermark trails the local clock. `None` before the first item.
+	pub fn lag(&self) -> Option<SignedDuration> {
+		self.watermark.map(|w| Timestamp::now().duration_since(w))
+	}
+
+	fn advance(&mut self, event_time: Timestamp) -> Timestamp {
+		match self.watermark {
+			Some(w) if event_time < w => {
+				self.violations += 1;
+				debug!(target: "v_exchanges", watermark = %w, %event_time, "Stream item older than the watermark, clamping");
+				w
+			}
+			_ => {
+				self.watermark = Some(event_time);
+				event_time
+			}
+		}
+	}
+}
+#[async_trait::async_trait]
+impl<S> ExchangeStream for Watermarked<S>
+where
+	S: ExchangeStream,
+	S::Item: EventTime + Send,
+{
+	type Item = (S::Item, Timestamp);
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.inner.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		self.inner.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.inner.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.inner.duplicates_skipped()
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let items = self.inner.next().await?;
+		let received = Timestamp::now();
+		Ok(items
+			.into_iter()
+			.map(|item| {
+				let watermark = self.advance(item.event_time().unwrap_or(received));
+				(item, watermark)
+			})
+			.collect())
+	}
+}
+
+// recording {{{
+/// Line of a recording: an item, and the watermark as of it.
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize)]
+struct RecordedLine<T> {
+	#[serde_as(as = "DisplayFromStr")]
+	watermark: Timestamp,
+	item: T,
+}
+
+/// Wraps a [Watermarked] stream, appending each item along with its watermark to `path`, as a JSON line. An existing recording is appended to, so one can span restarts.
+///
+/// An item is only yielded once it's written, so the last watermark on disk is never ahead of what the consumer has seen. Read back with [Recording::load].
+pub fn recorded<S>(stream: Watermarked<S>, path: impl Into<PathBuf>) -> std::io::Result<Recorded<S>> {
+	let path = path.into();
+	let file = OpenOptions::new().create(true).append(true).open(&path)?;
+	Ok(Recorded {
+		inner: stream,
+		path,
+		file: Arc::new(Mutex::new(file)),
+	})
+}
+
+/// See [recorded].
+#[derive(Debug)]
+pub struct Recorded<S> {
+	inner: Watermarked<S>,
+	path: PathBuf,
+	file: Arc<Mutex<File>>,
+}
+impl<S> Recorded<S> {
+	pub fn inner(&self) -> &Watermarked<S> {
+		&self.inner
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+}
+#[async_trait::async_trait]
+impl<S> ExchangeStream for Recorded<S>
+where
+	S: ExchangeStream,
+	S::Item: EventTime + Serialize + Send,
+{
+	type Item = (S::Item, Timestamp);
+
+	fn latency_stats(&self) -> Option<LatencyStats> {
+		self.inner.latency_stats()
+	}
+
+	fn subscription_snapshot(&self) -> Option<SubscriptionSnapshot> {
+		self.inner.subscription_snapshot()
+	}
+
+	fn set_clock_offset(&mut self, offset: SignedDuration) {
+		self.inner.set_clock_offset(offset);
+	}
+
+	fn duplicates_skipped(&self) -> Option<u64> {
+		self.inner.duplicates_skipped()
+	}
+
+	async fn next(&mut self) -> Result<Vec<Self::Item>, WsError> {
+		let items = self.inner.next().await?;
+		let mut lines = String::new();
+		for (item, watermark) in &items {
+			let line = RecordedLine { watermark: *watermark, item };
+			lines.push_str(&serde_json::to_string(&line).map_err(WsError::Parse)?);
+			lines.push('\n');
+		}
+		let file = Arc::clone(&self.file);
+		tokio::task::spawn_blocking(move || file.lock().unwrap().write_all(lines.as_bytes()))
+			.await
+			.expect("writing doesn't panic")
+			.map_err(|e| WsError::Other(eyre::Report::new(e).wrap_err(format!("Failed to record stream items to {}", self.path.display()))))?;
+		Ok(items)
+	}
+}
+
+/// A recording made by [recorded], read back.
+#[derive(Clone, Debug)]
+pub struct Recording<T> {
+	/// In the order they were yielded, each with the watermark as of it.
+	pub items: Vec<(T, Timestamp)>,
+}
+impl<T: DeserializeOwned> Recording<T> {
+	/// Blocking. A line cut short by a crash mid-write can only be the last one, and is dropped along with its item.
+	pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let content = std::fs::read_to_string(path).wrap_err_with(|| format!("Failed to read recording {}", path.display()))?;
+		let lines: Vec<&str> = content.lines().filter(|l| !l.is_empty()).collect();
+		let mut items = Vec::with_capacity(lines.len());
+		for (i, line) in lines.iter().enumerate() {
+			match serde_json::from_str::<RecordedLine<T>>(line) {
+				Ok(RecordedLine { watermark, item }) => items.push((item, watermark)),
+				Err(_) if i == lines.len() - 1 && !content.ends_with('\n') => break,
+				Err(e) => return Err(Report::new(e).wrap_err(format!("Malformed line {} of recording {}", i + 1, path.display()))),
+			}
+		}
+		Ok(Self { items })
+	}
+}
+impl<T> Recording<T> {
+	/// The last watermark written: every item the recorded stream yielded with an event time up to it is in here.
+	pub fn watermark(&self) -> Option<Timestamp> {
+		self.items.last().map(|(_, w)| *w)
+	}
+
+	/// Whether all events up to `t` are present, ie the watermark got to `t`.
+	pub fn is_complete_through(&self, t: Timestamp) -> bool {
+		self.watermark().is_some_and(|w| w >= t)
+	}
+}
+//,}}}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_utils::Scripted;
+
+	#[derive(Debug, Deserialize, PartialEq, Serialize)]
+	struct Ev(Option<i64>);
+	impl EventTime for Ev {
+		fn event_time(&self) -> Option<Timestamp> {
+			self.0.map(|s| Timestamp::from_second(s).unwrap())
+		}
+	}
+
+	fn seconds(items: Vec<(Ev, Timestamp)>) -> Vec<i64> {
+		items.into_iter().map(|(_, w)| w.as_second()).collect()
+	}
+
+	#[tokio::test]
+	async fn late_events_are_clamped() {
+		let batches = [vec![Ev(Some(10)), Ev(Some(30)), Ev(Some(20))], vec![Ev(Some(5)), Ev(Some(40)), Ev(Some(40))]];
+		let mut stream = watermarked(Scripted::batches(batches));
+		assert_eq!(stream.lag(), None);
+
+		assert_eq!(seconds(stream.next().await.unwrap()), [10, 30, 30]);
+		assert_eq!(seconds(stream.next().await.unwrap()), [30, 40, 40]);
+		assert_eq!(stream.violations(), 2, "equal times don't count");
+		assert_eq!(stream.watermark(), Some(Timestamp::from_second(40).unwrap()));
+		assert!(stream.lag().unwrap() > SignedDuration::ZERO);
+	}
+
+	#[tokio::test]
+	async fn untimed_items_take_receive_time() {
+		let mut stream = watermarked(Scripted::batches([vec![Ev(Some(10)), Ev(None)], vec![Ev(Some(20))]]));
+		let before = Timestamp::now();
+		let first = stream.next().await.unwrap();
+		assert!(first[1].1 >= before);
+		// anything exchange-timed in the past is now late
+		assert_eq!(seconds(stream.next().await.unwrap()), [first[1].1.as_second()]);
+		assert_eq!(stream.violations(), 1);
+	}
+
+	#[tokio::test]
+	async fn recording_keeps_the_watermark() {
+		let path = std::env::temp_dir().join(format!("v_exchanges_recording_{}.jsonl", uuid::Uuid::now_v7()));
+		let batches = [vec![Ev(Some(10)), Ev(Some(30))], vec![Ev(Some(20)), Ev(Some(40))]];
+		let mut stream = recorded(watermarked(Scripted::batches(batches)), &path).unwrap();
+		stream.next().await.unwrap();
+		stream.next().await.unwrap();
+
+		// as if the process died mid-write of a third batch
+		std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(br#"{"watermark":"1970-01-01T00:00:"#).unwrap();
+		let recording = Recording::<Ev>::load(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(recording.items.iter().map(|(ev, w)| (ev.0.unwrap(), w.as_second())).collect::<Vec<_>>(), [(10, 10), (30, 30), (20, 30), (40, 40)]);
+		assert!(recording.is_complete_through(Timestamp::from_second(40).unwrap()));
+		assert!(!recording.is_complete_through(Timestamp::from_second(41).unwrap()));
+	}
+
+	#[tokio::test]
+	async fn batch_trades_record_and_reload() {
+		let path = std::env::temp_dir().join(format!("v_exchanges_recording_{}.jsonl", uuid::Uuid::now_v7()));
+		let prec = PrecisionPriceQty { price: 2, qty: 3 };
+		let trade = |s: i64, price: i32, side: Side| crate::core::InnerTrade {
+			id: Some(TradeId::Numeric(s as u64)),
+			time: Timestamp::from_second(s).unwrap(),
+			price,
+			qty: 1500,
+			side: Some(side),
+		};
+		let now = Timestamp::now();
+		let batches = [
+			vec![BatchTrades::new(prec, vec![trade(10, 6_700_012, Side::Buy), trade(11, 6_700_000, Side::Sell)], now, now)],
+			vec![BatchTrades::new(prec, vec![trade(12, 6_699_950, Side::Sell)], now, now)],
+		];
+		let mut stream = recorded(watermarked(Scripted::batches(batches.clone())), &path).unwrap();
+		stream.next().await.unwrap();
+		stream.next().await.unwrap();
+
+		let recording = Recording::<BatchTrades>::load(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let reloaded: Vec<Vec<Trade>> = recording.items.iter().map(|(b, _)| b.trades().collect()).collect();
+		let sent: Vec<Vec<Trade>> = batches.iter().flatten().map(|b| b.trades().collect()).collect();
+		assert_eq!(reloaded, sent);
+		assert_eq!(recording.watermark(), Some(Timestamp::from_second(12).unwrap()));
+	}
+}
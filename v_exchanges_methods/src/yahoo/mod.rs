@@ -19,7 +19,7 @@ pub async fn vix(tf: YahooTimeframe, n: u8) -> Result<Vec<Close>> {
 	let response = client.get(url).query(&params).send().await?;
 
 	let data: Value = response.json().await?;
-	tracing::debug!("{}", data["chart"]["result"][0]);
+	tracing::debug!(target: "v_exchanges::yahoo", "{}", data["chart"]["result"][0]);
 
 	let vix_values = {
 		let result = &data["chart"]["result"][0]; // [0] because yahoo allows for batch requests
@@ -67,4 +67,19 @@ pub async fn vix_change(tf: YahooTimeframe, n: u8) -> Result<NowThen> {
 		.build())
 }
 
-crate::define_provider_timeframe!(YahooTimeframe, ["1m", "2m", "5m", "15m", "30m", "60m", "1h", "1d", "5d", "1wk", "1mo"]);
+crate::define_provider_timeframe!(
+	YahooTimeframe,
+	[
+		("1m", [], "1m"),
+		("2m", [], "2m"),
+		("5m", [], "5m"),
+		("15m", [], "15m"),
+		("30m", [], "30m"),
+		("1h", [], "60m"),
+		("1d", [], "1d"),
+		("5d", [], "5d"),
+		("1w", [], "1wk"),
+		("1M", [], "1mo"),
+	],
+	test = yahoo_timeframe_table
+);